@@ -0,0 +1,475 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::AiHandler;
+use super::token::get_max_tokens_with_fallback;
+use super::types::{ChatResponse, FinishReason, ModelCapabilities, ResponseSchema, Usage};
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::util::http_client_builder;
+
+/// Number of retry attempts for transient API errors (not rate limits).
+const MODEL_RETRIES: u32 = 2;
+
+/// Messages API version sent in the `anthropic-version` header.
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Output token cap when extended thinking is off.
+const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 8192;
+
+/// Smallest thinking budget the API accepts.
+const MIN_THINKING_BUDGET_TOKENS: u32 = 1024;
+
+/// Model name prefix that selects this handler.
+const CLAUDE_MODEL_PREFIX: &str = "claude-";
+
+/// Whether `model` selects the native Anthropic handler.
+pub fn is_anthropic_model(model: &str) -> bool {
+    model.starts_with(CLAUDE_MODEL_PREFIX)
+}
+
+/// Native Anthropic Messages API handler (`/v1/messages`).
+///
+/// Unlike the OpenAI-compatible endpoint, this supports extended thinking
+/// (`config.enable_claude_extended_thinking`).
+pub struct AnthropicHandler {
+    client: Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl AnthropicHandler {
+    /// Create a new handler from the current settings.
+    pub fn from_settings() -> Result<Self, PrAgentError> {
+        let settings = get_settings();
+        let api_key = settings.anthropic.key.clone();
+        let base_url = if settings.anthropic.api_base.is_empty() {
+            "https://api.anthropic.com/v1".to_string()
+        } else {
+            settings.anthropic.api_base.clone()
+        };
+        let timeout_secs = settings.config.ai_timeout;
+
+        let client = http_client_builder(&settings.http, &settings.http.ai_headers)?
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+            .map_err(PrAgentError::Http)?;
+
+        Ok(Self {
+            client,
+            base_url,
+            api_key,
+        })
+    }
+
+    /// Build the request body for the Messages API.
+    fn build_request_body(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+    ) -> serde_json::Value {
+        let settings = get_settings();
+        let config = &settings.config;
+
+        let content = match image_urls.filter(|u| !u.is_empty()) {
+            Some(urls) => {
+                let mut blocks = vec![json!({"type": "text", "text": user})];
+                blocks.extend(urls.iter().map(|url| image_block(url)));
+                json!(blocks)
+            }
+            None => json!(user),
+        };
+
+        let mut body = json!({
+            "model": model,
+            "messages": [{"role": "user", "content": content}],
+        });
+
+        if !system.is_empty() {
+            body["system"] = json!(system);
+        }
+
+        if config.enable_claude_extended_thinking {
+            // The budget counts towards max_tokens and must leave room for the answer.
+            let budget = config
+                .extended_thinking_budget_tokens
+                .max(MIN_THINKING_BUDGET_TOKENS);
+            let max_tokens = config.extended_thinking_max_output_tokens.max(budget + 1);
+            body["max_tokens"] = json!(max_tokens);
+            body["thinking"] = json!({"type": "enabled", "budget_tokens": budget});
+            // Thinking requires the default temperature, so none is sent.
+        } else {
+            body["max_tokens"] = json!(DEFAULT_MAX_OUTPUT_TOKENS);
+            body["temperature"] = json!(temperature.unwrap_or(config.temperature));
+        }
+
+        body
+    }
+
+    /// Send a single request and parse the response. No retry logic here.
+    async fn send_completion(
+        &self,
+        body: &serde_json::Value,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let url = format!("{}/messages", self.base_url.trim_end_matches('/'));
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(body)
+            .send()
+            .await
+            .map_err(PrAgentError::Http)?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+
+            if status.as_u16() == 429 {
+                let retry_after = resp
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(60);
+                return Err(PrAgentError::RateLimited {
+                    retry_after_secs: retry_after,
+                });
+            }
+
+            let body_text = resp.text().await.unwrap_or_default();
+            return Err(PrAgentError::AiHandler(format!(
+                "API returned {status}: {body_text}"
+            )));
+        }
+
+        let api_resp: ApiResponse = resp.json().await.map_err(PrAgentError::Http)?;
+        Ok(api_resp.into_chat_response())
+    }
+
+    /// Send `body`, retrying transient errors.
+    async fn send_with_retries(
+        &self,
+        body: &serde_json::Value,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let mut last_err = None;
+        for attempt in 0..=MODEL_RETRIES {
+            match self.send_completion(body).await {
+                Ok(resp) => return Ok(resp),
+                Err(e @ PrAgentError::RateLimited { .. }) => return Err(e),
+                Err(e) => {
+                    tracing::warn!(
+                        attempt = attempt + 1,
+                        max = MODEL_RETRIES + 1,
+                        error = %e,
+                        "Anthropic request failed, retrying"
+                    );
+                    last_err = Some(e);
+
+                    if attempt < MODEL_RETRIES {
+                        tokio::time::sleep(Duration::from_secs(2u64.pow(attempt + 1))).await;
+                    }
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| PrAgentError::AiHandler("all retries exhausted".into())))
+    }
+}
+
+#[async_trait]
+impl AiHandler for AnthropicHandler {
+    fn deployment_id(&self) -> &str {
+        ""
+    }
+
+    fn capabilities(&self, model: &str) -> ModelCapabilities {
+        let settings = get_settings();
+        ModelCapabilities {
+            supports_system_message: true,
+            supports_temperature: !settings.config.enable_claude_extended_thinking,
+            supports_images: true,
+            requires_streaming: false,
+            reasoning_effort: None,
+            max_tokens: get_max_tokens_with_fallback(model, settings.config.max_model_tokens),
+            // Structured output forces a tool call, which thinking does not allow.
+            supports_json_schema: !settings.config.enable_claude_extended_thinking,
+        }
+    }
+
+    async fn chat_completion(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let body = self.build_request_body(model, system, user, temperature, image_urls);
+        self.send_with_retries(&body).await
+    }
+
+    /// Forces a call to a single tool whose input schema is `schema`; the
+    /// tool input becomes the response content.
+    async fn chat_completion_structured(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+        schema: &ResponseSchema,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let mut body = self.build_request_body(model, system, user, temperature, image_urls);
+        body["tools"] = json!([{
+            "name": schema.name,
+            "description": "Submit the answer.",
+            "input_schema": schema.schema,
+        }]);
+        body["tool_choice"] = json!({"type": "tool", "name": schema.name});
+        self.send_with_retries(&body).await
+    }
+}
+
+/// Build an image content block from a URL or a base64 `data:` URL.
+fn image_block(url: &str) -> serde_json::Value {
+    let inline = url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"));
+    match inline {
+        Some((media_type, data)) => json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": media_type, "data": data}
+        }),
+        None => json!({
+            "type": "image",
+            "source": {"type": "url", "url": url}
+        }),
+    }
+}
+
+/// Map a Messages API `stop_reason` onto [`FinishReason`].
+fn finish_reason(stop_reason: &str) -> FinishReason {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => FinishReason::Stop,
+        "max_tokens" => FinishReason::Length,
+        "tool_use" => FinishReason::ToolCalls,
+        "refusal" => FinishReason::ContentFilter,
+        _ => FinishReason::Unknown,
+    }
+}
+
+// ── API response types ─────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse {
+    content: Vec<ApiContentBlock>,
+    stop_reason: Option<String>,
+    usage: Option<ApiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+    /// Arguments of a `tool_use` block (structured output).
+    input: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiUsage {
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+impl ApiResponse {
+    /// Keep only the answer: the tool input for structured output, else the
+    /// text blocks. Thinking blocks are dropped.
+    fn into_chat_response(self) -> ChatResponse {
+        let tool_input = self
+            .content
+            .iter()
+            .find(|b| b.kind == "tool_use")
+            .and_then(|b| b.input.as_ref())
+            .map(|input| input.to_string());
+        let content = tool_input.unwrap_or_else(|| {
+            self.content
+                .iter()
+                .filter(|b| b.kind == "text")
+                .map(|b| b.text.as_str())
+                .collect::<Vec<_>>()
+                .join("")
+        });
+        ChatResponse {
+            content,
+            finish_reason: self
+                .stop_reason
+                .as_deref()
+                .map(finish_reason)
+                .unwrap_or_default(),
+            usage: self.usage.map(|u| Usage {
+                prompt_tokens: u.input_tokens,
+                completion_tokens: u.output_tokens,
+                total_tokens: u.input_tokens + u.output_tokens,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::config::loader::{load_settings, with_settings};
+
+    fn test_handler() -> AnthropicHandler {
+        AnthropicHandler {
+            client: Client::new(),
+            base_url: "https://api.test.com/v1".into(),
+            api_key: "test-key".into(),
+        }
+    }
+
+    #[test]
+    fn test_is_anthropic_model() {
+        assert!(is_anthropic_model("claude-sonnet-4-5-20250929"));
+        assert!(!is_anthropic_model("anthropic/claude-sonnet-4-5"));
+        assert!(!is_anthropic_model("gpt-4o"));
+    }
+
+    #[test]
+    fn test_build_request_body_basic() {
+        let handler = test_handler();
+        let body = handler.build_request_body(
+            "claude-sonnet-4-5",
+            "system msg",
+            "user msg",
+            Some(0.5),
+            None,
+        );
+
+        assert_eq!(body["model"], "claude-sonnet-4-5");
+        assert_eq!(body["system"], "system msg");
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["role"], "user");
+        assert_eq!(messages[0]["content"], "user msg");
+        assert_eq!(body["temperature"], 0.5);
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_OUTPUT_TOKENS);
+        assert!(body.get("thinking").is_none());
+    }
+
+    #[test]
+    fn test_build_request_body_with_images() {
+        let handler = test_handler();
+        let urls = vec![
+            "https://img.com/a.png".to_string(),
+            "data:image/png;base64,iVBORw0K".to_string(),
+        ];
+        let body = handler.build_request_body("claude-sonnet-4-5", "", "user", None, Some(&urls));
+
+        assert!(
+            body.get("system").is_none(),
+            "empty system should be omitted"
+        );
+        let content = body["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(content.len(), 3);
+        assert_eq!(content[0]["type"], "text");
+        assert_eq!(content[1]["source"]["type"], "url");
+        assert_eq!(content[1]["source"]["url"], "https://img.com/a.png");
+        assert_eq!(content[2]["source"]["type"], "base64");
+        assert_eq!(content[2]["source"]["media_type"], "image/png");
+        assert_eq!(content[2]["source"]["data"], "iVBORw0K");
+    }
+
+    #[tokio::test]
+    async fn test_build_request_body_extended_thinking() {
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert(
+            "config.enable_claude_extended_thinking".into(),
+            "true".into(),
+        );
+        overrides.insert(
+            "config.extended_thinking_budget_tokens".into(),
+            "3000".into(),
+        );
+        overrides.insert(
+            "config.extended_thinking_max_output_tokens".into(),
+            "2000".into(),
+        );
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+
+        let handler = test_handler();
+        let (body, caps) = with_settings(settings, async {
+            (
+                handler.build_request_body("claude-opus-4-1", "sys", "user", Some(0.2), None),
+                handler.capabilities("claude-opus-4-1"),
+            )
+        })
+        .await;
+
+        assert_eq!(body["thinking"]["type"], "enabled");
+        assert_eq!(body["thinking"]["budget_tokens"], 3000);
+        // max_tokens must exceed the thinking budget.
+        assert_eq!(body["max_tokens"], 3001);
+        assert!(body.get("temperature").is_none());
+        assert!(!caps.supports_temperature);
+    }
+
+    #[test]
+    fn test_response_keeps_text_blocks_only() {
+        let api_resp: ApiResponse = serde_json::from_value(json!({
+            "content": [
+                {"type": "thinking", "thinking": "let me see", "signature": "abc"},
+                {"type": "text", "text": "review:\n"},
+                {"type": "text", "text": "  score: 80"}
+            ],
+            "stop_reason": "max_tokens",
+            "usage": {"input_tokens": 120, "output_tokens": 30}
+        }))
+        .unwrap();
+        let resp = api_resp.into_chat_response();
+
+        assert_eq!(resp.content, "review:\n  score: 80");
+        assert_eq!(resp.finish_reason, FinishReason::Length);
+        let usage = resp.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 120);
+        assert_eq!(usage.completion_tokens, 30);
+        assert_eq!(usage.total_tokens, 150);
+    }
+
+    #[test]
+    fn test_response_prefers_tool_input() {
+        let api_resp: ApiResponse = serde_json::from_value(json!({
+            "content": [
+                {"type": "text", "text": "Submitting."},
+                {"type": "tool_use", "id": "t1", "name": "pr_review",
+                 "input": {"review": {"security_concerns": "No"}}}
+            ],
+            "stop_reason": "tool_use"
+        }))
+        .unwrap();
+        let resp = api_resp.into_chat_response();
+
+        assert_eq!(resp.content, r#"{"review":{"security_concerns":"No"}}"#);
+        assert_eq!(resp.finish_reason, FinishReason::ToolCalls);
+    }
+
+    #[test]
+    fn test_finish_reason_mapping() {
+        assert_eq!(finish_reason("end_turn"), FinishReason::Stop);
+        assert_eq!(finish_reason("refusal"), FinishReason::ContentFilter);
+        assert_eq!(finish_reason("pause_turn"), FinishReason::Unknown);
+    }
+}
@@ -0,0 +1,644 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use super::AiHandler;
+use super::token::get_max_tokens_with_fallback;
+use super::types::{ChatResponse, FinishReason, ModelCapabilities, Usage};
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::util::http_client_builder;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Number of retry attempts for transient API errors (not rate limits).
+const MODEL_RETRIES: u32 = 2;
+
+/// Output token cap when extended thinking is off.
+const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 8192;
+
+/// Smallest thinking budget Claude accepts.
+const MIN_THINKING_BUDGET_TOKENS: u32 = 1024;
+
+/// Model name prefix that selects this handler.
+const BEDROCK_MODEL_PREFIX: &str = "bedrock/";
+
+/// SigV4 service name for the Bedrock runtime.
+const SIGNING_SERVICE: &str = "bedrock";
+
+/// Whether `model` selects the Bedrock handler.
+pub fn is_bedrock_model(model: &str) -> bool {
+    model.starts_with(BEDROCK_MODEL_PREFIX)
+}
+
+/// Whether `region` looks like an AWS region name (`us-east-1`). It goes
+/// into the endpoint host, so anything else could redirect signed requests.
+fn is_valid_region(region: &str) -> bool {
+    !region.is_empty()
+        && region
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+/// AWS credentials used to sign requests.
+#[derive(Clone)]
+struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    /// Set for temporary (STS) credentials.
+    session_token: String,
+}
+
+/// AWS Bedrock handler using the Converse API, signed with SigV4.
+///
+/// Models are selected as `bedrock/<model-id>`, e.g.
+/// `bedrock/anthropic.claude-sonnet-4-5-20250929-v1:0`.
+pub struct BedrockHandler {
+    client: Client,
+    endpoint: String,
+    region: String,
+    credentials: AwsCredentials,
+}
+
+impl BedrockHandler {
+    /// Create a new handler from the `[aws]` settings.
+    pub fn from_settings() -> Result<Self, PrAgentError> {
+        let settings = get_settings();
+        let aws = &settings.aws;
+        if aws.region_name.is_empty() {
+            return Err(PrAgentError::AiHandler(
+                "aws.region_name is required for bedrock/ models".into(),
+            ));
+        }
+        if !is_valid_region(&aws.region_name) {
+            return Err(PrAgentError::AiHandler(format!(
+                "aws.region_name {:?} is not a valid AWS region",
+                aws.region_name
+            )));
+        }
+        if aws.access_key_id.is_empty() || aws.secret_access_key.is_empty() {
+            return Err(PrAgentError::AiHandler(
+                "aws.access_key_id and aws.secret_access_key are required for bedrock/ models"
+                    .into(),
+            ));
+        }
+        let endpoint = if aws.bedrock_endpoint.is_empty() {
+            format!("https://bedrock-runtime.{}.amazonaws.com", aws.region_name)
+        } else {
+            aws.bedrock_endpoint.trim_end_matches('/').to_string()
+        };
+
+        let client = http_client_builder(&settings.http, &settings.http.ai_headers)?
+            .timeout(Duration::from_secs(settings.config.ai_timeout))
+            .build()
+            .map_err(PrAgentError::Http)?;
+
+        Ok(Self {
+            client,
+            endpoint,
+            region: aws.region_name.clone(),
+            credentials: AwsCredentials {
+                access_key_id: aws.access_key_id.clone(),
+                secret_access_key: aws.secret_access_key.clone(),
+                session_token: aws.session_token.clone(),
+            },
+        })
+    }
+
+    /// Build the request body for the Converse API.
+    fn build_request_body(
+        &self,
+        model_id: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+    ) -> serde_json::Value {
+        let settings = get_settings();
+        let config = &settings.config;
+
+        let mut content = vec![json!({"text": user})];
+        for url in image_urls.unwrap_or_default() {
+            match image_block(url) {
+                Some(block) => content.push(block),
+                None => tracing::debug!(url, "Bedrock only accepts inline images, skipping"),
+            }
+        }
+
+        let mut body = json!({
+            "messages": [{"role": "user", "content": content}],
+        });
+
+        if !system.is_empty() {
+            body["system"] = json!([{"text": system}]);
+        }
+
+        if config.enable_claude_extended_thinking && model_id.contains("claude") {
+            // Same budget rules as the native Anthropic handler.
+            let budget = config
+                .extended_thinking_budget_tokens
+                .max(MIN_THINKING_BUDGET_TOKENS);
+            let max_tokens = config.extended_thinking_max_output_tokens.max(budget + 1);
+            body["inferenceConfig"] = json!({"maxTokens": max_tokens});
+            body["additionalModelRequestFields"] =
+                json!({"thinking": {"type": "enabled", "budget_tokens": budget}});
+        } else {
+            body["inferenceConfig"] = json!({
+                "maxTokens": DEFAULT_MAX_OUTPUT_TOKENS,
+                "temperature": temperature.unwrap_or(config.temperature),
+            });
+        }
+
+        body
+    }
+
+    /// Sign and send a single request. No retry logic here.
+    async fn send_completion(
+        &self,
+        model_id: &str,
+        body: &serde_json::Value,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let path = format!("/model/{}/converse", uri_encode(model_id, true));
+        let url = format!("{}{path}", self.endpoint);
+        let host = url::Url::parse(&url)
+            .ok()
+            .and_then(|u| {
+                let host = u.host_str()?.to_string();
+                Some(match u.port() {
+                    Some(port) => format!("{host}:{port}"),
+                    None => host,
+                })
+            })
+            .ok_or_else(|| PrAgentError::AiHandler(format!("invalid Bedrock endpoint: {url}")))?;
+
+        let payload = serde_json::to_vec(body)?;
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let mut headers = vec![
+            ("content-type", "application/json".to_string()),
+            ("host", host),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if !self.credentials.session_token.is_empty() {
+            headers.push((
+                "x-amz-security-token",
+                self.credentials.session_token.clone(),
+            ));
+        }
+        let authorization = sigv4_authorization(
+            &self.credentials,
+            &self.region,
+            SIGNING_SERVICE,
+            "POST",
+            &path,
+            &headers,
+            &payload,
+            &amz_date,
+        );
+
+        let mut req = self
+            .client
+            .post(&url)
+            .header("authorization", authorization)
+            .body(payload);
+        // `host` is set by the client from the URL.
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            req = req.header(*name, value);
+        }
+        let resp = req.send().await.map_err(PrAgentError::Http)?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+
+            if status.as_u16() == 429 {
+                let retry_after = resp
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(60);
+                return Err(PrAgentError::RateLimited {
+                    retry_after_secs: retry_after,
+                });
+            }
+
+            let body_text = resp.text().await.unwrap_or_default();
+            return Err(PrAgentError::AiHandler(format!(
+                "Bedrock returned {status}: {body_text}"
+            )));
+        }
+
+        let api_resp: ConverseResponse = resp.json().await.map_err(PrAgentError::Http)?;
+        Ok(api_resp.into_chat_response())
+    }
+}
+
+#[async_trait]
+impl AiHandler for BedrockHandler {
+    fn deployment_id(&self) -> &str {
+        ""
+    }
+
+    fn capabilities(&self, model: &str) -> ModelCapabilities {
+        let settings = get_settings();
+        let thinking = settings.config.enable_claude_extended_thinking && model.contains("claude");
+        ModelCapabilities {
+            supports_system_message: true,
+            supports_temperature: !thinking,
+            supports_images: true,
+            requires_streaming: false,
+            reasoning_effort: None,
+            max_tokens: get_max_tokens_with_fallback(model, settings.config.max_model_tokens),
+            supports_json_schema: false,
+        }
+    }
+
+    async fn chat_completion(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let model_id = model.strip_prefix(BEDROCK_MODEL_PREFIX).unwrap_or(model);
+        let body = self.build_request_body(model_id, system, user, temperature, image_urls);
+
+        let mut last_err = None;
+        for attempt in 0..=MODEL_RETRIES {
+            match self.send_completion(model_id, &body).await {
+                Ok(resp) => return Ok(resp),
+                Err(e @ PrAgentError::RateLimited { .. }) => return Err(e),
+                Err(e) => {
+                    tracing::warn!(
+                        attempt = attempt + 1,
+                        max = MODEL_RETRIES + 1,
+                        error = %e,
+                        "Bedrock request failed, retrying"
+                    );
+                    last_err = Some(e);
+
+                    if attempt < MODEL_RETRIES {
+                        tokio::time::sleep(Duration::from_secs(2u64.pow(attempt + 1))).await;
+                    }
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| PrAgentError::AiHandler("all retries exhausted".into())))
+    }
+}
+
+/// Build a Converse image block from a base64 `data:` URL.
+///
+/// The Converse API only takes inline bytes, so remote URLs yield `None`.
+fn image_block(url: &str) -> Option<serde_json::Value> {
+    let (media_type, data) = url.strip_prefix("data:")?.split_once(";base64,")?;
+    let format = media_type.strip_prefix("image/")?;
+    let format = if format == "jpg" { "jpeg" } else { format };
+    Some(json!({"image": {"format": format, "source": {"bytes": data}}}))
+}
+
+// ── SigV4 signing ──────────────────────────────────────────────────
+
+/// Percent-encode `s` per SigV4 rules (everything but `A-Za-z0-9-_.~`).
+///
+/// With `encode_slash == false` path separators are kept.
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Compute the SigV4 `Authorization` header for a request without a query string.
+///
+/// `path` is the URL path as sent (already percent-encoded); `headers` are
+/// the signed headers, lowercase and sorted by name. `amz_date` is the
+/// `x-amz-date` value (`YYYYMMDDTHHMMSSZ`).
+#[allow(clippy::too_many_arguments)]
+fn sigv4_authorization(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    path: &str,
+    headers: &[(&str, String)],
+    payload: &[u8],
+    amz_date: &str,
+) -> String {
+    let date = &amz_date[..8];
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    // Services other than S3 sign the path encoded a second time.
+    let canonical_request = format!(
+        "{method}\n{}\n\n{canonical_headers}\n{signed_headers}\n{}",
+        uri_encode(path, false),
+        hex::encode(Sha256::digest(payload))
+    );
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = format!("AWS4{}", credentials.secret_access_key);
+    let key = hmac_sha256(key.as_bytes(), date);
+    let key = hmac_sha256(&key, region);
+    let key = hmac_sha256(&key, service);
+    let key = hmac_sha256(&key, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        credentials.access_key_id
+    )
+}
+
+/// Map a Converse `stopReason` onto [`FinishReason`].
+fn finish_reason(stop_reason: &str) -> FinishReason {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => FinishReason::Stop,
+        "max_tokens" => FinishReason::Length,
+        "tool_use" => FinishReason::ToolCalls,
+        "guardrail_intervened" | "content_filtered" => FinishReason::ContentFilter,
+        _ => FinishReason::Unknown,
+    }
+}
+
+// ── API response types ─────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseResponse {
+    output: ConverseOutput,
+    stop_reason: Option<String>,
+    usage: Option<ConverseUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConverseOutput {
+    message: ConverseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConverseMessage {
+    content: Vec<ConverseContentBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConverseContentBlock {
+    /// Absent for reasoning and other non-text blocks.
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseUsage {
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+impl ConverseResponse {
+    /// Keep only the answer text; reasoning blocks are dropped.
+    fn into_chat_response(self) -> ChatResponse {
+        let content = self
+            .output
+            .message
+            .content
+            .into_iter()
+            .filter_map(|b| b.text)
+            .collect::<Vec<_>>()
+            .join("");
+        ChatResponse {
+            content,
+            finish_reason: self
+                .stop_reason
+                .as_deref()
+                .map(finish_reason)
+                .unwrap_or_default(),
+            usage: self.usage.map(|u| Usage {
+                prompt_tokens: u.input_tokens,
+                completion_tokens: u.output_tokens,
+                total_tokens: u.input_tokens + u.output_tokens,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::config::loader::{load_settings, with_settings};
+
+    const EXAMPLE_SECRET: &str = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+
+    fn credentials(session_token: &str) -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".into(),
+            secret_access_key: EXAMPLE_SECRET.into(),
+            session_token: session_token.into(),
+        }
+    }
+
+    fn test_handler() -> BedrockHandler {
+        BedrockHandler {
+            client: Client::new(),
+            endpoint: "https://bedrock-runtime.us-east-1.amazonaws.com".into(),
+            region: "us-east-1".into(),
+            credentials: credentials(""),
+        }
+    }
+
+    #[test]
+    fn test_is_bedrock_model() {
+        assert!(is_bedrock_model(
+            "bedrock/anthropic.claude-sonnet-4-5-20250929-v1:0"
+        ));
+        assert!(!is_bedrock_model("claude-sonnet-4-5"));
+    }
+
+    #[test]
+    fn test_is_valid_region() {
+        assert!(is_valid_region("us-east-1"));
+        assert!(is_valid_region("ap-southeast-2"));
+        assert!(!is_valid_region(""));
+        assert!(!is_valid_region("evil.com/x?"));
+        assert!(!is_valid_region("US-EAST-1"));
+    }
+
+    #[test]
+    fn test_uri_encode() {
+        assert_eq!(
+            uri_encode("anthropic.claude-v1:0", true),
+            "anthropic.claude-v1%3A0"
+        );
+        assert_eq!(uri_encode("/model/a%3A0", false), "/model/a%253A0");
+    }
+
+    #[test]
+    fn test_sigv4_matches_aws_test_suite() {
+        // `get-vanilla` from the AWS Signature Version 4 test suite.
+        let auth = sigv4_authorization(
+            &credentials(""),
+            "us-east-1",
+            "service",
+            "GET",
+            "/",
+            &[
+                ("host", "example.amazonaws.com".into()),
+                ("x-amz-date", "20150830T123600Z".into()),
+            ],
+            b"",
+            "20150830T123600Z",
+        );
+        assert_eq!(
+            auth,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_sigv4_bedrock_request_with_session_token() {
+        let auth = sigv4_authorization(
+            &credentials("session-token"),
+            "us-east-1",
+            SIGNING_SERVICE,
+            "POST",
+            "/model/anthropic.claude-3-5-sonnet-20240620-v1%3A0/converse",
+            &[
+                ("content-type", "application/json".into()),
+                ("host", "bedrock-runtime.us-east-1.amazonaws.com".into()),
+                ("x-amz-date", "20150830T123600Z".into()),
+                ("x-amz-security-token", "session-token".into()),
+            ],
+            br#"{"messages":[]}"#,
+            "20150830T123600Z",
+        );
+        assert!(auth.contains(
+            "SignedHeaders=content-type;host;x-amz-date;x-amz-security-token, \
+             Signature=5651330a36be4e678c38f030d8681800f6ac1a6cbc5bdd0501cf9970155bca47"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_build_request_body_basic() {
+        let settings = Arc::new(load_settings(&Default::default(), None, None).unwrap());
+        let handler = test_handler();
+        let urls = vec![
+            "https://img.com/a.png".to_string(),
+            "data:image/png;base64,iVBORw0K".to_string(),
+        ];
+        let body = with_settings(settings, async {
+            handler.build_request_body(
+                "meta.llama3-70b-instruct-v1:0",
+                "system msg",
+                "user msg",
+                Some(0.5),
+                Some(&urls),
+            )
+        })
+        .await;
+
+        assert_eq!(body["system"][0]["text"], "system msg");
+        let content = body["messages"][0]["content"].as_array().unwrap();
+        // Remote image URLs are skipped; inline ones become byte blocks.
+        assert_eq!(content.len(), 2);
+        assert_eq!(content[0]["text"], "user msg");
+        assert_eq!(content[1]["image"]["format"], "png");
+        assert_eq!(content[1]["image"]["source"]["bytes"], "iVBORw0K");
+        assert_eq!(body["inferenceConfig"]["temperature"], 0.5);
+        assert_eq!(
+            body["inferenceConfig"]["maxTokens"],
+            DEFAULT_MAX_OUTPUT_TOKENS
+        );
+        assert!(body.get("additionalModelRequestFields").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_build_request_body_extended_thinking() {
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert(
+            "config.enable_claude_extended_thinking".into(),
+            "true".into(),
+        );
+        overrides.insert(
+            "config.extended_thinking_budget_tokens".into(),
+            "3000".into(),
+        );
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+
+        let handler = test_handler();
+        let body = with_settings(settings, async {
+            handler.build_request_body(
+                "anthropic.claude-opus-4-1-20250805-v1:0",
+                "",
+                "user",
+                Some(0.2),
+                None,
+            )
+        })
+        .await;
+
+        assert!(body.get("system").is_none());
+        assert_eq!(
+            body["additionalModelRequestFields"]["thinking"]["budget_tokens"],
+            3000
+        );
+        assert!(body["inferenceConfig"].get("temperature").is_none());
+    }
+
+    #[test]
+    fn test_converse_response_keeps_text_blocks_only() {
+        let api_resp: ConverseResponse = serde_json::from_value(json!({
+            "output": {"message": {"role": "assistant", "content": [
+                {"reasoningContent": {"reasoningText": {"text": "hmm"}}},
+                {"text": "review:\n"},
+                {"text": "  score: 80"}
+            ]}},
+            "stopReason": "max_tokens",
+            "usage": {"inputTokens": 120, "outputTokens": 30, "totalTokens": 150}
+        }))
+        .unwrap();
+        let resp = api_resp.into_chat_response();
+
+        assert_eq!(resp.content, "review:\n  score: 80");
+        assert_eq!(resp.finish_reason, FinishReason::Length);
+        let usage = resp.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 120);
+        assert_eq!(usage.total_tokens, 150);
+        assert_eq!(
+            finish_reason("guardrail_intervened"),
+            FinishReason::ContentFilter
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! Cache of AI responses for re-runs on an unchanged PR.
+//!
+//! Tools run inside a [`CacheScope`] naming the PR and its head commit.
+//! Completions in the scope are keyed by a hash of the PR, head SHA, model
+//! and prompt, so re-running `/review` on the same commit reuses the earlier
+//! answer. Entering a scope with a new head SHA drops the PR's old entries.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+use sha2::{Digest, Sha256};
+
+use crate::ai::types::{ChatResponse, FinishReason};
+use crate::config::loader::get_settings;
+
+tokio::task_local! {
+    /// PR and head commit the tool running in this task works on.
+    static CACHE_SCOPE: CacheScope;
+}
+
+static MEMORY: LazyLock<Mutex<MemoryCache>> = LazyLock::new(|| Mutex::new(MemoryCache::default()));
+
+/// The PR revision cached responses belong to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheScope {
+    pub pr_url: String,
+    pub head_sha: String,
+}
+
+#[derive(Debug, Default)]
+struct MemoryCache {
+    entries: HashMap<String, (String, ChatResponse)>,
+    /// Keys in insertion order, for eviction.
+    order: VecDeque<String>,
+    /// Last head SHA seen per PR URL.
+    heads: HashMap<String, String>,
+}
+
+impl MemoryCache {
+    fn get(&self, key: &str) -> Option<ChatResponse> {
+        self.entries.get(key).map(|(_, resp)| resp.clone())
+    }
+
+    fn insert(&mut self, key: String, pr_url: &str, response: ChatResponse, max_entries: usize) {
+        if max_entries == 0 {
+            return;
+        }
+        if self
+            .entries
+            .insert(key.clone(), (pr_url.to_string(), response))
+            .is_none()
+        {
+            self.order.push_back(key);
+        }
+        while self.order.len() > max_entries {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Record `scope` as the PR's current head, dropping entries of an older
+    /// one. Returns whether the head changed.
+    fn set_head(&mut self, scope: &CacheScope) -> bool {
+        let previous = self
+            .heads
+            .insert(scope.pr_url.clone(), scope.head_sha.clone());
+        let changed = previous.is_some_and(|sha| sha != scope.head_sha);
+        if changed {
+            self.entries
+                .retain(|_, (pr_url, _)| *pr_url != scope.pr_url);
+            let entries = &self.entries;
+            self.order.retain(|key| entries.contains_key(key));
+        }
+        changed
+    }
+
+    #[cfg(test)]
+    fn len_for(&self, pr_url: &str) -> usize {
+        self.entries
+            .values()
+            .filter(|(url, _)| url == pr_url)
+            .count()
+    }
+}
+
+/// Run `fut` with completions cached for `scope`.
+pub async fn with_cache_scope<F: std::future::Future>(scope: CacheScope, fut: F) -> F::Output {
+    let changed = MEMORY
+        .lock()
+        .map(|mut memory| memory.set_head(&scope))
+        .unwrap_or(false);
+    if let Some(dir) = disk_dir() {
+        remove_stale_heads(&dir, &scope);
+    }
+    if changed {
+        tracing::debug!(
+            pr_url = scope.pr_url,
+            head_sha = scope.head_sha,
+            "PR head changed, dropped cached AI responses"
+        );
+    }
+    CACHE_SCOPE.scope(scope, fut).await
+}
+
+/// Cache key for a completion, or `None` outside a [`with_cache_scope`].
+pub fn cache_key(
+    model: &str,
+    system: &str,
+    user: &str,
+    image_urls: Option<&[String]>,
+    schema: Option<&str>,
+) -> Option<String> {
+    CACHE_SCOPE
+        .try_with(|scope| {
+            let mut hasher = Sha256::new();
+            for part in [
+                scope.pr_url.as_str(),
+                scope.head_sha.as_str(),
+                model,
+                system,
+                user,
+                schema.unwrap_or(""),
+            ]
+            .into_iter()
+            .chain(image_urls.unwrap_or_default().iter().map(String::as_str))
+            {
+                hasher.update(part.as_bytes());
+                hasher.update([0]);
+            }
+            hex::encode(hasher.finalize())
+        })
+        .ok()
+}
+
+/// Cached response for `key`, from memory or else from disk.
+pub fn get(key: &str) -> Option<ChatResponse> {
+    if let Some(resp) = MEMORY.lock().ok()?.get(key) {
+        return Some(resp);
+    }
+    let scope = CACHE_SCOPE.try_with(Clone::clone).ok()?;
+    let path = entry_path(&disk_dir()?, &scope, key);
+    let raw = std::fs::read_to_string(&path).ok()?;
+    let resp: ChatResponse = match serde_json::from_str(&raw) {
+        Ok(resp) => resp,
+        Err(e) => {
+            tracing::warn!(
+                path = %path.display(),
+                error = %e,
+                "ignoring unreadable cached AI response"
+            );
+            return None;
+        }
+    };
+    if let Ok(mut memory) = MEMORY.lock() {
+        memory.insert(
+            key.to_string(),
+            &scope.pr_url,
+            resp.clone(),
+            get_settings().response_cache.max_entries,
+        );
+    }
+    Some(resp)
+}
+
+/// Cache a complete response under `key`. Truncated and cancelled answers
+/// are not cached, so a re-run gets a fresh chance.
+pub fn put(key: &str, response: &ChatResponse) {
+    if matches!(
+        response.finish_reason,
+        FinishReason::Length | FinishReason::Cancelled | FinishReason::ContentFilter
+    ) {
+        return;
+    }
+    let Ok(scope) = CACHE_SCOPE.try_with(Clone::clone) else {
+        return;
+    };
+    if let Ok(mut memory) = MEMORY.lock() {
+        memory.insert(
+            key.to_string(),
+            &scope.pr_url,
+            response.clone(),
+            get_settings().response_cache.max_entries,
+        );
+    }
+    if let Some(dir) = disk_dir() {
+        let path = entry_path(&dir, &scope, key);
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| {
+                let json = serde_json::to_string(response).map_err(std::io::Error::other)?;
+                std::fs::write(&path, json)
+            });
+        if let Err(e) = written {
+            tracing::warn!(
+                path = %path.display(),
+                error = %e,
+                "failed to write cached AI response"
+            );
+        }
+    }
+}
+
+fn disk_dir() -> Option<PathBuf> {
+    let dir = get_settings().response_cache.dir.trim().to_string();
+    (!dir.is_empty()).then(|| PathBuf::from(dir))
+}
+
+/// Directory holding the cached responses of one PR.
+fn pr_dir(dir: &Path, pr_url: &str) -> PathBuf {
+    let digest = hex::encode(Sha256::digest(pr_url.as_bytes()));
+    dir.join(&digest[..16])
+}
+
+/// `<dir>/<pr>/<head_sha>/<key>.json`
+fn entry_path(dir: &Path, scope: &CacheScope, key: &str) -> PathBuf {
+    pr_dir(dir, &scope.pr_url)
+        .join(&scope.head_sha)
+        .join(format!("{key}.json"))
+}
+
+/// Delete the on-disk responses of the PR's other head commits.
+fn remove_stale_heads(dir: &Path, scope: &CacheScope) {
+    let Ok(heads) = std::fs::read_dir(pr_dir(dir, &scope.pr_url)) else {
+        return;
+    };
+    for head in heads.flatten() {
+        if head.file_name() == scope.head_sha.as_str() {
+            continue;
+        }
+        if let Err(e) = std::fs::remove_dir_all(head.path()) {
+            tracing::warn!(
+                path = %head.path().display(),
+                error = %e,
+                "failed to remove stale cached AI responses"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::{load_settings, with_settings};
+    use std::sync::Arc;
+
+    fn scope(pr_url: &str, head_sha: &str) -> CacheScope {
+        CacheScope {
+            pr_url: pr_url.into(),
+            head_sha: head_sha.into(),
+        }
+    }
+
+    fn response(content: &str, finish_reason: FinishReason) -> ChatResponse {
+        ChatResponse {
+            content: content.into(),
+            finish_reason,
+            usage: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_requires_scope() {
+        assert!(cache_key("gpt-4o", "sys", "user", None, None).is_none());
+        let (a, b, c) = with_cache_scope(scope("https://example.com/pr/key", "a1"), async {
+            (
+                cache_key("gpt-4o", "sys", "user", None, None).unwrap(),
+                cache_key("gpt-4o", "sys", "user", None, None).unwrap(),
+                cache_key("gpt-4o", "sys", "other", None, None).unwrap(),
+            )
+        })
+        .await;
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        let d = with_cache_scope(scope("https://example.com/pr/key", "b2"), async {
+            cache_key("gpt-4o", "sys", "user", None, None).unwrap()
+        })
+        .await;
+        assert_ne!(a, d);
+    }
+
+    #[tokio::test]
+    async fn test_head_change_drops_entries() {
+        let pr_url = "https://example.com/pr/head-change";
+        with_cache_scope(scope(pr_url, "a1"), async {
+            let key = cache_key("gpt-4o", "sys", "user", None, None).unwrap();
+            put(&key, &response("done", FinishReason::Stop));
+            let partial = cache_key("gpt-4o", "sys", "partial", None, None).unwrap();
+            put(&partial, &response("cut", FinishReason::Cancelled));
+            assert_eq!(get(&key).unwrap().content, "done");
+            assert!(get(&partial).is_none());
+        })
+        .await;
+        assert_eq!(MEMORY.lock().unwrap().len_for(pr_url), 1);
+
+        // Same head again keeps the entry; a new head drops it.
+        with_cache_scope(scope(pr_url, "a1"), async {}).await;
+        assert_eq!(MEMORY.lock().unwrap().len_for(pr_url), 1);
+        with_cache_scope(scope(pr_url, "b2"), async {}).await;
+        assert_eq!(MEMORY.lock().unwrap().len_for(pr_url), 0);
+    }
+
+    #[test]
+    fn test_memory_eviction() {
+        let mut memory = MemoryCache::default();
+        for i in 0..3 {
+            memory.insert(
+                format!("k{i}"),
+                "pr",
+                response(&i.to_string(), FinishReason::Stop),
+                2,
+            );
+        }
+        assert!(memory.get("k0").is_none());
+        assert_eq!(memory.get("k2").unwrap().content, "2");
+        assert_eq!(memory.order.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_survives_memory_and_head_change() {
+        let dir = std::env::temp_dir().join(format!("pr-agent-ai-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut overrides = HashMap::new();
+        overrides.insert("response_cache.dir".into(), dir.display().to_string());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+        let pr_url = "https://example.com/pr/disk";
+
+        let key = with_settings(
+            settings.clone(),
+            with_cache_scope(scope(pr_url, "a1"), async {
+                let key = cache_key("gpt-4o", "sys", "user", None, None).unwrap();
+                put(&key, &response("from disk", FinishReason::Stop));
+                MEMORY.lock().unwrap().entries.remove(&key);
+                assert_eq!(get(&key).unwrap().content, "from disk");
+                key
+            }),
+        )
+        .await;
+        let path = entry_path(&dir, &scope(pr_url, "a1"), &key);
+        assert!(path.exists());
+
+        with_settings(settings, with_cache_scope(scope(pr_url, "b2"), async {})).await;
+        assert!(!path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,216 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+
+use super::AiHandler;
+use super::types::{ChatResponse, FinishReason, ModelCapabilities};
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+
+/// Model name prefix that selects the fixed-response handler.
+pub const MOCK_MODEL_PREFIX: &str = "mock/";
+
+/// Markers in the system prompt that identify which tool is calling.
+///
+/// Checked in order; the first match wins. Each kind maps to a canned
+/// response file `<kind>.yaml` (or `<kind>.md`) in `config.mock_responses_dir`.
+const PROMPT_KINDS: &[(&str, &str)] = &[
+    ("evaluate the correctness and importance", "reflect"),
+    (
+        "the organization's best practices listed below",
+        "best_practices",
+    ),
+    ("code analysis and suggestions", "improve"),
+    ("full description for the PR content", "describe"),
+    ("provide labels that describe the PR content", "labels"),
+    ("constructive and concise feedback", "review"),
+    ("generate 3 short questions", "questions"),
+    ("PR-Changelog-Updater", "changelog"),
+    ("PR-Changelog-Fragment-Writer", "changelog_fragment"),
+    ("PR-Test-Writer", "test"),
+    ("PR-Quick-Take", "quick_take"),
+    ("PR-File-Summarizer", "ai_metadata"),
+    ("PR-Patch-Summarizer", "large_patch"),
+    ("Doc-helper", "help"),
+    ("answer questions about a Git Pull Request", "ask"),
+];
+
+/// Deterministic handler that serves canned responses from a folder.
+///
+/// Selected with `config.model = "mock/fixed"`, so a staging deployment can
+/// exercise the full webhook → tool → publish path without a model provider.
+/// Falls back to `default.yaml` / `default.md` when no tool-specific file exists.
+pub struct FixedResponseHandler {
+    dir: PathBuf,
+}
+
+impl FixedResponseHandler {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Create from `config.mock_responses_dir`.
+    pub fn from_settings() -> Result<Self, PrAgentError> {
+        let settings = get_settings();
+        let dir = &settings.config.mock_responses_dir;
+        if dir.is_empty() {
+            return Err(PrAgentError::Other(
+                "config.mock_responses_dir must be set when using a mock/ model".into(),
+            ));
+        }
+        Ok(Self::new(dir))
+    }
+
+    /// Read the first existing canned response for `kind`, then `default`.
+    fn load_response(&self, kind: &str) -> Result<String, PrAgentError> {
+        for name in [kind, "default"] {
+            for ext in ["yaml", "md"] {
+                let path = self.dir.join(format!("{name}.{ext}"));
+                if path.is_file() {
+                    return std::fs::read_to_string(&path).map_err(PrAgentError::Io);
+                }
+            }
+        }
+        Err(PrAgentError::AiHandler(format!(
+            "no canned response for '{kind}' in {}",
+            self.dir.display()
+        )))
+    }
+}
+
+/// Identify the calling tool from its system prompt.
+fn classify_prompt(system: &str) -> &'static str {
+    PROMPT_KINDS
+        .iter()
+        .find(|(marker, _)| system.contains(marker))
+        .map_or("default", |(_, kind)| kind)
+}
+
+#[async_trait]
+impl AiHandler for FixedResponseHandler {
+    fn deployment_id(&self) -> &str {
+        ""
+    }
+
+    fn capabilities(&self, _model: &str) -> ModelCapabilities {
+        ModelCapabilities::default()
+    }
+
+    async fn chat_completion(
+        &self,
+        model: &str,
+        system: &str,
+        _user: &str,
+        _temperature: Option<f32>,
+        _image_urls: Option<&[String]>,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let kind = classify_prompt(system);
+        tracing::info!(model, kind, dir = %self.dir.display(), "serving canned AI response");
+        let content = self.load_response(kind)?;
+        Ok(ChatResponse {
+            content,
+            finish_reason: FinishReason::Stop,
+            usage: None,
+        })
+    }
+}
+
+/// Whether `model` selects the fixed-response handler.
+pub fn is_mock_model(model: &str) -> bool {
+    model.starts_with(MOCK_MODEL_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_prompt() {
+        let settings =
+            crate::config::loader::load_settings(&std::collections::HashMap::new(), None, None)
+                .unwrap();
+        assert_eq!(classify_prompt(&settings.pr_review_prompt.system), "review");
+        assert_eq!(
+            classify_prompt(&settings.pr_description_prompt.system),
+            "describe"
+        );
+        assert_eq!(
+            classify_prompt(&settings.pr_code_suggestions_prompt.system),
+            "improve"
+        );
+        assert_eq!(
+            classify_prompt(&settings.pr_code_suggestions_reflect_prompt.system),
+            "reflect"
+        );
+        assert_eq!(
+            classify_prompt(&settings.pr_best_practices_prompt.system),
+            "best_practices"
+        );
+        assert_eq!(classify_prompt(&settings.pr_questions_prompt.system), "ask");
+        assert_eq!(
+            classify_prompt(&settings.pr_information_from_user_prompt.system),
+            "questions"
+        );
+        assert_eq!(
+            classify_prompt(&settings.pr_update_changelog_prompt.system),
+            "changelog"
+        );
+        assert_eq!(
+            classify_prompt(&settings.pr_changelog_fragment_prompt.system),
+            "changelog_fragment"
+        );
+        assert_eq!(
+            classify_prompt(&settings.pr_custom_labels_prompt.system),
+            "labels"
+        );
+        assert_eq!(classify_prompt(&settings.pr_test_prompt.system), "test");
+        assert_eq!(
+            classify_prompt(&settings.pr_quick_take_prompt.system),
+            "quick_take"
+        );
+        assert_eq!(
+            classify_prompt(&settings.pr_ai_metadata_prompt.system),
+            "ai_metadata"
+        );
+        assert_eq!(
+            classify_prompt(&settings.pr_large_patch_prompt.system),
+            "large_patch"
+        );
+        assert_eq!(classify_prompt("something else"), "default");
+    }
+
+    #[tokio::test]
+    async fn test_fixed_handler_serves_files() {
+        let dir = std::env::temp_dir().join(format!("pr-agent-fixed-ai-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("review.yaml"), "review:\n  score: 90\n").unwrap();
+        std::fs::write(dir.join("default.md"), "fallback").unwrap();
+
+        let handler = FixedResponseHandler::new(&dir);
+        let resp = handler
+            .chat_completion(
+                "mock/fixed",
+                "Your task is to provide constructive and concise feedback for the PR.",
+                "",
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.content, "review:\n  score: 90\n");
+
+        let resp = handler
+            .chat_completion("mock/fixed", "unknown prompt", "", None, None)
+            .await
+            .unwrap();
+        assert_eq!(resp.content, "fallback");
+
+        std::fs::remove_file(dir.join("default.md")).unwrap();
+        let err = handler
+            .chat_completion("mock/fixed", "unknown prompt", "", None, None)
+            .await;
+        assert!(err.is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
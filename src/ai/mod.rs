@@ -1,11 +1,25 @@
+pub mod anthropic;
+pub mod bedrock;
+pub mod cache;
+pub mod fixed;
+pub mod ollama;
 pub mod openai;
+pub mod router;
+pub mod schema;
+pub mod stream;
 pub mod token;
 pub mod types;
 
 use crate::error::PrAgentError;
+use crate::output::terminal;
 use async_trait::async_trait;
+use stream::StreamProgress;
 use types::ChatResponse;
-pub use types::ModelCapabilities;
+pub use types::{ModelCapabilities, ResponseSchema};
+
+/// Appended to the system prompt when the answer is constrained to a schema,
+/// since the prompts themselves ask for YAML.
+const STRUCTURED_OUTPUT_NOTE: &str = "Return the answer as a single JSON object that follows the provided schema, using the same keys as the YAML format described above.";
 
 /// Trait for AI/LLM provider handlers.
 ///
@@ -29,6 +43,41 @@ pub trait AiHandler: Send + Sync {
         temperature: Option<f32>,
         image_urls: Option<&[String]>,
     ) -> Result<ChatResponse, PrAgentError>;
+
+    /// Send a chat completion whose answer must follow `schema`.
+    ///
+    /// Only called when [`ModelCapabilities::supports_json_schema`] is set;
+    /// the default ignores the schema.
+    async fn chat_completion_structured(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+        _schema: &ResponseSchema,
+    ) -> Result<ChatResponse, PrAgentError> {
+        self.chat_completion(model, system, user, temperature, image_urls)
+            .await
+    }
+
+    /// Send a chat completion, streaming the output into `progress`.
+    ///
+    /// Streaming handlers report tokens as they arrive and return the partial
+    /// answer ([`types::FinishReason::Cancelled`]) once `progress` is cancelled.
+    /// The default does not stream.
+    async fn chat_completion_stream(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+        _progress: &StreamProgress,
+    ) -> Result<ChatResponse, PrAgentError> {
+        self.chat_completion(model, system, user, temperature, image_urls)
+            .await
+    }
 }
 
 /// Try the primary model first, then each fallback in order.
@@ -43,13 +92,92 @@ pub async fn chat_completion_with_fallback(
     user: &str,
     temperature: Option<f32>,
     image_urls: Option<&[String]>,
+) -> Result<ChatResponse, PrAgentError> {
+    chat_completion_with_schema(
+        handler,
+        primary_model,
+        fallback_models,
+        system,
+        user,
+        temperature,
+        image_urls,
+        None,
+    )
+    .await
+}
+
+/// [`chat_completion_with_fallback`] that asks for JSON matching `schema`
+/// from models that support it. Other models answer in prose as usual.
+#[allow(clippy::too_many_arguments)]
+pub async fn chat_completion_with_schema(
+    handler: &dyn AiHandler,
+    primary_model: &str,
+    fallback_models: &[String],
+    system: &str,
+    user: &str,
+    temperature: Option<f32>,
+    image_urls: Option<&[String]>,
+    schema: Option<&ResponseSchema>,
+) -> Result<ChatResponse, PrAgentError> {
+    let cache_key = cache::cache_key(
+        primary_model,
+        system,
+        user,
+        image_urls,
+        schema.map(|s| s.name),
+    );
+    if let Some(key) = &cache_key
+        && let Some(mut resp) = cache::get(key)
+    {
+        tracing::info!(model = primary_model, "reusing cached AI response");
+        // No tokens were spent on this answer.
+        resp.usage = None;
+        return Ok(resp);
+    }
+    let resp = completion_with_fallbacks(
+        handler,
+        primary_model,
+        fallback_models,
+        system,
+        user,
+        temperature,
+        image_urls,
+        schema,
+    )
+    .await?;
+    if let Some(key) = &cache_key {
+        cache::put(key, &resp);
+    }
+    Ok(resp)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn completion_with_fallbacks(
+    handler: &dyn AiHandler,
+    primary_model: &str,
+    fallback_models: &[String],
+    system: &str,
+    user: &str,
+    temperature: Option<f32>,
+    image_urls: Option<&[String]>,
+    schema: Option<&ResponseSchema>,
 ) -> Result<ChatResponse, PrAgentError> {
     // Try primary model
-    match handler
-        .chat_completion(primary_model, system, user, temperature, image_urls)
-        .await
+    match timed_completion(
+        handler,
+        primary_model,
+        system,
+        user,
+        temperature,
+        image_urls,
+        schema,
+    )
+    .await
     {
-        Ok(resp) => return Ok(resp),
+        Ok(resp) => {
+            crate::usage::record_usage(primary_model, &resp);
+            return Ok(resp);
+        }
         Err(e) => {
             if fallback_models.is_empty() {
                 return Err(e);
@@ -70,12 +198,20 @@ pub async fn chat_completion_with_fallback(
             attempt = i + 2,
             "trying fallback model"
         );
-        match handler
-            .chat_completion(fallback, system, user, temperature, image_urls)
-            .await
+        match timed_completion(
+            handler,
+            fallback,
+            system,
+            user,
+            temperature,
+            image_urls,
+            schema,
+        )
+        .await
         {
             Ok(resp) => {
                 tracing::info!(model = fallback.as_str(), "fallback model succeeded");
+                crate::usage::record_usage(fallback, &resp);
                 return Ok(resp);
             }
             Err(e) => {
@@ -93,6 +229,48 @@ pub async fn chat_completion_with_fallback(
     Err(last_err)
 }
 
+/// Run one completion behind a terminal spinner that reports model latency.
+#[tracing::instrument(name = "ai_request", skip_all, fields(model = %model))]
+async fn timed_completion(
+    handler: &dyn AiHandler,
+    model: &str,
+    system: &str,
+    user: &str,
+    temperature: Option<f32>,
+    image_urls: Option<&[String]>,
+    schema: Option<&ResponseSchema>,
+) -> Result<ChatResponse, PrAgentError> {
+    let progress = terminal::spinner(&format!("Waiting for {model}"));
+    let started = std::time::Instant::now();
+    let result = match schema.filter(|_| handler.capabilities(model).supports_json_schema) {
+        Some(schema) => {
+            let system = format!("{system}\n\n{STRUCTURED_OUTPUT_NOTE}");
+            handler
+                .chat_completion_structured(model, &system, user, temperature, image_urls, schema)
+                .await
+        }
+        None => match stream::current() {
+            Some(progress) => {
+                handler
+                    .chat_completion_stream(model, system, user, temperature, image_urls, &progress)
+                    .await
+            }
+            None => {
+                handler
+                    .chat_completion(model, system, user, temperature, image_urls)
+                    .await
+            }
+        },
+    };
+    crate::metrics::record_ai_request(model, started.elapsed().as_secs_f64(), &result);
+    if let Ok(resp) = &result {
+        let secs = progress.elapsed_secs();
+        let tokens = resp.usage.map_or(0, |u| u.completion_tokens);
+        progress.finish(&format!("{model} answered in {secs:.1}s ({tokens} tokens)"));
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,6 +337,123 @@ mod tests {
         }
     }
 
+    /// Handler that reports which completion method was used.
+    struct SchemaTestHandler {
+        supports_json_schema: bool,
+        systems: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl AiHandler for SchemaTestHandler {
+        fn deployment_id(&self) -> &str {
+            "test"
+        }
+        fn capabilities(&self, _model: &str) -> ModelCapabilities {
+            ModelCapabilities {
+                supports_json_schema: self.supports_json_schema,
+                ..ModelCapabilities::default()
+            }
+        }
+        async fn chat_completion(
+            &self,
+            _model: &str,
+            system: &str,
+            _user: &str,
+            _temperature: Option<f32>,
+            _image_urls: Option<&[String]>,
+        ) -> Result<ChatResponse, PrAgentError> {
+            self.systems.lock().unwrap().push(system.to_string());
+            Ok(ChatResponse {
+                content: "prose".into(),
+                finish_reason: FinishReason::Stop,
+                usage: None,
+            })
+        }
+        async fn chat_completion_structured(
+            &self,
+            _model: &str,
+            system: &str,
+            _user: &str,
+            _temperature: Option<f32>,
+            _image_urls: Option<&[String]>,
+            schema: &ResponseSchema,
+        ) -> Result<ChatResponse, PrAgentError> {
+            self.systems.lock().unwrap().push(system.to_string());
+            Ok(ChatResponse {
+                content: schema.name.to_string(),
+                finish_reason: FinishReason::Stop,
+                usage: None,
+            })
+        }
+        async fn chat_completion_stream(
+            &self,
+            _model: &str,
+            system: &str,
+            _user: &str,
+            _temperature: Option<f32>,
+            _image_urls: Option<&[String]>,
+            progress: &StreamProgress,
+        ) -> Result<ChatResponse, PrAgentError> {
+            self.systems.lock().unwrap().push(system.to_string());
+            progress.add_tokens(7);
+            Ok(ChatResponse {
+                content: "streamed".into(),
+                finish_reason: FinishReason::Stop,
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_schema_used_only_when_supported() {
+        let schema = schema::review_schema();
+        for (supported, expected) in [(true, "pr_review"), (false, "prose")] {
+            let handler = SchemaTestHandler {
+                supports_json_schema: supported,
+                systems: Mutex::new(Vec::new()),
+            };
+            let resp = chat_completion_with_schema(
+                &handler,
+                "primary",
+                &[],
+                "sys",
+                "usr",
+                None,
+                None,
+                Some(&schema),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(resp.content, expected);
+            let system = handler.systems.lock().unwrap()[0].clone();
+            assert_eq!(system.contains(STRUCTURED_OUTPUT_NOTE), supported);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_used_inside_progress_scope() {
+        let handler = SchemaTestHandler {
+            supports_json_schema: false,
+            systems: Mutex::new(Vec::new()),
+        };
+        let resp =
+            chat_completion_with_fallback(&handler, "primary", &[], "sys", "usr", None, None)
+                .await
+                .unwrap();
+        assert_eq!(resp.content, "prose");
+
+        let progress = std::sync::Arc::new(StreamProgress::default());
+        let resp = stream::with_stream_progress(
+            progress.clone(),
+            chat_completion_with_fallback(&handler, "primary", &[], "sys", "usr", None, None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.content, "streamed");
+        assert_eq!(progress.tokens(), 7);
+    }
+
     #[tokio::test]
     async fn test_fallback_primary_succeeds_no_fallback_tried() {
         let handler = FallbackTestHandler::new(&[]);
@@ -240,4 +535,32 @@ mod tests {
         );
         assert_eq!(handler.attempted(), vec!["primary"]);
     }
+
+    #[tokio::test]
+    async fn test_cached_response_reused_for_same_head() {
+        let handler = FallbackTestHandler::new(&["primary"]);
+        let fallbacks = vec!["fallback-1".into()];
+        let scope = cache::CacheScope {
+            pr_url: "https://example.com/pr/fallback-cache".into(),
+            head_sha: "a1".into(),
+        };
+        let (first, second) = cache::with_cache_scope(scope, async {
+            let first = chat_completion_with_fallback(
+                &handler, "primary", &fallbacks, "sys", "usr", None, None,
+            )
+            .await
+            .unwrap();
+            let second = chat_completion_with_fallback(
+                &handler, "primary", &fallbacks, "sys", "usr", None, None,
+            )
+            .await
+            .unwrap();
+            (first, second)
+        })
+        .await;
+
+        assert_eq!(second.content, first.content);
+        assert!(second.usage.is_none(), "cache hits spend no tokens");
+        assert_eq!(handler.attempted(), vec!["primary", "fallback-1"]);
+    }
 }
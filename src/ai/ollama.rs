@@ -0,0 +1,454 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::AiHandler;
+use super::stream::StreamProgress;
+use super::token::get_max_tokens_with_fallback;
+use super::types::{ChatResponse, FinishReason, ModelCapabilities, ResponseSchema, Usage};
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::util::http_client_builder;
+
+/// Number of retry attempts for transient API errors.
+const MODEL_RETRIES: u32 = 2;
+
+/// Model name prefixes that select this handler (LiteLLM spellings).
+const OLLAMA_MODEL_PREFIXES: &[&str] = &["ollama/", "ollama_chat/"];
+
+/// Whether `model` selects the native Ollama handler.
+pub fn is_ollama_model(model: &str) -> bool {
+    OLLAMA_MODEL_PREFIXES.iter().any(|p| model.starts_with(p))
+}
+
+/// Model name as Ollama knows it (`ollama/llama3.1` → `llama3.1`).
+fn ollama_model_name(model: &str) -> &str {
+    OLLAMA_MODEL_PREFIXES
+        .iter()
+        .find_map(|p| model.strip_prefix(p))
+        .unwrap_or(model)
+}
+
+/// Native Ollama handler (`/api/chat`) for local models.
+///
+/// Responses are streamed and read line by line, so a long generation is
+/// bounded by `http.ai_chunk_read_timeout` between tokens rather than by
+/// `config.ai_timeout` for the whole request. No API key is needed.
+pub struct OllamaHandler {
+    client: Client,
+    base_url: String,
+}
+
+impl OllamaHandler {
+    /// Create a new handler from the current settings.
+    pub fn from_settings() -> Result<Self, PrAgentError> {
+        let settings = get_settings();
+        let client = http_client_builder(&settings.http, &settings.http.ai_headers)?
+            .read_timeout(Duration::from_secs(settings.http.ai_chunk_read_timeout))
+            .build()
+            .map_err(PrAgentError::Http)?;
+
+        Ok(Self {
+            client,
+            base_url: settings.ollama.api_base.clone(),
+        })
+    }
+
+    /// Build the request body for `/api/chat`.
+    fn build_request_body(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+    ) -> serde_json::Value {
+        let settings = get_settings();
+        let caps = self.capabilities(model);
+
+        let mut messages = Vec::new();
+        if !system.is_empty() {
+            messages.push(json!({"role": "system", "content": system}));
+        }
+        let mut user_msg = json!({"role": "user", "content": user});
+        let images: Vec<&str> = image_urls
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|url| {
+                let data = inline_image(url);
+                if data.is_none() {
+                    tracing::debug!(url, "Ollama only accepts inline images, skipping");
+                }
+                data
+            })
+            .collect();
+        if !images.is_empty() {
+            user_msg["images"] = json!(images);
+        }
+        messages.push(user_msg);
+
+        json!({
+            "model": ollama_model_name(model),
+            "messages": messages,
+            "stream": true,
+            "options": {
+                "temperature": temperature.unwrap_or(settings.config.temperature),
+                // Ollama defaults to a small context window and silently
+                // truncates longer prompts, so match our prompt budget.
+                "num_ctx": caps.max_tokens,
+            },
+        })
+    }
+
+    /// Send a single streaming request and collect the answer. No retry logic here.
+    ///
+    /// With `progress`, generated tokens are reported as they arrive and the
+    /// partial answer is returned as soon as it is cancelled.
+    async fn send_completion(
+        &self,
+        body: &serde_json::Value,
+        progress: Option<&StreamProgress>,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let url = format!("{}/api/chat", self.base_url.trim_end_matches('/'));
+
+        let mut resp = self
+            .client
+            .post(&url)
+            .json(body)
+            .send()
+            .await
+            .map_err(PrAgentError::Http)?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body_text = resp.text().await.unwrap_or_default();
+            return Err(PrAgentError::AiHandler(format!(
+                "Ollama returned {status}: {body_text}"
+            )));
+        }
+
+        let mut stream = StreamAccumulator::default();
+        while let Some(chunk) = resp.chunk().await.map_err(PrAgentError::Http)? {
+            let before = stream.tokens;
+            stream.push_bytes(&chunk)?;
+            if let Some(progress) = progress {
+                progress.add_tokens(stream.tokens - before);
+                if progress.is_cancelled() {
+                    // Dropping the response closes the stream, which stops generation.
+                    tracing::info!(
+                        tokens = stream.tokens,
+                        "generation cancelled, keeping partial Ollama output"
+                    );
+                    return Ok(stream.cancel());
+                }
+            }
+        }
+        stream.finish()
+    }
+
+    /// Send `body`, retrying transient errors.
+    async fn send_with_retries(
+        &self,
+        body: &serde_json::Value,
+        progress: Option<&StreamProgress>,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let mut last_err = None;
+        for attempt in 0..=MODEL_RETRIES {
+            match self.send_completion(body, progress).await {
+                Ok(resp) => return Ok(resp),
+                Err(e) if progress.is_some_and(StreamProgress::is_cancelled) => return Err(e),
+                Err(e) => {
+                    tracing::warn!(
+                        attempt = attempt + 1,
+                        max = MODEL_RETRIES + 1,
+                        error = %e,
+                        "Ollama request failed, retrying"
+                    );
+                    last_err = Some(e);
+
+                    if attempt < MODEL_RETRIES {
+                        tokio::time::sleep(Duration::from_secs(2u64.pow(attempt + 1))).await;
+                    }
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| PrAgentError::AiHandler("all retries exhausted".into())))
+    }
+}
+
+#[async_trait]
+impl AiHandler for OllamaHandler {
+    fn deployment_id(&self) -> &str {
+        ""
+    }
+
+    fn capabilities(&self, model: &str) -> ModelCapabilities {
+        let settings = get_settings();
+        ModelCapabilities {
+            supports_system_message: true,
+            supports_temperature: true,
+            supports_images: true,
+            requires_streaming: true,
+            reasoning_effort: None,
+            max_tokens: get_max_tokens_with_fallback(model, settings.config.max_model_tokens),
+            supports_json_schema: true,
+        }
+    }
+
+    async fn chat_completion(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let body = self.build_request_body(model, system, user, temperature, image_urls);
+        self.send_with_retries(&body, None).await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+        progress: &StreamProgress,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let body = self.build_request_body(model, system, user, temperature, image_urls);
+        self.send_with_retries(&body, Some(progress)).await
+    }
+
+    async fn chat_completion_structured(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+        schema: &ResponseSchema,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let mut body = self.build_request_body(model, system, user, temperature, image_urls);
+        body["format"] = schema.schema.clone();
+        self.send_with_retries(&body, None).await
+    }
+}
+
+/// Base64 payload of a `data:` image URL; Ollama takes no remote URLs.
+fn inline_image(url: &str) -> Option<&str> {
+    let (_, data) = url.strip_prefix("data:")?.split_once(";base64,")?;
+    Some(data)
+}
+
+/// Map an Ollama `done_reason` onto [`FinishReason`].
+fn finish_reason(done_reason: &str) -> FinishReason {
+    match done_reason {
+        "stop" => FinishReason::Stop,
+        "length" => FinishReason::Length,
+        _ => FinishReason::Unknown,
+    }
+}
+
+// ── Streaming ──────────────────────────────────────────────────────
+
+/// Collects a newline-delimited JSON stream from `/api/chat`.
+///
+/// Network chunks may split a line, so bytes are buffered until a newline.
+/// Ollama sends roughly one token per line, so non-empty lines are counted
+/// as tokens for progress reporting.
+#[derive(Default)]
+struct StreamAccumulator {
+    pending: Vec<u8>,
+    content: String,
+    tokens: u32,
+    done: Option<StreamChunk>,
+}
+
+impl StreamAccumulator {
+    fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), PrAgentError> {
+        self.pending.extend_from_slice(bytes);
+        while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            self.push_line(&line)?;
+        }
+        Ok(())
+    }
+
+    fn push_line(&mut self, line: &[u8]) -> Result<(), PrAgentError> {
+        if line.trim_ascii().is_empty() {
+            return Ok(());
+        }
+        let chunk: StreamChunk = serde_json::from_slice(line)?;
+        if let Some(error) = chunk.error {
+            return Err(PrAgentError::AiHandler(format!("Ollama error: {error}")));
+        }
+        if let Some(message) = &chunk.message
+            && !message.content.is_empty()
+        {
+            self.content.push_str(&message.content);
+            self.tokens += 1;
+        }
+        if chunk.done {
+            self.done = Some(chunk);
+        }
+        Ok(())
+    }
+
+    /// Build a partial response for a stream stopped before it ended.
+    fn cancel(self) -> ChatResponse {
+        ChatResponse {
+            content: self.content,
+            finish_reason: FinishReason::Cancelled,
+            usage: None,
+        }
+    }
+
+    /// Build the response once the stream has ended.
+    fn finish(mut self) -> Result<ChatResponse, PrAgentError> {
+        let rest = std::mem::take(&mut self.pending);
+        self.push_line(&rest)?;
+        let done = self.done.ok_or_else(|| {
+            PrAgentError::AiHandler("Ollama stream ended before the final message".into())
+        })?;
+        Ok(ChatResponse {
+            content: self.content,
+            finish_reason: done
+                .done_reason
+                .as_deref()
+                .map(finish_reason)
+                .unwrap_or(FinishReason::Stop),
+            usage: Some(Usage {
+                prompt_tokens: done.prompt_eval_count,
+                completion_tokens: done.eval_count,
+                total_tokens: done.prompt_eval_count + done.eval_count,
+            }),
+        })
+    }
+}
+
+// ── API response types ─────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    message: Option<StreamMessage>,
+    #[serde(default)]
+    done: bool,
+    done_reason: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: u32,
+    #[serde(default)]
+    eval_count: u32,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    #[serde(default)]
+    content: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_handler() -> OllamaHandler {
+        OllamaHandler {
+            client: Client::new(),
+            base_url: "http://localhost:11434".into(),
+        }
+    }
+
+    #[test]
+    fn test_is_ollama_model() {
+        assert!(is_ollama_model("ollama/llama3.1"));
+        assert!(is_ollama_model("ollama_chat/qwen2.5-coder:32b"));
+        assert!(!is_ollama_model("gpt-4o"));
+        assert_eq!(
+            ollama_model_name("ollama_chat/qwen2.5-coder:32b"),
+            "qwen2.5-coder:32b"
+        );
+    }
+
+    #[test]
+    fn test_build_request_body() {
+        let handler = test_handler();
+        let urls = vec![
+            "https://img.com/a.png".to_string(),
+            "data:image/png;base64,iVBORw0K".to_string(),
+        ];
+        let body = handler.build_request_body(
+            "ollama/llama3.1",
+            "system msg",
+            "user msg",
+            Some(0.3),
+            Some(&urls),
+        );
+
+        assert_eq!(body["model"], "llama3.1");
+        assert_eq!(body["stream"], true);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[1]["content"], "user msg");
+        assert_eq!(messages[1]["images"], json!(["iVBORw0K"]));
+        assert!((body["options"]["temperature"].as_f64().unwrap() - 0.3).abs() < 1e-6);
+        assert!(body["options"]["num_ctx"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_stream_accumulates_split_lines() {
+        let stream = concat!(
+            r#"{"message":{"role":"assistant","content":"review:"},"done":false}"#,
+            "\n",
+            r#"{"message":{"role":"assistant","content":"\n  score: 80"},"done":false}"#,
+            "\n",
+            r#"{"message":{"role":"assistant","content":""},"done":true,"done_reason":"length","prompt_eval_count":120,"eval_count":30}"#,
+        );
+        let mut acc = StreamAccumulator::default();
+        // Feed in pieces that cut through lines.
+        for piece in stream.as_bytes().chunks(7) {
+            acc.push_bytes(piece).unwrap();
+        }
+        let resp = acc.finish().unwrap();
+
+        assert_eq!(resp.content, "review:\n  score: 80");
+        assert_eq!(resp.finish_reason, FinishReason::Length);
+        let usage = resp.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 120);
+        assert_eq!(usage.completion_tokens, 30);
+        assert_eq!(usage.total_tokens, 150);
+    }
+
+    #[test]
+    fn test_stream_counts_tokens_and_cancels() {
+        let mut acc = StreamAccumulator::default();
+        acc.push_bytes(
+            b"{\"message\":{\"content\":\"review:\"},\"done\":false}\n{\"message\":{\"content\":\" ok\"},\"done\":false}\n",
+        )
+        .unwrap();
+        assert_eq!(acc.tokens, 2);
+
+        let resp = acc.cancel();
+        assert_eq!(resp.content, "review: ok");
+        assert_eq!(resp.finish_reason, FinishReason::Cancelled);
+        assert!(resp.usage.is_none());
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        let mut acc = StreamAccumulator::default();
+        let err = acc
+            .push_bytes(b"{\"error\":\"model 'llama9' not found\"}\n")
+            .unwrap_err();
+        assert!(err.to_string().contains("llama9"));
+
+        let mut acc = StreamAccumulator::default();
+        acc.push_bytes(b"{\"message\":{\"content\":\"partial\"},\"done\":false}\n")
+            .unwrap();
+        assert!(acc.finish().is_err(), "a cut-off stream must not succeed");
+    }
+}
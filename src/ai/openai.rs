@@ -1,32 +1,44 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
+use flate2::Compression;
+use flate2::write::GzEncoder;
 use reqwest::Client;
 use serde::Deserialize;
 use serde_json::json;
 
 use super::AiHandler;
+use super::stream::StreamProgress;
 use super::token::{
     get_max_tokens_with_fallback, is_no_temperature_model, is_user_message_only_model,
-    supports_reasoning_effort,
+    supports_json_schema, supports_reasoning_effort,
 };
-use super::types::{ChatResponse, FinishReason, ModelCapabilities, Usage};
+use super::types::{ChatResponse, FinishReason, ModelCapabilities, ResponseSchema, Usage};
 use crate::config::loader::get_settings;
 use crate::error::PrAgentError;
+use crate::util::http_client_builder;
 
 /// Number of retry attempts for transient API errors (not rate limits).
 const MODEL_RETRIES: u32 = 2;
 
+/// `api-version` used for Azure OpenAI when `openai.api_version` is empty.
+const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
+
 /// OpenAI-compatible chat completions handler.
 ///
 /// Works with: OpenAI, Azure OpenAI, Ollama, Groq, DeepSeek, DeepInfra,
 /// xAI, OpenRouter, Mistral — any provider exposing the `/v1/chat/completions` API.
+///
+/// With `openai.api_type = "azure"`, requests go to
+/// `{api_base}/openai/deployments/{deployment_id}/chat/completions` with
+/// `api-key` header auth.
 pub struct OpenAiCompatibleHandler {
     client: Client,
     base_url: String,
     api_key: String,
-    #[allow(dead_code)]
     deployment_id: String,
+    /// `api-version` query parameter; set only for Azure OpenAI.
+    azure_api_version: Option<String>,
 }
 
 impl OpenAiCompatibleHandler {
@@ -40,21 +52,65 @@ impl OpenAiCompatibleHandler {
             settings.openai.api_base.clone()
         };
         let deployment_id = settings.openai.deployment_id.clone();
+        let azure_api_version = settings
+            .openai
+            .api_type
+            .eq_ignore_ascii_case("azure")
+            .then(|| {
+                if settings.openai.api_version.is_empty() {
+                    DEFAULT_AZURE_API_VERSION.to_string()
+                } else {
+                    settings.openai.api_version.clone()
+                }
+            });
+        if azure_api_version.is_some() && settings.openai.api_base.is_empty() {
+            return Err(PrAgentError::AiHandler(
+                "openai.api_base must be set to the Azure resource endpoint when openai.api_type = \"azure\"".into(),
+            ));
+        }
         let timeout_secs = settings.config.ai_timeout as u64;
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(timeout_secs))
-            .build()
-            .map_err(PrAgentError::Http)?;
+        let builder = http_client_builder(&settings.http, &settings.http.ai_headers)?;
+        // In chunked or streaming mode a long generation may legitimately exceed
+        // ai_timeout, so bound the gap between chunks instead of the whole request.
+        let builder =
+            if settings.http.chunked_ai_responses || settings.config.stream_progress_interval > 0 {
+                builder.read_timeout(Duration::from_secs(settings.http.ai_chunk_read_timeout))
+            } else {
+                builder.timeout(Duration::from_secs(timeout_secs))
+            };
+        let client = builder.build().map_err(PrAgentError::Http)?;
 
         Ok(Self {
             client,
             base_url,
             api_key,
             deployment_id,
+            azure_api_version,
         })
     }
 
+    /// Chat completions URL for `model`.
+    ///
+    /// Azure routes by deployment (falling back to the model name when
+    /// `openai.deployment_id` is empty) and versions via `api-version`.
+    fn completions_url(&self, model: &str) -> String {
+        let base = self.base_url.trim_end_matches('/');
+        match &self.azure_api_version {
+            Some(api_version) => {
+                let deployment = if self.deployment_id.is_empty() {
+                    model
+                } else {
+                    &self.deployment_id
+                };
+                format!(
+                    "{base}/openai/deployments/{deployment}/chat/completions?api-version={api_version}"
+                )
+            }
+            None => format!("{base}/chat/completions"),
+        }
+    }
+
     /// Build the request body for the chat completions API.
     fn build_request_body(
         &self,
@@ -125,16 +181,44 @@ impl OpenAiCompatibleHandler {
     }
 
     /// Send a single request and parse the response. No retry logic here.
+    ///
+    /// With `progress`, `body` must request a stream; the server-sent events
+    /// are read as they arrive (see [`read_event_stream`]).
     async fn send_completion(
         &self,
         body: &serde_json::Value,
+        progress: Option<&StreamProgress>,
     ) -> Result<ChatResponse, PrAgentError> {
-        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
-
-        let mut req = self.client.post(&url).json(body);
+        let settings = get_settings();
+        let url = self.completions_url(body["model"].as_str().unwrap_or_default());
+
+        let payload = serde_json::to_vec(body)?;
+        let mut req = self
+            .client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if settings.http.compress_ai_requests
+            && payload.len() >= settings.http.ai_compression_min_bytes
+        {
+            let compressed = gzip_bytes(&payload)?;
+            tracing::debug!(
+                original = payload.len(),
+                compressed = compressed.len(),
+                "gzip-compressed AI request body"
+            );
+            req = req
+                .header(reqwest::header::CONTENT_ENCODING, "gzip")
+                .body(compressed);
+        } else {
+            req = req.body(payload);
+        }
 
         if !self.api_key.is_empty() {
-            req = req.bearer_auth(&self.api_key);
+            req = if self.azure_api_version.is_some() {
+                req.header("api-key", &self.api_key)
+            } else {
+                req.bearer_auth(&self.api_key)
+            };
         }
 
         let resp = req.send().await.map_err(PrAgentError::Http)?;
@@ -161,7 +245,15 @@ impl OpenAiCompatibleHandler {
             )));
         }
 
-        let api_resp: ApiResponse = resp.json().await.map_err(PrAgentError::Http)?;
+        if let Some(progress) = progress {
+            return read_event_stream(resp, progress).await;
+        }
+
+        let api_resp: ApiResponse = if settings.http.chunked_ai_responses {
+            serde_json::from_slice(&read_body_chunked(resp).await?)?
+        } else {
+            resp.json().await.map_err(PrAgentError::Http)?
+        };
 
         let choice = api_resp
             .choices
@@ -189,6 +281,43 @@ impl OpenAiCompatibleHandler {
             usage,
         })
     }
+
+    /// Send `body`, retrying transient errors.
+    async fn send_with_retries(
+        &self,
+        body: &serde_json::Value,
+        progress: Option<&StreamProgress>,
+    ) -> Result<ChatResponse, PrAgentError> {
+        // Retry logic: retry on transient errors with exponential backoff
+        let mut last_err = None;
+        for attempt in 0..=MODEL_RETRIES {
+            match self.send_completion(body, progress).await {
+                Ok(resp) => return Ok(resp),
+                Err(e @ PrAgentError::RateLimited { .. }) => {
+                    // Don't retry rate limits — propagate immediately
+                    return Err(e);
+                }
+                Err(e) if progress.is_some_and(StreamProgress::is_cancelled) => return Err(e),
+                Err(e) => {
+                    tracing::warn!(
+                        attempt = attempt + 1,
+                        max = MODEL_RETRIES + 1,
+                        error = %e,
+                        "AI request failed, retrying"
+                    );
+                    last_err = Some(e);
+
+                    // Exponential backoff: 2s, 4s, 8s, ...
+                    if attempt < MODEL_RETRIES {
+                        let delay = std::time::Duration::from_secs(2u64.pow(attempt + 1));
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| PrAgentError::AiHandler("all retries exhausted".into())))
+    }
 }
 
 #[async_trait]
@@ -213,6 +342,7 @@ impl AiHandler for OpenAiCompatibleHandler {
             requires_streaming: false,
             reasoning_effort,
             max_tokens,
+            supports_json_schema: supports_json_schema(model),
         }
     }
 
@@ -225,35 +355,175 @@ impl AiHandler for OpenAiCompatibleHandler {
         image_urls: Option<&[String]>,
     ) -> Result<ChatResponse, PrAgentError> {
         let body = self.build_request_body(model, system, user, temperature, image_urls);
+        self.send_with_retries(&body, None).await
+    }
 
-        // Retry logic: retry on transient errors with exponential backoff
-        let mut last_err = None;
-        for attempt in 0..=MODEL_RETRIES {
-            match self.send_completion(&body).await {
-                Ok(resp) => return Ok(resp),
-                Err(e @ PrAgentError::RateLimited { .. }) => {
-                    // Don't retry rate limits — propagate immediately
-                    return Err(e);
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        attempt = attempt + 1,
-                        max = MODEL_RETRIES + 1,
-                        error = %e,
-                        "AI request failed, retrying"
-                    );
-                    last_err = Some(e);
+    async fn chat_completion_stream(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+        progress: &StreamProgress,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let mut body = self.build_request_body(model, system, user, temperature, image_urls);
+        body["stream"] = json!(true);
+        body["stream_options"] = json!({"include_usage": true});
+        self.send_with_retries(&body, Some(progress)).await
+    }
 
-                    // Exponential backoff: 2s, 4s, 8s, ...
-                    if attempt < MODEL_RETRIES {
-                        let delay = std::time::Duration::from_secs(2u64.pow(attempt + 1));
-                        tokio::time::sleep(delay).await;
-                    }
-                }
+    async fn chat_completion_structured(
+        &self,
+        model: &str,
+        system: &str,
+        user: &str,
+        temperature: Option<f32>,
+        image_urls: Option<&[String]>,
+        schema: &ResponseSchema,
+    ) -> Result<ChatResponse, PrAgentError> {
+        let mut body = self.build_request_body(model, system, user, temperature, image_urls);
+        // Not `strict`: strict mode requires every property and forbids optional sections.
+        body["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": {"name": schema.name, "schema": schema.schema},
+        });
+        self.send_with_retries(&body, None).await
+    }
+}
+
+/// Gzip-compress a request body.
+fn gzip_bytes(data: &[u8]) -> Result<Vec<u8>, PrAgentError> {
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::with_capacity(data.len() / 4), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Read a response body chunk by chunk.
+///
+/// Together with the client's `read_timeout`, this keeps slow-but-alive
+/// responses from tripping a whole-request timeout.
+async fn read_body_chunked(mut resp: reqwest::Response) -> Result<Vec<u8>, PrAgentError> {
+    let mut buf = Vec::new();
+    while let Some(chunk) = resp.chunk().await.map_err(PrAgentError::Http)? {
+        buf.extend_from_slice(&chunk);
+    }
+    Ok(buf)
+}
+
+/// Read a streamed (`"stream": true`) response, reporting tokens to `progress`.
+///
+/// Returns the partial answer as soon as `progress` is cancelled; dropping
+/// the response closes the connection, which stops generation.
+async fn read_event_stream(
+    mut resp: reqwest::Response,
+    progress: &StreamProgress,
+) -> Result<ChatResponse, PrAgentError> {
+    let mut stream = EventStreamAccumulator::default();
+    while let Some(chunk) = resp.chunk().await.map_err(PrAgentError::Http)? {
+        let before = stream.tokens;
+        stream.push_bytes(&chunk)?;
+        progress.add_tokens(stream.tokens - before);
+        if progress.is_cancelled() {
+            tracing::info!(
+                tokens = stream.tokens,
+                "generation cancelled, keeping partial output"
+            );
+            return Ok(stream.cancel());
+        }
+    }
+    stream.finish()
+}
+
+// ── Streaming ──────────────────────────────────────────────────────
+
+/// Collects the server-sent events of a streamed chat completion.
+///
+/// Network chunks may split an event, so bytes are buffered until a newline.
+/// Each content delta is counted as one token for progress reporting; the
+/// final usage chunk (`stream_options.include_usage`) carries the real count.
+#[derive(Default)]
+struct EventStreamAccumulator {
+    pending: Vec<u8>,
+    content: String,
+    tokens: u32,
+    finish_reason: Option<String>,
+    usage: Option<ApiUsage>,
+    done: bool,
+}
+
+impl EventStreamAccumulator {
+    fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), PrAgentError> {
+        self.pending.extend_from_slice(bytes);
+        while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            self.push_line(&line)?;
+        }
+        Ok(())
+    }
+
+    fn push_line(&mut self, line: &[u8]) -> Result<(), PrAgentError> {
+        // Blank separators, comments and `event:` lines carry no data.
+        let Some(data) = line.trim_ascii().strip_prefix(b"data:") else {
+            return Ok(());
+        };
+        let data = data.trim_ascii();
+        if data == b"[DONE]" {
+            self.done = true;
+            return Ok(());
+        }
+        let chunk: StreamChunk = serde_json::from_slice(data)?;
+        if let Some(error) = chunk.error {
+            return Err(PrAgentError::AiHandler(format!("stream error: {error}")));
+        }
+        for choice in chunk.choices {
+            if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+                self.content.push_str(&content);
+                self.tokens += 1;
             }
+            if choice.finish_reason.is_some() {
+                self.finish_reason = choice.finish_reason;
+            }
+        }
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
         }
+        Ok(())
+    }
 
-        Err(last_err.unwrap_or_else(|| PrAgentError::AiHandler("all retries exhausted".into())))
+    /// Build a partial response for a stream stopped before it ended.
+    fn cancel(self) -> ChatResponse {
+        ChatResponse {
+            content: self.content,
+            finish_reason: FinishReason::Cancelled,
+            usage: None,
+        }
+    }
+
+    /// Build the response once the stream has ended.
+    fn finish(mut self) -> Result<ChatResponse, PrAgentError> {
+        let rest = std::mem::take(&mut self.pending);
+        self.push_line(&rest)?;
+        if !self.done && self.finish_reason.is_none() {
+            return Err(PrAgentError::AiHandler(
+                "stream ended before the final event".into(),
+            ));
+        }
+        Ok(ChatResponse {
+            content: self.content,
+            finish_reason: self
+                .finish_reason
+                .as_deref()
+                .map(FinishReason::from)
+                .unwrap_or_default(),
+            usage: self.usage.map(|u| Usage {
+                prompt_tokens: u.prompt_tokens,
+                completion_tokens: u.completion_tokens,
+                total_tokens: u.total_tokens,
+            }),
+        })
     }
 }
 
@@ -276,6 +546,26 @@ struct ApiMessage {
     content: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    usage: Option<ApiUsage>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: StreamDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StreamDelta {
+    content: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiUsage {
     prompt_tokens: u32,
@@ -293,6 +583,7 @@ mod tests {
             base_url: "https://api.test.com/v1".into(),
             api_key: "test-key".into(),
             deployment_id: "test-deploy".into(),
+            azure_api_version: None,
         }
     }
 
@@ -407,13 +698,136 @@ mod tests {
             base_url: "http://192.0.2.1:1".into(), // RFC 5737 non-routable
             api_key: "".into(),
             deployment_id: "".into(),
+            azure_api_version: None,
         };
 
         let body = json!({"model": "test", "messages": [{"role": "user", "content": "hi"}]});
-        let result = handler.send_completion(&body).await;
+        let result = handler.send_completion(&body, None).await;
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_event_stream_accumulates_split_events() {
+        let stream = concat!(
+            ": keep-alive\n\n",
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\",\"content\":\"\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"review:\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"\\n  score: 80\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"length\"}]}\n\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":120,\"completion_tokens\":30,\"total_tokens\":150}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let mut acc = EventStreamAccumulator::default();
+        // Feed in pieces that cut through events.
+        for piece in stream.as_bytes().chunks(7) {
+            acc.push_bytes(piece).unwrap();
+        }
+        assert_eq!(acc.tokens, 2);
+        let resp = acc.finish().unwrap();
+
+        assert_eq!(resp.content, "review:\n  score: 80");
+        assert_eq!(resp.finish_reason, FinishReason::Length);
+        assert_eq!(resp.usage.unwrap().total_tokens, 150);
+    }
+
+    #[test]
+    fn test_event_stream_cancel_and_errors() {
+        let mut acc = EventStreamAccumulator::default();
+        acc.push_bytes(b"data: {\"choices\":[{\"delta\":{\"content\":\"partial\"}}]}\n")
+            .unwrap();
+        let resp = acc.cancel();
+        assert_eq!(resp.content, "partial");
+        assert_eq!(resp.finish_reason, FinishReason::Cancelled);
+
+        let mut acc = EventStreamAccumulator::default();
+        acc.push_bytes(b"data: {\"choices\":[{\"delta\":{\"content\":\"partial\"}}]}\n")
+            .unwrap();
+        assert!(acc.finish().is_err(), "a cut-off stream must not succeed");
+
+        let mut acc = EventStreamAccumulator::default();
+        let err = acc
+            .push_bytes(b"data: {\"error\":{\"message\":\"overloaded\"}}\n")
+            .unwrap_err();
+        assert!(err.to_string().contains("overloaded"));
+    }
+
+    #[test]
+    fn test_gzip_bytes_round_trip() {
+        use std::io::Read;
+
+        let payload = serde_json::to_vec(&json!({"messages": ["x".repeat(10_000)]})).unwrap();
+        let compressed = gzip_bytes(&payload).unwrap();
+        assert!(compressed.len() < payload.len());
+
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    fn test_completions_url_openai_and_azure() {
+        let handler = test_handler();
+        assert_eq!(
+            handler.completions_url("gpt-4"),
+            "https://api.test.com/v1/chat/completions"
+        );
+
+        let azure = OpenAiCompatibleHandler {
+            base_url: "https://res.openai.azure.com/".into(),
+            azure_api_version: Some("2024-10-21".into()),
+            ..test_handler()
+        };
+        assert_eq!(
+            azure.completions_url("gpt-4"),
+            "https://res.openai.azure.com/openai/deployments/test-deploy/chat/completions?api-version=2024-10-21"
+        );
+
+        let by_model = OpenAiCompatibleHandler {
+            deployment_id: String::new(),
+            ..azure
+        };
+        assert!(
+            by_model
+                .completions_url("gpt-4o")
+                .contains("/deployments/gpt-4o/")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_from_settings_azure() {
+        use crate::config::loader::{load_settings, with_settings};
+        use std::sync::Arc;
+
+        let azure_toml = "[openai]\napi_type = \"azure\"\ndeployment_id = \"prod-gpt\"\n";
+        let settings = Arc::new(
+            load_settings(&std::collections::HashMap::new(), None, Some(azure_toml)).unwrap(),
+        );
+        let err = with_settings(settings, async { OpenAiCompatibleHandler::from_settings() })
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("openai.api_base"));
+
+        let azure_toml = format!("{azure_toml}api_base = \"https://res.openai.azure.com\"\n");
+        let settings = Arc::new(
+            load_settings(&std::collections::HashMap::new(), None, Some(&azure_toml)).unwrap(),
+        );
+        let handler = with_settings(settings, async { OpenAiCompatibleHandler::from_settings() })
+            .await
+            .unwrap();
+        assert_eq!(
+            handler.azure_api_version.as_deref(),
+            Some(DEFAULT_AZURE_API_VERSION)
+        );
+        assert!(
+            handler
+                .completions_url("gpt-4")
+                .contains("/deployments/prod-gpt/")
+        );
+    }
+
     #[test]
     fn test_from_settings_succeeds() {
         // Should successfully create handler from default settings
@@ -0,0 +1,115 @@
+//! Model tiers: which configured model a task runs on.
+//!
+//! Tools name the tier a call needs instead of reading `config.model*`
+//! directly. Cheap subtasks (the improve reflect pass, label
+//! classification) ask for [`ModelTier::Weak`]; the security-focused review
+//! asks for [`ModelTier::Reasoning`]. Unset tiers fall back to `config.model`.
+//!
+//! All tiers are served by the handler picked for `config.model`, so
+//! `model_weak` and `model_reasoning` must be models of the same provider.
+
+use crate::ai::{AiHandler, ModelCapabilities};
+use crate::config::types::GlobalConfig;
+
+/// The kind of model a task needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelTier {
+    /// `config.model`.
+    Default,
+    /// `config.model_weak`: cheap, fast subtasks.
+    Weak,
+    /// `config.model_reasoning`: tasks that benefit from deeper reasoning.
+    Reasoning,
+}
+
+impl ModelTier {
+    pub fn name(self) -> &'static str {
+        match self {
+            ModelTier::Default => "default",
+            ModelTier::Weak => "weak",
+            ModelTier::Reasoning => "reasoning",
+        }
+    }
+}
+
+/// A tier resolved to a concrete model.
+#[derive(Debug, Clone)]
+pub struct RoutedModel {
+    pub tier: ModelTier,
+    pub model: String,
+    pub capabilities: ModelCapabilities,
+}
+
+/// Resolves [`ModelTier`]s against `[config]`.
+pub struct ModelRouter<'a> {
+    config: &'a GlobalConfig,
+}
+
+impl<'a> ModelRouter<'a> {
+    pub fn new(config: &'a GlobalConfig) -> Self {
+        Self { config }
+    }
+
+    /// Model for `tier`, or `config.model` when the tier isn't configured.
+    pub fn model(&self, tier: ModelTier) -> &'a str {
+        let model = match tier {
+            ModelTier::Default => "",
+            ModelTier::Weak => self.config.model_weak.as_str(),
+            ModelTier::Reasoning => self.config.model_reasoning.as_str(),
+        };
+        if model.is_empty() {
+            &self.config.model
+        } else {
+            model
+        }
+    }
+
+    /// Model and capabilities for `tier`, as reported by `ai`.
+    pub fn resolve(&self, tier: ModelTier, ai: &dyn AiHandler) -> RoutedModel {
+        let model = self.model(tier);
+        let routed = RoutedModel {
+            tier,
+            model: model.to_string(),
+            capabilities: ai.capabilities(model),
+        };
+        tracing::debug!(
+            tier = routed.tier.name(),
+            model,
+            reasoning_effort = ?routed.capabilities.reasoning_effort,
+            "routed model tier"
+        );
+        routed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_falls_back_to_default() {
+        let config = GlobalConfig {
+            model: "gpt-5".into(),
+            model_weak: "gpt-4o-mini".into(),
+            model_reasoning: String::new(),
+            ..GlobalConfig::default()
+        };
+        let router = ModelRouter::new(&config);
+        assert_eq!(router.model(ModelTier::Default), "gpt-5");
+        assert_eq!(router.model(ModelTier::Weak), "gpt-4o-mini");
+        assert_eq!(router.model(ModelTier::Reasoning), "gpt-5");
+    }
+
+    #[test]
+    fn test_resolve_reports_capabilities() {
+        let config = GlobalConfig {
+            model_reasoning: "o4-mini".into(),
+            ..GlobalConfig::default()
+        };
+        let ai = crate::testing::mock_ai::MockAiHandler::new("");
+        let routed = ModelRouter::new(&config).resolve(ModelTier::Reasoning, &ai);
+        assert_eq!(routed.tier, ModelTier::Reasoning);
+        assert_eq!(routed.model, "o4-mini");
+        assert!(routed.capabilities.supports_system_message);
+    }
+}
@@ -0,0 +1,176 @@
+//! Per-tool JSON schemas for structured output (`config.structured_output`).
+//!
+//! They mirror the pydantic definitions in the prompt templates. Optional
+//! sections are left out of `required`, since the prompts toggle them.
+
+use serde_json::{Value, json};
+
+use super::types::ResponseSchema;
+
+fn string() -> Value {
+    json!({"type": "string"})
+}
+
+fn integer() -> Value {
+    json!({"type": "integer"})
+}
+
+fn array(items: Value) -> Value {
+    json!({"type": "array", "items": items})
+}
+
+fn object(properties: Value, required: &[&str]) -> Value {
+    json!({"type": "object", "properties": properties, "required": required})
+}
+
+/// Schema for `/review` (`PRReview` in the reviewer prompt).
+pub fn review_schema() -> ResponseSchema {
+    let key_issue = object(
+        json!({
+            "relevant_file": string(),
+            "issue_header": string(),
+            "issue_content": string(),
+            "start_line": integer(),
+            "end_line": integer(),
+            "severity": string(),
+        }),
+        &["relevant_file", "issue_header", "issue_content"],
+    );
+    let ticket = object(
+        json!({
+            "ticket_url": string(),
+            "ticket_requirements": string(),
+            "fully_compliant_requirements": string(),
+            "not_compliant_requirements": string(),
+            "requires_further_human_verification": string(),
+        }),
+        &["ticket_url"],
+    );
+    let todo = object(
+        json!({
+            "relevant_file": string(),
+            "line_number": integer(),
+            "content": string(),
+        }),
+        &["relevant_file", "content"],
+    );
+    let checklist = object(
+        json!({"question": string(), "answer": string(), "explanation": string()}),
+        &["question", "answer"],
+    );
+    let sub_pr = object(
+        json!({"relevant_files": array(string()), "title": string()}),
+        &["relevant_files", "title"],
+    );
+    let review = object(
+        json!({
+            "ticket_compliance_check": array(ticket),
+            "estimated_effort_to_review_[1-5]": integer(),
+            "contribution_time_cost_estimate": object(
+                json!({
+                    "best_case": string(),
+                    "average_case": string(),
+                    "worst_case": string(),
+                }),
+                &[],
+            ),
+            "score": string(),
+            "relevant_tests": string(),
+            "insights_from_user_answers": string(),
+            "key_issues_to_review": array(key_issue),
+            "security_concerns": string(),
+            "security_severity": string(),
+            "todo_sections": {"anyOf": [array(todo), string()]},
+            "checklist": array(checklist),
+            "can_be_split": array(sub_pr),
+        }),
+        &["key_issues_to_review", "security_concerns"],
+    );
+    ResponseSchema {
+        name: "pr_review",
+        schema: object(json!({"review": review}), &["review"]),
+    }
+}
+
+/// Schema for `/describe` (`PRDescription` in the description prompt).
+pub fn describe_schema() -> ResponseSchema {
+    let file = object(
+        json!({
+            "filename": string(),
+            "changes_summary": string(),
+            "changes_title": string(),
+            "label": string(),
+        }),
+        &["filename", "changes_summary", "changes_title", "label"],
+    );
+    ResponseSchema {
+        name: "pr_description",
+        schema: object(
+            json!({
+                "type": array(string()),
+                "description": string(),
+                "title": string(),
+                "changes_diagram": string(),
+                "pr_files": array(file),
+            }),
+            &["type", "description", "title"],
+        ),
+    }
+}
+
+/// Schema for `/improve` (`PRCodeSuggestions` in the suggestions prompt).
+pub fn improve_schema() -> ResponseSchema {
+    let suggestion = object(
+        json!({
+            "relevant_file": string(),
+            "language": string(),
+            "existing_code": string(),
+            "suggestion_content": string(),
+            "improved_code": string(),
+            "one_sentence_summary": string(),
+            "label": string(),
+        }),
+        &[
+            "relevant_file",
+            "existing_code",
+            "suggestion_content",
+            "improved_code",
+            "one_sentence_summary",
+            "label",
+        ],
+    );
+    ResponseSchema {
+        name: "pr_code_suggestions",
+        schema: object(
+            json!({"code_suggestions": array(suggestion)}),
+            &["code_suggestions"],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schemas_are_objects_with_known_top_level_keys() {
+        for (schema, key) in [
+            (review_schema(), "review"),
+            (describe_schema(), "pr_files"),
+            (improve_schema(), "code_suggestions"),
+        ] {
+            assert_eq!(schema.schema["type"], "object", "{}", schema.name);
+            assert!(
+                schema.schema["properties"].get(key).is_some(),
+                "{} lacks {key}",
+                schema.name
+            );
+        }
+        let review = review_schema().schema;
+        assert_eq!(
+            review["properties"]["review"]["properties"]["key_issues_to_review"]["items"]["properties"]
+                ["start_line"]["type"],
+            "integer"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+tokio::task_local! {
+    /// Progress of streamed completions for the tool running in this task.
+    static STREAM_PROGRESS: Arc<StreamProgress>;
+}
+
+/// Live state of the streamed completions of one tool run.
+///
+/// Handlers bump the token count as output arrives and stop early, keeping
+/// the partial answer, once [`StreamProgress::cancel`] is called.
+#[derive(Debug, Default)]
+pub struct StreamProgress {
+    tokens: AtomicU32,
+    cancelled: AtomicBool,
+}
+
+impl StreamProgress {
+    /// Record `n` newly generated tokens.
+    pub fn add_tokens(&self, n: u32) {
+        self.tokens.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Tokens generated so far, across all completions of the run.
+    pub fn tokens(&self) -> u32 {
+        self.tokens.load(Ordering::Relaxed)
+    }
+
+    /// Ask running completions to stop and return what they have.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Run `fut` with completions streamed into `progress`.
+pub async fn with_stream_progress<F: std::future::Future>(
+    progress: Arc<StreamProgress>,
+    fut: F,
+) -> F::Output {
+    STREAM_PROGRESS.scope(progress, fut).await
+}
+
+/// Progress of the enclosing [`with_stream_progress`] scope, if any.
+pub fn current() -> Option<Arc<StreamProgress>> {
+    STREAM_PROGRESS.try_with(Arc::clone).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_progress_scope() {
+        assert!(current().is_none());
+        let progress = Arc::new(StreamProgress::default());
+        with_stream_progress(progress.clone(), async {
+            let p = current().expect("inside scope");
+            p.add_tokens(3);
+            p.add_tokens(2);
+            p.cancel();
+        })
+        .await;
+        assert_eq!(progress.tokens(), 5);
+        assert!(progress.is_cancelled());
+    }
+}
@@ -4,35 +4,108 @@ use tiktoken_rs::CoreBPE;
 pub const OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD: u32 = 1500;
 pub const OUTPUT_BUFFER_TOKENS_HARD_THRESHOLD: u32 = 1000;
 
-// ── Encoder singleton ──────────────────────────────────────────────
+// ── Tokenizers ─────────────────────────────────────────────────────
+
+/// How tokens are counted for a model (see [`tokenizer_for`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tokenizer {
+    /// tiktoken `o200k_base` (GPT-4o and later, o-series).
+    O200k,
+    /// tiktoken `cl100k_base` (GPT-3.5, GPT-4, GPT-4 Turbo).
+    Cl100k,
+    /// No public tokenizer: the `o200k_base` count scaled by this factor.
+    Estimate(f32),
+}
+
+impl Tokenizer {
+    /// Returns the shared tiktoken BPE encoder.
+    /// Initialized once on first call; subsequent calls are free.
+    fn encoder(self) -> &'static CoreBPE {
+        match self {
+            Tokenizer::Cl100k => tiktoken_rs::cl100k_base_singleton(),
+            Tokenizer::O200k | Tokenizer::Estimate(_) => tiktoken_rs::o200k_base_singleton(),
+        }
+    }
 
-/// Returns a shared tiktoken BPE encoder (o200k_base).
-/// Initialized once on first call; subsequent calls are free.
-fn encoder() -> &'static CoreBPE {
-    tiktoken_rs::o200k_base_singleton()
+    /// Count the number of tokens in `text`.
+    pub fn count(self, text: &str) -> u32 {
+        let tokens = self.encoder().encode_ordinary(text).len() as u32;
+        match self {
+            Tokenizer::Estimate(factor) => (tokens as f32 * factor).ceil() as u32,
+            Tokenizer::O200k | Tokenizer::Cl100k => tokens,
+        }
+    }
+
+    /// Clip `text` to fit within `max_tokens`, using a character-ratio estimate.
+    ///
+    /// Algorithm:
+    /// 1. Count tokens in `text`.
+    /// 2. If already within budget, return as-is.
+    /// 3. Estimate chars-per-token ratio, apply 0.9 safety factor.
+    /// 4. Truncate to estimated char count.
+    /// 5. Optionally append a truncation indicator.
+    pub fn clip(self, text: &str, max_tokens: u32, add_three_dots: bool) -> String {
+        clip_with(text, self.count(text), max_tokens, add_three_dots)
+    }
+}
+
+/// Tokenizer for `model`.
+///
+/// OpenAI models use their tiktoken encoding. Other providers don't publish
+/// theirs, so their counts are estimated from `o200k_base`: Claude's
+/// tokenizer yields roughly a fifth more tokens on code, open models about
+/// a tenth more, and Gemini about the same.
+pub fn tokenizer_for(model: &str) -> Tokenizer {
+    let normalized = normalize_model_name(model);
+    let is_o_series = ["o1", "o3", "o4"]
+        .iter()
+        .any(|p| normalized == *p || normalized.starts_with(&format!("{p}-")));
+
+    if normalized.starts_with("gpt-4o")
+        || normalized.starts_with("gpt-4.")
+        || normalized.starts_with("gpt-5")
+        || is_o_series
+    {
+        Tokenizer::O200k
+    } else if normalized.starts_with("gpt-3.5") || normalized.starts_with("gpt-4") {
+        Tokenizer::Cl100k
+    } else if normalized.contains("claude") {
+        Tokenizer::Estimate(1.2)
+    } else if normalized.contains("gemini") {
+        Tokenizer::Estimate(1.0)
+    } else {
+        Tokenizer::Estimate(1.1)
+    }
 }
 
 // ── Token counting ─────────────────────────────────────────────────
 
 /// Count the number of tokens in `text` using the o200k_base BPE encoder.
+///
+/// Use [`count_tokens_for`] when checking text against a model's window.
+#[allow(dead_code)]
 pub fn count_tokens(text: &str) -> u32 {
-    encoder().encode_ordinary(text).len() as u32
+    Tokenizer::O200k.count(text)
+}
+
+/// Count the number of tokens in `text` with `model`'s tokenizer.
+#[allow(dead_code)]
+pub fn count_tokens_for(model: &str, text: &str) -> u32 {
+    tokenizer_for(model).count(text)
 }
 
-/// Clip `text` to fit within `max_tokens`, using a character-ratio estimate.
+/// Clip `text` to fit within `max_tokens` o200k_base tokens.
 ///
-/// Algorithm:
-/// 1. Count tokens in `text`.
-/// 2. If already within budget, return as-is.
-/// 3. Estimate chars-per-token ratio, apply 0.9 safety factor.
-/// 4. Truncate to estimated char count.
-/// 5. Optionally append a truncation indicator.
+/// See [`Tokenizer::clip`].
 pub fn clip_tokens(text: &str, max_tokens: u32, add_three_dots: bool) -> String {
+    Tokenizer::O200k.clip(text, max_tokens, add_three_dots)
+}
+
+fn clip_with(text: &str, num_input_tokens: u32, max_tokens: u32, add_three_dots: bool) -> String {
     if text.is_empty() || max_tokens == 0 {
         return String::new();
     }
 
-    let num_input_tokens = count_tokens(text);
     if num_input_tokens <= max_tokens {
         return text.to_string();
     }
@@ -158,16 +231,55 @@ pub fn get_max_tokens(model: &str) -> u32 {
         "mistral/open-codestral-mamba" => 256_000,
         s if s.starts_with("mistral/") => 128_000,
 
+        // Open models served by Ollama
+        s if s.starts_with("ollama/") || s.starts_with("ollama_chat/") => {
+            open_model_max_tokens(s.split_once('/').map_or(s, |(_, name)| name))
+        }
+
         // Default fallback
         _ => 0, // caller should use config.max_model_tokens
     }
 }
 
+/// Context window of an open-weight model by family (`llama3.1:8b` → 131072).
+///
+/// Returns 0 for unknown families. Ollama runs them with `num_ctx` set to
+/// this window, so it must not exceed what the model was trained on.
+fn open_model_max_tokens(name: &str) -> u32 {
+    let family = name.split_once(':').map_or(name, |(family, _)| family);
+    match family {
+        s if ["llama3.1", "llama3.2", "llama3.3"]
+            .iter()
+            .any(|p| s.starts_with(p)) =>
+        {
+            131_072
+        }
+        s if s.starts_with("llama3") => 8_192,
+        s if s.starts_with("qwen3") => 40_960,
+        s if s.starts_with("qwen2.5") => 32_768,
+        s if s.starts_with("deepseek-coder-v2") => 163_840,
+        s if s.starts_with("gemma3") => 131_072,
+        s if s.starts_with("gemma2") => 8_192,
+        s if s.starts_with("codellama") || s.starts_with("phi4") => 16_384,
+        s if s.starts_with("mistral-nemo") => 128_000,
+        _ => 0,
+    }
+}
+
 /// Look up the maximum context tokens for a model, falling back to the
 /// configured `max_model_tokens` if the model is unknown.
+///
+/// Ollama models are capped by `max_model_tokens` too: their window becomes
+/// `num_ctx`, which is allocated up front on the local machine.
 pub fn get_max_tokens_with_fallback(model: &str, config_max: u32) -> u32 {
     let known = get_max_tokens(model);
-    if known > 0 { known } else { config_max }
+    if known == 0 {
+        config_max
+    } else if model.starts_with("ollama") {
+        known.min(config_max)
+    } else {
+        known
+    }
 }
 
 /// Check if a model does NOT support the temperature parameter.
@@ -220,6 +332,17 @@ pub fn supports_reasoning_effort(model: &str) -> bool {
     )
 }
 
+/// Check if an OpenAI model accepts `response_format: json_schema`.
+pub fn supports_json_schema(model: &str) -> bool {
+    let normalized = normalize_model_name(model);
+
+    normalized != "gpt-4o-2024-05-13"
+        && ["gpt-4o", "gpt-4.1", "gpt-5", "o3", "o4-mini"]
+            .iter()
+            .any(|p| normalized.starts_with(p))
+        || matches!(normalized, "o1" | "o1-2024-12-17")
+}
+
 /// Check if a model requires streaming (e.g. some API providers require it).
 #[allow(dead_code)]
 pub fn requires_streaming(model: &str) -> bool {
@@ -265,6 +388,32 @@ mod tests {
         assert_eq!(clip_tokens("hello", 0, true), "");
     }
 
+    #[test]
+    fn test_tokenizer_for() {
+        assert_eq!(tokenizer_for("gpt-4o-mini"), Tokenizer::O200k);
+        assert_eq!(tokenizer_for("azure/gpt-5.2-2025-12-11"), Tokenizer::O200k);
+        assert_eq!(tokenizer_for("o3-mini"), Tokenizer::O200k);
+        assert_eq!(tokenizer_for("gpt-4-turbo"), Tokenizer::Cl100k);
+        assert_eq!(tokenizer_for("gpt-3.5-turbo"), Tokenizer::Cl100k);
+        assert_eq!(
+            tokenizer_for("anthropic/claude-sonnet-4-5-20250929"),
+            Tokenizer::Estimate(1.2)
+        );
+        assert_eq!(tokenizer_for("ollama/llama3.1"), Tokenizer::Estimate(1.1));
+    }
+
+    #[test]
+    fn test_count_tokens_per_model() {
+        let code = "fn main() {\n    println!(\"Hello, world!\");\n}\n".repeat(20);
+        let o200k = count_tokens(&code);
+        assert_eq!(count_tokens_for("gpt-4o", &code), o200k);
+        assert!(count_tokens_for("gpt-4", &code) > 0);
+        assert!(count_tokens_for("claude-sonnet-4-5", &code) > o200k);
+
+        let clipped = tokenizer_for("claude-sonnet-4-5").clip(&code, 50, false);
+        assert!(count_tokens_for("claude-sonnet-4-5", &clipped) <= 50);
+    }
+
     #[test]
     fn test_get_max_tokens() {
         assert_eq!(get_max_tokens("gpt-4"), 8_000);
@@ -278,6 +427,15 @@ mod tests {
         );
         assert_eq!(get_max_tokens("gemini/gemini-2.5-pro"), 1_048_576);
         assert_eq!(get_max_tokens("deepseek/deepseek-chat"), 128_000);
+        assert_eq!(get_max_tokens("ollama/llama3.1:8b"), 131_072);
+        assert_eq!(get_max_tokens("ollama_chat/qwen2.5-coder:32b"), 32_768);
+        assert_eq!(get_max_tokens("ollama/my-finetune"), 0);
+        assert_eq!(get_max_tokens_with_fallback("ollama/llama3", 32_000), 8_192);
+        assert_eq!(
+            get_max_tokens_with_fallback("ollama/llama3.1", 32_000),
+            32_000
+        );
+        assert_eq!(get_max_tokens_with_fallback("gpt-4o", 32_000), 128_000);
         assert_eq!(get_max_tokens("unknown-model"), 0);
     }
 
@@ -289,5 +447,10 @@ mod tests {
         assert!(!is_user_message_only_model("gpt-4o"));
         assert!(supports_reasoning_effort("o3-mini"));
         assert!(!supports_reasoning_effort("gpt-4o"));
+        assert!(supports_json_schema("gpt-4o-mini"));
+        assert!(supports_json_schema("azure/gpt-5.2-2025-12-11"));
+        assert!(!supports_json_schema("gpt-4o-2024-05-13"));
+        assert!(!supports_json_schema("o1-mini"));
+        assert!(!supports_json_schema("deepseek/deepseek-chat"));
     }
 }
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 /// Response from an AI chat completion call.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatResponse {
     pub content: String,
     pub finish_reason: FinishReason,
@@ -16,6 +16,8 @@ pub enum FinishReason {
     Length,
     ContentFilter,
     ToolCalls,
+    /// Streaming was stopped early (deadline); the content is partial.
+    Cancelled,
     #[default]
     Unknown,
 }
@@ -54,6 +56,8 @@ pub struct ModelCapabilities {
     pub reasoning_effort: Option<String>,
     #[allow(dead_code)]
     pub max_tokens: u32,
+    /// Output can be constrained to a JSON schema ([`ResponseSchema`]).
+    pub supports_json_schema: bool,
 }
 
 impl Default for ModelCapabilities {
@@ -65,6 +69,15 @@ impl Default for ModelCapabilities {
             requires_streaming: false,
             reasoning_effort: None,
             max_tokens: 32_000,
+            supports_json_schema: false,
         }
     }
 }
+
+/// JSON schema a tool expects its answer to follow (`config.structured_output`).
+#[derive(Debug, Clone)]
+pub struct ResponseSchema {
+    /// Short identifier (e.g. `pr_review`), used as the schema or tool name.
+    pub name: &'static str,
+    pub schema: serde_json::Value,
+}
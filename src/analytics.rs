@@ -0,0 +1,613 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::PrAgentError;
+
+/// Structured result of a `/describe` run, persisted for downstream tools.
+///
+/// Keyed by repository, PR number and head SHA so `/review` and `/improve`
+/// only reuse labels that were computed for the exact same code.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DescribeRecord {
+    /// Full repo name "owner/repo".
+    pub repo: String,
+    pub pr_number: u64,
+    pub head_sha: String,
+    pub pr_type: Vec<String>,
+    pub labels: Vec<String>,
+    pub files: Vec<FileSemanticSummary>,
+    pub created_at: String,
+}
+
+/// Semantic label and one-line title of a single changed file.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FileSemanticSummary {
+    pub filename: String,
+    pub label: String,
+    pub changes_title: String,
+}
+
+impl DescribeRecord {
+    /// Build a record from the parsed describe YAML (`type` + `pr_files`).
+    pub fn from_describe_yaml(
+        data: &serde_yaml_ng::Value,
+        repo: &str,
+        pr_number: u64,
+        head_sha: &str,
+    ) -> Self {
+        let pr_type = match data.get("type") {
+            Some(serde_yaml_ng::Value::Sequence(seq)) => seq
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            Some(v) => v
+                .as_str()
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect(),
+            None => Vec::new(),
+        };
+
+        // Explicit labels win; otherwise the PR type doubles as the label set,
+        // mirroring what describe publishes.
+        let labels = data
+            .get("labels")
+            .and_then(|v| v.as_sequence())
+            .map(|seq| {
+                seq.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|labels| !labels.is_empty())
+            .unwrap_or_else(|| pr_type.clone());
+
+        let files = data
+            .get("pr_files")
+            .and_then(|v| v.as_sequence())
+            .map(|seq| {
+                seq.iter()
+                    .filter_map(|item| {
+                        let field = |key: &str| {
+                            item.get(key)
+                                .and_then(|v| v.as_str())
+                                .unwrap_or_default()
+                                .trim()
+                                .to_string()
+                        };
+                        let filename = field("filename");
+                        if filename.is_empty() {
+                            return None;
+                        }
+                        Some(FileSemanticSummary {
+                            filename,
+                            label: field("label").to_lowercase(),
+                            changes_title: field("changes_title"),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            repo: repo.to_string(),
+            pr_number,
+            head_sha: head_sha.to_string(),
+            pr_type,
+            labels,
+            files,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Render the record as plain text for prompt templates.
+    pub fn to_prompt_context(&self) -> String {
+        let mut out = String::new();
+        if !self.pr_type.is_empty() {
+            let _ = writeln!(out, "PR type: {}", self.pr_type.join(", "));
+        }
+        for file in &self.files {
+            let _ = write!(out, "- {}", file.filename);
+            if !file.label.is_empty() {
+                let _ = write!(out, " [{}]", file.label);
+            }
+            if !file.changes_title.is_empty() {
+                let _ = write!(out, ": {}", file.changes_title);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Replace anything outside `[A-Za-z0-9._-]` so a value is safe as a path component.
+fn sanitize_component(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if cleaned.is_empty() || cleaned.chars().all(|c| c == '.') {
+        "_".into()
+    } else {
+        cleaned
+    }
+}
+
+/// Directory holding all describe records for a single PR.
+fn describe_dir(folder: &Path, repo: &str, pr_number: u64) -> PathBuf {
+    folder
+        .join("describe")
+        .join(sanitize_component(&repo.replace('/', "__")))
+        .join(pr_number.to_string())
+}
+
+/// Persist a describe record as `<folder>/describe/<owner>__<repo>/<pr>/<sha>.json`.
+pub fn save_describe_record(folder: &Path, record: &DescribeRecord) -> Result<(), PrAgentError> {
+    let dir = describe_dir(folder, &record.repo, record.pr_number);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.json", sanitize_component(&record.head_sha)));
+    std::fs::write(&path, serde_json::to_vec_pretty(record)?)?;
+    tracing::debug!(path = %path.display(), "persisted describe record");
+    Ok(())
+}
+
+/// Load the describe record for a PR at a given head SHA.
+///
+/// Returns `Ok(None)` when no record was stored for that commit.
+pub fn load_describe_record(
+    folder: &Path,
+    repo: &str,
+    pr_number: u64,
+    head_sha: &str,
+) -> Result<Option<DescribeRecord>, PrAgentError> {
+    let path = describe_dir(folder, repo, pr_number)
+        .join(format!("{}.json", sanitize_component(head_sha)));
+    match std::fs::read(&path) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Load the most recently written describe record for a PR, regardless of SHA.
+pub fn load_latest_describe_record(
+    folder: &Path,
+    repo: &str,
+    pr_number: u64,
+) -> Result<Option<DescribeRecord>, PrAgentError> {
+    let dir = describe_dir(folder, repo, pr_number);
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut latest: Option<DescribeRecord> = None;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(&path) else {
+            continue;
+        };
+        let Ok(record) = serde_json::from_slice::<DescribeRecord>(&bytes) else {
+            tracing::warn!(path = %path.display(), "skipping unreadable describe record");
+            continue;
+        };
+        if latest
+            .as_ref()
+            .is_none_or(|l| record.created_at > l.created_at)
+        {
+            latest = Some(record);
+        }
+    }
+    Ok(latest)
+}
+
+/// A maintainer's verdict on a review finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingStatus {
+    Resolved,
+    Wontfix,
+}
+
+/// Resolution of one numbered `/review` finding (`/resolve 2`, `/wontfix 3 reason`).
+///
+/// Persisted so acceptance rates can be computed across PRs.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FindingResolutionRecord {
+    /// Full repo name "owner/repo".
+    pub repo: String,
+    pub pr_number: u64,
+    /// 1-based finding number as shown in the review.
+    pub finding: usize,
+    /// Finding header (e.g. "Possible Issue").
+    pub header: String,
+    pub status: FindingStatus,
+    pub reason: String,
+    /// Login of the user who replied; empty when run from the CLI.
+    pub user: String,
+    pub created_at: String,
+}
+
+/// Directory holding finding resolutions for a single PR.
+fn findings_dir(folder: &Path, repo: &str, pr_number: u64) -> PathBuf {
+    folder
+        .join("findings")
+        .join(sanitize_component(&repo.replace('/', "__")))
+        .join(pr_number.to_string())
+}
+
+/// Persist a resolution as `<folder>/findings/<owner>__<repo>/<pr>/<finding>.json`.
+///
+/// A later verdict on the same finding replaces the earlier one.
+pub fn save_finding_resolution(
+    folder: &Path,
+    record: &FindingResolutionRecord,
+) -> Result<(), PrAgentError> {
+    let dir = findings_dir(folder, &record.repo, record.pr_number);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.json", record.finding));
+    std::fs::write(&path, serde_json::to_vec_pretty(record)?)?;
+    tracing::debug!(path = %path.display(), "persisted finding resolution");
+    Ok(())
+}
+
+/// Load all finding resolutions recorded for a PR, ordered by finding number.
+pub fn load_finding_resolutions(
+    folder: &Path,
+    repo: &str,
+    pr_number: u64,
+) -> Result<Vec<FindingResolutionRecord>, PrAgentError> {
+    let entries = match std::fs::read_dir(findings_dir(folder, repo, pr_number)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut records: Vec<FindingResolutionRecord> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| {
+            let bytes = std::fs::read(entry.path()).ok()?;
+            serde_json::from_slice(&bytes).ok()
+        })
+        .collect();
+    records.sort_by_key(|r| r.finding);
+    Ok(records)
+}
+
+/// Outcome of a closed PR, from the GitHub `pull_request` `closed` webhook.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ClosedPrRecord {
+    /// Full repo name "owner/repo".
+    pub repo: String,
+    pub pr_number: u64,
+    pub title: String,
+    pub url: String,
+    pub merged: bool,
+    /// Login of the user who merged; empty when closed without merge.
+    pub merged_by: String,
+    pub additions: u64,
+    pub deletions: u64,
+    /// Hours from opening to merge; 0 when closed without merge.
+    pub time_to_merge_hours: f64,
+    pub closed_at: String,
+}
+
+/// Findings of the latest full `/review` of a PR.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReviewRecord {
+    /// Full repo name "owner/repo".
+    pub repo: String,
+    pub pr_number: u64,
+    pub head_sha: String,
+    /// Headers of the numbered findings, in order.
+    pub findings: Vec<String>,
+    pub created_at: String,
+}
+
+impl ReviewRecord {
+    /// Build a record from the parsed review YAML (`key_issues_to_review`).
+    pub fn from_review_yaml(
+        data: &serde_yaml_ng::Value,
+        repo: &str,
+        pr_number: u64,
+        head_sha: &str,
+    ) -> Self {
+        let review = data.get("review").unwrap_or(data);
+        let findings = review
+            .get("key_issues_to_review")
+            .and_then(|v| v.as_sequence())
+            .map(|seq| {
+                seq.iter()
+                    .map(|item| {
+                        item.get("issue_header")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .trim()
+                            .to_string()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            repo: repo.to_string(),
+            pr_number,
+            head_sha: head_sha.to_string(),
+            findings,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Persist a closed PR as `<folder>/closed/<owner>__<repo>/<pr>.json`.
+pub fn save_closed_pr(folder: &Path, record: &ClosedPrRecord) -> Result<(), PrAgentError> {
+    save_pr_record(folder, "closed", &record.repo, record.pr_number, record)
+}
+
+/// Persist a review as `<folder>/reviews/<owner>__<repo>/<pr>.json`,
+/// replacing the PR's previous review.
+pub fn save_review_record(folder: &Path, record: &ReviewRecord) -> Result<(), PrAgentError> {
+    save_pr_record(folder, "reviews", &record.repo, record.pr_number, record)
+}
+
+/// Load the closed PRs of every repository.
+pub fn load_closed_prs(folder: &Path) -> Result<Vec<ClosedPrRecord>, PrAgentError> {
+    load_pr_records(folder, "closed")
+}
+
+/// Load the latest review of every PR in every repository.
+pub fn load_review_records(folder: &Path) -> Result<Vec<ReviewRecord>, PrAgentError> {
+    load_pr_records(folder, "reviews")
+}
+
+fn save_pr_record<T: Serialize>(
+    folder: &Path,
+    kind: &str,
+    repo: &str,
+    pr_number: u64,
+    record: &T,
+) -> Result<(), PrAgentError> {
+    let dir = folder
+        .join(kind)
+        .join(sanitize_component(&repo.replace('/', "__")));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{pr_number}.json"));
+    std::fs::write(&path, serde_json::to_vec_pretty(record)?)?;
+    tracing::debug!(path = %path.display(), "persisted {kind} record");
+    Ok(())
+}
+
+/// Every readable record under `<folder>/<kind>/<repo>/`; unreadable files
+/// are skipped.
+fn load_pr_records<T: serde::de::DeserializeOwned>(
+    folder: &Path,
+    kind: &str,
+) -> Result<Vec<T>, PrAgentError> {
+    let repos = match std::fs::read_dir(folder.join(kind)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut records = Vec::new();
+    for repo in repos.flatten() {
+        let Ok(entries) = std::fs::read_dir(repo.path()) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let Ok(bytes) = std::fs::read(&path) else {
+                continue;
+            };
+            match serde_json::from_slice(&bytes) {
+                Ok(record) => records.push(record),
+                Err(_) => {
+                    tracing::warn!(path = %path.display(), "skipping unreadable {kind} record")
+                }
+            }
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_folder(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pr-agent-analytics-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    const DESCRIBE_DATA: &str = r#"
+type:
+  - Enhancement
+  - Tests
+pr_files:
+  - filename: |
+      src/main.rs
+    changes_title: |
+      Add debug output
+    label: |
+      Enhancement
+  - filename: |
+      tests/main_test.rs
+    changes_title: |
+      Cover debug output
+    label: |
+      tests
+"#;
+
+    fn sample_record(sha: &str) -> DescribeRecord {
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(DESCRIBE_DATA).unwrap();
+        DescribeRecord::from_describe_yaml(&data, "owner/repo", 7, sha)
+    }
+
+    #[test]
+    fn test_from_describe_yaml_extracts_type_and_files() {
+        let record = sample_record("abc123");
+        assert_eq!(record.pr_type, vec!["Enhancement", "Tests"]);
+        assert_eq!(record.files.len(), 2);
+        assert_eq!(record.files[0].filename, "src/main.rs");
+        assert_eq!(record.files[0].label, "enhancement");
+        assert_eq!(record.files[1].changes_title, "Cover debug output");
+    }
+
+    #[test]
+    fn test_from_describe_yaml_accepts_comma_separated_type() {
+        let data: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str("type: \"Bug fix, Tests\"").unwrap();
+        let record = DescribeRecord::from_describe_yaml(&data, "o/r", 1, "sha");
+        assert_eq!(record.pr_type, vec!["Bug fix", "Tests"]);
+        assert_eq!(record.labels, record.pr_type);
+        assert!(record.files.is_empty());
+    }
+
+    #[test]
+    fn test_prompt_context_lists_files_with_labels() {
+        let context = sample_record("abc123").to_prompt_context();
+        assert!(context.starts_with("PR type: Enhancement, Tests\n"));
+        assert!(context.contains("- src/main.rs [enhancement]: Add debug output"));
+        assert!(context.contains("- tests/main_test.rs [tests]: Cover debug output"));
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip_by_sha() {
+        let folder = temp_folder("round-trip");
+        let record = sample_record("abc123");
+        save_describe_record(&folder, &record).unwrap();
+
+        let loaded = load_describe_record(&folder, "owner/repo", 7, "abc123").unwrap();
+        assert_eq!(loaded, Some(record));
+        assert!(
+            load_describe_record(&folder, "owner/repo", 7, "other")
+                .unwrap()
+                .is_none(),
+            "a different head SHA must not reuse the stored record"
+        );
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn test_load_latest_picks_newest_record() {
+        let folder = temp_folder("latest");
+        let mut old = sample_record("old");
+        old.created_at = "2024-01-01T00:00:00+00:00".into();
+        let mut new = sample_record("new");
+        new.created_at = "2024-06-01T00:00:00+00:00".into();
+        save_describe_record(&folder, &old).unwrap();
+        save_describe_record(&folder, &new).unwrap();
+
+        let latest = load_latest_describe_record(&folder, "owner/repo", 7)
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.head_sha, "new");
+        assert!(
+            load_latest_describe_record(&folder, "owner/repo", 8)
+                .unwrap()
+                .is_none()
+        );
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn test_finding_resolutions_latest_verdict_wins() {
+        let folder = temp_folder("findings");
+        let record = |finding: usize, status: FindingStatus| FindingResolutionRecord {
+            repo: "owner/repo".into(),
+            pr_number: 7,
+            finding,
+            header: "Possible Issue".into(),
+            status,
+            reason: String::new(),
+            user: "alice".into(),
+            created_at: "2026-01-01T00:00:00+00:00".into(),
+        };
+        save_finding_resolution(&folder, &record(3, FindingStatus::Resolved)).unwrap();
+        save_finding_resolution(&folder, &record(1, FindingStatus::Resolved)).unwrap();
+        save_finding_resolution(&folder, &record(3, FindingStatus::Wontfix)).unwrap();
+
+        let loaded = load_finding_resolutions(&folder, "owner/repo", 7).unwrap();
+        assert_eq!(
+            loaded,
+            vec![
+                record(1, FindingStatus::Resolved),
+                record(3, FindingStatus::Wontfix)
+            ]
+        );
+        assert!(
+            load_finding_resolutions(&folder, "owner/repo", 8)
+                .unwrap()
+                .is_empty()
+        );
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn test_pr_records_round_trip_across_repos() {
+        let folder = temp_folder("pr-records");
+        let closed = |repo: &str, pr_number: u64| ClosedPrRecord {
+            repo: repo.into(),
+            pr_number,
+            title: "Add feature".into(),
+            url: format!("https://github.com/{repo}/pull/{pr_number}"),
+            merged: true,
+            merged_by: "alice".into(),
+            additions: 10,
+            deletions: 2,
+            time_to_merge_hours: 5.5,
+            closed_at: "2026-01-02T00:00:00Z".into(),
+        };
+        save_closed_pr(&folder, &closed("owner/repo", 1)).unwrap();
+        save_closed_pr(&folder, &closed("owner/other", 2)).unwrap();
+        let mut loaded = load_closed_prs(&folder).unwrap();
+        loaded.sort_by_key(|r| r.pr_number);
+        assert_eq!(
+            loaded,
+            vec![closed("owner/repo", 1), closed("owner/other", 2)]
+        );
+
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(
+            "review:\n  key_issues_to_review:\n    - issue_header: |\n        Possible Bug\n    - issue_header: Performance\n",
+        )
+        .unwrap();
+        let review = ReviewRecord::from_review_yaml(&data, "owner/repo", 1, "abc");
+        assert_eq!(review.findings, vec!["Possible Bug", "Performance"]);
+        save_review_record(&folder, &review).unwrap();
+        assert_eq!(load_review_records(&folder).unwrap(), vec![review]);
+        assert!(
+            load_review_records(&folder.join("missing"))
+                .unwrap()
+                .is_empty()
+        );
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn test_sanitize_component_blocks_path_traversal() {
+        assert_eq!(sanitize_component(".."), "_");
+        assert_eq!(sanitize_component("../etc"), ".._etc");
+        assert_eq!(sanitize_component("abc123"), "abc123");
+    }
+}
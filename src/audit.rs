@@ -0,0 +1,207 @@
+use std::fmt::Write;
+use std::sync::LazyLock;
+
+use crate::error::PrAgentError;
+use crate::git::GitProvider;
+use crate::git::types::CommentId;
+use crate::output::markdown::{persistent_comment_marker, sanitize_table_cell, user_code_span};
+use crate::request_context;
+use crate::usage::AiUsage;
+use crate::util::KeyedLocks;
+
+/// Header row of the audit table; rows are appended below it.
+const TABLE_HEADER: &str =
+    "| Time (UTC) | Tool | Model | Tokens (in/out) | Outcome |\n|---|---|---|---|---|\n";
+
+/// Closing tag of the collapsed audit section; new rows go right before it.
+const TABLE_END: &str = "\n</details>";
+
+/// Rows kept in the audit comment. Older ones are dropped so the comment
+/// stays under GitHub's 65536-character body limit.
+const MAX_ENTRIES: usize = 100;
+
+/// One lock per PR, so concurrent tool runs don't overwrite each other's
+/// rows.
+static AUDIT_LOCKS: LazyLock<KeyedLocks> = LazyLock::new(KeyedLocks::default);
+
+/// One action the bot took on a PR.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub tool: String,
+    /// RFC 3339 timestamp.
+    pub timestamp: String,
+    pub usage: AiUsage,
+    /// `ok`, or the kind of error and the request id to find it in the logs.
+    pub outcome: String,
+}
+
+impl AuditEntry {
+    pub fn new(tool: &str, usage: AiUsage, result: &Result<(), PrAgentError>) -> Self {
+        Self {
+            tool: tool.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            usage,
+            outcome: match result {
+                Ok(()) => "ok".into(),
+                Err(e) => match request_context::current_id() {
+                    Some(id) => format!("error: {} (request id `{id}`)", e.public_summary()),
+                    None => format!("error: {}", e.public_summary()),
+                },
+            },
+        }
+    }
+
+    /// Render the entry as a markdown table row.
+    fn to_row(&self) -> String {
+        let models = if self.usage.models.is_empty() {
+            "—".to_string()
+        } else {
+            self.usage.model_names().join(", ")
+        };
+        let cell = |s: &str| sanitize_table_cell(&s.replace('\n', " "));
+        format!(
+            "| {} | {} | {} | {}/{} | {} |",
+            self.timestamp,
+            user_code_span(&self.tool),
+            cell(&models),
+            self.usage.prompt_tokens(),
+            self.usage.completion_tokens(),
+            cell(&self.outcome)
+        )
+    }
+}
+
+/// Append `entry` to the PR's audit comment, creating it on first use.
+///
+/// Appends for the same PR are serialized within this process. Separate
+/// server processes can still race, and one of two concurrent rows is lost.
+pub async fn append_audit_entry(
+    provider: &dyn GitProvider,
+    entry: &AuditEntry,
+) -> Result<(), PrAgentError> {
+    let _guard = AUDIT_LOCKS.lock(provider.get_pr_url()).await;
+    let marker = persistent_comment_marker("audit");
+    let row = entry.to_row();
+    let comments = provider.get_issue_comments().await?;
+    if let Some(existing) = comments.iter().find(|c| c.body.starts_with(&marker)) {
+        let body = append_row(&existing.body, &row);
+        return provider
+            .edit_comment(&CommentId(existing.id.to_string()), &body)
+            .await;
+    }
+
+    let mut body =
+        format!("{marker}\n<details><summary>🤖 PR-Agent activity log</summary>\n\n{TABLE_HEADER}");
+    let _ = write!(body, "{row}{TABLE_END}");
+    provider.publish_comment(&body, false).await?;
+    Ok(())
+}
+
+/// Insert `row` as the last table row of an audit comment body, keeping
+/// the last [`MAX_ENTRIES`] rows.
+fn append_row(body: &str, row: &str) -> String {
+    let (head, tail) = body
+        .rfind(TABLE_END)
+        .map_or((body, ""), |idx| body.split_at(idx));
+    let Some((intro, rows)) = head.split_once(TABLE_HEADER) else {
+        return format!("{head}\n{row}{tail}");
+    };
+    let rows: Vec<&str> = rows
+        .lines()
+        .filter(|line| !line.is_empty())
+        .chain([row])
+        .collect();
+    let kept = &rows[rows.len().saturating_sub(MAX_ENTRIES)..];
+    format!("{intro}{TABLE_HEADER}{}{tail}", kept.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::types::IssueComment;
+    use crate::testing::mock_git::MockGitProvider;
+    use crate::usage::ModelUsage;
+
+    #[test]
+    fn test_entry_row_escapes_cells() {
+        let entry = AuditEntry {
+            tool: "review".into(),
+            timestamp: "2026-01-02T03:04:05Z".into(),
+            usage: AiUsage {
+                models: vec![ModelUsage {
+                    model: "gpt-4o".into(),
+                    calls: 1,
+                    prompt_tokens: 1200,
+                    completion_tokens: 300,
+                }],
+            },
+            outcome: "error: bad | input\nline".into(),
+        };
+        assert_eq!(
+            entry.to_row(),
+            "| 2026-01-02T03:04:05Z | `review` | gpt-4o | 1200/300 | error: bad \\| input line |"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_append_audit_entry_creates_then_appends() {
+        let entry = |tool: &str| AuditEntry::new(tool, AiUsage::default(), &Ok(()));
+
+        let provider = MockGitProvider::new();
+        append_audit_entry(&provider, &entry("describe"))
+            .await
+            .unwrap();
+        let body = provider.get_calls().comments[0].0.clone();
+        assert!(body.starts_with("<!-- pr-agent:audit -->"));
+        assert!(body.contains("| `describe` | — | 0/0 | ok |\n</details>"));
+
+        let mut provider = MockGitProvider::new();
+        provider.issue_comments = vec![IssueComment {
+            id: 7,
+            body,
+            user: "bot".into(),
+            created_at: String::new(),
+            url: None,
+        }];
+        append_audit_entry(&provider, &entry("review"))
+            .await
+            .unwrap();
+        let calls = provider.get_calls();
+        assert!(calls.comments.is_empty());
+        let (id, edited) = &calls.edited_comments[0];
+        assert_eq!(id, "7");
+        assert!(edited.find("`describe`").unwrap() < edited.find("`review`").unwrap());
+        assert!(edited.ends_with("| `review` | — | 0/0 | ok |\n</details>"));
+    }
+
+    #[test]
+    fn test_append_row_keeps_last_entries() {
+        let mut body =
+            format!("<!-- pr-agent:audit -->\n<details>\n\n{TABLE_HEADER}| 0 |{TABLE_END}");
+        for i in 1..=MAX_ENTRIES {
+            body = append_row(&body, &format!("| {i} |"));
+        }
+        assert!(!body.contains("| 0 |"));
+        assert!(body.contains(&format!("{TABLE_HEADER}| 1 |\n| 2 |")));
+        assert!(body.ends_with(&format!("| {MAX_ENTRIES} |{TABLE_END}")));
+        assert_eq!(body.matches("\n| ").count(), MAX_ENTRIES + 1);
+    }
+
+    #[tokio::test]
+    async fn test_entry_outcome_hides_error_details() {
+        let result = Err(PrAgentError::AiHandler(
+            "401 from https://internal-gateway.example/v1: invalid key sk-123".into(),
+        ));
+        let entry = AuditEntry::new("review", AiUsage::default(), &result);
+        assert_eq!(entry.outcome, "error: the AI model request failed");
+
+        let entry = request_context::scope("job-7".into(), "test", async {
+            AuditEntry::new("review", AiUsage::default(), &result)
+        })
+        .await;
+        assert_eq!(
+            entry.outcome,
+            "error: the AI model request failed (request id `job-7`)"
+        );
+    }
+}
@@ -4,8 +4,13 @@ use std::sync::Arc;
 use clap::{Parser, Subcommand};
 
 use crate::config::loader::init_settings;
+use crate::config::types::Settings;
+use crate::config::validate::validate_config;
 use crate::error::PrAgentError;
 use crate::git::github::GithubProvider;
+use crate::output::report::{self, ReportFormat};
+use crate::output::terminal::{self, OutputMode};
+use crate::request_context;
 use crate::tools;
 
 /// PR-Agent: AI-powered code review and PR analysis tool.
@@ -20,6 +25,34 @@ pub struct Cli {
     #[arg(long)]
     pub issue_url: Option<String>,
 
+    /// Result format on stdout. `json` prints parsed results and disables
+    /// progress bars and colors (also off when stdout is not a terminal).
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub output: OutputMode,
+
+    /// Print what the tool would publish instead of posting it to the PR.
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Write dry-run output to this file instead of stdout (implies --dry-run).
+    #[arg(long, value_name = "PATH", global = true)]
+    pub dry_run_output: Option<String>,
+
+    /// Format of the `--output-file` report written by review, describe,
+    /// and improve.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        global = true,
+        requires = "output_file"
+    )]
+    pub output_format: ReportFormat,
+
+    /// Also write the tool's result to this file, for other CI steps.
+    #[arg(long, value_name = "PATH", global = true)]
+    pub output_file: Option<String>,
+
     #[command(subcommand)]
     pub command: Command,
 
@@ -34,6 +67,10 @@ pub enum Command {
     /// Add a review with summary and suggestions.
     #[command(alias = "review_pr")]
     Review,
+    /// Review the changes between two commits of the PR
+    /// (`-- --base=<sha> --head=<sha>`).
+    #[command(alias = "review_diff_between")]
+    ReviewRange,
     /// Automatic review (triggered by CI/webhooks).
     AutoReview,
     /// Answer mode (for issue comments).
@@ -51,10 +88,16 @@ pub enum Command {
     AskLine,
     /// Update changelog based on PR.
     UpdateChangelog,
+    /// Write the PR's changelog fragment file to its branch.
+    ChangelogFragment,
     /// Add documentation.
     AddDocs,
     /// Generate PR labels.
     GenerateLabels,
+    /// Generate unit tests for changed code.
+    Test,
+    /// List changed components with follow-up commands.
+    Analyze,
     /// Get help on issues/PRs.
     HelpDocs,
     /// Find similar issues.
@@ -64,8 +107,26 @@ pub enum Command {
     Config,
     /// Start the webhook server.
     Serve,
+    /// Show PR metrics per repository from the metrics store
+    /// (`metrics_store.database_url`).
+    Stats {
+        /// Only this repository, as "owner/repo".
+        #[arg(long)]
+        repo: Option<String>,
+        /// Days covered, up to now.
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+    },
     /// Check if the server is healthy (for Docker HEALTHCHECK).
     Health,
+    /// Check a `.pr_agent.toml` for unknown keys, wrong value types, invalid
+    /// regexes and prompt template errors.
+    #[command(alias = "validate_config")]
+    ValidateConfig {
+        /// Settings file to check.
+        #[arg(default_value = ".pr_agent.toml")]
+        path: String,
+    },
 }
 
 impl Command {
@@ -73,6 +134,7 @@ impl Command {
     pub fn canonical_name(&self) -> &'static str {
         match self {
             Command::Review => "review",
+            Command::ReviewRange => "review_range",
             Command::AutoReview => "auto_review",
             Command::Answer => "answer",
             Command::Describe => "describe",
@@ -80,13 +142,18 @@ impl Command {
             Command::Ask => "ask",
             Command::AskLine => "ask_line",
             Command::UpdateChangelog => "update_changelog",
+            Command::ChangelogFragment => "changelog_fragment",
             Command::AddDocs => "add_docs",
             Command::GenerateLabels => "generate_labels",
+            Command::Test => "test",
+            Command::Analyze => "analyze",
             Command::HelpDocs => "help_docs",
             Command::SimilarIssue => "similar_issue",
             Command::Config => "config",
             Command::Serve => "serve",
+            Command::Stats { .. } => "stats",
             Command::Health => "health",
+            Command::ValidateConfig { .. } => "validate_config",
         }
     }
 }
@@ -102,6 +169,8 @@ pub const FORBIDDEN_OVERRIDE_KEYS: &[&str] = &[
     "enable_comment_approval",
     "enable_manual_approval",
     "enable_auto_approval",
+    "auto_approve_for_low_review_effort",
+    "auto_approve_for_no_suggestions",
     "approve_pr_on_self_review",
     "base_url",
     "url",
@@ -110,7 +179,20 @@ pub const FORBIDDEN_OVERRIDE_KEYS: &[&str] = &[
     "git_provider",
     "skip_keys",
     "openai.key",
+    "access_key_id",
+    "secret_access_key",
+    "session_token",
+    "bedrock_endpoint",
+    "region_name",
     "analytics_folder",
+    "mock_responses_dir",
+    "response_cache.dir",
+    "dry_run_output",
+    "accepted_suggestions_file",
+    "accepted_suggestions_branch",
+    "fragment_path",
+    "diagram_renderer_url",
+    "render_diagram_image",
     "uri",
     "app_id",
     "webhook_secret",
@@ -121,9 +203,19 @@ pub const FORBIDDEN_OVERRIDE_KEYS: &[&str] = &[
     "local_cache_path",
     "enable_local_cache",
     "jira_base_url",
+    "jira_api_email",
+    "jira_api_token",
+    "slack_webhook_url",
+    "teams_webhook_url",
+    "database_url",
     "api_base",
+    "api_key",
     "api_type",
     "api_version",
+    "proxy",
+    "no_proxy",
+    "ai_headers",
+    "git_headers",
 ];
 
 /// Check if a config key is forbidden for override.
@@ -174,11 +266,28 @@ pub async fn run() -> Result<(), PrAgentError> {
     if cli.command == Command::Health {
         return health_check().await;
     }
+    if let Command::ValidateConfig { path } = &cli.command {
+        return validate_config_file(path);
+    }
 
-    let config_overrides = parse_config_overrides(&cli.rest)?;
+    let mut config_overrides = parse_config_overrides(&cli.rest)?;
+    // Set after the override check: `dry_run_output` is forbidden from
+    // comments, but a local file path is fine from the command line.
+    if cli.dry_run || cli.dry_run_output.is_some() {
+        config_overrides.insert("config.dry_run".into(), "true".into());
+    }
+    if let Some(path) = &cli.dry_run_output {
+        config_overrides.insert("config.dry_run_output".into(), path.clone());
+    }
+    if cli.command != Command::Serve {
+        terminal::init(cli.output);
+        if let Some(path) = &cli.output_file {
+            report::init(cli.output_format, path);
+        }
+    }
 
     // Bootstrap settings (no repo/global settings yet — need provider to fetch them)
-    let settings = init_settings(&config_overrides, None, None)?;
+    let settings = init_settings(&config_overrides, None, None, &[])?;
 
     let pr_url = cli.pr_url.as_deref().or(cli.issue_url.as_deref());
 
@@ -191,14 +300,29 @@ pub async fn run() -> Result<(), PrAgentError> {
     );
 
     match cli.command {
-        Command::Config => {
+        // Without a PR there are no repo settings to merge; show the basics.
+        Command::Config if pr_url.is_none() => {
             println!("Model: {}", settings.config.model);
             println!("Temperature: {}", settings.config.temperature);
             println!("Git provider: {}", settings.config.git_provider);
             println!("Max model tokens: {}", settings.config.max_model_tokens);
         }
         Command::Serve => {
-            crate::server::start_server().await?;
+            crate::server::start_server(&config_overrides).await?;
+        }
+        Command::Stats { repo, days } => {
+            let since = chrono::Utc::now() - chrono::Days::new(days.into());
+            let stats = crate::stats::query(
+                &settings.metrics_store.database_url,
+                &since.to_rfc3339(),
+                repo.as_deref(),
+            )
+            .await?;
+            if terminal::is_json() {
+                println!("{}", terminal::to_json(&stats));
+            } else {
+                print!("{}", crate::stats::render(&stats, days));
+            }
         }
         _ => {
             let url = pr_url.ok_or_else(|| {
@@ -208,63 +332,120 @@ pub async fn run() -> Result<(), PrAgentError> {
                 ))
             })?;
 
-            let provider: Arc<dyn crate::git::GitProvider> =
-                Arc::new(GithubProvider::new(url).await?);
-
-            // Load global org-level and repo-level .pr_agent.toml if enabled
-            let global_toml = if settings.config.use_global_settings_file {
-                match provider.get_global_settings().await {
-                    Ok(Some(toml)) => {
-                        tracing::info!("loaded global org-level .pr_agent.toml");
-                        Some(toml)
-                    }
-                    Ok(None) => {
-                        tracing::debug!("no global org-level .pr_agent.toml found");
-                        None
-                    }
-                    Err(e) => {
-                        tracing::warn!(error = %e, "failed to fetch global settings, continuing without");
-                        None
-                    }
-                }
-            } else {
+            // One request id covers the whole run, as for a webhook event.
+            request_context::scope(
+                request_context::new_id(),
+                "cli",
+                run_pr_command(
+                    cli.command.canonical_name(),
+                    url,
+                    &settings,
+                    &config_overrides,
+                ),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Load the PR's org-level and repo-level settings, then run `command` on it.
+async fn run_pr_command(
+    command: &str,
+    url: &str,
+    settings: &Settings,
+    config_overrides: &HashMap<String, String>,
+) -> Result<(), PrAgentError> {
+    let provider: Arc<dyn crate::git::GitProvider> = Arc::new(GithubProvider::new(url).await?);
+
+    // Load global org-level and repo-level .pr_agent.toml if enabled
+    let global_toml = if settings.config.use_global_settings_file {
+        match provider.get_global_settings().await {
+            Ok(Some(toml)) => {
+                tracing::info!("loaded global org-level .pr_agent.toml");
+                Some(toml)
+            }
+            Ok(None) => {
+                tracing::debug!("no global org-level .pr_agent.toml found");
                 None
-            };
-
-            let repo_toml = if settings.config.use_repo_settings_file {
-                match provider.get_repo_settings().await {
-                    Ok(Some(toml)) => {
-                        tracing::info!("loaded repo-level .pr_agent.toml");
-                        Some(toml)
-                    }
-                    Ok(None) => {
-                        tracing::debug!("no repo-level .pr_agent.toml found");
-                        None
-                    }
-                    Err(e) => {
-                        tracing::warn!(error = %e, "failed to fetch repo settings, continuing without");
-                        None
-                    }
-                }
-            } else {
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to fetch global settings, continuing without");
                 None
-            };
-
-            // Re-initialize settings with global + repo overrides if either was found
-            if global_toml.is_some() || repo_toml.is_some() {
-                init_settings(
-                    &config_overrides,
-                    global_toml.as_deref(),
-                    repo_toml.as_deref(),
-                )?;
             }
-
-            tools::handle_command(cli.command.canonical_name(), provider, &config_overrides)
-                .await?;
         }
+    } else {
+        None
+    };
+
+    let repo_toml = if settings.config.use_repo_settings_file {
+        match provider.get_repo_settings().await {
+            Ok(Some(toml)) => {
+                tracing::info!("loaded repo-level .pr_agent.toml");
+                Some(toml)
+            }
+            Ok(None) => {
+                tracing::debug!("no repo-level .pr_agent.toml found");
+                None
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to fetch repo settings, continuing without");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let prompt_files = if settings.config.use_repo_settings_file
+        && settings.config.use_repo_prompt_files
+    {
+        match provider.get_repo_prompt_files().await {
+            Ok(files) => {
+                if !files.is_empty() {
+                    tracing::info!(count = files.len(), "loaded repo prompt overrides");
+                }
+                files
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to fetch repo prompt overrides, continuing without");
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    // Re-initialize settings with global + repo overrides if any were found
+    if global_toml.is_some() || repo_toml.is_some() || !prompt_files.is_empty() {
+        init_settings(
+            config_overrides,
+            global_toml.as_deref(),
+            repo_toml.as_deref(),
+            &prompt_files,
+        )?;
     }
 
-    Ok(())
+    tools::handle_command(command, provider, config_overrides).await
+}
+
+/// Report every problem in a settings file; fails if there are any.
+fn validate_config_file(path: &str) -> Result<(), PrAgentError> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| PrAgentError::Other(format!("failed to read {path}: {e}")))?;
+    let issues = validate_config(&source);
+    if issues.is_empty() {
+        println!("{path}: OK");
+        return Ok(());
+    }
+    for issue in &issues {
+        println!("{path}: {issue}");
+    }
+    Err(PrAgentError::Other(format!(
+        "{} problem(s) found in {path}",
+        issues.len()
+    )))
 }
 
 /// TCP connect health check for Docker HEALTHCHECK.
@@ -297,6 +478,47 @@ mod tests {
         assert_eq!(overrides.get("config.model").unwrap(), "gpt-4");
     }
 
+    #[test]
+    fn test_validate_config_path() {
+        let cli = Cli::try_parse_from(["pr-agent", "validate-config"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Command::ValidateConfig {
+                path: ".pr_agent.toml".into()
+            }
+        );
+        let cli = Cli::try_parse_from(["pr-agent", "validate-config", "ci/agent.toml"]).unwrap();
+        assert_eq!(cli.command.canonical_name(), "validate_config");
+        assert_eq!(
+            cli.command,
+            Command::ValidateConfig {
+                path: "ci/agent.toml".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_stats_args() {
+        let cli = Cli::try_parse_from(["pr-agent", "stats"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Command::Stats {
+                repo: None,
+                days: 30
+            }
+        );
+        let cli =
+            Cli::try_parse_from(["pr-agent", "stats", "--repo", "o/r", "--days", "7"]).unwrap();
+        assert_eq!(cli.command.canonical_name(), "stats");
+        assert_eq!(
+            cli.command,
+            Command::Stats {
+                repo: Some("o/r".into()),
+                days: 7
+            }
+        );
+    }
+
     #[test]
     fn test_forbidden_overrides() {
         let args = vec!["--openai.key=sk-secret".into()];
@@ -308,6 +530,7 @@ mod tests {
     #[test]
     fn test_command_canonical_names() {
         assert_eq!(Command::Review.canonical_name(), "review");
+        assert_eq!(Command::ReviewRange.canonical_name(), "review_range");
         assert_eq!(Command::AutoReview.canonical_name(), "auto_review");
         assert_eq!(Command::Describe.canonical_name(), "describe");
         assert_eq!(Command::Improve.canonical_name(), "improve");
@@ -1,28 +1,35 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
-use figment::Figment;
-use figment::providers::{Env, Format, Toml};
+use figment::providers::{Env, Format, Serialized, Toml};
+use figment::value::{Dict, Value};
+use figment::{Figment, Profile, Provider};
 
-use crate::config::types::Settings;
+use crate::config::prompts;
+use crate::config::types::{LockedConfig, Settings};
 use crate::error::PrAgentError;
 
 // Embedded default TOML files.
 // This makes the binary self-contained while keeping retrocompatibility.
 static CONFIGURATION_TOML: &str = include_str!("../../settings/configuration.toml");
 static IGNORE_TOML: &str = include_str!("../../settings/ignore.toml");
+static GENERATED_CODE_IGNORE_TOML: &str = include_str!("../../settings/generated_code_ignore.toml");
 static LANGUAGE_EXTENSIONS_TOML: &str = include_str!("../../settings/language_extensions.toml");
 static CUSTOM_LABELS_TOML: &str = include_str!("../../settings/custom_labels.toml");
+static MODEL_PRICES_TOML: &str = include_str!("../../settings/model_prices.toml");
 
 // Prompt template TOML files
 static PR_REVIEWER_PROMPTS: &str = include_str!("../../settings/pr_reviewer_prompts.toml");
 static PR_DESCRIPTION_PROMPTS: &str = include_str!("../../settings/pr_description_prompts.toml");
+static PR_CUSTOM_LABELS_PROMPTS: &str = include_str!("../../settings/pr_custom_labels.toml");
 static PR_CODE_SUGGESTIONS_PROMPTS: &str =
     include_str!("../../settings/code_suggestions/pr_code_suggestions_prompts.toml");
 static PR_CODE_SUGGESTIONS_NOT_DECOUPLED: &str =
     include_str!("../../settings/code_suggestions/pr_code_suggestions_prompts_not_decoupled.toml");
 static PR_CODE_SUGGESTIONS_REFLECT: &str =
     include_str!("../../settings/code_suggestions/pr_code_suggestions_reflect_prompts.toml");
+static PR_BEST_PRACTICES_PROMPTS: &str =
+    include_str!("../../settings/code_suggestions/pr_best_practices_prompts.toml");
 static PR_QUESTIONS_PROMPTS: &str = include_str!("../../settings/pr_questions_prompts.toml");
 static PR_LINE_QUESTIONS_PROMPTS: &str =
     include_str!("../../settings/pr_line_questions_prompts.toml");
@@ -30,12 +37,17 @@ static PR_UPDATE_CHANGELOG_PROMPTS: &str =
     include_str!("../../settings/pr_update_changelog_prompts.toml");
 static PR_INFORMATION_FROM_USER: &str =
     include_str!("../../settings/pr_information_from_user_prompts.toml");
+static PR_TEST_PROMPTS: &str = include_str!("../../settings/pr_test_prompts.toml");
+static PR_QUICK_TAKE_PROMPTS: &str = include_str!("../../settings/pr_quick_take_prompts.toml");
+static PR_AI_METADATA_PROMPTS: &str = include_str!("../../settings/pr_ai_metadata_prompts.toml");
 static PR_HELP_PROMPTS: &str = include_str!("../../settings/pr_help_prompts.toml");
 static PR_HELP_DOCS_PROMPTS: &str = include_str!("../../settings/pr_help_docs_prompts.toml");
 static PR_HELP_DOCS_HEADINGS: &str =
     include_str!("../../settings/pr_help_docs_headings_prompts.toml");
 static PR_EVALUATE_PROMPT_RESPONSE: &str =
     include_str!("../../settings/pr_evaluate_prompt_response.toml");
+/// Profile applied over the defaults when `config.economy_mode = true`.
+static ECONOMY_MODE_TOML: &str = include_str!("../../settings/economy_mode.toml");
 
 /// Global settings, re-settable (e.g. after loading repo-level config).
 static GLOBAL_SETTINGS: RwLock<Option<Arc<Settings>>> = RwLock::new(None);
@@ -76,6 +88,14 @@ pub fn get_settings() -> Arc<Settings> {
     })
 }
 
+/// Whether [`init_settings`] has loaded the global settings.
+pub fn settings_initialized() -> bool {
+    GLOBAL_SETTINGS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .is_some()
+}
+
 /// Initialize (or re-initialize) global settings.
 ///
 /// Can be called multiple times — e.g. first for bootstrap, then again
@@ -84,11 +104,13 @@ pub fn init_settings(
     cli_overrides: &HashMap<String, String>,
     global_settings_toml: Option<&str>,
     repo_settings_toml: Option<&str>,
+    repo_prompt_files: &[String],
 ) -> Result<Arc<Settings>, PrAgentError> {
-    let settings = Arc::new(load_settings(
+    let settings = Arc::new(load_settings_with_prompts(
         cli_overrides,
         global_settings_toml,
         repo_settings_toml,
+        repo_prompt_files,
     )?);
     *GLOBAL_SETTINGS.write().unwrap_or_else(|poisoned| {
         tracing::error!("settings RwLock poisoned, recovering inner value");
@@ -107,50 +129,107 @@ where
 
 /// Build the full configuration by merging layers:
 ///
-/// 1. Embedded TOML defaults (`settings/configuration.toml`, etc.)
+/// 1. Embedded TOML defaults (`settings/configuration.toml`, etc.), plus
+///    `settings/economy_mode.toml` when any layer sets `config.economy_mode`
 /// 2. Secrets file from filesystem (`.secrets.toml`, optional)
 /// 3. Global org-level `.pr_agent.toml` (from `pr-agent-settings` repo, optional)
 /// 4. Repo-level `.pr_agent.toml` (fetched from git provider, optional)
 /// 5. CLI argument overrides (`--section.key=value`)
 /// 6. Environment variables (highest precedence for secrets)
+///
+/// Keys listed in `[locked] keys` by layers 2-3 are dropped from layers 4-5.
 pub fn load_settings(
     cli_overrides: &HashMap<String, String>,
     global_settings_toml: Option<&str>,
     repo_settings_toml: Option<&str>,
+) -> Result<Settings, PrAgentError> {
+    load_settings_with_prompts(cli_overrides, global_settings_toml, repo_settings_toml, &[])
+}
+
+/// [`load_settings`] with the repo's `.pr_agent/prompts/*.toml` files merged
+/// right after its `.pr_agent.toml`.
+///
+/// Prompt templates that differ from the built-ins are validated (see
+/// [`prompts::apply_overrides`]); invalid ones are replaced by the built-in.
+pub fn load_settings_with_prompts(
+    cli_overrides: &HashMap<String, String>,
+    global_settings_toml: Option<&str>,
+    repo_settings_toml: Option<&str>,
+    repo_prompt_files: &[String],
+) -> Result<Settings, PrAgentError> {
+    let global = global_settings_toml.map(|toml| Figment::from(Toml::string(toml)));
+    load_layers(cli_overrides, global, repo_settings_toml, repo_prompt_files)
+}
+
+/// Settings for a `/command --key=value` run: the overrides on top of the
+/// defaults, with the keys `current` locks kept at their current values.
+pub fn load_command_settings(
+    current: &Settings,
+    cli_overrides: &HashMap<String, String>,
+) -> Result<Settings, PrAgentError> {
+    let global = (!current.locked.keys.is_empty()).then(|| {
+        let values = Figment::from(Serialized::defaults(current));
+        std::iter::once("locked")
+            .chain(current.locked.keys.iter().map(String::as_str))
+            .filter_map(|key| Some((key, values.find_value(key).ok()?)))
+            .fold(Figment::new(), |pinned, (key, value)| {
+                pinned.merge(Serialized::default(key, value))
+            })
+    });
+    load_layers(cli_overrides, global, None, &[])
+}
+
+fn load_layers(
+    cli_overrides: &HashMap<String, String>,
+    global: Option<Figment>,
+    repo_settings_toml: Option<&str>,
+    repo_prompt_files: &[String],
 ) -> Result<Settings, PrAgentError> {
     // Layer 1: embedded defaults
-    let mut figment = Figment::new()
+    let mut defaults = Figment::new()
         .merge(Toml::string(CONFIGURATION_TOML))
         .merge(Toml::string(IGNORE_TOML))
+        .merge(Toml::string(GENERATED_CODE_IGNORE_TOML))
         .merge(Toml::string(LANGUAGE_EXTENSIONS_TOML))
         .merge(Toml::string(CUSTOM_LABELS_TOML))
+        .merge(Toml::string(MODEL_PRICES_TOML))
         // Prompt templates
         .merge(Toml::string(PR_REVIEWER_PROMPTS))
         .merge(Toml::string(PR_DESCRIPTION_PROMPTS))
+        .merge(Toml::string(PR_CUSTOM_LABELS_PROMPTS))
         .merge(Toml::string(PR_CODE_SUGGESTIONS_PROMPTS))
         .merge(Toml::string(PR_CODE_SUGGESTIONS_NOT_DECOUPLED))
         .merge(Toml::string(PR_CODE_SUGGESTIONS_REFLECT))
+        .merge(Toml::string(PR_BEST_PRACTICES_PROMPTS))
         .merge(Toml::string(PR_QUESTIONS_PROMPTS))
         .merge(Toml::string(PR_LINE_QUESTIONS_PROMPTS))
         .merge(Toml::string(PR_UPDATE_CHANGELOG_PROMPTS))
         .merge(Toml::string(PR_INFORMATION_FROM_USER))
+        .merge(Toml::string(PR_TEST_PROMPTS))
+        .merge(Toml::string(PR_QUICK_TAKE_PROMPTS))
+        .merge(Toml::string(PR_AI_METADATA_PROMPTS))
         .merge(Toml::string(PR_HELP_PROMPTS))
         .merge(Toml::string(PR_HELP_DOCS_PROMPTS))
         .merge(Toml::string(PR_HELP_DOCS_HEADINGS))
         .merge(Toml::string(PR_EVALUATE_PROMPT_RESPONSE));
 
     // Layer 2: secrets file (optional, from filesystem)
-    figment = figment.merge(Toml::file(".secrets.toml"));
+    let mut figment = Figment::new().merge(Toml::file(".secrets.toml"));
     figment = figment.merge(Toml::file("settings/.secrets.toml"));
 
     // Layer 3: global org-level .pr_agent.toml (from pr-agent-settings repo, optional)
-    if let Some(global_toml) = global_settings_toml {
-        figment = figment.merge(Toml::string(global_toml));
+    if let Some(global) = global {
+        figment = figment.merge(global);
     }
+    let locked: LockedConfig = figment.extract_inner("locked").unwrap_or_default();
 
     // Layer 4: repo-level .pr_agent.toml (provided as string from git provider)
+    let mut overrides = Figment::new();
     if let Some(repo_toml) = repo_settings_toml {
-        figment = figment.merge(Toml::string(repo_toml));
+        overrides = overrides.merge(Toml::string(repo_toml));
+    }
+    for prompt_toml in repo_prompt_files {
+        overrides = overrides.merge(Toml::string(prompt_toml));
     }
 
     // Layer 5: CLI argument overrides (--pr_reviewer.num_max_findings=5)
@@ -158,9 +237,15 @@ pub fn load_settings(
         // Figment doesn't have a direct "set key" method for arbitrary dotted keys,
         // so we build a TOML fragment: `[section]\nkey = value`
         if let Some(toml_fragment) = cli_override_to_toml(key, value) {
-            figment = figment.merge(Toml::string(&toml_fragment));
+            overrides = overrides.merge(Toml::string(&toml_fragment));
         }
     }
+    let mut overrides = overrides
+        .data()?
+        .remove(&Profile::Default)
+        .unwrap_or_default();
+    remove_locked(&mut overrides, "", &locked);
+    figment = figment.merge(Serialized::defaults(overrides));
 
     // Layer 6a: Well-known env var aliases (underscore-separated names)
     figment = figment.merge(
@@ -169,6 +254,11 @@ pub fn load_settings(
                 "OPENAI_API_KEY" | "OPENAI_KEY" => "openai.key".into(),
                 "GITHUB_TOKEN" | "GITHUB_USER_TOKEN" => "github.user_token".into(),
                 "ANTHROPIC_API_KEY" => "anthropic.key".into(),
+                "AWS_ACCESS_KEY_ID" => "aws.access_key_id".into(),
+                "AWS_SECRET_ACCESS_KEY" => "aws.secret_access_key".into(),
+                "AWS_SESSION_TOKEN" => "aws.session_token".into(),
+                "AWS_REGION" | "AWS_DEFAULT_REGION" => "aws.region_name".into(),
+                "JIRA_API_TOKEN" => "jira.jira_api_token".into(),
                 _ => key.into(),
             })
             .only(&[
@@ -177,6 +267,12 @@ pub fn load_settings(
                 "GITHUB_TOKEN",
                 "GITHUB_USER_TOKEN",
                 "ANTHROPIC_API_KEY",
+                "AWS_ACCESS_KEY_ID",
+                "AWS_SECRET_ACCESS_KEY",
+                "AWS_SESSION_TOKEN",
+                "AWS_REGION",
+                "AWS_DEFAULT_REGION",
+                "JIRA_API_TOKEN",
             ]),
     );
 
@@ -216,10 +312,42 @@ pub fn load_settings(
         figment = figment.merge(Toml::string(&fragment));
     }
 
-    let settings: Settings = figment.extract()?;
+    // The economy profile sits right above the defaults, so any explicit
+    // setting from layers 2-6 still overrides it.
+    let economy_mode = defaults
+        .clone()
+        .merge(figment.clone())
+        .extract_inner::<bool>("config.economy_mode")
+        .unwrap_or(false);
+    if economy_mode {
+        defaults = defaults.merge(Toml::string(ECONOMY_MODE_TOML));
+    }
+
+    let builtins = defaults.clone();
+    let mut settings: Settings = defaults.merge(figment).extract()?;
+    prompts::apply_overrides(&mut settings, &builtins);
     Ok(settings)
 }
 
+/// Drop the keys `locked` covers from a settings layer, at any depth under `prefix`.
+fn remove_locked(dict: &mut Dict, prefix: &str, locked: &LockedConfig) {
+    dict.retain(|key, value| {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        if locked.covers(&path) {
+            tracing::warn!(key = %path, "ignoring override of a setting locked by the org-level settings");
+            return false;
+        }
+        if let Value::Dict(_, inner) = value {
+            remove_locked(inner, &path, locked);
+        }
+        true
+    });
+}
+
 /// Encode a scalar value as a TOML literal (bool/int/float) or escaped string.
 fn encode_toml_scalar(value: &str) -> String {
     let is_literal = value == "true"
@@ -385,6 +513,103 @@ num_max_findings = 5
         assert_eq!(settings.pr_reviewer.num_max_findings, 99);
     }
 
+    #[test]
+    fn test_locked_keys_ignore_repo_and_cli_overrides() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let global_toml = r#"
+[config]
+model = "org-model"
+
+[locked]
+keys = ["config.model", "pr_code_suggestions"]
+"#;
+        let repo_toml = r#"
+[config]
+model = "repo-model"
+temperature = 0.5
+
+[pr_code_suggestions]
+num_code_suggestions_per_chunk = 9
+
+[locked]
+keys = []
+"#;
+        let mut cli = HashMap::new();
+        cli.insert("config.model".into(), "cli-model".into());
+        cli.insert("pr_reviewer.num_max_findings".into(), "8".into());
+
+        let settings = load_settings(&cli, Some(global_toml), Some(repo_toml))
+            .expect("should merge all layers");
+
+        assert_eq!(settings.config.model, "org-model");
+        let defaults = load_settings(&HashMap::new(), None, None).unwrap();
+        assert_eq!(
+            settings.pr_code_suggestions.num_code_suggestions_per_chunk,
+            defaults.pr_code_suggestions.num_code_suggestions_per_chunk
+        );
+        assert_eq!(
+            settings.locked.keys,
+            ["config.model", "pr_code_suggestions"]
+        );
+        // Unlocked keys still follow the usual precedence
+        assert!((settings.config.temperature - 0.5).abs() < f32::EPSILON);
+        assert_eq!(settings.pr_reviewer.num_max_findings, 8);
+    }
+
+    #[test]
+    fn test_command_settings_keep_locked_values() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let global_toml = r#"
+[config]
+model = "org-model"
+
+[locked]
+keys = ["config.model"]
+"#;
+        let current = load_settings(&HashMap::new(), Some(global_toml), None).unwrap();
+        let mut cli = HashMap::new();
+        cli.insert("config.model".into(), "cli-model".into());
+        cli.insert("locked.keys".into(), "[]".into());
+        cli.insert("pr_reviewer.num_max_findings".into(), "8".into());
+
+        let settings = load_command_settings(&current, &cli).unwrap();
+
+        assert_eq!(settings.config.model, "org-model");
+        assert_eq!(settings.locked.keys, ["config.model"]);
+        assert_eq!(settings.pr_reviewer.num_max_findings, 8);
+    }
+
+    #[test]
+    fn test_economy_mode_profile() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let repo_toml = r#"
+[config]
+model_weak = "gpt-4o-mini"
+
+[pr_code_suggestions]
+max_number_of_calls = 2
+"#;
+        let mut cli = HashMap::new();
+        cli.insert("config.economy_mode".into(), "true".into());
+        let settings = load_settings(&cli, None, Some(repo_toml)).expect("should load profile");
+
+        assert_eq!(settings.config.patch_extra_lines_before, 1);
+        assert_eq!(
+            settings.pr_code_suggestions.num_code_suggestions_per_chunk,
+            2
+        );
+        assert!(!settings.pr_description.enable_pr_diagram);
+        // Explicit settings win over the profile
+        assert_eq!(settings.pr_code_suggestions.max_number_of_calls, 2);
+        assert_eq!(settings.config.weak_model(), "gpt-4o-mini");
+
+        // Without the switch, the profile is not applied
+        let settings = load_settings(&HashMap::new(), None, Some(repo_toml)).unwrap();
+        assert_eq!(settings.config.patch_extra_lines_before, 5);
+        assert!(settings.pr_description.enable_pr_diagram);
+        assert_eq!(settings.config.weak_model(), settings.config.model);
+    }
+
     // All env var tests acquire ENV_LOCK. The `unsafe` blocks are required
     // because modifying env vars is inherently process-global.
 
@@ -519,6 +744,56 @@ num_max_findings = 5
         unsafe { std::env::remove_var("GITHUB.PRIVATE_KEY") };
     }
 
+    #[test]
+    fn test_repo_prompt_override_accepted() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let repo_toml = r#"
+[pr_review_prompt]
+system = "Review like a pirate."
+user = "{{ diff }}"
+"#;
+        let settings = load_settings(&HashMap::new(), None, Some(repo_toml)).unwrap();
+
+        assert_eq!(settings.pr_review_prompt.system, "Review like a pirate.");
+        let builtin = settings.pr_review_prompt.builtin.as_deref().unwrap();
+        assert!(builtin.system.contains("PR-Reviewer"));
+        // Untouched prompts keep no fallback
+        assert!(settings.pr_description_prompt.builtin.is_none());
+    }
+
+    #[test]
+    fn test_repo_prompt_override_missing_variable_rejected() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let repo_toml = r#"
+[pr_review_prompt]
+user = "Review {{ title }}"
+"#;
+        let settings = load_settings(&HashMap::new(), None, Some(repo_toml)).unwrap();
+
+        assert!(settings.pr_review_prompt.user.contains("{{ diff"));
+        assert!(settings.pr_review_prompt.builtin.is_none());
+    }
+
+    #[test]
+    fn test_repo_prompt_files_override_repo_settings() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let repo_toml = "[pr_questions_prompt]\nuser = \"{{ questions }} {{ diff }} (toml)\"\n";
+        let prompt_file = "[pr_questions_prompt]\nuser = \"{{ questions }} {{ diff }} (file)\"\n";
+        let settings = load_settings_with_prompts(
+            &HashMap::new(),
+            None,
+            Some(repo_toml),
+            &[prompt_file.to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            settings.pr_questions_prompt.user,
+            "{{ questions }} {{ diff }} (file)"
+        );
+        assert!(settings.pr_questions_prompt.builtin.is_some());
+    }
+
     #[test]
     fn test_cli_override_to_toml_types() {
         assert_eq!(
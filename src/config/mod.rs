@@ -1,6 +1,7 @@
 pub mod loader;
 pub mod prompts;
 pub mod types;
+pub mod validate;
 
 #[allow(unused_imports)]
 pub use loader::get_settings;
@@ -1,2 +1,180 @@
-// Prompt template types are defined in config/types.rs (PromptTemplate).
-// This module is reserved for prompt-specific helpers if needed.
+//! Repo overrides of the built-in prompt templates.
+//!
+//! A repository can replace any [`PromptTemplate`] by setting its section
+//! (e.g. `[pr_review_prompt]`) in `.pr_agent.toml`, or in a TOML file under
+//! `.pr_agent/prompts/`. An override is only accepted when it parses and still
+//! references the variables the tool can't work without; otherwise the
+//! built-in template is kept. Accepted overrides remember the built-in one so
+//! rendering can fall back to it.
+
+use std::collections::HashSet;
+
+use figment::Figment;
+use minijinja::Environment;
+
+use crate::config::types::{PromptTemplate, Settings};
+
+/// Repo directory whose `*.toml` files hold prompt overrides.
+pub const REPO_PROMPTS_DIR: &str = ".pr_agent/prompts/";
+
+/// Every prompt section, with the variables an override must reference.
+pub const PROMPT_SECTIONS: &[(&str, &[&str])] = &[
+    ("pr_review_prompt", &["diff"]),
+    ("pr_description_prompt", &["diff"]),
+    ("pr_description_merge_prompt", &["batch_summaries"]),
+    ("pr_custom_labels_prompt", &["diff"]),
+    ("pr_code_suggestions_prompt", &["diff_no_line_numbers"]),
+    (
+        "pr_code_suggestions_prompt_not_decoupled",
+        &["diff_no_line_numbers"],
+    ),
+    (
+        "pr_code_suggestions_reflect_prompt",
+        &["diff", "suggestion_str"],
+    ),
+    (
+        "pr_best_practices_prompt",
+        &["diff_no_line_numbers", "best_practices_content"],
+    ),
+    ("pr_questions_prompt", &["diff", "questions"]),
+    ("pr_line_questions_prompt", &["question", "full_hunk"]),
+    ("pr_update_changelog_prompt", &["diff"]),
+    ("pr_changelog_fragment_prompt", &["diff"]),
+    ("pr_information_from_user_prompt", &["diff"]),
+    ("pr_test_prompt", &["diff"]),
+    ("pr_quick_take_prompt", &["diff"]),
+    ("pr_ai_metadata_prompt", &["diff"]),
+    ("pr_large_patch_prompt", &["diff"]),
+    ("pr_help_prompts", &["question", "snippets"]),
+    ("pr_help_docs_prompts", &["question", "snippets"]),
+    ("pr_help_docs_headings_prompts", &["question", "snippets"]),
+    ("pr_evaluate_prompt_response", &["pr_task"]),
+];
+
+/// Whether `path` is a prompt override file in [`REPO_PROMPTS_DIR`].
+pub fn is_repo_prompt_file(path: &str) -> bool {
+    path.starts_with(REPO_PROMPTS_DIR) && path.ends_with(".toml")
+}
+
+/// The prompt template stored under `section`.
+pub fn prompt_mut<'a>(settings: &'a mut Settings, section: &str) -> Option<&'a mut PromptTemplate> {
+    Some(match section {
+        "pr_review_prompt" => &mut settings.pr_review_prompt,
+        "pr_description_prompt" => &mut settings.pr_description_prompt,
+        "pr_description_merge_prompt" => &mut settings.pr_description_merge_prompt,
+        "pr_custom_labels_prompt" => &mut settings.pr_custom_labels_prompt,
+        "pr_code_suggestions_prompt" => &mut settings.pr_code_suggestions_prompt,
+        "pr_code_suggestions_prompt_not_decoupled" => {
+            &mut settings.pr_code_suggestions_prompt_not_decoupled
+        }
+        "pr_code_suggestions_reflect_prompt" => &mut settings.pr_code_suggestions_reflect_prompt,
+        "pr_best_practices_prompt" => &mut settings.pr_best_practices_prompt,
+        "pr_questions_prompt" => &mut settings.pr_questions_prompt,
+        "pr_line_questions_prompt" => &mut settings.pr_line_questions_prompt,
+        "pr_update_changelog_prompt" => &mut settings.pr_update_changelog_prompt,
+        "pr_changelog_fragment_prompt" => &mut settings.pr_changelog_fragment_prompt,
+        "pr_information_from_user_prompt" => &mut settings.pr_information_from_user_prompt,
+        "pr_test_prompt" => &mut settings.pr_test_prompt,
+        "pr_quick_take_prompt" => &mut settings.pr_quick_take_prompt,
+        "pr_ai_metadata_prompt" => &mut settings.pr_ai_metadata_prompt,
+        "pr_large_patch_prompt" => &mut settings.pr_large_patch_prompt,
+        "pr_help_prompts" => &mut settings.pr_help_prompts,
+        "pr_help_docs_prompts" => &mut settings.pr_help_docs_prompts,
+        "pr_help_docs_headings_prompts" => &mut settings.pr_help_docs_headings_prompts,
+        "pr_evaluate_prompt_response" => &mut settings.pr_evaluate_prompt_response,
+        _ => return None,
+    })
+}
+
+/// Check an override: both templates must parse and, together, reference
+/// every variable in `required`.
+pub fn validate_override(template: &PromptTemplate, required: &[&str]) -> Result<(), String> {
+    let env = Environment::new();
+    let mut referenced = HashSet::new();
+    for (name, source) in [("system", &template.system), ("user", &template.user)] {
+        let tmpl = env
+            .template_from_str(source)
+            .map_err(|e| format!("{name} template does not parse: {e}"))?;
+        referenced.extend(tmpl.undeclared_variables(false));
+    }
+    let missing: Vec<&str> = required
+        .iter()
+        .copied()
+        .filter(|var| !referenced.contains(*var))
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "missing required variables: {}",
+            missing.join(", ")
+        ))
+    }
+}
+
+/// Compare each prompt in `settings` with the built-in one from `builtins`
+/// (the embedded defaults), keeping valid overrides and reverting invalid ones.
+pub fn apply_overrides(settings: &mut Settings, builtins: &Figment) {
+    for &(section, required) in PROMPT_SECTIONS {
+        let Ok(builtin) = builtins.extract_inner::<PromptTemplate>(section) else {
+            continue;
+        };
+        let Some(template) = prompt_mut(settings, section) else {
+            continue;
+        };
+        if template.system == builtin.system && template.user == builtin.user {
+            continue;
+        }
+        match validate_override(template, required) {
+            Ok(()) => {
+                tracing::info!(section, "using custom prompt template");
+                template.builtin = Some(Box::new(builtin));
+            }
+            Err(reason) => {
+                tracing::warn!(section, %reason, "ignoring invalid custom prompt template");
+                *template = builtin;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(system: &str, user: &str) -> PromptTemplate {
+        PromptTemplate {
+            system: system.into(),
+            user: user.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_override_requires_variables() {
+        assert!(validate_override(&template("Review.", "{{ diff }}"), &["diff"]).is_ok());
+        let err = validate_override(&template("Review.", "{{ title }}"), &["diff"]).unwrap_err();
+        assert_eq!(err, "missing required variables: diff");
+    }
+
+    #[test]
+    fn test_validate_override_rejects_syntax_errors() {
+        let err = validate_override(&template("{% if %}", "{{ diff }}"), &["diff"]).unwrap_err();
+        assert!(err.starts_with("system template does not parse"), "{err}");
+    }
+
+    #[test]
+    fn test_every_section_has_a_prompt() {
+        let mut settings = Settings::default();
+        for (section, _) in PROMPT_SECTIONS {
+            assert!(prompt_mut(&mut settings, section).is_some(), "{section}");
+        }
+    }
+
+    #[test]
+    fn test_is_repo_prompt_file() {
+        assert!(is_repo_prompt_file(".pr_agent/prompts/review.toml"));
+        assert!(!is_repo_prompt_file(".pr_agent/prompts/README.md"));
+        assert!(!is_repo_prompt_file("prompts/review.toml"));
+    }
+}
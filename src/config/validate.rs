@@ -0,0 +1,256 @@
+//! `pr-agent validate-config`: check a `.pr_agent.toml` before committing it.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use figment::Figment;
+use figment::providers::{Format, Toml};
+use minijinja::{Environment, Value};
+use regex::Regex;
+
+use crate::config::loader::load_settings;
+use crate::config::prompts::{PROMPT_SECTIONS, prompt_mut, validate_override};
+use crate::config::types::Settings;
+
+/// Sections keyed by user-chosen names (labels, models, frameworks).
+const FREE_FORM_SECTIONS: &[&str] = &[
+    "custom_labels",
+    "model_prices",
+    "generated_code",
+    "language_extension_map_org",
+];
+
+/// Settings holding regex lists, as `(section, key)`.
+const REGEX_LISTS: &[(&str, &str)] = &[
+    ("ignore", "regex"),
+    ("config", "ignore_pr_title"),
+    ("config", "ignore_pr_source_branches"),
+    ("config", "ignore_pr_target_branches"),
+];
+
+/// A problem found in a settings file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    /// Dotted key the problem is about; empty for the whole file.
+    pub key: String,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn new(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.key.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.key, self.message)
+        }
+    }
+}
+
+/// Check a settings file: TOML syntax, unknown keys, value types, regexes
+/// in ignore lists, and the prompt templates it ends up with.
+pub fn validate_config(source: &str) -> Vec<ConfigIssue> {
+    let table = match toml::from_str::<toml::Table>(source) {
+        Ok(table) => table,
+        Err(e) => return vec![ConfigIssue::new("", format!("invalid TOML: {e}"))],
+    };
+
+    let mut issues = unknown_keys(&table);
+    issues.extend(type_errors(source));
+    issues.extend(invalid_regexes(&table));
+    issues.extend(prompt_errors(&table));
+    issues
+}
+
+/// Keys that no setting matches (typos, removed options).
+fn unknown_keys(table: &toml::Table) -> Vec<ConfigIssue> {
+    let Ok(toml::Value::Table(known)) = toml::Value::try_from(Settings::default()) else {
+        return Vec::new();
+    };
+    let mut issues = Vec::new();
+    for (section, value) in table {
+        let Some(known_section) = known.get(section) else {
+            issues.push(ConfigIssue::new(section, "unknown section"));
+            continue;
+        };
+        if FREE_FORM_SECTIONS.contains(&section.as_str()) {
+            continue;
+        }
+        let (toml::Value::Table(fields), toml::Value::Table(known_fields)) = (value, known_section)
+        else {
+            continue;
+        };
+        for field in fields.keys() {
+            if !known_fields.contains_key(field) {
+                issues.push(ConfigIssue::new(
+                    format!("{section}.{field}"),
+                    "unknown key",
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// Values whose type doesn't match the setting (e.g. a string for a number).
+fn type_errors(source: &str) -> Vec<ConfigIssue> {
+    match Figment::from(Toml::string(source)).extract::<Settings>() {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors
+            .into_iter()
+            .map(|e| ConfigIssue::new(e.path.join("."), e.kind.to_string()))
+            .collect(),
+    }
+}
+
+/// Patterns in the regex lists that don't compile.
+fn invalid_regexes(table: &toml::Table) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    for &(section, key) in REGEX_LISTS {
+        let Some(patterns) = table
+            .get(section)
+            .and_then(|s| s.get(key))
+            .and_then(toml::Value::as_array)
+        else {
+            continue;
+        };
+        for pattern in patterns.iter().filter_map(toml::Value::as_str) {
+            if let Err(e) = Regex::new(pattern) {
+                issues.push(ConfigIssue::new(
+                    format!("{section}.{key}"),
+                    format!("invalid regex `{pattern}`: {e}"),
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// Overridden prompts missing required variables, and any prompt that fails
+/// to render with placeholder values.
+fn prompt_errors(table: &toml::Table) -> Vec<ConfigIssue> {
+    let Ok(mut settings) = load_settings(&HashMap::new(), None, None) else {
+        return Vec::new();
+    };
+    let mut issues = Vec::new();
+    for &(section, required) in PROMPT_SECTIONS {
+        let Some(template) = prompt_mut(&mut settings, section) else {
+            continue;
+        };
+        if let Some(overrides) = table.get(section) {
+            if let Some(system) = overrides.get("system").and_then(toml::Value::as_str) {
+                template.system = system.to_string();
+            }
+            if let Some(user) = overrides.get("user").and_then(toml::Value::as_str) {
+                template.user = user.to_string();
+            }
+            if let Err(reason) = validate_override(template, required) {
+                issues.push(ConfigIssue::new(section, reason));
+                continue;
+            }
+        }
+        for (name, source) in [("system", &template.system), ("user", &template.user)] {
+            if let Err(e) = render_with_placeholders(source) {
+                issues.push(ConfigIssue::new(format!("{section}.{name}"), e));
+            }
+        }
+    }
+    issues
+}
+
+/// Render a template with every variable it reads set to an empty string.
+fn render_with_placeholders(source: &str) -> Result<String, String> {
+    let env = Environment::new();
+    let tmpl = env
+        .template_from_str(source)
+        .map_err(|e| format!("template does not parse: {e}"))?;
+    let vars: HashMap<String, Value> = tmpl
+        .undeclared_variables(false)
+        .into_iter()
+        .map(|name| (name, Value::from("")))
+        .collect();
+    tmpl.render(vars)
+        .map_err(|e| format!("template does not render: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_config_has_no_issues() {
+        let source = r#"
+[pr_reviewer]
+num_max_findings = 5
+
+[custom_labels."Bug fix"]
+description = "Fixes a bug"
+"#;
+        assert_eq!(validate_config(source), vec![]);
+    }
+
+    #[test]
+    fn test_invalid_toml() {
+        let issues = validate_config("[pr_reviewer\nnum_max_findings = 5");
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.starts_with("invalid TOML"));
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let source = "[pr_reviewr]\nx = 1\n[pr_reviewer]\nnum_max_finding = 5\n";
+        let issues = validate_config(source);
+        assert!(issues.contains(&ConfigIssue::new("pr_reviewr", "unknown section")));
+        assert!(issues.contains(&ConfigIssue::new(
+            "pr_reviewer.num_max_finding",
+            "unknown key"
+        )));
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let issues = validate_config("[pr_reviewer]\nnum_max_findings = \"five\"\n");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key, "pr_reviewer.num_max_findings");
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let issues = validate_config("[ignore]\nregex = [\"^ok$\", \"(unclosed\"]\n");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key, "ignore.regex");
+        assert!(issues[0].message.contains("(unclosed"));
+    }
+
+    #[test]
+    fn test_prompt_template_errors() {
+        let source = r#"
+[pr_review_prompt]
+user = "{{ diff }} {% if title %}"
+
+[pr_description_prompt]
+user = "{{ title }}"
+"#;
+        let issues = validate_config(source);
+        assert_eq!(issues.len(), 2, "{issues:?}");
+        assert_eq!(issues[0].key, "pr_review_prompt");
+        assert!(issues[0].message.contains("does not parse"));
+        assert_eq!(
+            issues[1],
+            ConfigIssue::new("pr_description_prompt", "missing required variables: diff")
+        );
+    }
+
+    #[test]
+    fn test_builtin_prompts_render() {
+        assert_eq!(prompt_errors(&toml::Table::new()), vec![]);
+    }
+}
@@ -17,7 +17,6 @@ pub enum PrAgentError {
     #[error("Template rendering error: {0}")]
     Template(#[from] minijinja::Error),
 
-    #[allow(dead_code)]
     #[error("YAML parsing error: {0}")]
     YamlParse(String),
 
@@ -50,7 +49,34 @@ impl From<figment::Error> for PrAgentError {
     }
 }
 
+#[cfg(feature = "metrics-store")]
+impl From<sqlx::Error> for PrAgentError {
+    fn from(err: sqlx::Error) -> Self {
+        PrAgentError::Other(format!("metrics store: {err}"))
+    }
+}
+
 impl PrAgentError {
+    /// The kind of failure in a few words, for PR comments and other public
+    /// output. Unlike the `Display` text it never carries provider messages
+    /// or URLs; those stay in the logs.
+    pub fn public_summary(&self) -> &'static str {
+        match self {
+            PrAgentError::AiHandler(_) => "the AI model request failed",
+            PrAgentError::RateLimited { .. } => "the AI provider rate-limited the request",
+            PrAgentError::YamlParse(_) | PrAgentError::Json(_) => {
+                "the model's answer could not be parsed"
+            }
+            PrAgentError::GitProvider(_) => "a request to the git provider failed",
+            PrAgentError::Http(_) => "a network request failed",
+            PrAgentError::Config(_) | PrAgentError::Toml(_) => "the settings could not be loaded",
+            PrAgentError::Template(_) => "the prompt template could not be rendered",
+            PrAgentError::TokenBudget { .. } => "the PR does not fit the model's context window",
+            PrAgentError::Unsupported(_) => "this platform does not support the operation",
+            PrAgentError::Io(_) | PrAgentError::Other(_) => "an unexpected error occurred",
+        }
+    }
+
     #[allow(dead_code)]
     pub fn is_retryable(&self) -> bool {
         match self {
@@ -0,0 +1,144 @@
+//! Per-process cache of PR diffs (`config.diff_cache_max_entries`,
+//! `config.diff_cache_ttl`).
+//!
+//! Tools run back to back on one PR, like the `pr_commands` of a webhook
+//! event, each need its changed files. [`get_diff_files`] keeps them by PR
+//! URL, which names the repo and the PR, and head commit, so the diff is
+//! fetched once and a new push is always fetched fresh. Providers that
+//! report no PR URL or head SHA aren't cached.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::git::GitProvider;
+use crate::git::types::FilePatchInfo;
+
+static CACHE: LazyLock<Mutex<DiffCache>> = LazyLock::new(Default::default);
+
+/// PR URL and head SHA.
+type DiffKey = (String, String);
+
+#[derive(Debug, Default)]
+struct DiffCache {
+    entries: HashMap<DiffKey, (Instant, Vec<FilePatchInfo>)>,
+    /// Keys in insertion order, for eviction.
+    order: VecDeque<DiffKey>,
+}
+
+impl DiffCache {
+    fn get(&self, key: &DiffKey, ttl: Duration, now: Instant) -> Option<Vec<FilePatchInfo>> {
+        self.entries
+            .get(key)
+            .filter(|(fetched_at, _)| now.duration_since(*fetched_at) < ttl)
+            .map(|(_, files)| files.clone())
+    }
+
+    /// Store `files` for `key`, dropping the PR's diffs at older heads and
+    /// the oldest entries beyond `max_entries`.
+    fn insert(
+        &mut self,
+        key: DiffKey,
+        files: Vec<FilePatchInfo>,
+        now: Instant,
+        max_entries: usize,
+    ) {
+        self.entries
+            .retain(|(pr_url, sha), _| *pr_url != key.0 || *sha == key.1);
+        let entries = &self.entries;
+        self.order.retain(|k| entries.contains_key(k));
+        if self.entries.insert(key.clone(), (now, files)).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > max_entries {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// The PR's changed files, reused when they were fetched for the same head
+/// commit within `config.diff_cache_ttl` seconds.
+pub async fn get_diff_files(
+    provider: &dyn GitProvider,
+) -> Result<Vec<FilePatchInfo>, PrAgentError> {
+    let settings = get_settings();
+    let max_entries = settings.config.diff_cache_max_entries;
+    let ttl = Duration::from_secs(settings.config.diff_cache_ttl);
+    let pr_url = provider.get_pr_url();
+    if max_entries == 0 || ttl.is_zero() || pr_url.is_empty() {
+        return provider.get_diff_files().await;
+    }
+    let head_sha = match provider.get_pr_head_sha().await {
+        Ok(sha) if !sha.is_empty() => sha,
+        Ok(_) => return provider.get_diff_files().await,
+        Err(e) => {
+            tracing::debug!(error = %e, "no head SHA, not caching the PR diff");
+            return provider.get_diff_files().await;
+        }
+    };
+
+    let key = (pr_url.to_string(), head_sha);
+    if let Some(files) = CACHE.lock().unwrap().get(&key, ttl, Instant::now()) {
+        tracing::debug!(pr_url, head_sha = key.1, "reusing the cached PR diff");
+        return Ok(files);
+    }
+    let files = provider.get_diff_files().await?;
+    CACHE
+        .lock()
+        .unwrap()
+        .insert(key, files.clone(), Instant::now(), max_entries);
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(pr: &str, sha: &str) -> DiffKey {
+        (format!("https://github.com/o/r/pull/{pr}"), sha.to_string())
+    }
+
+    fn files(name: &str) -> Vec<FilePatchInfo> {
+        vec![FilePatchInfo::new(
+            String::new(),
+            String::new(),
+            "@@ -1 +1 @@\n-a\n+b".into(),
+            name.into(),
+        )]
+    }
+
+    #[test]
+    fn test_diff_cache_expires_after_ttl() {
+        let mut cache = DiffCache::default();
+        let now = Instant::now();
+        let ttl = Duration::from_secs(60);
+        cache.insert(key("1", "aaa"), files("a.rs"), now, 4);
+
+        let hit = cache.get(&key("1", "aaa"), ttl, now + Duration::from_secs(59));
+        assert_eq!(hit.unwrap()[0].filename, "a.rs");
+        assert!(cache.get(&key("1", "aaa"), ttl, now + ttl).is_none());
+        assert!(cache.get(&key("1", "bbb"), ttl, now).is_none());
+    }
+
+    #[test]
+    fn test_diff_cache_drops_old_heads_and_evicts_oldest() {
+        let mut cache = DiffCache::default();
+        let now = Instant::now();
+        let ttl = Duration::from_secs(60);
+        cache.insert(key("1", "aaa"), files("a.rs"), now, 2);
+        cache.insert(key("1", "bbb"), files("b.rs"), now, 2);
+        // A new push replaces the PR's diff
+        assert!(cache.get(&key("1", "aaa"), ttl, now).is_none());
+        assert_eq!(cache.order.len(), 1);
+
+        cache.insert(key("2", "ccc"), files("c.rs"), now, 2);
+        cache.insert(key("3", "ddd"), files("d.rs"), now, 2);
+        assert!(cache.get(&key("1", "bbb"), ttl, now).is_none());
+        assert!(cache.get(&key("2", "ccc"), ttl, now).is_some());
+        assert!(cache.get(&key("3", "ddd"), ttl, now).is_some());
+    }
+}
@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use async_trait::async_trait;
+
+use super::GitProvider;
+use super::types::*;
+use crate::error::PrAgentError;
+use crate::output::terminal;
+
+/// Provider wrapper for `config.dry_run`: reads go to the real provider,
+/// while everything that would change the PR is rendered to stdout (stderr
+/// with `--output json`) or appended to `config.dry_run_output` instead.
+///
+/// Temporary progress comments are dropped, since they never outlive the run.
+pub struct DryRunProvider {
+    inner: Arc<dyn GitProvider>,
+    /// File to append previews to; stdout when `None`.
+    output: Option<String>,
+    next_comment_id: AtomicU64,
+}
+
+impl DryRunProvider {
+    pub fn new(inner: Arc<dyn GitProvider>, output: &str) -> Self {
+        Self {
+            inner,
+            output: (!output.trim().is_empty()).then(|| output.trim().to_string()),
+            next_comment_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Write one would-be action: a header naming it, then its content.
+    fn emit(&self, action: &str, detail: &str, body: &str) {
+        let mut block = format!("──── [dry run] {action}");
+        if !detail.is_empty() {
+            let _ = write!(block, " ({detail})");
+        }
+        block.push_str(" ────\n");
+        if !body.is_empty() {
+            block.push_str(body.trim_end());
+            block.push('\n');
+        }
+        block.push('\n');
+
+        match &self.output {
+            Some(path) => {
+                let written = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .and_then(|mut f| f.write_all(block.as_bytes()));
+                if let Err(e) = written {
+                    tracing::warn!(path, error = %e, "failed to write dry-run output");
+                }
+            }
+            None if terminal::is_json() => eprint!("{block}"),
+            None => print!("{block}"),
+        }
+    }
+
+    fn new_comment_id(&self) -> CommentId {
+        let n = self.next_comment_id.fetch_add(1, Ordering::Relaxed);
+        CommentId(format!("dry-run-{n}"))
+    }
+}
+
+#[async_trait]
+impl GitProvider for DryRunProvider {
+    // ── Intercepted writes ────────────────────────────────────────────
+
+    async fn publish_description(&self, title: &str, body: &str) -> Result<(), PrAgentError> {
+        self.emit("publish_description", title, body);
+        Ok(())
+    }
+
+    async fn publish_comment(
+        &self,
+        text: &str,
+        is_temporary: bool,
+    ) -> Result<Option<CommentId>, PrAgentError> {
+        if is_temporary {
+            return Ok(None);
+        }
+        let id = self.new_comment_id();
+        self.emit("publish_comment", &id.0, text);
+        Ok(Some(id))
+    }
+
+    async fn publish_inline_comment(
+        &self,
+        body: &str,
+        file: &str,
+        line: &str,
+        _original_suggestion: Option<&str>,
+    ) -> Result<(), PrAgentError> {
+        self.emit("publish_inline_comment", &format!("{file}:{line}"), body);
+        Ok(())
+    }
+
+    async fn publish_inline_comments(
+        &self,
+        comments: &[InlineComment],
+    ) -> Result<(), PrAgentError> {
+        for c in comments {
+            let lines = match c.start_line {
+                Some(start) if start != c.line => format!("{start}-{}", c.line),
+                _ => c.line.to_string(),
+            };
+            self.emit(
+                "publish_inline_comment",
+                &format!("{}:{lines}", c.path),
+                &c.body,
+            );
+        }
+        Ok(())
+    }
+
+    async fn remove_initial_comment(&self) -> Result<(), PrAgentError> {
+        Ok(())
+    }
+
+    async fn remove_comment(&self, comment_id: &CommentId) -> Result<(), PrAgentError> {
+        self.emit("remove_comment", &comment_id.0, "");
+        Ok(())
+    }
+
+    async fn publish_code_suggestions(
+        &self,
+        suggestions: &[CodeSuggestion],
+    ) -> Result<Option<String>, PrAgentError> {
+        for s in suggestions {
+            let location = format!(
+                "{}:{}-{}",
+                s.relevant_file, s.relevant_lines_start, s.relevant_lines_end
+            );
+            let mut body = s.body.trim_end().to_string();
+            if !s.improved_code.is_empty() {
+                let _ = write!(
+                    body,
+                    "\n\n```suggestion\n{}\n```",
+                    s.improved_code.trim_end()
+                );
+            }
+            self.emit("publish_code_suggestion", &location, &body);
+        }
+        Ok(None)
+    }
+
+    async fn publish_labels(&self, labels: &[String]) -> Result<(), PrAgentError> {
+        self.emit("publish_labels", "", &labels.join(", "));
+        Ok(())
+    }
+
+    async fn remove_label(&self, label: &str) -> Result<(), PrAgentError> {
+        self.emit("remove_label", "", label);
+        Ok(())
+    }
+
+    async fn remove_reaction(
+        &self,
+        _comment_id: u64,
+        _reaction_id: u64,
+    ) -> Result<(), PrAgentError> {
+        Ok(())
+    }
+
+    async fn add_reaction(
+        &self,
+        _comment_id: u64,
+        _reaction: Reaction,
+    ) -> Result<Option<u64>, PrAgentError> {
+        Ok(None)
+    }
+
+    async fn upload_image(&self, name: &str, png: Vec<u8>) -> Result<String, PrAgentError> {
+        self.emit("upload_image", name, &format!("{} bytes", png.len()));
+        Ok(format!("dry-run://{name}"))
+    }
+
+    async fn publish_persistent_comment(
+        &self,
+        text: &str,
+        _initial_header: &str,
+        _update_header: &str,
+        name: &str,
+        _final_update_message: bool,
+    ) -> Result<(), PrAgentError> {
+        self.emit("publish_persistent_comment", name, text);
+        Ok(())
+    }
+
+    async fn edit_comment(&self, comment_id: &CommentId, body: &str) -> Result<(), PrAgentError> {
+        self.emit("edit_comment", &comment_id.0, body);
+        Ok(())
+    }
+
+    async fn reply_to_comment(&self, comment_id: u64, body: &str) -> Result<(), PrAgentError> {
+        self.emit("reply_to_comment", &comment_id.to_string(), body);
+        Ok(())
+    }
+
+    async fn create_pr_file(
+        &self,
+        file_path: &str,
+        contents: &[u8],
+        message: &str,
+    ) -> Result<bool, PrAgentError> {
+        self.emit(
+            "create_pr_file",
+            &format!("{file_path}: {message}"),
+            &String::from_utf8_lossy(contents),
+        );
+        Ok(true)
+    }
+
+    async fn append_to_repo_file(
+        &self,
+        file_path: &str,
+        branch: &str,
+        text: &str,
+        message: &str,
+    ) -> Result<(), PrAgentError> {
+        self.emit(
+            "append_to_repo_file",
+            &format!("{file_path} on {branch}: {message}"),
+            text,
+        );
+        Ok(())
+    }
+
+    async fn auto_approve(&self) -> Result<bool, PrAgentError> {
+        self.emit("auto_approve", "", "");
+        Ok(false)
+    }
+
+    async fn publish_check_run(&self, check: &CheckRunReport) -> Result<(), PrAgentError> {
+        let outcome = if check.success { "success" } else { "failure" };
+        self.emit(
+            "publish_check_run",
+            &format!("{}: {outcome}", check.name),
+            &check.summary,
+        );
+        Ok(())
+    }
+
+    // ── Delegated reads ───────────────────────────────────────────────
+
+    async fn get_diff_files(&self) -> Result<Vec<FilePatchInfo>, PrAgentError> {
+        self.inner.get_diff_files().await
+    }
+
+    async fn get_files(&self) -> Result<Vec<String>, PrAgentError> {
+        self.inner.get_files().await
+    }
+
+    async fn get_languages(&self) -> Result<HashMap<String, u64>, PrAgentError> {
+        self.inner.get_languages().await
+    }
+
+    async fn get_pr_branch(&self) -> Result<String, PrAgentError> {
+        self.inner.get_pr_branch().await
+    }
+
+    async fn get_pr_base_branch(&self) -> Result<String, PrAgentError> {
+        self.inner.get_pr_base_branch().await
+    }
+
+    async fn get_user_id(&self) -> Result<String, PrAgentError> {
+        self.inner.get_user_id().await
+    }
+
+    async fn get_pr_description_full(&self) -> Result<(String, String), PrAgentError> {
+        self.inner.get_pr_description_full().await
+    }
+
+    async fn get_pr_labels(&self) -> Result<Vec<String>, PrAgentError> {
+        self.inner.get_pr_labels().await
+    }
+
+    async fn get_commit_messages(&self) -> Result<String, PrAgentError> {
+        self.inner.get_commit_messages().await
+    }
+
+    async fn get_repo_settings(&self) -> Result<Option<String>, PrAgentError> {
+        self.inner.get_repo_settings().await
+    }
+
+    async fn get_global_settings(&self) -> Result<Option<String>, PrAgentError> {
+        self.inner.get_global_settings().await
+    }
+
+    async fn get_issue_comments(&self) -> Result<Vec<IssueComment>, PrAgentError> {
+        self.inner.get_issue_comments().await
+    }
+
+    fn get_pr_url(&self) -> &str {
+        self.inner.get_pr_url()
+    }
+
+    fn is_supported(&self, capability: Capability) -> bool {
+        self.inner.is_supported(capability)
+    }
+
+    fn reaction_name(&self, reaction: Reaction) -> Option<&'static str> {
+        self.inner.reaction_name(reaction)
+    }
+
+    async fn get_latest_commit_url(&self) -> Result<String, PrAgentError> {
+        self.inner.get_latest_commit_url().await
+    }
+
+    async fn get_pr_head_sha(&self) -> Result<String, PrAgentError> {
+        self.inner.get_pr_head_sha().await
+    }
+
+    async fn get_head_file_bytes(&self, path: &str) -> Result<Vec<u8>, PrAgentError> {
+        self.inner.get_head_file_bytes(path).await
+    }
+
+    async fn get_pr_commits(&self) -> Result<Vec<CommitInfo>, PrAgentError> {
+        self.inner.get_pr_commits().await
+    }
+
+    async fn get_diff_files_between(
+        &self,
+        base_sha: &str,
+        head_sha: &str,
+    ) -> Result<Vec<FilePatchInfo>, PrAgentError> {
+        self.inner.get_diff_files_between(base_sha, head_sha).await
+    }
+
+    async fn get_review_thread_comments(
+        &self,
+        comment_id: u64,
+    ) -> Result<Vec<IssueComment>, PrAgentError> {
+        self.inner.get_review_thread_comments(comment_id).await
+    }
+
+    async fn get_review_comments(&self) -> Result<Vec<ReviewComment>, PrAgentError> {
+        self.inner.get_review_comments().await
+    }
+
+    fn get_git_repo_url(&self) -> String {
+        self.inner.get_git_repo_url()
+    }
+
+    fn get_line_link(&self, file: &str, line_start: i32, line_end: Option<i32>) -> String {
+        self.inner.get_line_link(file, line_start, line_end)
+    }
+
+    fn get_best_practices_link(&self, line: usize) -> String {
+        self.inner.get_best_practices_link(line)
+    }
+
+    async fn get_num_of_files(&self) -> Result<usize, PrAgentError> {
+        self.inner.get_num_of_files().await
+    }
+
+    fn get_pr_id(&self) -> &str {
+        self.inner.get_pr_id()
+    }
+
+    fn get_pr_number(&self) -> Option<u64> {
+        self.inner.get_pr_number()
+    }
+
+    async fn get_best_practices(&self) -> Result<String, PrAgentError> {
+        self.inner.get_best_practices().await
+    }
+
+    async fn get_repo_file(&self, path: &str) -> Result<Option<String>, PrAgentError> {
+        self.inner.get_repo_file(path).await
+    }
+
+    async fn get_repo_tree(&self) -> Result<RepoTree, PrAgentError> {
+        self.inner.get_repo_tree().await
+    }
+
+    async fn search_code(&self, query: &str) -> Result<Vec<String>, PrAgentError> {
+        self.inner.search_code(query).await
+    }
+
+    async fn get_repo_metadata(&self) -> Result<String, PrAgentError> {
+        self.inner.get_repo_metadata().await
+    }
+
+    fn repo_owner_and_name(&self) -> (String, String) {
+        self.inner.repo_owner_and_name()
+    }
+
+    async fn get_issue_body(&self, issue_number: u64) -> Result<(String, String), PrAgentError> {
+        self.inner.get_issue_body(issue_number).await
+    }
+
+    async fn get_planning_context(&self) -> Result<PlanningContext, PrAgentError> {
+        self.inner.get_planning_context().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_git::MockGitProvider;
+
+    fn temp_output(name: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("pr-agent-dry-run-{name}-{}.md", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path.display().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_rendered_not_published() {
+        let mock = Arc::new(MockGitProvider::new().with_pr_description("Title", "Body"));
+        let output = temp_output("writes");
+        let provider = DryRunProvider::new(mock.clone(), &output);
+
+        // Reads still reach the real provider.
+        let (title, _) = provider.get_pr_description_full().await.unwrap();
+        assert_eq!(title, "Title");
+
+        assert!(
+            provider
+                .publish_comment("Preparing…", true)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let id = provider
+            .publish_comment("## Review", false)
+            .await
+            .unwrap()
+            .unwrap();
+        provider.edit_comment(&id, "## Review v2").await.unwrap();
+        provider
+            .publish_description("New title", "New body")
+            .await
+            .unwrap();
+        provider.publish_labels(&["Bug fix".into()]).await.unwrap();
+        provider
+            .publish_inline_comments(&[InlineComment {
+                body: "Check bounds".into(),
+                path: "src/lib.rs".into(),
+                line: 12,
+                start_line: Some(10),
+                side: "RIGHT".into(),
+            }])
+            .await
+            .unwrap();
+
+        let calls = mock.get_calls();
+        assert!(calls.comments.is_empty());
+        assert!(calls.descriptions.is_empty());
+        drop(calls);
+
+        let rendered = std::fs::read_to_string(&output).unwrap();
+        assert!(!rendered.contains("Preparing"));
+        assert!(rendered.contains("──── [dry run] publish_comment (dry-run-1) ────\n## Review\n"));
+        assert!(rendered.contains("[dry run] edit_comment (dry-run-1)"));
+        assert!(rendered.contains("[dry run] publish_description (New title) ────\nNew body\n"));
+        assert!(rendered.contains("Bug fix"));
+        assert!(rendered.contains("[dry run] publish_inline_comment (src/lib.rs:10-12)"));
+        let _ = std::fs::remove_file(&output);
+    }
+}
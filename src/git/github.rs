@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::fmt::Write;
+use std::sync::{LazyLock, Mutex};
 
 use async_trait::async_trait;
 use base64::Engine;
@@ -8,15 +9,44 @@ use reqwest::Client;
 use serde::Serialize;
 use serde_json::json;
 
-use super::GitProvider;
 use super::types::*;
 use super::url_parser::{ParsedPrUrl, parse_pr_url};
+use super::{BEST_PRACTICES_FILE, GitProvider};
 use crate::config::loader::get_settings;
 use crate::error::PrAgentError;
+use crate::output::markdown::{split_comment, truncate_comment};
+use crate::util::http_client_builder;
 
 /// Maximum characters in a single comment (GitHub limit ~65536).
 const MAX_COMMENT_CHARS: usize = 65000;
 
+/// Seconds to wait after a secondary rate limit that names no retry time.
+const SECONDARY_RATE_LIMIT_WAIT: u64 = 60;
+
+/// Unix time per token before which requests wait, set when a response
+/// reports the primary rate limit as used up.
+static RATE_LIMIT_RESETS: LazyLock<Mutex<HashMap<String, u64>>> = LazyLock::new(Default::default);
+
+/// Files read from wiki pages and the org `pr-agent-settings` repo, by URL,
+/// with the Unix time they were fetched. `None` records a missing file.
+type SharedFiles = HashMap<String, (u64, Option<String>)>;
+
+static SHARED_FILES: LazyLock<Mutex<SharedFiles>> = LazyLock::new(Default::default);
+
+/// Org repo holding settings shared by all its repositories.
+const GLOBAL_SETTINGS_REPO: &str = "pr-agent-settings";
+
+/// Wiki page file holding `.pr_agent.toml` settings.
+const WIKI_SETTINGS_FILE: &str = ".pr_agent.toml.md";
+
+/// Where the best practices were read from, for links to their rules.
+#[derive(Debug, Clone)]
+struct BestPracticesSource {
+    url: String,
+    /// Whether `#L<n>` anchors work (not on wiki pages).
+    line_anchors: bool,
+}
+
 /// JWT claims for GitHub App authentication.
 #[derive(Debug, Serialize)]
 struct GithubAppClaims {
@@ -37,6 +67,8 @@ pub struct GithubProvider {
     parsed: ParsedPrUrl,
     /// Full repo name "owner/repo".
     repo_full: String,
+    /// Set once `get_best_practices` found them.
+    best_practices_source: Mutex<Option<BestPracticesSource>>,
 }
 
 impl GithubProvider {
@@ -49,7 +81,7 @@ impl GithubProvider {
 
         let base_url = settings.github.base_url.clone();
         let timeout = std::time::Duration::from_secs(settings.config.ai_timeout as u64);
-        let client = Client::builder()
+        let client = http_client_builder(&settings.http, &settings.http.git_headers)?
             .timeout(timeout)
             .build()
             .map_err(|e| PrAgentError::Other(format!("failed to build HTTP client: {e}")))?;
@@ -74,13 +106,72 @@ impl GithubProvider {
             token,
             parsed,
             repo_full,
+            best_practices_source: Mutex::new(None),
+        })
+    }
+
+    /// Web URL of the GitHub instance, for links.
+    fn web_base(&self) -> String {
+        self.base_url
+            .replace("api.github.com", "github.com")
+            .replace("/api/v3", "")
+    }
+
+    /// Base URL for raw file content, which serves wiki pages.
+    fn raw_base(&self) -> String {
+        if self.base_url.contains("api.github.com") {
+            "https://raw.githubusercontent.com".into()
+        } else {
+            format!("{}/raw", self.web_base().trim_end_matches('/'))
+        }
+    }
+
+    /// Read a page of the repo's wiki, by its file name (e.g.
+    /// `best_practices.md` for the `best_practices` page). Wikis have no API,
+    /// so this reads the raw file.
+    async fn get_wiki_file(&self, file: &str) -> Option<String> {
+        let url = format!("{}/wiki/{}/{file}", self.raw_base(), self.repo_full);
+        cached_shared_file(&url, async {
+            let resp = self
+                .api_request_with_retry_url(reqwest::Method::GET, &url, None)
+                .await?;
+            if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            let resp = Self::check_response(resp, "GET").await?;
+            Ok(Some(resp.text().await.map_err(PrAgentError::Http)?))
+        })
+        .await
+    }
+
+    /// Read a file from the org [`GLOBAL_SETTINGS_REPO`].
+    async fn get_global_file(&self, path: &str) -> Option<String> {
+        let global_repo = format!("{}/{GLOBAL_SETTINGS_REPO}", self.parsed.owner);
+        let url = format!(
+            "{}/repos/{global_repo}/contents/{path}",
+            self.base_url.trim_end_matches('/')
+        );
+        cached_shared_file(&url, async {
+            let resp = self
+                .api_request_with_retry_url(reqwest::Method::GET, &url, None)
+                .await?;
+            if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            let resp = Self::check_response(resp, "GET").await?;
+            Ok(Some(decode_contents(
+                &resp.json().await.map_err(PrAgentError::Http)?,
+            )))
         })
+        .await
     }
 
-    /// Send a GitHub API request with automatic retry on rate limits (429).
+    /// Send a GitHub API request with automatic retry on rate limits.
     ///
-    /// Retries up to `ratelimit_retries` times with exponential backoff,
-    /// respecting the `Retry-After` header when present.
+    /// Retries up to `ratelimit_retries` times on a 429 or a 403 from the
+    /// primary or secondary rate limit, waiting for `Retry-After`, the
+    /// `x-ratelimit-reset` time, or an exponential backoff. Waits longer
+    /// than `ratelimit_max_wait` fail with `RateLimited` instead.
     async fn api_request_with_retry(
         &self,
         method: reqwest::Method,
@@ -92,6 +183,7 @@ impl GithubProvider {
     }
 
     /// Same as `api_request_with_retry` but accepts an absolute URL (for pagination).
+    #[tracing::instrument(name = "github_request", skip_all, fields(%method, url = %url))]
     async fn api_request_with_retry_url(
         &self,
         method: reqwest::Method,
@@ -100,8 +192,24 @@ impl GithubProvider {
     ) -> Result<reqwest::Response, PrAgentError> {
         let settings = get_settings();
         let max_retries = settings.github.ratelimit_retries;
+        let max_wait = settings.github.ratelimit_max_wait;
 
         for attempt in 0..=max_retries {
+            // Don't spend the next request when the last one used up the limit.
+            if let Some(wait) = rate_limit_pause(&self.token, now_secs()) {
+                if wait > max_wait {
+                    return Err(PrAgentError::RateLimited {
+                        retry_after_secs: wait,
+                    });
+                }
+                tracing::warn!(
+                    wait_secs = wait,
+                    url,
+                    "GitHub API rate limit used up, pausing"
+                );
+                tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+            }
+
             let mut req = self
                 .client
                 .request(method.clone(), url)
@@ -114,32 +222,49 @@ impl GithubProvider {
             }
 
             let resp = req.send().await.map_err(PrAgentError::Http)?;
-
-            if resp.status().as_u16() == 429 {
-                let retry_after = resp
-                    .headers()
-                    .get("retry-after")
-                    .and_then(|v| v.to_str().ok())
-                    .and_then(|s| s.parse::<u64>().ok())
-                    .unwrap_or(2u64.pow(attempt + 1));
-
-                if attempt < max_retries {
-                    tracing::warn!(
-                        attempt = attempt + 1,
-                        max = max_retries,
-                        retry_after_secs = retry_after,
-                        url,
-                        "GitHub API rate limited, retrying"
-                    );
-                    tokio::time::sleep(std::time::Duration::from_secs(retry_after)).await;
-                    continue;
-                }
-                return Err(PrAgentError::RateLimited {
-                    retry_after_secs: retry_after,
-                });
+            let status = resp.status();
+            let now = now_secs();
+            let retry_after = retry_after_secs(resp.headers());
+            let reset_wait = exhausted_limit_wait(resp.headers(), now);
+            if let (true, Some(wait)) = (status.is_success(), reset_wait) {
+                pause_until(&self.token, now + wait);
             }
 
-            return Ok(resp);
+            let backoff = 2u64.pow(attempt + 1);
+            let wait = match status.as_u16() {
+                429 => retry_after.unwrap_or(backoff),
+                403 => match retry_after.or(reset_wait) {
+                    Some(wait) => wait,
+                    None => {
+                        let body = resp.text().await.unwrap_or_default();
+                        if !is_rate_limit_message(&body) {
+                            return Err(PrAgentError::GitProvider(format!(
+                                "GitHub API {method} {status}: {body}"
+                            )));
+                        }
+                        // GitHub asks for at least a minute, then exponential backoff.
+                        SECONDARY_RATE_LIMIT_WAIT << attempt
+                    }
+                },
+                _ => return Ok(resp),
+            };
+
+            if attempt < max_retries && wait <= max_wait {
+                tracing::warn!(
+                    attempt = attempt + 1,
+                    max = max_retries,
+                    retry_after_secs = wait,
+                    status = status.as_u16(),
+                    url,
+                    "GitHub API rate limited, retrying"
+                );
+                crate::metrics::record_github_retry();
+                tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+                continue;
+            }
+            return Err(PrAgentError::RateLimited {
+                retry_after_secs: wait,
+            });
         }
 
         Err(PrAgentError::GitProvider(
@@ -230,6 +355,19 @@ impl GithubProvider {
         resp.json().await.map_err(PrAgentError::Http)
     }
 
+    /// Make an authenticated PUT request.
+    async fn api_put(
+        &self,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, PrAgentError> {
+        let resp = self
+            .api_request_with_retry(reqwest::Method::PUT, path, Some(body))
+            .await?;
+        let resp = Self::check_response(resp, "PUT").await?;
+        resp.json().await.map_err(PrAgentError::Http)
+    }
+
     /// Make an authenticated DELETE request.
     async fn api_delete(&self, path: &str) -> Result<(), PrAgentError> {
         let resp = self
@@ -239,62 +377,152 @@ impl GithubProvider {
         Ok(())
     }
 
-    /// Get file contents from the repo at a specific ref.
-    async fn get_file_content(&self, path: &str, git_ref: &str) -> Result<String, PrAgentError> {
-        self.get_file_content_from_repo(&self.repo_full, path, git_ref)
+    /// The contents API entry of `file_path` in `repo` on `branch` (the
+    /// default branch when empty), or `None` when the file doesn't exist.
+    async fn get_contents_entry(
+        &self,
+        repo: &str,
+        file_path: &str,
+        branch: &str,
+    ) -> Result<Option<serde_json::Value>, PrAgentError> {
+        let mut path = format!("repos/{repo}/contents/{}", encode_path(file_path));
+        if !branch.is_empty() {
+            let branch: String = url::form_urlencoded::byte_serialize(branch.as_bytes()).collect();
+            path.push_str(&format!("?ref={branch}"));
+        }
+        let resp = self
+            .api_request_with_retry(reqwest::Method::GET, &path, None)
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let file = Self::check_response(resp, "GET")
+            .await?
+            .json()
             .await
+            .map_err(PrAgentError::Http)?;
+        Ok(Some(file))
     }
 
-    /// Get file contents from an arbitrary repo at a specific ref.
-    ///
-    /// Like `get_file_content()` but allows specifying a different
-    /// `repo_full` (e.g. "org/pr-agent-settings" instead of the PR's repo).
-    async fn get_file_content_from_repo(
+    /// Commit `contents` to `file_path` in `repo` on `branch`. `sha` is the
+    /// blob being replaced; `None` creates the file.
+    async fn put_contents(
         &self,
-        repo_full: &str,
-        path: &str,
-        git_ref: &str,
-    ) -> Result<String, PrAgentError> {
-        let api_path = format!("repos/{}/contents/{}?ref={}", repo_full, path, git_ref);
+        repo: &str,
+        file_path: &str,
+        branch: &str,
+        contents: &[u8],
+        message: &str,
+        sha: Option<&str>,
+    ) -> Result<(), PrAgentError> {
+        let mut body = json!({
+            "message": message,
+            "content": base64::engine::general_purpose::STANDARD.encode(contents),
+        });
+        if let Some(sha) = sha {
+            body["sha"] = json!(sha);
+        }
+        if !branch.is_empty() {
+            body["branch"] = json!(branch);
+        }
+        self.api_put(
+            &format!("repos/{repo}/contents/{}", encode_path(file_path)),
+            &body,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Get file contents from the repo at a specific ref.
+    async fn get_file_content(&self, path: &str, git_ref: &str) -> Result<String, PrAgentError> {
+        let api_path = format!("repos/{}/contents/{}?ref={}", self.repo_full, path, git_ref);
         let resp = self.api_get(&api_path).await?;
+        Ok(decode_contents(&resp))
+    }
 
-        let content = resp["content"]
+    /// Login of the PR's author.
+    pub async fn get_pr_author(&self) -> Result<String, PrAgentError> {
+        let pr_path = format!("repos/{}/pulls/{}", self.repo_full, self.parsed.pr_number);
+        let pr_data = self.api_get(&pr_path).await?;
+        Ok(pr_data["user"]["login"]
             .as_str()
             .unwrap_or_default()
-            .replace('\n', "");
-        let encoding = resp["encoding"].as_str().unwrap_or("");
-
-        if encoding == "base64" {
-            let decoded = base64::engine::general_purpose::STANDARD
-                .decode(&content)
-                .unwrap_or_default();
-            Ok(String::from_utf8_lossy(&decoded).into_owned())
-        } else {
-            Ok(content)
+            .to_string())
+    }
+}
+
+/// File content from a contents API response.
+fn decode_contents(resp: &serde_json::Value) -> String {
+    String::from_utf8_lossy(&decode_contents_bytes(resp)).into_owned()
+}
+
+/// Raw file bytes from a contents API response. Empty for files over the
+/// API's 1 MB limit, which come without content.
+fn decode_contents_bytes(resp: &serde_json::Value) -> Vec<u8> {
+    let content = resp["content"]
+        .as_str()
+        .unwrap_or_default()
+        .replace('\n', "");
+    let encoding = resp["encoding"].as_str().unwrap_or("");
+
+    if encoding == "base64" {
+        base64::engine::general_purpose::STANDARD
+            .decode(&content)
+            .unwrap_or_default()
+    } else {
+        content.into_bytes()
+    }
+}
+
+/// `fetch` the shared file at `url`, reusing an earlier result for
+/// `github.shared_files_cache_ttl` seconds. Empty files count as missing;
+/// failures are logged and not cached.
+async fn cached_shared_file(
+    url: &str,
+    fetch: impl Future<Output = Result<Option<String>, PrAgentError>>,
+) -> Option<String> {
+    let ttl = get_settings().github.shared_files_cache_ttl;
+    let now = now_secs();
+    if let Some((fetched_at, content)) = SHARED_FILES.lock().unwrap().get(url)
+        && now < fetched_at + ttl
+    {
+        return content.clone();
+    }
+    match fetch.await {
+        Ok(content) => {
+            let content = content.filter(|c| !c.trim().is_empty());
+            if ttl > 0 {
+                SHARED_FILES
+                    .lock()
+                    .unwrap()
+                    .insert(url.to_string(), (now, content.clone()));
+            }
+            content
+        }
+        Err(e) => {
+            tracing::warn!(url, error = %e, "failed to read shared settings file");
+            None
         }
     }
 }
 
-/// Generate a GitHub App JWT and exchange it for an installation access token.
-///
-/// Flow:
-/// 1. Build RS256 JWT with iss=app_id, iat=now-60s, exp=now+10min
-/// 2. GET /app/installations → find installation matching the repo owner
-/// 3. POST /app/installations/{id}/access_tokens → return the token
-async fn get_app_installation_token(
-    client: &Client,
-    base_url: &str,
-    app_id: u64,
-    private_key_pem: &str,
-    owner: &str,
-) -> Result<String, PrAgentError> {
+/// The settings in a wiki page: the first ```toml block, or the whole page.
+fn toml_from_wiki_page(page: &str) -> String {
+    page.split_once("```toml")
+        .and_then(|(_, rest)| rest.split_once("```"))
+        .map_or(page, |(block, _)| block)
+        .trim()
+        .to_string()
+}
+
+/// Build the RS256 JWT a GitHub App authenticates with (iss=app_id,
+/// iat=now-60s, exp=now+10min).
+pub fn create_app_jwt(app_id: u64, private_key_pem: &str) -> Result<String, PrAgentError> {
     if app_id == 0 || private_key_pem.is_empty() {
         return Err(PrAgentError::Other(
             "GitHub App auth requires app_id and private_key".into(),
         ));
     }
-
-    // 1. Generate JWT
     let now = chrono::Utc::now().timestamp();
     let claims = GithubAppClaims {
         iat: now - 60,
@@ -305,8 +533,25 @@ async fn get_app_installation_token(
     let key = EncodingKey::from_rsa_pem(private_key_pem.as_bytes()).map_err(|_| {
         PrAgentError::Other("invalid GitHub App private key: failed to parse RSA PEM".into())
     })?;
-    let jwt = encode(&header, &claims, &key)
-        .map_err(|e| PrAgentError::Other(format!("failed to encode JWT: {e}")))?;
+    encode(&header, &claims, &key)
+        .map_err(|e| PrAgentError::Other(format!("failed to encode JWT: {e}")))
+}
+
+/// Generate a GitHub App JWT and exchange it for an installation access token.
+///
+/// Flow:
+/// 1. Build RS256 JWT with iss=app_id, iat=now-60s, exp=now+10min
+/// 2. GET /app/installations → find installation matching the repo owner
+/// 3. POST /app/installations/{id}/access_tokens → return the token
+async fn get_app_installation_token(
+    client: &Client,
+    base_url: &str,
+    app_id: u64,
+    private_key_pem: &str,
+    owner: &str,
+) -> Result<String, PrAgentError> {
+    // 1. Generate JWT
+    let jwt = create_app_jwt(app_id, private_key_pem)?;
 
     let api_base = base_url.trim_end_matches('/');
 
@@ -388,15 +633,16 @@ impl GitProvider for GithubProvider {
         let pr_path = format!("repos/{}/pulls/{}", self.repo_full, self.parsed.pr_number);
         let pr_data = self.api_get(&pr_path).await?;
 
-        let base_sha = pr_data["base"]["sha"]
-            .as_str()
-            .unwrap_or_default()
-            .to_string();
-        let head_sha = pr_data["head"]["sha"]
-            .as_str()
-            .unwrap_or_default()
-            .to_string();
+        let base_sha = pr_data["base"]["sha"].as_str().unwrap_or_default();
+        let head_sha = pr_data["head"]["sha"].as_str().unwrap_or_default();
+        self.get_diff_files_between(base_sha, head_sha).await
+    }
 
+    async fn get_diff_files_between(
+        &self,
+        base_sha: &str,
+        head_sha: &str,
+    ) -> Result<Vec<FilePatchInfo>, PrAgentError> {
         let compare_path = format!(
             "repos/{}/compare/{}...{}",
             self.repo_full, base_sha, head_sha
@@ -432,7 +678,7 @@ impl GitProvider for GithubProvider {
                 } else {
                     &filename
                 };
-                self.get_file_content(ref_name, &base_sha)
+                self.get_file_content(ref_name, base_sha)
                     .await
                     .unwrap_or_default()
             } else {
@@ -440,7 +686,7 @@ impl GitProvider for GithubProvider {
             };
 
             let head_file = if edit_type != EditType::Deleted {
-                self.get_file_content(&filename, &head_sha)
+                self.get_file_content(&filename, head_sha)
                     .await
                     .unwrap_or_default()
             } else {
@@ -522,23 +768,19 @@ impl GitProvider for GithubProvider {
         text: &str,
         _is_temporary: bool,
     ) -> Result<Option<CommentId>, PrAgentError> {
-        let truncated = if text.len() > MAX_COMMENT_CHARS {
-            // Find the largest char boundary at or before MAX_COMMENT_CHARS
-            let mut end = MAX_COMMENT_CHARS;
-            while end > 0 && !text.is_char_boundary(end) {
-                end -= 1;
-            }
-            &text[..end]
-        } else {
-            text
-        };
         let path = format!(
             "repos/{}/issues/{}/comments",
             self.repo_full, self.parsed.pr_number
         );
-        let resp = self.api_post(&path, &json!({"body": truncated})).await?;
-        let id = resp["id"].as_u64().map(|id| CommentId(id.to_string()));
-        Ok(id)
+        // Oversized output goes out as several comments; the first one's id is returned.
+        let mut first_id = None;
+        for part in split_comment(text, MAX_COMMENT_CHARS) {
+            let resp = self.api_post(&path, &json!({"body": part})).await?;
+            if first_id.is_none() {
+                first_id = resp["id"].as_u64().map(|id| CommentId(id.to_string()));
+            }
+        }
+        Ok(first_id)
     }
 
     async fn publish_inline_comment(
@@ -648,9 +890,9 @@ impl GitProvider for GithubProvider {
     async fn publish_code_suggestions(
         &self,
         suggestions: &[CodeSuggestion],
-    ) -> Result<bool, PrAgentError> {
+    ) -> Result<Option<String>, PrAgentError> {
         if suggestions.is_empty() {
-            return Ok(false);
+            return Ok(None);
         }
 
         let pr_path = format!("repos/{}/pulls/{}", self.repo_full, self.parsed.pr_number);
@@ -688,8 +930,8 @@ impl GitProvider for GithubProvider {
             "comments": comments,
         });
 
-        self.api_post(&path, &body).await?;
-        Ok(true)
+        let review = self.api_post(&path, &body).await?;
+        Ok(review["html_url"].as_str().map(String::from))
     }
 
     async fn publish_labels(&self, labels: &[String]) -> Result<(), PrAgentError> {
@@ -718,19 +960,39 @@ impl GitProvider for GithubProvider {
         Ok(labels)
     }
 
-    async fn add_eyes_reaction(
+    async fn remove_label(&self, label: &str) -> Result<(), PrAgentError> {
+        let name: String = url::form_urlencoded::byte_serialize(label.as_bytes())
+            .collect::<String>()
+            .replace('+', "%20");
+        let path = format!(
+            "repos/{}/issues/{}/labels/{name}",
+            self.repo_full, self.parsed.pr_number
+        );
+        self.api_delete(&path).await
+    }
+
+    fn reaction_name(&self, reaction: Reaction) -> Option<&'static str> {
+        // GitHub only offers a fixed set of eight reactions.
+        Some(match reaction {
+            Reaction::Ack => "eyes",
+            Reaction::Success => "rocket",
+            Reaction::Failure => "confused",
+        })
+    }
+
+    async fn add_reaction(
         &self,
         comment_id: u64,
-        disable_eyes: bool,
+        reaction: Reaction,
     ) -> Result<Option<u64>, PrAgentError> {
-        if disable_eyes {
+        let Some(content) = self.reaction_name(reaction) else {
             return Ok(None);
-        }
+        };
         let path = format!(
             "repos/{}/issues/comments/{}/reactions",
             self.repo_full, comment_id
         );
-        let resp = self.api_post(&path, &json!({"content": "eyes"})).await?;
+        let resp = self.api_post(&path, &json!({ "content": content })).await?;
         Ok(resp["id"].as_u64())
     }
 
@@ -760,34 +1022,166 @@ impl GitProvider for GithubProvider {
         Ok(messages.join("\n"))
     }
 
+    async fn get_pr_commits(&self) -> Result<Vec<CommitInfo>, PrAgentError> {
+        let path = format!(
+            "repos/{}/pulls/{}/commits?per_page=100",
+            self.repo_full, self.parsed.pr_number
+        );
+        let items = self.api_get_all_pages(&path).await?;
+        Ok(items
+            .iter()
+            .filter_map(|c| {
+                Some(CommitInfo {
+                    sha: c["sha"].as_str()?.to_string(),
+                    date: c["commit"]["committer"]["date"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                })
+            })
+            .collect())
+    }
+
     async fn get_repo_settings(&self) -> Result<Option<String>, PrAgentError> {
         match self.get_file_content(".pr_agent.toml", "HEAD").await {
             Ok(content) if !content.is_empty() => Ok(Some(content)),
+            _ if get_settings().config.use_wiki_settings_file => {
+                let page = self.get_wiki_file(WIKI_SETTINGS_FILE).await;
+                if page.is_some() {
+                    tracing::info!("loaded .pr_agent.toml from the repo wiki");
+                }
+                Ok(page.map(|page| toml_from_wiki_page(&page)))
+            }
             _ => Ok(None),
         }
     }
 
-    async fn get_global_settings(&self) -> Result<Option<String>, PrAgentError> {
-        let global_repo = format!("{}/pr-agent-settings", self.parsed.owner);
-        tracing::debug!(repo = %global_repo, "checking for org-level global settings");
-        match self
-            .get_file_content_from_repo(&global_repo, ".pr_agent.toml", "HEAD")
-            .await
+    async fn get_repo_file(&self, path: &str) -> Result<Option<String>, PrAgentError> {
+        match self.get_file_content(path, "HEAD").await {
+            Ok(content) if !content.is_empty() => Ok(Some(content)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn search_code(&self, query: &str) -> Result<Vec<String>, PrAgentError> {
+        let q: String = url::form_urlencoded::byte_serialize(
+            format!("{query} repo:{}", self.repo_full).as_bytes(),
+        )
+        .collect();
+        let resp = self
+            .api_get(&format!("search/code?q={q}&per_page=10"))
+            .await?;
+        Ok(resp["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item["path"].as_str().map(String::from))
+            .collect())
+    }
+
+    async fn get_repo_tree(&self) -> Result<RepoTree, PrAgentError> {
+        let resp = self
+            .api_get(&format!(
+                "repos/{}/git/trees/HEAD?recursive=1",
+                self.repo_full
+            ))
+            .await?;
+        if resp["truncated"].as_bool() == Some(true) {
+            tracing::warn!(repo = %self.repo_full, "repository tree truncated by the GitHub API");
+        }
+        let files = resp["tree"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry["type"] == "blob")
+            .filter_map(|entry| {
+                let path = entry["path"].as_str()?;
+                Some((path.to_string(), entry["size"].as_u64().unwrap_or(0)))
+            })
+            .collect();
+        Ok(RepoTree {
+            revision: resp["sha"].as_str().unwrap_or_default().to_string(),
+            files,
+        })
+    }
+
+    async fn create_pr_file(
+        &self,
+        file_path: &str,
+        contents: &[u8],
+        message: &str,
+    ) -> Result<bool, PrAgentError> {
+        let pr_path = format!("repos/{}/pulls/{}", self.repo_full, self.parsed.pr_number);
+        let pr_data = self.api_get(&pr_path).await?;
+        let (repo, branch) = pr_file_target(&pr_data, &self.repo_full)?;
+        if self
+            .get_contents_entry(&repo, file_path, &branch)
+            .await?
+            .is_some()
         {
-            Ok(content) if !content.is_empty() => {
-                tracing::info!(repo = %global_repo, "loaded global org-level .pr_agent.toml");
-                Ok(Some(content))
+            return Ok(false);
+        }
+        // Without a blob SHA the contents API refuses to replace a file that
+        // appeared in the meantime.
+        self.put_contents(&repo, file_path, &branch, contents, message, None)
+            .await?;
+        Ok(true)
+    }
+
+    async fn append_to_repo_file(
+        &self,
+        file_path: &str,
+        branch: &str,
+        text: &str,
+        message: &str,
+    ) -> Result<(), PrAgentError> {
+        // A missing file is created; its SHA is required to update an existing one.
+        let (mut content, sha) = match self
+            .get_contents_entry(&self.repo_full, file_path, branch)
+            .await?
+        {
+            None => (String::new(), None),
+            Some(file) => {
+                let encoded = file["content"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .replace('\n', "");
+                let decoded = base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .map_err(|e| PrAgentError::GitProvider(format!("invalid file content: {e}")))?;
+                (
+                    String::from_utf8_lossy(&decoded).into_owned(),
+                    file["sha"].as_str().map(String::from),
+                )
             }
-            Ok(_) => Ok(None),
-            Err(e) => {
-                tracing::info!(
-                    repo = %global_repo,
-                    error = %e,
-                    "no org-level pr-agent-settings repo found, continuing without global config"
-                );
-                Ok(None)
+        };
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(text);
+        self.put_contents(
+            &self.repo_full,
+            file_path,
+            branch,
+            content.as_bytes(),
+            message,
+            sha.as_deref(),
+        )
+        .await
+    }
+
+    async fn get_global_settings(&self) -> Result<Option<String>, PrAgentError> {
+        let content = self.get_global_file(".pr_agent.toml").await;
+        match &content {
+            Some(_) => {
+                tracing::info!(owner = %self.parsed.owner, "loaded global org-level .pr_agent.toml")
             }
+            None => tracing::debug!(
+                owner = %self.parsed.owner,
+                "no org-level pr-agent-settings .pr_agent.toml, continuing without global config"
+            ),
         }
+        Ok(content)
     }
 
     async fn get_issue_comments(&self) -> Result<Vec<IssueComment>, PrAgentError> {
@@ -811,23 +1205,42 @@ impl GitProvider for GithubProvider {
         Ok(comments)
     }
 
-    fn is_supported(&self, capability: &str) -> bool {
+    fn is_supported(&self, capability: Capability) -> bool {
         matches!(
             capability,
-            "gfm_markdown" | "labels" | "reactions" | "code_suggestions" | "inline_comments"
+            Capability::GfmMarkdown
+                | Capability::Labels
+                | Capability::Reactions
+                | Capability::CodeSuggestions
+                | Capability::InlineComments
         )
     }
 
+    fn max_comment_chars(&self) -> Option<usize> {
+        Some(MAX_COMMENT_CHARS)
+    }
+
     async fn edit_comment(&self, comment_id: &CommentId, body: &str) -> Result<(), PrAgentError> {
         let path = format!("repos/{}/issues/comments/{}", self.repo_full, comment_id.0);
+        // Overflow is cut rather than posted as new comments, which would pile
+        // up with every edit.
+        let body = truncate_comment(body, MAX_COMMENT_CHARS);
         self.api_patch(&path, &json!({"body": body})).await?;
         Ok(())
     }
 
     async fn reply_to_comment(&self, comment_id: u64, body: &str) -> Result<(), PrAgentError> {
         // GitHub API: POST /repos/{owner}/{repo}/pulls/{pull_number}/comments/{comment_id}/replies
+        // Replies to replies are not supported, so answer on the thread's first comment.
+        let comment = self
+            .api_get(&format!(
+                "repos/{}/pulls/comments/{comment_id}",
+                self.repo_full
+            ))
+            .await?;
+        let thread_root = comment["in_reply_to_id"].as_u64().unwrap_or(comment_id);
         let path = format!(
-            "repos/{}/pulls/{}/comments/{comment_id}/replies",
+            "repos/{}/pulls/{}/comments/{thread_root}/replies",
             self.repo_full, self.parsed.pr_number
         );
         self.api_post(&path, &json!({"body": body})).await?;
@@ -860,7 +1273,7 @@ impl GitProvider for GithubProvider {
             }
         }
 
-        let comments: Vec<IssueComment> = items
+        let mut comments: Vec<IssueComment> = items
             .iter()
             .filter(|c| {
                 let id = c["id"].as_u64().unwrap_or(0);
@@ -877,10 +1290,21 @@ impl GitProvider for GithubProvider {
                 })
             })
             .collect();
+        // Comment IDs increase over time, so this is the order they were written in.
+        comments.sort_by_key(|c| c.id);
 
         Ok(comments)
     }
 
+    async fn get_review_comments(&self) -> Result<Vec<ReviewComment>, PrAgentError> {
+        let path = format!(
+            "repos/{}/pulls/{}/comments?per_page=100",
+            self.repo_full, self.parsed.pr_number
+        );
+        let items = self.api_get_all_pages(&path).await?;
+        Ok(items.iter().filter_map(review_comment_from_json).collect())
+    }
+
     async fn get_latest_commit_url(&self) -> Result<String, PrAgentError> {
         let path = format!(
             "repos/{}/pulls/{}/commits?per_page=100",
@@ -894,6 +1318,25 @@ impl GitProvider for GithubProvider {
         Ok(url.to_string())
     }
 
+    async fn get_pr_head_sha(&self) -> Result<String, PrAgentError> {
+        let pr_path = format!("repos/{}/pulls/{}", self.repo_full, self.parsed.pr_number);
+        let pr_data = self.api_get(&pr_path).await?;
+        pr_data["head"]["sha"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| PrAgentError::GitProvider("PR has no head SHA".into()))
+    }
+
+    async fn get_head_file_bytes(&self, path: &str) -> Result<Vec<u8>, PrAgentError> {
+        let head_sha = self.get_pr_head_sha().await?;
+        let api_path = format!(
+            "repos/{}/contents/{}?ref={}",
+            self.repo_full, path, head_sha
+        );
+        let resp = self.api_get(&api_path).await?;
+        Ok(decode_contents_bytes(&resp))
+    }
+
     async fn get_best_practices(&self) -> Result<String, PrAgentError> {
         let settings = get_settings();
 
@@ -903,23 +1346,71 @@ impl GitProvider for GithubProvider {
             return Ok(String::new());
         }
 
-        match self.get_file_content("best_practices.md", "HEAD").await {
-            Ok(content) if !content.is_empty() => {
-                let max_lines = settings.best_practices.max_lines_allowed as usize;
-                let truncated: String = content
-                    .lines()
-                    .take(max_lines)
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                tracing::info!(
-                    lines = truncated.lines().count(),
-                    max = max_lines,
-                    "loaded best_practices.md from repo"
-                );
-                Ok(truncated)
+        // The repo root first, then the wiki, then the org settings repo.
+        let web_base = self.web_base();
+        let (content, source, from) = match self.get_file_content(BEST_PRACTICES_FILE, "HEAD").await
+        {
+            Ok(content) if !content.is_empty() => (
+                content,
+                BestPracticesSource {
+                    url: format!(
+                        "{web_base}/{}/blob/HEAD/{BEST_PRACTICES_FILE}",
+                        self.repo_full
+                    ),
+                    line_anchors: true,
+                },
+                "repo",
+            ),
+            _ => {
+                let wiki = if settings.config.use_wiki_settings_file {
+                    self.get_wiki_file(BEST_PRACTICES_FILE).await
+                } else {
+                    None
+                };
+                if let Some(content) = wiki {
+                    let page = BEST_PRACTICES_FILE.trim_end_matches(".md");
+                    (
+                        content,
+                        BestPracticesSource {
+                            url: format!("{web_base}/{}/wiki/{page}", self.repo_full),
+                            line_anchors: false,
+                        },
+                        "wiki",
+                    )
+                } else if settings.best_practices.enable_global_best_practices
+                    && let Some(content) = self.get_global_file(BEST_PRACTICES_FILE).await
+                {
+                    (
+                        content,
+                        BestPracticesSource {
+                            url: format!(
+                                "{web_base}/{}/{GLOBAL_SETTINGS_REPO}/blob/HEAD/{BEST_PRACTICES_FILE}",
+                                self.parsed.owner
+                            ),
+                            line_anchors: true,
+                        },
+                        "org settings repo",
+                    )
+                } else {
+                    return Ok(String::new());
+                }
             }
-            _ => Ok(String::new()),
-        }
+        };
+
+        let max_lines = settings.best_practices.max_lines_allowed as usize;
+        let truncated: String = content
+            .lines()
+            .take(max_lines)
+            .collect::<Vec<_>>()
+            .join("\n");
+        tracing::info!(
+            lines = truncated.lines().count(),
+            max = max_lines,
+            from,
+            "loaded best_practices.md"
+        );
+        *self.best_practices_source.lock().unwrap() = Some(source);
+        Ok(truncated)
     }
 
     async fn get_repo_metadata(&self) -> Result<String, PrAgentError> {
@@ -954,6 +1445,10 @@ impl GitProvider for GithubProvider {
         (self.parsed.owner.clone(), self.parsed.repo.clone())
     }
 
+    fn get_pr_number(&self) -> Option<u64> {
+        Some(self.parsed.pr_number)
+    }
+
     async fn get_issue_body(&self, issue_number: u64) -> Result<(String, String), PrAgentError> {
         let path = format!("repos/{}/issues/{}", self.repo_full, issue_number);
         let data = self.api_get(&path).await?;
@@ -962,6 +1457,77 @@ impl GitProvider for GithubProvider {
         Ok((title, body))
     }
 
+    async fn is_team_member(&self, team: &str, login: &str) -> Result<bool, PrAgentError> {
+        let (org, slug) = team
+            .split_once('/')
+            .unwrap_or((self.parsed.owner.as_str(), team));
+        let path = format!("orgs/{org}/teams/{slug}/memberships/{login}");
+        let resp = self
+            .api_request_with_retry(reqwest::Method::GET, &path, None)
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        let membership: serde_json::Value = Self::check_response(resp, "GET")
+            .await?
+            .json()
+            .await
+            .map_err(PrAgentError::Http)?;
+        Ok(membership["state"] == "active")
+    }
+
+    async fn get_author_association(&self, login: &str) -> Result<String, PrAgentError> {
+        let path = format!("repos/{}/collaborators/{login}/permission", self.repo_full);
+        let resp = self
+            .api_request_with_retry(reqwest::Method::GET, &path, None)
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok("NONE".into());
+        }
+        let data: serde_json::Value = Self::check_response(resp, "GET")
+            .await?
+            .json()
+            .await
+            .map_err(PrAgentError::Http)?;
+        // Anyone who can push counts as a collaborator.
+        let association = match data["permission"].as_str() {
+            Some("admin" | "maintain" | "write") => "COLLABORATOR",
+            _ => "NONE",
+        };
+        Ok(association.into())
+    }
+
+    async fn get_planning_context(&self) -> Result<PlanningContext, PrAgentError> {
+        let body = json!({
+            "query": PLANNING_CONTEXT_QUERY,
+            "variables": {
+                "owner": self.parsed.owner,
+                "repo": self.parsed.repo,
+                "number": self.parsed.pr_number,
+            },
+        });
+        let url = graphql_url(&self.base_url);
+        let resp = self
+            .api_request_with_retry_url(reqwest::Method::POST, &url, Some(&body))
+            .await?;
+        let resp = Self::check_response(resp, "POST").await?;
+        let data: serde_json::Value = resp.json().await.map_err(PrAgentError::Http)?;
+        if let Some(errors) = data["errors"].as_array()
+            && data["data"]["repository"]["pullRequest"].is_null()
+        {
+            return Err(PrAgentError::GitProvider(format!(
+                "GitHub GraphQL error: {}",
+                errors
+                    .first()
+                    .and_then(|e| e["message"].as_str())
+                    .unwrap_or("unknown error")
+            )));
+        }
+        Ok(parse_planning_context(
+            &data["data"]["repository"]["pullRequest"],
+        ))
+    }
+
     async fn auto_approve(&self) -> Result<bool, PrAgentError> {
         let path = format!(
             "repos/{}/pulls/{}/reviews",
@@ -980,12 +1546,26 @@ impl GitProvider for GithubProvider {
         }
     }
 
+    async fn publish_check_run(&self, check: &CheckRunReport) -> Result<(), PrAgentError> {
+        let head_sha = self.get_pr_head_sha().await?;
+        let path = format!("repos/{}/check-runs", self.repo_full);
+        let body = json!({
+            "name": check.name,
+            "head_sha": head_sha,
+            "external_id": check.command,
+            "status": "completed",
+            "conclusion": if check.success { "success" } else { "failure" },
+            "output": {
+                "title": check.command,
+                "summary": check.summary,
+            },
+        });
+        self.api_post(&path, &body).await?;
+        Ok(())
+    }
+
     fn get_line_link(&self, file: &str, line_start: i32, line_end: Option<i32>) -> String {
-        // Convert API URL back to web URL for links
-        let web_base = self
-            .base_url
-            .replace("api.github.com", "github.com")
-            .replace("/api/v3", "");
+        let web_base = self.web_base();
 
         // All links point to the PR files diff view
         use sha2::{Digest, Sha256};
@@ -1009,6 +1589,66 @@ impl GitProvider for GithubProvider {
             _ => base,
         }
     }
+
+    fn get_best_practices_link(&self, line: usize) -> String {
+        match &*self.best_practices_source.lock().unwrap() {
+            Some(source) if source.line_anchors => format!("{}#L{line}", source.url),
+            Some(source) => source.url.clone(),
+            None => String::new(),
+        }
+    }
+}
+
+/// Current Unix time in seconds.
+fn now_secs() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+/// Seconds from the `Retry-After` header.
+fn retry_after_secs(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    headers
+        .get("retry-after")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.trim().parse().ok())
+}
+
+/// Seconds until the rate limit resets, when `x-ratelimit-remaining` says
+/// it is used up.
+fn exhausted_limit_wait(headers: &reqwest::header::HeaderMap, now: u64) -> Option<u64> {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    if header("x-ratelimit-remaining")?.trim() != "0" {
+        return None;
+    }
+    let reset: u64 = header("x-ratelimit-reset")?.trim().parse().ok()?;
+    Some(reset.saturating_sub(now).max(1))
+}
+
+/// Whether a 403 body is GitHub's rate limit or abuse-detection message
+/// rather than a permission error.
+fn is_rate_limit_message(body: &str) -> bool {
+    let body = body.to_lowercase();
+    body.contains("rate limit") || body.contains("abuse")
+}
+
+/// Hold further requests with `token` until `until` (Unix seconds).
+fn pause_until(token: &str, until: u64) {
+    let mut resets = RATE_LIMIT_RESETS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let now = now_secs();
+    resets.retain(|_, reset| *reset > now);
+    resets.insert(token.to_string(), until);
+}
+
+/// Seconds left before requests with `token` may be sent again.
+fn rate_limit_pause(token: &str, now: u64) -> Option<u64> {
+    let resets = RATE_LIMIT_RESETS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    resets
+        .get(token)
+        .filter(|&&reset| reset > now)
+        .map(|reset| reset - now)
 }
 
 /// Parse the `Link` header to find the `rel="next"` URL.
@@ -1026,8 +1666,118 @@ fn parse_next_link(headers: &reqwest::header::HeaderMap) -> Option<String> {
     None
 }
 
+/// GraphQL query for a PR's milestone and Projects (v2) field values.
+const PLANNING_CONTEXT_QUERY: &str = r#"
+query($owner: String!, $repo: String!, $number: Int!) {
+  repository(owner: $owner, name: $repo) {
+    pullRequest(number: $number) {
+      milestone { title dueOn }
+      projectItems(first: 10) {
+        nodes {
+          project { title }
+          fieldValues(first: 20) {
+            nodes {
+              ... on ProjectV2ItemFieldSingleSelectValue { name field { ... on ProjectV2FieldCommon { name } } }
+              ... on ProjectV2ItemFieldTextValue { text field { ... on ProjectV2FieldCommon { name } } }
+              ... on ProjectV2ItemFieldDateValue { date field { ... on ProjectV2FieldCommon { name } } }
+              ... on ProjectV2ItemFieldNumberValue { number field { ... on ProjectV2FieldCommon { name } } }
+              ... on ProjectV2ItemFieldIterationValue { title field { ... on ProjectV2FieldCommon { name } } }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// GraphQL endpoint for a REST API base URL.
+///
+/// `https://api.github.com` → `https://api.github.com/graphql`;
+/// Enterprise `https://host/api/v3` → `https://host/api/graphql`.
+fn graphql_url(base_url: &str) -> String {
+    let base = base_url.trim_end_matches('/');
+    match base.strip_suffix("/api/v3") {
+        Some(host) => format!("{host}/api/graphql"),
+        None => format!("{base}/graphql"),
+    }
+}
+
+/// Extract milestone and project fields from a GraphQL `pullRequest` node.
+///
+/// Project fields without a value (or of unsupported types) are skipped;
+/// the built-in `Title` field only repeats the PR title and is dropped.
+fn parse_planning_context(pr: &serde_json::Value) -> PlanningContext {
+    let milestone = pr["milestone"]["title"].as_str().map(|title| Milestone {
+        title: title.to_string(),
+        due_on: pr["milestone"]["dueOn"]
+            .as_str()
+            .map(|d| d.split('T').next().unwrap_or(d).to_string()),
+    });
+
+    let projects = pr["projectItems"]["nodes"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let project = item["project"]["title"].as_str()?.to_string();
+                    let fields = item["fieldValues"]["nodes"]
+                        .as_array()
+                        .map(|values| values.iter().filter_map(parse_field_value).collect())
+                        .unwrap_or_default();
+                    Some(ProjectItem { project, fields })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    PlanningContext {
+        milestone,
+        projects,
+    }
+}
+
+/// Turn one `ProjectV2ItemField*Value` node into a `(field, value)` pair.
+fn parse_field_value(node: &serde_json::Value) -> Option<(String, String)> {
+    let field = node["field"]["name"].as_str()?;
+    if field == "Title" {
+        return None;
+    }
+    let value = ["name", "text", "date", "title"]
+        .iter()
+        .find_map(|key| node[*key].as_str().map(str::to_string))
+        .or_else(|| node["number"].as_f64().map(|n| n.to_string()))?;
+    if value.is_empty() {
+        return None;
+    }
+    Some((field.to_string(), value))
+}
+
+/// Read a pull request review comment. Comments on removed lines (`LEFT`)
+/// or outdated ones (no current `line`) are skipped.
+fn review_comment_from_json(c: &serde_json::Value) -> Option<ReviewComment> {
+    if c["side"].as_str() == Some("LEFT") {
+        return None;
+    }
+    let line = i32::try_from(c["line"].as_i64()?).ok()?;
+    let start_line = c["start_line"]
+        .as_i64()
+        .and_then(|l| i32::try_from(l).ok())
+        .unwrap_or(line);
+    Some(ReviewComment {
+        id: c["id"].as_u64()?,
+        path: c["path"].as_str()?.to_string(),
+        start_line,
+        line,
+        body: c["body"].as_str().unwrap_or_default().to_string(),
+        user: c["user"]["login"].as_str().unwrap_or_default().to_string(),
+        thumbs_down: c["reactions"]["-1"].as_u64().is_some_and(|n| n > 0),
+    })
+}
+
 /// Count added (+) and removed (-) lines in a unified diff patch.
-fn count_patch_lines(patch: &str) -> (i32, i32) {
+pub(super) fn count_patch_lines(patch: &str) -> (i32, i32) {
     let mut plus = 0i32;
     let mut minus = 0i32;
     for line in patch.lines() {
@@ -1040,6 +1790,49 @@ fn count_patch_lines(patch: &str) -> (i32, i32) {
     (plus, minus)
 }
 
+/// The `(repository, branch)` a PR's new files go to: its head branch, in
+/// the head repository. Refuses a fork that doesn't allow edits from
+/// maintainers, and a head branch with the same name as the base branch.
+fn pr_file_target(
+    pr_data: &serde_json::Value,
+    base_repo: &str,
+) -> Result<(String, String), PrAgentError> {
+    let head = &pr_data["head"];
+    let Some(head_repo) = head["repo"]["full_name"].as_str() else {
+        return Err(PrAgentError::GitProvider(
+            "the PR's head repository no longer exists".into(),
+        ));
+    };
+    let branch = head["ref"].as_str().unwrap_or_default();
+    if branch.is_empty() || Some(branch) == pr_data["base"]["ref"].as_str() {
+        return Err(PrAgentError::GitProvider(format!(
+            "not writing to {head_repo}@{branch}: the PR's head branch has the same name as \
+             its base branch"
+        )));
+    }
+    if !head_repo.eq_ignore_ascii_case(base_repo)
+        && pr_data["maintainer_can_modify"].as_bool() != Some(true)
+    {
+        return Err(PrAgentError::GitProvider(format!(
+            "can't push to {head_repo}: the PR comes from a fork that doesn't allow edits \
+             from maintainers"
+        )));
+    }
+    Ok((head_repo.to_string(), branch.to_string()))
+}
+
+/// Percent-encode each segment of a repository file path for the contents API.
+fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            url::form_urlencoded::byte_serialize(segment.as_bytes())
+                .collect::<String>()
+                .replace('+', "%20")
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1094,9 +1887,269 @@ mod tests {
         assert!(parse_next_link(&headers).is_none());
     }
 
+    #[test]
+    fn test_retry_after_secs() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_after_secs(&headers), None);
+        headers.insert("retry-after", "30".parse().unwrap());
+        assert_eq!(retry_after_secs(&headers), Some(30));
+    }
+
+    #[test]
+    fn test_exhausted_limit_wait() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-ratelimit-reset", "1000".parse().unwrap());
+        headers.insert("x-ratelimit-remaining", "12".parse().unwrap());
+        assert_eq!(exhausted_limit_wait(&headers, 900), None);
+
+        headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        assert_eq!(exhausted_limit_wait(&headers, 900), Some(100));
+        // A reset already in the past still waits a moment.
+        assert_eq!(exhausted_limit_wait(&headers, 1200), Some(1));
+    }
+
+    #[test]
+    fn test_is_rate_limit_message() {
+        assert!(is_rate_limit_message(
+            r#"{"message":"You have exceeded a secondary rate limit. Please wait a few minutes before you try again."}"#
+        ));
+        assert!(is_rate_limit_message(
+            r#"{"message":"You have triggered an abuse detection mechanism."}"#
+        ));
+        assert!(!is_rate_limit_message(
+            r#"{"message":"Resource not accessible by integration"}"#
+        ));
+    }
+
+    #[test]
+    fn test_rate_limit_pause() {
+        let now = now_secs();
+        assert_eq!(rate_limit_pause("test-pause-token", now), None);
+        pause_until("test-pause-token", now + 30);
+        assert_eq!(rate_limit_pause("test-pause-token", now), Some(30));
+        assert_eq!(rate_limit_pause("test-pause-token", now + 30), None);
+        assert_eq!(rate_limit_pause("other-token", now), None);
+    }
+
     #[test]
     fn test_parse_next_link_no_header() {
         let headers = reqwest::header::HeaderMap::new();
         assert!(parse_next_link(&headers).is_none());
     }
+
+    #[tokio::test]
+    async fn test_reaction_names_are_github_reactions() {
+        let settings = std::sync::Arc::new(
+            crate::config::loader::load_settings(&HashMap::new(), None, None).unwrap(),
+        );
+        let provider = crate::config::loader::with_settings(
+            settings,
+            GithubProvider::new("https://github.com/owner/repo/pull/1"),
+        )
+        .await
+        .unwrap();
+        // The only values the reactions API accepts.
+        let allowed = [
+            "+1", "-1", "laugh", "confused", "heart", "hooray", "rocket", "eyes",
+        ];
+        for reaction in [Reaction::Ack, Reaction::Success, Reaction::Failure] {
+            let name = provider.reaction_name(reaction).unwrap();
+            assert!(allowed.contains(&name), "{name} is not a GitHub reaction");
+        }
+        assert_eq!(provider.reaction_name(Reaction::Ack), Some("eyes"));
+    }
+
+    #[tokio::test]
+    async fn test_best_practices_link_follows_source() {
+        let settings = std::sync::Arc::new(
+            crate::config::loader::load_settings(&HashMap::new(), None, None).unwrap(),
+        );
+        let provider = crate::config::loader::with_settings(
+            settings,
+            GithubProvider::new("https://github.com/owner/repo/pull/1"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(provider.get_best_practices_link(3), "");
+
+        *provider.best_practices_source.lock().unwrap() = Some(BestPracticesSource {
+            url: "https://github.com/owner/repo/blob/HEAD/best_practices.md".into(),
+            line_anchors: true,
+        });
+        assert_eq!(
+            provider.get_best_practices_link(3),
+            "https://github.com/owner/repo/blob/HEAD/best_practices.md#L3"
+        );
+
+        *provider.best_practices_source.lock().unwrap() = Some(BestPracticesSource {
+            url: "https://github.com/owner/repo/wiki/best_practices".into(),
+            line_anchors: false,
+        });
+        assert_eq!(
+            provider.get_best_practices_link(3),
+            "https://github.com/owner/repo/wiki/best_practices"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_shared_file() {
+        let settings = std::sync::Arc::new(
+            crate::config::loader::load_settings(&HashMap::new(), None, None).unwrap(),
+        );
+        let url = "https://raw.githubusercontent.com/wiki/owner/cached/best_practices.md";
+        let fetches = std::sync::atomic::AtomicUsize::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(Some("- Rule".to_string()))
+        };
+        crate::config::loader::with_settings(settings.clone(), async {
+            assert_eq!(
+                cached_shared_file(url, fetch()).await.as_deref(),
+                Some("- Rule")
+            );
+            assert_eq!(
+                cached_shared_file(url, fetch()).await.as_deref(),
+                Some("- Rule")
+            );
+        })
+        .await;
+        assert_eq!(fetches.load(std::sync::atomic::Ordering::Relaxed), 1);
+
+        // Failures aren't cached
+        let failing = "https://raw.githubusercontent.com/wiki/owner/failing/best_practices.md";
+        crate::config::loader::with_settings(settings, async {
+            let err = async { Err(PrAgentError::GitProvider("boom".into())) };
+            assert_eq!(cached_shared_file(failing, err).await, None);
+            assert_eq!(
+                cached_shared_file(failing, fetch()).await.as_deref(),
+                Some("- Rule")
+            );
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_toml_from_wiki_page() {
+        let page = "Settings for this repo:\n\n```toml\n[pr_reviewer]\nnum_max_findings = 3\n```\n";
+        assert_eq!(
+            toml_from_wiki_page(page),
+            "[pr_reviewer]\nnum_max_findings = 3"
+        );
+        assert_eq!(
+            toml_from_wiki_page("[config]\nmodel = \"x\"\n"),
+            "[config]\nmodel = \"x\""
+        );
+    }
+
+    #[test]
+    fn test_graphql_url() {
+        assert_eq!(
+            graphql_url("https://api.github.com"),
+            "https://api.github.com/graphql"
+        );
+        assert_eq!(
+            graphql_url("https://ghe.example.com/api/v3/"),
+            "https://ghe.example.com/api/graphql"
+        );
+    }
+
+    #[test]
+    fn test_review_comment_from_json() {
+        let multi_line = json!({
+            "id": 7,
+            "path": "src/lib.rs",
+            "side": "RIGHT",
+            "start_line": 3,
+            "line": 5,
+            "body": "This clones on every call",
+            "user": { "login": "alice" },
+            "reactions": { "+1": 0, "-1": 1 },
+        });
+        let comment = review_comment_from_json(&multi_line).unwrap();
+        assert_eq!((comment.start_line, comment.line), (3, 5));
+        assert_eq!(comment.user, "alice");
+        assert!(comment.thumbs_down);
+
+        let single = json!({ "id": 8, "path": "a.rs", "line": 9, "start_line": null });
+        assert_eq!(review_comment_from_json(&single).unwrap().start_line, 9);
+
+        let outdated = json!({ "id": 9, "path": "a.rs", "line": null, "original_line": 4 });
+        assert!(review_comment_from_json(&outdated).is_none());
+        let removed = json!({ "id": 10, "path": "a.rs", "side": "LEFT", "line": 4 });
+        assert!(review_comment_from_json(&removed).is_none());
+    }
+
+    #[test]
+    fn test_parse_planning_context() {
+        let pr = json!({
+            "milestone": { "title": "v2.0", "dueOn": "2026-11-01T07:00:00Z" },
+            "projectItems": { "nodes": [{
+                "project": { "title": "Roadmap" },
+                "fieldValues": { "nodes": [
+                    { "text": "Fix login", "field": { "name": "Title" } },
+                    { "name": "P1", "field": { "name": "Priority" } },
+                    { "number": 3.0, "field": { "name": "Estimate" } },
+                    {},
+                ]},
+            }]},
+        });
+        let ctx = parse_planning_context(&pr);
+        assert_eq!(
+            ctx.milestone,
+            Some(Milestone {
+                title: "v2.0".into(),
+                due_on: Some("2026-11-01".into()),
+            })
+        );
+        assert_eq!(ctx.projects[0].project, "Roadmap");
+        assert_eq!(
+            ctx.projects[0].fields,
+            vec![
+                ("Priority".to_string(), "P1".to_string()),
+                ("Estimate".to_string(), "3".to_string()),
+            ]
+        );
+        assert_eq!(
+            ctx.to_prompt_context(),
+            "Milestone: v2.0 (due 2026-11-01)\nProject: Roadmap (Priority: P1, Estimate: 3)\n"
+        );
+
+        let empty = parse_planning_context(&json!({ "milestone": null }));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_pr_file_target() {
+        let pr = |head_repo: &str, head_ref: &str, can_modify: bool| {
+            json!({
+                "head": { "ref": head_ref, "repo": { "full_name": head_repo } },
+                "base": { "ref": "main", "repo": { "full_name": "owner/repo" } },
+                "maintainer_can_modify": can_modify,
+            })
+        };
+        assert_eq!(
+            pr_file_target(&pr("owner/repo", "feat/x", false), "owner/repo").unwrap(),
+            ("owner/repo".to_string(), "feat/x".to_string())
+        );
+        assert_eq!(
+            pr_file_target(&pr("alice/repo", "feat/x", true), "owner/repo").unwrap(),
+            ("alice/repo".to_string(), "feat/x".to_string())
+        );
+        let fork = pr_file_target(&pr("alice/repo", "feat/x", false), "owner/repo");
+        assert!(
+            fork.unwrap_err()
+                .to_string()
+                .contains("can't push to alice/repo")
+        );
+        let same_branch = pr_file_target(&pr("alice/repo", "main", true), "owner/repo");
+        assert!(same_branch.unwrap_err().to_string().contains("same name"));
+        let deleted = json!({ "head": { "ref": "feat/x", "repo": null } });
+        assert!(pr_file_target(&deleted, "owner/repo").is_err());
+    }
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(encode_path("changes/7.feature.md"), "changes/7.feature.md");
+        assert_eq!(encode_path("docs/a b#1.md"), "docs/a%20b%231.md");
+    }
 }
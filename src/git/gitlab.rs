@@ -0,0 +1,933 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use base64::Engine;
+use reqwest::Client;
+use serde_json::json;
+
+use super::GitProvider;
+use super::github::count_patch_lines;
+use super::types::*;
+use super::url_parser::{ParsedPrUrl, parse_pr_url};
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::output::markdown::{split_comment, truncate_comment};
+use crate::util::http_client_builder;
+
+/// Maximum characters in a single note (GitLab limit is 1,000,000).
+const MAX_COMMENT_CHARS: usize = 1_000_000;
+
+/// GitLab provider implementation on top of the REST v4 API.
+pub struct GitlabProvider {
+    /// Raw reqwest client.
+    client: Client,
+    /// Instance URL without trailing slash (e.g. "https://gitlab.com").
+    web_url: String,
+    /// Personal/project access token.
+    token: String,
+    /// Parsed URL info (owner is the full group path).
+    parsed: ParsedPrUrl,
+    /// Full project path "group/subgroup/project".
+    project_path: String,
+    /// MR web URL as given.
+    pr_url: String,
+    /// MR IID as a string (for `get_pr_id`).
+    pr_id: String,
+}
+
+impl GitlabProvider {
+    /// Create a new GitLab provider from a merge request URL.
+    pub fn new(pr_url: &str) -> Result<Self, PrAgentError> {
+        let parsed = parse_pr_url(pr_url)?;
+        let settings = get_settings();
+
+        let timeout = std::time::Duration::from_secs(settings.config.ai_timeout);
+        let client = http_client_builder(&settings.http, &settings.http.git_headers)?
+            .timeout(timeout)
+            .build()
+            .map_err(|e| PrAgentError::Other(format!("failed to build HTTP client: {e}")))?;
+        let project_path = if parsed.owner.is_empty() {
+            parsed.repo.clone()
+        } else {
+            format!("{}/{}", parsed.owner, parsed.repo)
+        };
+        let pr_id = parsed.pr_number.to_string();
+
+        Ok(Self {
+            client,
+            web_url: settings.gitlab.url.trim_end_matches('/').to_string(),
+            token: settings.gitlab.personal_access_token.clone(),
+            parsed,
+            project_path,
+            pr_url: pr_url.to_string(),
+            pr_id,
+        })
+    }
+
+    /// API path prefix for this MR: `projects/{id}/merge_requests/{iid}`.
+    fn mr_path(&self) -> String {
+        format!(
+            "projects/{}/merge_requests/{}",
+            encode_component(&self.project_path),
+            self.parsed.pr_number
+        )
+    }
+
+    /// Send an authenticated request to the GitLab API and check the status.
+    #[tracing::instrument(name = "gitlab_request", skip_all, fields(%method, path = %path))]
+    async fn api_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<reqwest::Response, PrAgentError> {
+        let url = format!("{}/api/v4/{}", self.web_url, path);
+        let mut req = self
+            .client
+            .request(method.clone(), &url)
+            .header("PRIVATE-TOKEN", &self.token)
+            .header("User-Agent", "pr-agent-rs");
+        if let Some(b) = body {
+            req = req.json(b);
+        }
+        self.send(&method, req).await
+    }
+
+    /// Send a request built by `api_request` or `upload_image`, mapping rate
+    /// limits and error statuses to errors.
+    async fn send(
+        &self,
+        method: &reqwest::Method,
+        req: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, PrAgentError> {
+        let resp = req.send().await.map_err(PrAgentError::Http)?;
+        if resp.status().as_u16() == 429 {
+            let retry_after = resp
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(60);
+            return Err(PrAgentError::RateLimited {
+                retry_after_secs: retry_after,
+            });
+        }
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(PrAgentError::GitProvider(format!(
+                "GitLab API {method} {status}: {body}"
+            )));
+        }
+        Ok(resp)
+    }
+
+    /// Make an authenticated GET request to the GitLab API.
+    async fn api_get(&self, path: &str) -> Result<serde_json::Value, PrAgentError> {
+        let resp = self.api_request(reqwest::Method::GET, path, None).await?;
+        resp.json().await.map_err(PrAgentError::Http)
+    }
+
+    /// Make a paginated GET request, following the `X-Next-Page` header.
+    async fn api_get_all_pages(&self, path: &str) -> Result<Vec<serde_json::Value>, PrAgentError> {
+        let sep = if path.contains('?') { '&' } else { '?' };
+        let mut all_items = Vec::new();
+        let mut page = 1u32;
+
+        loop {
+            let paged = format!("{path}{sep}per_page=100&page={page}");
+            let resp = self.api_request(reqwest::Method::GET, &paged, None).await?;
+            let next_page = resp
+                .headers()
+                .get("x-next-page")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|s| s.parse::<u32>().ok());
+            let data: serde_json::Value = resp.json().await.map_err(PrAgentError::Http)?;
+            if let Some(arr) = data.as_array() {
+                all_items.extend(arr.iter().cloned());
+            }
+            match next_page {
+                Some(n) if n > page => page = n,
+                _ => break,
+            }
+        }
+
+        Ok(all_items)
+    }
+
+    /// Make an authenticated POST request.
+    async fn api_post(
+        &self,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, PrAgentError> {
+        let resp = self
+            .api_request(reqwest::Method::POST, path, Some(body))
+            .await?;
+        resp.json().await.map_err(PrAgentError::Http)
+    }
+
+    /// Make an authenticated PUT request.
+    async fn api_put(
+        &self,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, PrAgentError> {
+        let resp = self
+            .api_request(reqwest::Method::PUT, path, Some(body))
+            .await?;
+        resp.json().await.map_err(PrAgentError::Http)
+    }
+
+    /// Make an authenticated DELETE request.
+    async fn api_delete(&self, path: &str) -> Result<(), PrAgentError> {
+        self.api_request(reqwest::Method::DELETE, path, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Fetch the merge request object.
+    async fn get_mr(&self) -> Result<serde_json::Value, PrAgentError> {
+        self.api_get(&self.mr_path()).await
+    }
+
+    /// Get raw file contents from the project at a specific ref.
+    async fn get_file_content(&self, path: &str, git_ref: &str) -> Result<String, PrAgentError> {
+        let bytes = self.get_file_bytes(path, git_ref).await?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Get raw file bytes from the project at a specific ref.
+    async fn get_file_bytes(&self, path: &str, git_ref: &str) -> Result<Vec<u8>, PrAgentError> {
+        let api_path = format!(
+            "projects/{}/repository/files/{}/raw?ref={}",
+            encode_component(&self.project_path),
+            encode_component(path),
+            encode_component(git_ref)
+        );
+        let resp = self
+            .api_request(reqwest::Method::GET, &api_path, None)
+            .await?;
+        let bytes = resp.bytes().await.map_err(PrAgentError::Http)?;
+        Ok(bytes.to_vec())
+    }
+
+    /// Build patch infos from GitLab diff entries (MR diffs or a compare),
+    /// with the file contents at `base_sha` and `head_sha`.
+    async fn diff_files_from(
+        &self,
+        diffs: &[serde_json::Value],
+        base_sha: &str,
+        head_sha: &str,
+    ) -> Vec<FilePatchInfo> {
+        let mut diff_files = Vec::with_capacity(diffs.len());
+        for d in diffs {
+            let filename = d["new_path"].as_str().unwrap_or_default().to_string();
+            let old_path = d["old_path"].as_str().unwrap_or_default().to_string();
+            let patch = d["diff"].as_str().unwrap_or_default().to_string();
+
+            let edit_type = if d["new_file"].as_bool() == Some(true) {
+                EditType::Added
+            } else if d["deleted_file"].as_bool() == Some(true) {
+                EditType::Deleted
+            } else if d["renamed_file"].as_bool() == Some(true) {
+                EditType::Renamed
+            } else {
+                EditType::Modified
+            };
+
+            let (plus_lines, minus_lines) = count_patch_lines(&patch);
+
+            let base_file = if edit_type != EditType::Added {
+                self.get_file_content(&old_path, base_sha)
+                    .await
+                    .unwrap_or_default()
+            } else {
+                String::new()
+            };
+            let head_file = if edit_type != EditType::Deleted {
+                self.get_file_content(&filename, head_sha)
+                    .await
+                    .unwrap_or_default()
+            } else {
+                String::new()
+            };
+
+            let mut info = FilePatchInfo::new(base_file, head_file, patch, filename);
+            info.edit_type = edit_type;
+            if edit_type == EditType::Renamed {
+                info.old_filename = Some(old_path);
+            }
+            info.num_plus_lines = plus_lines;
+            info.num_minus_lines = minus_lines;
+            diff_files.push(info);
+        }
+
+        diff_files
+    }
+
+    /// Open a diff discussion anchored to a line of the MR's head version.
+    async fn post_discussion(
+        &self,
+        mr: &serde_json::Value,
+        body: &str,
+        file: &str,
+        line: i32,
+    ) -> Result<(), PrAgentError> {
+        let refs = &mr["diff_refs"];
+        let path = format!("{}/discussions", self.mr_path());
+        let payload = json!({
+            "body": body,
+            "position": {
+                "position_type": "text",
+                "base_sha": refs["base_sha"],
+                "start_sha": refs["start_sha"],
+                "head_sha": refs["head_sha"],
+                "new_path": file,
+                "new_line": line,
+            }
+        });
+        self.api_post(&path, &payload).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GitProvider for GitlabProvider {
+    async fn get_diff_files(&self) -> Result<Vec<FilePatchInfo>, PrAgentError> {
+        let mr = self.get_mr().await?;
+        let base_sha = mr["diff_refs"]["base_sha"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let head_sha = mr["diff_refs"]["head_sha"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let diffs = self
+            .api_get_all_pages(&format!("{}/diffs", self.mr_path()))
+            .await?;
+        Ok(self.diff_files_from(&diffs, &base_sha, &head_sha).await)
+    }
+
+    async fn get_diff_files_between(
+        &self,
+        base_sha: &str,
+        head_sha: &str,
+    ) -> Result<Vec<FilePatchInfo>, PrAgentError> {
+        let path = format!(
+            "projects/{}/repository/compare?from={}&to={}",
+            encode_component(&self.project_path),
+            encode_component(base_sha),
+            encode_component(head_sha)
+        );
+        let data = self.api_get(&path).await?;
+        let diffs = data["diffs"].as_array().cloned().unwrap_or_default();
+        Ok(self.diff_files_from(&diffs, base_sha, head_sha).await)
+    }
+
+    async fn get_files(&self) -> Result<Vec<String>, PrAgentError> {
+        let diffs = self
+            .api_get_all_pages(&format!("{}/diffs", self.mr_path()))
+            .await?;
+        Ok(diffs
+            .iter()
+            .filter_map(|d| d["new_path"].as_str().map(String::from))
+            .collect())
+    }
+
+    async fn get_languages(&self) -> Result<HashMap<String, u64>, PrAgentError> {
+        // GitLab reports percentages rather than byte counts; the relative
+        // ordering is all callers rely on.
+        let path = format!(
+            "projects/{}/languages",
+            encode_component(&self.project_path)
+        );
+        let data = self.api_get(&path).await?;
+        Ok(data
+            .as_object()
+            .map(|obj| {
+                obj.iter()
+                    .filter_map(|(k, v)| v.as_f64().map(|n| (k.clone(), (n * 100.0) as u64)))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn get_pr_branch(&self) -> Result<String, PrAgentError> {
+        let mr = self.get_mr().await?;
+        Ok(mr["source_branch"].as_str().unwrap_or_default().to_string())
+    }
+
+    async fn get_pr_base_branch(&self) -> Result<String, PrAgentError> {
+        let mr = self.get_mr().await?;
+        Ok(mr["target_branch"].as_str().unwrap_or_default().to_string())
+    }
+
+    async fn get_user_id(&self) -> Result<String, PrAgentError> {
+        let data = self.api_get("user").await?;
+        Ok(data["username"].as_str().unwrap_or_default().to_string())
+    }
+
+    async fn get_pr_description_full(&self) -> Result<(String, String), PrAgentError> {
+        let mr = self.get_mr().await?;
+        let title = mr["title"].as_str().unwrap_or_default().to_string();
+        let body = mr["description"].as_str().unwrap_or_default().to_string();
+        Ok((title, body))
+    }
+
+    async fn publish_description(&self, title: &str, body: &str) -> Result<(), PrAgentError> {
+        self.api_put(
+            &self.mr_path(),
+            &json!({"title": title, "description": body}),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn publish_comment(
+        &self,
+        text: &str,
+        _is_temporary: bool,
+    ) -> Result<Option<CommentId>, PrAgentError> {
+        let path = format!("{}/notes", self.mr_path());
+        // Oversized output goes out as several notes; the first one's id is returned.
+        let mut first_id = None;
+        for part in split_comment(text, MAX_COMMENT_CHARS) {
+            let resp = self.api_post(&path, &json!({"body": part})).await?;
+            if first_id.is_none() {
+                first_id = resp["id"].as_u64().map(|id| CommentId(id.to_string()));
+            }
+        }
+        Ok(first_id)
+    }
+
+    async fn publish_inline_comment(
+        &self,
+        body: &str,
+        file: &str,
+        line: &str,
+        _original_suggestion: Option<&str>,
+    ) -> Result<(), PrAgentError> {
+        let mr = self.get_mr().await?;
+        match line.parse::<i32>() {
+            Ok(line_num) if line_num > 0 => self.post_discussion(&mr, body, file, line_num).await,
+            _ => {
+                let text = format!("**{file}**\n\n{body}");
+                self.publish_comment(&text, false).await?;
+                Ok(())
+            }
+        }
+    }
+
+    async fn publish_inline_comments(
+        &self,
+        comments: &[InlineComment],
+    ) -> Result<(), PrAgentError> {
+        if comments.is_empty() {
+            return Ok(());
+        }
+        // GitLab has no batched review API — each comment is its own discussion.
+        let mr = self.get_mr().await?;
+        for comment in comments {
+            if let Err(e) = self
+                .post_discussion(&mr, &comment.body, &comment.path, comment.line)
+                .await
+            {
+                tracing::warn!(path = comment.path, error = %e, "inline comment failed");
+            }
+        }
+        Ok(())
+    }
+
+    async fn remove_initial_comment(&self) -> Result<(), PrAgentError> {
+        Ok(())
+    }
+
+    async fn remove_comment(&self, comment_id: &CommentId) -> Result<(), PrAgentError> {
+        let path = format!("{}/notes/{}", self.mr_path(), comment_id.0);
+        self.api_delete(&path).await
+    }
+
+    async fn publish_code_suggestions(
+        &self,
+        suggestions: &[CodeSuggestion],
+    ) -> Result<Option<String>, PrAgentError> {
+        if suggestions.is_empty() {
+            return Ok(None);
+        }
+
+        let mr = self.get_mr().await?;
+        let mut published = false;
+        for s in suggestions {
+            // Anchor on the last line and extend the suggestion upwards.
+            let lines_above = (s.relevant_lines_end - s.relevant_lines_start).max(0);
+            let body = format!(
+                "{}\n\n```suggestion:-{lines_above}+0\n{}\n```",
+                s.body, s.improved_code
+            );
+            match self
+                .post_discussion(&mr, &body, &s.relevant_file, s.relevant_lines_end)
+                .await
+            {
+                Ok(()) => published = true,
+                Err(e) => {
+                    tracing::warn!(file = s.relevant_file, error = %e, "code suggestion failed");
+                }
+            }
+        }
+        if !published {
+            return Err(PrAgentError::GitProvider(
+                "no code suggestion could be published".into(),
+            ));
+        }
+        // Discussions are posted one by one; there is no single review to link.
+        Ok(None)
+    }
+
+    async fn publish_labels(&self, labels: &[String]) -> Result<(), PrAgentError> {
+        self.api_put(&self.mr_path(), &json!({"labels": labels.join(",")}))
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_label(&self, label: &str) -> Result<(), PrAgentError> {
+        self.api_put(&self.mr_path(), &json!({"remove_labels": label}))
+            .await?;
+        Ok(())
+    }
+
+    async fn get_pr_labels(&self) -> Result<Vec<String>, PrAgentError> {
+        let mr = self.get_mr().await?;
+        Ok(mr["labels"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|l| l.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn reaction_name(&self, reaction: Reaction) -> Option<&'static str> {
+        Some(match reaction {
+            Reaction::Ack => "eyes",
+            Reaction::Success => "white_check_mark",
+            Reaction::Failure => "x",
+        })
+    }
+
+    async fn add_reaction(
+        &self,
+        comment_id: u64,
+        reaction: Reaction,
+    ) -> Result<Option<u64>, PrAgentError> {
+        let Some(name) = self.reaction_name(reaction) else {
+            return Ok(None);
+        };
+        let path = format!("{}/notes/{}/award_emoji", self.mr_path(), comment_id);
+        let resp = self.api_post(&path, &json!({ "name": name })).await?;
+        Ok(resp["id"].as_u64())
+    }
+
+    async fn create_pr_file(
+        &self,
+        file_path: &str,
+        contents: &[u8],
+        message: &str,
+    ) -> Result<bool, PrAgentError> {
+        let mr = self.get_mr().await?;
+        let (project_id, branch) = mr_file_target(&mr)?;
+        let path = format!(
+            "projects/{project_id}/repository/files/{}",
+            encode_component(file_path)
+        );
+        let exists = self
+            .api_request(
+                reqwest::Method::HEAD,
+                &format!("{path}?ref={}", encode_component(&branch)),
+                None,
+            )
+            .await
+            .is_ok();
+        if exists {
+            return Ok(false);
+        }
+        // POST only creates; it fails rather than replace a file that
+        // appeared in the meantime.
+        let payload = json!({
+            "branch": branch,
+            "commit_message": message,
+            "encoding": "base64",
+            "content": base64::engine::general_purpose::STANDARD.encode(contents),
+        });
+        self.api_post(&path, &payload).await?;
+        Ok(true)
+    }
+
+    async fn upload_image(&self, name: &str, png: Vec<u8>) -> Result<String, PrAgentError> {
+        let url = format!(
+            "{}/api/v4/projects/{}/uploads",
+            self.web_url,
+            encode_component(&self.project_path)
+        );
+        let (content_type, body) = multipart_file(name, "image/png", &png);
+        let req = self
+            .client
+            .post(&url)
+            .header("PRIVATE-TOKEN", &self.token)
+            .header("User-Agent", "pr-agent-rs")
+            .header("Content-Type", content_type)
+            .body(body);
+        let resp = self.send(&reqwest::Method::POST, req).await?;
+        let upload: serde_json::Value = resp.json().await.map_err(PrAgentError::Http)?;
+        // `url` is relative to the project, e.g. `/uploads/<secret>/<name>`
+        let path = upload["url"]
+            .as_str()
+            .ok_or_else(|| PrAgentError::GitProvider("GitLab upload returned no url".into()))?;
+        Ok(format!("{}/{}{path}", self.web_url, self.project_path))
+    }
+
+    async fn remove_reaction(&self, comment_id: u64, reaction_id: u64) -> Result<(), PrAgentError> {
+        let path = format!(
+            "{}/notes/{}/award_emoji/{}",
+            self.mr_path(),
+            comment_id,
+            reaction_id
+        );
+        self.api_delete(&path).await
+    }
+
+    async fn get_commit_messages(&self) -> Result<String, PrAgentError> {
+        let items = self
+            .api_get_all_pages(&format!("{}/commits", self.mr_path()))
+            .await?;
+        // GitLab lists MR commits newest first.
+        let messages: Vec<String> = items
+            .iter()
+            .rev()
+            .filter_map(|c| c["message"].as_str())
+            .enumerate()
+            .map(|(i, m)| format!("{}. {}", i + 1, m))
+            .collect();
+        Ok(messages.join("\n"))
+    }
+
+    async fn get_repo_settings(&self) -> Result<Option<String>, PrAgentError> {
+        let base_branch = self.get_pr_base_branch().await?;
+        match self.get_file_content(".pr_agent.toml", &base_branch).await {
+            Ok(content) if !content.is_empty() => Ok(Some(content)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn get_repo_file(&self, path: &str) -> Result<Option<String>, PrAgentError> {
+        let base_branch = self.get_pr_base_branch().await?;
+        match self.get_file_content(path, &base_branch).await {
+            Ok(content) if !content.is_empty() => Ok(Some(content)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn get_issue_comments(&self) -> Result<Vec<IssueComment>, PrAgentError> {
+        let path = format!("{}/notes?sort=asc", self.mr_path());
+        let items = self.api_get_all_pages(&path).await?;
+        let comments = items
+            .iter()
+            .filter(|n| n["system"].as_bool() != Some(true))
+            .filter_map(|n| {
+                let id = n["id"].as_u64()?;
+                Some(IssueComment {
+                    id,
+                    body: n["body"].as_str().unwrap_or_default().to_string(),
+                    user: n["author"]["username"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    created_at: n["created_at"].as_str().unwrap_or_default().to_string(),
+                    url: Some(format!("{}#note_{id}", self.pr_url)),
+                })
+            })
+            .collect();
+        Ok(comments)
+    }
+
+    async fn get_review_comments(&self) -> Result<Vec<ReviewComment>, PrAgentError> {
+        let path = format!("{}/discussions?per_page=100", self.mr_path());
+        let discussions = self.api_get_all_pages(&path).await?;
+        Ok(review_comments_from_discussions(&discussions))
+    }
+
+    fn get_pr_url(&self) -> &str {
+        &self.pr_url
+    }
+
+    fn is_supported(&self, capability: Capability) -> bool {
+        matches!(
+            capability,
+            Capability::GfmMarkdown
+                | Capability::Labels
+                | Capability::Reactions
+                | Capability::CodeSuggestions
+                | Capability::InlineComments
+                | Capability::ImageUploads
+        )
+    }
+
+    fn max_comment_chars(&self) -> Option<usize> {
+        Some(MAX_COMMENT_CHARS)
+    }
+
+    async fn get_latest_commit_url(&self) -> Result<String, PrAgentError> {
+        let sha = self.get_pr_head_sha().await?;
+        Ok(format!(
+            "{}/{}/-/commit/{sha}",
+            self.web_url, self.project_path
+        ))
+    }
+
+    async fn get_pr_head_sha(&self) -> Result<String, PrAgentError> {
+        let mr = self.get_mr().await?;
+        Ok(mr["sha"].as_str().unwrap_or_default().to_string())
+    }
+
+    async fn get_head_file_bytes(&self, path: &str) -> Result<Vec<u8>, PrAgentError> {
+        let head_sha = self.get_pr_head_sha().await?;
+        self.get_file_bytes(path, &head_sha).await
+    }
+
+    async fn edit_comment(&self, comment_id: &CommentId, body: &str) -> Result<(), PrAgentError> {
+        let path = format!("{}/notes/{}", self.mr_path(), comment_id.0);
+        // Overflow is cut rather than posted as new notes, which would pile
+        // up with every edit.
+        let body = truncate_comment(body, MAX_COMMENT_CHARS);
+        self.api_put(&path, &json!({"body": body})).await?;
+        Ok(())
+    }
+
+    fn get_pr_id(&self) -> &str {
+        &self.pr_id
+    }
+
+    fn repo_owner_and_name(&self) -> (String, String) {
+        (self.parsed.owner.clone(), self.parsed.repo.clone())
+    }
+
+    async fn get_author_association(&self, login: &str) -> Result<String, PrAgentError> {
+        // `members/all` includes members inherited from parent groups.
+        let path = format!(
+            "projects/{}/members/all?query={}",
+            encode_component(&self.project_path),
+            encode_component(login)
+        );
+        let members = self.api_get_all_pages(&path).await?;
+        let access_level = members
+            .iter()
+            .find(|m| m["username"].as_str() == Some(login))
+            .and_then(|m| m["access_level"].as_u64())
+            .unwrap_or(0);
+        Ok(association_for_access_level(access_level).to_string())
+    }
+}
+
+/// Diff notes on the MR's new lines, from its discussions. A multi-line
+/// note carries its range in `position.line_range`; notes on removed lines
+/// have no `new_line`.
+fn review_comments_from_discussions(discussions: &[serde_json::Value]) -> Vec<ReviewComment> {
+    discussions
+        .iter()
+        .filter_map(|d| d["notes"].as_array())
+        .flatten()
+        .filter(|n| n["system"].as_bool() != Some(true))
+        .filter_map(|n| {
+            let position = &n["position"];
+            let line = i32::try_from(position["new_line"].as_i64()?).ok()?;
+            let start_line = position["line_range"]["start"]["new_line"]
+                .as_i64()
+                .and_then(|l| i32::try_from(l).ok())
+                .unwrap_or(line);
+            Some(ReviewComment {
+                id: n["id"].as_u64()?,
+                path: position["new_path"].as_str()?.to_string(),
+                start_line,
+                line,
+                body: n["body"].as_str().unwrap_or_default().to_string(),
+                user: n["author"]["username"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                // Notes don't carry their award emoji.
+                thumbs_down: false,
+            })
+        })
+        .collect()
+}
+
+/// A `multipart/form-data` body with `bytes` as its `file` field. Returns
+/// the content type, with its boundary, and the body.
+fn multipart_file(name: &str, content_type: &str, bytes: &[u8]) -> (String, Vec<u8>) {
+    const BOUNDARY: &str = "pr-agent-rs-upload-7f3a9c";
+    let name = name.replace(['"', '\r', '\n'], "_");
+    let mut body = format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{name}\"\r\n\
+         Content-Type: {content_type}\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+    (format!("multipart/form-data; boundary={BOUNDARY}"), body)
+}
+
+/// The `(project id, branch)` an MR's new files go to: its source branch, in
+/// the source project. Refuses a fork that doesn't allow commits from
+/// members of the target project, and a source branch with the same name as
+/// the target branch.
+fn mr_file_target(mr: &serde_json::Value) -> Result<(u64, String), PrAgentError> {
+    let Some(source_project) = mr["source_project_id"].as_u64() else {
+        return Err(PrAgentError::GitProvider(
+            "the MR's source project no longer exists".into(),
+        ));
+    };
+    let branch = mr["source_branch"].as_str().unwrap_or_default();
+    if branch.is_empty() || Some(branch) == mr["target_branch"].as_str() {
+        return Err(PrAgentError::GitProvider(format!(
+            "not writing to {branch}: the MR's source branch has the same name as its target \
+             branch"
+        )));
+    }
+    if Some(source_project) != mr["target_project_id"].as_u64()
+        && mr["allow_collaboration"].as_bool() != Some(true)
+    {
+        return Err(PrAgentError::GitProvider(format!(
+            "can't push to project {source_project}: the MR comes from a fork that doesn't \
+             allow commits from members of the target project"
+        )));
+    }
+    Ok((source_project, branch.to_string()))
+}
+
+/// GitHub-style `author_association` for a GitLab access level: the Owner,
+/// Maintainer and Developer roles count as maintainers, Reporter and below
+/// do not.
+fn association_for_access_level(access_level: u64) -> &'static str {
+    match access_level {
+        50.. => "OWNER",
+        40..50 => "MEMBER",
+        30..40 => "COLLABORATOR",
+        _ => "NONE",
+    }
+}
+
+/// Percent-encode a value for use as a single URL path segment or query value.
+///
+/// GitLab addresses projects and repository files by their URL-encoded path
+/// (`group%2Fproject`, `src%2Fmain.rs`).
+fn encode_component(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_component() {
+        assert_eq!(
+            encode_component("group/sub/project"),
+            "group%2Fsub%2Fproject"
+        );
+        assert_eq!(encode_component("src/my file.rs"), "src%2Fmy%20file.rs");
+        assert_eq!(encode_component("main"), "main");
+    }
+
+    #[test]
+    fn test_mr_file_target() {
+        let mr = |source: u64, branch: &str, allow: bool| {
+            json!({
+                "source_project_id": source,
+                "target_project_id": 1,
+                "source_branch": branch,
+                "target_branch": "main",
+                "allow_collaboration": allow,
+            })
+        };
+        assert_eq!(
+            mr_file_target(&mr(1, "feat/x", false)).unwrap(),
+            (1, "feat/x".to_string())
+        );
+        assert_eq!(
+            mr_file_target(&mr(2, "feat/x", true)).unwrap(),
+            (2, "feat/x".to_string())
+        );
+        let fork = mr_file_target(&mr(2, "feat/x", false));
+        assert!(
+            fork.unwrap_err()
+                .to_string()
+                .contains("can't push to project 2")
+        );
+        let same_branch = mr_file_target(&mr(2, "main", true));
+        assert!(same_branch.unwrap_err().to_string().contains("same name"));
+    }
+
+    #[test]
+    fn test_association_for_access_level() {
+        assert_eq!(association_for_access_level(50), "OWNER");
+        assert_eq!(association_for_access_level(40), "MEMBER");
+        assert_eq!(association_for_access_level(30), "COLLABORATOR");
+        assert_eq!(association_for_access_level(20), "NONE");
+        assert_eq!(association_for_access_level(0), "NONE");
+    }
+
+    #[test]
+    fn test_multipart_file() {
+        let (content_type, body) = multipart_file("a\"b.png", "image/png", b"PNG");
+        assert_eq!(
+            content_type,
+            "multipart/form-data; boundary=pr-agent-rs-upload-7f3a9c"
+        );
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--pr-agent-rs-upload-7f3a9c\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"a_b.png\"\r\n\
+             Content-Type: image/png\r\n\r\nPNG\r\n--pr-agent-rs-upload-7f3a9c--\r\n"
+        );
+    }
+
+    #[test]
+    fn test_review_comments_from_discussions() {
+        let discussions = vec![
+            json!({ "notes": [
+                {
+                    "id": 1,
+                    "body": "Why not reuse the parser here?",
+                    "author": { "username": "alice" },
+                    "position": {
+                        "new_path": "src/lib.rs",
+                        "new_line": 12,
+                        "line_range": {
+                            "start": { "new_line": 10 },
+                            "end": { "new_line": 12 },
+                        },
+                    },
+                },
+                { "id": 2, "body": "changed the description", "system": true },
+            ]}),
+            json!({ "notes": [
+                { "id": 3, "body": "General note", "author": { "username": "bob" } },
+                {
+                    "id": 4,
+                    "body": "Removed line",
+                    "position": { "new_path": "src/lib.rs", "new_line": null, "old_line": 4 },
+                },
+            ]}),
+        ];
+        let comments = review_comments_from_discussions(&discussions);
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].path, "src/lib.rs");
+        assert_eq!((comments[0].start_line, comments[0].line), (10, 12));
+        assert_eq!(comments[0].user, "alice");
+    }
+}
@@ -1,13 +1,23 @@
+pub mod diff_cache;
+pub mod dry_run;
 pub mod github;
+pub mod gitlab;
 pub mod types;
 pub mod url_parser;
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use types::*;
 
+use crate::config::prompts::is_repo_prompt_file;
 use crate::error::PrAgentError;
+use crate::output::markdown::split_comment;
+use url_parser::{ProviderType, parse_pr_url};
+
+/// Repo-root file holding the organization's best practices.
+pub const BEST_PRACTICES_FILE: &str = "best_practices.md";
 
 /// Capitalize the first letter of a string.
 fn capitalize_first(s: &str) -> String {
@@ -18,6 +28,94 @@ fn capitalize_first(s: &str) -> String {
     }
 }
 
+/// Hidden marker recording the head commit a persistent comment was generated from.
+const GENERATED_FROM_PREFIX: &str = "<!-- pr-agent:generated-from ";
+
+/// Hidden marker in front of the collapsed copy of a persistent comment's
+/// previous content.
+const PREVIOUS_CONTENT_MARKER: &str = "<!-- pr-agent:previous-content -->";
+
+/// Hidden marker opening each continuation part of a split persistent
+/// comment, followed by the id of the comment it continues.
+const CONTINUES_PREFIX: &str = "<!-- pr-agent:continues ";
+
+/// Room kept in each part of a split persistent comment for the continues
+/// marker.
+const CONTINUES_MARKER_RESERVE: usize = 64;
+
+/// `part` marked as a continuation of comment `first_id`.
+fn continuation_part(first_id: &str, part: &str) -> String {
+    format!("{CONTINUES_PREFIX}{first_id} -->\n{part}")
+}
+
+/// The id of the comment that `body` is a continuation part of.
+fn continues(body: &str) -> Option<&str> {
+    let rest = body.strip_prefix(CONTINUES_PREFIX)?;
+    let (id, _) = rest.split_once(" -->")?;
+    Some(id.trim())
+}
+
+/// Insert a generated-from marker for `head_sha` after the first line of `text`.
+fn with_generated_from(text: &str, head_sha: &str) -> String {
+    let marker = format!("{GENERATED_FROM_PREFIX}{head_sha} -->");
+    match text.split_once('\n') {
+        Some((first, rest)) => format!("{first}\n{marker}\n{rest}"),
+        None => format!("{text}\n{marker}\n"),
+    }
+}
+
+/// The head commit recorded in a persistent comment body.
+fn generated_from(body: &str) -> Option<&str> {
+    let start = body.find(GENERATED_FROM_PREFIX)? + GENERATED_FROM_PREFIX.len();
+    let (sha, _) = body[start..].split_once(" -->")?;
+    Some(sha.trim()).filter(|sha| !sha.is_empty())
+}
+
+/// Add a history-rewritten banner after the header lines of `text` (its
+/// first line and generated-from marker) and a collapsed copy of
+/// `previous_body` at the end.
+///
+/// The copy drops hidden comments, so its markers and checkboxes stay inert,
+/// and any copy it held itself, so only one previous version is kept.
+fn with_previous_content(text: &str, previous_body: &str, previous_sha: &str) -> String {
+    let short_sha = &previous_sha[..previous_sha.len().min(7)];
+    let previous = previous_body
+        .split(PREVIOUS_CONTENT_MARKER)
+        .next()
+        .unwrap_or(previous_body);
+    let previous = strip_html_comments(previous);
+    let banner = format!(
+        "> ⚠️ **History rewritten since last review:** commit `{short_sha}` that the previous \
+         version was generated from is no longer on the branch. The previous version is kept \
+         at the end of this comment.\n"
+    );
+    let mut lines = text.splitn(3, '\n');
+    let (first, marker, rest) = (
+        lines.next().unwrap_or(""),
+        lines.next().unwrap_or(""),
+        lines.next().unwrap_or(""),
+    );
+    format!(
+        "{first}\n{marker}\n{banner}\n{}\n\n{PREVIOUS_CONTENT_MARKER}\n<details><summary>Previous version (commit {short_sha})</summary>\n\n{}\n\n</details>\n",
+        rest.trim_end(),
+        previous.trim()
+    )
+}
+
+/// Remove `<!-- ... -->` comments (an unterminated one to the end).
+fn strip_html_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<!--") {
+        out.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find("-->")
+            .map_or("", |end| &rest[start + end + 3..]);
+    }
+    out.push_str(rest);
+    out
+}
+
 /// Trait for git hosting platform providers (GitHub, GitLab, Bitbucket, etc.).
 ///
 /// Methods with default implementations return `Err(Unsupported)` or sensible
@@ -78,10 +176,12 @@ pub trait GitProvider: Send + Sync {
     async fn remove_comment(&self, comment_id: &CommentId) -> Result<(), PrAgentError>;
 
     /// Publish code suggestions (inline comments with before/after code blocks).
+    ///
+    /// Returns a link to the published review when the provider reports one.
     async fn publish_code_suggestions(
         &self,
         suggestions: &[CodeSuggestion],
-    ) -> Result<bool, PrAgentError>;
+    ) -> Result<Option<String>, PrAgentError>;
 
     /// Apply labels to the PR.
     async fn publish_labels(&self, labels: &[String]) -> Result<(), PrAgentError>;
@@ -89,12 +189,10 @@ pub trait GitProvider: Send + Sync {
     /// Get current PR labels.
     async fn get_pr_labels(&self) -> Result<Vec<String>, PrAgentError>;
 
-    /// Add eyes reaction. Returns reaction ID if successful.
-    async fn add_eyes_reaction(
-        &self,
-        comment_id: u64,
-        disable_eyes: bool,
-    ) -> Result<Option<u64>, PrAgentError>;
+    /// Remove a label from the PR.
+    async fn remove_label(&self, _label: &str) -> Result<(), PrAgentError> {
+        Err(PrAgentError::Unsupported("remove_label".into()))
+    }
 
     /// Remove a reaction from a comment.
     async fn remove_reaction(&self, comment_id: u64, reaction_id: u64) -> Result<(), PrAgentError>;
@@ -123,17 +221,53 @@ pub trait GitProvider: Send + Sync {
         ""
     }
 
-    /// Whether this provider supports a named capability.
-    fn is_supported(&self, _capability: &str) -> bool {
+    /// Whether this provider supports `capability`.
+    fn is_supported(&self, _capability: Capability) -> bool {
         false
     }
 
+    /// Longest comment body the platform accepts, if it has a limit.
+    fn max_comment_chars(&self) -> Option<usize> {
+        None
+    }
+
+    /// Platform emoji for a semantic reaction, or `None` if the platform
+    /// has no reactions (or no equivalent).
+    fn reaction_name(&self, _reaction: Reaction) -> Option<&'static str> {
+        None
+    }
+
+    /// Attach a PNG image to the PR and return its URL
+    /// ([`Capability::ImageUploads`]).
+    async fn upload_image(&self, _name: &str, _png: Vec<u8>) -> Result<String, PrAgentError> {
+        Err(PrAgentError::Unsupported("upload_image".into()))
+    }
+
+    /// Add a reaction to a comment. Returns the reaction ID if one was added.
+    async fn add_reaction(
+        &self,
+        _comment_id: u64,
+        _reaction: Reaction,
+    ) -> Result<Option<u64>, PrAgentError> {
+        Ok(None)
+    }
+
     /// Find an existing comment by header marker, update it, or create a new one.
     ///
     /// Find-or-create a persistent comment:
     /// 1. Search existing comments for `initial_header` marker
     /// 2. If found: edit in place with updated content + commit header
     /// 3. If not found: create new comment
+    ///
+    /// The head commit the comment was generated from is recorded under the
+    /// header. When that commit is gone from the branch at the next update
+    /// (force-push), the new content gets a banner saying so and keeps a
+    /// collapsed copy of the previous content.
+    ///
+    /// Content over [`Self::max_comment_chars`] is split: the first part is
+    /// the persistent comment and the others are marked as its continuation.
+    /// Updates edit those parts in place, post the ones that are new and
+    /// remove the ones no longer needed.
     async fn publish_persistent_comment(
         &self,
         text: &str,
@@ -142,6 +276,12 @@ pub trait GitProvider: Send + Sync {
         name: &str,
         final_update_message: bool,
     ) -> Result<(), PrAgentError> {
+        let head_sha = self.get_pr_head_sha().await.ok().filter(|s| !s.is_empty());
+        let text = match &head_sha {
+            Some(sha) if text.starts_with(initial_header) => with_generated_from(text, sha),
+            _ => text.to_string(),
+        };
+        let text = text.as_str();
         let comments = self.get_issue_comments().await?;
         for comment in &comments {
             if comment.body.starts_with(initial_header) {
@@ -151,6 +291,27 @@ pub trait GitProvider: Send + Sync {
                 );
                 let comment_url = comment.url.as_deref().unwrap_or("");
 
+                let previous_sha = generated_from(&comment.body)
+                    .filter(|prev| head_sha.as_deref().is_some_and(|head| head != *prev));
+                let rewritten = match previous_sha {
+                    Some(prev) => self
+                        .get_pr_commits()
+                        .await
+                        .is_ok_and(|commits| !commits.iter().any(|c| c.sha == prev)),
+                    None => false,
+                };
+                let text = match previous_sha.filter(|_| rewritten) {
+                    Some(prev) => {
+                        tracing::info!(
+                            comment_id = comment.id,
+                            previous_sha = prev,
+                            "history rewritten since the persistent comment was generated"
+                        );
+                        with_previous_content(text, &comment.body, prev)
+                    }
+                    None => text.to_string(),
+                };
+
                 // Add "updated until commit" header
                 let latest_commit_url = self.get_latest_commit_url().await.unwrap_or_default();
                 let updated_text = if !latest_commit_url.is_empty() {
@@ -160,11 +321,33 @@ pub trait GitProvider: Send + Sync {
                     );
                     text.replace(initial_header, &updated_header)
                 } else {
-                    text.to_string()
+                    text
                 };
 
-                self.edit_comment(&CommentId(comment.id.to_string()), &updated_text)
+                let first_id = comment.id.to_string();
+                let parts = self.split_persistent_comment(&updated_text);
+                self.edit_comment(&CommentId(first_id.clone()), &parts[0])
                     .await?;
+                let continuations: Vec<&IssueComment> = comments
+                    .iter()
+                    .filter(|c| continues(&c.body) == Some(first_id.as_str()))
+                    .collect();
+                for (i, part) in parts[1..].iter().enumerate() {
+                    let body = continuation_part(&first_id, part);
+                    match continuations.get(i) {
+                        Some(c) => {
+                            self.edit_comment(&CommentId(c.id.to_string()), &body)
+                                .await?
+                        }
+                        None => {
+                            self.publish_comment(&body, false).await?;
+                        }
+                    }
+                }
+                for stale in continuations.iter().skip(parts.len() - 1) {
+                    self.remove_comment(&CommentId(stale.id.to_string()))
+                        .await?;
+                }
 
                 // Post notification comment linking to updated persistent comment
                 if final_update_message && !comment_url.is_empty() && !latest_commit_url.is_empty()
@@ -179,15 +362,56 @@ pub trait GitProvider: Send + Sync {
             }
         }
         tracing::info!("creating new persistent comment");
-        self.publish_comment(text, false).await?;
+        let parts = self.split_persistent_comment(text);
+        let first_id = self.publish_comment(&parts[0], false).await?;
+        for part in &parts[1..] {
+            let body = match &first_id {
+                Some(id) => continuation_part(&id.0, part),
+                None => part.clone(),
+            };
+            self.publish_comment(&body, false).await?;
+        }
         Ok(())
     }
 
+    /// `text` in parts that fit [`Self::max_comment_chars`] with a continues
+    /// marker; a single part when the platform has no limit.
+    fn split_persistent_comment(&self, text: &str) -> Vec<String> {
+        match self.max_comment_chars() {
+            Some(max) => split_comment(text, max.saturating_sub(CONTINUES_MARKER_RESERVE)),
+            None => vec![text.to_string()],
+        }
+    }
+
     /// Get URL for the latest commit in the PR.
     async fn get_latest_commit_url(&self) -> Result<String, PrAgentError> {
         Ok(String::new())
     }
 
+    /// SHA of the PR's current head commit.
+    async fn get_pr_head_sha(&self) -> Result<String, PrAgentError> {
+        Err(PrAgentError::Unsupported("get_pr_head_sha".into()))
+    }
+
+    /// Raw bytes of `path` at the PR's head commit.
+    async fn get_head_file_bytes(&self, _path: &str) -> Result<Vec<u8>, PrAgentError> {
+        Err(PrAgentError::Unsupported("get_head_file_bytes".into()))
+    }
+
+    /// Commits on the PR branch, oldest first.
+    async fn get_pr_commits(&self) -> Result<Vec<CommitInfo>, PrAgentError> {
+        Err(PrAgentError::Unsupported("get_pr_commits".into()))
+    }
+
+    /// Diff files between two commits of the PR (for incremental review).
+    async fn get_diff_files_between(
+        &self,
+        _base_sha: &str,
+        _head_sha: &str,
+    ) -> Result<Vec<FilePatchInfo>, PrAgentError> {
+        Err(PrAgentError::Unsupported("get_diff_files_between".into()))
+    }
+
     /// Edit an existing comment.
     async fn edit_comment(&self, _comment_id: &CommentId, _body: &str) -> Result<(), PrAgentError> {
         Err(PrAgentError::Unsupported("edit_comment".into()))
@@ -208,15 +432,34 @@ pub trait GitProvider: Send + Sync {
         ))
     }
 
-    /// Create or update a file in the repo (e.g. for changelog pushes).
-    async fn create_or_update_pr_file(
+    /// Every comment left on lines of the PR's new code, replies included.
+    /// Comments on outdated or removed lines are left out.
+    async fn get_review_comments(&self) -> Result<Vec<ReviewComment>, PrAgentError> {
+        Err(PrAgentError::Unsupported("get_review_comments".into()))
+    }
+
+    /// Add a new file to the PR's head branch, in the head repository (the
+    /// fork, for a PR from one). Never replaces a file: returns `Ok(false)`
+    /// when `file_path` already exists there.
+    async fn create_pr_file(
         &self,
         _file_path: &str,
-        _branch: &str,
         _contents: &[u8],
         _message: &str,
+    ) -> Result<bool, PrAgentError> {
+        Err(PrAgentError::Unsupported("create_pr_file".into()))
+    }
+
+    /// Append `text` to a repo file on `branch` (the default branch when
+    /// empty), creating the file if it doesn't exist.
+    async fn append_to_repo_file(
+        &self,
+        _file_path: &str,
+        _branch: &str,
+        _text: &str,
+        _message: &str,
     ) -> Result<(), PrAgentError> {
-        Err(PrAgentError::Unsupported("create_or_update_pr_file".into()))
+        Err(PrAgentError::Unsupported("append_to_repo_file".into()))
     }
 
     /// Auto-approve the PR.
@@ -224,6 +467,11 @@ pub trait GitProvider: Send + Sync {
         Ok(false)
     }
 
+    /// Publish a completed check run on the PR head commit.
+    async fn publish_check_run(&self, _check: &CheckRunReport) -> Result<(), PrAgentError> {
+        Err(PrAgentError::Unsupported("publish_check_run".into()))
+    }
+
     /// Git clone URL for the repository.
     fn get_git_repo_url(&self) -> String {
         String::new()
@@ -234,6 +482,12 @@ pub trait GitProvider: Send + Sync {
         String::new()
     }
 
+    /// Get URL linking to a line of the best practices returned by
+    /// [`Self::get_best_practices`]; empty when they didn't come from here.
+    fn get_best_practices_link(&self, _line: usize) -> String {
+        String::new()
+    }
+
     /// Number of changed files in the PR.
     async fn get_num_of_files(&self) -> Result<usize, PrAgentError> {
         Ok(self.get_diff_files().await?.len())
@@ -253,7 +507,8 @@ pub trait GitProvider: Send + Sync {
         if id.is_empty() { None } else { id.parse().ok() }
     }
 
-    /// Fetch `best_practices.md` content from the repo root.
+    /// Fetch [`BEST_PRACTICES_FILE`] content from the repo root, or wherever
+    /// the provider keeps shared settings when the root has none.
     ///
     /// Returns the file content truncated to `max_lines_allowed`, or empty
     /// string if the file doesn't exist. Config `best_practices.content`
@@ -262,6 +517,50 @@ pub trait GitProvider: Send + Sync {
         Ok(String::new())
     }
 
+    /// Fetch a file from the repository's default branch.
+    ///
+    /// Returns `None` when the file doesn't exist or the provider can't read files.
+    async fn get_repo_file(&self, _path: &str) -> Result<Option<String>, PrAgentError> {
+        Ok(None)
+    }
+
+    /// List the files on the repository's default branch.
+    async fn get_repo_tree(&self) -> Result<RepoTree, PrAgentError> {
+        Err(PrAgentError::Unsupported("get_repo_tree".into()))
+    }
+
+    /// Paths of default-branch files matching the code search `query`,
+    /// best match first.
+    async fn search_code(&self, _query: &str) -> Result<Vec<String>, PrAgentError> {
+        Err(PrAgentError::Unsupported("search_code".into()))
+    }
+
+    /// Fetch the prompt override files under `.pr_agent/prompts/` on the
+    /// default branch, in path order.
+    ///
+    /// Returns an empty list when the provider can't list the repository.
+    async fn get_repo_prompt_files(&self) -> Result<Vec<String>, PrAgentError> {
+        let tree = match self.get_repo_tree().await {
+            Ok(tree) => tree,
+            Err(PrAgentError::Unsupported(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut paths: Vec<&str> = tree
+            .files
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .filter(|path| is_repo_prompt_file(path))
+            .collect();
+        paths.sort_unstable();
+        let mut files = Vec::with_capacity(paths.len());
+        for path in paths {
+            if let Some(content) = self.get_repo_file(path).await? {
+                files.push(content);
+            }
+        }
+        Ok(files)
+    }
+
     /// Fetch repo metadata files (e.g. AGENTS.MD, CLAUDE.MD).
     ///
     /// Returns concatenated content of all found files with headers,
@@ -279,4 +578,219 @@ pub trait GitProvider: Send + Sync {
     async fn get_issue_body(&self, _issue_number: u64) -> Result<(String, String), PrAgentError> {
         Err(PrAgentError::Unsupported("get_issue_body".into()))
     }
+
+    /// Whether `login` is an active member of `team`, given as `org/team-slug`
+    /// or as a slug in the repository owner's organization.
+    async fn is_team_member(&self, _team: &str, _login: &str) -> Result<bool, PrAgentError> {
+        Err(PrAgentError::Unsupported("is_team_member".into()))
+    }
+
+    /// `login`'s access to the repository as a GitHub `author_association`
+    /// (`OWNER`, `MEMBER`, `COLLABORATOR` or `NONE`), for hosts whose comment
+    /// events don't carry one.
+    async fn get_author_association(&self, _login: &str) -> Result<String, PrAgentError> {
+        Err(PrAgentError::Unsupported("get_author_association".into()))
+    }
+
+    /// Fetch the PR's milestone and project-board fields.
+    async fn get_planning_context(&self) -> Result<PlanningContext, PrAgentError> {
+        Err(PrAgentError::Unsupported("get_planning_context".into()))
+    }
+}
+
+/// Build the provider matching the host of a PR/MR URL.
+pub async fn create_provider(pr_url: &str) -> Result<Arc<dyn GitProvider>, PrAgentError> {
+    match parse_pr_url(pr_url)?.provider {
+        ProviderType::GitHub => Ok(Arc::new(github::GithubProvider::new(pr_url).await?)),
+        ProviderType::GitLab => Ok(Arc::new(gitlab::GitlabProvider::new(pr_url)?)),
+        other => Err(PrAgentError::Unsupported(format!(
+            "git provider {other} is not implemented"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_git::{MOCK_HEAD_SHA, MockGitProvider};
+
+    fn persistent(id: u64, body: &str) -> IssueComment {
+        IssueComment {
+            id,
+            body: body.into(),
+            user: "bot".into(),
+            created_at: String::new(),
+            url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_persistent_comment_records_head_sha() {
+        let provider = MockGitProvider::new();
+        provider
+            .publish_persistent_comment(
+                "<!-- pr-agent:review -->\n## Review",
+                "<!-- pr-agent:review -->",
+                "",
+                "review",
+                false,
+            )
+            .await
+            .unwrap();
+        let calls = provider.get_calls();
+        let body = &calls.comments[0].0;
+        assert!(body.starts_with("<!-- pr-agent:review -->\n"));
+        assert_eq!(generated_from(body), Some(MOCK_HEAD_SHA));
+    }
+
+    /// A persistent review that needs several comments of at most 300
+    /// characters.
+    fn long_review(header: &str) -> String {
+        let lines: String = (0..12)
+            .map(|i| format!("- finding number {i:03} of the review\n"))
+            .collect();
+        format!("{header}\n{lines}")
+    }
+
+    #[tokio::test]
+    async fn test_persistent_comment_split_marks_continuations() {
+        let header = "<!-- pr-agent:review -->";
+        let mut provider = MockGitProvider::new();
+        provider.max_comment_chars = Some(300);
+        provider
+            .publish_persistent_comment(&long_review(header), header, "", "review", false)
+            .await
+            .unwrap();
+
+        let calls = provider.get_calls();
+        assert!(calls.comments.len() > 2);
+        assert!(calls.comments[0].0.starts_with(header));
+        for (body, _) in &calls.comments[1..] {
+            assert_eq!(continues(body), Some("mock-comment-1"));
+            assert!(body.len() <= 300);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_persistent_comment_updates_continuations_in_place() {
+        let header = "<!-- pr-agent:review -->";
+        let continuation = |id: u64, of: u64| {
+            persistent(
+                id,
+                &continuation_part(&of.to_string(), "*(part 2/2)*\n\nold"),
+            )
+        };
+        let mut provider = MockGitProvider::new();
+        provider.max_comment_chars = Some(300);
+        provider.issue_comments = vec![
+            persistent(5, &format!("{header}\n## Old review")),
+            continuation(6, 5),
+            continuation(7, 5),
+            continuation(8, 9),
+        ];
+
+        // The new review fits in one comment: its old continuations go.
+        provider
+            .publish_persistent_comment(
+                &format!("{header}\n## New review"),
+                header,
+                "",
+                "review",
+                false,
+            )
+            .await
+            .unwrap();
+        {
+            let calls = provider.get_calls();
+            assert_eq!(calls.edited_comments.len(), 1);
+            assert_eq!(calls.edited_comments[0].0, "5");
+            assert_eq!(calls.removed_comments, vec!["6", "7"]);
+            assert!(calls.comments.is_empty());
+        }
+
+        // More parts than before: the existing one is edited, the rest posted.
+        provider.issue_comments.truncate(2);
+        provider.calls = Default::default();
+        provider
+            .publish_persistent_comment(&long_review(header), header, "", "review", false)
+            .await
+            .unwrap();
+        let calls = provider.get_calls();
+        assert_eq!(calls.edited_comments.len(), 2);
+        assert_eq!(calls.edited_comments[1].0, "6");
+        assert!(!calls.comments.is_empty());
+        for (body, _) in &calls.comments {
+            assert_eq!(continues(body), Some("5"));
+        }
+        assert!(calls.removed_comments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_persistent_comment_after_force_push() {
+        let header = "<!-- pr-agent:review -->";
+        let mut provider = MockGitProvider::new().with_commits(vec![CommitInfo {
+            sha: MOCK_HEAD_SHA.into(),
+            date: String::new(),
+        }]);
+        provider.issue_comments = vec![persistent(
+            5,
+            &format!(
+                "{header}\n{GENERATED_FROM_PREFIX}0ld5ha0 -->\n## Old review\n- [x] done <!-- approve pr self-review -->"
+            ),
+        )];
+        provider
+            .publish_persistent_comment(
+                &format!("{header}\n## New review"),
+                header,
+                "",
+                "review",
+                false,
+            )
+            .await
+            .unwrap();
+
+        let calls = provider.get_calls();
+        let (id, body) = &calls.edited_comments[0];
+        assert_eq!(id, "5");
+        assert!(body.starts_with(header));
+        assert_eq!(generated_from(body), Some(MOCK_HEAD_SHA));
+        assert!(body.contains("History rewritten since last review"));
+        assert!(body.contains("<details><summary>Previous version (commit 0ld5ha0)</summary>\n\n## Old review\n- [x] done"));
+        assert!(!body.contains("approve pr self-review"));
+        assert!(body.find("## New review") < body.find("## Old review"));
+    }
+
+    #[tokio::test]
+    async fn test_persistent_comment_same_history_has_no_banner() {
+        let header = "<!-- pr-agent:review -->";
+        let mut provider = MockGitProvider::new().with_commits(vec![
+            CommitInfo {
+                sha: "0ld5ha0".into(),
+                date: String::new(),
+            },
+            CommitInfo {
+                sha: MOCK_HEAD_SHA.into(),
+                date: String::new(),
+            },
+        ]);
+        provider.issue_comments = vec![persistent(
+            5,
+            &format!("{header}\n{GENERATED_FROM_PREFIX}0ld5ha0 -->\n## Old review"),
+        )];
+        provider
+            .publish_persistent_comment(
+                &format!("{header}\n## New review"),
+                header,
+                "",
+                "review",
+                false,
+            )
+            .await
+            .unwrap();
+
+        let calls = provider.get_calls();
+        let body = &calls.edited_comments[0].1;
+        assert!(!body.contains("History rewritten"));
+        assert!(!body.contains("## Old review"));
+    }
 }
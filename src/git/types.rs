@@ -37,6 +37,9 @@ pub struct FilePatchInfo {
     pub language: Option<String>,
     /// AI-generated summary of changes (populated by AI metadata pass).
     pub ai_file_summary: Option<String>,
+    /// Summary shown instead of a patch too large for the prompt
+    /// (`config.large_patch_policy = "summarize"`).
+    pub patch_summary: Option<String>,
 }
 
 impl FilePatchInfo {
@@ -53,10 +56,19 @@ impl FilePatchInfo {
             num_minus_lines: -1,
             language: None,
             ai_file_summary: None,
+            patch_summary: None,
         }
     }
 }
 
+/// A commit on the PR branch.
+#[derive(Debug, Clone)]
+pub struct CommitInfo {
+    pub sha: String,
+    /// Commit date (RFC 3339).
+    pub date: String,
+}
+
 /// Opaque comment identifier. Platform-specific (e.g. GitHub comment ID).
 #[derive(Debug, Clone)]
 pub struct CommentId(pub String);
@@ -75,6 +87,29 @@ pub struct InlineComment {
     pub side: String,
 }
 
+/// A completed check run recording one tool run on the PR head commit.
+///
+/// `command` is stored as the check's `external_id`, so a "Re-run" click in
+/// the checks UI can be mapped back to the command that produced it.
+#[derive(Debug, Clone)]
+pub struct CheckRunReport {
+    /// Check name shown in the PR checks list (e.g. "pr-agent: review").
+    pub name: String,
+    /// Command line to replay on re-run (e.g. "/review").
+    pub command: String,
+    pub success: bool,
+    pub summary: String,
+}
+
+/// Files on the repository's default branch, for indexing.
+#[derive(Debug, Clone, Default)]
+pub struct RepoTree {
+    /// Identifies this version of the tree (e.g. the git tree SHA).
+    pub revision: String,
+    /// `(path, size in bytes)` of every file.
+    pub files: Vec<(String, u64)>,
+}
+
 /// A code improvement suggestion.
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -87,6 +122,41 @@ pub struct CodeSuggestion {
     pub improved_code: String,
 }
 
+/// Semantic reaction the bot leaves on a triggering comment.
+///
+/// Each provider maps these onto its own emoji set via
+/// [`GitProvider::reaction_name`](super::GitProvider::reaction_name).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Reaction {
+    /// The command was received and is being handled.
+    Ack,
+    /// The command finished successfully.
+    Success,
+    /// The command failed.
+    Failure,
+}
+
+/// Optional platform feature. Tools check it with
+/// [`GitProvider::is_supported`](super::GitProvider::is_supported) and pick
+/// an output the platform can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// GitHub-flavored markdown: tables, `<details>` and other HTML.
+    GfmMarkdown,
+    /// PR labels.
+    Labels,
+    /// Reactions on comments.
+    Reactions,
+    /// Committable code suggestions on diff lines.
+    CodeSuggestions,
+    /// Comments and threaded replies on diff lines.
+    InlineComments,
+    /// Image files attached to the PR, for use in its description or
+    /// comments.
+    ImageUploads,
+}
+
 /// A comment on the PR/issue.
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -98,3 +168,74 @@ pub struct IssueComment {
     /// HTML URL for the comment (for persistent comment link-back).
     pub url: Option<String>,
 }
+
+/// A comment on a line range of the PR's new code, from any author.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct ReviewComment {
+    pub id: u64,
+    pub path: String,
+    /// First line of the range; equal to `line` for a single-line comment.
+    pub start_line: i32,
+    /// Last line of the range.
+    pub line: i32,
+    pub body: String,
+    pub user: String,
+    /// Whether anyone reacted with a thumbs-down.
+    pub thumbs_down: bool,
+}
+
+/// Milestone and project-board context for a PR (deadlines, priority).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlanningContext {
+    pub milestone: Option<Milestone>,
+    pub projects: Vec<ProjectItem>,
+}
+
+/// The milestone a PR is attached to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Milestone {
+    pub title: String,
+    /// Due date (`YYYY-MM-DD`), if one is set.
+    pub due_on: Option<String>,
+}
+
+/// A project board the PR belongs to, with its custom field values
+/// (e.g. `("Priority", "P1")`, `("Status", "In review")`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectItem {
+    pub project: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl PlanningContext {
+    pub fn is_empty(&self) -> bool {
+        self.milestone.is_none() && self.projects.is_empty()
+    }
+
+    /// Render the context as plain-text lines for the `planning_context`
+    /// prompt variable.
+    pub fn to_prompt_context(&self) -> String {
+        let mut out = String::new();
+        if let Some(m) = &self.milestone {
+            out.push_str(&format!("Milestone: {}", m.title));
+            if let Some(due) = &m.due_on {
+                out.push_str(&format!(" (due {due})"));
+            }
+            out.push('\n');
+        }
+        for item in &self.projects {
+            out.push_str(&format!("Project: {}", item.project));
+            if !item.fields.is_empty() {
+                let fields: Vec<String> = item
+                    .fields
+                    .iter()
+                    .map(|(name, value)| format!("{name}: {value}"))
+                    .collect();
+                out.push_str(&format!(" ({})", fields.join(", ")));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
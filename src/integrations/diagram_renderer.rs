@@ -0,0 +1,153 @@
+//! Mermaid diagrams as images (`pr_description.render_diagram_image`).
+//!
+//! Some platforms and markdown viewers show a ```` ```mermaid ```` block as
+//! plain code. The describe diagram is then rendered to PNG by a
+//! Kroki-compatible service at `pr_description.diagram_renderer_url`. On
+//! platforms with [`Capability::ImageUploads`] the PNG is attached to the PR;
+//! elsewhere, or when the upload fails, the image links to the renderer.
+//! The server fetches the renderer URL itself, so neither setting can be
+//! overridden from a PR comment.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::Duration;
+
+use base64::Engine;
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::git::GitProvider;
+use crate::git::types::Capability;
+use crate::util::http_client_builder;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// File name of the uploaded image.
+const IMAGE_NAME: &str = "pr-diagram.png";
+
+/// The mermaid source inside a fenced block, or `None` for other blocks.
+fn mermaid_source(block: &str) -> Option<&str> {
+    let rest = block.trim().strip_prefix("```mermaid")?;
+    let rest = rest.strip_suffix("```").unwrap_or(rest);
+    let source = rest.trim();
+    (!source.is_empty()).then_some(source)
+}
+
+/// URL of `source` rendered as PNG by the Kroki-compatible service at
+/// `renderer_url`: the diagram is zlib-compressed and base64url-encoded
+/// into the path.
+pub fn image_url(renderer_url: &str, source: &str) -> String {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    // Writing to a Vec can't fail
+    let _ = encoder.write_all(source.as_bytes());
+    let compressed = encoder.finish().unwrap_or_default();
+    let encoded = base64::engine::general_purpose::URL_SAFE.encode(compressed);
+    format!(
+        "{}/mermaid/png/{encoded}",
+        renderer_url.trim_end_matches('/')
+    )
+}
+
+/// Markdown showing the fenced mermaid `block` as an image, or `None` when
+/// it isn't a mermaid block or no renderer is configured.
+pub async fn image_markdown(provider: &dyn GitProvider, block: &str) -> Option<String> {
+    let settings = get_settings();
+    let renderer_url = settings.pr_description.diagram_renderer_url.trim();
+    if renderer_url.is_empty() {
+        return None;
+    }
+    let url = image_url(renderer_url, mermaid_source(block)?);
+    if provider.is_supported(Capability::ImageUploads) {
+        match upload(provider, &url).await {
+            Ok(uploaded) => return Some(format!("![Diagram]({uploaded})")),
+            Err(e) => {
+                tracing::warn!(error = %e, "could not attach the diagram image, linking the renderer");
+            }
+        }
+    }
+    Some(format!("![Diagram]({url})"))
+}
+
+/// Fetch the rendered PNG and attach it to the PR.
+async fn upload(provider: &dyn GitProvider, url: &str) -> Result<String, PrAgentError> {
+    let settings = get_settings();
+    let client = http_client_builder(&settings.http, &HashMap::new())?
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| PrAgentError::Other(format!("failed to build renderer HTTP client: {e}")))?;
+    let resp = client.get(url).send().await.map_err(PrAgentError::Http)?;
+    if !resp.status().is_success() {
+        return Err(PrAgentError::Other(format!(
+            "diagram renderer returned {}",
+            resp.status()
+        )));
+    }
+    let png = resp.bytes().await.map_err(PrAgentError::Http)?;
+    provider.upload_image(IMAGE_NAME, png.to_vec()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::sync::Arc;
+
+    use flate2::read::ZlibDecoder;
+
+    use super::*;
+    use crate::config::loader::{load_settings, with_settings};
+    use crate::testing::mock_git::MockGitProvider;
+
+    #[test]
+    fn test_image_url_round_trips() {
+        let source = "flowchart LR\n  A[\"parse\"] --> B[\"render\"]";
+        let url = image_url("https://kroki.example/", source);
+        let encoded = url
+            .strip_prefix("https://kroki.example/mermaid/png/")
+            .unwrap();
+        assert!(!encoded.contains(['+', '/']));
+
+        let compressed = base64::engine::general_purpose::URL_SAFE
+            .decode(encoded)
+            .unwrap();
+        let mut decoded = String::new();
+        ZlibDecoder::new(compressed.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, source);
+    }
+
+    #[test]
+    fn test_mermaid_source() {
+        assert_eq!(
+            mermaid_source("```mermaid\nflowchart LR\n  A --> B\n```"),
+            Some("flowchart LR\n  A --> B")
+        );
+        assert_eq!(mermaid_source("```mermaid\n```"), None);
+        assert_eq!(mermaid_source("```rust\nfn main() {}\n```"), None);
+    }
+
+    #[tokio::test]
+    async fn test_image_markdown_links_renderer_without_uploads() {
+        let provider = MockGitProvider::new().without(&[Capability::ImageUploads]);
+        let block = "```mermaid\nflowchart LR\n  A --> B\n```";
+        let overrides = HashMap::from([(
+            "pr_description.diagram_renderer_url".to_string(),
+            "https://kroki.example.com".to_string(),
+        )]);
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+        let markdown = with_settings(settings, image_markdown(&provider, block))
+            .await
+            .unwrap();
+        assert!(markdown.starts_with("![Diagram](https://kroki.example.com/mermaid/png/"));
+        assert!(provider.get_calls().uploaded_images.is_empty());
+
+        // No renderer configured, the default.
+        let settings = Arc::new(load_settings(&HashMap::new(), None, None).unwrap());
+        assert_eq!(
+            with_settings(settings, image_markdown(&provider, block)).await,
+            None
+        );
+    }
+}
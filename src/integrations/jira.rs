@@ -0,0 +1,383 @@
+//! Jira client for tickets referenced by a PR (`[jira]`).
+//!
+//! Ticket keys are taken from the PR title, branch name, and `/browse/`
+//! links (plus bare keys of `project_keys` in the description), fetched via
+//! the REST API v2, and passed to the `/review` and `/describe` prompts as
+//! `related_tickets`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use minijinja::Value;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::config::types::{JiraConfig, Settings};
+use crate::error::PrAgentError;
+use crate::output::markdown::{sanitize_table_cell, sanitize_user_markdown};
+use crate::util::http_client_builder;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A ticket key such as `PROJ-123`.
+static TICKET_KEY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b([A-Z][A-Z0-9]{1,9}-[1-9][0-9]*)\b").unwrap());
+
+/// A Jira browse link; captures the ticket key.
+static BROWSE_LINK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"/browse/([A-Z][A-Z0-9]{1,9}-[1-9][0-9]*)\b").unwrap());
+
+/// A Jira ticket as used in prompts and output.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Ticket {
+    pub key: String,
+    pub url: String,
+    pub title: String,
+    pub description: String,
+    pub acceptance_criteria: String,
+    pub labels: Vec<String>,
+}
+
+/// Authenticated Jira REST client.
+pub struct JiraClient {
+    client: reqwest::Client,
+    base_url: String,
+    email: String,
+    token: String,
+    acceptance_criteria_field: String,
+}
+
+impl JiraClient {
+    /// Client for `settings.jira`, or `None` when Jira is not configured.
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>, PrAgentError> {
+        let jira = &settings.jira;
+        if !jira.is_configured() {
+            return Ok(None);
+        }
+        let client = http_client_builder(&settings.http, &HashMap::new())?
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| PrAgentError::Other(format!("failed to build Jira HTTP client: {e}")))?;
+        Ok(Some(Self {
+            client,
+            base_url: jira.jira_base_url.trim_end_matches('/').to_string(),
+            email: jira.jira_api_email.clone(),
+            token: jira.jira_api_token.clone(),
+            acceptance_criteria_field: jira.acceptance_criteria_field.clone(),
+        }))
+    }
+
+    /// Fetch the ticket `key` (e.g. `PROJ-123`).
+    pub async fn fetch_ticket(&self, key: &str) -> Result<Ticket, PrAgentError> {
+        let mut fields = "summary,description,labels".to_string();
+        if !self.acceptance_criteria_field.is_empty() {
+            let _ = write!(fields, ",{}", self.acceptance_criteria_field);
+        }
+        let fields: String = url::form_urlencoded::byte_serialize(fields.as_bytes()).collect();
+        let url = format!("{}/rest/api/2/issue/{key}?fields={fields}", self.base_url);
+        let request = self.client.get(&url);
+        let request = if self.email.is_empty() {
+            request.bearer_auth(&self.token)
+        } else {
+            request.basic_auth(&self.email, Some(&self.token))
+        };
+        let resp = request.send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(PrAgentError::Other(format!(
+                "Jira GET {key} {status}: {body}"
+            )));
+        }
+        let issue: serde_json::Value = resp.json().await?;
+        Ok(self.parse_issue(key, &issue))
+    }
+
+    fn parse_issue(&self, key: &str, issue: &serde_json::Value) -> Ticket {
+        let fields = &issue["fields"];
+        let description = field_text(&fields["description"]);
+        let acceptance_criteria = match fields.get(&self.acceptance_criteria_field) {
+            Some(value) if !self.acceptance_criteria_field.is_empty() => field_text(value),
+            _ => acceptance_criteria_section(&description),
+        };
+        let key = issue["key"].as_str().unwrap_or(key).to_string();
+        Ticket {
+            url: format!("{}/browse/{key}", self.base_url),
+            title: fields["summary"].as_str().unwrap_or_default().to_string(),
+            description,
+            acceptance_criteria,
+            labels: fields["labels"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|l| l.as_str().map(String::from))
+                .collect(),
+            key,
+        }
+    }
+}
+
+/// Plain text of a field value: wiki-markup strings as-is, Atlassian
+/// Document Format (API v3 style rich-text custom fields) flattened.
+fn field_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.trim().to_string(),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .map(field_text)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        serde_json::Value::Object(node) => match node.get("text") {
+            Some(text) => field_text(text),
+            None => node.get("content").map(field_text).unwrap_or_default(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// Whether `line` is a wiki-markup (`h2.`) or markdown (`##`) heading.
+fn is_heading(line: &str) -> bool {
+    let t = line.trim_start();
+    let b = t.as_bytes();
+    t.starts_with('#') || (b.len() > 3 && b[0] == b'h' && b[1].is_ascii_digit() && b[2] == b'.')
+}
+
+/// The lines under an "Acceptance Criteria" heading or label, up to the
+/// next heading.
+fn acceptance_criteria_section(description: &str) -> String {
+    let mut lines = description.lines();
+    let found = lines.by_ref().any(|line| {
+        let t = line.trim();
+        t.to_lowercase().contains("acceptance criteria")
+            && (is_heading(t) || t.ends_with(':') || t.starts_with('*'))
+    });
+    if !found {
+        return String::new();
+    }
+    lines
+        .take_while(|line| !is_heading(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Ticket keys referenced by a PR, in order of appearance, without
+/// duplicates, and at most `jira.max_tickets`.
+///
+/// Bare keys count in the title and branch name; in the description only
+/// `/browse/` links and keys of `jira.project_keys` do, since text like
+/// `UTF-8` would otherwise match.
+pub fn find_ticket_keys(
+    jira: &JiraConfig,
+    title: &str,
+    branch: &str,
+    description: &str,
+) -> Vec<String> {
+    let in_project = |key: &str| {
+        jira.project_keys.is_empty()
+            || jira
+                .project_keys
+                .iter()
+                .any(|p| key.split_once('-').is_some_and(|(k, _)| k == p))
+    };
+    let branch = branch.to_uppercase();
+    let bare = |text: &str| {
+        TICKET_KEY_RE
+            .captures_iter(text)
+            .map(|c| c[1].to_string())
+            .collect::<Vec<_>>()
+    };
+    let mut candidates = bare(title);
+    candidates.extend(bare(&branch));
+    candidates.extend(
+        BROWSE_LINK_RE
+            .captures_iter(description)
+            .map(|c| c[1].to_string()),
+    );
+    if !jira.project_keys.is_empty() {
+        candidates.extend(bare(description));
+    }
+
+    let mut keys: Vec<String> = Vec::new();
+    for key in candidates {
+        if in_project(&key) && !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys.truncate(jira.max_tickets);
+    keys
+}
+
+/// Fetch the tickets referenced by a PR. Empty when Jira is not configured;
+/// tickets that fail to load are skipped with a warning.
+pub async fn fetch_related_tickets(
+    settings: &Settings,
+    title: &str,
+    branch: &str,
+    description: &str,
+) -> Vec<Ticket> {
+    let client = match JiraClient::from_settings(settings) {
+        Ok(Some(client)) => client,
+        Ok(None) => return Vec::new(),
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to create Jira client");
+            return Vec::new();
+        }
+    };
+    let mut tickets = Vec::new();
+    for key in find_ticket_keys(&settings.jira, title, branch, description) {
+        match client.fetch_ticket(&key).await {
+            Ok(ticket) => tickets.push(ticket),
+            Err(e) => tracing::warn!(key, error = %e, "failed to fetch Jira ticket"),
+        }
+    }
+    tickets
+}
+
+/// Tickets as the `related_tickets` prompt variable.
+pub fn tickets_prompt_value(tickets: &[Ticket]) -> Value {
+    let tickets: Vec<Value> = tickets
+        .iter()
+        .map(|t| {
+            Value::from_serialize(serde_json::json!({
+                "ticket_url": t.url,
+                "title": t.title,
+                "body": t.description,
+                "requirements": t.acceptance_criteria,
+                "labels": t.labels.join(", "),
+            }))
+        })
+        .collect();
+    Value::from(tickets)
+}
+
+/// Inline HTML links to the tickets (`<a href>KEY</a>: title`), one per
+/// line, for the review table.
+pub fn tickets_html(tickets: &[Ticket]) -> String {
+    tickets
+        .iter()
+        .map(|t| {
+            format!(
+                "<a href='{}'>{}</a>: {}",
+                t.url,
+                t.key,
+                sanitize_table_cell(&t.title)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+/// Markdown section linking the tickets, for the PR description.
+pub fn tickets_markdown(tickets: &[Ticket]) -> String {
+    let mut out = String::from("### 🎫 Related tickets\n");
+    for t in tickets {
+        let _ = writeln!(
+            out,
+            "- [{}]({}): {}",
+            t.key,
+            t.url,
+            sanitize_user_markdown(&t.title)
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jira(project_keys: &[&str]) -> JiraConfig {
+        JiraConfig {
+            jira_base_url: "https://acme.atlassian.net".into(),
+            jira_api_token: "token".into(),
+            project_keys: project_keys.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_ticket_keys() {
+        let keys = find_ticket_keys(
+            &jira(&[]),
+            "PROJ-12: Add pagination",
+            "feature/proj-7-pagination",
+            "Uses UTF-8. See https://acme.atlassian.net/browse/OPS-3 and PROJ-99.",
+        );
+        assert_eq!(keys, vec!["PROJ-12", "PROJ-7", "OPS-3"]);
+
+        // With project keys, bare description mentions count, other projects don't.
+        let keys = find_ticket_keys(&jira(&["PROJ"]), "PROJ-12", "main", "Also PROJ-99, OPS-3");
+        assert_eq!(keys, vec!["PROJ-12", "PROJ-99"]);
+
+        let mut limited = jira(&[]);
+        limited.max_tickets = 1;
+        assert_eq!(find_ticket_keys(&limited, "AB-1 CD-2", "", "").len(), 1);
+    }
+
+    #[test]
+    fn test_acceptance_criteria_section() {
+        let description = "Context here.\n\nh3. Acceptance Criteria\n\
+                           * Lists are paged\n* 50 per page\n\nh3. Notes\nNone";
+        assert_eq!(
+            acceptance_criteria_section(description),
+            "* Lists are paged\n* 50 per page"
+        );
+        assert_eq!(
+            acceptance_criteria_section("Acceptance criteria:\n- works"),
+            "- works"
+        );
+        assert_eq!(acceptance_criteria_section("No criteria here"), "");
+    }
+
+    #[test]
+    fn test_parse_issue() {
+        let settings = crate::config::loader::load_settings(
+            &[
+                ("jira.jira_base_url", "https://acme.atlassian.net/"),
+                ("jira.jira_api_token", "token"),
+                ("jira.acceptance_criteria_field", "customfield_10020"),
+            ]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            None,
+            None,
+        )
+        .unwrap();
+        let client = JiraClient::from_settings(&settings).unwrap().unwrap();
+        let issue = serde_json::json!({
+            "key": "PROJ-12",
+            "fields": {
+                "summary": "Paginate lists",
+                "description": "Long lists are slow.",
+                "labels": ["backend"],
+                "customfield_10020": {
+                    "type": "doc",
+                    "content": [{
+                        "type": "paragraph",
+                        "content": [{"type": "text", "text": "50 per page"}]
+                    }]
+                }
+            }
+        });
+        let ticket = client.parse_issue("PROJ-12", &issue);
+        assert_eq!(ticket.url, "https://acme.atlassian.net/browse/PROJ-12");
+        assert_eq!(ticket.title, "Paginate lists");
+        assert_eq!(ticket.acceptance_criteria, "50 per page");
+        assert_eq!(ticket.labels, vec!["backend"]);
+
+        assert!(
+            tickets_markdown(std::slice::from_ref(&ticket))
+                .contains("- [PROJ-12](https://acme.atlassian.net/browse/PROJ-12): Paginate lists")
+        );
+        assert_eq!(
+            tickets_html(&[ticket]),
+            "<a href='https://acme.atlassian.net/browse/PROJ-12'>PROJ-12</a>: Paginate lists"
+        );
+    }
+}
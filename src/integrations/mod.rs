@@ -0,0 +1,2 @@
+pub mod diagram_renderer;
+pub mod jira;
@@ -1,13 +1,22 @@
 pub mod ai;
+pub mod analytics;
+pub mod audit;
 pub mod cli;
 pub mod config;
 pub mod error;
 pub mod git;
+pub mod integrations;
+pub mod metrics;
+pub mod notify;
 pub mod output;
 pub mod processing;
+pub mod request_context;
 pub mod server;
+pub mod stats;
+pub mod telemetry;
 pub mod template;
 pub mod tools;
+pub mod usage;
 pub mod util;
 
 #[cfg(test)]
@@ -1,15 +1,22 @@
-use tracing_subscriber::EnvFilter;
-
 mod ai;
+mod analytics;
+mod audit;
 mod cli;
 mod config;
 mod error;
 mod git;
+mod integrations;
+mod metrics;
+mod notify;
 mod output;
 mod processing;
+mod request_context;
 mod server;
+mod stats;
+mod telemetry;
 mod template;
 mod tools;
+mod usage;
 mod util;
 
 #[cfg(test)]
@@ -17,11 +24,11 @@ mod testing;
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .init();
+    let telemetry = telemetry::init();
+    let result = cli::run().await;
+    telemetry.shutdown();
 
-    if let Err(e) = cli::run().await {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         std::process::exit(1);
     }
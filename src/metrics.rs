@@ -0,0 +1,420 @@
+//! Prometheus metrics, served by the webhook server at `GET /metrics`.
+//!
+//! A small in-process registry rendered in the Prometheus text format, or in
+//! OpenMetrics when the scraper asks for it. Instrumented code calls the
+//! `record_*` helpers below. In OpenMetrics, histogram buckets carry the
+//! request id of their latest observation as an exemplar, linking a slow
+//! bucket to the logs and trace of one run.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{LazyLock, Mutex};
+
+use crate::ai::types::ChatResponse;
+use crate::error::PrAgentError;
+use crate::request_context;
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::default);
+
+/// Upper bounds (seconds) of the latency histogram buckets.
+const DURATION_BUCKETS: [f64; 10] = [0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// Exposition format of [`render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Prometheus text format 0.0.4.
+    Prometheus,
+    /// OpenMetrics 1.0 text format, with exemplars.
+    OpenMetrics,
+}
+
+impl Format {
+    /// The format asked for by a scrape request's `Accept` header.
+    pub fn from_accept(accept: &str) -> Self {
+        if accept.contains("application/openmetrics-text") {
+            Format::OpenMetrics
+        } else {
+            Format::Prometheus
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Prometheus => "text/plain; version=0.0.4",
+            Format::OpenMetrics => "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    Histogram,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Counter => "counter",
+            Kind::Histogram => "histogram",
+        }
+    }
+}
+
+/// `(name, help, kind)` of every metric, in output order.
+const METRICS: &[(&str, &str, Kind)] = &[
+    (
+        "pr_agent_webhook_events_total",
+        "Webhook events received, by source and event type.",
+        Kind::Counter,
+    ),
+    (
+        "pr_agent_tool_runs_total",
+        "Tool runs, by command and outcome.",
+        Kind::Counter,
+    ),
+    (
+        "pr_agent_tool_run_duration_seconds",
+        "Duration of tool runs, by command.",
+        Kind::Histogram,
+    ),
+    (
+        "pr_agent_ai_requests_total",
+        "AI completion requests, by model and outcome.",
+        Kind::Counter,
+    ),
+    (
+        "pr_agent_ai_request_duration_seconds",
+        "Latency of successful AI completion requests, by model.",
+        Kind::Histogram,
+    ),
+    (
+        "pr_agent_ai_tokens_total",
+        "Tokens used by AI completion requests, by model and kind (prompt or completion).",
+        Kind::Counter,
+    ),
+    (
+        "pr_agent_github_api_retries_total",
+        "GitHub API requests retried after a rate limit response.",
+        Kind::Counter,
+    ),
+];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Observations per bucket in [`DURATION_BUCKETS`], not cumulative.
+    buckets: [u64; DURATION_BUCKETS.len()],
+    /// Latest observation with a request id per bucket; the last one is `+Inf`.
+    exemplars: [Option<Exemplar>; DURATION_BUCKETS.len() + 1],
+    count: u64,
+    sum: f64,
+}
+
+#[derive(Debug, Clone)]
+struct Exemplar {
+    request_id: String,
+    value: f64,
+}
+
+/// Samples keyed by metric name, then by rendered label set.
+#[derive(Debug, Default)]
+struct Registry {
+    counters: Mutex<BTreeMap<&'static str, BTreeMap<String, u64>>>,
+    histograms: Mutex<BTreeMap<&'static str, BTreeMap<String, Histogram>>>,
+}
+
+impl Registry {
+    fn inc(&self, name: &'static str, labels: &[(&str, &str)], by: u64) {
+        let mut counters = self.counters.lock().unwrap();
+        *counters
+            .entry(name)
+            .or_default()
+            .entry(render_labels(labels))
+            .or_default() += by;
+    }
+
+    fn observe(
+        &self,
+        name: &'static str,
+        labels: &[(&str, &str)],
+        value: f64,
+        request_id: Option<String>,
+    ) {
+        let mut histograms = self.histograms.lock().unwrap();
+        let h = histograms
+            .entry(name)
+            .or_default()
+            .entry(render_labels(labels))
+            .or_default();
+        let bucket = DURATION_BUCKETS.iter().position(|&le| value <= le);
+        if let Some(i) = bucket {
+            h.buckets[i] += 1;
+        }
+        if let Some(request_id) = request_id {
+            h.exemplars[bucket.unwrap_or(DURATION_BUCKETS.len())] =
+                Some(Exemplar { request_id, value });
+        }
+        h.count += 1;
+        h.sum += value;
+    }
+
+    fn render(&self, format: Format) -> String {
+        let counters = self.counters.lock().unwrap();
+        let histograms = self.histograms.lock().unwrap();
+        let mut out = String::new();
+        for &(name, help, kind) in METRICS {
+            // OpenMetrics names a counter family without its `_total` suffix.
+            let family = match (format, kind) {
+                (Format::OpenMetrics, Kind::Counter) => name.trim_end_matches("_total"),
+                _ => name,
+            };
+            let _ = writeln!(out, "# HELP {family} {help}");
+            let _ = writeln!(out, "# TYPE {family} {}", kind.as_str());
+            match kind {
+                Kind::Counter => {
+                    for (labels, value) in counters.get(name).into_iter().flatten() {
+                        let _ = writeln!(out, "{name}{} {value}", braced(labels));
+                    }
+                }
+                Kind::Histogram => {
+                    for (labels, h) in histograms.get(name).into_iter().flatten() {
+                        let mut cumulative = 0;
+                        for (i, (le, n)) in DURATION_BUCKETS.iter().zip(h.buckets).enumerate() {
+                            cumulative += n;
+                            let _ = writeln!(
+                                out,
+                                "{name}_bucket{} {cumulative}{}",
+                                braced(&join_labels(labels, &format!("le=\"{le}\""))),
+                                exemplar(format, &h.exemplars[i])
+                            );
+                        }
+                        let _ = writeln!(
+                            out,
+                            "{name}_bucket{} {}{}",
+                            braced(&join_labels(labels, "le=\"+Inf\"")),
+                            h.count,
+                            exemplar(format, &h.exemplars[DURATION_BUCKETS.len()])
+                        );
+                        let _ = writeln!(out, "{name}_sum{} {}", braced(labels), h.sum);
+                        let _ = writeln!(out, "{name}_count{} {}", braced(labels), h.count);
+                    }
+                }
+            }
+        }
+        if format == Format::OpenMetrics {
+            out.push_str("# EOF\n");
+        }
+        out
+    }
+}
+
+/// ` # {request_id="…"} value` after a bucket sample, in OpenMetrics only.
+fn exemplar(format: Format, exemplar: &Option<Exemplar>) -> String {
+    match (format, exemplar) {
+        (Format::OpenMetrics, Some(e)) => format!(
+            " # {{{}}} {}",
+            render_labels(&[("request_id", &e.request_id)]),
+            e.value
+        ),
+        _ => String::new(),
+    }
+}
+
+/// `a="x",b="y"`, with values escaped per the text format.
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    labels
+        .iter()
+        .map(|(k, v)| {
+            let v = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{k}=\"{v}\"")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn join_labels(labels: &str, extra: &str) -> String {
+    if labels.is_empty() {
+        extra.to_string()
+    } else {
+        format!("{labels},{extra}")
+    }
+}
+
+fn braced(labels: &str) -> String {
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{labels}}}")
+    }
+}
+
+fn outcome<T>(result: &Result<T, PrAgentError>) -> &'static str {
+    if result.is_ok() { "success" } else { "error" }
+}
+
+/// Count a received webhook event.
+pub fn record_webhook_event(source: &str, event: &str) {
+    REGISTRY.inc(
+        "pr_agent_webhook_events_total",
+        &[("source", source), ("event", event)],
+        1,
+    );
+}
+
+/// Count a finished tool run and its duration.
+pub fn record_tool_run(command: &str, secs: f64, result: &Result<(), PrAgentError>) {
+    REGISTRY.inc(
+        "pr_agent_tool_runs_total",
+        &[("command", command), ("outcome", outcome(result))],
+        1,
+    );
+    REGISTRY.observe(
+        "pr_agent_tool_run_duration_seconds",
+        &[("command", command)],
+        secs,
+        request_context::current_id(),
+    );
+}
+
+/// Count an AI completion request, with its latency and tokens on success.
+pub fn record_ai_request(model: &str, secs: f64, result: &Result<ChatResponse, PrAgentError>) {
+    REGISTRY.inc(
+        "pr_agent_ai_requests_total",
+        &[("model", model), ("outcome", outcome(result))],
+        1,
+    );
+    let Ok(resp) = result else {
+        return;
+    };
+    REGISTRY.observe(
+        "pr_agent_ai_request_duration_seconds",
+        &[("model", model)],
+        secs,
+        request_context::current_id(),
+    );
+    if let Some(usage) = resp.usage {
+        for (kind, tokens) in [
+            ("prompt", usage.prompt_tokens),
+            ("completion", usage.completion_tokens),
+        ] {
+            REGISTRY.inc(
+                "pr_agent_ai_tokens_total",
+                &[("model", model), ("kind", kind)],
+                tokens.into(),
+            );
+        }
+    }
+}
+
+/// Count a GitHub API request retried after a rate limit.
+pub fn record_github_retry() {
+    REGISTRY.inc("pr_agent_github_api_retries_total", &[], 1);
+}
+
+/// All metrics in the given exposition format.
+pub fn render(format: Format) -> String {
+    REGISTRY.render(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counters_and_histograms() {
+        let registry = Registry::default();
+        registry.inc(
+            "pr_agent_tool_runs_total",
+            &[("command", "review"), ("outcome", "success")],
+            1,
+        );
+        registry.inc(
+            "pr_agent_tool_runs_total",
+            &[("command", "review"), ("outcome", "success")],
+            2,
+        );
+        registry.inc("pr_agent_github_api_retries_total", &[], 1);
+        registry.observe(
+            "pr_agent_ai_request_duration_seconds",
+            &[("model", "gpt-4o")],
+            0.7,
+            None,
+        );
+        registry.observe(
+            "pr_agent_ai_request_duration_seconds",
+            &[("model", "gpt-4o")],
+            1000.0,
+            None,
+        );
+
+        let out = registry.render(Format::Prometheus);
+        assert!(out.contains("# TYPE pr_agent_tool_runs_total counter\n"));
+        assert!(
+            out.contains("pr_agent_tool_runs_total{command=\"review\",outcome=\"success\"} 3\n")
+        );
+        assert!(out.contains("pr_agent_github_api_retries_total 1\n"));
+        assert!(out.contains("# TYPE pr_agent_ai_request_duration_seconds histogram\n"));
+        assert!(out.contains(
+            "pr_agent_ai_request_duration_seconds_bucket{model=\"gpt-4o\",le=\"0.5\"} 0\n"
+        ));
+        assert!(out.contains(
+            "pr_agent_ai_request_duration_seconds_bucket{model=\"gpt-4o\",le=\"1\"} 1\n"
+        ));
+        assert!(out.contains(
+            "pr_agent_ai_request_duration_seconds_bucket{model=\"gpt-4o\",le=\"600\"} 1\n"
+        ));
+        assert!(out.contains(
+            "pr_agent_ai_request_duration_seconds_bucket{model=\"gpt-4o\",le=\"+Inf\"} 2\n"
+        ));
+        assert!(out.contains("pr_agent_ai_request_duration_seconds_count{model=\"gpt-4o\"} 2\n"));
+        // Metrics without samples still declare their type.
+        assert!(out.contains("# TYPE pr_agent_webhook_events_total counter\n"));
+    }
+
+    #[test]
+    fn test_render_openmetrics_with_exemplars() {
+        let registry = Registry::default();
+        registry.inc("pr_agent_github_api_retries_total", &[], 1);
+        registry.observe(
+            "pr_agent_tool_run_duration_seconds",
+            &[("command", "review")],
+            3.0,
+            Some("0001-000007".into()),
+        );
+        registry.observe(
+            "pr_agent_tool_run_duration_seconds",
+            &[("command", "review")],
+            900.0,
+            Some("0001-000008".into()),
+        );
+
+        let out = registry.render(Format::OpenMetrics);
+        assert!(out.contains("# TYPE pr_agent_github_api_retries counter\n"));
+        assert!(out.contains("pr_agent_github_api_retries_total 1\n"));
+        assert!(out.contains(
+            "pr_agent_tool_run_duration_seconds_bucket{command=\"review\",le=\"5\"} 1 \
+             # {request_id=\"0001-000007\"} 3\n"
+        ));
+        assert!(out.contains(
+            "pr_agent_tool_run_duration_seconds_bucket{command=\"review\",le=\"+Inf\"} 2 \
+             # {request_id=\"0001-000008\"} 900\n"
+        ));
+        assert!(out.ends_with("# EOF\n"));
+
+        // The Prometheus format has no exemplars.
+        let plain = registry.render(Format::Prometheus);
+        assert!(!plain.contains("request_id"));
+        assert!(!plain.contains("# EOF"));
+    }
+
+    #[test]
+    fn test_label_escaping() {
+        assert_eq!(
+            render_labels(&[("event", "a\"b\\c\nd"), ("source", "github")]),
+            "event=\"a\\\"b\\\\c\\nd\",source=\"github\""
+        );
+    }
+}
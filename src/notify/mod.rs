@@ -0,0 +1,253 @@
+//! Chat notifications after tool runs (`[notifications]`).
+//!
+//! Tools record a one-line summary of what they published with
+//! [`set_summary`]; once the run finishes, the summary is posted to the
+//! configured Slack and Teams webhooks, e.g. "Review posted for PR #123:
+//! effort 3/5, 2 key issues, security concerns flagged".
+
+pub mod slack;
+pub mod teams;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::types::{NotificationsConfig, Settings};
+use crate::error::PrAgentError;
+use crate::output::improve_formatter::ParsedSuggestion;
+use crate::output::review_formatter::{extract_effort_score, is_value_no, yaml_value_to_string};
+use crate::util::http_client_builder;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+tokio::task_local! {
+    /// Summary recorded by the tool currently running in this task.
+    static SUMMARY: Mutex<Option<String>>;
+}
+
+/// Run `fut`, returning its output and the summary it recorded, if any.
+pub async fn track_summary<F, T>(fut: F) -> (T, Option<String>)
+where
+    F: std::future::Future<Output = T>,
+{
+    SUMMARY
+        .scope(Mutex::new(None), async {
+            let out = fut.await;
+            let summary = SUMMARY
+                .try_with(|s| s.lock().ok().and_then(|s| s.clone()))
+                .ok()
+                .flatten();
+            (out, summary)
+        })
+        .await
+}
+
+/// Record the current tool's summary; a no-op outside [`track_summary`].
+pub fn set_summary(summary: impl Into<String>) {
+    let _ = SUMMARY.try_with(|s| {
+        if let Ok(mut s) = s.lock() {
+            *s = Some(summary.into());
+        }
+    });
+}
+
+/// A finished tool run, as sent to chat.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// Canonical tool name (`review`, `describe`, ...).
+    pub tool: String,
+    pub pr_url: String,
+    pub pr_number: Option<u64>,
+    pub success: bool,
+    /// What the tool published, or the error when it failed.
+    pub summary: String,
+}
+
+impl Notification {
+    /// Lead of the message, e.g. "Review posted" or "/improve failed".
+    pub fn headline(&self) -> String {
+        if !self.success {
+            return format!("/{} failed", self.tool);
+        }
+        match self.tool.as_str() {
+            "review" => "Review posted".into(),
+            "describe" => "Description updated".into(),
+            "improve" => "Code suggestions posted".into(),
+            tool => format!("/{tool} completed"),
+        }
+    }
+}
+
+/// Whether a run of `tool` with this outcome should be sent.
+pub fn should_notify(config: &NotificationsConfig, tool: &str, success: bool) -> bool {
+    let has_target = !config.slack_webhook_url.is_empty() || !config.teams_webhook_url.is_empty();
+    has_target && config.events.iter().any(|e| e == tool) && (success || config.notify_on_failure)
+}
+
+/// Post `notification` to every configured webhook. Failures are logged,
+/// never returned.
+pub async fn send(settings: &Settings, notification: &Notification) {
+    let client = match http_client_builder(&settings.http, &HashMap::new()).and_then(|b| {
+        b.timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(PrAgentError::Http)
+    }) {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to build notification HTTP client");
+            return;
+        }
+    };
+    let config = &settings.notifications;
+    let targets = [
+        (
+            "slack",
+            &config.slack_webhook_url,
+            slack::payload(notification),
+        ),
+        (
+            "teams",
+            &config.teams_webhook_url,
+            teams::payload(notification),
+        ),
+    ];
+    for (target, url, payload) in targets {
+        if url.is_empty() {
+            continue;
+        }
+        let sent = client
+            .post(url.as_str())
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        if let Err(e) = sent {
+            tracing::warn!(target, error = %e, "failed to send notification");
+        }
+    }
+}
+
+/// Summary of a published review: effort, key issues, and security.
+pub fn summarize_review(data: &serde_yaml_ng::Value) -> String {
+    let review = data.get("review").unwrap_or(data);
+    let mut parts = Vec::new();
+    if let Some(effort) = review
+        .get("estimated_effort_to_review_[1-5]")
+        .or_else(|| review.get("estimated_effort_to_review"))
+        .map(extract_effort_score)
+        .filter(|&e| e > 0)
+    {
+        parts.push(format!("effort {effort}/5"));
+    }
+    let issues = review
+        .get("key_issues_to_review")
+        .and_then(|v| v.as_sequence())
+        .map_or(0, |s| s.len());
+    parts.push(match issues {
+        1 => "1 key issue".to_string(),
+        n => format!("{n} key issues"),
+    });
+    if let Some(security) = review.get("security_concerns") {
+        parts.push(if is_value_no(&yaml_value_to_string(security)) {
+            "no security concerns".to_string()
+        } else {
+            "security concerns flagged".to_string()
+        });
+    }
+    parts.join(", ")
+}
+
+/// Summary of published code suggestions: count and how many are critical.
+pub fn summarize_suggestions(suggestions: &[ParsedSuggestion], th_high: u32) -> String {
+    let critical = suggestions.iter().filter(|s| s.score >= th_high).count();
+    match suggestions.len() {
+        0 => "no suggestions".to_string(),
+        1 => format!("1 suggestion ({critical} critical)"),
+        n => format!("{n} suggestions ({critical} critical)"),
+    }
+}
+
+/// Summary of a generated description: its title (`fallback_title` when the
+/// response has none) and PR type.
+pub fn summarize_description(data: &serde_yaml_ng::Value, fallback_title: &str) -> String {
+    let title = data
+        .get("title")
+        .map(yaml_value_to_string)
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| fallback_title.to_string());
+    let title = title.trim();
+    let pr_type = data
+        .get("type")
+        .map(yaml_value_to_string)
+        .unwrap_or_default()
+        .replace('\n', ", ");
+    if pr_type.is_empty() {
+        format!("\"{title}\"")
+    } else {
+        format!("\"{title}\" ({pr_type})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(events: &[&str], slack: &str) -> NotificationsConfig {
+        NotificationsConfig {
+            slack_webhook_url: slack.into(),
+            events: events.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_should_notify() {
+        let config = config(&["review"], "https://hooks.slack.com/services/x");
+        assert!(should_notify(&config, "review", true));
+        assert!(should_notify(&config, "review", false));
+        assert!(!should_notify(&config, "describe", true));
+
+        let quiet = NotificationsConfig {
+            notify_on_failure: false,
+            ..config.clone()
+        };
+        assert!(!should_notify(&quiet, "review", false));
+
+        // No webhook configured.
+        assert!(!should_notify(
+            &self::config(&["review"], ""),
+            "review",
+            true
+        ));
+    }
+
+    #[test]
+    fn test_summarize_review() {
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(
+            r#"
+review:
+  estimated_effort_to_review_[1-5]: 3
+  security_concerns: "SQL injection: query built from input"
+  key_issues_to_review:
+    - issue_header: A
+    - issue_header: B
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            summarize_review(&data),
+            "effort 3/5, 2 key issues, security concerns flagged"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_track_summary() {
+        let ((), summary) =
+            track_summary(async { set_summary("3 suggestions (1 critical)") }).await;
+        assert_eq!(summary.as_deref(), Some("3 suggestions (1 critical)"));
+        let ((), none) = track_summary(async {}).await;
+        assert!(none.is_none());
+        // Outside a tracked scope recording is a no-op.
+        set_summary("ignored");
+    }
+}
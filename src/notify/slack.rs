@@ -0,0 +1,52 @@
+//! Slack incoming-webhook payloads.
+
+use super::Notification;
+
+/// Message for a Slack incoming webhook, in Slack's `mrkdwn` format.
+pub fn payload(notification: &Notification) -> serde_json::Value {
+    let pr = match notification.pr_number {
+        Some(n) => format!("PR #{n}"),
+        None => "PR".to_string(),
+    };
+    let link = if notification.pr_url.is_empty() {
+        pr
+    } else {
+        format!("<{}|{}>", notification.pr_url, escape(&pr))
+    };
+    let icon = if notification.success { "✅" } else { "❌" };
+    serde_json::json!({
+        "text": format!(
+            "{icon} {} for {link}: {}",
+            escape(&notification.headline()),
+            escape(&notification.summary)
+        ),
+    })
+}
+
+/// Escape the characters Slack treats as control sequences.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let notification = Notification {
+            tool: "review".into(),
+            pr_url: "https://github.com/o/r/pull/123".into(),
+            pr_number: Some(123),
+            success: true,
+            summary: "effort 3/5, <2> key issues".into(),
+        };
+        assert_eq!(
+            payload(&notification)["text"],
+            "✅ Review posted for <https://github.com/o/r/pull/123|PR #123>: \
+             effort 3/5, &lt;2&gt; key issues"
+        );
+    }
+}
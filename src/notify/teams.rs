@@ -0,0 +1,59 @@
+//! Microsoft Teams webhook payloads.
+
+use super::Notification;
+
+/// Adaptive Card message, as accepted by Teams workflow webhooks.
+pub fn payload(notification: &Notification) -> serde_json::Value {
+    let title = match notification.pr_number {
+        Some(n) => format!("{} for PR #{n}", notification.headline()),
+        None => format!("{} for PR", notification.headline()),
+    };
+    let mut card = serde_json::json!({
+        "type": "AdaptiveCard",
+        "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+        "version": "1.4",
+        "body": [
+            {
+                "type": "TextBlock",
+                "text": title,
+                "weight": "Bolder",
+                "color": if notification.success { "Good" } else { "Attention" },
+                "wrap": true,
+            },
+            {"type": "TextBlock", "text": notification.summary, "wrap": true},
+        ],
+    });
+    if !notification.pr_url.is_empty() {
+        card["actions"] = serde_json::json!([
+            {"type": "Action.OpenUrl", "title": "Open PR", "url": notification.pr_url},
+        ]);
+    }
+    serde_json::json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "content": card,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let notification = Notification {
+            tool: "improve".into(),
+            pr_url: "https://github.com/o/r/pull/7".into(),
+            pr_number: Some(7),
+            success: false,
+            summary: "rate limited".into(),
+        };
+        let card = &payload(&notification)["attachments"][0]["content"];
+        assert_eq!(card["body"][0]["text"], "/improve failed for PR #7");
+        assert_eq!(card["body"][0]["color"], "Attention");
+        assert_eq!(card["body"][1]["text"], "rate limited");
+        assert_eq!(card["actions"][0]["url"], "https://github.com/o/r/pull/7");
+    }
+}
@@ -24,6 +24,12 @@ pub struct FileStats {
     pub num_minus_lines: i32,
     /// Link to the file in the PR diff page.
     pub link: String,
+    /// New-side line range of the file's largest hunk, if any.
+    pub main_hunk: Option<(usize, usize)>,
+    /// CODEOWNERS owners of the file (`config.include_codeowners`).
+    pub owners: Vec<String>,
+    /// Detected language of the file.
+    pub language: Option<String>,
 }
 
 /// Convert parsed describe YAML into a formatted PR title + body.
@@ -32,12 +38,10 @@ pub struct FileStats {
 pub fn format_describe_output(
     data: &serde_yaml_ng::Value,
     original_title: &str,
-    original_body: &str,
     config: &PrDescriptionConfig,
     file_stats: &HashMap<String, FileStats>,
 ) -> DescribeOutput {
     let generate_ai_title = config.generate_ai_title;
-    let add_original_description = config.add_original_user_description;
     let enable_semantic_files_types = config.enable_semantic_files_types;
     let marker = persistent_comment_marker("describe");
 
@@ -53,39 +57,13 @@ pub fn format_describe_output(
         original_title.trim().to_string()
     };
 
-    let pr_type = data
-        .get("type")
-        .map(|v| {
-            // AI may return type as a string or as a list of strings
-            if let Some(s) = v.as_str() {
-                s.trim().to_string()
-            } else if let Some(seq) = v.as_sequence() {
-                seq.iter()
-                    .filter_map(|item| item.as_str())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            } else {
-                String::new()
-            }
-        })
-        .unwrap_or_default();
-
-    let description = data
-        .get("description")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
+    let pr_type = extract_pr_type(data);
+    let description = format_description_bullets(data);
 
-    // Build body
-    // The user's original description MUST come BEFORE the marker so that
-    // `strip_pr_agent_content()` can recover it on subsequent runs.
-    // (It returns everything before `<!-- pr-agent:` .)
+    // Build body. The author's own text around it is kept by
+    // `DescriptionDocument` when publishing.
     let mut body = String::with_capacity(4_000);
 
-    if add_original_description && !original_body.is_empty() {
-        let _ = writeln!(body, "{original_body}");
-        let _ = writeln!(body, "\n---\n");
-    }
-
     let _ = writeln!(body, "{marker}");
 
     if config.enable_pr_type {
@@ -99,64 +77,25 @@ pub fn format_describe_output(
 
     let _ = writeln!(body, "### **Description**");
     if !description.is_empty() {
-        // Format description as bullet points if it isn't already
-        for line in description.lines() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                body.push('\n');
-            } else if trimmed.starts_with('-') || trimmed.starts_with('*') {
-                let _ = writeln!(body, "{trimmed}");
-            } else {
-                let _ = writeln!(body, "- {trimmed}");
-            }
-        }
-        body.push('\n');
+        let _ = writeln!(body, "{description}");
     }
 
     let _ = writeln!(body, "\n___\n");
 
     // Diagram
-    if let Some(diagram) = data.get("changes_diagram") {
-        let diagram_str = diagram.as_str().unwrap_or("").trim();
-        if !diagram_str.is_empty() {
-            let _ = writeln!(body, "### Diagram Walkthrough\n");
-            // Sanitize mermaid content: quote text with special chars like (){}
-            let sanitized = sanitize_mermaid(diagram_str);
-            // Preserve existing fences from AI, only add closing if missing.
-            if sanitized.starts_with("```") {
-                let mut d = sanitized;
-                if !d.ends_with("```") {
-                    d.push_str("\n```");
-                }
-                let _ = writeln!(body, "{d}\n");
-            } else {
-                let _ = writeln!(body, "```mermaid\n{sanitized}\n```\n");
-            }
-        }
+    let diagram = format_diagram(data);
+    if !diagram.is_empty() {
+        let _ = writeln!(body, "### Diagram Walkthrough\n");
+        let _ = writeln!(body, "{diagram}\n");
     }
 
     // Changes walkthrough / PR files
-    if enable_semantic_files_types && let Some(files) = data.get("pr_files") {
-        let mut walkthrough = String::new();
-        format_pr_files(
-            files,
-            &mut walkthrough,
-            &config.collapsible_file_list,
-            config.collapsible_file_list_threshold,
-            file_stats,
-        );
-        if !walkthrough.is_empty() {
-            let _ = writeln!(
-                body,
-                "<details> <summary><h3> File Walkthrough</h3></summary>\n"
-            );
-            body.push_str(&walkthrough);
-            let _ = writeln!(body, "\n</details>\n");
-        }
+    if enable_semantic_files_types {
+        body.push_str(&format_walkthrough(data, config, file_stats));
     }
 
     // Labels
-    let labels = extract_labels(data, &pr_type);
+    let labels = describe_labels(data, config);
 
     DescribeOutput {
         title,
@@ -165,6 +104,171 @@ pub fn format_describe_output(
     }
 }
 
+/// Sections that `use_description_markers` can fill, by placeholder key.
+const DESCRIPTION_MARKER_KEYS: &[&str] = &["type", "summary", "diagram", "walkthrough"];
+
+/// Fill the `pr_agent:<key>` placeholders of an author-written description.
+///
+/// Each placeholder is replaced by a `<!-- pr_agent:<key> -->` ...
+/// `<!-- /pr_agent:<key> -->` region holding the generated section, so a
+/// later run replaces the same region again. Everything outside the regions
+/// is kept as written. Returns `None` when the body has no placeholders.
+pub fn fill_description_markers(
+    body: &str,
+    data: &serde_yaml_ng::Value,
+    config: &PrDescriptionConfig,
+    file_stats: &HashMap<String, FileStats>,
+) -> Option<String> {
+    let mut filled = body.to_string();
+    let mut found = false;
+    for key in DESCRIPTION_MARKER_KEYS {
+        let re = Regex::new(&format!(
+            r"(?s)<!-- pr_agent:{key} -->.*?<!-- /pr_agent:{key} -->|\bpr_agent:{key}\b"
+        ))
+        .expect("marker regex is valid");
+        if !re.is_match(&filled) {
+            continue;
+        }
+        found = true;
+        let content = match *key {
+            "type" => extract_pr_type(data),
+            "summary" => format_description_bullets(data),
+            "diagram" => format_diagram(data),
+            _ => format_walkthrough(data, config, file_stats),
+        };
+        let region = format!(
+            "<!-- pr_agent:{key} -->\n{}\n<!-- /pr_agent:{key} -->",
+            content.trim()
+        );
+        filled = re
+            .replace_all(&filled, regex::NoExpand(&region))
+            .into_owned();
+    }
+    found.then_some(filled)
+}
+
+/// Labels for the describe result, as `format_describe_output` reports them:
+/// the PR type labels followed by the aggregated file-type labels.
+pub fn describe_labels(data: &serde_yaml_ng::Value, config: &PrDescriptionConfig) -> Vec<String> {
+    let mut labels = extract_labels(data, &extract_pr_type(data));
+    for label in file_type_labels(data, &config.file_label_mapping) {
+        if !labels.iter().any(|l| l.eq_ignore_ascii_case(&label)) {
+            labels.push(label);
+        }
+    }
+    labels
+}
+
+/// PR-level labels for the per-file `label`s in `pr_files`, via `mapping`
+/// (lowercase file label → PR label). Unmapped file labels are dropped.
+fn file_type_labels(data: &serde_yaml_ng::Value, mapping: &HashMap<String, String>) -> Vec<String> {
+    let Some(files) = data.get("pr_files").and_then(|v| v.as_sequence()) else {
+        return Vec::new();
+    };
+    let mut labels: Vec<String> = Vec::new();
+    for file in files {
+        let Some(label) = file.get("label").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let Some(mapped) = mapping.get(&label.trim().to_lowercase()) else {
+            continue;
+        };
+        let mapped = mapped.trim();
+        if !mapped.is_empty() && !labels.iter().any(|l| l == mapped) {
+            labels.push(mapped.to_string());
+        }
+    }
+    labels
+}
+
+/// PR type as a comma-separated string; the AI may return a string or a list.
+fn extract_pr_type(data: &serde_yaml_ng::Value) -> String {
+    data.get("type")
+        .map(|v| {
+            if let Some(s) = v.as_str() {
+                s.trim().to_string()
+            } else if let Some(seq) = v.as_sequence() {
+                seq.iter()
+                    .filter_map(|item| item.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            } else {
+                String::new()
+            }
+        })
+        .unwrap_or_default()
+}
+
+/// The AI description as bullet points (lines not already bulleted get `- `).
+fn format_description_bullets(data: &serde_yaml_ng::Value) -> String {
+    let description = data
+        .get("description")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let mut out = String::new();
+    for line in description.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            out.push('\n');
+        } else if trimmed.starts_with('-') || trimmed.starts_with('*') {
+            let _ = writeln!(out, "{trimmed}");
+        } else {
+            let _ = writeln!(out, "- {trimmed}");
+        }
+    }
+    out
+}
+
+/// The `changes_diagram` as a fenced mermaid block, or empty when missing.
+pub fn format_diagram(data: &serde_yaml_ng::Value) -> String {
+    let diagram = data
+        .get("changes_diagram")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim();
+    if diagram.is_empty() {
+        return String::new();
+    }
+    // Sanitize mermaid content: quote text with special chars like (){}
+    let sanitized = sanitize_mermaid(diagram);
+    // Preserve existing fences from AI, only add closing if missing.
+    if sanitized.starts_with("```") {
+        let mut d = sanitized;
+        if !d.ends_with("```") {
+            d.push_str("\n```");
+        }
+        d
+    } else {
+        format!("```mermaid\n{sanitized}\n```")
+    }
+}
+
+/// The collapsible "File Walkthrough" section, or empty when there are no files.
+fn format_walkthrough(
+    data: &serde_yaml_ng::Value,
+    config: &PrDescriptionConfig,
+    file_stats: &HashMap<String, FileStats>,
+) -> String {
+    let Some(files) = data.get("pr_files") else {
+        return String::new();
+    };
+    let mut walkthrough = String::new();
+    format_pr_files(
+        files,
+        &mut walkthrough,
+        &config.collapsible_file_list,
+        config.collapsible_file_list_threshold,
+        config.enable_ask_line_snippets,
+        file_stats,
+    );
+    if walkthrough.is_empty() {
+        return String::new();
+    }
+    format!(
+        "<details> <summary><h3> File Walkthrough</h3></summary>\n\n{walkthrough}\n</details>\n\n"
+    )
+}
+
 /// Format the PR files section as a nested HTML table grouped by label.
 ///
 /// The `collapsible` config controls the **per-category** `<details>` nesting
@@ -175,6 +279,7 @@ fn format_pr_files(
     out: &mut String,
     collapsible: &BoolOrString,
     threshold: u32,
+    ask_line_snippets: bool,
     file_stats: &HashMap<String, FileStats>,
 ) {
     let file_list = match files.as_sequence() {
@@ -230,7 +335,7 @@ fn format_pr_files(
         }
 
         for entry in files {
-            write_file_row(out, entry, file_stats);
+            write_file_row(out, entry, file_stats, ask_line_snippets);
         }
 
         if use_collapsible {
@@ -244,7 +349,8 @@ fn format_pr_files(
 }
 
 /// A single file entry parsed from the AI YAML.
-struct FileEntry {
+#[derive(Debug, serde::Serialize)]
+pub struct FileEntry {
     filename: String,
     changes_title: String,
     changes_summary: String,
@@ -252,7 +358,7 @@ struct FileEntry {
 }
 
 impl FileEntry {
-    fn from_yaml(item: &serde_yaml_ng::Value) -> Self {
+    pub(crate) fn from_yaml(item: &serde_yaml_ng::Value) -> Self {
         let filename = item
             .get("filename")
             .and_then(|v| v.as_str())
@@ -296,7 +402,12 @@ impl FileEntry {
 /// Write a single file `<tr>` row to the output.
 ///
 /// Writes a single file entry as an HTML table row with optional diff stats link.
-fn write_file_row(out: &mut String, entry: &FileEntry, file_stats: &HashMap<String, FileStats>) {
+fn write_file_row(
+    out: &mut String,
+    entry: &FileEntry,
+    file_stats: &HashMap<String, FileStats>,
+    ask_line_snippets: bool,
+) {
     let short_name = entry.short_name();
 
     // Build filename_publish with title
@@ -311,7 +422,8 @@ fn write_file_row(out: &mut String, entry: &FileEntry, file_stats: &HashMap<Stri
 
     // Look up diff stats (case-insensitive, strip leading '/')
     let lookup_key = entry.filename.trim_start_matches('/').to_lowercase();
-    let (diff_pm, delta_nbsp, link) = if let Some(stats) = file_stats.get(&lookup_key) {
+    let stats = file_stats.get(&lookup_key);
+    let (diff_pm, delta_nbsp, link) = if let Some(stats) = stats {
         let mut pm = format!("+{}/-{}", stats.num_plus_lines, stats.num_minus_lines);
         if pm.len() > 12 || pm == "+0/-0" {
             pm = "[link]".to_string();
@@ -330,11 +442,31 @@ fn write_file_row(out: &mut String, entry: &FileEntry, file_stats: &HashMap<Stri
         String::new()
     };
 
+    // Pre-filled /ask_line command targeting the file's main hunk
+    let ask_line = match stats.and_then(|s| s.main_hunk) {
+        Some((start, end)) if ask_line_snippets => format!(
+            "<dd><code>{}</code></dd>",
+            ask_line_command(&entry.filename, start, end)
+        ),
+        _ => String::new(),
+    };
+
+    let owners = match stats {
+        Some(stats) if !stats.owners.is_empty() => {
+            format!("<dd>Owners: {}</dd>", stats.owners.join(" "))
+        }
+        _ => String::new(),
+    };
+    let language = match stats.and_then(|s| s.language.as_deref()) {
+        Some(language) => format!("<dd>Language: {language}</dd>"),
+        None => String::new(),
+    };
+
     if entry.changes_summary.is_empty() {
         // No summary: simple row without description
         let _ = write!(
             out,
-            "\n<tr>\n  <td>{filename_publish}</td>\n  <td>{link_cell}</td>\n\n</tr>\n"
+            "\n<tr>\n  <td>{filename_publish}{language}{owners}{ask_line}</td>\n  <td>{link_cell}</td>\n\n</tr>\n"
         );
     } else {
         // With summary: collapsible details per file
@@ -342,13 +474,21 @@ fn write_file_row(out: &mut String, entry: &FileEntry, file_stats: &HashMap<Stri
         let _ = write!(
             out,
             "\n<tr>\n  <td>\n    <details>\n      \
-             <summary>{filename_publish}</summary>\n<hr>\n\n{}\n\n{desc_br}\n\n\n\
+             <summary>{filename_publish}{language}{owners}</summary>\n<hr>\n\n{}\n\n{desc_br}\n\n{ask_line}\n\
              </details>\n\n\n  </td>\n  <td>{link_cell}</td>\n\n</tr>\n",
             entry.filename
         );
     }
 }
 
+/// Build an `/ask_line` command a reviewer can paste as a PR comment.
+fn ask_line_command(filename: &str, start: usize, end: usize) -> String {
+    format!(
+        "/ask_line --file_name={filename} --line_start={start} --line_end={end} --side=RIGHT \
+         Explain this change"
+    )
+}
+
 /// Insert `<br>` breaks into text to keep visual line length manageable.
 ///
 /// Inserts `<br>` at word boundaries to limit visual line length.
@@ -493,7 +633,7 @@ pr_files:
 "#;
         let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
         let config = test_config(true, false, true);
-        let result = format_describe_output(&data, "Original title", "", &config, &empty_stats());
+        let result = format_describe_output(&data, "Original title", &config, &empty_stats());
 
         assert_eq!(result.title, "Fix authentication bug in login flow");
         assert!(result.body.contains("Bug fix"));
@@ -503,6 +643,48 @@ pr_files:
         assert_eq!(result.labels, vec!["Bug fix"]);
     }
 
+    #[test]
+    fn test_fill_description_markers() {
+        let yaml_str = r#"
+type: "Bug fix"
+description: "Fixed the login bug"
+pr_files:
+  - filename: "src/auth.rs"
+    changes_title: "Fix token validation"
+    label: "bug fix"
+"#;
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
+        let config = test_config(false, false, true);
+        let body = "## Ticket\nJIRA-1\n\n## Type\npr_agent:type\n\n## Summary\npr_agent:summary\n\n## Files\npr_agent:walkthrough\n\nThanks!";
+
+        let filled = fill_description_markers(body, &data, &config, &empty_stats()).unwrap();
+        assert!(filled.starts_with(
+            "## Ticket\nJIRA-1\n\n## Type\n<!-- pr_agent:type -->\nBug fix\n<!-- /pr_agent:type -->"
+        ));
+        assert!(filled.contains(
+            "<!-- pr_agent:summary -->\n- Fixed the login bug\n<!-- /pr_agent:summary -->"
+        ));
+        assert!(filled.contains("auth.rs"));
+        assert!(filled.ends_with("<!-- /pr_agent:walkthrough -->\n\nThanks!"));
+
+        // A second run replaces the filled regions instead of appending.
+        let data: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str("type: Enhancement\ndescription: Reworked login").unwrap();
+        let refilled = fill_description_markers(&filled, &data, &config, &empty_stats()).unwrap();
+        assert!(refilled.contains("<!-- pr_agent:type -->\nEnhancement\n<!-- /pr_agent:type -->"));
+        assert!(!refilled.contains("Bug fix"));
+        assert!(!refilled.contains("Fixed the login bug"));
+        assert!(refilled.starts_with("## Ticket\nJIRA-1\n"));
+
+        assert!(
+            fill_description_markers("No placeholders", &data, &config, &empty_stats()).is_none()
+        );
+        assert!(
+            fill_description_markers("pr_agent:typescript", &data, &config, &empty_stats())
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_format_describe_keep_original_title() {
         let yaml_str = r#"
@@ -513,7 +695,7 @@ description: "Some changes"
         let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
         let config = test_config(false, false, false);
         let result =
-            format_describe_output(&data, "User's original title", "", &config, &empty_stats());
+            format_describe_output(&data, "User's original title", &config, &empty_stats());
 
         assert_eq!(result.title, "User's original title");
     }
@@ -530,6 +712,34 @@ labels:
         assert_eq!(labels, vec!["Bug fix", "Tests"]);
     }
 
+    #[test]
+    fn test_describe_labels_adds_file_type_labels() {
+        let yaml_str = r#"
+type: "Enhancement"
+pr_files:
+  - filename: "src/lib.rs"
+    label: "enhancement"
+  - filename: "tests/lib_test.rs"
+    label: "Tests"
+  - filename: "tests/other_test.rs"
+    label: "tests"
+  - filename: "README.md"
+    label: "documentation"
+  - filename: "Cargo.toml"
+    label: "dependencies"
+"#;
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
+        let mut config = PrDescriptionConfig::default();
+        config
+            .file_label_mapping
+            .insert("dependencies".into(), String::new());
+        let labels = describe_labels(&data, &config);
+        assert_eq!(labels, vec!["Enhancement", "Tests", "Documentation"]);
+
+        config.file_label_mapping.clear();
+        assert_eq!(describe_labels(&data, &config), vec!["Enhancement"]);
+    }
+
     #[test]
     fn test_extract_labels_from_type() {
         let data = serde_yaml_ng::Value::Mapping(serde_yaml_ng::Mapping::new());
@@ -551,7 +761,7 @@ changes_diagram: |
 "#;
         let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
         let config = test_config(false, false, false);
-        let result = format_describe_output(&data, "Test", "", &config, &empty_stats());
+        let result = format_describe_output(&data, "Test", &config, &empty_stats());
         // Should NOT have double fences
         assert!(!result.body.contains("```mermaid\n```mermaid"));
         assert!(result.body.contains("```mermaid"));
@@ -570,7 +780,7 @@ changes_diagram: |
 "#;
         let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
         let config = test_config(false, false, false);
-        let result = format_describe_output(&data, "Test", "", &config, &empty_stats());
+        let result = format_describe_output(&data, "Test", &config, &empty_stats());
         // Should wrap in mermaid fences
         assert!(result.body.contains("```mermaid\ngraph TD"));
     }
@@ -587,7 +797,7 @@ description: "Some changes"
             enable_pr_type: false,
             ..PrDescriptionConfig::default()
         };
-        let result = format_describe_output(&data, "Test", "", &config, &empty_stats());
+        let result = format_describe_output(&data, "Test", &config, &empty_stats());
         assert!(!result.body.contains("### **PR Type**"));
     }
 
@@ -612,7 +822,7 @@ pr_files:
             collapsible_file_list_threshold: 6,
             ..PrDescriptionConfig::default()
         };
-        let result = format_describe_output(&data, "Test", "", &config, &empty_stats());
+        let result = format_describe_output(&data, "Test", &config, &empty_stats());
         // 2 files < threshold 6 → per-category should NOT be collapsible
         // But outer <details> for File Walkthrough is always present
         assert!(result.body.contains("File Walkthrough"));
@@ -638,7 +848,7 @@ pr_files:
             collapsible_file_list: BoolOrString::Bool(true),
             ..PrDescriptionConfig::default()
         };
-        let result = format_describe_output(&data, "Test", "", &config, &empty_stats());
+        let result = format_describe_output(&data, "Test", &config, &empty_stats());
         // Per-category should be collapsible
         assert!(result.body.contains("1 files</summary>"));
     }
@@ -652,7 +862,7 @@ description: "Some changes"
 "#;
         let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
         let config = test_config(false, false, false);
-        let result = format_describe_output(&data, "Test", "", &config, &empty_stats());
+        let result = format_describe_output(&data, "Test", &config, &empty_stats());
         assert!(
             result.body.contains("___"),
             "body must contain ___ separators"
@@ -671,7 +881,7 @@ changes_diagram: |
 "#;
         let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
         let config = test_config(false, false, false);
-        let result = format_describe_output(&data, "Test", "", &config, &empty_stats());
+        let result = format_describe_output(&data, "Test", &config, &empty_stats());
         assert!(result.body.contains("### Diagram Walkthrough"));
         assert!(!result.body.contains("### **Changes Diagram**"));
     }
@@ -701,7 +911,7 @@ pr_files:
             collapsible_file_list: BoolOrString::Bool(true),
             ..PrDescriptionConfig::default()
         };
-        let result = format_describe_output(&data, "Test", "", &config, &empty_stats());
+        let result = format_describe_output(&data, "Test", &config, &empty_stats());
 
         // Should have HTML table structure
         assert!(result.body.contains("<table>"));
@@ -752,10 +962,13 @@ pr_files:
                 num_plus_lines: 10,
                 num_minus_lines: 5,
                 link: "https://github.com/owner/repo/pull/1/files#diff-abc123".to_string(),
+                main_hunk: None,
+                owners: Vec::new(),
+                language: None,
             },
         );
 
-        let result = format_describe_output(&data, "Test", "", &config, &stats);
+        let result = format_describe_output(&data, "Test", &config, &stats);
         assert!(result.body.contains("+10/-5"));
         assert!(
             result
@@ -764,6 +977,77 @@ pr_files:
         );
     }
 
+    #[test]
+    fn test_ask_line_snippets() {
+        let yaml_str = r#"
+title: "Test"
+type: "Enhancement"
+description: "Test"
+pr_files:
+  - filename: "src/main.rs"
+    changes_title: "Main changes"
+    changes_summary: "Adds a flag"
+    label: "enhancement"
+"#;
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
+        let mut stats = HashMap::new();
+        stats.insert(
+            "src/main.rs".to_string(),
+            FileStats {
+                num_plus_lines: 3,
+                num_minus_lines: 1,
+                link: String::new(),
+                main_hunk: Some((12, 18)),
+                owners: Vec::new(),
+                language: None,
+            },
+        );
+        let snippet = "<code>/ask_line --file_name=src/main.rs --line_start=12 --line_end=18 \
+                       --side=RIGHT Explain this change</code>";
+
+        let config = PrDescriptionConfig {
+            enable_ask_line_snippets: true,
+            ..PrDescriptionConfig::default()
+        };
+        let result = format_describe_output(&data, "Test", &config, &stats);
+        assert!(result.body.contains(snippet));
+
+        let result = format_describe_output(&data, "Test", &PrDescriptionConfig::default(), &stats);
+        assert!(!result.body.contains("/ask_line"));
+    }
+
+    #[test]
+    fn test_file_walkthrough_shows_owners() {
+        let yaml_str = r#"
+title: "Test"
+type: "Enhancement"
+description: "Test"
+pr_files:
+  - filename: "src/main.rs"
+    changes_title: "Main changes"
+    label: "enhancement"
+"#;
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
+        let mut stats = HashMap::new();
+        stats.insert(
+            "src/main.rs".to_string(),
+            FileStats {
+                num_plus_lines: 1,
+                num_minus_lines: 0,
+                link: String::new(),
+                main_hunk: None,
+                owners: vec!["@org/core".into(), "@alice".into()],
+                language: Some("Rust".into()),
+            },
+        );
+        let result = format_describe_output(&data, "Test", &PrDescriptionConfig::default(), &stats);
+        assert!(
+            result
+                .body
+                .contains("<dd>Language: Rust</dd><dd>Owners: @org/core @alice</dd>")
+        );
+    }
+
     // ── Mermaid sanitization tests ──────────────────────────────────
 
     #[test]
@@ -0,0 +1,295 @@
+//! A PR description as the author's text around pr-agent's sections.
+//!
+//! `/describe` writes its output between `<!-- pr-agent:describe -->` and
+//! `<!-- /pr-agent:describe -->`. [`DescriptionDocument::parse`] splits a
+//! description into these sections and the text around them, so the next
+//! run replaces its section in place and keeps what the author wrote before
+//! or after it. Descriptions written by older versions still parse: a
+//! section without an end marker runs to the end of the body, and a body of
+//! legacy `### **PR Type**` headers keeps only its "User description".
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+static SECTION_START: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<!-- pr-agent:([a-z_]+) -->").expect("valid regex"));
+
+/// Separator between the author's text and a section.
+const SEPARATOR: &str = "---";
+
+/// Headers that indicate the body was generated by pr-agent.
+///
+/// Known section headers emitted by pr-agent tools.
+const PR_AGENT_HEADERS: &[&str] = &[
+    "### **user description**",
+    "### **pr type**",
+    "### **pr description**",
+    "### **pr labels**",
+    "### **type**",
+    "### **description**",
+    "### **labels**",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    /// Text written by the author.
+    User(String),
+    /// A generated section, by marker name, without its markers.
+    Section { name: String, content: String },
+}
+
+/// A PR description split into author text and generated sections.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DescriptionDocument {
+    parts: Vec<Part>,
+}
+
+impl DescriptionDocument {
+    /// Split `body` at the section markers.
+    pub fn parse(body: &str) -> Self {
+        let mut doc = Self::default();
+        if !SECTION_START.is_match(body) && is_legacy_description(body) {
+            doc.push_user(&legacy_user_description(body));
+            return doc;
+        }
+        let mut rest = body;
+        while let Some(caps) = SECTION_START.captures(rest) {
+            let start = caps.get(0).expect("whole match");
+            let name = caps[1].to_string();
+            doc.push_user(&rest[..start.start()]);
+            let after = &rest[start.end()..];
+            let end = section_end(&name);
+            let (content, next) = match after.find(&end) {
+                Some(pos) => (&after[..pos], &after[pos + end.len()..]),
+                None => (after, ""),
+            };
+            doc.parts.push(Part::Section {
+                name,
+                content: content.trim().to_string(),
+            });
+            rest = next;
+        }
+        doc.push_user(rest);
+        doc
+    }
+
+    /// The author's text, without generated sections.
+    #[allow(dead_code)]
+    pub fn user_description(&self) -> String {
+        let texts: Vec<&str> = self
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::User(text) => Some(text.as_str()),
+                Part::Section { .. } => None,
+            })
+            .collect();
+        texts.join("\n\n")
+    }
+
+    /// Drop the author's text, keeping only generated sections.
+    pub fn clear_user_content(&mut self) {
+        self.parts
+            .retain(|part| matches!(part, Part::Section { .. }));
+    }
+
+    /// Replace the `name` section with `content`, or append it after the
+    /// author's text. A leading start marker in `content` is ignored.
+    pub fn set_section(&mut self, name: &str, content: &str) {
+        let start = section_start(name);
+        let content = content.trim();
+        let content = content.strip_prefix(&start).unwrap_or(content).trim();
+        let mut replaced = false;
+        self.parts.retain_mut(|part| match part {
+            Part::Section {
+                name: n,
+                content: c,
+            } if n == name => {
+                if replaced {
+                    return false;
+                }
+                *c = content.to_string();
+                replaced = true;
+                true
+            }
+            _ => true,
+        });
+        if !replaced {
+            self.parts.push(Part::Section {
+                name: name.to_string(),
+                content: content.to_string(),
+            });
+        }
+    }
+
+    /// The description text, with the author's text and each section
+    /// apart by a `---` rule.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut previous: Option<&Part> = None;
+        for part in &self.parts {
+            match (previous, part) {
+                (None, _) => {}
+                (Some(Part::Section { .. }), Part::Section { .. }) => out.push_str("\n\n"),
+                _ => out.push_str(&format!("\n\n{SEPARATOR}\n\n")),
+            }
+            match part {
+                Part::User(text) => out.push_str(text),
+                Part::Section { name, content } => {
+                    out.push_str(&format!(
+                        "{}\n{content}\n{}",
+                        section_start(name),
+                        section_end(name)
+                    ));
+                }
+            }
+            previous = Some(part);
+        }
+        out
+    }
+
+    /// Add author text, without the rules that separated it from sections.
+    fn push_user(&mut self, text: &str) {
+        let text = text.trim();
+        let text = text.strip_prefix(SEPARATOR).unwrap_or(text).trim_start();
+        let text = text.strip_suffix(SEPARATOR).unwrap_or(text).trim_end();
+        if text.is_empty() {
+            return;
+        }
+        match self.parts.last_mut() {
+            Some(Part::User(previous)) => {
+                previous.push_str("\n\n");
+                previous.push_str(text);
+            }
+            _ => self.parts.push(Part::User(text.to_string())),
+        }
+    }
+}
+
+fn section_start(name: &str) -> String {
+    format!("<!-- pr-agent:{name} -->")
+}
+
+fn section_end(name: &str) -> String {
+    format!("<!-- /pr-agent:{name} -->")
+}
+
+/// Whether `body` starts with one of the legacy pr-agent headers.
+fn is_legacy_description(body: &str) -> bool {
+    let lower = body.trim_start().to_lowercase();
+    PR_AGENT_HEADERS
+        .iter()
+        .any(|header| lower.starts_with(header))
+}
+
+/// The "User description" section of a legacy generated body, or nothing.
+fn legacy_user_description(body: &str) -> String {
+    let lower = body.to_lowercase();
+    let user_desc_header = PR_AGENT_HEADERS[0];
+    let Some(start) = lower.find(user_desc_header) else {
+        return String::new();
+    };
+    let content_start = start + user_desc_header.len();
+
+    // The user description ends at the next pr-agent header
+    let end = PR_AGENT_HEADERS[1..]
+        .iter()
+        .filter_map(|header| lower[content_start..].find(header))
+        .map(|pos| content_start + pos)
+        .min()
+        .unwrap_or(body.len());
+
+    let user_content = body[content_start..end].trim();
+    // Strip trailing separator (___) that pr-agent adds
+    let user_content = user_content.strip_suffix("___").unwrap_or(user_content);
+    user_content.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keeps_text_around_section() {
+        let body = "Intro.\n\n---\n\n<!-- pr-agent:describe -->\n### **PR Type**\nOld\n\
+                    <!-- /pr-agent:describe -->\n\n---\n\nNotes added later.";
+        let mut doc = DescriptionDocument::parse(body);
+        assert_eq!(doc.user_description(), "Intro.\n\nNotes added later.");
+        assert_eq!(doc.render(), body);
+
+        doc.set_section(
+            "describe",
+            "<!-- pr-agent:describe -->\n### **PR Type**\nNew",
+        );
+        assert_eq!(
+            doc.render(),
+            "Intro.\n\n---\n\n<!-- pr-agent:describe -->\n### **PR Type**\nNew\n\
+             <!-- /pr-agent:describe -->\n\n---\n\nNotes added later."
+        );
+        // A re-run parses what it wrote
+        assert_eq!(DescriptionDocument::parse(&doc.render()), doc);
+    }
+
+    #[test]
+    fn test_parse_section_without_end_marker() {
+        let body = "User wrote this.\n\n---\n\n<!-- pr-agent:describe -->\n### PR Type\nGenerated";
+        let mut doc = DescriptionDocument::parse(body);
+        assert_eq!(doc.user_description(), "User wrote this.");
+
+        doc.set_section("describe", "Fresh");
+        assert_eq!(
+            doc.render(),
+            "User wrote this.\n\n---\n\n<!-- pr-agent:describe -->\nFresh\n<!-- /pr-agent:describe -->"
+        );
+        let generated_only =
+            DescriptionDocument::parse("<!-- pr-agent:describe -->\nAll generated");
+        assert_eq!(generated_only.user_description(), "");
+    }
+
+    #[test]
+    fn test_parse_without_sections() {
+        let doc = DescriptionDocument::parse("Just a normal body with no markers.");
+        assert_eq!(
+            doc.user_description(),
+            "Just a normal body with no markers."
+        );
+        assert_eq!(DescriptionDocument::parse("").render(), "");
+
+        let mut doc = DescriptionDocument::default();
+        doc.set_section("describe", "Generated");
+        assert_eq!(
+            doc.render(),
+            "<!-- pr-agent:describe -->\nGenerated\n<!-- /pr-agent:describe -->"
+        );
+    }
+
+    #[test]
+    fn test_set_section_merges_duplicates_and_keeps_others() {
+        let body = "<!-- pr-agent:describe -->\nA\n<!-- /pr-agent:describe -->\n\
+                    Middle\n<!-- pr-agent:other -->\nB\n<!-- /pr-agent:other -->\n\
+                    <!-- pr-agent:describe -->\nC\n<!-- /pr-agent:describe -->";
+        let mut doc = DescriptionDocument::parse(body);
+        doc.set_section("describe", "D");
+        let rendered = doc.render();
+        assert_eq!(rendered.matches("<!-- pr-agent:describe -->").count(), 1);
+        assert!(rendered.starts_with("<!-- pr-agent:describe -->\nD\n"));
+        assert!(rendered.contains("Middle"));
+        assert!(rendered.contains("<!-- pr-agent:other -->\nB\n<!-- /pr-agent:other -->"));
+
+        doc.clear_user_content();
+        assert_eq!(doc.user_description(), "");
+        assert!(doc.render().contains("<!-- pr-agent:other -->"));
+    }
+
+    #[test]
+    fn test_parse_legacy_headers() {
+        let body = "### **User description**\nUser wrote this.\n\n___\n\n### **PR Type**\nEnhancement\n\n___\n\n### **Description**\n- Generated bullet";
+        assert_eq!(
+            DescriptionDocument::parse(body).user_description(),
+            "User wrote this."
+        );
+        let body = "### **PR Type**\nEnhancement\n\n### **Description**\n- Generated";
+        assert_eq!(DescriptionDocument::parse(body).user_description(), "");
+    }
+}
@@ -0,0 +1,138 @@
+//! Intro sentences, usage guides and chat hints shared by the tool comments.
+//!
+//! Each tool gates these on its own `enable_intro_text`, `enable_help_text`
+//! and `enable_chat_text` flags; the texts and their placement live here so
+//! every comment renders them the same way.
+
+use super::markdown::collapsible_section;
+
+/// Built-in intro line for the review comment (`pr_reviewer.enable_intro_text`).
+pub const REVIEW_INTRO_TEXT: &str = "Here are some key observations to aid the review process:";
+
+/// Built-in usage guide for the review comment (`pr_reviewer.enable_help_text`).
+pub const REVIEW_HELP_TEXT: &str = "The `review` tool scans the PR code changes and generates a \
+PR review focused on possible issues, security concerns and review effort.\n\n\
+- Comment `/review` to run it again.\n\
+- Steer the focus with `/review --pr_reviewer.extra_instructions=\"...\"`.\n\
+- Configure it under `[pr_reviewer]` in the repo's `.pr_agent.toml`.";
+
+/// Built-in usage guide for the description (`pr_description.enable_help_text`).
+pub const DESCRIBE_HELP_TEXT: &str = "The `describe` tool generates a PR title, type, summary \
+and walkthrough of the changed files.\n\n\
+- Comment `/describe` to run it again.\n\
+- Text above the generated section is kept, so you can add your own notes there.\n\
+- Configure it under `[pr_description]` in the repo's `.pr_agent.toml`.";
+
+/// Built-in usage guide for the suggestions comment (`pr_code_suggestions.enable_help_text`).
+pub const IMPROVE_HELP_TEXT: &str = "The `improve` tool scans the PR code changes and suggests \
+fixes for possible bugs and problems.\n\n\
+- Comment `/improve` to run it again.\n\
+- Steer the focus with `/improve --pr_code_suggestions.extra_instructions=\"...\"`.\n\
+- Configure it under `[pr_code_suggestions]` in the repo's `.pr_agent.toml`.";
+
+/// Built-in chat hint for the suggestions comment (`pr_code_suggestions.enable_chat_text`).
+pub const IMPROVE_CHAT_TEXT: &str = "💬 Have a question about a suggestion? Reply to a suggestion \
+line with `/ask_line <question>`, or comment `/ask <question>` about the whole PR.";
+
+/// Built-in usage guide for the generated tests comment (`pr_test.enable_help_text`).
+pub const TEST_HELP_TEXT: &str = "The `test` tool generates unit tests for code changed in the PR.\n\n\
+- Comment `/test <component>` to target a function or class, or `/test` for the whole PR.\n\
+- Narrow it with `--pr_test.file=<path>` and `--pr_test.class_name=<name>`.\n\
+- Set `num_tests`, `testing_framework` and `avoid_mocks` under `[pr_test]` in the repo's `.pr_agent.toml`.";
+
+/// Built-in usage guide for the analysis comment (`pr_analyze.enable_help_text`).
+pub const ANALYZE_HELP_TEXT: &str = "The `analyze` tool lists the functions and classes changed \
+in the PR, with follow-up commands for each of them.\n\n\
+- Comment `/test <component>` to generate unit tests for a component.\n\
+- Comment `/improve_component <component>` to get suggestions focused on a component.\n\
+- Comment `/analyze` to refresh the list after new commits.";
+
+/// Add intro and help text to a tool comment.
+///
+/// The intro goes right below the first `## ` heading; the help text is
+/// appended as a collapsible usage guide. Empty texts are skipped.
+pub fn add_guide_text(body: &mut String, intro: &str, help: &str) {
+    let intro = intro.trim();
+    if !intro.is_empty() {
+        let heading = if body.starts_with("## ") {
+            Some(0)
+        } else {
+            body.find("\n## ").map(|i| i + 1)
+        };
+        let pos = heading.map_or(0, |start| {
+            let end = body[start..]
+                .find('\n')
+                .map_or(body.len(), |i| start + i + 1);
+            if body[end..].starts_with('\n') {
+                end + 1
+            } else {
+                end
+            }
+        });
+        body.insert_str(pos, &format!("{intro}\n\n"));
+    }
+
+    let help = help.trim();
+    if !help.is_empty() {
+        if !body.ends_with('\n') {
+            body.push('\n');
+        }
+        body.push('\n');
+        body.push_str(&collapsible_section("💡 Tool usage guide:", help));
+    }
+}
+
+/// Add a chat hint to the end of a tool comment. An empty text is skipped.
+pub fn add_chat_text(body: &mut String, chat: &str) {
+    let chat = chat.trim();
+    if chat.is_empty() {
+        return;
+    }
+    if !body.ends_with('\n') {
+        body.push('\n');
+    }
+    body.push('\n');
+    body.push_str(chat);
+    body.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_guide_text() {
+        let mut body =
+            "<!-- pr-agent:review -->\n## PR Reviewer Guide 🔍\n\n<table>\n</table>\n".to_string();
+        add_guide_text(
+            &mut body,
+            "See [our guidelines](https://wiki/review).",
+            "Custom help",
+        );
+        assert!(body.starts_with(
+            "<!-- pr-agent:review -->\n## PR Reviewer Guide 🔍\n\nSee [our guidelines](https://wiki/review).\n\n<table>"
+        ));
+        assert!(body.ends_with(
+            "</table>\n\n<details><summary>💡 Tool usage guide:</summary>\n\nCustom help\n\n</details>\n"
+        ));
+
+        let mut body = "## Header\n\nContent\n".to_string();
+        add_guide_text(&mut body, "", "");
+        assert_eq!(body, "## Header\n\nContent\n");
+    }
+
+    #[test]
+    fn test_add_chat_text_before_guide() {
+        let mut body = "## Header\n\nContent".to_string();
+        add_chat_text(&mut body, IMPROVE_CHAT_TEXT);
+        add_guide_text(&mut body, "", IMPROVE_HELP_TEXT);
+        let chat = body.find("💬").unwrap();
+        let guide = body.find("💡 Tool usage guide").unwrap();
+        assert!(body.starts_with("## Header\n\nContent\n\n💬"));
+        assert!(chat < guide);
+
+        let mut body = "## Header\n".to_string();
+        add_chat_text(&mut body, "  ");
+        assert_eq!(body, "## Header\n");
+    }
+}
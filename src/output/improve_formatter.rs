@@ -1,11 +1,12 @@
 use std::fmt::Write;
 
-use crate::git::types::CodeSuggestion;
-use crate::output::markdown::persistent_comment_marker;
+use crate::git::types::{CodeSuggestion, FilePatchInfo};
+use crate::output::markdown::{persistent_comment_marker, sanitize_table_cell, user_code_span};
 use crate::output::yaml_parser::{yaml_value_as_i64, yaml_value_as_u64};
+use crate::processing::diff::new_side_hunks;
 
 /// A parsed code suggestion from the AI response.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct ParsedSuggestion {
     pub label: String,
     pub relevant_file: String,
@@ -16,8 +17,23 @@ pub struct ParsedSuggestion {
     pub one_sentence_summary: String,
     pub suggestion_content: String,
     pub score: u32,
+    /// Set on suggestions from the best-practices pass.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_practice: Option<BestPracticeRule>,
+}
+
+/// The organization rule a best-practice suggestion applies.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct BestPracticeRule {
+    /// The rule's heading or first line, as quoted by the model.
+    pub text: String,
+    /// Link to the rule in the repo's best practices file, if it has one.
+    pub link: Option<String>,
 }
 
+/// Label of suggestions from the best-practices pass.
+pub const BEST_PRACTICE_LABEL: &str = "Organization best practice";
+
 /// Extract a trimmed string field from a YAML mapping, with a fallback default.
 fn yaml_str_field(item: &serde_yaml_ng::Value, key: &str, default: &str) -> String {
     item.get(key)
@@ -48,6 +64,9 @@ pub fn parse_suggestions(data: &serde_yaml_ng::Value) -> Vec<ParsedSuggestion> {
         let improved_code = yaml_str_field(item, "improved_code", "");
         let one_sentence_summary = yaml_str_field(item, "one_sentence_summary", "");
         let suggestion_content = yaml_str_field(item, "suggestion_content", "");
+        let best_practice = Some(yaml_str_field(item, "relevant_best_practice", ""))
+            .filter(|text| !text.is_empty())
+            .map(|text| BestPracticeRule { text, link: None });
 
         let lines_start = item
             .get("relevant_lines_start")
@@ -73,6 +92,7 @@ pub fn parse_suggestions(data: &serde_yaml_ng::Value) -> Vec<ParsedSuggestion> {
             one_sentence_summary,
             suggestion_content,
             score,
+            best_practice,
         });
     }
 
@@ -105,11 +125,146 @@ pub fn suggestions_to_code_suggestions(suggestions: &[ParsedSuggestion]) -> Vec<
         .collect()
 }
 
+/// Fit inline suggestions to the diff, as providers reject comments on lines
+/// outside it.
+///
+/// A suggestion moves to where its `existing_code` appears on the new side of
+/// its file's patch, taking the match nearest its given lines. Without a
+/// match it's kept only when its range lies within one hunk. Suggestions on
+/// files missing from `files` are dropped.
+pub fn align_to_diff(
+    suggestions: Vec<CodeSuggestion>,
+    files: &[FilePatchInfo],
+) -> Vec<CodeSuggestion> {
+    suggestions
+        .into_iter()
+        .filter_map(|mut s| {
+            let file = files.iter().find(|f| f.filename == s.relevant_file)?;
+            let hunks = new_side_hunks(&file.patch);
+            if let Some((start, end)) = locate_existing_code(&s, &hunks) {
+                s.relevant_lines_start = start;
+                s.relevant_lines_end = end;
+                return Some(s);
+            }
+            let (start, end) = (s.relevant_lines_start, s.relevant_lines_end);
+            let in_hunk = |h: &Vec<(usize, &str)>| match (h.first(), h.last()) {
+                (Some(first), Some(last)) => {
+                    start >= 1 && start <= end && first.0 as i32 <= start && end <= last.0 as i32
+                }
+                _ => false,
+            };
+            hunks.iter().any(in_hunk).then_some(s)
+        })
+        .collect()
+}
+
+/// New-side range where the suggestion's `existing_code` appears, comparing
+/// trimmed non-blank lines. The match nearest the given start wins.
+fn locate_existing_code(s: &CodeSuggestion, hunks: &[Vec<(usize, &str)>]) -> Option<(i32, i32)> {
+    let wanted: Vec<&str> = s
+        .existing_code
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    if wanted.is_empty() {
+        return None;
+    }
+    hunks
+        .iter()
+        .flat_map(|hunk| {
+            let lines: Vec<(usize, &str)> = hunk
+                .iter()
+                .map(|&(n, l)| (n, l.trim()))
+                .filter(|(_, l)| !l.is_empty())
+                .collect();
+            lines
+                .windows(wanted.len())
+                .filter(|w| w.iter().map(|(_, l)| *l).eq(wanted.iter().copied()))
+                .map(|w| (w[0].0 as i32, w[w.len() - 1].0 as i32))
+                .collect::<Vec<_>>()
+        })
+        .min_by_key(|(start, _)| (start - s.relevant_lines_start).abs())
+}
+
+/// Inline suggestions for one file, published in a single review call.
+#[derive(Debug, Clone)]
+pub struct SuggestionBatch {
+    pub file: String,
+    pub suggestions: Vec<CodeSuggestion>,
+    /// Link to the published review, when the provider returns one.
+    pub url: Option<String>,
+}
+
+/// Group inline suggestions by file, splitting files with more than
+/// `max_per_batch` suggestions (0 = no limit) into several batches.
+///
+/// Files keep the order of their first suggestion.
+pub fn batch_code_suggestions(
+    suggestions: Vec<CodeSuggestion>,
+    max_per_batch: usize,
+) -> Vec<SuggestionBatch> {
+    let mut by_file: Vec<(String, Vec<CodeSuggestion>)> = Vec::new();
+    for s in suggestions {
+        match by_file
+            .iter_mut()
+            .find(|(file, _)| *file == s.relevant_file)
+        {
+            Some((_, group)) => group.push(s),
+            None => by_file.push((s.relevant_file.clone(), vec![s])),
+        }
+    }
+
+    let mut batches = Vec::new();
+    for (file, group) in by_file {
+        let size = if max_per_batch == 0 {
+            group.len()
+        } else {
+            max_per_batch
+        };
+        for chunk in group.chunks(size) {
+            batches.push(SuggestionBatch {
+                file: file.clone(),
+                suggestions: chunk.to_vec(),
+                url: None,
+            });
+        }
+    }
+    batches
+}
+
+/// Format published suggestion batches as a table linking each review.
+pub fn format_suggestion_batches(batches: &[SuggestionBatch]) -> String {
+    let mut out = String::from("### Committable suggestions\n\n");
+    let _ = writeln!(
+        out,
+        "Inline suggestions were published in {} review batches:\n",
+        batches.len()
+    );
+    out.push_str("| Batch | File | Suggestions |\n|---|---|---|\n");
+    for (i, batch) in batches.iter().enumerate() {
+        let n = i + 1;
+        let batch_cell = match &batch.url {
+            Some(url) => format!("[#{n}]({url})"),
+            None => format!("#{n}"),
+        };
+        let _ = writeln!(
+            out,
+            "| {batch_cell} | {} | {} |",
+            user_code_span(&batch.file),
+            batch.suggestions.len()
+        );
+    }
+    out
+}
+
 /// Format suggestions as a summary comment (table format).
 ///
 /// Used when `commitable_code_suggestions = false`.
 /// Suggestions with no valid line numbers (lines <= 0) are displayed in a
 /// separate "Architecture & Design" section as high-level observations.
+/// Best-practice suggestions get their own section, linking the rule each
+/// one applies.
 pub fn format_suggestions_table(
     suggestions: &[ParsedSuggestion],
     th_high: u32,
@@ -126,9 +281,12 @@ pub fn format_suggestions_table(
         return out;
     }
 
-    // Split into code-level (valid line numbers) and high-level (no lines)
-    let (code_level, high_level): (Vec<&ParsedSuggestion>, Vec<&ParsedSuggestion>) = suggestions
-        .iter()
+    // Split off best-practice suggestions, then the rest into code-level
+    // (valid line numbers) and high-level (no lines)
+    let (best_practices, rest): (Vec<&ParsedSuggestion>, Vec<&ParsedSuggestion>) =
+        suggestions.iter().partition(|s| s.best_practice.is_some());
+    let (code_level, high_level): (Vec<&ParsedSuggestion>, Vec<&ParsedSuggestion>) = rest
+        .into_iter()
         .partition(|s| s.relevant_lines_start > 0 && s.relevant_lines_end > 0);
 
     // Render high-level suggestions first (if any)
@@ -142,15 +300,15 @@ pub fn format_suggestions_table(
             };
             let summary = sanitize_table_cell(raw_summary);
             let importance = importance_label(s.score, th_high, th_medium);
-            let file = sanitize_table_cell(&s.relevant_file);
-            let _ = writeln!(out, "- **[{importance}] {summary}** (`{file}`)");
+            let file = user_code_span(&s.relevant_file);
+            let _ = writeln!(out, "- **[{importance}] {summary}** ({file})");
         }
         let _ = writeln!(out);
     }
 
     // Render code-level suggestions table
     if !code_level.is_empty() {
-        if !high_level.is_empty() {
+        if !high_level.is_empty() || !best_practices.is_empty() {
             let _ = writeln!(out, "### Code Suggestions\n");
         }
 
@@ -159,52 +317,78 @@ pub fn format_suggestions_table(
 
         for s in &code_level {
             let importance = importance_label(s.score, th_high, th_medium);
-
-            let raw_summary = if s.one_sentence_summary.is_empty() {
-                &s.suggestion_content
-            } else {
-                &s.one_sentence_summary
-            };
-
-            // Truncate long summaries for table (char-safe)
-            let summary = if raw_summary.len() > 200 {
-                let end = raw_summary
-                    .char_indices()
-                    .take_while(|(i, _)| *i < 200)
-                    .last()
-                    .map(|(i, c)| i + c.len_utf8())
-                    .unwrap_or(200.min(raw_summary.len()));
-                format!("{}...", &raw_summary[..end])
-            } else {
-                raw_summary.to_string()
-            };
-
-            // Sanitize for markdown table: replace newlines and pipes
-            let summary = sanitize_table_cell(&summary);
             let label = sanitize_table_cell(&s.label);
-            let file = sanitize_table_cell(&s.relevant_file);
+            let cell = suggestion_cell(s);
+            let _ = writeln!(out, "| {label} | {cell} | {importance} |");
+        }
+    }
 
-            // Format line range
-            let lines_str = if s.relevant_lines_start == s.relevant_lines_end {
-                format!(" [{}]", s.relevant_lines_start)
-            } else {
-                format!(" [{}-{}]", s.relevant_lines_start, s.relevant_lines_end)
-            };
+    if !best_practices.is_empty() {
+        if !code_level.is_empty() {
+            let _ = writeln!(out);
+        }
+        let _ = writeln!(out, "### Organization Best Practices\n");
+        let _ = writeln!(out, "| Rule | Suggestion | Score |");
+        let _ = writeln!(out, "| --- | --- | --- |");
 
-            let _ = writeln!(
-                out,
-                "| {label} | **{summary}**<br>`{file}`{lines_str} | {importance} |",
-            );
+        for s in &best_practices {
+            let importance = importance_label(s.score, th_high, th_medium);
+            let rule = s.best_practice.as_ref().map_or_else(String::new, |rule| {
+                let text = sanitize_table_cell(&rule.text);
+                match &rule.link {
+                    Some(link) => format!("[{text}]({link})"),
+                    None => text,
+                }
+            });
+            let cell = suggestion_cell(s);
+            let _ = writeln!(out, "| {rule} | {cell} | {importance} |");
         }
     }
 
     out
 }
 
+/// Table cell for a suggestion: its summary, then its file and line range.
+fn suggestion_cell(s: &ParsedSuggestion) -> String {
+    let raw_summary = if s.one_sentence_summary.is_empty() {
+        &s.suggestion_content
+    } else {
+        &s.one_sentence_summary
+    };
+
+    // Truncate long summaries for table (char-safe)
+    let summary = if raw_summary.len() > 200 {
+        let end = raw_summary
+            .char_indices()
+            .take_while(|(i, _)| *i < 200)
+            .last()
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(200.min(raw_summary.len()));
+        format!("{}...", &raw_summary[..end])
+    } else {
+        raw_summary.to_string()
+    };
+
+    // Sanitize for markdown table: replace newlines and pipes
+    let summary = sanitize_table_cell(&summary);
+    let file = user_code_span(&s.relevant_file);
+
+    // Format line range; best-practice suggestions may have none
+    let lines_str = if s.relevant_lines_start <= 0 {
+        String::new()
+    } else if s.relevant_lines_start == s.relevant_lines_end {
+        format!(" [{}]", s.relevant_lines_start)
+    } else {
+        format!(" [{}-{}]", s.relevant_lines_start, s.relevant_lines_end)
+    };
+
+    format!("**{summary}**<br>{file}{lines_str}")
+}
+
 /// Map a suggestion score to an importance label using configurable thresholds.
 ///
 /// `th_high` is the minimum score for "Critical", `th_medium` for "Important".
-fn importance_label(score: u32, th_high: u32, th_medium: u32) -> &'static str {
+pub(crate) fn importance_label(score: u32, th_high: u32, th_medium: u32) -> &'static str {
     if score >= th_high {
         "Critical"
     } else if score >= th_medium {
@@ -214,12 +398,30 @@ fn importance_label(score: u32, th_high: u32, th_medium: u32) -> &'static str {
     }
 }
 
+/// HTML comment markers that tag the self-review checkbox line.
+const SELF_REVIEW_MARKERS: &[&str] = &[
+    "<!-- approve and fold suggestions self-review -->",
+    "<!-- approve pr self-review -->",
+    "<!-- fold suggestions self-review -->",
+];
+
 /// Append a self-review checkbox to the suggestions body.
 ///
 /// Adds a markdown checkbox with an HTML comment indicating which actions
-/// to take when checked (approve, fold, or both).
-pub fn append_self_review_checkbox(body: &mut String, text: &str, approve: bool, fold: bool) {
-    body.push_str("\n\n- [ ]  ");
+/// to take when checked (approve, fold, or both). `checked` carries over the
+/// state of the checkbox in the comment being replaced.
+pub fn append_self_review_checkbox(
+    body: &mut String,
+    text: &str,
+    approve: bool,
+    fold: bool,
+    checked: bool,
+) {
+    body.push_str(if checked {
+        "\n\n- [x]  "
+    } else {
+        "\n\n- [ ]  "
+    });
     body.push_str(text);
     if approve && !fold {
         body.push_str(" <!-- approve pr self-review -->");
@@ -231,12 +433,15 @@ pub fn append_self_review_checkbox(body: &mut String, text: &str, approve: bool,
     body.push('\n');
 }
 
-/// Sanitize text for use inside a markdown table cell.
-/// Replaces newlines with `<br>` and escapes pipe characters.
-fn sanitize_table_cell(text: &str) -> String {
-    text.replace('\n', "<br>")
-        .replace('\r', "")
-        .replace('|', "\\|")
+/// Check if the self-review checkbox in the comment body is checked.
+///
+/// Searches for `- [x]` on the same line as an exact self-review marker.
+pub fn is_self_review_checked(body: &str) -> bool {
+    body.lines().any(|line| {
+        let trimmed = line.trim();
+        SELF_REVIEW_MARKERS.iter().any(|m| line.contains(m))
+            && (trimmed.starts_with("- [x]") || trimmed.starts_with("- [X]"))
+    })
 }
 
 #[cfg(test)]
@@ -288,6 +493,7 @@ code_suggestions:
             one_sentence_summary: "Fix bug".into(),
             suggestion_content: "Fix the bug".into(),
             score: 8,
+            best_practice: None,
         }];
 
         let code_suggestions = suggestions_to_code_suggestions(&suggestions);
@@ -308,6 +514,7 @@ code_suggestions:
             one_sentence_summary: "Improve performance".into(),
             suggestion_content: "Use a better algorithm".into(),
             score: 7,
+            best_practice: None,
         }];
 
         let result = format_suggestions_table(&suggestions, 9, 7);
@@ -335,6 +542,7 @@ code_suggestions:
             one_sentence_summary: "Fix issue".into(),
             suggestion_content: "Fix".into(),
             score: 5,
+            best_practice: None,
         }];
 
         let result = format_suggestions_table(&suggestions, 9, 7);
@@ -359,6 +567,7 @@ code_suggestions:
                 one_sentence_summary: "Consider splitting module".into(),
                 suggestion_content: "Split".into(),
                 score: 8,
+                best_practice: None,
             },
             ParsedSuggestion {
                 label: "bug".into(),
@@ -370,6 +579,7 @@ code_suggestions:
                 one_sentence_summary: "Fix null check".into(),
                 suggestion_content: "Add null check".into(),
                 score: 9,
+                best_practice: None,
             },
         ];
 
@@ -384,6 +594,67 @@ code_suggestions:
         assert!(result.contains("[10-15]"));
     }
 
+    #[test]
+    fn test_format_suggestions_table_best_practice_section() {
+        let code = ParsedSuggestion {
+            label: "bug".into(),
+            relevant_file: "src/main.rs".into(),
+            relevant_lines_start: 10,
+            relevant_lines_end: 10,
+            existing_code: "old".into(),
+            improved_code: "new".into(),
+            one_sentence_summary: "Fix null check".into(),
+            suggestion_content: "Add null check".into(),
+            score: 9,
+            best_practice: None,
+        };
+        let practice = |text: &str, link: Option<&str>| ParsedSuggestion {
+            label: BEST_PRACTICE_LABEL.into(),
+            one_sentence_summary: "Use typed errors".into(),
+            score: 7,
+            best_practice: Some(BestPracticeRule {
+                text: text.into(),
+                link: link.map(String::from),
+            }),
+            ..code.clone()
+        };
+        let suggestions = vec![
+            code.clone(),
+            practice(
+                "Return typed errors",
+                Some("https://github.com/o/r/blob/HEAD/best_practices.md#L3"),
+            ),
+            practice("No panics | in libraries", None),
+        ];
+
+        let result = format_suggestions_table(&suggestions, 9, 7);
+        assert!(result.contains("### Code Suggestions"));
+        assert!(result.contains("| bug | **Fix null check**"));
+        let section = result
+            .split("### Organization Best Practices")
+            .nth(1)
+            .unwrap();
+        assert!(section.contains(
+            "| [Return typed errors](https://github.com/o/r/blob/HEAD/best_practices.md#L3) | **Use typed errors**<br>`src/main.rs` [10] | Important |"
+        ));
+        assert!(section.contains("| No panics \\| in libraries |"));
+        assert!(!section.contains("Fix null check"));
+    }
+
+    #[test]
+    fn test_parse_suggestions_best_practice() {
+        let yaml: serde_yaml_ng::Value = serde_yaml_ng::from_str(
+            "code_suggestions:\n- relevant_file: a.rs\n  improved_code: x\n  relevant_best_practice: |\n    Return typed errors\n- relevant_file: b.rs\n  improved_code: y\n",
+        )
+        .unwrap();
+        let parsed = parse_suggestions(&yaml);
+        assert_eq!(
+            parsed[0].best_practice.as_ref().map(|r| r.text.as_str()),
+            Some("Return typed errors")
+        );
+        assert!(parsed[1].best_practice.is_none());
+    }
+
     #[test]
     fn test_format_suggestions_table_single_line() {
         let suggestions = vec![ParsedSuggestion {
@@ -396,6 +667,7 @@ code_suggestions:
             one_sentence_summary: "Fix".into(),
             suggestion_content: "Fix".into(),
             score: 8,
+            best_practice: None,
         }];
 
         let result = format_suggestions_table(&suggestions, 9, 7);
@@ -415,6 +687,7 @@ code_suggestions:
             one_sentence_summary: "Summary with\nnewline".into(),
             suggestion_content: "Content".into(),
             score: 6,
+            best_practice: None,
         }];
 
         let result = format_suggestions_table(&suggestions, 9, 7);
@@ -430,7 +703,7 @@ code_suggestions:
     #[test]
     fn test_append_self_review_checkbox_approve_only() {
         let mut body = String::from("table content");
-        append_self_review_checkbox(&mut body, "I reviewed", true, false);
+        append_self_review_checkbox(&mut body, "I reviewed", true, false, false);
         assert!(body.contains("- [ ]  I reviewed"));
         assert!(body.contains("<!-- approve pr self-review -->"));
         assert!(!body.contains("fold"));
@@ -439,7 +712,7 @@ code_suggestions:
     #[test]
     fn test_append_self_review_checkbox_fold_only() {
         let mut body = String::from("table content");
-        append_self_review_checkbox(&mut body, "I reviewed", false, true);
+        append_self_review_checkbox(&mut body, "I reviewed", false, true, false);
         assert!(body.contains("- [ ]  I reviewed"));
         assert!(body.contains("<!-- fold suggestions self-review -->"));
         assert!(!body.contains("approve"));
@@ -448,7 +721,7 @@ code_suggestions:
     #[test]
     fn test_append_self_review_checkbox_both() {
         let mut body = String::from("table content");
-        append_self_review_checkbox(&mut body, "I reviewed", true, true);
+        append_self_review_checkbox(&mut body, "I reviewed", true, true, false);
         assert!(body.contains("- [ ]  I reviewed"));
         assert!(body.contains("<!-- approve and fold suggestions self-review -->"));
     }
@@ -456,9 +729,114 @@ code_suggestions:
     #[test]
     fn test_append_self_review_checkbox_neither() {
         let mut body = String::from("table content");
-        append_self_review_checkbox(&mut body, "I reviewed", false, false);
+        append_self_review_checkbox(&mut body, "I reviewed", false, false, false);
         assert!(body.contains("- [ ]  I reviewed"));
         // When both false, defaults to "approve and fold"
         assert!(body.contains("<!-- approve and fold suggestions self-review -->"));
     }
+
+    #[test]
+    fn test_append_self_review_checkbox_keeps_checked_state() {
+        let mut body = String::from("table content");
+        append_self_review_checkbox(&mut body, "I reviewed", true, false, true);
+        assert!(body.contains("- [x]  I reviewed <!-- approve pr self-review -->"));
+        assert!(is_self_review_checked(&body));
+
+        let mut body = String::from("table content");
+        append_self_review_checkbox(&mut body, "I reviewed", true, false, false);
+        assert!(!is_self_review_checked(&body));
+    }
+
+    fn code_suggestion(file: &str, line: i32) -> CodeSuggestion {
+        CodeSuggestion {
+            body: String::new(),
+            relevant_file: file.into(),
+            relevant_lines_start: line,
+            relevant_lines_end: line,
+            existing_code: String::new(),
+            improved_code: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_batch_code_suggestions_per_file() {
+        let suggestions = vec![
+            code_suggestion("a.rs", 1),
+            code_suggestion("b.rs", 1),
+            code_suggestion("a.rs", 2),
+            code_suggestion("a.rs", 3),
+        ];
+        let batches = batch_code_suggestions(suggestions.clone(), 2);
+        let shape: Vec<(&str, Vec<i32>)> = batches
+            .iter()
+            .map(|b| {
+                (
+                    b.file.as_str(),
+                    b.suggestions
+                        .iter()
+                        .map(|s| s.relevant_lines_start)
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            shape,
+            vec![("a.rs", vec![1, 2]), ("a.rs", vec![3]), ("b.rs", vec![1])]
+        );
+
+        let unlimited = batch_code_suggestions(suggestions, 0);
+        assert_eq!(unlimited.len(), 2);
+        assert_eq!(unlimited[0].suggestions.len(), 3);
+    }
+
+    #[test]
+    fn test_align_to_diff() {
+        let file = FilePatchInfo::new(
+            String::new(),
+            String::new(),
+            "@@ -10,3 +10,4 @@\n fn f() {\n-    old();\n+    let a = 1;\n+    let b = 2;\n }\n"
+                .into(),
+            "src/a.rs".into(),
+        );
+        let suggestion = |start, end, code: &str| CodeSuggestion {
+            existing_code: code.into(),
+            relevant_lines_start: start,
+            relevant_lines_end: end,
+            ..code_suggestion("src/a.rs", start)
+        };
+
+        let aligned = align_to_diff(
+            vec![
+                // Off by one: moved onto the lines holding the code
+                suggestion(10, 11, "  let a = 1;\n  let b = 2;"),
+                // No match, but inside the hunk: kept as is
+                suggestion(13, 13, "paraphrased"),
+                // No match and outside the diff: dropped
+                suggestion(40, 40, "paraphrased"),
+                code_suggestion("src/missing.rs", 1),
+            ],
+            &[file],
+        );
+        let ranges: Vec<(i32, i32)> = aligned
+            .iter()
+            .map(|s| (s.relevant_lines_start, s.relevant_lines_end))
+            .collect();
+        assert_eq!(ranges, vec![(11, 12), (13, 13)]);
+    }
+
+    #[test]
+    fn test_format_suggestion_batches() {
+        let mut batches = batch_code_suggestions(
+            vec![
+                code_suggestion("src/a.rs", 1),
+                code_suggestion("src/b.rs", 1),
+            ],
+            10,
+        );
+        batches[0].url = Some("https://example.com/review/1".into());
+        let out = format_suggestion_batches(&batches);
+        assert!(out.contains("published in 2 review batches"));
+        assert!(out.contains("| [#1](https://example.com/review/1) | `src/a.rs` | 1 |"));
+        assert!(out.contains("| #2 | `src/b.rs` | 1 |"));
+    }
 }
@@ -0,0 +1,63 @@
+/// Parse a JSON object from an AI model response (structured output mode).
+///
+/// Tolerates a surrounding ```` ```json ```` fence. The result is converted
+/// to a YAML value so it feeds the same formatters as [`super::yaml_parser`].
+/// Returns `None` if the response is not a JSON object, so callers can fall
+/// back to `load_yaml`.
+pub fn load_json(response_text: &str) -> Option<serde_yaml_ng::Value> {
+    let trimmed = response_text.trim();
+    let stripped = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .unwrap_or(trimmed)
+        .trim();
+    let cleaned = stripped.strip_suffix("```").unwrap_or(stripped).trim();
+    if !cleaned.starts_with('{') {
+        return None;
+    }
+
+    let value: serde_json::Value = serde_json::from_str(cleaned)
+        .inspect_err(|e| tracing::debug!(error = %e, "JSON parse failed"))
+        .ok()?;
+    if !value.is_object() {
+        return None;
+    }
+    serde_yaml_ng::to_value(value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_json_object() {
+        let data = load_json(
+            r#"{"review": {"estimated_effort_to_review_[1-5]": 2, "key_issues_to_review": []}}"#,
+        )
+        .unwrap();
+        let review = data.get("review").unwrap();
+        assert_eq!(
+            review
+                .get("estimated_effort_to_review_[1-5]")
+                .and_then(|v| v.as_u64()),
+            Some(2)
+        );
+        assert!(review.get("key_issues_to_review").unwrap().is_sequence());
+    }
+
+    #[test]
+    fn test_load_json_fenced() {
+        let data = load_json("```json\n{\"title\": \"Fix: a \\\"quoted\\\" bug\"}\n```").unwrap();
+        assert_eq!(
+            data.get("title").and_then(|v| v.as_str()),
+            Some("Fix: a \"quoted\" bug")
+        );
+    }
+
+    #[test]
+    fn test_load_json_rejects_non_objects() {
+        assert!(load_json("review:\n  score: 80").is_none());
+        assert!(load_json("[1, 2]").is_none());
+        assert!(load_json("{not json").is_none());
+    }
+}
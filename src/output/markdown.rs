@@ -113,6 +113,8 @@ pub fn section_emoji(section: &str) -> &'static str {
         "Estimated effort to review [1-5]" => "\u{23F1}\u{FE0F}", // ⏱️
         "Contribution time cost estimate" => "\u{23F3}",          // ⏳
         "Ticket compliance check" => "\u{1F3AB}",                 // 🎫
+        "Planning context" => "\u{1F5D3}\u{FE0F}",                // 🗓️
+        "Review checklist" => "\u{1F4CB}",                        // 📋
         _ => "",
     }
 }
@@ -128,10 +130,291 @@ pub fn persistent_comment_marker(tool_name: &str) -> String {
     format!("<!-- pr-agent:{tool_name} -->")
 }
 
+/// Prefix shared by every hidden marker the bot writes.
+const BOT_MARKER_PREFIX: &str = "<!-- pr-agent";
+
+/// Make user-controlled text (PR titles, descriptions, comment text) safe to
+/// embed in a bot comment.
+///
+/// Strips hidden bot markers so the text can't pass for a bot comment, and
+/// escapes HTML outside code spans and fenced blocks so `<script>`,
+/// `<details>` or an unclosed `<!--` can't hide or restructure the rest of
+/// the comment. Markdown formatting is kept.
+pub fn sanitize_user_markdown(text: &str) -> String {
+    let text = strip_bot_markers(text);
+    let mut out = String::with_capacity(text.len());
+    let mut in_fence = false;
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            out.push_str(line);
+        } else if in_fence {
+            out.push_str(line);
+        } else {
+            escape_html_outside_code_spans(line, &mut out);
+        }
+    }
+    out
+}
+
+/// [`sanitize_user_markdown`] for a markdown table cell: newlines become
+/// `<br>` and pipes are escaped.
+pub fn sanitize_table_cell(text: &str) -> String {
+    sanitize_user_markdown(&text.replace('\r', ""))
+        .replace('\n', "<br>")
+        .replace('|', "\\|")
+}
+
+/// Wrap user-controlled text in an inline code span that is safe on a single
+/// line or in a table cell.
+pub fn user_code_span(text: &str) -> String {
+    let text = strip_bot_markers(text)
+        .replace('`', "'")
+        .replace(['\r', '\n'], " ")
+        .replace('|', "\\|");
+    format!("`{}`", text.trim())
+}
+
+/// Remove `<!-- pr-agent... -->` markers (an unterminated one up to its prefix).
+fn strip_bot_markers(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(BOT_MARKER_PREFIX) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + BOT_MARKER_PREFIX.len()..];
+        rest = after.find("-->").map_or(after, |end| &after[end + 3..]);
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Escape `<` that would open an HTML tag or comment, except inside `code` spans.
+fn escape_html_outside_code_spans(line: &str, out: &mut String) {
+    let mut in_code = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => {
+                in_code = !in_code;
+                out.push(c);
+            }
+            '<' if !in_code
+                && chars
+                    .peek()
+                    .is_some_and(|n| n.is_ascii_alphabetic() || matches!(n, '/' | '!' | '?')) =>
+            {
+                out.push_str("&lt;");
+            }
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Room left in each part for the `part i/n` label.
+const PART_LABEL_RESERVE: usize = 64;
+
+/// Split a comment longer than `max_chars` bytes into labelled parts.
+///
+/// Parts break on line ends outside fenced code blocks and `<details>` /
+/// `<table>` blocks where possible. The first part keeps the original start
+/// (so a persistent comment marker stays only there) and ends with a
+/// "continued" note; later parts start with `part i/n`. Text within the
+/// limit is returned unchanged as a single part.
+pub fn split_comment(text: &str, max_chars: usize) -> Vec<String> {
+    if text.len() <= max_chars {
+        return vec![text.to_string()];
+    }
+    let budget = max_chars.saturating_sub(PART_LABEL_RESERVE).max(1);
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    // Length of `current` at the last line end outside any block.
+    let mut safe_end = 0;
+    let mut in_fence = false;
+    let mut depth = 0i32;
+    for line in text.split_inclusive('\n') {
+        if !current.is_empty() && current.len() + line.len() > budget {
+            let cut = if safe_end > 0 {
+                safe_end
+            } else {
+                current.len()
+            };
+            let rest = current.split_off(cut);
+            chunks.push(current);
+            current = rest;
+            safe_end = 0;
+        }
+        let mut line = line;
+        while current.len() + line.len() > budget {
+            let mut end = budget - current.len();
+            while end > 0 && !line.is_char_boundary(end) {
+                end -= 1;
+            }
+            current.push_str(&line[..end]);
+            chunks.push(std::mem::take(&mut current));
+            line = &line[end..];
+        }
+        current.push_str(line);
+
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            let lower = line.to_lowercase();
+            depth += (lower.matches("<details").count() + lower.matches("<table").count()) as i32;
+            depth -=
+                (lower.matches("</details>").count() + lower.matches("</table>").count()) as i32;
+        }
+        if !in_fence && depth <= 0 {
+            safe_end = current.len();
+        }
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+
+    let n = chunks.len();
+    if n <= 1 {
+        return chunks;
+    }
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            if i == 0 {
+                format!(
+                    "{}\n\n*(part 1/{n}, continued in the next comment)*",
+                    chunk.trim_end()
+                )
+            } else {
+                format!(
+                    "*(part {}/{n})*\n\n{}",
+                    i + 1,
+                    chunk.trim_start_matches('\n')
+                )
+            }
+        })
+        .collect()
+}
+
+/// Cut a comment longer than `max_chars` bytes down to a single comment.
+///
+/// Used when editing a comment in place: its overflow can't go out as new
+/// comments, or every edit would leave the previous edit's parts behind.
+/// Persistent comments are split beforehand and update their parts instead. Cuts at a line end where possible, never inside a character,
+/// closes a code fence left open and ends with a "truncated" note.
+pub fn truncate_comment(text: &str, max_chars: usize) -> String {
+    if text.len() <= max_chars {
+        return text.to_string();
+    }
+    let budget = max_chars.saturating_sub(PART_LABEL_RESERVE);
+    let end = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .take_while(|&i| i <= budget)
+        .last()
+        .unwrap_or(0);
+    let kept = &text[..end];
+    let kept = match kept.rfind('\n') {
+        Some(line_end) if line_end > 0 => &kept[..line_end],
+        _ => kept,
+    };
+
+    let mut out = kept.to_string();
+    let fences = kept
+        .lines()
+        .map(str::trim_start)
+        .filter(|line| line.starts_with("```") || line.starts_with("~~~"))
+        .count();
+    if fences % 2 == 1 {
+        out.push_str("\n```");
+    }
+    out.push_str("\n\n*(truncated: the full output does not fit in one comment)*");
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_split_comment_short_is_unchanged() {
+        assert_eq!(split_comment("short", 100), vec!["short"]);
+    }
+
+    #[test]
+    fn test_split_comment_keeps_blocks_together() {
+        let block = "<details><summary>Details</summary>\n\nline one\nline two\n\n</details>\n";
+        let text = format!(
+            "<!-- pr-agent:review -->\n## Title\n\n{}```rust\nlet a = 1;\nlet b = 2;\n```\n",
+            block.repeat(3)
+        );
+        let parts = split_comment(&text, 64 + 80);
+        assert!(parts.len() > 1);
+        assert!(parts[0].starts_with("<!-- pr-agent:review -->"));
+        assert!(parts[0].ends_with(&format!(
+            "*(part 1/{}, continued in the next comment)*",
+            parts.len()
+        )));
+        for (i, part) in parts.iter().enumerate().skip(1) {
+            assert!(part.starts_with(&format!("*(part {}/{})*", i + 1, parts.len())));
+            assert!(!part.contains("pr-agent:review"));
+        }
+        for part in &parts {
+            assert!(part.len() <= 144);
+            assert_eq!(
+                part.matches("<details>").count(),
+                part.matches("</details>").count()
+            );
+            assert_eq!(part.matches("```").count() % 2, 0);
+        }
+    }
+
+    #[test]
+    fn test_split_comment_hard_cuts_long_lines() {
+        let text = "é".repeat(200);
+        let parts = split_comment(&text, 64 + 50);
+        assert!(parts.len() > 1);
+        assert!(parts.iter().all(|p| p.len() <= 114));
+        let rejoined: String = parts
+            .iter()
+            .map(|p| {
+                p.split(")*\n\n")
+                    .last()
+                    .unwrap()
+                    .split("\n\n*(part")
+                    .next()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(rejoined, text);
+    }
+
+    #[test]
+    fn test_truncate_comment() {
+        assert_eq!(truncate_comment("short", 100), "short");
+
+        let text = format!("<!-- marker -->\n```rust\n{}", "let x = 1;\n".repeat(40));
+        let out = truncate_comment(&text, 64 + 100);
+        assert!(out.len() <= 164);
+        assert!(out.starts_with("<!-- marker -->\n```rust\nlet x = 1;\n"));
+        assert!(out.ends_with(
+            "let x = 1;\n```\n\n*(truncated: the full output does not fit in one comment)*"
+        ));
+    }
+
+    #[test]
+    fn test_truncate_comment_keeps_whole_characters() {
+        let out = truncate_comment(&"é".repeat(200), 64 + 51);
+        assert!(out.starts_with(&"é".repeat(25)));
+        assert!(out.ends_with("*(truncated: the full output does not fit in one comment)*"));
+        assert!(out.len() <= 115);
+    }
+
     #[test]
     fn test_collapsible_section() {
         let result = collapsible_section("Click me", "Hidden content");
@@ -198,4 +481,30 @@ mod tests {
         let marker = persistent_comment_marker("review");
         assert_eq!(marker, "<!-- pr-agent:review -->");
     }
+
+    #[test]
+    fn test_sanitize_user_markdown() {
+        let input =
+            "Fix <script>alert(1)</script> **bug**\n<!-- pr-agent:review -->\n<details><!-- hide";
+        assert_eq!(
+            sanitize_user_markdown(input),
+            "Fix &lt;script>alert(1)&lt;/script> **bug**\n\n&lt;details>&lt;!-- hide"
+        );
+
+        // Code spans and fenced blocks are left alone.
+        let input = "Use `Vec<u8>` here\n```rust\nlet v: Vec<u8> = vec![];\n```\n1 < 2";
+        assert_eq!(sanitize_user_markdown(input), input);
+    }
+
+    #[test]
+    fn test_sanitize_table_cell_and_code_span() {
+        assert_eq!(
+            sanitize_table_cell("a | b\r\n<img src=x>"),
+            "a \\| b<br>&lt;img src=x>"
+        );
+        assert_eq!(
+            user_code_span("name` | <!-- pr-agent:audit -->x\ny"),
+            "`name' \\| x y`"
+        );
+    }
 }
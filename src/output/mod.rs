@@ -1,5 +1,10 @@
 pub mod describe_formatter;
+pub mod description_document;
+pub mod help_text;
 pub mod improve_formatter;
+pub mod json_parser;
 pub mod markdown;
+pub mod report;
 pub mod review_formatter;
+pub mod terminal;
 pub mod yaml_parser;
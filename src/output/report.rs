@@ -0,0 +1,287 @@
+//! Result files for other CI steps (`--output-format`, `--output-file`).
+//!
+//! `review`, `describe`, and `improve` write one report per run, as JSON
+//! with normalized fields or as the markdown they would publish, whether or
+//! not the result is also posted to the PR.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::error::PrAgentError;
+use crate::output::describe_formatter::{DescribeOutput, FileEntry};
+use crate::output::improve_formatter::{
+    ParsedSuggestion, format_suggestions_table, importance_label,
+};
+use crate::output::review_formatter::{
+    extract_effort_score, format_review_markdown, yaml_value_to_string,
+};
+
+/// Format of the `--output-file` report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Normalized JSON: findings, suggestions, and description fields.
+    #[default]
+    Json,
+    /// The markdown the tool would publish.
+    Markdown,
+}
+
+struct ReportTarget {
+    format: ReportFormat,
+    path: PathBuf,
+}
+
+static TARGET: OnceLock<ReportTarget> = OnceLock::new();
+
+/// Write reports to `path` in `format`. Without this call (e.g. in the
+/// webhook server) [`write`] does nothing.
+pub fn init(format: ReportFormat, path: impl Into<PathBuf>) {
+    let _ = TARGET.set(ReportTarget {
+        format,
+        path: path.into(),
+    });
+}
+
+/// Whether a report file was requested.
+pub fn is_enabled() -> bool {
+    TARGET.get().is_some()
+}
+
+/// A tool result as written to the report file.
+#[derive(Debug, Serialize)]
+#[serde(tag = "tool", rename_all = "snake_case")]
+pub enum Report {
+    Review(ReviewReport),
+    Describe(DescribeReport),
+    Improve(ImproveReport),
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewReport {
+    pub pr_url: String,
+    /// Estimated review effort, 1-5.
+    pub estimated_effort: Option<u8>,
+    pub security_concerns: Option<String>,
+    pub relevant_tests: Option<String>,
+    pub findings: Vec<Finding>,
+    #[serde(skip)]
+    markdown: String,
+}
+
+/// One entry of the review's key issues.
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub file: String,
+    pub start_line: Option<i64>,
+    pub end_line: Option<i64>,
+    pub header: String,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DescribeReport {
+    pub pr_url: String,
+    pub title: String,
+    pub pr_type: String,
+    pub description: String,
+    pub labels: Vec<String>,
+    pub files: Vec<FileEntry>,
+    #[serde(skip)]
+    markdown: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImproveReport {
+    pub pr_url: String,
+    pub suggestions: Vec<ReportedSuggestion>,
+    #[serde(skip)]
+    markdown: String,
+}
+
+/// A code suggestion with its importance level (`Critical`, `Important`,
+/// `Minor`) from the score thresholds.
+#[derive(Debug, Serialize)]
+pub struct ReportedSuggestion {
+    #[serde(flatten)]
+    pub suggestion: ParsedSuggestion,
+    pub importance: &'static str,
+}
+
+impl Report {
+    /// Build a review report from the parsed response; falls back to the
+    /// raw response as markdown when it could not be parsed.
+    pub fn review(pr_url: &str, data: Option<&serde_yaml_ng::Value>, raw_response: &str) -> Self {
+        let review = data.map(|d| d.get("review").unwrap_or(d));
+        let text = |key: &str| review.and_then(|r| r.get(key)).map(yaml_value_to_string);
+        let estimated_effort = review.and_then(|r| {
+            r.get("estimated_effort_to_review_[1-5]")
+                .or_else(|| r.get("estimated_effort_to_review"))
+                .map(extract_effort_score)
+        });
+        let findings = review
+            .and_then(|r| r.get("key_issues_to_review"))
+            .and_then(|v| v.as_sequence())
+            .map(|issues| issues.iter().map(Finding::from_yaml).collect())
+            .unwrap_or_default();
+        Report::Review(ReviewReport {
+            pr_url: pr_url.to_string(),
+            estimated_effort,
+            security_concerns: text("security_concerns"),
+            relevant_tests: text("relevant_tests"),
+            findings,
+            markdown: match data {
+                Some(d) => format_review_markdown(d, true, None),
+                None => raw_response.to_string(),
+            },
+        })
+    }
+
+    /// Build a describe report from the parsed response and its formatted
+    /// output.
+    pub fn describe(pr_url: &str, data: &serde_yaml_ng::Value, output: &DescribeOutput) -> Self {
+        let field = |key: &str| data.get(key).map(yaml_value_to_string).unwrap_or_default();
+        let files = data
+            .get("pr_files")
+            .and_then(|v| v.as_sequence())
+            .map(|files| files.iter().map(FileEntry::from_yaml).collect())
+            .unwrap_or_default();
+        Report::Describe(DescribeReport {
+            pr_url: pr_url.to_string(),
+            title: output.title.clone(),
+            pr_type: field("type"),
+            description: field("description"),
+            labels: output.labels.clone(),
+            files,
+            markdown: format!("# {}\n\n{}", output.title, output.body),
+        })
+    }
+
+    /// Build an improve report, ranking suggestions with the given thresholds.
+    pub fn improve(
+        pr_url: &str,
+        suggestions: &[ParsedSuggestion],
+        th_high: u32,
+        th_medium: u32,
+    ) -> Self {
+        Report::Improve(ImproveReport {
+            pr_url: pr_url.to_string(),
+            suggestions: suggestions
+                .iter()
+                .map(|s| ReportedSuggestion {
+                    suggestion: s.clone(),
+                    importance: importance_label(s.score, th_high, th_medium),
+                })
+                .collect(),
+            markdown: format_suggestions_table(suggestions, th_high, th_medium),
+        })
+    }
+
+    /// The report in `format`.
+    pub fn render(&self, format: ReportFormat) -> Result<String, PrAgentError> {
+        Ok(match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Markdown => match self {
+                Report::Review(r) => r.markdown.clone(),
+                Report::Describe(r) => r.markdown.clone(),
+                Report::Improve(r) => r.markdown.clone(),
+            },
+        })
+    }
+}
+
+impl Finding {
+    fn from_yaml(issue: &serde_yaml_ng::Value) -> Self {
+        let field = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|n| issue.get(*n))
+                .map(yaml_value_to_string)
+                .unwrap_or_default()
+        };
+        let line = |name: &str| field(&[name]).parse().ok();
+        Self {
+            file: field(&["relevant_file"]),
+            start_line: line("start_line"),
+            end_line: line("end_line"),
+            header: field(&["issue_header", "header"]),
+            content: field(&["issue_content", "content"]),
+        }
+    }
+}
+
+/// Write `report` to the `--output-file`, replacing it; no-op when no
+/// report file was requested.
+pub fn write(report: &Report) -> Result<(), PrAgentError> {
+    let Some(target) = TARGET.get() else {
+        return Ok(());
+    };
+    std::fs::write(&target.path, report.render(target.format)?)?;
+    tracing::info!(path = %target.path.display(), "wrote result report");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review_report_json() {
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(
+            r#"
+review:
+  estimated_effort_to_review_[1-5]: 3
+  security_concerns: "No"
+  key_issues_to_review:
+    - relevant_file: src/lib.rs
+      issue_header: Possible Bug
+      issue_content: Off by one
+      start_line: 10
+      end_line: 12
+"#,
+        )
+        .unwrap();
+        let report = Report::review("https://example.com/pr/1", Some(&data), "");
+        let json: serde_json::Value =
+            serde_json::from_str(&report.render(ReportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["tool"], "review");
+        assert_eq!(json["estimated_effort"], 3);
+        assert_eq!(json["security_concerns"], "No");
+        assert_eq!(json["findings"][0]["file"], "src/lib.rs");
+        assert_eq!(json["findings"][0]["start_line"], 10);
+        assert_eq!(json["findings"][0]["end_line"], 12);
+        assert_eq!(json["findings"][0]["header"], "Possible Bug");
+        assert!(json.get("markdown").is_none());
+
+        let markdown = report.render(ReportFormat::Markdown).unwrap();
+        assert!(markdown.contains("<strong>Possible Issue</strong>"));
+        assert!(markdown.contains("Off by one"));
+    }
+
+    #[test]
+    fn test_improve_report_json() {
+        let suggestion = ParsedSuggestion {
+            label: "possible issue".into(),
+            relevant_file: "src/main.rs".into(),
+            relevant_lines_start: 3,
+            relevant_lines_end: 5,
+            existing_code: "a".into(),
+            improved_code: "b".into(),
+            one_sentence_summary: "Handle the empty case".into(),
+            suggestion_content: String::new(),
+            score: 8,
+            best_practice: None,
+        };
+        let report = Report::improve("https://example.com/pr/1", &[suggestion], 9, 7);
+        let json: serde_json::Value =
+            serde_json::from_str(&report.render(ReportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["tool"], "improve");
+        let s = &json["suggestions"][0];
+        assert_eq!(s["relevant_file"], "src/main.rs");
+        assert_eq!(s["relevant_lines_start"], 3);
+        assert_eq!(s["relevant_lines_end"], 5);
+        assert_eq!(s["score"], 8);
+        assert_eq!(s["importance"], "Important");
+    }
+}
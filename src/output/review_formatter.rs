@@ -1,7 +1,9 @@
 use std::fmt::Write;
 
+use crate::config::types::PrReviewerConfig;
 use crate::output::markdown::{
-    collapsible_section, effort_bar, persistent_comment_marker, section_emoji,
+    collapsible_section, effort_bar, persistent_comment_marker, sanitize_table_cell,
+    sanitize_user_markdown, section_emoji,
 };
 
 /// A function that generates a link to a file in the PR diff view.
@@ -10,6 +12,28 @@ use crate::output::markdown::{
 /// When None, no links are generated.
 pub type LinkGenerator = Box<dyn Fn(&str, i32, Option<i32>) -> String + Send + Sync>;
 
+/// How the key issues are laid out in the review table.
+#[derive(Default)]
+pub struct FindingsLayout {
+    /// Group findings into collapsible sections per category.
+    pub collapsible: bool,
+    /// Categories (lowercase issue headers, `security` for security findings)
+    /// listed first, in this order.
+    pub section_order: Vec<String>,
+    /// Findings shown per section (`0` = all).
+    pub max_per_section: usize,
+}
+
+impl FindingsLayout {
+    pub fn from_config(config: &PrReviewerConfig) -> Self {
+        Self {
+            collapsible: config.collapsible_findings,
+            section_order: config.findings_section_order.clone(),
+            max_per_section: config.max_findings_per_section as usize,
+        }
+    }
+}
+
 /// Convert a parsed review YAML response into formatted GitHub markdown.
 ///
 /// `link_gen` optionally provides a function to generate clickable file links.
@@ -17,6 +41,16 @@ pub fn format_review_markdown(
     data: &serde_yaml_ng::Value,
     gfm_supported: bool,
     link_gen: Option<&LinkGenerator>,
+) -> String {
+    format_review_markdown_with_layout(data, gfm_supported, link_gen, &FindingsLayout::default())
+}
+
+/// [`format_review_markdown`] with the key issues laid out per `layout`.
+pub fn format_review_markdown_with_layout(
+    data: &serde_yaml_ng::Value,
+    gfm_supported: bool,
+    link_gen: Option<&LinkGenerator>,
+    layout: &FindingsLayout,
 ) -> String {
     let mut out = String::with_capacity(8_000);
 
@@ -33,7 +67,7 @@ pub fn format_review_markdown(
     }
 
     if gfm_supported {
-        format_review_gfm(review, &mut out, link_gen);
+        format_review_gfm(review, &mut out, link_gen, layout);
     } else {
         format_review_plain(review, &mut out);
     }
@@ -41,11 +75,50 @@ pub fn format_review_markdown(
     out
 }
 
+/// Section id used by `pr_reviewer.sections` for a review YAML key.
+///
+/// Keys without a dedicated id (e.g. `insights_from_user_answers`) use
+/// their own name.
+pub fn review_section_id(key: &str) -> &str {
+    match key {
+        "estimated_effort_to_review_[1-5]" | "estimated_effort_to_review" => "effort",
+        "relevant_tests" | "untested_changes" => "tests",
+        "key_issues_to_review" | "possible_issues" => "issues",
+        "security_concerns" | "security_severity" => "security",
+        "can_be_split" => "split",
+        "ticket_compliance_check" | "related_tickets" => "ticket",
+        "todo_sections" | "todo_summary" => "todo",
+        "contribution_time_cost_estimate" => "contribution_time",
+        other => other,
+    }
+}
+
+/// Drop review sections that are not in the `sections` whitelist.
+///
+/// An empty whitelist keeps everything.
+pub fn retain_review_sections(data: &mut serde_yaml_ng::Value, sections: &[String]) {
+    if sections.is_empty() {
+        return;
+    }
+    let review = if data.get("review").is_some() {
+        &mut data["review"]
+    } else {
+        data
+    };
+    if let Some(map) = review.as_mapping_mut() {
+        map.retain(|key, _| {
+            let id = review_section_id(key.as_str().unwrap_or_default());
+            sections.iter().any(|s| s == id)
+        });
+    }
+}
+
 /// Format review using GitHub Flavored Markdown (HTML tables).
 fn format_review_gfm(
     review: &serde_yaml_ng::Value,
     out: &mut String,
     link_gen: Option<&LinkGenerator>,
+    layout: &FindingsLayout,
 ) {
     out.push_str("<table>\n");
 
@@ -53,6 +126,11 @@ fn format_review_gfm(
         return;
     };
 
+    // Milestone/project context leads the table regardless of key order
+    if let Some(planning) = review.get("planning_context") {
+        format_planning_row(planning, out);
+    }
+
     for (key, value) in mapping {
         let key_str = key.as_str().unwrap_or_default();
 
@@ -71,16 +149,24 @@ fn format_review_gfm(
                 format_score_row(value, out);
             }
             "relevant_tests" => {
-                format_relevant_tests_row(value, out);
+                format_relevant_tests_row(value, review.get("untested_changes"), out);
+            }
+            // Normally folded into the relevant_tests row
+            "untested_changes" => {
+                if review.get("relevant_tests").is_none() {
+                    let emoji = section_emoji("Relevant tests");
+                    let files = untested_changes_html(value);
+                    let _ = writeln!(out, "<tr><td>{emoji}&nbsp;{files}</td></tr>");
+                }
             }
             "possible_issues" => {
                 format_simple_row("⚡ Possible issues", value, out);
             }
             "security_concerns" => {
-                format_security_row(value, out);
+                format_security_row(value, security_severity(review), out);
             }
             "key_issues_to_review" => {
-                format_key_issues_rows(value, out, link_gen);
+                format_key_issues_rows(value, out, link_gen, layout);
             }
             "can_be_split" => {
                 format_simple_row("🔀 Can be split", value, out);
@@ -88,11 +174,17 @@ fn format_review_gfm(
             "ticket_compliance_check" => {
                 format_simple_row("🎫 Ticket compliance", value, out);
             }
+            "related_tickets" => {
+                format_simple_row("🎫 Related tickets", value, out);
+            }
             "todo_sections" => {
                 format_todo_sections_row(value, out);
             }
-            // Skip internal fields that shouldn't be rendered
-            "todo_summary" => {}
+            "checklist" => {
+                format_checklist_row(value, out);
+            }
+            // Skip internal fields that shouldn't be rendered (or were already)
+            "todo_summary" | "planning_context" | "security_severity" => {}
             _ => {
                 // Generic section
                 let emoji = section_emoji(key_str);
@@ -132,24 +224,62 @@ fn format_score_row(value: &serde_yaml_ng::Value, out: &mut String) {
     );
 }
 
+/// Format the milestone/project context injected by the reviewer.
+fn format_planning_row(value: &serde_yaml_ng::Value, out: &mut String) {
+    let text = yaml_value_to_string(value);
+    if text.trim().is_empty() {
+        return;
+    }
+    let emoji = section_emoji("Planning context");
+    let lines = sanitize_user_markdown(text.trim()).replace('\n', "<br>");
+    let _ = writeln!(
+        out,
+        "<tr><td>{emoji}&nbsp;<strong>Planning context</strong><br>{lines}</td></tr>"
+    );
+}
+
 /// Format the relevant tests row as an HTML table row.
-fn format_relevant_tests_row(value: &serde_yaml_ng::Value, out: &mut String) {
+///
+/// `untested` is the test-impact list injected by the reviewer, if any.
+fn format_relevant_tests_row(
+    value: &serde_yaml_ng::Value,
+    untested: Option<&serde_yaml_ng::Value>,
+    out: &mut String,
+) {
     let emoji = section_emoji("Relevant tests");
     let text = yaml_value_to_string(value);
+    let untested = untested
+        .map(|v| format!("<br><br>{}", untested_changes_html(v)))
+        .unwrap_or_default();
 
     if is_value_no(&text) {
         let _ = writeln!(
             out,
-            "<tr><td>{emoji}&nbsp;<strong>No relevant tests</strong></td></tr>"
+            "<tr><td>{emoji}&nbsp;<strong>No relevant tests</strong>{untested}</td></tr>"
         );
     } else {
         let _ = writeln!(
             out,
-            "<tr><td>{emoji}&nbsp;<strong>PR contains tests</strong></td></tr>"
+            "<tr><td>{emoji}&nbsp;<strong>PR contains tests</strong>{untested}</td></tr>"
         );
     }
 }
 
+/// Render the list of changed source files that have no matching test changes.
+fn untested_changes_html(value: &serde_yaml_ng::Value) -> String {
+    let files: Vec<String> = match value.as_sequence() {
+        Some(seq) => seq
+            .iter()
+            .map(|f| format!("<code>{}</code>", yaml_value_to_string(f)))
+            .collect(),
+        None => vec![yaml_value_to_string(value)],
+    };
+    format!(
+        "Changed files without matching test updates:<br>{}",
+        files.join("<br>")
+    )
+}
+
 /// Format todo sections as HTML table rows.
 fn format_todo_sections_row(value: &serde_yaml_ng::Value, out: &mut String) {
     let text = yaml_value_to_string(value);
@@ -168,8 +298,55 @@ fn format_todo_sections_row(value: &serde_yaml_ng::Value, out: &mut String) {
     }
 }
 
+/// Format the team review checklist as a question/answer table.
+fn format_checklist_row(value: &serde_yaml_ng::Value, out: &mut String) {
+    let Some(entries) = value.as_sequence().filter(|s| !s.is_empty()) else {
+        return;
+    };
+    let emoji = section_emoji("Review checklist");
+    let _ = writeln!(
+        out,
+        "<tr><td>{emoji}&nbsp;<strong>Review checklist</strong><br><br>\n<table>\n<tr><th>Question</th><th>Answer</th><th>Notes</th></tr>"
+    );
+    for entry in entries {
+        let (question, answer, explanation) = checklist_entry(entry);
+        let cell = |text: &str| sanitize_user_markdown(text).replace('\n', "<br>");
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            cell(&question),
+            checklist_answer(&answer),
+            cell(&explanation)
+        );
+    }
+    let _ = writeln!(out, "</table>\n</td></tr>");
+}
+
+/// `(question, answer, explanation)` of a checklist entry, trimmed.
+fn checklist_entry(entry: &serde_yaml_ng::Value) -> (String, String, String) {
+    let field = |key: &str| {
+        entry
+            .get(key)
+            .map(yaml_value_to_string)
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+    (field("question"), field("answer"), field("explanation"))
+}
+
+/// Display form of a checklist answer: yes, no, N/A, or the raw answer.
+fn checklist_answer(answer: &str) -> String {
+    match answer.to_lowercase().trim_end_matches('.') {
+        "yes" | "true" => "✅ Yes".to_string(),
+        "no" | "false" => "❌ No".to_string(),
+        "n/a" | "na" | "n-a" | "not applicable" => "➖ N/A".to_string(),
+        _ => format!("❔ {}", sanitize_table_cell(answer)),
+    }
+}
+
 /// Format security concerns with collapsible details.
-fn format_security_row(value: &serde_yaml_ng::Value, out: &mut String) {
+fn format_security_row(value: &serde_yaml_ng::Value, severity: Option<&str>, out: &mut String) {
     let text = yaml_value_to_string(value);
     let emoji = section_emoji("Security concerns");
 
@@ -179,18 +356,30 @@ fn format_security_row(value: &serde_yaml_ng::Value, out: &mut String) {
             "<tr><td>{emoji}&nbsp;<strong>No security concerns identified</strong></td></tr>"
         );
     } else {
-        let details = collapsible_section("Security concerns", &text);
+        let title = match severity {
+            Some(severity) => format!("Security concerns ({severity} severity)"),
+            None => "Security concerns".to_string(),
+        };
+        let details = collapsible_section(&title, &text);
         let _ = writeln!(out, "<tr><td>{emoji}&nbsp;{details}</td></tr>");
     }
 }
 
+/// Hidden marker in front of review finding `n` (1-based), so `/resolve n`
+/// and `/wontfix n` can locate it when editing the review.
+pub fn finding_marker(n: usize) -> String {
+    persistent_comment_marker(&format!("finding {n}"))
+}
+
 /// Format key issues to review as individual rows with file links.
 ///
-/// Formats the "key issues to review" section as linked HTML rows.
+/// Formats the "key issues to review" section as linked HTML rows. Findings
+/// are numbered so maintainers can reply `/resolve 2` or `/wontfix 3 reason`.
 fn format_key_issues_rows(
     value: &serde_yaml_ng::Value,
     out: &mut String,
     link_gen: Option<&LinkGenerator>,
+    layout: &FindingsLayout,
 ) {
     let emoji = section_emoji("Key issues to review");
 
@@ -226,51 +415,169 @@ fn format_key_issues_rows(
         "<tr><td>{emoji}&nbsp;<strong>Recommended focus areas for review</strong><br><br>\n\n"
     );
 
-    for issue in issues {
-        // Support both field name variants: issue_header/issue_content and header/content
-        // .trim() all values to strip YAML trailing newlines
-        let header = issue
-            .get("issue_header")
-            .or(issue.get("header"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.trim())
-            .unwrap_or("Issue");
-        // Rename "Possible Bug" to "Possible Issue" for display
-        let header = if header.eq_ignore_ascii_case("possible bug") {
-            "Possible Issue"
+    if layout.collapsible {
+        format_grouped_findings(issues, layout, out, link_gen);
+    } else {
+        for (i, issue) in issues.iter().enumerate() {
+            let _ = writeln!(out, "{}\n", key_issue_entry(i + 1, issue, link_gen));
+        }
+    }
+
+    let _ = writeln!(out, "</td></tr>");
+}
+
+/// Group findings into collapsible sections by category.
+///
+/// Sections listed in `layout.section_order` come first, in that order; the
+/// rest follow by their most severe finding. Findings within a section are
+/// sorted by severity and keep their original numbers for `/resolve N`.
+fn format_grouped_findings(
+    issues: &[serde_yaml_ng::Value],
+    layout: &FindingsLayout,
+    out: &mut String,
+    link_gen: Option<&LinkGenerator>,
+) {
+    // (category, findings as (number, issue))
+    let mut sections: Vec<(String, Vec<(usize, &serde_yaml_ng::Value)>)> = Vec::new();
+    for (i, issue) in issues.iter().enumerate() {
+        let category = finding_category(issue);
+        match sections.iter_mut().find(|(c, _)| *c == category) {
+            Some((_, findings)) => findings.push((i + 1, issue)),
+            None => sections.push((category, vec![(i + 1, issue)])),
+        }
+    }
+    for (_, findings) in &mut sections {
+        findings.sort_by_key(|(_, issue)| severity_rank(issue));
+    }
+    let position = |category: &str| {
+        layout
+            .section_order
+            .iter()
+            .position(|c| c.eq_ignore_ascii_case(category))
+            .unwrap_or(usize::MAX)
+    };
+    sections.sort_by_key(|(category, findings)| {
+        let top = findings.first().map_or(u8::MAX, |(_, f)| severity_rank(f));
+        (position(category), top)
+    });
+
+    for (category, findings) in &sections {
+        let shown = if layout.max_per_section > 0 {
+            findings.len().min(layout.max_per_section)
         } else {
-            header
+            findings.len()
         };
+        let mut body = String::new();
+        for (n, issue) in &findings[..shown] {
+            let _ = writeln!(body, "{}\n", key_issue_entry(*n, issue, link_gen));
+        }
+        if shown < findings.len() {
+            let _ = writeln!(body, "<i>{} more not shown</i>\n", findings.len() - shown);
+        }
+        let title = format!(
+            "<strong>{}</strong> ({})",
+            capitalize_words(category),
+            findings.len()
+        );
+        out.push_str(&collapsible_section(&title, body.trim_end()));
+        out.push('\n');
+    }
+}
 
-        let body = issue
-            .get("issue_content")
-            .or(issue.get("content"))
-            .or(issue.get("details"))
-            .or(issue.get("suggestion"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.trim())
-            .unwrap_or("");
-        let file = issue
-            .get("relevant_file")
-            .and_then(|v| v.as_str())
-            .map(|s| s.trim())
-            .unwrap_or("");
-
-        // Prefer start_line/end_line; fall back to relevant_line
-        let start_line_str = issue
-            .get("start_line")
-            .map(yaml_value_to_string)
-            .unwrap_or_default();
-        let end_line_str = issue
-            .get("end_line")
-            .map(yaml_value_to_string)
-            .unwrap_or_default();
-        let start_line_num: i32 = start_line_str.parse().unwrap_or(0);
-        let end_line_num: i32 = end_line_str.parse().unwrap_or(0);
+/// Section a finding is grouped under: `security` for security findings,
+/// otherwise its lowercase header.
+fn finding_category(issue: &serde_yaml_ng::Value) -> String {
+    let header = finding_header(issue).to_lowercase();
+    if header.contains("security") || header.contains("vulnerab") {
+        "security".to_string()
+    } else {
+        header
+    }
+}
+
+/// Sort rank of a finding's `severity` (high first, missing last).
+fn severity_rank(issue: &serde_yaml_ng::Value) -> u8 {
+    match finding_severity(issue).as_deref() {
+        Some("high") => 0,
+        Some("medium") => 1,
+        Some("low") => 2,
+        _ => 3,
+    }
+}
+
+fn finding_severity(issue: &serde_yaml_ng::Value) -> Option<String> {
+    issue
+        .get("severity")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| matches!(s.as_str(), "high" | "medium" | "low"))
+}
+
+/// Display header of a finding ("Possible Bug" is shown as "Possible Issue").
+fn finding_header(issue: &serde_yaml_ng::Value) -> &str {
+    // Support both field name variants: issue_header/issue_content and header/content
+    let header = issue
+        .get("issue_header")
+        .or(issue.get("header"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .unwrap_or("Issue");
+    if header.eq_ignore_ascii_case("possible bug") {
+        "Possible Issue"
+    } else {
+        header
+    }
+}
+
+fn capitalize_words(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().collect::<String>() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// One numbered finding: linked header, file/line info and body.
+fn key_issue_entry(
+    number: usize,
+    issue: &serde_yaml_ng::Value,
+    link_gen: Option<&LinkGenerator>,
+) -> String {
+    // .trim() all values to strip YAML trailing newlines
+    let header = finding_header(issue);
+    let body = issue
+        .get("issue_content")
+        .or(issue.get("content"))
+        .or(issue.get("details"))
+        .or(issue.get("suggestion"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .unwrap_or("");
+    let file = issue
+        .get("relevant_file")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .unwrap_or("");
+
+    // Prefer start_line/end_line; fall back to relevant_line
+    let start_line_str = issue
+        .get("start_line")
+        .map(yaml_value_to_string)
+        .unwrap_or_default();
+    let end_line_str = issue
+        .get("end_line")
+        .map(yaml_value_to_string)
+        .unwrap_or_default();
+    let start_line_num: i32 = start_line_str.parse().unwrap_or(0);
+    let end_line_num: i32 = end_line_str.parse().unwrap_or(0);
 
-        let line_display = if !start_line_str.is_empty()
-            && !end_line_str.is_empty()
-            && start_line_str != end_line_str
+    let line_display =
+        if !start_line_str.is_empty() && !end_line_str.is_empty() && start_line_str != end_line_str
         {
             format!("{start_line_str}-{end_line_str}")
         } else if !start_line_str.is_empty() {
@@ -282,49 +589,53 @@ fn format_key_issues_rows(
                 .unwrap_or_default()
         };
 
-        // Generate link if provider is available
-        let reference_link: Option<String> = if !file.is_empty() {
-            link_gen.map(|link_fn| {
-                let end = if end_line_num > 0 && end_line_num != start_line_num {
-                    Some(end_line_num)
-                } else {
-                    None
-                };
-                link_fn(file, start_line_num, end)
-            })
-        } else {
-            None
-        };
+    // Generate link if provider is available
+    let reference_link: Option<String> = if !file.is_empty() {
+        link_gen.map(|link_fn| {
+            let end = if end_line_num > 0 && end_line_num != start_line_num {
+                Some(end_line_num)
+            } else {
+                None
+            };
+            link_fn(file, start_line_num, end)
+        })
+    } else {
+        None
+    };
 
-        // Build the issue entry in GFM format
-        // All issues are within the same <td>, not separate rows
-        let header_html = match &reference_link {
-            Some(link) if !link.is_empty() => {
-                format!("<a href='{link}'><strong>{header}</strong></a>")
-            }
-            _ => format!("<strong>{header}</strong>"),
-        };
+    // Build the issue entry in GFM format
+    // All issues are within the same <td>, not separate rows
+    let header_html = match &reference_link {
+        Some(link) if !link.is_empty() => {
+            format!("<a href='{link}'><strong>{header}</strong></a>")
+        }
+        _ => format!("<strong>{header}</strong>"),
+    };
 
-        let file_info = if !file.is_empty() {
-            if !line_display.is_empty() {
-                format!("<br><code>{file}</code> (line {line_display})")
-            } else {
-                format!("<br><code>{file}</code>")
-            }
+    let file_info = if !file.is_empty() {
+        if !line_display.is_empty() {
+            format!("<br><code>{file}</code> (line {line_display})")
         } else {
-            String::new()
-        };
+            format!("<br><code>{file}</code>")
+        }
+    } else {
+        String::new()
+    };
 
-        let body_html = if !body.is_empty() {
-            format!("<br>{body}")
-        } else {
-            String::new()
-        };
+    let body_html = if !body.is_empty() {
+        format!("<br>{body}")
+    } else {
+        String::new()
+    };
 
-        let _ = writeln!(out, "{header_html}{file_info}{body_html}\n");
-    }
+    let severity = finding_severity(issue)
+        .map(|s| format!(" <code>{s}</code>"))
+        .unwrap_or_default();
 
-    let _ = writeln!(out, "</td></tr>");
+    format!(
+        "{}{number}.&nbsp;{header_html}{severity}{file_info}{body_html}",
+        finding_marker(number)
+    )
 }
 
 /// Format a simple key-value row. Skips "No"/"None"/"False" values.
@@ -342,12 +653,42 @@ fn format_review_plain(review: &serde_yaml_ng::Value, out: &mut String) {
         return;
     };
 
+    if let Some(planning) = review.get("planning_context") {
+        let text = sanitize_user_markdown(yaml_value_to_string(planning).trim());
+        if !text.is_empty() {
+            let emoji = section_emoji("Planning context");
+            let _ = writeln!(out, "{emoji} **Planning context**:\n{text}\n");
+        }
+    }
+
     for (key, value) in mapping {
         let key_str = key.as_str().unwrap_or_default();
         let emoji = section_emoji(key_str);
         let text = yaml_value_to_string(value);
 
-        if text.is_empty() {
+        if text.is_empty() || key_str == "planning_context" {
+            continue;
+        }
+
+        if key_str == "checklist"
+            && let Some(entries) = value.as_sequence()
+        {
+            let emoji = section_emoji("Review checklist");
+            let _ = writeln!(
+                out,
+                "{emoji} **Review checklist**:\n\n| Question | Answer | Notes |\n|---|---|---|"
+            );
+            for entry in entries {
+                let (question, answer, explanation) = checklist_entry(entry);
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    sanitize_table_cell(&question),
+                    checklist_answer(&answer),
+                    sanitize_table_cell(&explanation)
+                );
+            }
+            out.push('\n');
             continue;
         }
 
@@ -380,6 +721,19 @@ pub(crate) fn extract_effort_score(value: &serde_yaml_ng::Value) -> u8 {
         .unwrap_or(3)
 }
 
+/// Severity (`high`, `medium` or `low`) of the review's security concerns,
+/// or `None` when there are none or the model gave no severity.
+pub(crate) fn security_severity(review: &serde_yaml_ng::Value) -> Option<&'static str> {
+    let concerns = review.get("security_concerns").map(yaml_value_to_string)?;
+    if is_value_no(&concerns) {
+        return None;
+    }
+    let severity = yaml_value_to_string(review.get("security_severity")?).to_lowercase();
+    ["high", "medium", "low"]
+        .into_iter()
+        .find(|level| severity.starts_with(level))
+}
+
 /// Check if a value represents "no" (handles "no", "none", empty, etc.).
 pub(crate) fn is_value_no(text: &str) -> bool {
     let t = text.trim().to_lowercase();
@@ -504,6 +858,69 @@ review:
         assert!(!result.contains("Relevant tests: Yes"));
     }
 
+    #[test]
+    fn test_relevant_tests_row_lists_untested_changes() {
+        let yaml_str = r#"
+review:
+  relevant_tests: "No"
+  untested_changes:
+    - src/parser.rs
+    - src/lexer.rs
+"#;
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
+        let result = format_review_markdown(&data, true, None);
+        assert!(result.contains("No relevant tests"));
+        assert!(result.contains("Changed files without matching test updates"));
+        assert!(result.contains("<code>src/parser.rs</code><br><code>src/lexer.rs</code>"));
+        // Folded into the relevant_tests row, not rendered twice
+        assert_eq!(result.matches("Changed files without").count(), 1);
+    }
+
+    #[test]
+    fn test_planning_context_row_leads_table() {
+        let yaml_str = r#"
+review:
+  relevant_tests: "Yes"
+  planning_context: "Milestone: v2.0 (due 2026-11-01)\nProject: Roadmap (Priority: <b>P1</b>)"
+"#;
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
+        let result = format_review_markdown(&data, true, None);
+        assert!(result.contains(
+            "<table>\n<tr><td>🗓️&nbsp;<strong>Planning context</strong><br>Milestone: v2.0 (due 2026-11-01)<br>Project: Roadmap (Priority: &lt;b>P1&lt;/b>)</td></tr>"
+        ));
+        assert_eq!(result.matches("Planning context").count(), 1);
+    }
+
+    #[test]
+    fn test_retain_review_sections_whitelist() {
+        let yaml_str = r#"
+review:
+  estimated_effort_to_review_[1-5]: 2
+  relevant_tests: "No"
+  key_issues_to_review:
+    - issue_header: "Possible Bug"
+      issue_content: "Off by one"
+      relevant_file: "src/lib.rs"
+  security_concerns: "No"
+"#;
+        let mut data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
+        retain_review_sections(&mut data, &["issues".into(), "security".into()]);
+        let result = format_review_markdown(&data, true, None);
+
+        assert!(result.contains("Off by one"));
+        assert!(result.contains("No security concerns identified"));
+        assert!(!result.contains("Estimated effort to review"));
+        assert!(!result.contains("No relevant tests"));
+    }
+
+    #[test]
+    fn test_retain_review_sections_empty_keeps_all() {
+        let yaml_str = "review:\n  relevant_tests: \"No\"\n  score: 80\n";
+        let mut data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
+        retain_review_sections(&mut data, &[]);
+        assert_eq!(data["review"].as_mapping().unwrap().len(), 2);
+    }
+
     #[test]
     fn test_todo_sections_no_shows_no_todos() {
         let yaml_str = r#"
@@ -516,6 +933,37 @@ review:
         assert!(!result.contains("todo_sections"));
     }
 
+    #[test]
+    fn test_checklist_table() {
+        let yaml_str = r#"
+review:
+  checklist:
+    - question: Are migrations reversible?
+      answer: "yes"
+      explanation: The down migration drops the new column.
+    - question: Is user-facing text translated?
+      answer: n/a
+      explanation: ""
+    - question: Are feature flags cleaned up?
+      answer: "no"
+      explanation: "`old_flow` is still read in <b>main</b>"
+"#;
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
+        let result = format_review_markdown(&data, true, None);
+        assert!(result.contains("<strong>Review checklist</strong>"));
+        assert!(result.contains(
+            "<tr><td>Are migrations reversible?</td><td>✅ Yes</td><td>The down migration drops the new column.</td></tr>"
+        ));
+        assert!(result.contains("<td>➖ N/A</td>"));
+        assert!(result.contains("<td>❌ No</td>"));
+        assert!(!result.contains("<b>main</b>"));
+
+        let plain = format_review_markdown(&data, false, None);
+        assert!(plain.contains(
+            "| Are migrations reversible? | ✅ Yes | The down migration drops the new column. |"
+        ));
+    }
+
     #[test]
     fn test_key_issues_with_canonical_field_names() {
         let yaml_str = r#"
@@ -530,7 +978,9 @@ review:
         let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
         let result = format_review_markdown(&data, true, None);
 
-        assert!(result.contains("Possible Issue"));
+        assert!(
+            result.contains("<!-- pr-agent:finding 1 -->1.&nbsp;<strong>Possible Issue</strong>")
+        );
         assert!(!result.contains("Possible Bug"));
         assert!(result.contains("Null pointer dereference"));
         assert!(result.contains("src/parser.rs"));
@@ -556,6 +1006,57 @@ review:
         assert!(result.contains("100"));
     }
 
+    #[test]
+    fn test_key_issues_grouped_by_category() {
+        let yaml_str = r#"
+review:
+  key_issues_to_review:
+    - issue_header: "Performance"
+      issue_content: "N+1 queries"
+      relevant_file: "src/db.rs"
+      severity: low
+    - issue_header: "Possible Bug"
+      issue_content: "Off by one"
+      relevant_file: "src/a.rs"
+      severity: medium
+    - issue_header: "Possible Bug"
+      issue_content: "Null deref"
+      relevant_file: "src/b.rs"
+      severity: high
+    - issue_header: "Security Risk"
+      issue_content: "Token logged"
+      relevant_file: "src/auth.rs"
+      severity: high
+    - issue_header: "Naming"
+      issue_content: "Unclear name"
+      relevant_file: "src/c.rs"
+"#;
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml_str).unwrap();
+        let layout = FindingsLayout {
+            collapsible: true,
+            section_order: vec!["security".into(), "possible issue".into()],
+            max_per_section: 1,
+        };
+        let result = format_review_markdown_with_layout(&data, true, None, &layout);
+
+        let pos = |needle: &str| result.find(needle).unwrap_or_else(|| panic!("{needle}"));
+        assert!(pos("<strong>Security</strong> (1)") < pos("<strong>Possible Issue</strong> (2)"));
+        assert!(
+            pos("<strong>Possible Issue</strong> (2)") < pos("<strong>Performance</strong> (1)")
+        );
+        assert!(pos("<strong>Performance</strong> (1)") < pos("<strong>Naming</strong> (1)"));
+        assert_eq!(result.matches("<details>").count(), 4);
+        // Highest severity first, original numbers kept, the rest cut off
+        assert!(result.contains("3.&nbsp;<strong>Possible Issue</strong> <code>high</code>"));
+        assert!(!result.contains("Off by one"));
+        assert!(result.contains("<i>1 more not shown</i>"));
+        assert!(result.contains(&finding_marker(4)));
+
+        let flat = format_review_markdown(&data, true, None);
+        assert!(!flat.contains("<details>"));
+        assert!(flat.contains("Off by one"));
+    }
+
     #[test]
     fn test_is_value_no() {
         assert!(is_value_no("No"));
@@ -567,4 +1068,27 @@ review:
         assert!(!is_value_no("Yes"));
         assert!(!is_value_no("Some value"));
     }
+
+    #[test]
+    fn test_security_severity() {
+        let review = |yaml: &str| serde_yaml_ng::from_str::<serde_yaml_ng::Value>(yaml).unwrap();
+        assert_eq!(
+            security_severity(&review(
+                "security_concerns: 'SQL injection: ...'\nsecurity_severity: High"
+            )),
+            Some("high")
+        );
+        assert_eq!(security_severity(&review("security_concerns: 'XSS'")), None);
+        assert_eq!(
+            security_severity(&review("security_concerns: 'No'\nsecurity_severity: high")),
+            None
+        );
+
+        let data = review(
+            "review:\n  security_concerns: 'SQL injection: ...'\n  security_severity: medium",
+        );
+        let result = format_review_markdown(&data, true, None);
+        assert!(result.contains("<summary>Security concerns (medium severity)</summary>"));
+        assert!(!result.contains("security_severity"));
+    }
 }
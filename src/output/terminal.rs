@@ -0,0 +1,330 @@
+use std::fmt::Write;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use console::style;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::output::improve_formatter::{ParsedSuggestion, importance_label};
+use crate::output::review_formatter::{extract_effort_score, is_value_no, yaml_value_to_string};
+
+/// How CLI results are written to stdout (`--output`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputMode {
+    /// Human-readable text, with progress and colors on a terminal.
+    #[default]
+    Text,
+    /// Parsed results as JSON; no progress UI.
+    Json,
+}
+
+struct TerminalState {
+    mode: OutputMode,
+    /// Progress UI and colors are on (text mode, stdout and stderr are TTYs).
+    rich: bool,
+    progress: MultiProgress,
+}
+
+static STATE: OnceLock<TerminalState> = OnceLock::new();
+
+/// Enable CLI output handling. Without this call (e.g. in the webhook
+/// server) every helper here is a no-op and output stays plain.
+pub fn init(mode: OutputMode) {
+    let rich = mode == OutputMode::Text
+        && std::io::stdout().is_terminal()
+        && std::io::stderr().is_terminal();
+    let progress = MultiProgress::with_draw_target(if rich {
+        ProgressDrawTarget::stderr()
+    } else {
+        ProgressDrawTarget::hidden()
+    });
+    let _ = STATE.set(TerminalState {
+        mode,
+        rich,
+        progress,
+    });
+}
+
+/// Whether the progress UI and colorized summaries are enabled.
+pub fn is_rich() -> bool {
+    STATE.get().is_some_and(|s| s.rich)
+}
+
+/// Whether results should be printed as JSON.
+pub fn is_json() -> bool {
+    STATE.get().is_some_and(|s| s.mode == OutputMode::Json)
+}
+
+/// A spinner or bar on stderr; every method is a no-op when the UI is off.
+///
+/// Cleared from the terminal when dropped without [`Progress::finish`].
+pub struct Progress(Option<ProgressBar>);
+
+impl Progress {
+    pub fn inc(&self, delta: u64) {
+        if let Some(bar) = &self.0 {
+            bar.inc(delta);
+        }
+    }
+
+    /// Replace the bar with a ✔ line showing `message`.
+    pub fn finish(self, message: &str) {
+        if let Some(bar) = &self.0 {
+            bar.set_style(ProgressStyle::with_template("{msg}").expect("valid template"));
+            bar.finish_with_message(format!("{} {message}", style("✔").green()));
+        }
+    }
+
+    /// Seconds since the bar was created (zero when the UI is off).
+    pub fn elapsed_secs(&self) -> f64 {
+        self.0.as_ref().map_or(0.0, |b| b.elapsed().as_secs_f64())
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(bar) = &self.0
+            && !bar.is_finished()
+        {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+fn add_bar(bar: ProgressBar) -> Progress {
+    match STATE.get().filter(|s| s.rich) {
+        Some(state) => Progress(Some(state.progress.add(bar))),
+        None => Progress(None),
+    }
+}
+
+/// Start a spinner with an elapsed-time counter.
+pub fn spinner(message: &str) -> Progress {
+    if !is_rich() {
+        return Progress(None);
+    }
+    let bar = ProgressBar::new_spinner().with_message(message.to_string());
+    bar.set_style(
+        ProgressStyle::with_template("{spinner:.cyan} {msg} {elapsed:.dim}")
+            .expect("valid template"),
+    );
+    bar.enable_steady_tick(Duration::from_millis(100));
+    add_bar(bar)
+}
+
+/// Start an `N/M` bar for `len` steps.
+pub fn bar(len: u64, message: &str) -> Progress {
+    if !is_rich() {
+        return Progress(None);
+    }
+    let bar = ProgressBar::new(len).with_message(message.to_string());
+    bar.set_style(
+        ProgressStyle::with_template("{msg} [{bar:30.cyan/blue}] {pos}/{len} {elapsed:.dim}")
+            .expect("valid template")
+            .progress_chars("=> "),
+    );
+    add_bar(bar)
+}
+
+/// Serialize a parsed result for `--output=json`.
+pub fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|e| format!("{{\"error\": \"{e}\"}}"))
+}
+
+/// Render a review as a colorized terminal summary.
+pub fn render_review_summary(data: &serde_yaml_ng::Value) -> String {
+    let review = data.get("review").unwrap_or(data);
+    let mut out = format!("{}\n\n", style("PR Review").bold().underlined());
+
+    for key in [
+        "estimated_effort_to_review_[1-5]",
+        "estimated_effort_to_review",
+    ] {
+        if let Some(v) = review.get(key) {
+            let effort = extract_effort_score(v);
+            let colored = match effort {
+                1..=2 => style(format!("{effort}/5")).green(),
+                3 => style(format!("{effort}/5")).yellow(),
+                _ => style(format!("{effort}/5")).red(),
+            };
+            let _ = writeln!(out, "{} {colored}", style("Effort to review:").bold());
+            break;
+        }
+    }
+
+    if let Some(v) = review.get("security_concerns") {
+        let text = yaml_value_to_string(v);
+        if is_value_no(&text) {
+            let _ = writeln!(
+                out,
+                "{} {}",
+                style("Security:").bold(),
+                style("no concerns").green()
+            );
+        } else {
+            let _ = writeln!(out, "{} {}", style("Security:").bold(), style(text).red());
+        }
+    }
+
+    if let Some(v) = review.get("relevant_tests") {
+        let text = yaml_value_to_string(v);
+        let tests = if is_value_no(&text) {
+            style("none").yellow()
+        } else {
+            style("included").green()
+        };
+        let _ = writeln!(out, "{} {tests}", style("Tests:").bold());
+    }
+
+    match review
+        .get("key_issues_to_review")
+        .and_then(|v| v.as_sequence())
+    {
+        Some(issues) if !issues.is_empty() => {
+            let _ = writeln!(out, "\n{}", style("Focus areas").bold());
+            for (i, issue) in issues.iter().enumerate() {
+                let field = |names: &[&str]| {
+                    names
+                        .iter()
+                        .find_map(|n| issue.get(*n))
+                        .map(yaml_value_to_string)
+                        .unwrap_or_default()
+                };
+                let header = field(&["issue_header", "header"]);
+                let file = field(&["relevant_file"]);
+                let start = field(&["start_line"]);
+                let end = field(&["end_line"]);
+                let location = match (start.is_empty(), end.is_empty() || end == start) {
+                    (true, _) => file,
+                    (false, true) => format!("{file}:{start}"),
+                    (false, false) => format!("{file}:{start}-{end}"),
+                };
+                let _ = writeln!(
+                    out,
+                    "  {}. {} {}",
+                    i + 1,
+                    style(header).yellow().bold(),
+                    style(location).cyan()
+                );
+                let content = field(&["issue_content", "content"]);
+                if !content.is_empty() {
+                    let _ = writeln!(out, "     {content}");
+                }
+            }
+        }
+        _ => {
+            let _ = writeln!(out, "\n{}", style("No major issues detected").green());
+        }
+    }
+
+    out
+}
+
+/// Render code suggestions as a colorized terminal list.
+pub fn render_suggestions(
+    suggestions: &[ParsedSuggestion],
+    th_high: u32,
+    th_medium: u32,
+) -> String {
+    if suggestions.is_empty() {
+        return "No code suggestions found.\n".to_string();
+    }
+    let mut out = format!("{}\n\n", style("PR Code Suggestions").bold().underlined());
+    for s in suggestions {
+        let importance = importance_label(s.score, th_high, th_medium);
+        let badge = format!("[{importance} {}]", s.score);
+        let badge = if s.score >= th_high {
+            style(badge).red().bold()
+        } else if s.score >= th_medium {
+            style(badge).yellow()
+        } else {
+            style(badge).dim()
+        };
+        let location = if s.relevant_lines_start > 0 {
+            format!(
+                "{}:{}-{}",
+                s.relevant_file, s.relevant_lines_start, s.relevant_lines_end
+            )
+        } else {
+            s.relevant_file.clone()
+        };
+        let summary = if s.one_sentence_summary.is_empty() {
+            &s.suggestion_content
+        } else {
+            &s.one_sentence_summary
+        };
+        let _ = writeln!(
+            out,
+            "{badge} {} {}\n  {summary}",
+            style(&s.label).bold(),
+            style(location).cyan()
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_helpers_are_noops_without_init() {
+        assert!(!is_rich());
+        assert!(!is_json());
+        let progress = spinner("working");
+        progress.inc(1);
+        assert_eq!(progress.elapsed_secs(), 0.0);
+        progress.finish("done");
+    }
+
+    #[test]
+    fn test_render_review_summary() {
+        console::set_colors_enabled(false);
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(
+            r#"
+review:
+  estimated_effort_to_review_[1-5]: 4
+  relevant_tests: "No"
+  security_concerns: "No"
+  key_issues_to_review:
+    - relevant_file: src/lib.rs
+      issue_header: Possible Bug
+      issue_content: Off by one
+      start_line: 10
+      end_line: 12
+"#,
+        )
+        .unwrap();
+        let out = render_review_summary(&data);
+        assert!(out.contains("Effort to review: 4/5"));
+        assert!(out.contains("Security: no concerns"));
+        assert!(out.contains("Tests: none"));
+        assert!(out.contains("  1. Possible Bug src/lib.rs:10-12\n     Off by one"));
+    }
+
+    #[test]
+    fn test_render_suggestions() {
+        console::set_colors_enabled(false);
+        let suggestion = ParsedSuggestion {
+            label: "possible issue".into(),
+            relevant_file: "src/main.rs".into(),
+            relevant_lines_start: 3,
+            relevant_lines_end: 5,
+            existing_code: String::new(),
+            improved_code: String::new(),
+            one_sentence_summary: "Handle the empty case".into(),
+            suggestion_content: String::new(),
+            score: 8,
+            best_practice: None,
+        };
+        let out = render_suggestions(&[suggestion], 9, 7);
+        assert!(
+            out.contains("[Important 8] possible issue src/main.rs:3-5\n  Handle the empty case")
+        );
+        assert_eq!(
+            render_suggestions(&[], 9, 7),
+            "No code suggestions found.\n"
+        );
+    }
+}
@@ -27,14 +27,7 @@ pub fn load_yaml(
     first_key: &str,
     last_key: &str,
 ) -> Option<serde_yaml_ng::Value> {
-    // Strip markdown fences and whitespace — trim once, reuse the slice
-    let trimmed = response_text.trim_matches('\n');
-    let stripped = trimmed
-        .strip_prefix("yaml")
-        .or_else(|| trimmed.strip_prefix("```yaml"))
-        .unwrap_or(trimmed)
-        .trim();
-    let cleaned = stripped.strip_suffix("```").unwrap_or(stripped).trim();
+    let cleaned = strip_yaml_fences(response_text);
 
     // Direct parse attempt — zero allocations on the happy path
     if let Ok(data) = serde_yaml_ng::from_str::<serde_yaml_ng::Value>(cleaned)
@@ -53,6 +46,26 @@ pub fn load_yaml(
     try_fix_yaml(cleaned, &keys, first_key, last_key, response_text)
 }
 
+/// Strip markdown fences and whitespace — trim once, reuse the slice.
+fn strip_yaml_fences(response_text: &str) -> &str {
+    let trimmed = response_text.trim_matches('\n');
+    let stripped = trimmed
+        .strip_prefix("yaml")
+        .or_else(|| trimmed.strip_prefix("```yaml"))
+        .unwrap_or(trimmed)
+        .trim();
+    stripped.strip_suffix("```").unwrap_or(stripped).trim()
+}
+
+/// The parser's complaint about a response, for telling the model what to fix.
+pub fn yaml_parse_error(response_text: &str) -> String {
+    match serde_yaml_ng::from_str::<serde_yaml_ng::Value>(strip_yaml_fences(response_text)) {
+        Ok(data) if data.is_null() => "the response is empty".into(),
+        Ok(_) => "the response is missing the expected keys".into(),
+        Err(e) => e.to_string(),
+    }
+}
+
 /// Convenience wrapper with no extra keys or key boundaries.
 #[allow(dead_code)]
 pub fn load_yaml_simple(response_text: &str) -> Option<serde_yaml_ng::Value> {
@@ -550,6 +563,13 @@ mod tests {
         assert_eq!(data["second_key"].as_str().unwrap(), "world");
     }
 
+    #[test]
+    fn test_yaml_parse_error_reports_location() {
+        let err = yaml_parse_error("```yaml\nreview:\n  key: [unclosed\n```");
+        assert!(err.contains("line"), "error should point at a line: {err}");
+        assert_eq!(yaml_parse_error(""), "the response is empty");
+    }
+
     #[test]
     fn test_load_yaml_empty_returns_none() {
         assert!(load_yaml_simple("").is_none());
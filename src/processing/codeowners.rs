@@ -0,0 +1,208 @@
+//! CODEOWNERS lookup (`config.include_codeowners`).
+//!
+//! The repository's CODEOWNERS file is read from the default branch and each
+//! changed file is mapped to its owners. As on GitHub and GitLab, the last
+//! matching rule wins, and a rule with no owners leaves the file unowned.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::config::types::Settings;
+use crate::git::GitProvider;
+use crate::git::types::FilePatchInfo;
+use crate::processing::filter::glob_matches;
+
+/// Where CODEOWNERS may live, checked in order; the first one found is used.
+const CODEOWNERS_PATHS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// Owners of each changed file, by filename. Unowned files are left out.
+pub type FileOwners = BTreeMap<String, Vec<String>>;
+
+/// Parsed CODEOWNERS rules, in file order.
+#[derive(Debug, Default)]
+pub struct CodeOwners {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    /// Glob matching the path itself.
+    glob: String,
+    /// Whether the pattern only names a directory (trailing `/`).
+    dir_only: bool,
+    owners: Vec<String>,
+}
+
+impl CodeOwners {
+    /// Parse CODEOWNERS content. Comments, blank lines and GitLab
+    /// `[Section]` headers are skipped.
+    pub fn parse(content: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter(|line| !line.starts_with('[') && !line.starts_with("^["))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pattern = parts.next()?;
+                let owners = parts
+                    .take_while(|part| !part.starts_with('#'))
+                    .map(String::from)
+                    .collect();
+                Some(Rule::new(pattern, owners))
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Owners of `path`; empty when no rule matches or the last match has no owners.
+    pub fn owners_of(&self, path: &str) -> &[String] {
+        let path = path.trim_start_matches('/');
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path))
+            .map(|rule| rule.owners.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+impl Rule {
+    /// Turn a gitignore-style pattern into a glob: a leading `/` or an inner
+    /// `/` anchors it to the repository root, otherwise it matches at any depth.
+    fn new(pattern: &str, owners: Vec<String>) -> Self {
+        let dir_only = pattern.ends_with('/');
+        let trimmed = pattern.trim_end_matches('/');
+        let glob = match trimmed.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if trimmed.contains('/') => trimmed.to_string(),
+            None => format!("**/{trimmed}"),
+        };
+        Self {
+            glob,
+            dir_only,
+            owners,
+        }
+    }
+
+    /// A pattern matches the path itself or anything under it.
+    fn matches(&self, path: &str) -> bool {
+        (!self.dir_only && glob_matches(&self.glob, path))
+            || glob_matches(&format!("{}/**", self.glob), path)
+    }
+}
+
+/// Owners of the changed files, or an empty map when `config.include_codeowners`
+/// is off or the repository has no CODEOWNERS file.
+pub async fn changed_file_owners(
+    provider: &dyn GitProvider,
+    files: &[FilePatchInfo],
+    settings: &Settings,
+) -> FileOwners {
+    if !settings.config.include_codeowners {
+        return FileOwners::new();
+    }
+    let Some(content) = fetch_codeowners(provider).await else {
+        tracing::debug!("no CODEOWNERS file found");
+        return FileOwners::new();
+    };
+    let codeowners = CodeOwners::parse(&content);
+    files
+        .iter()
+        .filter_map(|f| {
+            let owners = codeowners.owners_of(&f.filename);
+            (!owners.is_empty()).then(|| (f.filename.clone(), owners.to_vec()))
+        })
+        .collect()
+}
+
+async fn fetch_codeowners(provider: &dyn GitProvider) -> Option<String> {
+    for path in CODEOWNERS_PATHS {
+        match provider.get_repo_file(path).await {
+            Ok(Some(content)) => return Some(content),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(path, error = %e, "failed to fetch CODEOWNERS"),
+        }
+    }
+    None
+}
+
+/// Render file owners for the prompt, one `- path: @owner …` line per file.
+pub fn format_file_owners(owners: &FileOwners) -> String {
+    let mut out = String::new();
+    for (filename, file_owners) in owners {
+        let _ = writeln!(out, "- {filename}: {}", file_owners.join(" "));
+    }
+    out
+}
+
+/// Every owner across the changed files, deduplicated in first-seen order.
+pub fn all_owners(owners: &FileOwners) -> Vec<&str> {
+    let mut all: Vec<&str> = Vec::new();
+    for owner in owners.values().flatten() {
+        if !all.contains(&owner.as_str()) {
+            all.push(owner);
+        }
+    }
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+# Default owners
+*       @org/core
+
+[Docs]
+*.md    @org/docs   # inline comment
+/src/api/   @org/api @alice
+tests/  @org/qa
+/vendor/
+";
+
+    #[test]
+    fn test_last_matching_rule_wins() {
+        let owners = CodeOwners::parse(SAMPLE);
+        assert_eq!(owners.owners_of("README.md"), ["@org/docs"]);
+        assert_eq!(
+            owners.owners_of("src/api/handler.rs"),
+            ["@org/api", "@alice"]
+        );
+        assert_eq!(owners.owners_of("src/main.rs"), ["@org/core"]);
+    }
+
+    #[test]
+    fn test_anchored_and_directory_patterns() {
+        let owners = CodeOwners::parse(SAMPLE);
+        // `/src/api/` is anchored to the root.
+        assert_eq!(owners.owners_of("lib/src/api/x.rs"), ["@org/core"]);
+        // `tests/` has no inner slash, so it matches at any depth.
+        assert_eq!(owners.owners_of("tests/unit/a.rs"), ["@org/qa"]);
+        assert_eq!(owners.owners_of("crates/x/tests/a.rs"), ["@org/qa"]);
+    }
+
+    #[test]
+    fn test_rule_without_owners_unowns() {
+        let owners = CodeOwners::parse(SAMPLE);
+        assert!(owners.owners_of("vendor/lib.js").is_empty());
+    }
+
+    #[test]
+    fn test_format_and_dedupe() {
+        let mut map = FileOwners::new();
+        map.insert("a.rs".into(), vec!["@org/core".into(), "@alice".into()]);
+        map.insert("b.rs".into(), vec!["@alice".into()]);
+        assert_eq!(
+            format_file_owners(&map),
+            "- a.rs: @org/core @alice\n- b.rs: @alice\n"
+        );
+        assert_eq!(all_owners(&map), ["@org/core", "@alice"]);
+    }
+}
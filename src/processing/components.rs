@@ -0,0 +1,347 @@
+use crate::git::types::{EditType, FilePatchInfo};
+use crate::processing::diff::HunkHeader;
+
+/// Words that may precede a definition keyword (`pub async fn`, `export default class`).
+const MODIFIERS: &[&str] = &[
+    "pub",
+    "pub(crate)",
+    "pub(super)",
+    "async",
+    "const",
+    "unsafe",
+    "extern",
+    "export",
+    "default",
+    "static",
+    "public",
+    "private",
+    "protected",
+    "internal",
+    "abstract",
+    "final",
+    "sealed",
+    "open",
+    "override",
+    "data",
+];
+
+/// Definition keywords and the component kind they introduce.
+const DEFINITION_KEYWORDS: &[(&str, &str)] = &[
+    ("fn", "function"),
+    ("def", "function"),
+    ("func", "function"),
+    ("function", "function"),
+    ("fun", "function"),
+    ("class", "class"),
+    ("struct", "struct"),
+    ("enum", "enum"),
+    ("trait", "trait"),
+    ("interface", "interface"),
+    ("impl", "impl"),
+    ("module", "module"),
+    ("object", "object"),
+];
+
+/// How a component changed in the PR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentChange {
+    Added,
+    Modified,
+    Removed,
+}
+
+impl ComponentChange {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ComponentChange::Added => "added",
+            ComponentChange::Modified => "modified",
+            ComponentChange::Removed => "removed",
+        }
+    }
+}
+
+/// A function, class or similar definition touched by the diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedComponent {
+    /// File the component lives in.
+    pub file: String,
+    /// Component name (`parse_config`, `Settings`).
+    pub name: String,
+    /// Kind of definition (`function`, `class`, `struct`, ...).
+    pub kind: &'static str,
+    pub change: ComponentChange,
+    /// Added lines attributed to the component.
+    pub added_lines: u32,
+    /// Removed lines attributed to the component.
+    pub removed_lines: u32,
+}
+
+/// Per-component bookkeeping while walking a patch.
+struct Tracked {
+    component: ChangedComponent,
+    def_added: bool,
+    def_removed: bool,
+}
+
+/// List the components changed in each file, in diff order.
+///
+/// Works on the patch alone: every changed line is attributed to the nearest
+/// preceding definition in its hunk, or to the definition named in the hunk
+/// header. A definition whose own line is only added (or only removed) counts
+/// as added (or removed). Deleted files and changes outside any definition
+/// are skipped.
+pub fn extract_changed_components(files: &[FilePatchInfo]) -> Vec<ChangedComponent> {
+    files
+        .iter()
+        .filter(|f| f.edit_type != EditType::Deleted)
+        .flat_map(|f| components_in_patch(&f.filename, &f.patch))
+        .collect()
+}
+
+fn components_in_patch(filename: &str, patch: &str) -> Vec<ChangedComponent> {
+    let mut tracked: Vec<Tracked> = Vec::new();
+    let mut current: Option<usize> = None;
+
+    for line in patch.lines() {
+        if let Some(header) = HunkHeader::parse(line) {
+            current = parse_definition(&header.section_header)
+                .map(|(kind, name)| track(&mut tracked, filename, kind, name));
+            continue;
+        }
+        if line.starts_with("+++") || line.starts_with("---") {
+            continue;
+        }
+        let (marker, content) = match line.chars().next() {
+            Some(c @ ('+' | '-' | ' ')) => (c, &line[1..]),
+            _ => continue,
+        };
+
+        if let Some((kind, name)) = parse_definition(content) {
+            let idx = track(&mut tracked, filename, kind, name);
+            match marker {
+                '+' => tracked[idx].def_added = true,
+                '-' => tracked[idx].def_removed = true,
+                _ => {}
+            }
+            current = Some(idx);
+        }
+
+        if let Some(idx) = current {
+            match marker {
+                '+' => tracked[idx].component.added_lines += 1,
+                '-' => tracked[idx].component.removed_lines += 1,
+                _ => {}
+            }
+        }
+    }
+
+    tracked
+        .into_iter()
+        .filter(|t| t.component.added_lines + t.component.removed_lines > 0)
+        .map(|t| {
+            let mut component = t.component;
+            component.change = match (t.def_added, t.def_removed) {
+                (true, false) => ComponentChange::Added,
+                (false, true) => ComponentChange::Removed,
+                _ => ComponentChange::Modified,
+            };
+            component
+        })
+        .collect()
+}
+
+/// Index of the tracked component `(kind, name)`, inserting it if new.
+fn track(tracked: &mut Vec<Tracked>, filename: &str, kind: &'static str, name: String) -> usize {
+    if let Some(idx) = tracked
+        .iter()
+        .position(|t| t.component.kind == kind && t.component.name == name)
+    {
+        return idx;
+    }
+    tracked.push(Tracked {
+        component: ChangedComponent {
+            file: filename.to_string(),
+            name,
+            kind,
+            change: ComponentChange::Modified,
+            added_lines: 0,
+            removed_lines: 0,
+        },
+        def_added: false,
+        def_removed: false,
+    });
+    tracked.len() - 1
+}
+
+/// Parse a source line as a definition, returning its kind and name.
+fn parse_definition(line: &str) -> Option<(&'static str, String)> {
+    let mut words = line.split_whitespace().peekable();
+    while words.next_if(|w| MODIFIERS.contains(w)).is_some() {
+        // `extern "C" fn`
+        words.next_if(|w| w.starts_with('"'));
+    }
+    let token = words.next()?;
+    // `impl<T>` keeps its generics in the same token.
+    let (keyword, generics) = token.split_at(token.find('<').unwrap_or(token.len()));
+    let kind = DEFINITION_KEYWORDS
+        .iter()
+        .find(|(k, _)| *k == keyword)
+        .map(|(_, kind)| *kind)?;
+
+    let rest = std::iter::once(generics)
+        .chain(words)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let rest = skip_generics(rest.trim_start());
+    let target = match kind {
+        // `impl Display for Config` names the type, not the trait.
+        "impl" => rest.split_once(" for ").map_or(rest, |(_, ty)| ty),
+        // Go methods: `func (s *Server) Start(`
+        "function" if rest.starts_with('(') => rest.split_once(')')?.1,
+        _ => rest,
+    };
+    let name: String = target
+        .trim_start()
+        .trim_start_matches('*')
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+        .collect();
+    let starts_ok = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$');
+    starts_ok.then_some((kind, name))
+}
+
+/// Strip a leading `<...>` generic parameter list.
+fn skip_generics(s: &str) -> &str {
+    if !s.starts_with('<') {
+        return s;
+    }
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &s[i + 1..];
+                }
+            }
+            _ => {}
+        }
+    }
+    ""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, patch: &str) -> FilePatchInfo {
+        let mut f = FilePatchInfo::new(String::new(), String::new(), patch.into(), name.into());
+        f.edit_type = EditType::Modified;
+        f
+    }
+
+    #[test]
+    fn test_parse_definition() {
+        let parse = parse_definition;
+        assert_eq!(
+            parse("pub async fn fetch(url: &str) {"),
+            Some(("function", "fetch".into()))
+        );
+        assert_eq!(
+            parse("pub(crate) struct Config<T> {"),
+            Some(("struct", "Config".into()))
+        );
+        assert_eq!(
+            parse("impl<T: Clone> Display for Wrapper<T> {"),
+            Some(("impl", "Wrapper".into()))
+        );
+        assert_eq!(
+            parse("    def __init__(self):"),
+            Some(("function", "__init__".into()))
+        );
+        assert_eq!(
+            parse("class Parser(Base):"),
+            Some(("class", "Parser".into()))
+        );
+        assert_eq!(
+            parse("export default function render() {"),
+            Some(("function", "render".into()))
+        );
+        assert_eq!(
+            parse("func (s *Server) Start() error {"),
+            Some(("function", "Start".into()))
+        );
+        assert_eq!(
+            parse("extern \"C\" fn callback() {"),
+            Some(("function", "callback".into()))
+        );
+        assert_eq!(
+            parse("impl<T: Clone> Wrapper<T> {"),
+            Some(("impl", "Wrapper".into()))
+        );
+        assert_eq!(parse("let x = fn_call();"), None);
+        assert_eq!(parse("const MAX: u32 = 5;"), None);
+        assert_eq!(parse("fn (broken"), None);
+    }
+
+    #[test]
+    fn test_extract_changed_components() {
+        let patch = "\
+@@ -10,8 +10,14 @@ impl Parser {
+     fn parse(&self) {
+-        old();
++        new();
+     }
++
++    fn validate(&self) -> bool {
++        true
++    }
+@@ -40,4 +46,2 @@ fn helper() {
+-fn unused() {
+-}
+     let a = 1;";
+        let components = extract_changed_components(&[file("src/parser.rs", patch)]);
+        let summary: Vec<(&str, &str, &str, u32, u32)> = components
+            .iter()
+            .map(|c| {
+                (
+                    c.name.as_str(),
+                    c.kind,
+                    c.change.as_str(),
+                    c.added_lines,
+                    c.removed_lines,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("parse", "function", "modified", 2, 1),
+                ("validate", "function", "added", 3, 0),
+                ("unused", "function", "removed", 0, 2),
+            ]
+        );
+        assert!(components.iter().all(|c| c.file == "src/parser.rs"));
+    }
+
+    #[test]
+    fn test_extract_changed_components_uses_hunk_header() {
+        let patch = "@@ -1,5 +1,6 @@ fn main() {\n     let a = 1;\n+    let b = 2;\n }";
+        let components = extract_changed_components(&[file("src/main.rs", patch)]);
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].name, "main");
+        assert_eq!(components[0].change, ComponentChange::Modified);
+    }
+
+    #[test]
+    fn test_extract_changed_components_skips_deleted_and_top_level() {
+        let mut deleted = file("src/old.rs", "@@ -1,2 +0,0 @@\n-fn gone() {\n-}");
+        deleted.edit_type = EditType::Deleted;
+        let top_level = file("config.py", "@@ -1 +1 @@\n-DEBUG = True\n+DEBUG = False");
+        assert!(extract_changed_components(&[deleted, top_level]).is_empty());
+    }
+}
@@ -1,11 +1,17 @@
 use crate::ai::token::{
-    OUTPUT_BUFFER_TOKENS_HARD_THRESHOLD, OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD, clip_tokens,
-    count_tokens, get_max_tokens_with_fallback,
+    OUTPUT_BUFFER_TOKENS_HARD_THRESHOLD, OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD, Tokenizer,
+    get_max_tokens_with_fallback, tokenizer_for,
 };
+use std::collections::HashMap;
+
 use crate::config::loader::get_settings;
+use crate::config::types::CompressionConfig;
 use crate::git::types::{EditType, FilePatchInfo};
-use crate::processing::diff::{convert_to_hunks_with_line_numbers, format_patch_simple};
-use crate::processing::filter::filter_files;
+use crate::processing::diff::{
+    convert_to_hunks_with_line_numbers, format_patch_simple, format_patch_stats, insert_ai_summary,
+    insert_language,
+};
+use crate::processing::filter::{filter_files, is_patch_skipped};
 use crate::processing::patch::extend_patch;
 
 /// Processed file entry for compression.
@@ -27,6 +33,8 @@ pub struct CompressedDiffResult {
     pub remaining_files: Vec<String>,
     /// Filenames included in this batch.
     pub files_in_patch: Vec<String>,
+    /// Files too large for any batch, left out by `large_patch_policy = "skip"`.
+    pub skipped_files: Vec<String>,
 }
 
 /// Result from `get_pr_diff`.
@@ -39,21 +47,34 @@ pub struct PrDiffResult {
     pub files_in_diff: Vec<String>,
     /// Files that were skipped due to budget.
     pub remaining_files: Vec<String>,
+    /// Files too large for the prompt, left out by `large_patch_policy = "skip"`.
+    pub skipped_files: Vec<String>,
 }
 
 /// Main entry: generate the PR diff with optional compression.
 ///
 /// Algorithm:
 /// 1. Filter files (binary, ignore patterns)
-/// 2. Sort by language
-/// 3. Generate extended diff with extra context lines
-/// 4. If under token budget, return full diff
-/// 5. If over budget, compress: sort by tokens, pack greedily
-/// 6. Append unprocessed file lists if space remains
+/// 2. Generate extended diff with extra context lines
+/// 3. If under token budget, return full diff
+/// 4. If over budget, compress: rank files (main language, file kind, change
+///    density; largest first on ties), pack greedily
+/// 5. Append unprocessed file lists if space remains
 pub fn get_pr_diff(
     files: &mut Vec<FilePatchInfo>,
     model: &str,
     add_line_numbers: bool,
+) -> PrDiffResult {
+    get_pr_diff_with_reserve(files, model, add_line_numbers, 0)
+}
+
+/// Like [`get_pr_diff`], but holds `reserved_tokens` of the model's context
+/// window back for the rest of the prompt (instructions, PR metadata).
+pub fn get_pr_diff_with_reserve(
+    files: &mut Vec<FilePatchInfo>,
+    model: &str,
+    add_line_numbers: bool,
+    reserved_tokens: u32,
 ) -> PrDiffResult {
     let settings = get_settings();
     let extra_before = settings.config.patch_extra_lines_before;
@@ -68,11 +89,20 @@ pub fn get_pr_diff(
             token_count: 0,
             files_in_diff: Vec::new(),
             remaining_files: Vec::new(),
+            skipped_files: Vec::new(),
         };
     }
 
     // 2. Build file dictionary (extends patches with context + counts tokens)
-    let file_dict = build_file_dict(files, add_line_numbers, extra_before, extra_after);
+    let tokenizer = tokenizer_for(model);
+    let mut file_dict = build_file_dict(
+        files,
+        add_line_numbers,
+        extra_before,
+        extra_after,
+        &settings.config.patch_extension_skip_types,
+        tokenizer,
+    );
 
     // Release large file contents — only needed during extend_patch above.
     // Filenames and edit_type are still available for append_remaining_file_lists.
@@ -81,7 +111,8 @@ pub fn get_pr_diff(
         drop(std::mem::take(&mut file.head_file));
     }
 
-    let max_tokens = get_max_tokens_with_fallback(model, settings.config.max_model_tokens);
+    let max_tokens = get_max_tokens_with_fallback(model, settings.config.max_model_tokens)
+        .saturating_sub(reserved_tokens);
 
     // 3. Check total tokens against budget
     let total_tokens: u32 = file_dict.iter().map(|(_, e)| e.tokens).sum();
@@ -99,6 +130,7 @@ pub fn get_pr_diff(
             token_count: total_tokens,
             files_in_diff: filenames,
             remaining_files: Vec::new(),
+            skipped_files: Vec::new(),
         };
     }
 
@@ -109,8 +141,15 @@ pub fn get_pr_diff(
         "diff exceeds token budget, compressing"
     );
 
+    rank_files(&mut file_dict, files, &settings.config.compression);
     let all_filenames: Vec<String> = file_dict.iter().map(|(f, _)| f.clone()).collect();
-    let result = generate_full_patch(&file_dict, max_tokens, &all_filenames);
+    let result = generate_full_patch(
+        &file_dict,
+        max_tokens,
+        &all_filenames,
+        &settings.config.large_patch_policy,
+        tokenizer,
+    );
 
     // 5. Append unprocessed file lists if space remains
     let final_diff = append_remaining_file_lists(
@@ -119,41 +158,74 @@ pub fn get_pr_diff(
         max_tokens,
         files,
         &result.files_in_patch,
+        &result.skipped_files,
+        tokenizer,
     );
 
-    let final_tokens = count_tokens(&final_diff);
+    let final_tokens = tokenizer.count(&final_diff);
 
     PrDiffResult {
         diff: final_diff,
         token_count: final_tokens,
         files_in_diff: result.files_in_patch,
         remaining_files: result.remaining_files,
+        skipped_files: result.skipped_files,
     }
 }
 
 /// Build a dictionary of filename → FileEntry with token counts.
 ///
-/// Files are sorted by token count descending (largest first).
+/// Files matching `skip_types` (`config.patch_extension_skip_types`) get
+/// their header and line counts only. Files are sorted by token count
+/// descending (largest first).
 fn build_file_dict(
     files: &[FilePatchInfo],
     add_line_numbers: bool,
     extra_before: usize,
     extra_after: usize,
+    skip_types: &[String],
+    tokenizer: Tokenizer,
 ) -> Vec<(String, FileEntry)> {
     let mut entries: Vec<(String, FileEntry)> = Vec::with_capacity(files.len());
 
     for file in files {
+        if is_patch_skipped(&file.filename, skip_types) {
+            let patch = format_patch_stats(&file.filename, &file.patch, file.edit_type);
+            entries.push((
+                file.filename.clone(),
+                FileEntry {
+                    tokens: tokenizer.count(&patch),
+                    patch,
+                    edit_type: file.edit_type,
+                },
+            ));
+            continue;
+        }
         let extended = extend_patch(&file.base_file, &file.patch, extra_before, extra_after);
 
         // Pass raw parts directly — avoids constructing a temporary FilePatchInfo
         // and eliminates one filename clone per file.
-        let patch_text = if add_line_numbers {
+        let patch_text = if let Some(summary) = &file.patch_summary {
+            format!(
+                "\n\n## File: '{}'\n\n(The patch is too large to show; summary of the changes:)\n{}\n",
+                file.filename.trim(),
+                summary.trim()
+            )
+        } else if add_line_numbers {
             convert_to_hunks_with_line_numbers(&file.filename, &extended, file.edit_type)
         } else {
             format_patch_simple(&file.filename, &extended, file.edit_type)
         };
+        let patch_text = match &file.ai_file_summary {
+            Some(summary) => insert_ai_summary(&patch_text, summary),
+            None => patch_text,
+        };
+        let patch_text = match &file.language {
+            Some(language) => insert_language(&patch_text, language),
+            None => patch_text,
+        };
 
-        let tokens = count_tokens(&patch_text);
+        let tokens = tokenizer.count(&patch_text);
 
         entries.push((
             file.filename.clone(),
@@ -170,15 +242,166 @@ fn build_file_dict(
     entries
 }
 
+/// Kind of file, for the `[config.compression]` kind weights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FileKind {
+    Source,
+    Test,
+    Config,
+    Docs,
+}
+
+/// Classify a path as source, test, config/build or documentation.
+pub(crate) fn file_kind(path: &str) -> FileKind {
+    let lower = path.to_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or(&lower);
+    let ext = name.rsplit_once('.').map_or("", |(_, e)| e);
+    let dirs: Vec<&str> = lower.split('/').collect();
+    let dirs = &dirs[..dirs.len() - 1];
+
+    if dirs
+        .iter()
+        .any(|d| matches!(*d, "test" | "tests" | "spec" | "specs" | "__tests__"))
+        || name.starts_with("test_")
+        || name.contains("_test.")
+        || name.contains(".test.")
+        || name.contains("_spec.")
+        || name.contains(".spec.")
+    {
+        return FileKind::Test;
+    }
+    if matches!(ext, "md" | "rst" | "adoc" | "txt")
+        || dirs.iter().any(|d| matches!(*d, "doc" | "docs"))
+    {
+        return FileKind::Docs;
+    }
+    if matches!(
+        ext,
+        "toml" | "yaml" | "yml" | "json" | "ini" | "cfg" | "conf" | "lock" | "xml" | "properties"
+    ) || matches!(
+        name,
+        "dockerfile" | "makefile" | ".gitignore" | ".dockerignore"
+    ) {
+        return FileKind::Config;
+    }
+    FileKind::Source
+}
+
+/// Language key of a file: its detected language, else its extension.
+fn file_language(file: &FilePatchInfo) -> String {
+    if let Some(language) = file.language.as_deref().filter(|l| !l.is_empty()) {
+        return language.to_lowercase();
+    }
+    let name = file.filename.rsplit('/').next().unwrap_or(&file.filename);
+    name.rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Changed lines per patch line (0.0–1.0); dense patches carry less context.
+fn change_density(patch: &str) -> f64 {
+    let mut total = 0u32;
+    let mut changed = 0u32;
+    for line in patch.lines() {
+        if line.starts_with("@@") || line.starts_with("+++") || line.starts_with("---") {
+            continue;
+        }
+        total += 1;
+        if line.starts_with('+') || line.starts_with('-') {
+            changed += 1;
+        }
+    }
+    if total == 0 {
+        0.0
+    } else {
+        f64::from(changed) / f64::from(total)
+    }
+}
+
+/// Main language of the PR: the source-file language with the most changed lines.
+fn primary_language(files: &[FilePatchInfo]) -> Option<String> {
+    let mut changed: HashMap<String, usize> = HashMap::new();
+    for file in files {
+        if file_kind(&file.filename) != FileKind::Source {
+            continue;
+        }
+        let language = file_language(file);
+        if language.is_empty() {
+            continue;
+        }
+        let lines = file
+            .patch
+            .lines()
+            .filter(|l| {
+                (l.starts_with('+') || l.starts_with('-'))
+                    && !l.starts_with("+++")
+                    && !l.starts_with("---")
+            })
+            .count();
+        *changed.entry(language).or_default() += lines;
+    }
+    changed
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map(|(language, _)| language)
+}
+
+/// Ranking score of a file; higher is packed first.
+fn file_score(file: &FilePatchInfo, primary: Option<&str>, weights: &CompressionConfig) -> f64 {
+    let kind = match file_kind(&file.filename) {
+        FileKind::Source => weights.source_weight,
+        FileKind::Test => weights.test_weight,
+        FileKind::Config => weights.config_weight,
+        FileKind::Docs => weights.docs_weight,
+    };
+    let language = if primary.is_some_and(|p| p == file_language(file)) {
+        weights.language_weight
+    } else {
+        0.0
+    };
+    kind + language + weights.density_weight * change_density(&file.patch)
+}
+
+/// Reorder `file_dict` by [`file_score`] so the most relevant files survive
+/// clipping. The sort is stable, so equal scores stay largest first.
+fn rank_files(
+    file_dict: &mut [(String, FileEntry)],
+    files: &[FilePatchInfo],
+    weights: &CompressionConfig,
+) {
+    if !weights.rank_files {
+        return;
+    }
+    let primary = primary_language(files);
+    let scores: HashMap<&str, f64> = files
+        .iter()
+        .map(|f| {
+            let score = file_score(f, primary.as_deref(), weights);
+            (f.filename.as_str(), score)
+        })
+        .collect();
+    let score = |name: &str| scores.get(name).copied().unwrap_or(0.0);
+    file_dict.sort_by(|a, b| score(&b.0).total_cmp(&score(&a.0)));
+}
+
+/// Smallest clipped patch worth including; less budget defers the file.
+const MIN_CLIPPED_PATCH_TOKENS: u32 = 200;
+
 /// Pack files into a single patch batch, respecting token budget.
 ///
 /// Uses two thresholds:
 /// - **Soft**: skip file but keep in remaining (can go in next batch)
 /// - **Hard**: skip file entirely (no more tokens available at all)
+///
+/// A file larger than the whole soft budget can't fit any batch, so
+/// `large_patch_policy` decides: "skip" leaves it out, anything else clips it
+/// to the budget left in this batch.
 fn generate_full_patch(
     file_dict: &[(String, FileEntry)],
     max_tokens: u32,
     remaining_files_prev: &[String],
+    large_patch_policy: &str,
+    tokenizer: Tokenizer,
 ) -> CompressedDiffResult {
     let remaining_set: std::collections::HashSet<&str> =
         remaining_files_prev.iter().map(|s| s.as_str()).collect();
@@ -187,6 +410,8 @@ fn generate_full_patch(
     let mut total_tokens: u32 = 0;
     let mut remaining_files: Vec<String> = Vec::new();
     let mut files_in_patch: Vec<String> = Vec::new();
+    let mut skipped_files: Vec<String> = Vec::new();
+    let soft_budget = max_tokens.saturating_sub(OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD);
 
     for (filename, entry) in file_dict {
         if !remaining_set.contains(filename.as_str()) {
@@ -199,10 +424,29 @@ fn generate_full_patch(
             continue;
         }
 
+        // Oversized: the file alone exceeds the soft budget
+        if entry.tokens > soft_budget {
+            if large_patch_policy == "skip" {
+                tracing::info!(file = %filename, file_tokens = entry.tokens, "skipped: patch too large");
+                skipped_files.push(filename.clone());
+                continue;
+            }
+            let room = soft_budget.saturating_sub(total_tokens);
+            if room >= MIN_CLIPPED_PATCH_TOKENS {
+                tracing::info!(file = %filename, file_tokens = entry.tokens, room, "clipped: patch too large");
+                let clipped = tokenizer.clip(&entry.patch, room, true);
+                total_tokens += tokenizer.count(&clipped);
+                patches.push_str(&clipped);
+                patches.push('\n');
+                files_in_patch.push(filename.clone());
+            } else {
+                remaining_files.push(filename.clone());
+            }
+            continue;
+        }
+
         // Soft threshold: file would push us over the preferred buffer
-        if total_tokens + entry.tokens
-            > max_tokens.saturating_sub(OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD)
-        {
+        if total_tokens + entry.tokens > soft_budget {
             tracing::debug!(
                 file = %filename,
                 file_tokens = entry.tokens,
@@ -226,17 +470,21 @@ fn generate_full_patch(
         total_tokens,
         remaining_files,
         files_in_patch,
+        skipped_files,
     }
 }
 
 /// If there is remaining token budget after compression, append lists of
-/// unprocessed files grouped by edit type (added, modified, deleted).
+/// unprocessed files: oversized files left out by `large_patch_policy`, then
+/// the rest grouped by edit type (added, modified, deleted).
 fn append_remaining_file_lists(
     patches: String,
     current_tokens: u32,
     max_tokens: u32,
     all_files: &[FilePatchInfo],
     files_in_patch: &[String],
+    skipped_files: &[String],
+    tokenizer: Tokenizer,
 ) -> String {
     let budget = max_tokens.saturating_sub(OUTPUT_BUFFER_TOKENS_HARD_THRESHOLD);
     let delta_tokens: u32 = 10;
@@ -246,8 +494,11 @@ fn append_remaining_file_lists(
     }
 
     let mut remaining_budget = budget - current_tokens;
-    let files_set: std::collections::HashSet<&str> =
-        files_in_patch.iter().map(|s| s.as_str()).collect();
+    let files_set: std::collections::HashSet<&str> = files_in_patch
+        .iter()
+        .chain(skipped_files)
+        .map(|s| s.as_str())
+        .collect();
 
     // Collect unprocessed files by edit type
     let mut added = Vec::new();
@@ -269,30 +520,47 @@ fn append_remaining_file_lists(
     let mut result = patches;
 
     // Helper closure: format and append a file list
-    let mut append_list = |label: &str, files: &[&str], budget: &mut u32| {
+    let mut append_list = |heading: &str, files: &[&str], budget: &mut u32| {
         if files.is_empty() || *budget < delta_tokens {
             return;
         }
         let list_str = format!(
-            "\n\n### Additional {} files (not included in diff):\n{}",
-            label,
+            "\n\n### {heading}:\n{}",
             files
                 .iter()
                 .map(|f| format!("- {f}"))
                 .collect::<Vec<_>>()
                 .join("\n")
         );
-        let clipped = clip_tokens(&list_str, *budget, true);
+        let clipped = tokenizer.clip(&list_str, *budget, true);
         if !clipped.is_empty() {
-            let tokens = count_tokens(&clipped);
+            let tokens = tokenizer.count(&clipped);
             result.push_str(&clipped);
             *budget = budget.saturating_sub(tokens + 2);
         }
     };
 
-    append_list("added", &added, &mut remaining_budget);
-    append_list("modified", &modified, &mut remaining_budget);
-    append_list("deleted", &deleted, &mut remaining_budget);
+    let skipped: Vec<&str> = skipped_files.iter().map(String::as_str).collect();
+    append_list(
+        "Files too large to include (changes not shown)",
+        &skipped,
+        &mut remaining_budget,
+    );
+    append_list(
+        "Additional added files (not included in diff)",
+        &added,
+        &mut remaining_budget,
+    );
+    append_list(
+        "Additional modified files (not included in diff)",
+        &modified,
+        &mut remaining_budget,
+    );
+    append_list(
+        "Additional deleted files (not included in diff)",
+        &deleted,
+        &mut remaining_budget,
+    );
 
     result
 }
@@ -318,7 +586,16 @@ pub fn get_pr_diff_multiple_patches(
     }
 
     let max_tokens = get_max_tokens_with_fallback(model, settings.config.max_model_tokens);
-    let file_dict = build_file_dict(files, add_line_numbers, extra_before, extra_after);
+    let tokenizer = tokenizer_for(model);
+    let mut file_dict = build_file_dict(
+        files,
+        add_line_numbers,
+        extra_before,
+        extra_after,
+        &settings.config.patch_extension_skip_types,
+        tokenizer,
+    );
+    rank_files(&mut file_dict, files, &settings.config.compression);
     let mut remaining: Vec<String> = file_dict.iter().map(|(f, _)| f.clone()).collect();
     let mut batches = Vec::new();
 
@@ -326,7 +603,13 @@ pub fn get_pr_diff_multiple_patches(
         if remaining.is_empty() {
             break;
         }
-        let result = generate_full_patch(&file_dict, max_tokens, &remaining);
+        let result = generate_full_patch(
+            &file_dict,
+            max_tokens,
+            &remaining,
+            &settings.config.large_patch_policy,
+            tokenizer,
+        );
         remaining.clone_from(&result.remaining_files);
         batches.push(result);
     }
@@ -356,12 +639,71 @@ mod tests {
             ),
         ];
 
-        let dict = build_file_dict(&files, true, 0, 0);
+        let dict = build_file_dict(&files, true, 0, 0, &[], Tokenizer::O200k);
         // First entry should be the larger file
         assert_eq!(dict[0].0, "large.rs");
         assert!(dict[0].1.tokens > dict[1].1.tokens);
     }
 
+    #[test]
+    fn test_file_kind() {
+        assert_eq!(file_kind("src/lib.rs"), FileKind::Source);
+        assert_eq!(file_kind("tests/api.rs"), FileKind::Test);
+        assert_eq!(file_kind("src/app.test.ts"), FileKind::Test);
+        assert_eq!(file_kind("pkg/handler_test.go"), FileKind::Test);
+        assert_eq!(file_kind("README.md"), FileKind::Docs);
+        assert_eq!(file_kind("docs/guide/setup.rs"), FileKind::Docs);
+        assert_eq!(file_kind("Cargo.lock"), FileKind::Config);
+        assert_eq!(file_kind("deploy/Dockerfile"), FileKind::Config);
+        // Only directory names mark tests, not the file name itself
+        assert_eq!(file_kind("src/tests.rs"), FileKind::Source);
+    }
+
+    #[test]
+    fn test_rank_files_prefers_main_language_source() {
+        let files = vec![
+            make_file("README.md", "@@ -1 +1 @@\n-a\n+b", EditType::Modified),
+            make_file(
+                "scripts/gen.py",
+                "@@ -1,3 +1,3 @@\n ctx\n-a\n+b\n ctx",
+                EditType::Modified,
+            ),
+            make_file("tests/api.rs", "@@ -1 +1 @@\n-a\n+b", EditType::Modified),
+            make_file(
+                "src/api.rs",
+                "@@ -1,3 +1,4 @@\n ctx\n-a\n+b\n+c\n ctx",
+                EditType::Modified,
+            ),
+            make_file("src/lib.rs", "@@ -1 +1 @@\n-a\n+b", EditType::Modified),
+        ];
+        assert_eq!(primary_language(&files).as_deref(), Some("rs"));
+
+        let mut dict = build_file_dict(&files, false, 0, 0, &[], Tokenizer::O200k);
+        rank_files(&mut dict, &files, &CompressionConfig::default());
+        let order: Vec<&str> = dict.iter().map(|(f, _)| f.as_str()).collect();
+        // rs source (3.0 + density) > rs test (2.0 + 1.0) > py source (2.0 + 0.5) > docs
+        assert_eq!(
+            order,
+            vec![
+                "src/lib.rs",
+                "src/api.rs",
+                "tests/api.rs",
+                "scripts/gen.py",
+                "README.md"
+            ]
+        );
+
+        let mut dict = build_file_dict(&files, false, 0, 0, &[], Tokenizer::O200k);
+        let before: Vec<String> = dict.iter().map(|(f, _)| f.clone()).collect();
+        let off = CompressionConfig {
+            rank_files: false,
+            ..CompressionConfig::default()
+        };
+        rank_files(&mut dict, &files, &off);
+        let after: Vec<String> = dict.iter().map(|(f, _)| f.clone()).collect();
+        assert_eq!(before, after);
+    }
+
     #[test]
     fn test_generate_full_patch_respects_thresholds() {
         let entries = vec![
@@ -400,7 +742,7 @@ mod tests {
             "file2.rs".to_string(),
             "file3.rs".to_string(),
         ];
-        let result = generate_full_patch(&entries, 3000, &remaining);
+        let result = generate_full_patch(&entries, 3000, &remaining, "clip", Tokenizer::O200k);
         assert_eq!(result.files_in_patch.len(), 3);
         assert!(result.remaining_files.is_empty());
 
@@ -408,7 +750,7 @@ mod tests {
         // file1 (500): 0+500 <= 1000 → fits
         // file2 (500): 500+500=1000 <= 1000 → fits (equal)
         // file3 (500): 1000+500=1500 > 1000 → deferred (soft)
-        let result = generate_full_patch(&entries, 2500, &remaining);
+        let result = generate_full_patch(&entries, 2500, &remaining, "clip", Tokenizer::O200k);
         assert_eq!(result.files_in_patch.len(), 2);
         assert!(result.remaining_files.contains(&"file3.rs".to_string()));
     }
@@ -435,12 +777,86 @@ mod tests {
         ];
 
         let remaining = vec!["a.rs".to_string(), "b.rs".to_string()];
-        let result = generate_full_patch(&entries, 100_000, &remaining);
+        let result = generate_full_patch(&entries, 100_000, &remaining, "clip", Tokenizer::O200k);
 
         assert_eq!(result.files_in_patch.len(), 2);
         assert!(result.remaining_files.is_empty());
     }
 
+    #[test]
+    fn test_generate_full_patch_large_patch_policy() {
+        let big = "+line\n".repeat(3000);
+        let tokenizer = Tokenizer::O200k;
+        let entries = vec![
+            (
+                "small.rs".to_string(),
+                FileEntry {
+                    patch: "+small".to_string(),
+                    tokens: 100,
+                    edit_type: EditType::Modified,
+                },
+            ),
+            (
+                "big.rs".to_string(),
+                FileEntry {
+                    tokens: tokenizer.count(&big),
+                    patch: big,
+                    edit_type: EditType::Modified,
+                },
+            ),
+        ];
+        let remaining = vec!["small.rs".to_string(), "big.rs".to_string()];
+
+        // soft budget = 3000 - 1500 = 1500, far below big.rs
+        let result = generate_full_patch(&entries, 3000, &remaining, "skip", tokenizer);
+        assert_eq!(result.files_in_patch, vec!["small.rs"]);
+        assert_eq!(result.skipped_files, vec!["big.rs"]);
+        assert!(result.remaining_files.is_empty());
+
+        let result = generate_full_patch(&entries, 3000, &remaining, "clip", tokenizer);
+        assert_eq!(result.files_in_patch, vec!["small.rs", "big.rs"]);
+        assert!(result.total_tokens <= 1500);
+        assert!(result.patches.contains("+line"));
+        assert!(result.skipped_files.is_empty());
+
+        let diff = append_remaining_file_lists(
+            String::new(),
+            0,
+            100_000,
+            &[make_file("big.rs", "", EditType::Modified)],
+            &[],
+            &["big.rs".to_string()],
+            tokenizer,
+        );
+        assert!(diff.contains("### Files too large to include (changes not shown):\n- big.rs"));
+        assert!(!diff.contains("Additional modified files"));
+    }
+
+    #[test]
+    fn test_build_file_dict_skips_patch_extension_types() {
+        let files = vec![
+            make_file("README.md", "@@ -1 +1,2 @@\n-a\n+b\n+c", EditType::Modified),
+            make_file("src/main.rs", "@@ -1 +1 @@\n-a\n+b", EditType::Modified),
+        ];
+        let dict = build_file_dict(&files, true, 0, 0, &[".md".into()], Tokenizer::O200k);
+        let readme = &dict.iter().find(|(f, _)| f == "README.md").unwrap().1;
+        assert!(readme.patch.contains("## File: 'README.md'"));
+        assert!(readme.patch.contains("2 lines added, 1 lines removed"));
+        assert!(!readme.patch.contains("+c"));
+        let main = &dict.iter().find(|(f, _)| f == "src/main.rs").unwrap().1;
+        assert!(main.patch.contains("+b"));
+    }
+
+    #[test]
+    fn test_build_file_dict_uses_patch_summary() {
+        let mut file = make_file("big.rs", "@@ -1 +1 @@\n-a\n+b", EditType::Modified);
+        file.patch_summary = Some("- Rewrote the parser".into());
+        let dict = build_file_dict(&[file], true, 0, 0, &[], Tokenizer::O200k);
+        assert!(dict[0].1.patch.contains("## File: 'big.rs'"));
+        assert!(dict[0].1.patch.contains("- Rewrote the parser"));
+        assert!(!dict[0].1.patch.contains("+b"));
+    }
+
     #[test]
     fn test_append_remaining_file_lists_adds_sections() {
         let files = vec![
@@ -455,6 +871,8 @@ mod tests {
             100_000,
             &files,
             &["included.rs".to_string()],
+            &[],
+            Tokenizer::O200k,
         );
 
         assert!(result.contains("existing patch"));
@@ -0,0 +1,751 @@
+//! Repository context retrieval (`config.enable_repo_context`).
+//!
+//! Files on the default branch are split into line chunks, embedded through
+//! the OpenAI-compatible `/embeddings` endpoint at `repo_context.api_base`
+//! (`openai.api_base` when unset), and kept in the vector store
+//! chosen by `repo_context.vectordb`. For each tool run, the symbols touched
+//! in the diff form a query, and the closest chunks from files the PR does
+//! not change are added to the prompt as `repo_context`.
+//!
+//! An index is tagged with the tree revision it was built from and rebuilt
+//! once the default branch moves.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::ai::token::clip_tokens;
+use crate::config::types::Settings;
+use crate::error::PrAgentError;
+use crate::git::GitProvider;
+use crate::git::types::{FilePatchInfo, RepoTree};
+use crate::processing::filter::{build_ignore_patterns, is_binary};
+use crate::processing::token_budget::{PromptComponent, cap_component};
+use crate::util::{KeyedLocks, http_client_builder};
+
+/// Inputs per `/embeddings` request.
+const EMBEDDING_BATCH: usize = 64;
+/// Tokens of a chunk sent for embedding.
+const MAX_CHUNK_TOKENS: u32 = 2000;
+const MAX_QUERY_SYMBOLS: usize = 40;
+/// Repository files fetched at the same time while indexing.
+const FETCH_CONCURRENCY: usize = 8;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Words that introduce a definition; the name after them is a symbol even
+/// without `_` or inner capitals.
+pub const DEFINITION_KEYWORDS: &[&str] = &[
+    "fn",
+    "def",
+    "class",
+    "struct",
+    "enum",
+    "trait",
+    "impl",
+    "interface",
+    "func",
+    "function",
+    "type",
+    "const",
+];
+
+/// In-memory indexes by repository, for `vectordb = "memory"`.
+static MEMORY: LazyLock<Mutex<HashMap<String, MemoryIndex>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Held per repository while checking or building its index, so concurrent
+/// runs on the same repository index it once.
+static INDEXING: LazyLock<KeyedLocks> = LazyLock::new(KeyedLocks::default);
+
+/// A slice of a repository file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub path: String,
+    /// 1-based, inclusive.
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// Repository snippets related to the changed `files`, capped at
+/// `config.max_repo_context_tokens`. Empty when `config.enable_repo_context`
+/// is off or retrieval fails; failures are logged.
+pub async fn retrieve(
+    provider: &dyn GitProvider,
+    files: &[FilePatchInfo],
+    settings: &Settings,
+) -> String {
+    if !settings.config.enable_repo_context {
+        return String::new();
+    }
+    match retrieve_snippets(provider, files, settings).await {
+        Ok(snippets) => cap_component(
+            PromptComponent::RepoContext,
+            &format_snippets(&snippets),
+            &settings.config,
+        ),
+        Err(e) => {
+            tracing::warn!(error = %e, "repository context retrieval failed");
+            String::new()
+        }
+    }
+}
+
+async fn retrieve_snippets(
+    provider: &dyn GitProvider,
+    files: &[FilePatchInfo],
+    settings: &Settings,
+) -> Result<Vec<Chunk>, PrAgentError> {
+    let symbols = diff_symbols(files);
+    if symbols.is_empty() {
+        return Ok(Vec::new());
+    }
+    let store = Store::from_settings(provider, settings)?;
+    let embedder = Embedder::from_settings(settings)?;
+    let revision = ensure_index(provider, settings, &store, &embedder).await?;
+
+    let query = format!("Code defining or using: {}", symbols.join(", "));
+    let vector = embedder.embed(&[query]).await?.pop().unwrap_or_default();
+    let changed: HashSet<&str> = files.iter().map(|f| f.filename.as_str()).collect();
+    let top_k = settings.repo_context.top_k;
+    let hits = store
+        .search(&revision, &vector, top_k * 2 + changed.len())
+        .await?;
+    let snippets: Vec<Chunk> = hits
+        .into_iter()
+        .filter(|c| !changed.contains(c.path.as_str()))
+        .take(top_k)
+        .collect();
+    tracing::info!(
+        symbols = symbols.len(),
+        snippets = snippets.len(),
+        "retrieved repository context"
+    );
+    Ok(snippets)
+}
+
+/// Build the index for the current default-branch tree unless the store
+/// already has it. Returns the tree revision.
+async fn ensure_index(
+    provider: &dyn GitProvider,
+    settings: &Settings,
+    store: &Store,
+    embedder: &Embedder,
+) -> Result<String, PrAgentError> {
+    let (owner, name) = provider.repo_owner_and_name();
+    let _guard = INDEXING.lock(&format!("{owner}/{name}")).await;
+    let tree = provider.get_repo_tree().await?;
+    if !store.is_indexed(&tree.revision).await? {
+        build_index(provider, settings, store, embedder, &tree).await?;
+    }
+    Ok(tree.revision)
+}
+
+async fn build_index(
+    provider: &dyn GitProvider,
+    settings: &Settings,
+    store: &Store,
+    embedder: &Embedder,
+    tree: &RepoTree,
+) -> Result<(), PrAgentError> {
+    let config = &settings.repo_context;
+    let ignore = build_ignore_patterns();
+    let paths: Vec<&str> = tree
+        .files
+        .iter()
+        .filter(|(path, size)| {
+            *size > 0
+                && *size <= config.max_file_bytes
+                && !is_binary(path)
+                && !ignore.iter().any(|re| re.is_match(path))
+        })
+        .map(|(path, _)| path.as_str())
+        .take(config.max_files)
+        .collect();
+    tracing::info!(
+        revision = %tree.revision,
+        files = paths.len(),
+        "indexing repository for context retrieval"
+    );
+
+    // Batches instead of a buffered stream: a stream of borrowed paths makes
+    // the tool future fail the `Send` bound of the webhook job queue.
+    let mut contents: Vec<(&str, Option<String>)> = Vec::with_capacity(paths.len());
+    for batch in paths.chunks(FETCH_CONCURRENCY) {
+        let fetched = join_all(batch.iter().map(|path| provider.get_repo_file(path))).await;
+        for (&path, result) in batch.iter().zip(fetched) {
+            match result {
+                Ok(content) => contents.push((path, content)),
+                Err(e) => {
+                    tracing::debug!(path, error = %e, "skipping file in repository index");
+                    contents.push((path, None));
+                }
+            }
+        }
+    }
+    let chunks: Vec<Chunk> = contents
+        .into_iter()
+        .filter_map(|(path, content)| Some(chunk_file(path, &content?, config.chunk_lines)))
+        .flatten()
+        .collect();
+    let texts: Vec<String> = chunks.iter().map(embedding_text).collect();
+    let vectors = embedder.embed(&texts).await?;
+    store.replace(&tree.revision, chunks, vectors).await
+}
+
+/// Split `content` into chunks of `chunk_lines` lines, skipping blank ones.
+pub fn chunk_file(path: &str, content: &str, chunk_lines: usize) -> Vec<Chunk> {
+    let chunk_lines = chunk_lines.max(1);
+    let lines: Vec<&str> = content.lines().collect();
+    lines
+        .chunks(chunk_lines)
+        .enumerate()
+        .filter_map(|(i, window)| {
+            let text = window.join("\n");
+            if text.trim().is_empty() {
+                return None;
+            }
+            let start_line = i * chunk_lines + 1;
+            Some(Chunk {
+                path: path.to_string(),
+                start_line,
+                end_line: start_line + window.len() - 1,
+                text,
+            })
+        })
+        .collect()
+}
+
+/// Names on the changed lines that look like code symbols: `snake_case`,
+/// `camelCase`, or the name after a definition keyword (`fn`, `class`, ...).
+pub fn diff_symbols(files: &[FilePatchInfo]) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    for file in files {
+        for line in file.patch.lines() {
+            let Some(code) = line.strip_prefix('+').or_else(|| line.strip_prefix('-')) else {
+                continue;
+            };
+            // `+++ b/path` and `--- a/path` headers.
+            if code.starts_with("++") || code.starts_with("--") {
+                continue;
+            }
+            let words: Vec<&str> = code
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .filter(|w| !w.is_empty())
+                .collect();
+            for (i, word) in words.iter().enumerate() {
+                let after_keyword = i > 0 && DEFINITION_KEYWORDS.contains(&words[i - 1]);
+                let code_like = word.contains('_') || is_mixed_case(word);
+                if word.len() >= 4
+                    && (after_keyword || code_like)
+                    && !word.starts_with(|c: char| c.is_ascii_digit())
+                    && !symbols.iter().any(|s| s == word)
+                {
+                    symbols.push(word.to_string());
+                    if symbols.len() == MAX_QUERY_SYMBOLS {
+                        return symbols;
+                    }
+                }
+            }
+        }
+    }
+    symbols
+}
+
+fn is_mixed_case(word: &str) -> bool {
+    word.chars().skip(1).any(char::is_uppercase) && word.chars().any(char::is_lowercase)
+}
+
+/// The text embedded for a chunk: its location, then the code.
+fn embedding_text(chunk: &Chunk) -> String {
+    clip_tokens(
+        &format!("{}:{}\n{}", chunk.path, chunk.start_line, chunk.text),
+        MAX_CHUNK_TOKENS,
+        false,
+    )
+}
+
+fn format_snippets(snippets: &[Chunk]) -> String {
+    let mut out = String::new();
+    for chunk in snippets {
+        let _ = write!(
+            out,
+            "## File: '{}' (lines {}-{})\n```\n{}\n```\n\n",
+            chunk.path, chunk.start_line, chunk.end_line, chunk.text
+        );
+    }
+    out
+}
+
+/// Client for an OpenAI-compatible `/embeddings` endpoint.
+struct Embedder {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    model: String,
+}
+
+impl Embedder {
+    fn from_settings(settings: &Settings) -> Result<Self, PrAgentError> {
+        let config = &settings.repo_context;
+        let base = [&config.api_base, &settings.openai.api_base]
+            .into_iter()
+            .find(|base| !base.is_empty())
+            .map_or("https://api.openai.com/v1", |base| {
+                base.trim_end_matches('/')
+            });
+        let api_key = if config.api_key.is_empty() {
+            &settings.openai.key
+        } else {
+            &config.api_key
+        };
+        let client = http_client_builder(&settings.http, &settings.http.ai_headers)?
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(PrAgentError::Http)?;
+        Ok(Self {
+            client,
+            url: format!("{base}/embeddings"),
+            api_key: api_key.clone(),
+            model: config.embedding_model.clone(),
+        })
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, PrAgentError> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(EMBEDDING_BATCH) {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .json(&json!({ "model": self.model, "input": batch }));
+            if !self.api_key.is_empty() {
+                request = request.bearer_auth(&self.api_key);
+            }
+            let resp: serde_json::Value = request.send().await?.error_for_status()?.json().await?;
+            let embedded = parse_embeddings(&resp);
+            if embedded.len() != batch.len() {
+                return Err(PrAgentError::AiHandler(format!(
+                    "embeddings response has {} vectors for {} inputs",
+                    embedded.len(),
+                    batch.len()
+                )));
+            }
+            vectors.extend(embedded);
+        }
+        Ok(vectors)
+    }
+}
+
+/// Vectors of an `/embeddings` response, in input order.
+fn parse_embeddings(resp: &serde_json::Value) -> Vec<Vec<f32>> {
+    let mut data: Vec<(u64, Vec<f32>)> = resp["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(i, item)| {
+            let index = item["index"].as_u64().unwrap_or(i as u64);
+            let vector = item["embedding"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|x| x.as_f64())
+                .map(|x| x as f32)
+                .collect();
+            (index, vector)
+        })
+        .collect();
+    data.sort_by_key(|(index, _)| *index);
+    data.into_iter().map(|(_, vector)| vector).collect()
+}
+
+/// Chunks and their vectors for one revision of a repository.
+struct MemoryIndex {
+    revision: String,
+    entries: Vec<(Chunk, Vec<f32>)>,
+}
+
+/// Where the index lives (`repo_context.vectordb`).
+enum Store {
+    Memory {
+        repo: String,
+    },
+    Qdrant {
+        client: reqwest::Client,
+        url: String,
+        api_key: String,
+        collection: String,
+    },
+}
+
+impl Store {
+    fn from_settings(
+        provider: &dyn GitProvider,
+        settings: &Settings,
+    ) -> Result<Self, PrAgentError> {
+        let (owner, name) = provider.repo_owner_and_name();
+        match settings.repo_context.vectordb.as_str() {
+            "memory" => Ok(Store::Memory {
+                repo: format!("{owner}/{name}"),
+            }),
+            "qdrant" => {
+                if settings.qdrant.url.is_empty() {
+                    return Err(PrAgentError::Other(
+                        "repo_context.vectordb = \"qdrant\" requires qdrant.url".into(),
+                    ));
+                }
+                let client = http_client_builder(&settings.http, &HashMap::new())?
+                    .timeout(REQUEST_TIMEOUT)
+                    .build()
+                    .map_err(PrAgentError::Http)?;
+                Ok(Store::Qdrant {
+                    client,
+                    url: settings.qdrant.url.trim_end_matches('/').to_string(),
+                    api_key: settings.qdrant.api_key.clone(),
+                    collection: collection_name(
+                        &settings.repo_context.collection_prefix,
+                        &owner,
+                        &name,
+                    ),
+                })
+            }
+            other => Err(PrAgentError::Other(format!(
+                "unsupported repo_context.vectordb '{other}' (expected \"memory\" or \"qdrant\")"
+            ))),
+        }
+    }
+
+    async fn is_indexed(&self, revision: &str) -> Result<bool, PrAgentError> {
+        match self {
+            Store::Memory { repo } => Ok(MEMORY
+                .lock()
+                .map_err(|_| PrAgentError::Other("repository index lock poisoned".into()))?
+                .get(repo)
+                .is_some_and(|index| index.revision == revision)),
+            Store::Qdrant { collection, .. } => {
+                let body = json!({ "filter": revision_filter(revision), "exact": true });
+                let resp = self
+                    .qdrant(
+                        reqwest::Method::POST,
+                        &format!("collections/{collection}/points/count"),
+                        Some(&body),
+                    )
+                    .await?;
+                Ok(resp.is_some_and(|r| r["result"]["count"].as_u64().unwrap_or(0) > 0))
+            }
+        }
+    }
+
+    /// Replace the stored index with `chunks` for `revision`.
+    async fn replace(
+        &self,
+        revision: &str,
+        chunks: Vec<Chunk>,
+        vectors: Vec<Vec<f32>>,
+    ) -> Result<(), PrAgentError> {
+        match self {
+            Store::Memory { repo } => {
+                MEMORY
+                    .lock()
+                    .map_err(|_| PrAgentError::Other("repository index lock poisoned".into()))?
+                    .insert(
+                        repo.clone(),
+                        MemoryIndex {
+                            revision: revision.to_string(),
+                            entries: chunks.into_iter().zip(vectors).collect(),
+                        },
+                    );
+                Ok(())
+            }
+            Store::Qdrant { collection, .. } => {
+                let Some(size) = vectors.first().map(Vec::len) else {
+                    return Ok(());
+                };
+                let path = format!("collections/{collection}");
+                if self
+                    .qdrant(reqwest::Method::GET, &path, None)
+                    .await?
+                    .is_none()
+                {
+                    let body = json!({ "vectors": { "size": size, "distance": "Cosine" } });
+                    self.qdrant(reqwest::Method::PUT, &path, Some(&body))
+                        .await?;
+                }
+                let points: Vec<serde_json::Value> = chunks
+                    .iter()
+                    .zip(&vectors)
+                    .map(|(chunk, vector)| {
+                        json!({
+                            "id": point_id(revision, chunk),
+                            "vector": vector,
+                            "payload": {
+                                "revision": revision,
+                                "path": chunk.path,
+                                "start_line": chunk.start_line,
+                                "end_line": chunk.end_line,
+                                "text": chunk.text,
+                            },
+                        })
+                    })
+                    .collect();
+                for batch in points.chunks(EMBEDDING_BATCH) {
+                    self.qdrant(
+                        reqwest::Method::PUT,
+                        &format!("{path}/points?wait=true"),
+                        Some(&json!({ "points": batch })),
+                    )
+                    .await?;
+                }
+                // Drop chunks of earlier revisions.
+                let stale = json!({ "filter": { "must_not": revision_filter(revision)["must"] } });
+                self.qdrant(
+                    reqwest::Method::POST,
+                    &format!("{path}/points/delete?wait=true"),
+                    Some(&stale),
+                )
+                .await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// The `limit` chunks closest to `vector`, best first.
+    async fn search(
+        &self,
+        revision: &str,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<Chunk>, PrAgentError> {
+        match self {
+            Store::Memory { repo } => {
+                let indexes = MEMORY
+                    .lock()
+                    .map_err(|_| PrAgentError::Other("repository index lock poisoned".into()))?;
+                let Some(index) = indexes.get(repo).filter(|i| i.revision == revision) else {
+                    return Ok(Vec::new());
+                };
+                let mut scored: Vec<(f32, &Chunk)> = index
+                    .entries
+                    .iter()
+                    .map(|(chunk, v)| (cosine_similarity(vector, v), chunk))
+                    .collect();
+                scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+                Ok(scored
+                    .into_iter()
+                    .take(limit)
+                    .map(|(_, chunk)| chunk.clone())
+                    .collect())
+            }
+            Store::Qdrant { collection, .. } => {
+                let body = json!({
+                    "vector": vector,
+                    "limit": limit,
+                    "with_payload": true,
+                    "filter": revision_filter(revision),
+                });
+                let resp = self
+                    .qdrant(
+                        reqwest::Method::POST,
+                        &format!("collections/{collection}/points/search"),
+                        Some(&body),
+                    )
+                    .await?
+                    .unwrap_or_default();
+                Ok(resp["result"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|hit| {
+                        let payload = &hit["payload"];
+                        Some(Chunk {
+                            path: payload["path"].as_str()?.to_string(),
+                            start_line: payload["start_line"].as_u64()? as usize,
+                            end_line: payload["end_line"].as_u64()? as usize,
+                            text: payload["text"].as_str()?.to_string(),
+                        })
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Send a Qdrant REST request; `None` when the collection doesn't exist.
+    async fn qdrant(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<Option<serde_json::Value>, PrAgentError> {
+        let Store::Qdrant {
+            client,
+            url,
+            api_key,
+            ..
+        } = self
+        else {
+            return Ok(None);
+        };
+        let mut request = client.request(method, format!("{url}/{path}"));
+        if !api_key.is_empty() {
+            request = request.header("api-key", api_key);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let resp = request.send().await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(PrAgentError::Other(format!(
+                "Qdrant {path} failed ({status}): {text}"
+            )));
+        }
+        Ok(Some(resp.json().await?))
+    }
+}
+
+fn revision_filter(revision: &str) -> serde_json::Value {
+    json!({ "must": [{ "key": "revision", "match": { "value": revision } }] })
+}
+
+/// Qdrant collection for a repository: `{prefix}_{owner}_{repo}`, limited to
+/// characters Qdrant accepts in names.
+fn collection_name(prefix: &str, owner: &str, repo: &str) -> String {
+    format!("{prefix}_{owner}_{repo}")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Stable Qdrant point ID for a chunk of a revision.
+fn point_id(revision: &str, chunk: &Chunk) -> u64 {
+    let digest = Sha256::digest(format!("{revision}\0{}\0{}", chunk.path, chunk.start_line));
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom == 0.0 { 0.0 } else { dot / denom }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(path: &str, text: &str) -> Chunk {
+        Chunk {
+            path: path.into(),
+            start_line: 1,
+            end_line: 1,
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn test_chunk_file() {
+        let content = "a\nb\nc\n\n\n\nd";
+        let chunks = chunk_file("src/lib.rs", content, 3);
+        assert_eq!(chunks.len(), 2, "the blank middle chunk is skipped");
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (1, 3));
+        assert_eq!(chunks[0].text, "a\nb\nc");
+        assert_eq!((chunks[1].start_line, chunks[1].end_line), (7, 7));
+    }
+
+    #[test]
+    fn test_diff_symbols() {
+        let file = FilePatchInfo::new(
+            String::new(),
+            String::new(),
+            "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n \
+             unchanged_name()\n-fn load(path: &str) {}\n+fn parse_config(retryCount: u32) {}\n"
+                .into(),
+            "src/lib.rs".into(),
+        );
+        assert_eq!(
+            diff_symbols(&[file]),
+            vec!["load", "parse_config", "retryCount"]
+        );
+    }
+
+    #[test]
+    fn test_parse_embeddings_orders_by_index() {
+        let resp = json!({ "data": [
+            { "index": 1, "embedding": [0.0, 1.0] },
+            { "index": 0, "embedding": [1.0, 0.0] },
+        ]});
+        assert_eq!(
+            parse_embeddings(&resp),
+            vec![vec![1.0, 0.0], vec![0.0, 1.0]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_search() {
+        let store = Store::Memory {
+            repo: "test/memory-store".into(),
+        };
+        assert!(!store.is_indexed("rev1").await.unwrap());
+        store
+            .replace(
+                "rev1",
+                vec![chunk("a.rs", "fn a() {}"), chunk("b.rs", "fn b() {}")],
+                vec![vec![1.0, 0.0], vec![0.0, 1.0]],
+            )
+            .await
+            .unwrap();
+        assert!(store.is_indexed("rev1").await.unwrap());
+        assert!(!store.is_indexed("rev2").await.unwrap());
+
+        let hits = store.search("rev1", &[0.1, 0.9], 1).await.unwrap();
+        assert_eq!(hits, vec![chunk("b.rs", "fn b() {}")]);
+        assert!(
+            store
+                .search("rev2", &[0.1, 0.9], 1)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_collection_name() {
+        assert_eq!(
+            collection_name("pr_agent", "my.org", "web app"),
+            "pr_agent_my_org_web_app"
+        );
+    }
+
+    #[test]
+    fn test_embedder_endpoint_falls_back_to_openai() {
+        let mut settings = Settings::default();
+        settings.openai.api_base = "https://llm.example.com/v1/".into();
+        settings.openai.key = "sk-openai".into();
+        let embedder = Embedder::from_settings(&settings).unwrap();
+        assert_eq!(embedder.url, "https://llm.example.com/v1/embeddings");
+        assert_eq!(embedder.api_key, "sk-openai");
+
+        settings.repo_context.api_base = "https://embed.example.com/v1".into();
+        settings.repo_context.api_key = "sk-embed".into();
+        let embedder = Embedder::from_settings(&settings).unwrap();
+        assert_eq!(embedder.url, "https://embed.example.com/v1/embeddings");
+        assert_eq!(embedder.api_key, "sk-embed");
+    }
+}
@@ -126,6 +126,68 @@ pub fn format_patch_simple(
     format!("\n\n## File: '{}'\n\n{}\n", filename.trim(), patch.trim())
 }
 
+/// Format a file as its header and line counts only, for file types in
+/// `config.patch_extension_skip_types`.
+pub fn format_patch_stats(
+    filename: &str,
+    patch: &str,
+    edit_type: crate::git::types::EditType,
+) -> String {
+    if edit_type == crate::git::types::EditType::Deleted {
+        return format!("## File '{}' was deleted\n", filename.trim());
+    }
+    format!(
+        "\n\n## File: '{}'\n\n{}\n",
+        filename.trim(),
+        patch_stats_note(patch)
+    )
+}
+
+/// One-line stand-in for a patch whose content is left out of prompts.
+pub fn patch_stats_note(patch: &str) -> String {
+    let (mut added, mut removed) = (0, 0);
+    for line in patch.lines() {
+        if line.starts_with('+') && !line.starts_with("+++") {
+            added += 1;
+        } else if line.starts_with('-') && !line.starts_with("---") {
+            removed += 1;
+        }
+    }
+    format!("(changes not shown for this file type: {added} lines added, {removed} lines removed)")
+}
+
+/// Insert an AI-generated `summary` under the `## File:` header of a formatted
+/// patch. Patches without that header (deleted files) are returned unchanged.
+pub fn insert_ai_summary(patch_text: &str, summary: &str) -> String {
+    insert_under_header(
+        patch_text,
+        &format!("### AI-generated changes summary:\n* {}\n", summary.trim()),
+    )
+}
+
+/// Insert the file's `language` under the `## File:` header of a formatted
+/// patch, like [`insert_ai_summary`].
+pub fn insert_language(patch_text: &str, language: &str) -> String {
+    insert_under_header(patch_text, &format!("Language: {language}\n"))
+}
+
+fn insert_under_header(patch_text: &str, text: &str) -> String {
+    let Some(header) = patch_text.find("## File: '") else {
+        return patch_text.to_string();
+    };
+    let end = patch_text[header..]
+        .find('\n')
+        .map_or(patch_text.len(), |i| header + i + 1);
+    let mut out = String::with_capacity(patch_text.len() + text.len() + 1);
+    out.push_str(&patch_text[..end]);
+    if end == patch_text.len() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(text);
+    out.push_str(&patch_text[end..]);
+    out
+}
+
 /// Extract hunk lines from a diff patch for the /ask_line tool.
 ///
 /// Given a raw diff hunk (typically from `body["comment"]["diff_hunk"]`),
@@ -193,6 +255,59 @@ pub fn extract_hunk_lines_from_patch(
     (full_hunk, selected)
 }
 
+/// New-side (RIGHT) lines of each hunk as `(line number, content)`, without
+/// the `+`/` ` prefix. Line numbers within a hunk are consecutive.
+pub fn new_side_hunks(patch: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut hunks: Vec<Vec<(usize, &str)>> = Vec::new();
+    let mut new_line = 0;
+    for line in patch.lines() {
+        if let Some(header) = HunkHeader::parse(line) {
+            hunks.push(Vec::new());
+            new_line = header.start2;
+        } else if let Some(hunk) = hunks.last_mut()
+            && !line.starts_with('-')
+            && !line.starts_with('\\')
+        {
+            hunk.push((new_line, line.get(1..).unwrap_or_default()));
+            new_line += 1;
+        }
+    }
+    hunks
+}
+
+/// New-side line range `(start, end)` of the hunk with the most changed lines.
+///
+/// Used to point quick `/ask_line` commands at the most significant part of a
+/// file's diff. Returns `None` when the patch has no hunks.
+pub fn main_hunk_range(patch: &str) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize, usize)> = None; // (changes, start, end)
+    let mut current: Option<(usize, usize, usize)> = None;
+
+    for line in patch.lines() {
+        if let Some(header) = HunkHeader::parse(line) {
+            if let Some(c) = current.take()
+                && best.is_none_or(|b| c.0 > b.0)
+            {
+                best = Some(c);
+            }
+            let start = header.start2.max(1);
+            let end = start + header.size2.saturating_sub(1);
+            current = Some((0, start, end));
+        } else if let Some(c) = current.as_mut()
+            && (line.starts_with('+') || line.starts_with('-'))
+        {
+            c.0 += 1;
+        }
+    }
+    if let Some(c) = current
+        && best.is_none_or(|b| c.0 > b.0)
+    {
+        best = Some(c);
+    }
+
+    best.map(|(_, start, end)| (start, end))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -208,6 +323,59 @@ mod tests {
         assert_eq!(h.section_header, "fn main()");
     }
 
+    #[test]
+    fn test_main_hunk_range_picks_largest_hunk() {
+        let patch = "@@ -1,3 +1,3 @@\n a\n-b\n+c\n@@ -20,2 +20,5 @@\n x\n+y\n+z\n+w\n x2";
+        assert_eq!(main_hunk_range(patch), Some((20, 24)));
+        assert_eq!(main_hunk_range("@@ -0,0 +1 @@\n+only"), Some((1, 1)));
+        assert_eq!(main_hunk_range(""), None);
+    }
+
+    #[test]
+    fn test_new_side_hunks() {
+        let patch = "@@ -1,3 +1,3 @@\n a\n-b\n+c\n@@ -20,2 +20,3 @@\n x\n+y\n x2\n\\ No newline at end of file";
+        assert_eq!(
+            new_side_hunks(patch),
+            vec![
+                vec![(1, "a"), (2, "c")],
+                vec![(20, "x"), (21, "y"), (22, "x2")],
+            ]
+        );
+        assert!(new_side_hunks("").is_empty());
+    }
+
+    #[test]
+    fn test_insert_ai_summary() {
+        let patch = format_patch_simple("src/main.rs", "@@ -1 +1 @@\n-a\n+b", EditType::Modified);
+        let with_summary = insert_ai_summary(&patch, "Renames `a` to `b`.");
+        assert!(with_summary.contains(
+            "## File: 'src/main.rs'\n### AI-generated changes summary:\n* Renames `a` to `b`.\n\n@@"
+        ));
+        let deleted = "## File 'old.rs' was deleted\n";
+        assert_eq!(insert_ai_summary(deleted, "Removed."), deleted);
+
+        let both = insert_language(&with_summary, "Rust");
+        assert!(
+            both.contains(
+                "## File: 'src/main.rs'\nLanguage: Rust\n### AI-generated changes summary:"
+            )
+        );
+        assert_eq!(insert_language(deleted, "Rust"), deleted);
+    }
+
+    #[test]
+    fn test_format_patch_stats() {
+        let patch = "@@ -1,2 +1,3 @@\n intro\n-old\n+new\n+more";
+        assert_eq!(
+            format_patch_stats("README.md", patch, EditType::Modified),
+            "\n\n## File: 'README.md'\n\n(changes not shown for this file type: 2 lines added, 1 lines removed)\n"
+        );
+        assert_eq!(
+            format_patch_stats("old.md", patch, EditType::Deleted),
+            "## File 'old.md' was deleted\n"
+        );
+    }
+
     #[test]
     fn test_convert_simple_patch() {
         let patch = "@@ -1,3 +1,4 @@\n context\n-removed\n+added\n+new line\n context2";
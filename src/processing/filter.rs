@@ -1,6 +1,7 @@
 use regex::Regex;
 
 use crate::config::loader::get_settings;
+use crate::config::types::Settings;
 use crate::git::types::FilePatchInfo;
 
 /// Common binary file extensions that should be excluded from diff processing.
@@ -21,10 +22,32 @@ pub fn is_binary(filename: &str) -> bool {
     }
 }
 
+/// Check if only the name and line counts of a file go into prompts, per
+/// `config.patch_extension_skip_types` (e.g. `.md`, case-insensitive).
+pub fn is_patch_skipped(filename: &str, skip_types: &[String]) -> bool {
+    let name = filename
+        .rsplit('/')
+        .next()
+        .unwrap_or(filename)
+        .to_lowercase();
+    skip_types.iter().any(|ext| {
+        let ext = ext.trim().trim_start_matches('.').to_lowercase();
+        !ext.is_empty()
+            && name
+                .strip_suffix(&ext)
+                .is_some_and(|stem| stem.ends_with('.'))
+    })
+}
+
 /// Build the list of compiled ignore patterns from settings.
 /// Combines regex patterns and glob patterns (converted to regex).
 pub fn build_ignore_patterns() -> Vec<Regex> {
-    let settings = get_settings();
+    ignore_patterns(&get_settings())
+}
+
+/// Ignore patterns for `settings`: `[ignore]` regexes and globs, plus the
+/// generated-code globs of each `config.ignore_language_framework` entry.
+fn ignore_patterns(settings: &Settings) -> Vec<Regex> {
     let mut patterns = Vec::new();
 
     // Regex patterns from settings
@@ -38,22 +61,39 @@ pub fn build_ignore_patterns() -> Vec<Regex> {
 
     // Glob patterns from settings (convert to regex)
     for glob in &settings.ignore.glob {
-        let regex_str = glob_to_regex(glob);
-        if let Ok(re) = Regex::new(&regex_str) {
-            patterns.push(re);
-        }
-        // Also cover root-level files for `**/` prefixed globs
-        if let Some(root_glob) = glob.strip_prefix("**/") {
-            let root_regex = glob_to_regex(root_glob);
-            if let Ok(re) = Regex::new(&root_regex) {
-                patterns.push(re);
-            }
+        push_glob(&mut patterns, glob);
+    }
+
+    // Generated code of the configured languages/frameworks
+    for framework in &settings.config.ignore_language_framework {
+        match settings.generated_code.get(framework) {
+            Some(globs) => globs.iter().for_each(|glob| push_glob(&mut patterns, glob)),
+            None => tracing::warn!(framework, "unknown config.ignore_language_framework entry"),
         }
     }
 
     patterns
 }
 
+fn push_glob(patterns: &mut Vec<Regex>, glob: &str) {
+    let regex_str = glob_to_regex(glob);
+    if let Ok(re) = Regex::new(&regex_str) {
+        patterns.push(re);
+    }
+    // Also cover root-level files for `**/` prefixed globs
+    if let Some(root_glob) = glob.strip_prefix("**/") {
+        let root_regex = glob_to_regex(root_glob);
+        if let Ok(re) = Regex::new(&root_regex) {
+            patterns.push(re);
+        }
+    }
+}
+
+/// Whether `path` matches `glob`.
+pub fn glob_matches(glob: &str, path: &str) -> bool {
+    Regex::new(&glob_to_regex(glob)).is_ok_and(|re| re.is_match(path))
+}
+
 /// Convert a glob pattern to a regex string.
 /// Supports `*`, `**`, `?`, and character classes `[...]`.
 fn glob_to_regex(glob: &str) -> String {
@@ -96,8 +136,10 @@ fn glob_to_regex(glob: &str) -> String {
 }
 
 /// Filter a list of files, removing those that match ignore patterns or are binary.
-pub fn filter_files(files: &mut Vec<FilePatchInfo>) {
+/// Returns how many files were removed.
+pub fn filter_files(files: &mut Vec<FilePatchInfo>) -> usize {
     let patterns = build_ignore_patterns();
+    let before = files.len();
 
     files.retain(|file| {
         if is_binary(&file.filename) {
@@ -112,6 +154,7 @@ pub fn filter_files(files: &mut Vec<FilePatchInfo>) {
 
         true
     });
+    before - files.len()
 }
 
 #[cfg(test)]
@@ -127,6 +170,16 @@ mod tests {
         assert!(!is_binary("README.md"));
     }
 
+    #[test]
+    fn test_is_patch_skipped() {
+        let skip = vec![".md".to_string(), "txt".to_string()];
+        assert!(is_patch_skipped("README.md", &skip));
+        assert!(is_patch_skipped("docs/NOTES.TXT", &skip));
+        assert!(!is_patch_skipped("src/main.rs", &skip));
+        assert!(!is_patch_skipped("src/cmd", &skip));
+        assert!(!is_patch_skipped("README.md", &[]));
+    }
+
     #[test]
     fn test_glob_to_regex() {
         let re = Regex::new(&glob_to_regex("*.rs")).unwrap();
@@ -138,6 +191,14 @@ mod tests {
         assert!(re.is_match("deep/path/package.lock"));
     }
 
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("**/*.figma.md", "design/home.figma.md"));
+        assert!(glob_matches("**/*.figma.md", "home.figma.md"));
+        assert!(glob_matches("design/**", "design/icons/logo.svg"));
+        assert!(!glob_matches("design/**", "src/design.rs"));
+    }
+
     #[test]
     fn test_glob_double_star_slash() {
         let re = Regex::new(&glob_to_regex("**/node_modules/**")).unwrap();
@@ -206,6 +267,23 @@ mod tests {
         assert_eq!(files[0].filename, "src/main.rs");
     }
 
+    #[test]
+    fn test_ignore_patterns_include_language_frameworks() {
+        let mut settings =
+            crate::config::loader::load_settings(&std::collections::HashMap::new(), None, None)
+                .unwrap();
+        let ignored = |settings: &Settings, path: &str| {
+            ignore_patterns(settings).iter().any(|re| re.is_match(path))
+        };
+        assert!(ignored(&settings, "vendor/lib/mod.go"));
+        assert!(!ignored(&settings, "api/user.pb.go"));
+
+        settings.config.ignore_language_framework = vec!["protobuf".into(), "bogus".into()];
+        assert!(ignored(&settings, "api/user.pb.go"));
+        assert!(ignored(&settings, "user_pb2.py"));
+        assert!(!ignored(&settings, "api/user.go"));
+    }
+
     #[test]
     fn test_is_binary_no_extension() {
         assert!(!is_binary("Makefile"));
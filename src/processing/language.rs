@@ -0,0 +1,177 @@
+//! Languages of the changed files, and the PR's main language.
+//!
+//! A file's language comes from its extension, looked up in
+//! `[language_extension_map_org]` (`settings/language_extensions.toml`).
+//! Extensions claimed by several languages (`.h` is C and C++) go to the one
+//! with the most bytes in the repository, as reported by
+//! [`GitProvider::get_languages`]. The main language, the `language`
+//! template variable, is the one with the most changed lines of source code.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::config::loader::get_settings;
+use crate::git::GitProvider;
+use crate::git::types::FilePatchInfo;
+use crate::processing::compression::{FileKind, file_kind};
+
+/// Language of `filename`, by the longest extension that matches it.
+/// `repo_languages` (name → bytes) breaks ties between languages.
+pub fn detect(
+    filename: &str,
+    extensions: &HashMap<String, Vec<String>>,
+    repo_languages: &HashMap<String, u64>,
+) -> Option<String> {
+    let name = filename.rsplit('/').next().unwrap_or(filename);
+    let lower = name.to_lowercase();
+    extensions
+        .iter()
+        .flat_map(|(language, exts)| {
+            exts.iter()
+                .map(move |ext| (language, ext.trim_start_matches('*')))
+        })
+        .filter_map(|(language, ext)| {
+            if !ext.starts_with('.') || ext.len() >= name.len() {
+                return None;
+            }
+            // `.C` is C++ and `.c` is C, so an exact-case match wins.
+            let exact = name.ends_with(ext);
+            if !exact && !lower.ends_with(&ext.to_lowercase()) {
+                return None;
+            }
+            let bytes = repo_languages.get(language).copied().unwrap_or(0);
+            Some(((ext.len(), exact, bytes, Reverse(language)), language))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, language)| language.clone())
+}
+
+/// Main language of the PR: the detected language with the most changed
+/// lines in source files. Without one, the repository's largest language.
+pub fn main_language(files: &[FilePatchInfo], repo_languages: &HashMap<String, u64>) -> String {
+    let mut changed: HashMap<&str, usize> = HashMap::new();
+    for file in files {
+        let Some(language) = file.language.as_deref() else {
+            continue;
+        };
+        if file_kind(&file.filename) != FileKind::Source {
+            continue;
+        }
+        *changed.entry(language).or_default() += changed_lines(&file.patch);
+    }
+    let bytes = |language: &str| repo_languages.get(language).copied().unwrap_or(0);
+    if let Some((language, _)) = changed
+        .into_iter()
+        .max_by_key(|&(language, lines)| (lines, bytes(language), Reverse(language)))
+    {
+        return language.to_string();
+    }
+    repo_languages
+        .iter()
+        .max_by_key(|&(language, bytes)| (bytes, Reverse(language)))
+        .map(|(language, _)| language.clone())
+        .unwrap_or_default()
+}
+
+/// Set the language of each changed file and return the PR's main language
+/// (empty when unknown).
+pub async fn annotate_files(provider: &dyn GitProvider, files: &mut [FilePatchInfo]) -> String {
+    let settings = get_settings();
+    let repo_languages = provider.get_languages().await.unwrap_or_else(|e| {
+        tracing::debug!(error = %e, "repository languages unavailable");
+        HashMap::new()
+    });
+    for file in files.iter_mut().filter(|f| f.language.is_none()) {
+        file.language = detect(
+            &file.filename,
+            &settings.language_extension_map_org,
+            &repo_languages,
+        );
+    }
+    let language = main_language(files, &repo_languages);
+    tracing::debug!(%language, "detected the PR's main language");
+    language
+}
+
+fn changed_lines(patch: &str) -> usize {
+    patch
+        .lines()
+        .filter(|l| {
+            (l.starts_with('+') || l.starts_with('-'))
+                && !l.starts_with("+++")
+                && !l.starts_with("---")
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extensions() -> HashMap<String, Vec<String>> {
+        [
+            ("C", vec![".c", ".h"]),
+            ("C++", vec![".cpp", ".h", ".C"]),
+            ("Rust", vec![".rs", ".rs.in"]),
+            ("Markdown", vec![".md"]),
+            ("1C Enterprise", vec!["*.bsl"]),
+        ]
+        .into_iter()
+        .map(|(l, exts)| (l.to_string(), exts.into_iter().map(String::from).collect()))
+        .collect()
+    }
+
+    fn file(name: &str, language: Option<&str>, patch: &str) -> FilePatchInfo {
+        let mut file = FilePatchInfo::new(String::new(), String::new(), patch.into(), name.into());
+        file.language = language.map(String::from);
+        file
+    }
+
+    #[test]
+    fn test_detect() {
+        let exts = extensions();
+        let none = HashMap::new();
+        assert_eq!(detect("src/main.rs", &exts, &none).as_deref(), Some("Rust"));
+        assert_eq!(detect("gen/x.rs.in", &exts, &none).as_deref(), Some("Rust"));
+        assert_eq!(detect("MAIN.RS", &exts, &none).as_deref(), Some("Rust"));
+        assert_eq!(
+            detect("a/b.bsl", &exts, &none).as_deref(),
+            Some("1C Enterprise")
+        );
+        assert_eq!(detect("lib.c", &exts, &none).as_deref(), Some("C"));
+        assert_eq!(detect("lib.C", &exts, &none).as_deref(), Some("C++"));
+        assert_eq!(detect("Makefile", &exts, &none), None);
+        assert_eq!(detect(".rs", &exts, &none), None);
+
+        // A shared extension goes to the repository's larger language
+        assert_eq!(detect("api.h", &exts, &none).as_deref(), Some("C"));
+        let repo = HashMap::from([("C++".to_string(), 900), ("C".to_string(), 100)]);
+        assert_eq!(detect("api.h", &exts, &repo).as_deref(), Some("C++"));
+    }
+
+    #[test]
+    fn test_main_language() {
+        let files = vec![
+            file(
+                "README.md",
+                Some("Markdown"),
+                "@@ -1 +1,3 @@\n-a\n+b\n+c\n+d",
+            ),
+            file("src/lib.rs", Some("Rust"), "@@ -1 +1 @@\n-a\n+b"),
+            file("tests/it.c", Some("C"), "@@ -1 +1,3 @@\n-a\n+b\n+c\n+d"),
+            file("tool.c", Some("C"), "@@ -1 +1 @@\n+b"),
+            file("Makefile", None, "@@ -1 +1 @@\n-a\n+b"),
+        ];
+        // Docs and tests don't count
+        assert_eq!(main_language(&files, &HashMap::new()), "Rust");
+
+        let repo = HashMap::from([("C".to_string(), 10), ("Go".to_string(), 50)]);
+        let files = vec![
+            file("a.rs", Some("Rust"), "@@ -1 +1 @@\n-a"),
+            file("a.c", Some("C"), "@@ -1 +1 @@\n-a"),
+        ];
+        assert_eq!(main_language(&files, &repo), "C");
+        assert_eq!(main_language(&files[..0], &repo), "Go");
+        assert_eq!(main_language(&[], &HashMap::new()), "");
+    }
+}
@@ -1,4 +1,11 @@
+pub mod codeowners;
+pub mod components;
 pub mod compression;
+pub mod context_retrieval;
 pub mod diff;
 pub mod filter;
+pub mod language;
 pub mod patch;
+pub mod symbol_lookup;
+pub mod test_impact;
+pub mod token_budget;
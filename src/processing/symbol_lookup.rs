@@ -0,0 +1,171 @@
+//! Definitions of the code an `/ask` question names
+//! (`pr_questions.enable_symbol_lookup`).
+//!
+//! Each `backticked` identifier in the question is looked up with the
+//! provider's code search. The first matching files are read from the
+//! default branch, and the lines from the symbol's definition on are added
+//! to the prompt as `symbol_definitions`, capped at
+//! `config.max_symbol_definitions_tokens`.
+
+use std::fmt::Write;
+
+use futures_util::future::join_all;
+
+use crate::config::types::Settings;
+use crate::git::GitProvider;
+use crate::processing::context_retrieval::DEFINITION_KEYWORDS;
+use crate::processing::token_budget::{PromptComponent, cap_component};
+
+/// Symbols looked up per question.
+const MAX_SYMBOLS: usize = 3;
+/// Search results read per symbol.
+const MAX_FILES_PER_SYMBOL: usize = 3;
+/// Lines shown from a definition on.
+const SNIPPET_LINES: usize = 30;
+
+/// Identifiers quoted in backticks in `question`, in order, without
+/// duplicates. Paths such as `config::load` give their last segment.
+pub fn backticked_identifiers(question: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for span in question.split('`').skip(1).step_by(2) {
+        let Some(name) = span.trim().trim_end_matches("()").rsplit([':', '.']).next() else {
+            continue;
+        };
+        let is_identifier = name.len() >= 3
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+            && !name.starts_with(|c: char| c.is_ascii_digit());
+        if is_identifier && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Definitions of the `backticked` identifiers in `question`, capped at
+/// `config.max_symbol_definitions_tokens`. Empty when the lookup is off,
+/// nothing is found, or the provider has no code search.
+pub async fn lookup(provider: &dyn GitProvider, question: &str, settings: &Settings) -> String {
+    if !settings.pr_questions.enable_symbol_lookup {
+        return String::new();
+    }
+    let symbols: Vec<String> = backticked_identifiers(question)
+        .into_iter()
+        .take(MAX_SYMBOLS)
+        .collect();
+    if symbols.is_empty() {
+        return String::new();
+    }
+    let found = join_all(symbols.iter().map(|s| find_definition(provider, s))).await;
+    let mut out = String::new();
+    let mut included = 0;
+    for (symbol, definition) in symbols.iter().zip(found) {
+        if let Some((path, line, snippet)) = definition {
+            let _ = write!(
+                out,
+                "## `{symbol}` in '{path}' (line {line})\n```\n{snippet}\n```\n\n"
+            );
+            included += 1;
+        }
+    }
+    tracing::info!(symbols = ?symbols, found = included, "looked up symbol definitions");
+    cap_component(PromptComponent::SymbolDefinitions, &out, &settings.config)
+}
+
+/// The first search result that defines `symbol`: path, 1-based line and
+/// snippet.
+async fn find_definition(
+    provider: &dyn GitProvider,
+    symbol: &str,
+) -> Option<(String, usize, String)> {
+    let paths = match provider.search_code(symbol).await {
+        Ok(paths) => paths,
+        Err(e) => {
+            tracing::debug!(symbol, error = %e, "code search failed");
+            return None;
+        }
+    };
+    for path in paths.into_iter().take(MAX_FILES_PER_SYMBOL) {
+        let Ok(Some(content)) = provider.get_repo_file(&path).await else {
+            continue;
+        };
+        if let Some((line, snippet)) = definition_snippet(&content, symbol) {
+            return Some((path, line, snippet));
+        }
+    }
+    None
+}
+
+/// The line defining `symbol` in `content` (1-based) and up to
+/// [`SNIPPET_LINES`] lines from it.
+fn definition_snippet(content: &str, symbol: &str) -> Option<(usize, String)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let index = lines.iter().position(|line| defines(line, symbol))?;
+    let end = (index + SNIPPET_LINES).min(lines.len());
+    Some((index + 1, lines[index..end].join("\n")))
+}
+
+/// Whether `line` defines `symbol`: a definition keyword followed by the
+/// name, as in `pub fn load(` or `class Loader:`.
+fn defines(line: &str, symbol: &str) -> bool {
+    let words: Vec<&str> = line
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    words
+        .windows(2)
+        .any(|w| DEFINITION_KEYWORDS.contains(&w[0]) && w[1] == symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::config::loader::load_settings;
+    use crate::testing::mock_git::MockGitProvider;
+
+    #[test]
+    fn test_backticked_identifiers() {
+        assert_eq!(
+            backticked_identifiers(
+                "Why does `load_config()` call `settings::merge` and not `Loader.read`? \
+                 See `a`, `42x`, `load_config` and `x + y`."
+            ),
+            vec!["load_config", "merge", "read"]
+        );
+        assert!(backticked_identifiers("No code here").is_empty());
+    }
+
+    #[test]
+    fn test_definition_snippet() {
+        let content = "use std::fs;\n\n// calls load_config\npub async fn load_config(path: &str) {\n    fs::read(path);\n}\n";
+        let (line, snippet) = definition_snippet(content, "load_config").unwrap();
+        assert_eq!(line, 4);
+        assert!(snippet.starts_with("pub async fn load_config(path: &str) {"));
+        assert!(snippet.ends_with('}'));
+        assert!(definition_snippet("class Loader:\n    pass", "Loader").is_some());
+        assert!(definition_snippet("load_config(\"a\")", "load_config").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lookup() {
+        let provider = MockGitProvider::new()
+            .with_repo_file("src/main.rs", "fn main() {\n    load_config(\"a\");\n}\n")
+            .with_repo_file("src/config.rs", "pub fn load_config(path: &str) {}\n");
+        let question = "What does `load_config` return? And `missing_fn`?";
+
+        let settings = load_settings(&HashMap::new(), None, None).unwrap();
+        assert_eq!(lookup(&provider, question, &settings).await, "");
+
+        let overrides = HashMap::from([(
+            "pr_questions.enable_symbol_lookup".to_string(),
+            "true".to_string(),
+        )]);
+        let settings = load_settings(&overrides, None, None).unwrap();
+        let context = lookup(&provider, question, &settings).await;
+        assert_eq!(
+            context,
+            "## `load_config` in 'src/config.rs' (line 1)\n```\npub fn load_config(path: &str) {}\n```\n\n"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use crate::git::types::{EditType, FilePatchInfo};
+
+/// Extensions treated as source code for test-impact purposes.
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "rs", "py", "go", "js", "jsx", "ts", "tsx", "mjs", "cjs", "java", "kt", "kts", "scala", "rb",
+    "php", "cs", "c", "cc", "cpp", "cxx", "h", "hpp", "swift", "m", "mm", "dart", "ex", "exs",
+];
+
+/// Directory names that hold tests.
+const TEST_DIRS: &[&str] = &["test", "tests", "__tests__", "spec", "specs", "testing"];
+
+/// File stems that name their directory rather than themselves
+/// (`src/parser/mod.rs` is the `parser` module).
+const DIRECTORY_STEMS: &[&str] = &["mod", "index", "__init__", "lib", "main"];
+
+/// Markers in added lines that show a source file gained inline tests
+/// (e.g. a Rust `#[cfg(test)] mod tests`).
+const INLINE_TEST_MARKERS: &[&str] = &["#[test]", "#[tokio::test]", "#[cfg(test)]"];
+
+/// Result of mapping changed source files to changed test files.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TestImpact {
+    /// Changed files recognised as tests.
+    pub test_files: Vec<String>,
+    /// Changed source files with a matching test change.
+    pub covered: Vec<String>,
+    /// Changed source files with no matching test change.
+    pub untested: Vec<String>,
+}
+
+/// Pair each changed source file with the changed test files that likely cover it.
+///
+/// Matching is purely name-based: a test file covers a source file when its
+/// subject (`test_foo.py`, `foo_test.go`, `foo.spec.ts`, `FooTest.java` → `foo`)
+/// equals the source stem. Deleted files are ignored, and source files whose
+/// patch adds inline test markers count as covered.
+pub fn analyze_test_impact(files: &[FilePatchInfo]) -> TestImpact {
+    let mut impact = TestImpact::default();
+    let live: Vec<&FilePatchInfo> = files
+        .iter()
+        .filter(|f| f.edit_type != EditType::Deleted)
+        .collect();
+
+    let mut subjects = Vec::new();
+    for file in live.iter().filter(|f| is_test_file(&f.filename)) {
+        impact.test_files.push(file.filename.clone());
+        subjects.extend(test_subject(&file.filename));
+    }
+
+    for file in live {
+        if is_test_file(&file.filename) || !is_source_file(&file.filename) {
+            continue;
+        }
+        let stem = source_stem(&file.filename);
+        if subjects.contains(&stem) || adds_inline_tests(&file.patch) {
+            impact.covered.push(file.filename.clone());
+        } else {
+            impact.untested.push(file.filename.clone());
+        }
+    }
+
+    impact
+}
+
+/// Whether a path looks like a test file, by directory or by file name.
+pub fn is_test_file(path: &str) -> bool {
+    let mut parts: Vec<&str> = path.split('/').collect();
+    let Some(name) = parts.pop() else {
+        return false;
+    };
+    if parts
+        .iter()
+        .any(|dir| TEST_DIRS.contains(&dir.to_lowercase().as_str()))
+    {
+        return true;
+    }
+    let base = name.split('.').next().unwrap_or(name);
+    let lower = name.to_lowercase();
+    base.starts_with("test_")
+        || base.ends_with("_test")
+        || base.ends_with("_tests")
+        || base.ends_with("_spec")
+        || lower.contains(".test.")
+        || lower.contains(".spec.")
+        || (base.len() > 4 && base.ends_with("Test"))
+        || (base.len() > 5 && base.ends_with("Tests"))
+}
+
+/// Whether a path has a source-code extension.
+fn is_source_file(path: &str) -> bool {
+    path.rsplit_once('.')
+        .is_some_and(|(_, ext)| SOURCE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Lowercased stem of a source file, or its directory name for `mod.rs`-style files.
+fn source_stem(path: &str) -> String {
+    let mut parts: Vec<&str> = path.split('/').collect();
+    let name = parts.pop().unwrap_or(path);
+    let stem = name.split('.').next().unwrap_or(name);
+    if DIRECTORY_STEMS.contains(&stem)
+        && let Some(dir) = parts.pop()
+    {
+        return dir.to_lowercase();
+    }
+    stem.to_lowercase()
+}
+
+/// The source stem a test file is about, with test prefixes/suffixes stripped.
+fn test_subject(path: &str) -> Option<String> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let base = name.split('.').next().unwrap_or(name);
+
+    let mut subject = base.strip_prefix("test_").unwrap_or(base);
+    for suffix in ["_tests", "_test", "_spec", "Tests", "Test"] {
+        if let Some(s) = subject.strip_suffix(suffix) {
+            subject = s;
+            break;
+        }
+    }
+
+    let subject = subject.to_lowercase();
+    if subject.is_empty() || subject == "test" || DIRECTORY_STEMS.contains(&subject.as_str()) {
+        // Generic names (`parser/tests/mod.rs`, `test.py`) fall back to the
+        // nearest directory that isn't itself a test directory.
+        return path
+            .split('/')
+            .rev()
+            .skip(1)
+            .map(str::to_lowercase)
+            .find(|dir| !TEST_DIRS.contains(&dir.as_str()));
+    }
+    Some(subject)
+}
+
+/// Whether a unified diff adds lines containing inline test markers.
+fn adds_inline_tests(patch: &str) -> bool {
+    patch.lines().any(|line| {
+        line.starts_with('+')
+            && !line.starts_with("+++")
+            && INLINE_TEST_MARKERS.iter().any(|m| line.contains(m))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, patch: &str) -> FilePatchInfo {
+        let mut f = FilePatchInfo::new(String::new(), String::new(), patch.into(), name.into());
+        f.edit_type = EditType::Modified;
+        f
+    }
+
+    #[test]
+    fn test_is_test_file() {
+        assert!(is_test_file("tests/integration.rs"));
+        assert!(is_test_file("pkg/api/handler_test.go"));
+        assert!(is_test_file("app/test_models.py"));
+        assert!(is_test_file("src/components/Button.test.tsx"));
+        assert!(is_test_file("src/main/java/FooTest.java"));
+        assert!(is_test_file("web/__tests__/util.js"));
+        assert!(!is_test_file("src/contest.rs"));
+        assert!(!is_test_file("src/Test.java"));
+        assert!(!is_test_file("README.md"));
+    }
+
+    #[test]
+    fn test_test_subject() {
+        assert_eq!(
+            test_subject("tests/test_models.py").as_deref(),
+            Some("models")
+        );
+        assert_eq!(
+            test_subject("api/handler_test.go").as_deref(),
+            Some("handler")
+        );
+        assert_eq!(
+            test_subject("src/Button.spec.ts").as_deref(),
+            Some("button")
+        );
+        assert_eq!(
+            test_subject("FooServiceTests.cs").as_deref(),
+            Some("fooservice")
+        );
+        assert_eq!(
+            test_subject("src/parser/tests/mod.rs").as_deref(),
+            Some("parser")
+        );
+        assert_eq!(test_subject("tests/mod.rs").as_deref(), None);
+    }
+
+    #[test]
+    fn test_analyze_pairs_sources_with_tests() {
+        let files = vec![
+            file("app/models.py", ""),
+            file("tests/test_models.py", ""),
+            file("pkg/api/handler.go", ""),
+            file("src/parser/mod.rs", ""),
+            file("tests/parser.rs", ""),
+            file("docs/guide.md", ""),
+        ];
+        let impact = analyze_test_impact(&files);
+        assert_eq!(
+            impact.test_files,
+            vec!["tests/test_models.py", "tests/parser.rs"]
+        );
+        assert_eq!(impact.covered, vec!["app/models.py", "src/parser/mod.rs"]);
+        assert_eq!(impact.untested, vec!["pkg/api/handler.go"]);
+    }
+
+    #[test]
+    fn test_analyze_inline_tests_and_deletions() {
+        let mut deleted = file("src/old.rs", "-fn old() {}");
+        deleted.edit_type = EditType::Deleted;
+        let files = vec![
+            file(
+                "src/util.rs",
+                "@@ -1 +1,4 @@\n+#[cfg(test)]\n+mod tests {\n+}",
+            ),
+            deleted,
+        ];
+        let impact = analyze_test_impact(&files);
+        assert_eq!(impact.covered, vec!["src/util.rs"]);
+        assert!(impact.untested.is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+//! Token budget for the PR context embedded in prompts.
+//!
+//! Besides the diff, prompts carry commit messages, best practices, repo
+//! metadata, retrieved repository snippets and, for `/ask`, full file
+//! contents and symbol definitions. Each of these is capped on its own
+//! (`max_*_tokens`), and when a rendered prompt still overflows the model's
+//! context window, they are shortened in `config.prompt_trim_order` before
+//! the diff is re-compressed.
+
+use std::collections::HashMap;
+
+use minijinja::Value;
+
+use crate::ai::token::{Tokenizer, clip_tokens};
+use crate::config::types::GlobalConfig;
+
+/// A trimmable part of the prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptComponent {
+    Diff,
+    CommitMessages,
+    BestPractices,
+    RepoMetadata,
+    FullFiles,
+    RepoContext,
+    SymbolDefinitions,
+}
+
+impl PromptComponent {
+    pub const ALL: [PromptComponent; 7] = [
+        PromptComponent::Diff,
+        PromptComponent::CommitMessages,
+        PromptComponent::BestPractices,
+        PromptComponent::RepoMetadata,
+        PromptComponent::FullFiles,
+        PromptComponent::RepoContext,
+        PromptComponent::SymbolDefinitions,
+    ];
+
+    /// Name used in `config.prompt_trim_order`.
+    pub fn name(self) -> &'static str {
+        match self {
+            PromptComponent::Diff => "diff",
+            PromptComponent::CommitMessages => "commit_messages",
+            PromptComponent::BestPractices => "best_practices",
+            PromptComponent::RepoMetadata => "repo_metadata",
+            PromptComponent::FullFiles => "full_files",
+            PromptComponent::RepoContext => "repo_context",
+            PromptComponent::SymbolDefinitions => "symbol_definitions",
+        }
+    }
+
+    /// Human-readable label for notices.
+    pub fn label(self) -> &'static str {
+        match self {
+            PromptComponent::Diff => "diff",
+            PromptComponent::CommitMessages => "commit messages",
+            PromptComponent::BestPractices => "best practices",
+            PromptComponent::RepoMetadata => "repo metadata",
+            PromptComponent::FullFiles => "full file contents",
+            PromptComponent::RepoContext => "repository context",
+            PromptComponent::SymbolDefinitions => "symbol definitions",
+        }
+    }
+
+    /// Template variable holding the component.
+    pub fn var_name(self) -> &'static str {
+        match self {
+            PromptComponent::Diff => "diff",
+            PromptComponent::CommitMessages => "commit_messages_str",
+            PromptComponent::BestPractices => "best_practices_content",
+            PromptComponent::RepoMetadata => "repo_metadata",
+            PromptComponent::FullFiles => "full_files",
+            PromptComponent::RepoContext => "repo_context",
+            PromptComponent::SymbolDefinitions => "symbol_definitions",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name.trim())
+    }
+
+    /// Configured cap for the component; `None` for the diff, which is
+    /// budgeted by the compression pipeline, and for a cap of 0.
+    pub fn max_tokens(self, config: &GlobalConfig) -> Option<u32> {
+        let max = match self {
+            PromptComponent::Diff => return None,
+            PromptComponent::CommitMessages => config.max_commits_tokens,
+            PromptComponent::BestPractices => config.max_best_practices_tokens,
+            PromptComponent::RepoMetadata => config.max_repo_metadata_tokens,
+            PromptComponent::FullFiles => config.max_full_files_tokens,
+            PromptComponent::RepoContext => config.max_repo_context_tokens,
+            PromptComponent::SymbolDefinitions => config.max_symbol_definitions_tokens,
+        };
+        (max > 0).then_some(max)
+    }
+}
+
+/// Clip `text` to the component's configured cap.
+pub fn cap_component(component: PromptComponent, text: &str, config: &GlobalConfig) -> String {
+    match component.max_tokens(config) {
+        Some(max) => {
+            let clipped = clip_tokens(text, max, true);
+            if clipped.len() < text.len() {
+                tracing::debug!(
+                    component = component.name(),
+                    max,
+                    "clipped prompt component"
+                );
+            }
+            clipped
+        }
+        None => text.to_string(),
+    }
+}
+
+/// Components in the order they give up tokens, from `config.prompt_trim_order`.
+///
+/// Unknown names are skipped with a warning. The diff is appended when
+/// missing, so there is always a last resort.
+pub fn trim_order(config: &GlobalConfig) -> Vec<PromptComponent> {
+    let mut order = Vec::new();
+    for name in &config.prompt_trim_order {
+        match PromptComponent::from_name(name) {
+            Some(component) if !order.contains(&component) => order.push(component),
+            Some(_) => {}
+            None => tracing::warn!(name, "unknown component in config.prompt_trim_order"),
+        }
+    }
+    if !order.contains(&PromptComponent::Diff) {
+        order.push(PromptComponent::Diff);
+    }
+    order
+}
+
+/// Token count of each component present in `vars`.
+#[allow(dead_code)]
+pub fn measure(vars: &HashMap<String, Value>, tokenizer: Tokenizer) -> Vec<(PromptComponent, u32)> {
+    PromptComponent::ALL
+        .into_iter()
+        .filter_map(|c| {
+            let text = vars.get(c.var_name())?.as_str()?;
+            Some((c, tokenizer.count(text)))
+        })
+        .collect()
+}
+
+/// Result of [`trim_to_fit`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TrimOutcome {
+    /// Components that were shortened, in trim order.
+    pub trimmed: Vec<PromptComponent>,
+    /// Tokens still to free; the diff has to give these up.
+    pub remaining: u32,
+}
+
+/// Shorten components of `vars` in `order` until `excess` tokens are freed.
+///
+/// Stops at the diff: it is re-compressed by the caller, which reserves room
+/// for everything else, so components listed after it are kept whole.
+pub fn trim_to_fit(
+    vars: &mut HashMap<String, Value>,
+    excess: u32,
+    order: &[PromptComponent],
+    tokenizer: Tokenizer,
+) -> TrimOutcome {
+    let mut outcome = TrimOutcome {
+        trimmed: Vec::new(),
+        remaining: excess,
+    };
+    for &component in order {
+        if outcome.remaining == 0 || component == PromptComponent::Diff {
+            break;
+        }
+        let Some(text) = vars.get(component.var_name()).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let tokens = tokenizer.count(text);
+        if tokens == 0 {
+            continue;
+        }
+        let shortened = tokenizer.clip(text, tokens.saturating_sub(outcome.remaining), true);
+        let freed = tokens.saturating_sub(tokenizer.count(&shortened));
+        if freed == 0 {
+            continue;
+        }
+        tracing::info!(
+            component = component.name(),
+            tokens,
+            freed,
+            "trimmed prompt component to fit context window"
+        );
+        vars.insert(component.var_name().into(), Value::from(shortened));
+        outcome.trimmed.push(component);
+        outcome.remaining = outcome.remaining.saturating_sub(freed);
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::token::count_tokens;
+
+    fn vars(commits: &str, best_practices: &str) -> HashMap<String, Value> {
+        HashMap::from([
+            ("diff".to_string(), Value::from("+fn main() {}")),
+            ("commit_messages_str".to_string(), Value::from(commits)),
+            (
+                "best_practices_content".to_string(),
+                Value::from(best_practices),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_trim_order_from_config() {
+        let config = GlobalConfig {
+            prompt_trim_order: vec![
+                "best_practices".into(),
+                "bogus".into(),
+                "best_practices".into(),
+            ],
+            ..GlobalConfig::default()
+        };
+        assert_eq!(
+            trim_order(&config),
+            vec![PromptComponent::BestPractices, PromptComponent::Diff]
+        );
+        assert_eq!(
+            trim_order(&GlobalConfig::default()),
+            vec![
+                PromptComponent::FullFiles,
+                PromptComponent::SymbolDefinitions,
+                PromptComponent::RepoContext,
+                PromptComponent::RepoMetadata,
+                PromptComponent::BestPractices,
+                PromptComponent::CommitMessages,
+                PromptComponent::Diff,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cap_component() {
+        let config = GlobalConfig {
+            max_commits_tokens: 10,
+            max_best_practices_tokens: 0,
+            ..GlobalConfig::default()
+        };
+        let long = "1. fix the parser edge case\n".repeat(50);
+        let capped = cap_component(PromptComponent::CommitMessages, &long, &config);
+        assert!(capped.ends_with("...(truncated)"));
+        assert!(count_tokens(&capped) < 20);
+        // A cap of 0 means no limit.
+        assert_eq!(
+            cap_component(PromptComponent::BestPractices, &long, &config),
+            long
+        );
+    }
+
+    #[test]
+    fn test_trim_to_fit_follows_order_and_stops_at_diff() {
+        let commits = "1. refactor the module layout\n".repeat(40);
+        let best_practices = "- Prefer small functions.\n".repeat(40);
+        let mut vars = vars(&commits, &best_practices);
+        let before = measure(&vars, Tokenizer::O200k);
+        let bp_tokens = before
+            .iter()
+            .find(|(c, _)| *c == PromptComponent::BestPractices)
+            .unwrap()
+            .1;
+
+        let order = [
+            PromptComponent::BestPractices,
+            PromptComponent::Diff,
+            PromptComponent::CommitMessages,
+        ];
+        let outcome = trim_to_fit(&mut vars, 50, &order, Tokenizer::O200k);
+        assert_eq!(outcome.trimmed, vec![PromptComponent::BestPractices]);
+        assert_eq!(outcome.remaining, 0);
+        let bp = vars["best_practices_content"].as_str().unwrap();
+        assert!(count_tokens(bp) < bp_tokens);
+        assert_eq!(vars["commit_messages_str"].as_str().unwrap(), commits);
+
+        // More than best practices can give: the rest falls to the diff,
+        // and commit messages (after the diff) stay whole.
+        let mut vars = self::vars(&commits, &best_practices);
+        let outcome = trim_to_fit(&mut vars, 10_000, &order, Tokenizer::O200k);
+        assert!(outcome.remaining > 0);
+        assert_eq!(vars["best_practices_content"].as_str().unwrap(), "");
+        assert_eq!(vars["commit_messages_str"].as_str().unwrap(), commits);
+        assert_eq!(vars["diff"].as_str().unwrap(), "+fn main() {}");
+    }
+}
@@ -0,0 +1,58 @@
+//! Correlation ids for webhook events and CLI runs.
+//!
+//! Each unit of work runs inside [`scope`], which opens a `request` span
+//! carrying its `request_id`. Tool runs, git provider and AI calls open
+//! their spans beneath it, so every log line and exported trace of a run
+//! shares the id. [`current_id`] reads it back for error comments and
+//! metric exemplars.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::Instrument;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// A new id, unique within the process and sortable by creation time.
+pub fn new_id() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    let seq = NEXT_SEQ.fetch_add(1, Ordering::Relaxed);
+    format!("{millis:013}-{seq:06}")
+}
+
+/// Run `fut` as the request `id`; `kind` says where it came from
+/// (`webhook` or `cli`).
+pub async fn scope<F: Future>(id: String, kind: &'static str, fut: F) -> F::Output {
+    let span = tracing::info_span!("request", request_id = %id, kind);
+    REQUEST_ID.scope(id, fut.instrument(span)).await
+}
+
+/// Id of the request the current task is running, if any.
+pub fn current_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope_sets_current_id() {
+        assert_eq!(current_id(), None);
+        let id = new_id();
+        let seen = scope(id.clone(), "cli", async { current_id() }).await;
+        assert_eq!(seen, Some(id));
+        assert_eq!(current_id(), None);
+    }
+
+    #[test]
+    fn test_new_ids_are_unique() {
+        assert_ne!(new_id(), new_id());
+    }
+}
@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use axum::extract::{Path as UrlPath, Query};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+
+use crate::analytics::{load_describe_record, load_latest_describe_record};
+use crate::config::loader::get_settings;
+
+/// GET /api/v1/describe/{owner}/{repo}/{pr_number}[?sha=<head_sha>]
+///
+/// Returns the persisted `/describe` result for a PR as JSON. Without `sha`,
+/// the most recent record is returned.
+pub async fn get_describe_record(
+    UrlPath((owner, repo, pr_number)): UrlPath<(String, String, u64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let settings = get_settings();
+    let folder = &settings.config.analytics_folder;
+    if folder.is_empty() {
+        return (
+            StatusCode::NOT_FOUND,
+            axum::Json(serde_json::json!({"error": "analytics store is disabled"})),
+        );
+    }
+
+    let repo_full = format!("{owner}/{repo}");
+    let result = match params.get("sha") {
+        Some(sha) => load_describe_record(Path::new(folder), &repo_full, pr_number, sha),
+        None => load_latest_describe_record(Path::new(folder), &repo_full, pr_number),
+    };
+
+    match result {
+        Ok(Some(record)) => (StatusCode::OK, axum::Json(serde_json::json!(record))),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            axum::Json(serde_json::json!({"error": "no describe record found"})),
+        ),
+        Err(e) => {
+            tracing::error!(error = %e, repo = %repo_full, pr_number, "failed to load describe record");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(serde_json::json!({"error": "failed to load describe record"})),
+            )
+        }
+    }
+}
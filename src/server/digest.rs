@@ -0,0 +1,395 @@
+//! Weekly PR activity digest for server mode (`[digest]`).
+//!
+//! Once a week, at `digest.weekday` and `digest.hour_utc`, the analytics
+//! store is summarized per repository: PRs merged in the past seven days,
+//! their average time to merge, and the findings of the latest `/review` on
+//! each open PR that were not yet resolved with `/resolve` or `/wontfix`.
+//! The digest is posted as a comment on `digest.issue_url` and to Slack at
+//! `digest.slack_webhook_url`.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Days, Utc, Weekday};
+
+use crate::analytics::{
+    ClosedPrRecord, load_closed_prs, load_finding_resolutions, load_review_records,
+};
+use crate::config::loader::get_settings;
+use crate::config::types::{DigestConfig, Settings};
+use crate::error::PrAgentError;
+use crate::git::create_provider;
+use crate::notify::slack;
+use crate::util::http_client_builder;
+
+/// Longest wait before the schedule is read again, so a settings reload
+/// that moves the digest takes effect.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Activity of one repository over the digest period.
+#[derive(Debug)]
+struct RepoDigest {
+    repo: String,
+    /// PRs merged in the period, oldest first.
+    merged: Vec<ClosedPrRecord>,
+    /// `(pr_number, unresolved findings)` of open PRs, by PR number.
+    open_findings: Vec<(u64, usize)>,
+}
+
+impl RepoDigest {
+    fn average_time_to_merge(&self) -> Option<f64> {
+        if self.merged.is_empty() {
+            return None;
+        }
+        let total: f64 = self.merged.iter().map(|pr| pr.time_to_merge_hours).sum();
+        Some(total / self.merged.len() as f64)
+    }
+
+    fn open_findings_total(&self) -> usize {
+        self.open_findings.iter().map(|(_, n)| n).sum()
+    }
+}
+
+/// Start the digest loop in the background. It runs for the life of the
+/// server and posts only while `digest.enabled` is set.
+pub fn spawn() {
+    tokio::spawn(async {
+        loop {
+            let now = Utc::now();
+            let at = next_run(now, &get_settings().digest);
+            let wait = (at - now).to_std().unwrap_or_default();
+            if wait > RECHECK_INTERVAL {
+                tokio::time::sleep(RECHECK_INTERVAL).await;
+                continue;
+            }
+            tokio::time::sleep(wait).await;
+            let settings = get_settings();
+            if settings.digest.enabled {
+                post(&settings, at).await;
+            }
+        }
+    });
+}
+
+/// The next time after `now` the digest is due.
+fn next_run(now: DateTime<Utc>, config: &DigestConfig) -> DateTime<Utc> {
+    let weekday = config.weekday.parse::<Weekday>().unwrap_or_else(|_| {
+        tracing::warn!(weekday = %config.weekday, "invalid digest.weekday, using Monday");
+        Weekday::Mon
+    });
+    let days_ahead =
+        (7 + weekday.num_days_from_monday() - now.weekday().num_days_from_monday()) % 7;
+    let at = (now.date_naive() + Days::new(days_ahead.into()))
+        .and_hms_opt(config.hour_utc.min(23), 0, 0)
+        .expect("hour is below 24")
+        .and_utc();
+    if at <= now { at + Days::new(7) } else { at }
+}
+
+/// Build the digest for the week up to `until` and post it to every
+/// configured target. Failures are logged, never returned.
+async fn post(settings: &Settings, until: DateTime<Utc>) {
+    let folder = &settings.config.analytics_folder;
+    if folder.is_empty() {
+        tracing::warn!("digest is enabled but config.analytics_folder is empty, skipping");
+        return;
+    }
+    let since = until - Days::new(7);
+    let digests = match collect(Path::new(folder), &settings.digest.repos, since) {
+        Ok(digests) => digests,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to read the analytics store for the digest");
+            return;
+        }
+    };
+    let config = &settings.digest;
+    if !config.issue_url.is_empty() {
+        let body = to_markdown(&digests, since, until);
+        let published = async {
+            create_provider(&config.issue_url)
+                .await?
+                .publish_comment(&body, false)
+                .await
+        };
+        if let Err(e) = published.await {
+            tracing::warn!(error = %e, "failed to post the digest to the issue");
+        }
+    }
+    if !config.slack_webhook_url.is_empty()
+        && let Err(e) = post_to_slack(settings, &to_slack(&digests, since, until)).await
+    {
+        tracing::warn!(error = %e, "failed to post the digest to Slack");
+    }
+    tracing::info!(repos = digests.len(), "sent weekly digest");
+}
+
+async fn post_to_slack(settings: &Settings, text: &str) -> Result<(), PrAgentError> {
+    http_client_builder(&settings.http, &HashMap::new())?
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(PrAgentError::Http)?
+        .post(settings.digest.slack_webhook_url.as_str())
+        .json(&serde_json::json!({ "text": text }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(PrAgentError::Http)?;
+    Ok(())
+}
+
+/// Per-repository activity since `since`, for `repos` or, when empty, every
+/// repository in the store.
+fn collect(
+    folder: &Path,
+    repos: &[String],
+    since: DateTime<Utc>,
+) -> Result<Vec<RepoDigest>, PrAgentError> {
+    let closed = load_closed_prs(folder)?;
+    let reviews = load_review_records(folder)?;
+    let repos: BTreeSet<&str> = if repos.is_empty() {
+        closed
+            .iter()
+            .map(|pr| pr.repo.as_str())
+            .chain(reviews.iter().map(|r| r.repo.as_str()))
+            .collect()
+    } else {
+        repos.iter().map(String::as_str).collect()
+    };
+
+    let mut digests = Vec::new();
+    for repo in repos {
+        let mut merged: Vec<ClosedPrRecord> = closed
+            .iter()
+            .filter(|pr| pr.repo == repo && pr.merged)
+            .filter(|pr| parse_time(&pr.closed_at).is_some_and(|t| t >= since))
+            .cloned()
+            .collect();
+        merged.sort_by(|a, b| a.closed_at.cmp(&b.closed_at));
+
+        let closed_prs: HashSet<u64> = closed
+            .iter()
+            .filter(|pr| pr.repo == repo)
+            .map(|pr| pr.pr_number)
+            .collect();
+        let mut open_findings = Vec::new();
+        for review in reviews
+            .iter()
+            .filter(|r| r.repo == repo && !closed_prs.contains(&r.pr_number))
+        {
+            // Verdicts given before this review refer to an earlier one's numbering.
+            let reviewed_at = parse_time(&review.created_at);
+            let resolved = load_finding_resolutions(folder, repo, review.pr_number)?
+                .iter()
+                .filter(|r| r.finding <= review.findings.len())
+                .filter(|r| parse_time(&r.created_at) >= reviewed_at)
+                .count();
+            let open = review.findings.len().saturating_sub(resolved);
+            if open > 0 {
+                open_findings.push((review.pr_number, open));
+            }
+        }
+        open_findings.sort_unstable();
+
+        digests.push(RepoDigest {
+            repo: repo.to_string(),
+            merged,
+            open_findings,
+        });
+    }
+    Ok(digests)
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn period(since: DateTime<Utc>, until: DateTime<Utc>) -> String {
+    format!(
+        "{} to {}",
+        since.format("%Y-%m-%d"),
+        until.format("%Y-%m-%d")
+    )
+}
+
+fn plural(n: usize, word: &str) -> String {
+    if n == 1 {
+        format!("1 {word}")
+    } else {
+        format!("{n} {word}s")
+    }
+}
+
+/// One line of totals for a repository, e.g. "2 merged PRs (avg 5.5 h to
+/// merge), 3 open findings in 1 PR".
+fn totals(digest: &RepoDigest) -> String {
+    let mut line = plural(digest.merged.len(), "merged PR");
+    if let Some(hours) = digest.average_time_to_merge() {
+        let _ = write!(line, " (avg {hours:.1} h to merge)");
+    }
+    let _ = write!(
+        line,
+        ", {} in {}",
+        plural(digest.open_findings_total(), "open finding"),
+        plural(digest.open_findings.len(), "PR")
+    );
+    line
+}
+
+/// The digest as a markdown comment.
+fn to_markdown(digests: &[RepoDigest], since: DateTime<Utc>, until: DateTime<Utc>) -> String {
+    let mut out = format!("## Weekly PR digest\n\n_{}_\n", period(since, until));
+    if digests.is_empty() {
+        out.push_str("\nNo PR activity recorded.\n");
+    }
+    for digest in digests {
+        let _ = write!(out, "\n### {}\n\n{}\n", digest.repo, totals(digest));
+        if !digest.merged.is_empty() {
+            out.push_str("\n**Merged**\n");
+            for pr in &digest.merged {
+                let _ = write!(out, "- [#{} {}]({})", pr.pr_number, pr.title, pr.url);
+                if !pr.merged_by.is_empty() {
+                    let _ = write!(out, " by @{}", pr.merged_by);
+                }
+                let _ = writeln!(out, ", {:.1} h", pr.time_to_merge_hours);
+            }
+        }
+        if !digest.open_findings.is_empty() {
+            out.push_str("\n**Open review findings**\n");
+            for (pr_number, open) in &digest.open_findings {
+                let _ = writeln!(out, "- #{pr_number}: {}", plural(*open, "finding"));
+            }
+        }
+    }
+    out
+}
+
+/// The digest as a Slack message: one line of totals per repository.
+fn to_slack(digests: &[RepoDigest], since: DateTime<Utc>, until: DateTime<Utc>) -> String {
+    let mut out = format!("*Weekly PR digest* ({})", period(since, until));
+    if digests.is_empty() {
+        out.push_str("\nNo PR activity recorded.");
+    }
+    for digest in digests {
+        let _ = write!(
+            out,
+            "\n*{}*: {}",
+            slack::escape(&digest.repo),
+            totals(digest)
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::{
+        FindingResolutionRecord, FindingStatus, ReviewRecord, save_closed_pr,
+        save_finding_resolution, save_review_record,
+    };
+
+    fn time(value: &str) -> DateTime<Utc> {
+        parse_time(value).unwrap()
+    }
+
+    fn merged(repo: &str, pr_number: u64, closed_at: &str, hours: f64) -> ClosedPrRecord {
+        ClosedPrRecord {
+            repo: repo.into(),
+            pr_number,
+            title: format!("PR {pr_number}"),
+            url: format!("https://github.com/{repo}/pull/{pr_number}"),
+            merged: true,
+            merged_by: "alice".into(),
+            additions: 1,
+            deletions: 1,
+            time_to_merge_hours: hours,
+            closed_at: closed_at.into(),
+        }
+    }
+
+    #[test]
+    fn test_next_run() {
+        let config = DigestConfig::default(); // Monday 09:00 UTC
+        // Wednesday
+        let now = time("2026-01-07T12:00:00Z");
+        assert_eq!(next_run(now, &config), time("2026-01-12T09:00:00Z"));
+        // Monday before and at the hour
+        let monday = time("2026-01-12T08:30:00Z");
+        assert_eq!(next_run(monday, &config), time("2026-01-12T09:00:00Z"));
+        let due = time("2026-01-12T09:00:00Z");
+        assert_eq!(next_run(due, &config), time("2026-01-19T09:00:00Z"));
+
+        let friday = DigestConfig {
+            weekday: "Friday".into(),
+            hour_utc: 17,
+            ..Default::default()
+        };
+        assert_eq!(next_run(now, &friday), time("2026-01-09T17:00:00Z"));
+    }
+
+    #[test]
+    fn test_collect_and_render() {
+        let folder = std::env::temp_dir().join(format!("pr-agent-digest-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        let since = time("2026-01-05T09:00:00Z");
+        save_closed_pr(&folder, &merged("o/r", 1, "2026-01-06T10:00:00Z", 4.0)).unwrap();
+        save_closed_pr(&folder, &merged("o/r", 2, "2026-01-08T10:00:00Z", 7.0)).unwrap();
+        // Merged before the period
+        save_closed_pr(&folder, &merged("o/r", 3, "2026-01-01T10:00:00Z", 1.0)).unwrap();
+        save_closed_pr(&folder, &merged("o/other", 9, "2026-01-06T10:00:00Z", 1.0)).unwrap();
+
+        let review = |pr_number: u64, findings: usize| ReviewRecord {
+            repo: "o/r".into(),
+            pr_number,
+            head_sha: "abc".into(),
+            findings: vec!["Possible Bug".to_string(); findings],
+            created_at: "2026-01-07T00:00:00Z".into(),
+        };
+        save_review_record(&folder, &review(4, 3)).unwrap();
+        // Closed PRs have no open findings
+        save_review_record(&folder, &review(1, 2)).unwrap();
+        let resolution = |finding: usize, created_at: &str| FindingResolutionRecord {
+            repo: "o/r".into(),
+            pr_number: 4,
+            finding,
+            header: "Possible Bug".into(),
+            status: FindingStatus::Resolved,
+            reason: String::new(),
+            user: "bob".into(),
+            created_at: created_at.into(),
+        };
+        save_finding_resolution(&folder, &resolution(1, "2026-01-08T00:00:00Z")).unwrap();
+        // Given on an earlier review
+        save_finding_resolution(&folder, &resolution(2, "2026-01-06T00:00:00Z")).unwrap();
+
+        let digests = collect(&folder, &["o/r".to_string()], since).unwrap();
+        assert_eq!(digests.len(), 1);
+        let digest = &digests[0];
+        let numbers: Vec<u64> = digest.merged.iter().map(|pr| pr.pr_number).collect();
+        assert_eq!(numbers, vec![1, 2]);
+        assert_eq!(digest.average_time_to_merge(), Some(5.5));
+        assert_eq!(digest.open_findings, vec![(4, 2)]);
+
+        let until = since + Days::new(7);
+        let markdown = to_markdown(&digests, since, until);
+        assert!(markdown.starts_with("## Weekly PR digest\n\n_2026-01-05 to 2026-01-12_\n"));
+        assert!(markdown.contains("2 merged PRs (avg 5.5 h to merge), 2 open findings in 1 PR"));
+        assert!(markdown.contains("- [#1 PR 1](https://github.com/o/r/pull/1) by @alice, 4.0 h"));
+        assert!(markdown.contains("- #4: 2 findings"));
+        assert_eq!(
+            to_slack(&digests, since, until),
+            "*Weekly PR digest* (2026-01-05 to 2026-01-12)\n*o/r*: 2 merged PRs (avg 5.5 h to merge), 2 open findings in 1 PR"
+        );
+
+        let all = collect(&folder, &[], since).unwrap();
+        let repos: Vec<&str> = all.iter().map(|d| d.repo.as_str()).collect();
+        assert_eq!(repos, vec!["o/other", "o/r"]);
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+}
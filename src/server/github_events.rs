@@ -0,0 +1,289 @@
+//! Typed GitHub webhook payloads.
+//!
+//! Only the fields the webhook handler reads are modeled. Missing fields take
+//! their defaults, so partial payloads still parse, and the rest of each
+//! event is kept in `extra` for code that needs fields not listed here.
+
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+/// `pull_request` event.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PullRequestEvent {
+    pub action: String,
+    pub pull_request: PullRequest,
+    pub repository: Repository,
+    pub sender: User,
+    /// Head SHA before a `synchronize` push.
+    pub before: Option<String>,
+    /// Head SHA after a `synchronize` push.
+    pub after: Option<String>,
+    #[serde(flatten)]
+    #[allow(dead_code)]
+    pub extra: Map<String, Value>,
+}
+
+/// `issue_comment` event. GitHub sends it for PR conversation comments too.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct IssueCommentEvent {
+    pub action: String,
+    pub issue: Issue,
+    pub comment: Comment,
+    /// Previous values, on `edited`.
+    pub changes: Option<Changes>,
+    pub repository: Repository,
+    pub sender: User,
+    #[serde(flatten)]
+    #[allow(dead_code)]
+    pub extra: Map<String, Value>,
+}
+
+impl IssueCommentEvent {
+    /// The comment body before an edit.
+    pub fn previous_body(&self) -> Option<&str> {
+        self.changes
+            .as_ref()?
+            .body
+            .as_ref()
+            .map(|b| b.from.as_str())
+    }
+}
+
+/// `pull_request_review_comment` event: a comment on lines of the diff.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PullRequestReviewCommentEvent {
+    pub action: String,
+    pub comment: Comment,
+    pub pull_request: PullRequest,
+    pub repository: Repository,
+    pub sender: User,
+    #[serde(flatten)]
+    #[allow(dead_code)]
+    pub extra: Map<String, Value>,
+}
+
+/// `check_run` event.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CheckRunEvent {
+    pub action: String,
+    pub check_run: CheckRun,
+    pub repository: Repository,
+    pub sender: User,
+    #[serde(flatten)]
+    #[allow(dead_code)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PullRequest {
+    /// API URL.
+    pub url: String,
+    pub html_url: Option<String>,
+    pub number: u64,
+    pub title: String,
+    pub state: String,
+    pub draft: bool,
+    #[serde(deserialize_with = "nullable")]
+    pub user: User,
+    pub labels: Vec<Label>,
+    pub head: Branch,
+    pub base: Branch,
+    pub created_at: String,
+    pub updated_at: String,
+    pub closed_at: Option<String>,
+    pub merged: bool,
+    pub merged_at: Option<String>,
+    pub merged_by: Option<User>,
+    pub merge_commit_sha: Option<String>,
+    pub commits: u64,
+    pub additions: u64,
+    pub deletions: u64,
+    pub changed_files: u64,
+    pub comments: u64,
+    pub review_comments: u64,
+    pub requested_reviewers: Vec<User>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Issue {
+    pub html_url: Option<String>,
+    #[serde(deserialize_with = "nullable")]
+    pub user: User,
+    /// Set when the issue is a pull request.
+    pub pull_request: Option<IssuePullRequest>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct IssuePullRequest {
+    pub html_url: Option<String>,
+}
+
+/// A conversation comment or a review comment. The line fields are only
+/// set on review comments.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Comment {
+    pub id: u64,
+    #[serde(deserialize_with = "nullable")]
+    pub body: String,
+    #[serde(deserialize_with = "nullable")]
+    pub user: User,
+    /// `OWNER`, `MEMBER`, `COLLABORATOR`, `CONTRIBUTOR`, `NONE`, …
+    pub author_association: String,
+    /// `line` or `file`.
+    pub subject_type: Option<String>,
+    /// API URL of the PR.
+    pub pull_request_url: Option<String>,
+    pub diff_hunk: Option<String>,
+    pub path: String,
+    pub line: Option<u64>,
+    pub start_line: Option<u64>,
+    /// `LEFT` or `RIGHT`.
+    pub side: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Changes {
+    pub body: Option<PreviousValue>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PreviousValue {
+    pub from: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CheckRun {
+    /// Set by the app that created the check; pr-agent stores the command.
+    #[serde(deserialize_with = "nullable")]
+    pub external_id: String,
+    pub app: Option<App>,
+    pub pull_requests: Vec<CheckRunPullRequest>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CheckRunPullRequest {
+    pub number: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct App {
+    pub id: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Repository {
+    /// `owner/name`.
+    pub full_name: String,
+    pub html_url: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct User {
+    pub login: String,
+    /// `User`, `Bot` or `Organization`.
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Label {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Branch {
+    #[serde(rename = "ref")]
+    pub name: String,
+    pub sha: String,
+}
+
+/// Read `null` like a missing field (deleted users, empty bodies).
+fn nullable<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pull_request_event() {
+        let event: PullRequestEvent = serde_json::from_value(serde_json::json!({
+            "action": "closed",
+            "number": 7,
+            "pull_request": {
+                "html_url": "https://github.com/o/r/pull/7",
+                "title": "Fix",
+                "state": "closed",
+                "user": null,
+                "labels": [{ "name": "bug", "color": "red" }],
+                "head": { "ref": "feature", "sha": "abc" },
+                "merged": true,
+                "merged_by": { "login": "alice", "type": "User" },
+                "merge_commit_sha": null,
+                "additions": 3,
+            },
+            "repository": { "full_name": "o/r" },
+            "sender": { "login": "app[bot]", "type": "Bot" },
+            "installation": { "id": 99 },
+        }))
+        .unwrap();
+
+        let pr = &event.pull_request;
+        assert_eq!(
+            pr.html_url.as_deref(),
+            Some("https://github.com/o/r/pull/7")
+        );
+        assert_eq!(pr.user.login, "");
+        assert_eq!(pr.labels[0].name, "bug");
+        assert_eq!(pr.head.name, "feature");
+        assert_eq!(pr.merged_by.as_ref().unwrap().login, "alice");
+        assert_eq!(pr.merge_commit_sha, None);
+        assert_eq!((pr.additions, pr.deletions), (3, 0));
+        assert_eq!(event.sender.kind, "Bot");
+        assert_eq!(event.before, None);
+        // Unmodeled fields stay available
+        assert_eq!(event.extra["number"], 7);
+        assert_eq!(event.extra["installation"]["id"], 99);
+    }
+
+    #[test]
+    fn test_parse_issue_comment_event() {
+        let event: IssueCommentEvent = serde_json::from_value(serde_json::json!({
+            "action": "edited",
+            "issue": { "user": { "login": "author" }, "pull_request": {} },
+            "comment": { "id": 5, "body": null, "user": { "login": "bob" } },
+            "changes": { "body": { "from": "old" } },
+        }))
+        .unwrap();
+        assert!(event.issue.pull_request.is_some());
+        assert_eq!(event.comment.body, "");
+        assert_eq!(event.previous_body(), Some("old"));
+
+        let plain: IssueCommentEvent =
+            serde_json::from_value(serde_json::json!({ "issue": { "user": null } })).unwrap();
+        assert!(plain.issue.pull_request.is_none());
+        assert_eq!(plain.previous_body(), None);
+    }
+}
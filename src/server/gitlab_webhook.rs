@@ -0,0 +1,508 @@
+use axum::body::Bytes;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+
+use super::job_queue::{self, Job, JobSource};
+use super::webhook::{
+    PrFilterFields, ack_reaction, draft_pr_commands, enqueue_response, fetch_scoped_settings,
+    finish_reaction, is_ignored_pr, run_commands, run_tool,
+};
+use super::{permissions, rate_limit};
+use crate::config::loader::get_settings;
+use crate::config::types::Settings;
+use crate::error::PrAgentError;
+use crate::git::{GitProvider, create_provider};
+use crate::tools;
+
+/// GitLab webhook handler: POST /api/v1/gitlab_webhooks
+///
+/// Steps:
+/// 1. Verify the `X-Gitlab-Token` header against `gitlab.shared_secret`
+/// 2. Parse the event kind (`merge_request` or `note`)
+/// 3. Queue the event for the worker pool, which runs the shared command runner
+/// 4. Return 200 immediately (503 if the queue is full)
+pub async fn handle_gitlab_webhook(headers: HeaderMap, body: Bytes) -> impl IntoResponse {
+    // 1. Verify token
+    let settings = get_settings();
+    let secret = &settings.gitlab.shared_secret;
+
+    if secret.is_empty() {
+        tracing::error!("gitlab.shared_secret is not configured — rejecting request for safety");
+        return (StatusCode::FORBIDDEN, "webhook secret not configured").into_response();
+    }
+
+    let token = headers
+        .get("x-gitlab-token")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if !verify_token(token, secret) {
+        tracing::warn!("GitLab webhook token verification failed");
+        return (StatusCode::FORBIDDEN, "token verification failed").into_response();
+    }
+
+    // 2. Parse body and event kind
+    let payload: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to parse GitLab webhook payload");
+            return (StatusCode::BAD_REQUEST, "invalid JSON").into_response();
+        }
+    };
+
+    let kind = payload["object_kind"].as_str().unwrap_or("").to_string();
+    let action = payload["object_attributes"]["action"]
+        .as_str()
+        .unwrap_or("")
+        .to_string();
+
+    tracing::info!(kind = %kind, action = %action, "received GitLab webhook");
+
+    // 3. Queue for processing
+    let job = Job {
+        source: JobSource::Gitlab,
+        event: kind,
+        action,
+        payload,
+    };
+
+    // 4. Return immediately
+    enqueue_response(job_queue::global().enqueue(job))
+}
+
+/// Compare the received token with the configured secret in constant time.
+fn verify_token(token: &str, secret: &str) -> bool {
+    let (a, b) = (token.as_bytes(), secret.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Route GitLab events to the same command dispatch as GitHub webhooks.
+pub(super) async fn dispatch_event(
+    kind: &str,
+    action: &str,
+    payload: &serde_json::Value,
+) -> Result<(), PrAgentError> {
+    let settings = get_settings();
+
+    match kind {
+        "merge_request" => {
+            let mr_url = extract_mr_url(payload)?;
+
+            let username = payload["user"]["username"].as_str().unwrap_or("");
+            if settings.gitlab.ignore_bot_pr && is_bot_username(username) {
+                tracing::info!(username, "ignoring MR event from bot user");
+                return Ok(());
+            }
+
+            // Check all ignore filters (title, author, repo, labels, branches)
+            let labels = mr_labels(payload);
+            if is_ignored_pr(&settings, &mr_filter_fields(payload, &labels)) {
+                return Ok(());
+            }
+
+            let is_draft = is_draft_mr(payload);
+            match classify_mr_action(action, payload) {
+                trigger @ (MrTrigger::Opened | MrTrigger::Ready) => {
+                    if settings.config.disable_auto_feedback {
+                        tracing::info!(mr_url = %mr_url, "auto feedback is disabled, skipping pr_commands");
+                        return Ok(());
+                    }
+                    // `draft_pr_commands` speaks GitHub's action names.
+                    let github_action = if trigger == MrTrigger::Ready {
+                        "ready_for_review"
+                    } else {
+                        "opened"
+                    };
+                    let Some(commands) = draft_pr_commands(
+                        &settings,
+                        &settings.gitlab.pr_commands,
+                        github_action,
+                        is_draft,
+                    ) else {
+                        tracing::info!(mr_url = %mr_url, action, is_draft, "skipping MR event for draft handling mode");
+                        return Ok(());
+                    };
+
+                    tracing::info!(mr_url = %mr_url, action, "handling MR event");
+                    let quick_take = action == "open" && commands == settings.gitlab.pr_commands;
+                    run_commands(&mr_url, &commands, quick_take, false).await?;
+                }
+                MrTrigger::Pushed if settings.gitlab.handle_push_trigger => {
+                    let Some(commands) = draft_pr_commands(
+                        &settings,
+                        &settings.gitlab.push_commands,
+                        "synchronize",
+                        is_draft,
+                    ) else {
+                        tracing::info!(mr_url = %mr_url, "skipping push trigger on draft MR");
+                        return Ok(());
+                    };
+
+                    let _guard = match super::push_dedup::acquire_push_slot(&mr_url).await {
+                        Some(guard) => guard,
+                        None => {
+                            tracing::info!(mr_url = %mr_url, "push trigger deduplicated, skipping");
+                            return Ok(());
+                        }
+                    };
+
+                    tracing::info!(mr_url = %mr_url, "handling push trigger");
+                    run_commands(&mr_url, &commands, false, true).await?;
+                }
+                _ => {
+                    tracing::debug!(action, "ignoring merge_request action");
+                }
+            }
+        }
+        "note" => {
+            if payload["object_attributes"]["noteable_type"].as_str() != Some("MergeRequest") {
+                tracing::debug!("ignoring note on non-MR object");
+                return Ok(());
+            }
+
+            let comment_body = payload["object_attributes"]["note"]
+                .as_str()
+                .unwrap_or("")
+                .trim();
+            if !comment_body.starts_with('/') {
+                tracing::debug!("ignoring non-command note");
+                return Ok(());
+            }
+
+            let (command, mut args) = tools::parse_command(comment_body);
+            if !tools::is_known_command(&command) {
+                tracing::debug!(command, "ignoring unknown command from note");
+                return Ok(());
+            }
+
+            let mr_url = payload["merge_request"]["url"]
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| PrAgentError::Other("missing merge_request.url in note".into()))?;
+            tracing::info!(mr_url = %mr_url, command = comment_body, "handling note command");
+
+            let provider = create_provider(&mr_url).await?;
+            let scoped_settings = fetch_scoped_settings(provider.as_ref(), &settings).await;
+            let effective = scoped_settings.as_deref().unwrap_or(settings.as_ref());
+            if !authorize_note(provider.as_ref(), effective, &command, payload).await {
+                return Ok(());
+            }
+            let login = payload["user"]["username"].as_str().unwrap_or("");
+            if !rate_limit::check(provider.as_ref(), effective, login, &mr_url).await {
+                return Ok(());
+            }
+            if tools::is_finding_reply_command(&command) && !login.is_empty() {
+                args.insert("_author".to_string(), login.to_string());
+            }
+
+            let note_id = payload["object_attributes"]["id"].as_u64().unwrap_or(0);
+            let ack_id = ack_reaction(provider.as_ref(), note_id).await;
+            let result = run_tool(provider.clone(), scoped_settings, &command, &args).await;
+            finish_reaction(provider.as_ref(), note_id, ack_id, &result).await;
+            result?;
+        }
+        _ => {
+            tracing::debug!(kind, "ignoring unsupported GitLab event kind");
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the author of a note may run `command`: never the bot itself,
+/// `/resolve` and `/wontfix` only for project maintainers, everything under
+/// `[server.command_permissions]`. GitLab notes carry no association, so it
+/// is looked up from the author's project role.
+async fn authorize_note(
+    provider: &dyn GitProvider,
+    settings: &Settings,
+    command: &str,
+    payload: &serde_json::Value,
+) -> bool {
+    let user = &payload["user"];
+    let login = user["username"].as_str().unwrap_or("");
+    // The bot's own replies may start with `/`; never act on them.
+    match provider.get_user_id().await {
+        Ok(bot) if bot == login => {
+            tracing::debug!(login, "ignoring note written by the bot");
+            return false;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to look up the bot user, ignoring note");
+            return false;
+        }
+    }
+    let association = match provider.get_author_association(login).await {
+        Ok(association) => association,
+        Err(e) => {
+            tracing::warn!(login, error = %e, "failed to look up the note author's project role");
+            "NONE".to_string()
+        }
+    };
+    // `/resolve` and `/wontfix` change the review for everyone, so only
+    // project maintainers may use them.
+    if tools::is_finding_reply_command(command)
+        && !permissions::is_maintainer_association(&association)
+    {
+        tracing::info!(
+            command,
+            login,
+            association,
+            "ignoring finding reply from non-maintainer"
+        );
+        return false;
+    }
+    // The note hook names the MR author only by user id.
+    let is_mr_author = user["id"]
+        .as_u64()
+        .is_some_and(|id| payload["merge_request"]["author_id"].as_u64() == Some(id));
+    let pr_author = if is_mr_author { login } else { "" };
+    permissions::check_user(provider, settings, command, login, &association, pr_author).await
+}
+
+/// What a `merge_request` hook should trigger.
+#[derive(Debug, PartialEq, Eq)]
+enum MrTrigger {
+    /// MR opened or reopened — run `pr_commands`.
+    Opened,
+    /// Draft marked ready — run `pr_commands`, as for GitHub's `ready_for_review`.
+    Ready,
+    /// New commits pushed to the source branch — run `push_commands`.
+    Pushed,
+    /// Anything else (title edits, approvals, close, merge).
+    Ignored,
+}
+
+/// Classify a `merge_request` hook. GitLab sends `update` for both metadata
+/// edits and pushes; only the latter carries `object_attributes.oldrev`.
+/// Leaving draft is an `update` whose `changes` flip `draft` (or
+/// `work_in_progress` on older GitLab) from true to false.
+fn classify_mr_action(action: &str, payload: &serde_json::Value) -> MrTrigger {
+    let changes = &payload["changes"];
+    let left_draft = ["draft", "work_in_progress"].iter().any(|key| {
+        changes[key]["previous"].as_bool() == Some(true)
+            && changes[key]["current"].as_bool() == Some(false)
+    });
+    match action {
+        "open" | "reopen" => MrTrigger::Opened,
+        "update" if left_draft => MrTrigger::Ready,
+        "update" if payload["object_attributes"]["oldrev"].is_string() => MrTrigger::Pushed,
+        _ => MrTrigger::Ignored,
+    }
+}
+
+/// Whether the MR is a draft (`draft` on current GitLab, `work_in_progress` on older).
+fn is_draft_mr(payload: &serde_json::Value) -> bool {
+    let attrs = &payload["object_attributes"];
+    attrs["draft"].as_bool().unwrap_or(false)
+        || attrs["work_in_progress"].as_bool().unwrap_or(false)
+}
+
+/// Whether `username` belongs to a GitLab bot: project and group access
+/// tokens act as `project_<id>_bot_<suffix>` / `group_<id>_bot_<suffix>` users.
+fn is_bot_username(username: &str) -> bool {
+    ["project_", "group_"].iter().any(|prefix| {
+        username.strip_prefix(prefix).is_some_and(|rest| {
+            rest.split_once("_bot")
+                .is_some_and(|(id, _)| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+        })
+    })
+}
+
+/// Label titles of the MR in a merge_request hook payload.
+fn mr_labels(payload: &serde_json::Value) -> Vec<&str> {
+    payload["labels"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|label| label["title"].as_str())
+        .collect()
+}
+
+/// The fields the `config.ignore_*` filters match. The hook names the MR
+/// author only by id, so the author filter matches the user who triggered
+/// the event — the author when the MR is opened.
+fn mr_filter_fields<'a>(payload: &'a serde_json::Value, labels: &[&'a str]) -> PrFilterFields<'a> {
+    let attrs = &payload["object_attributes"];
+    PrFilterFields {
+        title: attrs["title"].as_str().unwrap_or(""),
+        author: payload["user"]["username"].as_str().unwrap_or(""),
+        repo_full_name: payload["project"]["path_with_namespace"]
+            .as_str()
+            .unwrap_or(""),
+        labels: labels.to_vec(),
+        source_branch: attrs["source_branch"].as_str().unwrap_or(""),
+        target_branch: attrs["target_branch"].as_str().unwrap_or(""),
+    }
+}
+
+/// Extract the MR web URL from a merge_request hook payload.
+fn extract_mr_url(payload: &serde_json::Value) -> Result<String, PrAgentError> {
+    payload["object_attributes"]["url"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| PrAgentError::Other("missing object_attributes.url in payload".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_git::MockGitProvider;
+
+    #[test]
+    fn test_verify_token() {
+        assert!(verify_token("s3cret", "s3cret"));
+        assert!(!verify_token("s3cret", "s3creT"));
+        assert!(!verify_token("s3cre", "s3cret"));
+        assert!(!verify_token("", "s3cret"));
+    }
+
+    #[test]
+    fn test_classify_mr_action() {
+        let payload = serde_json::json!({"object_attributes": {}});
+        assert_eq!(classify_mr_action("open", &payload), MrTrigger::Opened);
+        assert_eq!(classify_mr_action("reopen", &payload), MrTrigger::Opened);
+        assert_eq!(classify_mr_action("update", &payload), MrTrigger::Ignored);
+        assert_eq!(classify_mr_action("merge", &payload), MrTrigger::Ignored);
+
+        let pushed = serde_json::json!({"object_attributes": {"oldrev": "abc123"}});
+        assert_eq!(classify_mr_action("update", &pushed), MrTrigger::Pushed);
+
+        let ready = serde_json::json!({
+            "object_attributes": {},
+            "changes": {"draft": {"previous": true, "current": false}},
+        });
+        assert_eq!(classify_mr_action("update", &ready), MrTrigger::Ready);
+        let to_draft = serde_json::json!({
+            "object_attributes": {},
+            "changes": {"work_in_progress": {"previous": false, "current": true}},
+        });
+        assert_eq!(classify_mr_action("update", &to_draft), MrTrigger::Ignored);
+    }
+
+    #[test]
+    fn test_is_bot_username() {
+        assert!(is_bot_username("project_42_bot_3f1a"));
+        assert!(is_bot_username("group_7_bot"));
+        assert!(!is_bot_username("maria"));
+        assert!(!is_bot_username("project_lead_bot"));
+        assert!(!is_bot_username("robot"));
+    }
+
+    #[test]
+    fn test_mr_events_apply_ignore_filters() {
+        let payload = serde_json::json!({
+            "user": {"username": "renovate"},
+            "project": {"path_with_namespace": "group/project"},
+            "labels": [{"title": "wip"}, {"title": "backend"}],
+            "object_attributes": {
+                "title": "[skip] bump deps",
+                "source_branch": "release/1.2",
+                "target_branch": "main",
+            },
+        });
+        let labels = mr_labels(&payload);
+        let fields = mr_filter_fields(&payload, &labels);
+        assert_eq!(fields.labels, ["wip", "backend"]);
+
+        for toml in [
+            "[config]\nignore_pr_title = ['^\\[skip\\]']",
+            "[config]\nignore_pr_authors = ['renovate']",
+            "[config]\nignore_repositories = ['^group/']",
+            "[config]\nignore_pr_labels = ['wip']",
+            "[config]\nignore_pr_source_branches = ['^release/']",
+            "[config]\nignore_pr_target_branches = ['^main$']",
+        ] {
+            assert!(is_ignored_pr(&settings(toml), &fields), "{toml}");
+        }
+        assert!(!is_ignored_pr(&Settings::default(), &fields));
+    }
+
+    #[test]
+    fn test_is_draft_mr() {
+        assert!(is_draft_mr(
+            &serde_json::json!({"object_attributes": {"draft": true}})
+        ));
+        assert!(is_draft_mr(
+            &serde_json::json!({"object_attributes": {"work_in_progress": true}})
+        ));
+        assert!(!is_draft_mr(
+            &serde_json::json!({"object_attributes": {"draft": false}})
+        ));
+    }
+
+    #[test]
+    fn test_extract_mr_url() {
+        let payload = serde_json::json!({
+            "object_kind": "merge_request",
+            "object_attributes": {"url": "https://gitlab.com/group/project/-/merge_requests/7"}
+        });
+        assert_eq!(
+            extract_mr_url(&payload).unwrap(),
+            "https://gitlab.com/group/project/-/merge_requests/7"
+        );
+        assert!(extract_mr_url(&serde_json::json!({})).is_err());
+    }
+
+    fn note_payload(username: &str, user_id: u64) -> serde_json::Value {
+        serde_json::json!({
+            "object_kind": "note",
+            "user": { "id": user_id, "username": username },
+            "merge_request": { "author_id": 7, "url": "https://gitlab.com/group/project/-/merge_requests/3" },
+            "object_attributes": { "id": 11, "noteable_type": "MergeRequest", "note": "/review" },
+        })
+    }
+
+    fn settings(toml: &str) -> Settings {
+        crate::config::loader::load_settings(&Default::default(), None, Some(toml)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_authorize_note_applies_command_permissions() {
+        let mut provider = MockGitProvider::new();
+        provider
+            .associations
+            .insert("maria".into(), "MEMBER".into());
+        let settings = settings(
+            r#"
+[server.command_permissions]
+allow_by_command = { improve = "members", review = "author" }
+"#,
+        );
+
+        assert!(authorize_note(&provider, &settings, "improve", &note_payload("maria", 1)).await);
+        assert!(!authorize_note(&provider, &settings, "improve", &note_payload("bob", 2)).await);
+        assert!(authorize_note(&provider, &settings, "review", &note_payload("alice", 7)).await);
+        assert!(!authorize_note(&provider, &settings, "review", &note_payload("maria", 1)).await);
+        assert!(authorize_note(&provider, &settings, "ask", &note_payload("bob", 2)).await);
+
+        let comments = provider.get_calls().comments.clone();
+        assert_eq!(comments.len(), 2);
+        assert!(comments[0].0.starts_with("Sorry @bob, `/improve`"));
+    }
+
+    #[tokio::test]
+    async fn test_authorize_note_limits_finding_replies_to_maintainers() {
+        let mut provider = MockGitProvider::new();
+        provider
+            .associations
+            .insert("maria".into(), "COLLABORATOR".into());
+        let settings = settings("");
+
+        assert!(authorize_note(&provider, &settings, "wontfix", &note_payload("maria", 1)).await);
+        assert!(!authorize_note(&provider, &settings, "wontfix", &note_payload("bob", 2)).await);
+        assert!(!authorize_note(&provider, &settings, "resolve", &note_payload("alice", 7)).await);
+        assert!(provider.get_calls().comments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_authorize_note_skips_the_bots_own_notes() {
+        let provider = MockGitProvider::new();
+        let note = note_payload("mock-bot[bot]", 99);
+        assert!(!authorize_note(&provider, &settings(""), "review", &note).await);
+        assert!(provider.get_calls().comments.is_empty());
+    }
+}
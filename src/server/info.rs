@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Serialize;
+
+use crate::ai::anthropic::is_anthropic_model;
+use crate::ai::bedrock::is_bedrock_model;
+use crate::ai::fixed::is_mock_model;
+use crate::ai::ollama::is_ollama_model;
+use crate::config::loader::get_settings;
+use crate::config::types::Settings;
+use crate::tools::TOOL_COMMANDS;
+
+/// Commit the binary was built from, from `PR_AGENT_GIT_SHA` at build time.
+pub fn git_sha() -> Option<&'static str> {
+    option_env!("PR_AGENT_GIT_SHA").filter(|sha| !sha.is_empty())
+}
+
+/// Snapshot of what a running image has enabled.
+///
+/// Logged on server startup and served at `GET /api/v1/info` so operators can
+/// check a deployment without shelling into it. Secrets are reported only as
+/// set/unset.
+#[derive(Debug, Serialize)]
+pub struct RuntimeReport {
+    pub version: &'static str,
+    pub git_sha: Option<&'static str>,
+    pub arch: &'static str,
+    pub os: &'static str,
+    /// Git providers with credentials configured.
+    pub git_providers: BTreeMap<&'static str, bool>,
+    pub default_git_provider: String,
+    pub ai_handler: &'static str,
+    pub model: String,
+    pub fallback_models: Vec<String>,
+    /// Tool commands this build can run.
+    pub tools: Vec<&'static str>,
+    /// Commands run automatically on PR / push events.
+    pub auto_commands: AutoCommands,
+    pub routes: Vec<String>,
+    pub config: ConfigSummary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AutoCommands {
+    pub github_pr: Vec<String>,
+    pub github_push: Vec<String>,
+    pub gitlab_pr: Vec<String>,
+    pub gitlab_push: Vec<String>,
+}
+
+/// Non-secret settings that most often differ between deployments.
+#[derive(Debug, Serialize)]
+pub struct ConfigSummary {
+    pub publish_output: bool,
+    pub ai_timeout: u64,
+    pub enable_vision: bool,
+    pub analytics_enabled: bool,
+    pub github_deployment_type: String,
+    pub github_base_url: String,
+    pub gitlab_url: String,
+    pub openai_api_base: String,
+    /// Secret name → whether it is set.
+    pub secrets: BTreeMap<&'static str, bool>,
+}
+
+impl RuntimeReport {
+    pub fn from_settings(settings: &Settings, routes: &[(&str, &str)]) -> Self {
+        let github = &settings.github;
+        let gitlab = &settings.gitlab;
+        let app = &settings.github_app;
+
+        let github_configured =
+            !github.user_token.is_empty() || (github.app_id != 0 && !github.private_key.is_empty());
+        let git_providers = BTreeMap::from([
+            ("github", github_configured),
+            ("gitlab", !gitlab.personal_access_token.is_empty()),
+        ]);
+
+        let push_commands = |enabled: bool, commands: &[String]| {
+            if enabled {
+                commands.to_vec()
+            } else {
+                Vec::new()
+            }
+        };
+
+        let secrets = BTreeMap::from([
+            ("openai.key", !settings.openai.key.is_empty()),
+            ("anthropic.key", !settings.anthropic.key.is_empty()),
+            (
+                "aws.secret_access_key",
+                !settings.aws.secret_access_key.is_empty(),
+            ),
+            ("github.user_token", !github.user_token.is_empty()),
+            ("github.private_key", !github.private_key.is_empty()),
+            ("github.webhook_secret", !github.webhook_secret.is_empty()),
+            (
+                "gitlab.personal_access_token",
+                !gitlab.personal_access_token.is_empty(),
+            ),
+            ("gitlab.shared_secret", !gitlab.shared_secret.is_empty()),
+        ]);
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: git_sha(),
+            arch: std::env::consts::ARCH,
+            os: std::env::consts::OS,
+            git_providers,
+            default_git_provider: settings.config.git_provider.clone(),
+            ai_handler: ai_handler_name(&settings.config.model),
+            model: settings.config.model.clone(),
+            fallback_models: settings.config.fallback_models.clone(),
+            tools: TOOL_COMMANDS.to_vec(),
+            auto_commands: AutoCommands {
+                github_pr: app.pr_commands.clone(),
+                github_push: push_commands(app.handle_push_trigger, &app.push_commands),
+                gitlab_pr: gitlab.pr_commands.clone(),
+                gitlab_push: push_commands(gitlab.handle_push_trigger, &gitlab.push_commands),
+            },
+            routes: routes
+                .iter()
+                .map(|(method, path)| format!("{method} {path}"))
+                .collect(),
+            config: ConfigSummary {
+                publish_output: settings.config.publish_output,
+                ai_timeout: settings.config.ai_timeout,
+                enable_vision: settings.config.enable_vision,
+                analytics_enabled: !settings.config.analytics_folder.is_empty(),
+                github_deployment_type: github.deployment_type.clone(),
+                github_base_url: github.base_url.clone(),
+                gitlab_url: gitlab.url.clone(),
+                openai_api_base: settings.openai.api_base.clone(),
+                secrets,
+            },
+        }
+    }
+}
+
+/// Name of the AI handler that serves `model`.
+pub fn ai_handler_name(model: &str) -> &'static str {
+    if is_mock_model(model) {
+        "fixed"
+    } else if is_anthropic_model(model) {
+        "anthropic"
+    } else if is_bedrock_model(model) {
+        "bedrock"
+    } else if is_ollama_model(model) {
+        "ollama"
+    } else {
+        "openai_compatible"
+    }
+}
+
+/// Log the runtime report once at startup.
+pub fn log_runtime_report() {
+    let report = RuntimeReport::from_settings(&get_settings(), super::ROUTES);
+    match serde_json::to_string(&report) {
+        Ok(json) => tracing::info!(report = %json, "runtime configuration"),
+        Err(e) => tracing::warn!(error = %e, "failed to serialize runtime report"),
+    }
+}
+
+/// GET /api/v1/info — the runtime report as JSON.
+pub async fn get_info() -> impl IntoResponse {
+    let report = RuntimeReport::from_settings(&get_settings(), super::ROUTES);
+    (StatusCode::OK, axum::Json(serde_json::json!(report)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::load_settings;
+
+    #[test]
+    fn test_runtime_report_redacts_secrets() {
+        let repo_toml =
+            "[openai]\nkey = \"sk-very-secret\"\n\n[github]\nuser_token = \"ghp_secret\"\n";
+        let settings =
+            load_settings(&std::collections::HashMap::new(), None, Some(repo_toml)).unwrap();
+        let report = RuntimeReport::from_settings(&settings, &[("GET", "/")]);
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(!json.contains("sk-very-secret"));
+        assert!(!json.contains("ghp_secret"));
+        assert!(report.config.secrets["openai.key"]);
+        assert!(!report.config.secrets["gitlab.shared_secret"]);
+        assert!(report.git_providers["github"]);
+        assert!(!report.git_providers["gitlab"]);
+        assert_eq!(report.routes, vec!["GET /"]);
+        assert_eq!(report.ai_handler, "openai_compatible");
+        assert!(report.tools.contains(&"review"));
+        assert!(report.auto_commands.github_push.is_empty());
+    }
+}
@@ -0,0 +1,689 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinSet;
+
+use super::job_store::{JobStore, StoredJob};
+use super::{gitlab_webhook, webhook};
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::metrics;
+use crate::request_context;
+
+/// Global webhook job queue, started on first use.
+static JOB_QUEUE: OnceLock<Arc<JobQueue>> = OnceLock::new();
+
+/// Runs one job to completion.
+pub type JobRunner = Arc<dyn Fn(Job) -> BoxFuture<'static, Result<(), PrAgentError>> + Send + Sync>;
+
+/// Git platform a webhook came from; selects its event dispatcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobSource {
+    Github,
+    Gitlab,
+}
+
+impl JobSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            JobSource::Github => "github",
+            JobSource::Gitlab => "gitlab",
+        }
+    }
+}
+
+/// One received webhook event, waiting to be processed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub source: JobSource,
+    pub event: String,
+    pub action: String,
+    pub payload: serde_json::Value,
+}
+
+impl Job {
+    /// URL of the PR/MR the event is about, if any.
+    pub fn pr_url(&self) -> Option<&str> {
+        let p = &self.payload;
+        match self.source {
+            JobSource::Github => p["pull_request"]["html_url"]
+                .as_str()
+                .or_else(|| p["issue"]["pull_request"]["html_url"].as_str()),
+            JobSource::Gitlab => p["merge_request"]["url"]
+                .as_str()
+                .or_else(|| p["object_attributes"]["url"].as_str()),
+        }
+    }
+
+    /// Identity for deduplication: the PR plus a hash of the whole event, so
+    /// only a redelivered (identical) event is a duplicate.
+    fn key(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!(
+            "{}\0{}\0{}\0",
+            self.source.name(),
+            self.event,
+            self.action
+        ));
+        hasher.update(self.payload.to_string());
+        let digest = hex::encode(hasher.finalize());
+        format!("{}#{}", self.pr_url().unwrap_or(""), &digest[..16])
+    }
+}
+
+/// Outcome of [`JobQueue::enqueue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnqueueResult {
+    Queued,
+    /// An identical job is already waiting.
+    Duplicate,
+    /// The queue is at capacity.
+    Full,
+    /// The server is shutting down and takes no new jobs.
+    ShuttingDown,
+}
+
+/// Queue depth and worker usage, as reported by the health endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct QueueStats {
+    pub pending: usize,
+    pub running: usize,
+    pub workers: usize,
+    pub capacity: usize,
+}
+
+/// Start attempts after which a stored job is dropped instead of recovered,
+/// so an event that crashes the process cannot do so forever.
+const MAX_JOB_ATTEMPTS: u32 = 3;
+
+/// A job on its way to a worker.
+struct QueuedJob {
+    /// Deduplication key ([`Job::key`]).
+    key: String,
+    /// Id in the job store, also the request id the job runs as.
+    id: String,
+    job: Job,
+}
+
+type JobReceiver = Arc<tokio::sync::Mutex<mpsc::Receiver<QueuedJob>>>;
+
+/// Bounded queue of webhook jobs processed by a fixed pool of workers.
+pub struct JobQueue {
+    tx: mpsc::Sender<QueuedJob>,
+    rx: JobReceiver,
+    /// Keys of jobs waiting for a worker.
+    pending: Mutex<HashSet<String>>,
+    running: AtomicUsize,
+    /// Unix time the last job finished, 0 before the first.
+    last_finished: AtomicI64,
+    /// Jobs being run, by id: their PR URL and event, for the shutdown log.
+    in_flight: Mutex<HashMap<String, (Option<String>, String)>>,
+    workers: usize,
+    capacity: usize,
+    /// Keeps queued jobs on disk until they finish, if configured.
+    store: Option<JobStore>,
+    /// Set by [`JobQueue::close`]: new jobs are refused and workers stop
+    /// once their current job is done.
+    closing: watch::Sender<bool>,
+    /// The worker tasks, awaited by [`JobQueue::shutdown`].
+    tasks: Mutex<JoinSet<()>>,
+}
+
+impl JobQueue {
+    /// Start `workers` workers taking jobs from a queue holding up to `capacity`.
+    ///
+    /// Must be called inside a Tokio runtime.
+    pub fn start(
+        workers: usize,
+        capacity: usize,
+        runner: JobRunner,
+        store: Option<JobStore>,
+    ) -> Arc<Self> {
+        let workers = workers.max(1);
+        let capacity = capacity.max(1);
+        let (tx, rx) = mpsc::channel(capacity);
+        let queue = Arc::new(Self {
+            tx,
+            rx: Arc::new(tokio::sync::Mutex::new(rx)),
+            pending: Mutex::new(HashSet::new()),
+            running: AtomicUsize::new(0),
+            last_finished: AtomicI64::new(0),
+            in_flight: Mutex::new(HashMap::new()),
+            workers,
+            capacity,
+            store,
+            closing: watch::Sender::new(false),
+            tasks: Mutex::new(JoinSet::new()),
+        });
+        {
+            let mut tasks = queue.tasks.lock().unwrap();
+            for _ in 0..workers {
+                tasks.spawn(Self::work(queue.clone(), runner.clone()));
+            }
+        }
+        queue
+    }
+
+    async fn work(queue: Arc<Self>, runner: JobRunner) {
+        let mut closing = queue.closing.subscribe();
+        loop {
+            let next = tokio::select! {
+                biased;
+                _ = closing.wait_for(|closing| *closing) => None,
+                job = async { queue.rx.lock().await.recv().await } => job,
+            };
+            let Some(QueuedJob { key, id, job }) = next else {
+                return;
+            };
+            // Running jobs no longer dedupe: a new event may need a fresh run.
+            queue.pending.lock().unwrap().remove(&key);
+            queue.running.fetch_add(1, Ordering::Relaxed);
+            let (source, event, action) = (job.source, job.event.clone(), job.action.clone());
+            queue.in_flight.lock().unwrap().insert(
+                id.clone(),
+                (job.pr_url().map(str::to_string), event.clone()),
+            );
+            let run = request_context::scope(id.clone(), "webhook", runner(job));
+            if let Err(e) = run.await {
+                tracing::error!(
+                    request_id = %id,
+                    ?source,
+                    event = %event,
+                    action = %action,
+                    error = %e,
+                    "webhook handler failed"
+                );
+            }
+            // Failed jobs are not retried: only interrupted ones are recovered.
+            if let Some(store) = &queue.store {
+                store.remove(&id);
+            }
+            queue.in_flight.lock().unwrap().remove(&id);
+            queue
+                .last_finished
+                .store(Utc::now().timestamp(), Ordering::Relaxed);
+            queue.running.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Add `job` to the queue unless an identical job is already waiting or
+    /// the queue is full.
+    pub fn enqueue(&self, job: Job) -> EnqueueResult {
+        metrics::record_webhook_event(job.source.name(), &job.event);
+        if *self.closing.borrow() {
+            tracing::warn!(
+                pr_url = job.pr_url(),
+                event = %job.event,
+                "shutting down, rejecting webhook"
+            );
+            return EnqueueResult::ShuttingDown;
+        }
+        let key = job.key();
+        let mut pending = self.pending.lock().unwrap();
+        if pending.contains(&key) {
+            tracing::info!(
+                pr_url = job.pr_url(),
+                event = %job.event,
+                "identical job already queued, skipping"
+            );
+            return EnqueueResult::Duplicate;
+        }
+        // Enqueue-time ids sort oldest first, which recovery relies on.
+        let id = request_context::new_id();
+        // Stored before it is sent, so a worker can't finish it first.
+        if let Some(store) = &self.store {
+            let stored = StoredJob {
+                job: job.clone(),
+                attempts: 1,
+            };
+            if let Err(e) = store.save(&id, &stored) {
+                tracing::warn!(error = %e, "failed to store job, it won't survive a restart");
+            }
+        }
+        let (pr_url, event) = (job.pr_url().map(str::to_string), job.event.clone());
+        match self.tx.try_send(QueuedJob {
+            key: key.clone(),
+            id: id.clone(),
+            job,
+        }) {
+            Ok(()) => {
+                tracing::info!(
+                    request_id = %id,
+                    pr_url = pr_url.as_deref(),
+                    event = %event,
+                    "webhook event queued"
+                );
+                pending.insert(key);
+                EnqueueResult::Queued
+            }
+            Err(mpsc::error::TrySendError::Full(queued)) => {
+                if let Some(store) = &self.store {
+                    store.remove(&queued.id);
+                }
+                tracing::warn!(
+                    pr_url = queued.job.pr_url(),
+                    event = %queued.job.event,
+                    capacity = self.capacity,
+                    "job queue full, rejecting webhook"
+                );
+                EnqueueResult::Full
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => EnqueueResult::Full,
+        }
+    }
+
+    /// Re-queue the jobs a previous process left unfinished in the store.
+    ///
+    /// Waits for room in the queue rather than rejecting. Returns the number
+    /// of jobs resumed.
+    pub async fn recover(&self) -> usize {
+        let Some(store) = &self.store else {
+            return 0;
+        };
+        let jobs = match store.load_all() {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to read job store, nothing recovered");
+                return 0;
+            }
+        };
+        let mut recovered = 0;
+        for (id, mut stored) in jobs {
+            if stored.attempts >= MAX_JOB_ATTEMPTS {
+                tracing::error!(
+                    request_id = id,
+                    pr_url = stored.job.pr_url(),
+                    event = %stored.job.event,
+                    attempts = stored.attempts,
+                    "dropping stored job that never finished"
+                );
+                store.remove(&id);
+                continue;
+            }
+            stored.attempts += 1;
+            if let Err(e) = store.save(&id, &stored) {
+                tracing::warn!(request_id = id, error = %e, "failed to update stored job");
+            }
+            tracing::info!(
+                request_id = id,
+                pr_url = stored.job.pr_url(),
+                event = %stored.job.event,
+                attempt = stored.attempts,
+                "resuming unfinished job"
+            );
+            let key = stored.job.key();
+            self.pending.lock().unwrap().insert(key.clone());
+            let queued = QueuedJob {
+                key,
+                id,
+                job: stored.job,
+            };
+            if self.tx.send(queued).await.is_err() {
+                break;
+            }
+            recovered += 1;
+        }
+        recovered
+    }
+
+    /// Refuse new jobs and let workers stop after their current one.
+    pub fn close(&self) {
+        self.closing.send_replace(true);
+    }
+
+    /// Close the queue and wait up to `grace` for running jobs to finish.
+    ///
+    /// Jobs still running after `grace`, and those that never started, are
+    /// logged and abandoned; with a job store they are resumed on the next
+    /// start. Returns the number of abandoned jobs.
+    pub async fn shutdown(&self, grace: Duration) -> usize {
+        self.close();
+        let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        let running = self.running.load(Ordering::Relaxed);
+        if running > 0 {
+            tracing::info!(
+                running,
+                grace_secs = grace.as_secs(),
+                "waiting for running jobs to finish"
+            );
+        }
+        let drained =
+            tokio::time::timeout(grace, async { while tasks.join_next().await.is_some() {} })
+                .await
+                .is_ok();
+
+        let resumed = self.store.is_some();
+        let mut abandoned = 0;
+        if !drained {
+            for (id, (pr_url, event)) in self.in_flight.lock().unwrap().iter() {
+                tracing::warn!(request_id = %id, pr_url = pr_url.as_deref(), event = %event, resumed, "abandoning running job");
+                abandoned += 1;
+            }
+        }
+        if let Ok(mut rx) = self.rx.try_lock() {
+            while let Ok(queued) = rx.try_recv() {
+                tracing::warn!(request_id = %queued.id, pr_url = queued.job.pr_url(), event = %queued.job.event, resumed, "abandoning queued job");
+                abandoned += 1;
+            }
+        }
+        // Dropping `tasks` aborts the workers still running.
+        drop(tasks);
+        abandoned
+    }
+
+    /// When a worker last finished a job, if any has.
+    pub fn last_finished_at(&self) -> Option<DateTime<Utc>> {
+        match self.last_finished.load(Ordering::Relaxed) {
+            0 => None,
+            secs => DateTime::from_timestamp(secs, 0),
+        }
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        QueueStats {
+            pending: self.pending.lock().unwrap().len(),
+            running: self.running.load(Ordering::Relaxed),
+            workers: self.workers,
+            capacity: self.capacity,
+        }
+    }
+}
+
+/// The server's job queue, sized from `[server]` on first use. With
+/// `server.job_store_dir` set, jobs left unfinished by a previous process
+/// are resumed.
+pub fn global() -> &'static Arc<JobQueue> {
+    JOB_QUEUE.get_or_init(|| {
+        let settings = get_settings();
+        let store_dir = settings.server.job_store_dir.trim();
+        let store = (!store_dir.is_empty()).then(|| JobStore::new(store_dir));
+        let queue = JobQueue::start(
+            settings.server.max_concurrent_jobs,
+            settings.server.job_queue_size,
+            Arc::new(dispatch_job),
+            store,
+        );
+        let stats = queue.stats();
+        tracing::info!(
+            workers = stats.workers,
+            capacity = stats.capacity,
+            persistent = queue.store.is_some(),
+            "started webhook job queue"
+        );
+        let recovering = queue.clone();
+        tokio::spawn(async move {
+            let recovered = recovering.recover().await;
+            if recovered > 0 {
+                tracing::info!(recovered, "resumed unfinished webhook jobs");
+            }
+        });
+        queue
+    })
+}
+
+/// Route a job to its platform's event dispatcher.
+fn dispatch_job(job: Job) -> BoxFuture<'static, Result<(), PrAgentError>> {
+    Box::pin(async move {
+        match job.source {
+            JobSource::Github => {
+                webhook::dispatch_event(&job.event, &job.action, &job.payload).await
+            }
+            JobSource::Gitlab => {
+                gitlab_webhook::dispatch_event(&job.event, &job.action, &job.payload).await
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+
+    fn job(pr: u32, action: &str) -> Job {
+        Job {
+            source: JobSource::Github,
+            event: "pull_request".into(),
+            action: action.into(),
+            payload: serde_json::json!({
+                "action": action,
+                "pull_request": {"html_url": format!("https://github.com/o/r/pull/{pr}")},
+            }),
+        }
+    }
+
+    /// Runner whose jobs block until `gate` hands out a permit.
+    fn gated_runner(gate: Arc<Semaphore>, done: Arc<AtomicUsize>) -> JobRunner {
+        Arc::new(move |_job| {
+            let gate = gate.clone();
+            let done = done.clone();
+            Box::pin(async move {
+                gate.acquire().await.unwrap().forget();
+                done.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            })
+        })
+    }
+
+    async fn wait_for(mut cond: impl FnMut() -> bool) {
+        for _ in 0..200 {
+            if cond() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached");
+    }
+
+    #[test]
+    fn test_job_pr_url() {
+        assert_eq!(
+            job(7, "opened").pr_url(),
+            Some("https://github.com/o/r/pull/7")
+        );
+        let note = Job {
+            source: JobSource::Gitlab,
+            event: "note".into(),
+            action: String::new(),
+            payload: serde_json::json!({
+                "merge_request": {"url": "https://gitlab.com/g/p/-/merge_requests/3"},
+            }),
+        };
+        assert_eq!(
+            note.pr_url(),
+            Some("https://gitlab.com/g/p/-/merge_requests/3")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bounded_concurrency_and_dedup() {
+        let gate = Arc::new(Semaphore::new(0));
+        let done = Arc::new(AtomicUsize::new(0));
+        let queue = JobQueue::start(2, 10, gated_runner(gate.clone(), done.clone()), None);
+
+        for pr in 1..=4 {
+            assert_eq!(queue.enqueue(job(pr, "opened")), EnqueueResult::Queued);
+        }
+        // Two workers pick up jobs; the rest wait.
+        wait_for(|| queue.stats().running == 2).await;
+        assert_eq!(queue.stats().pending, 2);
+
+        // A redelivery of a waiting event is dropped; a different event on
+        // the same PR is not.
+        assert_eq!(queue.enqueue(job(4, "opened")), EnqueueResult::Duplicate);
+        assert_eq!(queue.enqueue(job(4, "reopened")), EnqueueResult::Queued);
+        // A running job no longer blocks its duplicate.
+        let running_pr = (1..=2)
+            .map(|pr| queue.enqueue(job(pr, "opened")))
+            .filter(|r| *r == EnqueueResult::Queued)
+            .count();
+        assert_eq!(running_pr, 2);
+
+        gate.add_permits(7);
+        wait_for(|| done.load(Ordering::Relaxed) == 7).await;
+        wait_for(|| queue.stats().running == 0).await;
+        assert_eq!(queue.stats().pending, 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_rejects() {
+        let gate = Arc::new(Semaphore::new(0));
+        let queue = JobQueue::start(
+            1,
+            1,
+            gated_runner(gate.clone(), Arc::new(AtomicUsize::new(0))),
+            None,
+        );
+        assert_eq!(queue.enqueue(job(1, "opened")), EnqueueResult::Queued);
+        wait_for(|| queue.stats().running == 1).await;
+        assert_eq!(queue.enqueue(job(2, "opened")), EnqueueResult::Queued);
+        assert_eq!(queue.enqueue(job(3, "opened")), EnqueueResult::Full);
+        assert_eq!(queue.stats().pending, 1);
+        gate.add_permits(2);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_running_jobs() {
+        let gate = Arc::new(Semaphore::new(0));
+        let done = Arc::new(AtomicUsize::new(0));
+        let queue = JobQueue::start(2, 10, gated_runner(gate.clone(), done.clone()), None);
+        assert_eq!(queue.enqueue(job(1, "opened")), EnqueueResult::Queued);
+        wait_for(|| queue.stats().running == 1).await;
+        assert_eq!(queue.last_finished_at(), None);
+
+        queue.close();
+        assert_eq!(queue.enqueue(job(2, "opened")), EnqueueResult::ShuttingDown);
+        let shutdown = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.shutdown(Duration::from_secs(5)).await }
+        });
+        gate.add_permits(1);
+        assert_eq!(shutdown.await.unwrap(), 0);
+        assert_eq!(done.load(Ordering::Relaxed), 1);
+        assert!(queue.last_finished_at().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_abandons_jobs_after_grace_period() {
+        let gate = Arc::new(Semaphore::new(0));
+        let done = Arc::new(AtomicUsize::new(0));
+        let queue = JobQueue::start(1, 10, gated_runner(gate, done.clone()), None);
+        assert_eq!(queue.enqueue(job(1, "opened")), EnqueueResult::Queued);
+        wait_for(|| queue.stats().running == 1).await;
+        assert_eq!(queue.enqueue(job(2, "opened")), EnqueueResult::Queued);
+
+        // One job is stuck running, the other never starts
+        assert_eq!(queue.shutdown(Duration::from_millis(50)).await, 2);
+        assert_eq!(done.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_job_runs_as_its_stored_id() {
+        let store = temp_store("request-id");
+        let seen = Arc::new(Mutex::new(None));
+        let runner: JobRunner = {
+            let seen = seen.clone();
+            Arc::new(move |_job| {
+                let seen = seen.clone();
+                Box::pin(async move {
+                    *seen.lock().unwrap() = request_context::current_id();
+                    Ok(())
+                })
+            })
+        };
+        let queue = JobQueue::start(1, 10, runner, Some(store.clone()));
+        store
+            .save(
+                "0000000000001-000000",
+                &StoredJob {
+                    job: job(1, "opened"),
+                    attempts: 1,
+                },
+            )
+            .unwrap();
+        assert_eq!(queue.recover().await, 1);
+        wait_for(|| seen.lock().unwrap().is_some()).await;
+        assert_eq!(
+            seen.lock().unwrap().as_deref(),
+            Some("0000000000001-000000")
+        );
+    }
+
+    fn temp_store(name: &str) -> JobStore {
+        let dir =
+            std::env::temp_dir().join(format!("pr-agent-queue-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        JobStore::new(dir)
+    }
+
+    #[tokio::test]
+    async fn test_store_keeps_job_until_finished() {
+        let store = temp_store("finish");
+        let gate = Arc::new(Semaphore::new(0));
+        let done = Arc::new(AtomicUsize::new(0));
+        let queue = JobQueue::start(
+            1,
+            10,
+            gated_runner(gate.clone(), done.clone()),
+            Some(store.clone()),
+        );
+        assert_eq!(queue.enqueue(job(1, "opened")), EnqueueResult::Queued);
+        wait_for(|| queue.stats().running == 1).await;
+        let stored = store.load_all().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].1.attempts, 1);
+
+        gate.add_permits(1);
+        wait_for(|| queue.stats().running == 0).await;
+        assert!(store.load_all().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recover_resumes_unfinished_jobs() {
+        let store = temp_store("recover");
+        let interrupted = StoredJob {
+            job: job(1, "opened"),
+            attempts: 1,
+        };
+        let crashing = StoredJob {
+            job: job(2, "opened"),
+            attempts: MAX_JOB_ATTEMPTS,
+        };
+        store.save("0000000000001-000000", &interrupted).unwrap();
+        store.save("0000000000002-000000", &crashing).unwrap();
+
+        let gate = Arc::new(Semaphore::new(0));
+        let done = Arc::new(AtomicUsize::new(0));
+        let queue = JobQueue::start(
+            1,
+            10,
+            gated_runner(gate.clone(), done.clone()),
+            Some(store.clone()),
+        );
+        assert_eq!(queue.recover().await, 1);
+
+        // The resumed job is stored with its new attempt count until it ends;
+        // the one that kept failing is gone.
+        wait_for(|| queue.stats().running == 1).await;
+        let stored = store.load_all().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].1.attempts, 2);
+        assert_eq!(
+            stored[0].1.job.pr_url(),
+            Some("https://github.com/o/r/pull/1")
+        );
+
+        gate.add_permits(1);
+        wait_for(|| done.load(Ordering::Relaxed) == 1).await;
+        wait_for(|| store.load_all().unwrap().is_empty()).await;
+    }
+}
@@ -0,0 +1,135 @@
+//! On-disk record of queued webhook jobs (`server.job_store_dir`).
+//!
+//! Each job is written to `<dir>/<id>.json` when it is queued and deleted
+//! once a worker has finished it, so the files left behind after a restart
+//! are exactly the jobs that never completed.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::job_queue::Job;
+use crate::error::PrAgentError;
+
+/// A job as stored on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredJob {
+    pub job: Job,
+    /// Times the job was started: its first run plus one per recovery.
+    pub attempts: u32,
+}
+
+/// Directory of unfinished jobs.
+#[derive(Debug, Clone)]
+pub struct JobStore {
+    dir: PathBuf,
+}
+
+impl JobStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    /// Write `job` under `id`, replacing any earlier version.
+    pub fn save(&self, id: &str, job: &StoredJob) -> Result<(), PrAgentError> {
+        std::fs::create_dir_all(&self.dir)?;
+        // Write then rename, so a crash mid-write never leaves a torn file.
+        let tmp = self.dir.join(format!("{id}.json.tmp"));
+        std::fs::write(&tmp, serde_json::to_vec(job)?)?;
+        std::fs::rename(&tmp, self.path(id))?;
+        Ok(())
+    }
+
+    /// Forget a finished job.
+    pub fn remove(&self, id: &str) {
+        if let Err(e) = std::fs::remove_file(self.path(id))
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!(id, error = %e, "failed to remove finished job from store");
+        }
+    }
+
+    /// All stored jobs as `(id, job)`, oldest first. Unreadable files are
+    /// skipped with a warning.
+    pub fn load_all(&self) -> Result<Vec<(String, StoredJob)>, PrAgentError> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut jobs: Vec<(String, StoredJob)> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let id = path.file_stem()?.to_str()?.to_string();
+                match read_job(&path) {
+                    Ok(job) => Some((id, job)),
+                    Err(e) => {
+                        tracing::warn!(
+                            path = %path.display(),
+                            error = %e,
+                            "skipping unreadable stored job"
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+        // Ids start with the enqueue time in milliseconds.
+        jobs.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(jobs)
+    }
+}
+
+fn read_job(path: &Path) -> Result<StoredJob, PrAgentError> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::job_queue::JobSource;
+
+    fn temp_store(name: &str) -> JobStore {
+        let dir = std::env::temp_dir().join(format!("pr-agent-jobs-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        JobStore::new(dir)
+    }
+
+    fn stored(action: &str) -> StoredJob {
+        StoredJob {
+            job: Job {
+                source: JobSource::Github,
+                event: "pull_request".into(),
+                action: action.into(),
+                payload: serde_json::json!({"action": action}),
+            },
+            attempts: 1,
+        }
+    }
+
+    #[test]
+    fn test_save_load_remove() {
+        let store = temp_store("roundtrip");
+        assert!(store.load_all().unwrap().is_empty());
+
+        store.save("0002-b", &stored("reopened")).unwrap();
+        store.save("0001-a", &stored("opened")).unwrap();
+        std::fs::write(store.dir.join("0003-c.json"), "not json").unwrap();
+
+        let jobs = store.load_all().unwrap();
+        let ids: Vec<&str> = jobs.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["0001-a", "0002-b"]);
+        assert_eq!(jobs[0].1.job.action, "opened");
+
+        store.remove("0001-a");
+        store.remove("0001-a");
+        assert_eq!(store.load_all().unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(&store.dir);
+    }
+}
@@ -1,7 +1,20 @@
+pub mod analytics;
+pub mod digest;
+pub mod github_events;
+pub mod gitlab_webhook;
+pub mod health;
+pub mod info;
+pub mod job_queue;
+pub mod job_store;
+pub mod permissions;
 pub mod push_dedup;
+pub mod rate_limit;
+pub mod reload;
 pub mod webhook;
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::time::Duration;
 
 use axum::Router;
 use axum::extract::DefaultBodyLimit;
@@ -10,27 +23,60 @@ use axum::response::IntoResponse;
 use axum::routing::{get, post};
 use tower_http::trace::TraceLayer;
 
+use crate::config::loader::get_settings;
 use crate::error::PrAgentError;
 
+const GITHUB_WEBHOOK_ROUTE: &str = "/api/v1/github_webhooks";
+const GITLAB_WEBHOOK_ROUTE: &str = "/api/v1/gitlab_webhooks";
+const DESCRIBE_RECORD_ROUTE: &str = "/api/v1/describe/{owner}/{repo}/{pr_number}";
+const INFO_ROUTE: &str = "/api/v1/info";
+const METRICS_ROUTE: &str = "/metrics";
+const LIVENESS_ROUTE: &str = "/healthz";
+const READINESS_ROUTE: &str = "/readyz";
+
+/// `(method, path)` of every route, as listed in the runtime report.
+const ROUTES: &[(&str, &str)] = &[
+    ("GET", "/"),
+    ("POST", GITHUB_WEBHOOK_ROUTE),
+    ("POST", GITLAB_WEBHOOK_ROUTE),
+    ("GET", DESCRIBE_RECORD_ROUTE),
+    ("GET", INFO_ROUTE),
+    ("GET", METRICS_ROUTE),
+    ("GET", LIVENESS_ROUTE),
+    ("GET", READINESS_ROUTE),
+];
+
 /// Start the webhook server.
 ///
 /// Listens on port 3000 by default (overridable via PORT env var).
-pub async fn start_server() -> Result<(), PrAgentError> {
+/// `cli_overrides` are re-applied whenever the settings are reloaded.
+pub async fn start_server(cli_overrides: &HashMap<String, String>) -> Result<(), PrAgentError> {
     let port: u16 = std::env::var("PORT")
         .ok()
         .and_then(|p| p.parse().ok())
         .unwrap_or(3000);
 
     let app = Router::new()
-        .route("/", get(health_check))
+        .route("/", get(health::status))
+        .route(GITHUB_WEBHOOK_ROUTE, post(webhook::handle_github_webhook))
         .route(
-            "/api/v1/github_webhooks",
-            post(webhook::handle_github_webhook),
+            GITLAB_WEBHOOK_ROUTE,
+            post(gitlab_webhook::handle_gitlab_webhook),
         )
+        .route(DESCRIBE_RECORD_ROUTE, get(analytics::get_describe_record))
+        .route(INFO_ROUTE, get(info::get_info))
+        .route(METRICS_ROUTE, get(metrics))
+        .route(LIVENESS_ROUTE, get(health::healthz))
+        .route(READINESS_ROUTE, get(health::readyz))
         .layer(TraceLayer::new_for_http())
         .layer(DefaultBodyLimit::max(2 * 1024 * 1024)); // 2 MB
 
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    health::mark_started();
+    info::log_runtime_report();
+    job_queue::global();
+    reload::spawn(cli_overrides.clone());
+    digest::spawn();
     tracing::info!(%addr, "starting webhook server");
 
     let listener = tokio::net::TcpListener::bind(addr)
@@ -42,11 +88,18 @@ pub async fn start_server() -> Result<(), PrAgentError> {
         .await
         .map_err(|e| PrAgentError::Other(format!("server error: {e}")))?;
 
-    tracing::info!("server shut down gracefully");
+    let grace = Duration::from_secs(get_settings().server.shutdown_grace_period);
+    let abandoned = job_queue::global().shutdown(grace).await;
+    if abandoned > 0 {
+        tracing::warn!(abandoned, "server shut down with unfinished jobs");
+    } else {
+        tracing::info!("server shut down gracefully");
+    }
     Ok(())
 }
 
-/// Wait for SIGINT (Ctrl+C) or SIGTERM for clean container shutdown.
+/// Wait for SIGINT (Ctrl+C) or SIGTERM for clean container shutdown, then
+/// stop taking webhook events.
 async fn shutdown_signal() {
     let ctrl_c = tokio::signal::ctrl_c();
 
@@ -65,12 +118,21 @@ async fn shutdown_signal() {
         ctrl_c.await.ok();
         tracing::info!("received SIGINT, shutting down");
     }
+    job_queue::global().close();
 }
 
-/// Health check endpoint: GET /
-async fn health_check() -> impl IntoResponse {
+/// Prometheus scrape endpoint: GET /metrics
+///
+/// Answers in OpenMetrics, with exemplars, when the scraper accepts it.
+async fn metrics(headers: axum::http::HeaderMap) -> impl IntoResponse {
+    let accept = headers
+        .get(axum::http::header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let format = crate::metrics::Format::from_accept(accept);
     (
         StatusCode::OK,
-        axum::Json(serde_json::json!({"status": "ok"})),
+        [(axum::http::header::CONTENT_TYPE, format.content_type())],
+        crate::metrics::render(format),
     )
 }
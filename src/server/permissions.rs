@@ -0,0 +1,328 @@
+//! Who may run comment commands (`[server.command_permissions]`).
+//!
+//! Checked by the GitHub and GitLab webhooks before a command from a PR
+//! comment runs, using the commenter's `author_association` and the PR
+//! author. Settings come from the org-level and repo-level `.pr_agent.toml`.
+//! A rejected user gets at most one reply per PR, so repeating a command
+//! can't make the bot flood the PR with replies.
+
+use super::github_events::Comment;
+use crate::config::types::{CommandPermissionsConfig, Settings};
+use crate::git::GitProvider;
+use crate::tools;
+
+/// Hidden marker on a rejection reply, followed by the login it answered.
+const REJECTED_MARKER_PREFIX: &str = "<!-- pr-agent:command-rejected ";
+
+/// The person behind a comment command.
+#[derive(Debug)]
+struct Commenter<'a> {
+    login: &'a str,
+    /// GitHub `author_association` (`OWNER`, `MEMBER`, `COLLABORATOR`, …).
+    association: &'a str,
+    pr_author: &'a str,
+}
+
+/// Whether a comment's `author_association` belongs to a repo maintainer.
+pub fn is_maintainer_association(association: &str) -> bool {
+    matches!(association, "OWNER" | "MEMBER" | "COLLABORATOR")
+}
+
+/// Whether the author of `comment`, on a PR by `pr_author`, may run
+/// `command`. A rejected command gets a reply on the PR when
+/// `reply_when_rejected` is set and the user has not had one there yet.
+pub async fn check(
+    provider: &dyn GitProvider,
+    settings: &Settings,
+    command: &str,
+    comment: &Comment,
+    pr_author: &str,
+) -> bool {
+    check_user(
+        provider,
+        settings,
+        command,
+        &comment.user.login,
+        &comment.author_association,
+        pr_author,
+    )
+    .await
+}
+
+/// Like [`check`], for hosts whose comment events carry no association;
+/// see `GitProvider::get_author_association`.
+pub async fn check_user(
+    provider: &dyn GitProvider,
+    settings: &Settings,
+    command: &str,
+    login: &str,
+    association: &str,
+    pr_author: &str,
+) -> bool {
+    let config = &settings.server.command_permissions;
+    let commenter = Commenter {
+        login,
+        association,
+        pr_author,
+    };
+    if is_allowed(provider, config, command, &commenter).await {
+        return true;
+    }
+    tracing::info!(
+        command,
+        login = commenter.login,
+        association = commenter.association,
+        policy = policy(config, command),
+        "rejecting comment command from user without permission"
+    );
+    if !config.reply_when_rejected {
+        return false;
+    }
+    let marker = format!("{REJECTED_MARKER_PREFIX}{} -->", commenter.login);
+    let already_replied = provider
+        .get_issue_comments()
+        .await
+        .is_ok_and(|comments| comments.iter().any(|c| c.body.contains(&marker)));
+    if already_replied {
+        tracing::debug!(
+            login = commenter.login,
+            "already replied to a rejected command"
+        );
+        return false;
+    }
+    let reply = format!(
+        "{}\n{marker}",
+        rejection_message(config, command, commenter.login)
+    );
+    if let Err(e) = provider.publish_comment(&reply, false).await {
+        tracing::warn!(error = %e, "failed to reply to rejected command");
+    }
+    false
+}
+
+/// Policy for `command`: its `allow_by_command` entry, else `allow`.
+fn policy<'a>(config: &'a CommandPermissionsConfig, command: &str) -> &'a str {
+    tools::canonical_command_name(command)
+        .and_then(|name| config.allow_by_command.get(name))
+        .unwrap_or(&config.allow)
+}
+
+/// Unknown policies and failed team lookups deny.
+async fn is_allowed(
+    provider: &dyn GitProvider,
+    config: &CommandPermissionsConfig,
+    command: &str,
+    commenter: &Commenter<'_>,
+) -> bool {
+    match policy(config, command) {
+        "anyone" => true,
+        "members" => is_maintainer_association(commenter.association),
+        "author" => !commenter.login.is_empty() && commenter.login == commenter.pr_author,
+        "teams" => {
+            for team in &config.teams {
+                match provider.is_team_member(team, commenter.login).await {
+                    Ok(true) => return true,
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!(team, error = %e, "failed to check team membership"),
+                }
+            }
+            false
+        }
+        other => {
+            tracing::warn!(policy = other, "unknown command permission policy, denying");
+            false
+        }
+    }
+}
+
+fn rejection_message(config: &CommandPermissionsConfig, command: &str, login: &str) -> String {
+    let who = match policy(config, command) {
+        "members" => "repository members and collaborators".to_string(),
+        "author" => "the PR author".to_string(),
+        "teams" if !config.teams.is_empty() => {
+            let teams: Vec<String> = config.teams.iter().map(|t| format!("`{t}`")).collect();
+            format!("members of {}", teams.join(", "))
+        }
+        _ => "users allowed by this repository's settings".to_string(),
+    };
+    format!("Sorry @{login}, `/{command}` can only be run by {who} on this repository.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_git::MockGitProvider;
+
+    fn comment(login: &str, association: &str) -> Comment {
+        serde_json::from_value(serde_json::json!({
+            "user": { "login": login },
+            "author_association": association,
+        }))
+        .unwrap()
+    }
+
+    fn settings(toml: &str) -> Settings {
+        crate::config::loader::load_settings(&Default::default(), None, Some(toml)).unwrap()
+    }
+
+    #[test]
+    fn test_is_maintainer_association() {
+        assert!(is_maintainer_association("OWNER"));
+        assert!(is_maintainer_association("COLLABORATOR"));
+        assert!(!is_maintainer_association("CONTRIBUTOR"));
+        assert!(!is_maintainer_association(""));
+    }
+
+    #[tokio::test]
+    async fn test_anyone_by_default() {
+        let provider = MockGitProvider::new();
+        let settings = settings("");
+        assert!(
+            check(
+                &provider,
+                &settings,
+                "review",
+                &comment("bob", "NONE"),
+                "author"
+            )
+            .await
+        );
+        assert!(provider.get_calls().comments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_per_command_policy_rejects_with_reply() {
+        let provider = MockGitProvider::new();
+        let settings = settings(
+            r#"
+[server.command_permissions]
+allow_by_command = { improve = "members", review = "author" }
+"#,
+        );
+        // `improve_code` is an alias of `improve`
+        assert!(
+            !check(
+                &provider,
+                &settings,
+                "improve_code",
+                &comment("bob", "NONE"),
+                "author"
+            )
+            .await
+        );
+        assert!(
+            check(
+                &provider,
+                &settings,
+                "improve",
+                &comment("bob", "MEMBER"),
+                "author"
+            )
+            .await
+        );
+        assert!(
+            check(
+                &provider,
+                &settings,
+                "review",
+                &comment("author", "NONE"),
+                "author"
+            )
+            .await
+        );
+        assert!(
+            !check(
+                &provider,
+                &settings,
+                "review",
+                &comment("bob", "OWNER"),
+                "author"
+            )
+            .await
+        );
+        assert!(
+            check(
+                &provider,
+                &settings,
+                "ask",
+                &comment("bob", "NONE"),
+                "author"
+            )
+            .await
+        );
+
+        let comments = provider.get_calls().comments.clone();
+        assert_eq!(comments.len(), 2);
+        assert_eq!(
+            comments[0].0,
+            "Sorry @bob, `/improve_code` can only be run by repository members and \
+             collaborators on this repository.\n<!-- pr-agent:command-rejected bob -->"
+        );
+        assert!(comments[1].0.contains("by the PR author"));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_user_gets_one_reply_per_pr() {
+        let mut provider = MockGitProvider::new();
+        provider.issue_comments = vec![crate::git::types::IssueComment {
+            id: 1,
+            body: "Sorry @bob, `/review` can only be run by the PR author on this repository.\n\
+                   <!-- pr-agent:command-rejected bob -->"
+                .into(),
+            user: "bot".into(),
+            created_at: String::new(),
+            url: None,
+        }];
+        let settings = settings("[server.command_permissions]\nallow = \"author\"\n");
+        for login in ["bob", "bob", "carol"] {
+            assert!(
+                !check(
+                    &provider,
+                    &settings,
+                    "review",
+                    &comment(login, "NONE"),
+                    "author"
+                )
+                .await
+            );
+        }
+        let comments = provider.get_calls().comments.clone();
+        assert_eq!(comments.len(), 1);
+        assert!(comments[0].0.starts_with("Sorry @carol,"));
+    }
+
+    #[tokio::test]
+    async fn test_teams_policy() {
+        let mut provider = MockGitProvider::new();
+        provider.team_members = vec![("acme/platform".into(), "carol".into())];
+        let settings = settings(
+            r#"
+[server.command_permissions]
+allow = "teams"
+teams = ["acme/platform"]
+reply_when_rejected = false
+"#,
+        );
+        assert!(
+            check(
+                &provider,
+                &settings,
+                "review",
+                &comment("carol", "NONE"),
+                "author"
+            )
+            .await
+        );
+        assert!(
+            !check(
+                &provider,
+                &settings,
+                "review",
+                &comment("bob", "MEMBER"),
+                "author"
+            )
+            .await
+        );
+        assert!(provider.get_calls().comments.is_empty());
+    }
+}
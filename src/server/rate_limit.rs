@@ -0,0 +1,207 @@
+//! Hourly limits on comment commands per user and per PR
+//! (`server.max_commands_per_user_per_hour`, `server.max_commands_per_pr_per_hour`).
+//!
+//! Counts are kept in memory over a sliding one-hour window. A command over
+//! either limit is not run; the first one rejected in a window gets a
+//! "please wait" reply so the user knows why nothing happened.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::types::Settings;
+use crate::git::GitProvider;
+
+const WINDOW: Duration = Duration::from_secs(3600);
+
+static LIMITER: LazyLock<RateLimiter> = LazyLock::new(RateLimiter::default);
+
+/// Which limit a command hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Scope {
+    User,
+    Pr,
+}
+
+/// A command over its limit.
+#[derive(Debug, PartialEq, Eq)]
+struct Limited {
+    scope: Scope,
+    limit: u32,
+    /// Until the oldest counted run leaves the window.
+    retry_after: Duration,
+    /// Whether this is the first rejection since the limit was reached.
+    first: bool,
+}
+
+#[derive(Default)]
+struct RateLimiter {
+    windows: Mutex<HashMap<(Scope, String), Window>>,
+}
+
+/// Runs counted for one user or PR.
+#[derive(Default)]
+struct Window {
+    runs: VecDeque<Instant>,
+    notified: bool,
+}
+
+impl RateLimiter {
+    /// Count a run against every `(scope, key, limit)`, or reject it without
+    /// counting when any of them is full.
+    fn try_acquire(&self, limits: &[(Scope, &str, u32)], now: Instant) -> Result<(), Limited> {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        for window in windows.values_mut() {
+            while window
+                .runs
+                .front()
+                .is_some_and(|run| now.duration_since(*run) >= WINDOW)
+            {
+                window.runs.pop_front();
+            }
+        }
+        windows.retain(|_, window| !window.runs.is_empty());
+
+        for &(scope, key, limit) in limits {
+            if let Some(window) = windows.get_mut(&(scope, key.to_string()))
+                && window.runs.len() >= limit as usize
+            {
+                let oldest = window.runs.front().copied().unwrap_or(now);
+                let first = !window.notified;
+                window.notified = true;
+                return Err(Limited {
+                    scope,
+                    limit,
+                    retry_after: WINDOW.saturating_sub(now.duration_since(oldest)),
+                    first,
+                });
+            }
+        }
+        for &(scope, key, _) in limits {
+            let window = windows.entry((scope, key.to_string())).or_default();
+            window.runs.push_back(now);
+            window.notified = false;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `login` may run another command on `pr_url` under the hourly
+/// limits in `settings`. Counts the run when it may.
+pub async fn check(
+    provider: &dyn GitProvider,
+    settings: &Settings,
+    login: &str,
+    pr_url: &str,
+) -> bool {
+    let server = &settings.server;
+    let limits: Vec<(Scope, &str, u32)> = [
+        (Scope::User, login, server.max_commands_per_user_per_hour),
+        (Scope::Pr, pr_url, server.max_commands_per_pr_per_hour),
+    ]
+    .into_iter()
+    .filter(|&(_, key, limit)| limit > 0 && !key.is_empty())
+    .collect();
+    let Err(limited) = LIMITER.try_acquire(&limits, Instant::now()) else {
+        return true;
+    };
+    tracing::info!(
+        login,
+        pr_url,
+        scope = ?limited.scope,
+        limit = limited.limit,
+        retry_after_secs = limited.retry_after.as_secs(),
+        "comment command rate limited"
+    );
+    if limited.first
+        && let Err(e) = provider
+            .publish_comment(&wait_message(&limited, login), false)
+            .await
+    {
+        tracing::warn!(error = %e, "failed to reply to rate-limited command");
+    }
+    false
+}
+
+fn wait_message(limited: &Limited, login: &str) -> String {
+    let minutes = limited.retry_after.as_secs().div_ceil(60).max(1);
+    let unit = if minutes == 1 { "minute" } else { "minutes" };
+    let who = match limited.scope {
+        Scope::User => "you can run",
+        Scope::Pr => "this PR can run",
+    };
+    format!(
+        "⏳ Please wait about {minutes} {unit}, @{login}: {who} up to {} commands per hour.",
+        limited.limit
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_limit_rejects_until_window_passes() {
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        let limits = [(Scope::User, "bob", 2)];
+        assert!(limiter.try_acquire(&limits, start).is_ok());
+        assert!(
+            limiter
+                .try_acquire(&limits, start + Duration::from_secs(60))
+                .is_ok()
+        );
+
+        let later = start + Duration::from_secs(600);
+        let limited = limiter.try_acquire(&limits, later).unwrap_err();
+        assert_eq!(
+            limited,
+            Limited {
+                scope: Scope::User,
+                limit: 2,
+                retry_after: Duration::from_secs(3000),
+                first: true,
+            }
+        );
+        // Only the first rejection asks for a reply
+        assert!(!limiter.try_acquire(&limits, later).unwrap_err().first);
+
+        assert!(limiter.try_acquire(&limits, start + WINDOW).is_ok());
+    }
+
+    #[test]
+    fn test_pr_limit_counts_all_users() {
+        let limiter = RateLimiter::default();
+        let now = Instant::now();
+        let pr = "https://github.com/o/r/pull/1";
+        assert!(
+            limiter
+                .try_acquire(&[(Scope::User, "bob", 5), (Scope::Pr, pr, 1)], now)
+                .is_ok()
+        );
+        let limited = limiter
+            .try_acquire(&[(Scope::User, "carol", 5), (Scope::Pr, pr, 1)], now)
+            .unwrap_err();
+        assert_eq!(limited.scope, Scope::Pr);
+        // The rejected run was not counted against carol
+        assert!(
+            limiter
+                .try_acquire(&[(Scope::User, "carol", 1)], now)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_wait_message() {
+        let limited = Limited {
+            scope: Scope::Pr,
+            limit: 20,
+            retry_after: Duration::from_secs(61),
+            first: true,
+        };
+        assert_eq!(
+            wait_message(&limited, "bob"),
+            "⏳ Please wait about 2 minutes, @bob: this PR can run up to 20 commands per hour."
+        );
+    }
+}
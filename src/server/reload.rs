@@ -0,0 +1,110 @@
+//! Settings hot-reload for server mode.
+//!
+//! On SIGHUP, and every `server.settings_reload_interval` seconds when set,
+//! the settings are rebuilt from the secrets files and environment variables
+//! and swapped in as the new global settings. Requests already running keep
+//! the settings they started with; a reload that fails to load keeps the
+//! current settings.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::config::loader::{get_settings, init_settings};
+
+/// Start the reload loop in the background.
+///
+/// `cli_overrides` are the `--section.key=value` arguments the server was
+/// started with, re-applied on every reload.
+pub fn spawn(cli_overrides: HashMap<String, String>) {
+    tokio::spawn(async move {
+        let mut hangup = Hangup::install();
+        loop {
+            let interval = get_settings().server.settings_reload_interval;
+            let trigger = next_trigger(&mut hangup, interval).await;
+            reload(&cli_overrides, trigger);
+        }
+    });
+}
+
+/// Rebuild the global settings. Returns whether the new settings were applied.
+pub fn reload(cli_overrides: &HashMap<String, String>, trigger: &str) -> bool {
+    match init_settings(cli_overrides, None, None, &[]) {
+        Ok(_) => {
+            tracing::info!(trigger, "settings reloaded");
+            true
+        }
+        Err(e) => {
+            tracing::error!(trigger, error = %e, "settings reload failed, keeping current settings");
+            false
+        }
+    }
+}
+
+/// Wait for SIGHUP or the reload interval (`0` waits for SIGHUP only).
+async fn next_trigger(hangup: &mut Hangup, interval: u64) -> &'static str {
+    let tick = async {
+        if interval == 0 {
+            std::future::pending::<()>().await;
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    };
+    tokio::select! {
+        _ = hangup.recv() => "SIGHUP",
+        _ = tick => "interval",
+    }
+}
+
+/// SIGHUP listener; never fires where signals aren't available.
+struct Hangup {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl Hangup {
+    fn install() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+            let signal = signal(SignalKind::hangup())
+                .inspect_err(|e| tracing::warn!(error = %e, "failed to install SIGHUP handler"))
+                .ok();
+            Self { signal }
+        }
+
+        #[cfg(not(unix))]
+        {
+            Self {}
+        }
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = self.signal.as_mut() {
+            signal.recv().await;
+            return;
+        }
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_interval_triggers_reload() {
+        let mut hangup = Hangup::install();
+        let trigger = tokio::time::timeout(Duration::from_secs(5), next_trigger(&mut hangup, 1))
+            .await
+            .expect("interval should fire");
+        assert_eq!(trigger, "interval");
+    }
+
+    #[tokio::test]
+    async fn test_zero_interval_waits_for_signal() {
+        let mut hangup = Hangup::install();
+        let waited =
+            tokio::time::timeout(Duration::from_millis(50), next_trigger(&mut hangup, 0)).await;
+        assert!(waited.is_err(), "should not fire without SIGHUP");
+    }
+}
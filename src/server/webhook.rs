@@ -1,17 +1,30 @@
+use std::collections::HashMap;
+use std::fmt::Write;
 use std::sync::Arc;
 
 use axum::body::Bytes;
 use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
 use hmac::{Hmac, Mac};
+use serde::Deserialize;
 use sha2::Sha256;
 
-use crate::config::loader::{get_settings, load_settings, with_settings};
+use super::github_events::{
+    CheckRunEvent, Comment, IssueCommentEvent, PullRequestEvent, PullRequestReviewCommentEvent,
+};
+use super::job_queue::{self, EnqueueResult, Job, JobSource};
+use super::permissions::{self, is_maintainer_association};
+use super::rate_limit;
+use crate::analytics::{ClosedPrRecord, save_closed_pr};
+use crate::config::loader::{get_settings, load_settings_with_prompts, with_settings};
 use crate::config::types::Settings;
 use crate::error::PrAgentError;
-use crate::git::GitProvider;
 use crate::git::github::GithubProvider;
-use crate::git::types::CommentId;
+use crate::git::types::{Capability, CommentId, Reaction};
+use crate::git::{GitProvider, create_provider};
+use crate::output::improve_formatter::is_self_review_checked;
+use crate::request_context;
+use crate::stats::{self, MetricEvent};
 use crate::tools;
 
 type HmacSha256 = Hmac<Sha256>;
@@ -21,8 +34,8 @@ type HmacSha256 = Hmac<Sha256>;
 /// Steps:
 /// 1. Verify HMAC-SHA256 signature
 /// 2. Parse event type and action
-/// 3. Dispatch to appropriate handler in a background task
-/// 4. Return 200 immediately
+/// 3. Queue the event for the worker pool (see [`job_queue`])
+/// 4. Return 200 immediately (503 if the queue is full)
 pub async fn handle_github_webhook(headers: HeaderMap, body: Bytes) -> impl IntoResponse {
     // 1. Verify signature
     let settings = get_settings();
@@ -64,15 +77,29 @@ pub async fn handle_github_webhook(headers: HeaderMap, body: Bytes) -> impl Into
 
     tracing::info!(event = %event, action = %action, "received webhook");
 
-    // 3. Dispatch in background task
-    tokio::spawn(async move {
-        if let Err(e) = dispatch_event(&event, &action, &payload).await {
-            tracing::error!(event = %event, action = %action, error = %e, "webhook handler failed");
-        }
-    });
+    // 3. Queue for processing
+    let job = Job {
+        source: JobSource::Github,
+        event,
+        action,
+        payload,
+    };
 
-    // 4. Return 200 immediately
-    (StatusCode::OK, "ok").into_response()
+    // 4. Return immediately
+    enqueue_response(job_queue::global().enqueue(job))
+}
+
+/// HTTP response for a queued webhook. A full queue answers 503 so the
+/// sender records a failed delivery that can be redelivered.
+pub(super) fn enqueue_response(result: EnqueueResult) -> axum::response::Response {
+    match result {
+        EnqueueResult::Queued => (StatusCode::OK, "ok").into_response(),
+        EnqueueResult::Duplicate => (StatusCode::OK, "duplicate").into_response(),
+        EnqueueResult::Full => (StatusCode::SERVICE_UNAVAILABLE, "job queue full").into_response(),
+        EnqueueResult::ShuttingDown => {
+            (StatusCode::SERVICE_UNAVAILABLE, "shutting down").into_response()
+        }
+    }
 }
 
 /// Verify the HMAC-SHA256 signature from GitHub.
@@ -95,10 +122,12 @@ fn verify_signature(body: &[u8], secret: &str, signature_header: &str) -> Result
         .map_err(|_| "HMAC verification failed".to_string())
 }
 
-/// Route webhook events to the appropriate handler.
-///
 /// Route webhook events to the appropriate tool handler.
-async fn dispatch_event(
+///
+/// The payload is parsed into the event's type from [`super::github_events`];
+/// one that doesn't fit the type (a string where a number belongs) fails
+/// the job.
+pub(super) async fn dispatch_event(
     event: &str,
     action: &str,
     payload: &serde_json::Value,
@@ -107,11 +136,12 @@ async fn dispatch_event(
 
     match event {
         "pull_request" => {
+            let payload = &PullRequestEvent::deserialize(payload)?;
             let pr_url = extract_pr_url(payload)?;
 
             // Bot detection: skip bot PRs (including pr-agent's own events like label changes).
-            let sender = payload["sender"]["login"].as_str().unwrap_or("");
-            let sender_type = payload["sender"]["type"].as_str().unwrap_or("");
+            let sender = payload.sender.login.as_str();
+            let sender_type = payload.sender.kind.as_str();
             if settings.github.ignore_bot_pr && sender_type == "Bot" {
                 if !sender.contains("pr-agent") {
                     tracing::info!(sender, sender_type, "ignoring PR from bot user");
@@ -126,15 +156,16 @@ async fn dispatch_event(
 
             // Handle PR closed/merged event (before state check since closed PRs aren't "open")
             if action == "closed" {
-                handle_closed_pr(payload);
+                handle_closed_pr(payload, &settings.config.analytics_folder).await;
                 return Ok(());
             }
 
-            // Validate PR state: skip drafts and non-open PRs
+            // Validate PR state: skip non-open PRs
             if !check_pull_request_event(action, payload) {
-                tracing::info!(pr_url = %pr_url, action, "skipping PR event (draft, not open, or duplicate)");
+                tracing::info!(pr_url = %pr_url, action, "skipping PR event (not open, or duplicate)");
                 return Ok(());
             }
+            let is_draft = payload.pull_request.draft;
 
             if settings
                 .github_app
@@ -147,14 +178,37 @@ async fn dispatch_event(
                     return Ok(());
                 }
 
+                let Some(commands) = draft_pr_commands(
+                    &settings,
+                    &settings.github_app.pr_commands,
+                    action,
+                    is_draft,
+                ) else {
+                    tracing::info!(pr_url = %pr_url, action, is_draft, "skipping PR event for draft handling mode");
+                    return Ok(());
+                };
+
                 tracing::info!(pr_url = %pr_url, action, "handling PR event");
-                run_commands(&pr_url, &settings.github_app.pr_commands).await?;
+                let quick_take = action == "opened" && commands == settings.github_app.pr_commands;
+                run_commands(&pr_url, &commands, quick_take, false).await?;
             } else if action == "synchronize" && settings.github_app.handle_push_trigger {
+                let Some(commands) = draft_pr_commands(
+                    &settings,
+                    &settings.github_app.push_commands,
+                    action,
+                    is_draft,
+                ) else {
+                    tracing::info!(pr_url = %pr_url, "skipping push trigger on draft PR");
+                    return Ok(());
+                };
+
                 // Skip merge commits if configured
                 if settings.github_app.push_trigger_ignore_merge_commits {
-                    let after_sha = payload["after"].as_str().unwrap_or("");
-                    let merge_commit_sha = payload["pull_request"]["merge_commit_sha"]
-                        .as_str()
+                    let after_sha = payload.after.as_deref().unwrap_or("");
+                    let merge_commit_sha = payload
+                        .pull_request
+                        .merge_commit_sha
+                        .as_deref()
                         .unwrap_or("");
                     if !after_sha.is_empty()
                         && !merge_commit_sha.is_empty()
@@ -166,8 +220,8 @@ async fn dispatch_event(
                 }
 
                 // Skip identical before/after SHAs (no-op push)
-                let before_sha = payload["before"].as_str().unwrap_or("");
-                let after_sha = payload["after"].as_str().unwrap_or("");
+                let before_sha = payload.before.as_deref().unwrap_or("");
+                let after_sha = payload.after.as_deref().unwrap_or("");
                 if !before_sha.is_empty() && before_sha == after_sha {
                     tracing::debug!(pr_url = %pr_url, "skipping push trigger: before == after SHA");
                     return Ok(());
@@ -183,12 +237,13 @@ async fn dispatch_event(
                 };
 
                 tracing::info!(pr_url = %pr_url, "handling push trigger");
-                run_commands(&pr_url, &settings.github_app.push_commands).await?;
+                run_commands(&pr_url, &commands, false, true).await?;
             } else {
                 tracing::debug!(action, "ignoring pull_request action");
             }
         }
         "issue_comment" => {
+            let payload = &IssueCommentEvent::deserialize(payload)?;
             if action == "edited" {
                 // Check for self-review checkbox toggle
                 return handle_checkbox_edit(payload).await;
@@ -200,12 +255,13 @@ async fn dispatch_event(
             }
 
             // Only handle comments on PRs (have pull_request key)
-            if payload["issue"]["pull_request"].is_null() {
+            if payload.issue.pull_request.is_none() {
                 tracing::debug!("ignoring comment on non-PR issue");
                 return Ok(());
             }
+            let comment = &payload.comment;
 
-            let raw_comment = payload["comment"]["body"].as_str().unwrap_or("").trim();
+            let raw_comment = comment.body.trim();
 
             // Handle image-reply format: "> ![image](url)\n/ask question"
             // When users quote an image and then write /ask, the command isn't at
@@ -220,62 +276,96 @@ async fn dispatch_event(
 
             // Check if this is a line-level /ask comment (code review comment on specific lines).
             // If so, transform it to /ask_line with the appropriate flags.
-            let mut disable_eyes = false;
+            let mut skip_ack_reaction = false;
             let comment_body = if comment_body.contains("/ask")
-                && payload["comment"]["subject_type"].as_str() == Some("line")
-                && payload["comment"]["pull_request_url"].as_str().is_some()
+                && comment.subject_type.as_deref() == Some("line")
+                && comment.pull_request_url.is_some()
             {
-                disable_eyes = true;
-                handle_line_comments(payload, comment_body)
+                skip_ack_reaction = true;
+                handle_line_comments(comment, comment_body)
             } else {
                 comment_body.to_string()
             };
             let comment_body = comment_body.as_str();
 
             // Parse command early so we can reject unknown commands before
-            // creating a provider, adding eyes reactions, or fetching settings.
+            // creating a provider, adding ack reactions, or fetching settings.
             let (command, mut args) = tools::parse_command(comment_body);
             if !tools::is_known_command(&command) {
                 tracing::debug!(command, "ignoring unknown command from comment");
                 return Ok(());
             }
 
+            // `/resolve` and `/wontfix` change the review for everyone, so
+            // only repo maintainers may use them.
+            if tools::is_finding_reply_command(&command) {
+                let association = comment.author_association.as_str();
+                if !is_maintainer_association(association) {
+                    tracing::info!(
+                        command,
+                        association,
+                        "ignoring finding reply from non-maintainer"
+                    );
+                    return Ok(());
+                }
+                if !comment.user.login.is_empty() {
+                    args.insert("_author".to_string(), comment.user.login.clone());
+                }
+            }
+
             // Extract PR URL — from issue or from review comment's pull_request_url
-            let pr_url = if let Some(url) = payload["comment"]["pull_request_url"].as_str() {
-                url.to_string()
+            let pr_url = if let Some(url) = &comment.pull_request_url {
+                url.clone()
             } else {
                 extract_pr_url_from_issue(payload)?
             };
             tracing::info!(pr_url = %pr_url, command = comment_body, "handling comment command");
 
-            // Add eyes reaction to the comment
-            let comment_id = payload["comment"]["id"].as_u64().unwrap_or(0);
             let provider: Arc<dyn GitProvider> = Arc::new(GithubProvider::new(&pr_url).await?);
-            let _ = provider.add_eyes_reaction(comment_id, disable_eyes).await;
 
             // Fetch global + repo settings and scope them for this command
             let scoped_settings = fetch_scoped_settings(provider.as_ref(), &settings).await;
+            let effective = scoped_settings.as_deref().unwrap_or(settings.as_ref());
+            let pr_author = &payload.issue.user.login;
+            if !permissions::check(provider.as_ref(), effective, &command, comment, pr_author).await
+            {
+                return Ok(());
+            }
+            let login = &comment.user.login;
+            if !rate_limit::check(provider.as_ref(), effective, login, &pr_url).await {
+                return Ok(());
+            }
+
+            // Acknowledge the comment with a reaction
+            let comment_id = comment.id;
+            let ack_id = if skip_ack_reaction {
+                None
+            } else {
+                ack_reaction(provider.as_ref(), comment_id).await
+            };
 
             // Inject diff_hunk for ask_line when available
             if command == "ask_line"
-                && let Some(diff_hunk) = payload["comment"]["diff_hunk"].as_str()
+                && let Some(diff_hunk) = &comment.diff_hunk
             {
-                args.insert("_diff_hunk".to_string(), diff_hunk.to_string());
+                args.insert("_diff_hunk".to_string(), diff_hunk.clone());
             }
 
-            if let Some(s) = scoped_settings {
-                with_settings(s, tools::handle_command(&command, provider, &args)).await?;
-            } else {
-                tools::handle_command(&command, provider, &args).await?;
+            let result = run_tool(provider.clone(), scoped_settings, &command, &args).await;
+            if !skip_ack_reaction {
+                finish_reaction(provider.as_ref(), comment_id, ack_id, &result).await;
             }
+            result?;
         }
         "pull_request_review_comment" => {
             if action != "created" {
                 tracing::debug!(action, "ignoring pull_request_review_comment action");
                 return Ok(());
             }
+            let payload = &PullRequestReviewCommentEvent::deserialize(payload)?;
+            let comment = &payload.comment;
 
-            let raw_comment = payload["comment"]["body"].as_str().unwrap_or("").trim();
+            let raw_comment = comment.body.trim();
             let comment_body = reformat_image_reply(raw_comment);
 
             if !comment_body.contains("/ask") {
@@ -284,45 +374,101 @@ async fn dispatch_event(
             }
 
             // Extract PR URL from the review comment payload
-            let pr_url = payload["comment"]["pull_request_url"]
-                .as_str()
-                .map(|u| u.to_string())
-                .or_else(|| {
-                    payload["pull_request"]["url"]
-                        .as_str()
-                        .map(|u| u.to_string())
-                })
+            let pr_url = comment
+                .pull_request_url
+                .clone()
+                .or_else(|| Some(payload.pull_request.url.clone()).filter(|u| !u.is_empty()))
                 .ok_or_else(|| {
                     PrAgentError::Other("no pull_request_url in review comment".into())
                 })?;
 
             // Transform line comment to /ask_line command
-            let transformed = handle_line_comments(payload, &comment_body);
+            let transformed = handle_line_comments(comment, &comment_body);
             tracing::info!(
                 pr_url = %pr_url,
                 command = %transformed,
                 "handling line comment command"
             );
 
-            // Add eyes reaction (disabled for line comments to avoid noise)
-            let comment_id = payload["comment"]["id"].as_u64().unwrap_or(0);
+            // No ack reaction on line comments, to avoid noise
             let provider: Arc<dyn GitProvider> = Arc::new(GithubProvider::new(&pr_url).await?);
-            let _ = provider.add_eyes_reaction(comment_id, true).await;
 
             let scoped_settings = fetch_scoped_settings(provider.as_ref(), &settings).await;
             let (command, args) = tools::parse_command(&transformed);
+            let effective = scoped_settings.as_deref().unwrap_or(settings.as_ref());
+            let pr_author = &payload.pull_request.user.login;
+            if !permissions::check(provider.as_ref(), effective, &command, comment, pr_author).await
+            {
+                return Ok(());
+            }
+            let login = &comment.user.login;
+            if !rate_limit::check(provider.as_ref(), effective, login, &pr_url).await {
+                return Ok(());
+            }
 
             // Inject the diff_hunk from the webhook payload for ask_line
             let mut args = args;
-            if let Some(diff_hunk) = payload["comment"]["diff_hunk"].as_str() {
-                args.insert("_diff_hunk".to_string(), diff_hunk.to_string());
+            if let Some(diff_hunk) = &comment.diff_hunk {
+                args.insert("_diff_hunk".to_string(), diff_hunk.clone());
             }
 
-            if let Some(s) = scoped_settings {
-                with_settings(s, tools::handle_command(&command, provider, &args)).await?;
-            } else {
-                tools::handle_command(&command, provider, &args).await?;
+            run_tool(provider, scoped_settings, &command, &args).await?;
+        }
+        "check_run" => {
+            if action != "rerequested" {
+                tracing::debug!(action, "ignoring check_run action");
+                return Ok(());
+            }
+            let payload = &CheckRunEvent::deserialize(payload)?;
+            let Some((pr_url, command_line)) =
+                extract_check_run_rerun(payload, settings.github.app_id)
+            else {
+                tracing::debug!("ignoring re-run of a check not published by pr-agent");
+                return Ok(());
+            };
+
+            let (command, args) = tools::parse_command(&command_line);
+            if !tools::is_known_command(&command) {
+                tracing::warn!(command, "check run references an unknown command");
+                return Ok(());
+            }
+            tracing::info!(pr_url = %pr_url, command = %command_line, "re-running command from check run");
+
+            let github = GithubProvider::new(&pr_url).await?;
+            let pr_author = github.get_pr_author().await.unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "failed to look up the PR author");
+                String::new()
+            });
+            let provider: Arc<dyn GitProvider> = Arc::new(github);
+            let scoped_settings = fetch_scoped_settings(provider.as_ref(), &settings).await;
+            let effective = scoped_settings.as_deref().unwrap_or(settings.as_ref());
+
+            // A re-run is a command from whoever clicked it; check-run events
+            // carry no association, so it is looked up.
+            let login = &payload.sender.login;
+            let association = provider
+                .get_author_association(login)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!(login, error = %e, "failed to look up the re-run sender's permission");
+                    "NONE".into()
+                });
+            if !permissions::check_user(
+                provider.as_ref(),
+                effective,
+                &command,
+                login,
+                &association,
+                &pr_author,
+            )
+            .await
+            {
+                return Ok(());
             }
+            if !rate_limit::check(provider.as_ref(), effective, login, &pr_url).await {
+                return Ok(());
+            }
+            run_tool(provider, scoped_settings, &command, &args).await?;
         }
         _ => {
             tracing::debug!(event, "ignoring unsupported event type");
@@ -332,28 +478,131 @@ async fn dispatch_event(
     Ok(())
 }
 
-/// Validate a pull_request event payload before processing.
-fn check_pull_request_event(action: &str, payload: &serde_json::Value) -> bool {
-    let pr = &payload["pull_request"];
+/// Run a tool command with the PR's scoped settings (the global settings
+/// when there are none). A failure is reported on the PR when
+/// `config.publish_errors` is set, then returned.
+pub(super) async fn run_tool(
+    provider: Arc<dyn GitProvider>,
+    scoped_settings: Option<Arc<Settings>>,
+    command: &str,
+    args: &HashMap<String, String>,
+) -> Result<(), PrAgentError> {
+    let settings = scoped_settings.unwrap_or_else(get_settings);
+    let publish_errors = settings.config.publish_errors;
+    let result = with_settings(
+        settings,
+        tools::handle_command(command, provider.clone(), args),
+    )
+    .await;
+    if let Err(e) = &result
+        && publish_errors
+    {
+        let body = error_comment(command, e, request_context::current_id().as_deref());
+        if let Err(publish_err) = provider.publish_comment(&body, false).await {
+            tracing::warn!(error = %publish_err, "failed to publish error comment");
+        }
+    }
+    result
+}
 
-    // Skip draft PRs — default to false (non-draft) if field missing
-    let is_draft = pr["draft"].as_bool().unwrap_or(false);
-    if is_draft {
-        return false;
+/// Short collapsed comment reporting a failed tool run. Only the kind of
+/// failure is shown; the details are in the server logs, on the lines
+/// carrying its `request_id`.
+fn error_comment(command: &str, error: &PrAgentError, request_id: Option<&str>) -> String {
+    let cause = error.public_summary();
+    let mut body =
+        format!("<details><summary>⚠️ <code>/{command}</code> failed: {cause}</summary>\n\n");
+    match request_id {
+        Some(id) => {
+            let _ = writeln!(
+                body,
+                "Request id: `{id}`. Share it with your pr-agent administrator to find the details in the server logs."
+            );
+        }
+        None => body.push_str("The details are in the server logs.\n"),
+    }
+    body.push_str("\n</details>");
+    body
+}
+
+/// Acknowledge a command comment with a reaction (👀). Returns the
+/// reaction's ID, `None` on platforms without reactions.
+pub(super) async fn ack_reaction(provider: &dyn GitProvider, comment_id: u64) -> Option<u64> {
+    if !provider.is_supported(Capability::Reactions) {
+        return None;
     }
+    provider
+        .add_reaction(comment_id, Reaction::Ack)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Swap a command comment's acknowledgement reaction (👀) for the success or
+/// failure reaction once the command has finished.
+pub(super) async fn finish_reaction(
+    provider: &dyn GitProvider,
+    comment_id: u64,
+    ack_id: Option<u64>,
+    result: &Result<(), PrAgentError>,
+) {
+    if !provider.is_supported(Capability::Reactions) {
+        return;
+    }
+    if let Some(ack_id) = ack_id
+        && let Err(e) = provider.remove_reaction(comment_id, ack_id).await
+    {
+        tracing::debug!(error = %e, "failed to remove acknowledgement reaction");
+    }
+    let reaction = if result.is_ok() {
+        Reaction::Success
+    } else {
+        Reaction::Failure
+    };
+    if let Err(e) = provider.add_reaction(comment_id, reaction).await {
+        tracing::debug!(error = %e, "failed to add completion reaction");
+    }
+}
+
+/// Map a `check_run` re-run request back to `(pr_url, command_line)`.
+///
+/// Only checks published by this app (matching `app_id`, which must be
+/// configured) whose `external_id` holds a command and that belong to a PR
+/// qualify.
+fn extract_check_run_rerun(payload: &CheckRunEvent, app_id: u64) -> Option<(String, String)> {
+    let check = &payload.check_run;
+    if app_id == 0 || check.app.as_ref().map(|app| app.id) != Some(app_id) {
+        return None;
+    }
+    let command_line = check.external_id.trim();
+    if !command_line.starts_with('/') {
+        return None;
+    }
+    let number = check.pull_requests.first()?.number;
+    let repo_url = payload.repository.html_url.as_str();
+    if repo_url.is_empty() {
+        return None;
+    }
+    Some((
+        format!("{repo_url}/pull/{number}"),
+        command_line.to_string(),
+    ))
+}
+
+/// Validate a pull_request event payload before processing.
+fn check_pull_request_event(action: &str, payload: &PullRequestEvent) -> bool {
+    let pr = &payload.pull_request;
 
     // Skip non-open PRs
-    let state = pr["state"].as_str().unwrap_or("");
-    if state != "open" {
+    if pr.state != "open" {
         return false;
     }
 
     // For review_requested and synchronize: skip if created_at == updated_at
     // to avoid double-processing when a PR is first opened (both events fire)
     if action == "review_requested" || action == "synchronize" {
-        let created_at = pr["created_at"].as_str().unwrap_or("");
-        let updated_at = pr["updated_at"].as_str().unwrap_or("");
-        if !created_at.is_empty() && created_at == updated_at {
+        let created_at = pr.created_at.as_str();
+        if !created_at.is_empty() && created_at == pr.updated_at {
             tracing::debug!(
                 action,
                 created_at,
@@ -366,12 +615,71 @@ fn check_pull_request_event(action: &str, payload: &serde_json::Value) -> bool {
     true
 }
 
+/// Commands to run for a PR event under `github_app.handle_draft_prs`, or
+/// `None` to skip it.
+///
+/// `ignore` skips drafts, `describe_only` runs just the `/describe` commands
+/// on them, and `full` treats them like any PR. Leaving draft runs the full
+/// list, except under `full`, where the draft already had it.
+pub(super) fn draft_pr_commands(
+    settings: &Settings,
+    commands: &[String],
+    action: &str,
+    is_draft: bool,
+) -> Option<Vec<String>> {
+    let mode = settings.github_app.handle_draft_prs.as_str();
+    if !is_draft {
+        return (action != "ready_for_review" || mode != "full").then(|| commands.to_vec());
+    }
+    match mode {
+        "full" => Some(commands.to_vec()),
+        "describe_only" => {
+            let describe: Vec<String> = commands
+                .iter()
+                .filter(|cmd| tools::parse_command(cmd).0 == "describe")
+                .cloned()
+                .collect();
+            (!describe.is_empty()).then_some(describe)
+        }
+        _ => None,
+    }
+}
+
+/// The PR fields the `config.ignore_*` filters match, from either host's
+/// webhook payload.
+pub(super) struct PrFilterFields<'a> {
+    pub title: &'a str,
+    pub author: &'a str,
+    pub repo_full_name: &'a str,
+    pub labels: Vec<&'a str>,
+    pub source_branch: &'a str,
+    pub target_branch: &'a str,
+}
+
+impl<'a> From<&'a PullRequestEvent> for PrFilterFields<'a> {
+    fn from(payload: &'a PullRequestEvent) -> Self {
+        let pr = &payload.pull_request;
+        Self {
+            title: &pr.title,
+            author: &pr.user.login,
+            repo_full_name: &payload.repository.full_name,
+            labels: pr.labels.iter().map(|l| l.name.as_str()).collect(),
+            source_branch: &pr.head.name,
+            target_branch: &pr.base.name,
+        }
+    }
+}
+
 /// Check if a PR should be ignored based on configured filters.
-fn should_ignore_pr(settings: &Settings, payload: &serde_json::Value) -> bool {
-    let title = payload["pull_request"]["title"].as_str().unwrap_or("");
-    let author = payload["pull_request"]["user"]["login"]
-        .as_str()
-        .unwrap_or("");
+fn should_ignore_pr(settings: &Settings, payload: &PullRequestEvent) -> bool {
+    is_ignored_pr(settings, &PrFilterFields::from(payload))
+}
+
+/// Whether any of the configured title, author, repository, label or branch
+/// filters matches `pr`.
+pub(super) fn is_ignored_pr(settings: &Settings, pr: &PrFilterFields<'_>) -> bool {
+    let title = pr.title;
+    let author = pr.author;
 
     // 1. Title regex patterns
     for pattern in &settings.config.ignore_pr_title {
@@ -401,7 +709,7 @@ fn should_ignore_pr(settings: &Settings, payload: &serde_json::Value) -> bool {
     }
 
     // 3. Repository full name regex patterns
-    let repo_full_name = payload["repository"]["full_name"].as_str().unwrap_or("");
+    let repo_full_name = pr.repo_full_name;
     if !repo_full_name.is_empty() {
         for pattern in &settings.config.ignore_repositories {
             match crate::util::get_or_compile_regex(pattern) {
@@ -423,11 +731,8 @@ fn should_ignore_pr(settings: &Settings, payload: &serde_json::Value) -> bool {
     }
 
     // 4. PR labels (exact match)
-    if !settings.config.ignore_pr_labels.is_empty()
-        && let Some(labels) = payload["pull_request"]["labels"].as_array()
-    {
-        for label in labels {
-            let label_name = label["name"].as_str().unwrap_or("");
+    if !settings.config.ignore_pr_labels.is_empty() {
+        for &label_name in &pr.labels {
             if settings
                 .config
                 .ignore_pr_labels
@@ -441,9 +746,7 @@ fn should_ignore_pr(settings: &Settings, payload: &serde_json::Value) -> bool {
     }
 
     // 5. Source branch regex patterns (head.ref)
-    let source_branch = payload["pull_request"]["head"]["ref"]
-        .as_str()
-        .unwrap_or("");
+    let source_branch = pr.source_branch;
     if !source_branch.is_empty() {
         for pattern in &settings.config.ignore_pr_source_branches {
             match crate::util::get_or_compile_regex(pattern) {
@@ -465,9 +768,7 @@ fn should_ignore_pr(settings: &Settings, payload: &serde_json::Value) -> bool {
     }
 
     // 6. Target branch regex patterns (base.ref)
-    let target_branch = payload["pull_request"]["base"]["ref"]
-        .as_str()
-        .unwrap_or("");
+    let target_branch = pr.target_branch;
     if !target_branch.is_empty() {
         for pattern in &settings.config.ignore_pr_target_branches {
             match crate::util::get_or_compile_regex(pattern) {
@@ -491,52 +792,70 @@ fn should_ignore_pr(settings: &Settings, payload: &serde_json::Value) -> bool {
     false
 }
 
-/// Log PR merge statistics when a PR is closed and merged.
+/// Log PR merge statistics when a PR is closed and merged, and record the
+/// closed PR in the analytics store when `config.analytics_folder` is set
+/// and in the metrics store when `metrics_store.database_url` is set.
 ///
 /// Extracts real statistics from the webhook payload: commits, additions,
 /// deletions, changed files, reviewers, comments, and time-to-merge.
-fn handle_closed_pr(payload: &serde_json::Value) {
-    let pr = &payload["pull_request"];
-    let is_merged = pr["merged"].as_bool().unwrap_or(false);
-    if !is_merged {
-        tracing::debug!("PR closed without merge, skipping analytics");
-        return;
+async fn handle_closed_pr(payload: &PullRequestEvent, analytics_folder: &str) {
+    let pr = &payload.pull_request;
+    let record = closed_pr_record(payload);
+    if record.merged {
+        tracing::info!(
+            pr_url = %record.url,
+            title = %pr.title,
+            commits = pr.commits,
+            additions = pr.additions,
+            deletions = pr.deletions,
+            changed_files = pr.changed_files,
+            reviewers = pr.requested_reviewers.len(),
+            comments = pr.comments + pr.review_comments,
+            merged_by = %record.merged_by,
+            time_to_merge_hours = record.time_to_merge_hours,
+            "PR merged — statistics"
+        );
+    } else {
+        tracing::debug!(pr_url = %record.url, "PR closed without merge");
     }
 
-    let pr_url = pr["html_url"].as_str().unwrap_or("");
-    let title = pr["title"].as_str().unwrap_or("");
-    let commits = pr["commits"].as_u64().unwrap_or(0);
-    let additions = pr["additions"].as_u64().unwrap_or(0);
-    let deletions = pr["deletions"].as_u64().unwrap_or(0);
-    let changed_files = pr["changed_files"].as_u64().unwrap_or(0);
-    let comments =
-        pr["comments"].as_u64().unwrap_or(0) + pr["review_comments"].as_u64().unwrap_or(0);
-    let merged_by = pr["merged_by"]["login"].as_str().unwrap_or("");
-
-    // Count requested reviewers
-    let reviewers = pr["requested_reviewers"]
-        .as_array()
-        .map(|a| a.len())
-        .unwrap_or(0);
-
-    // Calculate time to merge
-    let created_at = pr["created_at"].as_str().unwrap_or("");
-    let merged_at = pr["merged_at"].as_str().unwrap_or("");
-    let time_to_merge_hours = compute_hours_between(created_at, merged_at);
-
-    tracing::info!(
-        pr_url,
-        title,
-        commits,
-        additions,
-        deletions,
-        changed_files,
-        reviewers,
-        comments,
-        merged_by,
-        time_to_merge_hours,
-        "PR merged — statistics"
-    );
+    // The weekly digest reads merged PRs from the analytics store, and
+    // closed ones drop out of its open findings.
+    if !analytics_folder.is_empty()
+        && record.pr_number > 0
+        && let Err(e) = save_closed_pr(std::path::Path::new(analytics_folder), &record)
+    {
+        tracing::warn!(error = %e, "failed to persist closed PR");
+    }
+    if record.pr_number > 0 {
+        stats::record(&MetricEvent::Closed(record)).await;
+    }
+}
+
+/// The analytics record of a closed PR.
+fn closed_pr_record(payload: &PullRequestEvent) -> ClosedPrRecord {
+    let pr = &payload.pull_request;
+    let merged_at = pr.merged_at.as_deref().filter(|_| pr.merged);
+    let closed_at = merged_at
+        .or(pr.closed_at.as_deref())
+        .map_or_else(|| chrono::Utc::now().to_rfc3339(), String::from);
+    ClosedPrRecord {
+        repo: payload.repository.full_name.clone(),
+        pr_number: pr.number,
+        title: pr.title.clone(),
+        url: pr.html_url.clone().unwrap_or_default(),
+        merged: pr.merged,
+        merged_by: pr
+            .merged_by
+            .as_ref()
+            .map_or_else(String::new, |u| u.login.clone()),
+        additions: pr.additions,
+        deletions: pr.deletions,
+        time_to_merge_hours: merged_at.map_or(0.0, |merged_at| {
+            compute_hours_between(&pr.created_at, merged_at)
+        }),
+        closed_at,
+    }
 }
 
 /// Compute hours between two ISO 8601 timestamps.
@@ -552,19 +871,15 @@ fn compute_hours_between(start: &str, end: &str) -> f64 {
 }
 
 /// Transform a line-level `/ask` comment into an `/ask_line` command string.
-fn handle_line_comments(payload: &serde_json::Value, comment_body: &str) -> String {
-    let comment = &payload["comment"];
-
-    let end_line = comment["line"].as_u64().unwrap_or(0);
-    let start_line = comment["start_line"].as_u64().unwrap_or(end_line);
-    let start_line = if start_line == 0 {
-        end_line
-    } else {
-        start_line
-    };
-    let side = comment["side"].as_str().unwrap_or("RIGHT");
-    let path = comment["path"].as_str().unwrap_or("");
-    let comment_id = comment["id"].as_u64().unwrap_or(0);
+fn handle_line_comments(comment: &Comment, comment_body: &str) -> String {
+    let end_line = comment.line.unwrap_or(0);
+    let start_line = comment
+        .start_line
+        .filter(|&line| line != 0)
+        .unwrap_or(end_line);
+    let side = comment.side.as_deref().unwrap_or("RIGHT");
+    let path = &comment.path;
+    let comment_id = comment.id;
 
     // Extract the question text by stripping the leading /ask command (only the first one)
     let question = comment_body
@@ -624,10 +939,32 @@ async fn fetch_optional_toml(
     }
 }
 
+/// Fetch the repo's `.pr_agent/prompts/*.toml` overrides, when enabled.
+async fn fetch_repo_prompt_files(provider: &dyn GitProvider, settings: &Settings) -> Vec<String> {
+    if !(settings.config.use_repo_settings_file && settings.config.use_repo_prompt_files) {
+        return Vec::new();
+    }
+    match provider.get_repo_prompt_files().await {
+        Ok(files) => {
+            if !files.is_empty() {
+                tracing::info!(
+                    count = files.len(),
+                    "loaded repo prompt overrides for webhook request"
+                );
+            }
+            files
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to fetch repo prompt overrides");
+            Vec::new()
+        }
+    }
+}
+
 /// Fetch global org-level and repo-level settings, then build a scoped `Arc<Settings>`.
 ///
 /// Returns `Some(settings)` if any overrides were loaded, `None` if neither exists.
-async fn fetch_scoped_settings(
+pub(super) async fn fetch_scoped_settings(
     provider: &dyn GitProvider,
     settings: &Settings,
 ) -> Option<Arc<Settings>> {
@@ -645,11 +982,14 @@ async fn fetch_scoped_settings(
     )
     .await;
 
-    if global_toml.is_some() || repo_toml.is_some() {
-        match load_settings(
+    let prompt_files = fetch_repo_prompt_files(provider, settings).await;
+
+    if global_toml.is_some() || repo_toml.is_some() || !prompt_files.is_empty() {
+        match load_settings_with_prompts(
             &std::collections::HashMap::new(),
             global_toml.as_deref(),
             repo_toml.as_deref(),
+            &prompt_files,
         ) {
             Ok(s) => Some(Arc::new(s)),
             Err(e) => {
@@ -665,49 +1005,72 @@ async fn fetch_scoped_settings(
 /// Run a list of commands against a PR (e.g. pr_commands or push_commands).
 ///
 /// Fetches global org-level and repo-level `.pr_agent.toml` once, then runs
-/// all commands within a scoped settings context.
-async fn run_commands(pr_url: &str, commands: &[String]) -> Result<(), crate::error::PrAgentError> {
-    let provider: Arc<dyn GitProvider> = Arc::new(GithubProvider::new(pr_url).await?);
+/// all commands within a scoped settings context. With `quick_take` (PR
+/// opened) and `pr_quick_take.enable`, a quick take is posted first and
+/// linked to the outputs afterwards. With `push_trigger`, `/review` only
+/// runs once the incremental review thresholds are met.
+pub(super) async fn run_commands(
+    pr_url: &str,
+    commands: &[String],
+    quick_take: bool,
+    push_trigger: bool,
+) -> Result<(), crate::error::PrAgentError> {
+    let provider = create_provider(pr_url).await?;
     let settings = get_settings();
 
     // Fetch global + repo settings once for all commands in this PR
     let scoped_settings = fetch_scoped_settings(provider.as_ref(), &settings).await;
 
+    let quick_take = quick_take.then(|| tools::quick_take::PRQuickTake::new(provider.clone()));
+    let effective = scoped_settings.clone().unwrap_or(settings);
+    let posted = match &quick_take {
+        Some(tool) if effective.pr_quick_take.enable && effective.config.publish_output => {
+            with_settings(effective.clone(), tool.publish()).await
+        }
+        _ => None,
+    };
+
     for cmd_str in commands {
-        let (command, args) = tools::parse_command(cmd_str);
-        let cmd_provider: Arc<dyn GitProvider> = Arc::new(GithubProvider::new(pr_url).await?);
+        let (command, mut args) = tools::parse_command(cmd_str);
+        if push_trigger {
+            args.insert("_push_trigger".to_string(), "true".to_string());
+        }
+        let cmd_provider = create_provider(pr_url).await?;
 
         tracing::info!(command = %command, "running auto-command");
-        let result = if let Some(ref s) = scoped_settings {
-            with_settings(
-                s.clone(),
-                tools::handle_command(&command, cmd_provider, &args),
-            )
-            .await
-        } else {
-            tools::handle_command(&command, cmd_provider, &args).await
-        };
+        let result = run_tool(cmd_provider, scoped_settings.clone(), &command, &args).await;
         if let Err(e) = result {
             tracing::error!(command = %command, error = %e, "auto-command failed");
             // Continue with other commands even if one fails
         }
     }
+
+    if let (Some(tool), Some(posted)) = (&quick_take, &posted) {
+        with_settings(effective, tool.finalize(posted, commands)).await;
+    }
     Ok(())
 }
 
-/// Handle an `issue_comment` `edited` event — detect self-review checkbox toggle.
+/// Handle an `issue_comment` `edited` event — detect checkbox toggles.
 ///
 /// When the PR author checks the self-review checkbox (added by the improve tool),
-/// this handler can auto-approve the PR and/or post a confirmation.
+/// this handler can auto-approve the PR and/or post a confirmation. Suggestions
+/// checked in the "Applied suggestions" checklist are recorded in the accepted
+/// suggestions file.
 async fn handle_checkbox_edit(
-    payload: &serde_json::Value,
+    payload: &IssueCommentEvent,
 ) -> Result<(), crate::error::PrAgentError> {
     // Only handle comments on PRs
-    if payload["issue"]["pull_request"].is_null() {
+    if payload.issue.pull_request.is_none() {
         return Ok(());
     }
 
-    let comment_body = payload["comment"]["body"].as_str().unwrap_or("");
+    let comment_body = payload.comment.body.as_str();
+
+    let accepted = accepted_suggestion_edits(payload);
+    if !accepted.is_empty() {
+        record_accepted_suggestions(payload, &accepted).await?;
+    }
 
     // Check if this comment contains a self-review checkbox marker
     let action = detect_self_review_action(comment_body);
@@ -721,9 +1084,15 @@ async fn handle_checkbox_edit(
         return Ok(());
     }
 
+    // Only a fresh check counts; re-published comments keep the old state
+    if payload.previous_body().is_some_and(is_self_review_checked) {
+        tracing::debug!("self-review checkbox was already checked, ignoring");
+        return Ok(());
+    }
+
     // Verify the editor is the PR author
-    let sender = payload["sender"]["login"].as_str().unwrap_or("");
-    let pr_author = payload["issue"]["user"]["login"].as_str().unwrap_or("");
+    let sender = payload.sender.login.as_str();
+    let pr_author = payload.issue.user.login.as_str();
 
     if sender.is_empty() || pr_author.is_empty() || sender != pr_author {
         tracing::info!(
@@ -784,6 +1153,53 @@ async fn handle_checkbox_edit(
     Ok(())
 }
 
+/// Suggestions a user just checked in the bot's improve comment.
+///
+/// Compares the edited body with `changes.body.from`; edits by bots (including
+/// our own re-publishing) and checklists in user comments are ignored.
+fn accepted_suggestion_edits(payload: &IssueCommentEvent) -> Vec<String> {
+    let comment = &payload.comment;
+    let body = comment.body.as_str();
+    if comment.user.kind != "Bot"
+        || payload.sender.kind == "Bot"
+        || !body.trim_start().starts_with("<!-- pr-agent:improve -->")
+    {
+        return Vec::new();
+    }
+    tools::accepted_suggestions::newly_checked(body, payload.previous_body())
+}
+
+/// Append checked suggestions to the accepted suggestions file when
+/// `pr_code_suggestions.wiki_page_accepted_suggestions` is set for the repo.
+async fn record_accepted_suggestions(
+    payload: &IssueCommentEvent,
+    entries: &[String],
+) -> Result<(), crate::error::PrAgentError> {
+    let pr_url = extract_pr_url_from_issue(payload)?;
+    let sender = payload.sender.login.as_str();
+    let provider = GithubProvider::new(&pr_url).await?;
+    let base_settings = get_settings();
+    let settings = fetch_scoped_settings(&provider, &base_settings)
+        .await
+        .unwrap_or(base_settings);
+    if !settings.pr_code_suggestions.wiki_page_accepted_suggestions {
+        tracing::debug!(pr_url = %pr_url, "accepted suggestions tracking disabled, ignoring");
+        return Ok(());
+    }
+    tracing::info!(pr_url = %pr_url, sender, count = entries.len(), "suggestions accepted");
+    if let Some(pr_number) = provider.get_pr_number() {
+        stats::record(&MetricEvent::Accepted {
+            repo: payload.repository.full_name.clone(),
+            pr_number,
+            user: sender.to_string(),
+            count: entries.len(),
+        })
+        .await;
+    }
+    tools::accepted_suggestions::record(&provider, &settings, entries, sender).await;
+    Ok(())
+}
+
 /// Find the improve suggestions comment and collapse it inside `<details>`.
 ///
 /// Searches PR comments for the `<!-- pr-agent:improve -->` marker, then wraps
@@ -847,47 +1263,26 @@ fn detect_self_review_action(body: &str) -> SelfReviewAction {
     }
 }
 
-/// Check if the self-review checkbox in the comment body is checked.
-///
-/// Searches for `- [x]` on the same line as an exact self-review marker.
-fn is_self_review_checked(body: &str) -> bool {
-    const MARKERS: &[&str] = &[
-        "<!-- approve and fold suggestions self-review -->",
-        "<!-- approve pr self-review -->",
-        "<!-- fold suggestions self-review -->",
-    ];
-    for line in body.lines() {
-        if MARKERS.iter().any(|m| line.contains(m)) {
-            let trimmed = line.trim();
-            if trimmed.starts_with("- [x]") || trimmed.starts_with("- [X]") {
-                return true;
-            }
-        }
-    }
-    false
-}
-
 /// Extract the PR URL from a pull_request webhook event payload.
-fn extract_pr_url(payload: &serde_json::Value) -> Result<String, crate::error::PrAgentError> {
-    payload["pull_request"]["html_url"]
-        .as_str()
-        .map(String::from)
-        .ok_or_else(|| {
-            crate::error::PrAgentError::Other("missing pull_request.html_url in payload".into())
-        })
+fn extract_pr_url(payload: &PullRequestEvent) -> Result<String, crate::error::PrAgentError> {
+    payload.pull_request.html_url.clone().ok_or_else(|| {
+        crate::error::PrAgentError::Other("missing pull_request.html_url in payload".into())
+    })
 }
 
 /// Extract the PR URL from an issue_comment webhook event payload.
 fn extract_pr_url_from_issue(
-    payload: &serde_json::Value,
+    payload: &IssueCommentEvent,
 ) -> Result<String, crate::error::PrAgentError> {
     // The issue_comment event has issue.pull_request.html_url
-    payload["issue"]["pull_request"]["html_url"]
-        .as_str()
-        .map(String::from)
+    let issue = &payload.issue;
+    issue
+        .pull_request
+        .as_ref()
+        .and_then(|pr| pr.html_url.clone())
         .or_else(|| {
             // Fallback: construct from issue URL
-            payload["issue"]["html_url"].as_str().map(String::from)
+            issue.html_url.clone()
         })
         .ok_or_else(|| {
             crate::error::PrAgentError::Other(
@@ -899,6 +1294,11 @@ fn extract_pr_url_from_issue(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::mock_git::MockGitProvider;
+
+    fn parse<T: serde::de::DeserializeOwned>(payload: serde_json::Value) -> T {
+        serde_json::from_value(payload).unwrap()
+    }
 
     #[test]
     fn test_verify_signature_valid() {
@@ -931,13 +1331,101 @@ mod tests {
         assert!(verify_signature(body, secret, "invalid").is_err());
     }
 
+    #[test]
+    fn test_extract_check_run_rerun() {
+        let payload: CheckRunEvent = parse(serde_json::json!({
+            "check_run": {
+                "external_id": "/review --pr_reviewer.num_max_findings=5",
+                "app": { "id": 123 },
+                "pull_requests": [{ "number": 7 }]
+            },
+            "repository": { "html_url": "https://github.com/owner/repo" }
+        }));
+        assert_eq!(
+            extract_check_run_rerun(&payload, 123),
+            Some((
+                "https://github.com/owner/repo/pull/7".to_string(),
+                "/review --pr_reviewer.num_max_findings=5".to_string()
+            ))
+        );
+        // Without a configured app id, no check qualifies.
+        assert!(extract_check_run_rerun(&payload, 0).is_none());
+        // Checks from other apps are ignored.
+        assert!(extract_check_run_rerun(&payload, 999).is_none());
+
+        let mut foreign = payload.clone();
+        foreign.check_run.external_id = "ci-build-42".into();
+        assert!(extract_check_run_rerun(&foreign, 123).is_none());
+
+        let mut no_pr = payload;
+        no_pr.check_run.pull_requests.clear();
+        assert!(extract_check_run_rerun(&no_pr, 123).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_finish_reaction_replaces_ack() {
+        let provider = MockGitProvider::new();
+        finish_reaction(&provider, 7, Some(1), &Ok(())).await;
+        let failed = Err(PrAgentError::Other("boom".into()));
+        finish_reaction(&provider, 8, None, &failed).await;
+
+        let calls = provider.get_calls();
+        assert_eq!(calls.removed_reactions, vec![(7, 1)]);
+        assert_eq!(
+            calls.reactions,
+            vec![(7, Reaction::Success), (8, Reaction::Failure)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_tool_publishes_error_comment() {
+        let provider = Arc::new(MockGitProvider::new());
+        let result = run_tool(provider.clone(), None, "nope", &HashMap::new()).await;
+        assert!(result.is_err());
+
+        let comments = provider.get_calls().comments.clone();
+        assert_eq!(comments.len(), 1);
+        assert!(comments[0].0.starts_with(
+            "<details><summary>⚠️ <code>/nope</code> failed: an unexpected error occurred</summary>"
+        ));
+        // The raw error message stays in the logs
+        assert!(!comments[0].0.contains("unknown command"));
+    }
+
+    #[tokio::test]
+    async fn test_run_tool_respects_publish_errors() {
+        let provider = Arc::new(MockGitProvider::new());
+        let mut settings = Settings::default();
+        settings.config.publish_errors = false;
+        let result = run_tool(
+            provider.clone(),
+            Some(Arc::new(settings)),
+            "nope",
+            &HashMap::new(),
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(provider.get_calls().comments.is_empty());
+    }
+
+    #[test]
+    fn test_error_comment_includes_request_id() {
+        let error = PrAgentError::RateLimited {
+            retry_after_secs: 30,
+        };
+        let body = error_comment("review", &error, Some("job-42"));
+        assert!(body.contains("the AI provider rate-limited the request"));
+        assert!(body.contains("Request id: `job-42`"));
+        assert!(body.ends_with("</details>"));
+    }
+
     #[test]
     fn test_extract_pr_url() {
-        let payload = serde_json::json!({
+        let payload: PullRequestEvent = parse(serde_json::json!({
             "pull_request": {
                 "html_url": "https://github.com/owner/repo/pull/1"
             }
-        });
+        }));
         let url = extract_pr_url(&payload).unwrap();
         assert_eq!(url, "https://github.com/owner/repo/pull/1");
     }
@@ -993,9 +1481,30 @@ mod tests {
         assert!(!is_self_review_checked(body));
     }
 
+    #[test]
+    fn test_accepted_suggestion_edits() {
+        let marker = tools::accepted_suggestions::SUGGESTION_MARKER;
+        let before = format!("<!-- pr-agent:improve -->\n- [ ] **a**: Fix it {marker}\n");
+        let after = before.replace("- [ ]", "- [x]");
+        let payload = |author_type: &str, sender_type: &str, body: &str| -> IssueCommentEvent {
+            parse(serde_json::json!({
+                "comment": { "body": body, "user": { "type": author_type } },
+                "changes": { "body": { "from": before } },
+                "sender": { "login": "alice", "type": sender_type }
+            }))
+        };
+        assert_eq!(
+            accepted_suggestion_edits(&payload("Bot", "User", &after)),
+            vec!["**a**: Fix it"]
+        );
+        // Our own re-publish, and checklists copied into user comments.
+        assert!(accepted_suggestion_edits(&payload("Bot", "Bot", &after)).is_empty());
+        assert!(accepted_suggestion_edits(&payload("User", "User", &after)).is_empty());
+    }
+
     /// Helper: build a minimal PR payload for should_ignore_pr tests.
-    fn make_pr_payload(title: &str, author: &str) -> serde_json::Value {
-        serde_json::json!({
+    fn make_pr_payload(title: &str, author: &str) -> PullRequestEvent {
+        parse(serde_json::json!({
             "pull_request": {
                 "title": title,
                 "user": { "login": author },
@@ -1004,7 +1513,7 @@ mod tests {
                 "base": { "ref": "main" }
             },
             "repository": { "full_name": "owner/repo" }
-        })
+        }))
     }
 
     #[test]
@@ -1061,7 +1570,7 @@ mod tests {
         settings.config.ignore_repositories = vec![r"^org/internal-".into()];
 
         let mut payload = make_pr_payload("My PR", "user1");
-        payload["repository"]["full_name"] = serde_json::json!("org/internal-tools");
+        payload.repository.full_name = "org/internal-tools".into();
         assert!(should_ignore_pr(&settings, &payload));
 
         let payload = make_pr_payload("My PR", "user1"); // default: owner/repo
@@ -1074,16 +1583,14 @@ mod tests {
         settings.config.ignore_pr_labels = vec!["do-not-review".into(), "wip".into()];
 
         let mut payload = make_pr_payload("My PR", "user1");
-        payload["pull_request"]["labels"] = serde_json::json!([
+        payload.pull_request.labels = parse(serde_json::json!([
             { "name": "enhancement" },
             { "name": "do-not-review" }
-        ]);
+        ]));
         assert!(should_ignore_pr(&settings, &payload));
 
         let mut payload = make_pr_payload("My PR", "user1");
-        payload["pull_request"]["labels"] = serde_json::json!([
-            { "name": "enhancement" }
-        ]);
+        payload.pull_request.labels = parse(serde_json::json!([{ "name": "enhancement" }]));
         assert!(!should_ignore_pr(&settings, &payload));
     }
 
@@ -1093,7 +1600,7 @@ mod tests {
         settings.config.ignore_pr_source_branches = vec![r"^dependabot/".into()];
 
         let mut payload = make_pr_payload("My PR", "user1");
-        payload["pull_request"]["head"]["ref"] = serde_json::json!("dependabot/npm/lodash-4.17.21");
+        payload.pull_request.head.name = "dependabot/npm/lodash-4.17.21".into();
         assert!(should_ignore_pr(&settings, &payload));
 
         let payload = make_pr_payload("My PR", "user1"); // default: feature/test
@@ -1106,7 +1613,7 @@ mod tests {
         settings.config.ignore_pr_target_branches = vec![r"^release/".into()];
 
         let mut payload = make_pr_payload("My PR", "user1");
-        payload["pull_request"]["base"]["ref"] = serde_json::json!("release/v2.0");
+        payload.pull_request.base.name = "release/v2.0".into();
         assert!(should_ignore_pr(&settings, &payload));
 
         let payload = make_pr_payload("My PR", "user1"); // default: main
@@ -1114,29 +1621,66 @@ mod tests {
     }
 
     #[test]
-    fn test_check_pull_request_event_draft() {
-        let payload = serde_json::json!({
-            "pull_request": { "draft": true, "state": "open",
-                "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T01:00:00Z" }
-        });
-        assert!(!check_pull_request_event("opened", &payload));
+    fn test_draft_pr_commands() {
+        let mut settings = Settings::default();
+        let commands = settings.github_app.pr_commands.clone();
+
+        // ignore (default): drafts skipped, leaving draft runs everything
+        assert_eq!(
+            draft_pr_commands(&settings, &commands, "opened", true),
+            None
+        );
+        assert_eq!(
+            draft_pr_commands(&settings, &commands, "ready_for_review", false),
+            Some(commands.clone())
+        );
+
+        settings.github_app.handle_draft_prs = "describe_only".into();
+        assert_eq!(
+            draft_pr_commands(&settings, &commands, "opened", true),
+            Some(vec![
+                "/describe --pr_description.final_update_message=false".to_string()
+            ])
+        );
+        assert_eq!(
+            draft_pr_commands(&settings, &["/review".to_string()], "opened", true),
+            None
+        );
+        assert_eq!(
+            draft_pr_commands(&settings, &commands, "ready_for_review", false),
+            Some(commands.clone())
+        );
+
+        settings.github_app.handle_draft_prs = "full".into();
+        assert_eq!(
+            draft_pr_commands(&settings, &commands, "opened", true),
+            Some(commands.clone())
+        );
+        assert_eq!(
+            draft_pr_commands(&settings, &commands, "ready_for_review", false),
+            None
+        );
+        assert_eq!(
+            draft_pr_commands(&settings, &commands, "opened", false),
+            Some(commands.clone())
+        );
     }
 
     #[test]
     fn test_check_pull_request_event_closed() {
-        let payload = serde_json::json!({
+        let payload: PullRequestEvent = parse(serde_json::json!({
             "pull_request": { "draft": false, "state": "closed",
                 "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T01:00:00Z" }
-        });
+        }));
         assert!(!check_pull_request_event("opened", &payload));
     }
 
     #[test]
     fn test_check_pull_request_event_open_non_draft() {
-        let payload = serde_json::json!({
+        let payload: PullRequestEvent = parse(serde_json::json!({
             "pull_request": { "draft": false, "state": "open",
                 "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T01:00:00Z" }
-        });
+        }));
         assert!(check_pull_request_event("opened", &payload));
     }
 
@@ -1144,10 +1688,10 @@ mod tests {
     fn test_check_pull_request_event_sync_created_eq_updated() {
         // When created_at == updated_at, synchronize should be skipped
         // (avoids double-processing on initial PR creation)
-        let payload = serde_json::json!({
+        let payload: PullRequestEvent = parse(serde_json::json!({
             "pull_request": { "draft": false, "state": "open",
                 "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T00:00:00Z" }
-        });
+        }));
         assert!(!check_pull_request_event("synchronize", &payload));
         assert!(!check_pull_request_event("review_requested", &payload));
         // But opened should still be allowed
@@ -1156,23 +1700,23 @@ mod tests {
 
     #[test]
     fn test_check_pull_request_event_sync_different_timestamps() {
-        let payload = serde_json::json!({
+        let payload: PullRequestEvent = parse(serde_json::json!({
             "pull_request": { "draft": false, "state": "open",
                 "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-02T00:00:00Z" }
-        });
+        }));
         assert!(check_pull_request_event("synchronize", &payload));
     }
 
     #[test]
     fn test_extract_pr_url_from_issue() {
-        let payload = serde_json::json!({
+        let payload: IssueCommentEvent = parse(serde_json::json!({
             "issue": {
                 "html_url": "https://github.com/owner/repo/pull/1",
                 "pull_request": {
                     "html_url": "https://github.com/owner/repo/pull/1"
                 }
             }
-        });
+        }));
         let url = extract_pr_url_from_issue(&payload).unwrap();
         assert_eq!(url, "https://github.com/owner/repo/pull/1");
     }
@@ -1292,6 +1836,23 @@ num_max_findings = 3
         assert_eq!(s.pr_reviewer.extra_instructions, "Org rule");
     }
 
+    #[tokio::test]
+    async fn test_fetch_scoped_settings_with_repo_prompt_files() {
+        use crate::testing::mock_git::MockGitProvider;
+        let provider = MockGitProvider::new()
+            .with_repo_file(
+                ".pr_agent/prompts/review.toml",
+                "[pr_review_prompt]\nsystem = \"Be terse.\"\nuser = \"{{ diff }}\"\n",
+            )
+            .with_repo_file(
+                "docs/review.toml",
+                "[pr_review_prompt]\nsystem = \"Ignored\"\n",
+            );
+        let base = Settings::default();
+        let scoped = fetch_scoped_settings(&provider, &base).await.unwrap();
+        assert_eq!(scoped.pr_review_prompt.system, "Be terse.");
+    }
+
     #[tokio::test]
     async fn test_fetch_scoped_settings_returns_none_when_no_overrides() {
         use crate::testing::mock_git::MockGitProvider;
@@ -1316,7 +1877,7 @@ num_max_findings = 3
 
     #[test]
     fn test_extract_pr_url_missing_field() {
-        let payload = serde_json::json!({ "pull_request": {} });
+        let payload: PullRequestEvent = parse(serde_json::json!({ "pull_request": {} }));
         let result = extract_pr_url(&payload);
         assert!(result.is_err());
         assert!(
@@ -1330,19 +1891,19 @@ num_max_findings = 3
     #[test]
     fn test_extract_pr_url_from_issue_fallback() {
         // When pull_request.html_url is missing, should fallback to issue.html_url
-        let payload = serde_json::json!({
+        let payload: IssueCommentEvent = parse(serde_json::json!({
             "issue": {
                 "html_url": "https://github.com/owner/repo/pull/42",
                 "pull_request": {}
             }
-        });
+        }));
         let url = extract_pr_url_from_issue(&payload).unwrap();
         assert_eq!(url, "https://github.com/owner/repo/pull/42");
     }
 
     #[test]
     fn test_extract_pr_url_from_issue_missing_both() {
-        let payload = serde_json::json!({ "issue": {} });
+        let payload: IssueCommentEvent = parse(serde_json::json!({ "issue": {} }));
         let result = extract_pr_url_from_issue(&payload);
         assert!(result.is_err());
     }
@@ -1374,7 +1935,7 @@ num_max_findings = 3
     }
 
     /// dispatch_event should return Ok(()) without attempting network calls
-    /// when the PR is a draft — the draft check short-circuits before run_commands.
+    /// when the PR is a draft and drafts are ignored (the default).
     #[tokio::test]
     async fn test_dispatch_event_skips_draft_pr() {
         let payload = serde_json::json!({
@@ -1479,6 +2040,19 @@ num_max_findings = 3
         );
     }
 
+    #[tokio::test]
+    async fn test_dispatch_event_rejects_mistyped_payload() {
+        let payload = serde_json::json!({
+            "action": "opened",
+            "pull_request": {
+                "html_url": "https://github.com/owner/repo/pull/1",
+                "draft": "yes"
+            }
+        });
+        let result = dispatch_event("pull_request", "opened", &payload).await;
+        assert!(matches!(result, Err(PrAgentError::Json(_))));
+    }
+
     #[test]
     fn test_fold_comment_body_preserves_marker_and_content() {
         let body = "<!-- pr-agent:improve -->\n## PR Code Suggestions ✨\n\n| Category | Suggestion | Score |\n| --- | --- | --- |\n| bug | Fix null check | Important |\n\n- [ ]  I reviewed <!-- approve and fold suggestions self-review -->";
@@ -1532,7 +2106,7 @@ num_max_findings = 3
 
     #[test]
     fn test_handle_line_comments_basic() {
-        let payload = serde_json::json!({
+        let payload: PullRequestReviewCommentEvent = parse(serde_json::json!({
             "comment": {
                 "id": 12345,
                 "line": 20,
@@ -1541,9 +2115,9 @@ num_max_findings = 3
                 "path": "src/main.rs",
                 "diff_hunk": "@@ -10,5 +10,7 @@ fn main()"
             }
-        });
+        }));
 
-        let result = handle_line_comments(&payload, "/ask What does this do?");
+        let result = handle_line_comments(&payload.comment, "/ask What does this do?");
         assert!(result.starts_with("/ask_line"));
         assert!(result.contains("--line_start=15"));
         assert!(result.contains("--line_end=20"));
@@ -1555,7 +2129,7 @@ num_max_findings = 3
 
     #[test]
     fn test_handle_line_comments_no_start_line() {
-        let payload = serde_json::json!({
+        let payload: PullRequestReviewCommentEvent = parse(serde_json::json!({
             "comment": {
                 "id": 100,
                 "line": 42,
@@ -1563,9 +2137,9 @@ num_max_findings = 3
                 "side": "LEFT",
                 "path": "lib.rs"
             }
-        });
+        }));
 
-        let result = handle_line_comments(&payload, "/ask Why was this removed?");
+        let result = handle_line_comments(&payload.comment, "/ask Why was this removed?");
         // When start_line is null, it should default to end_line
         assert!(result.contains("--line_start=42"));
         assert!(result.contains("--line_end=42"));
@@ -1575,7 +2149,7 @@ num_max_findings = 3
     #[test]
     fn test_handle_line_comments_question_containing_ask() {
         // Question text contains "/ask" — only the leading one should be stripped
-        let payload = serde_json::json!({
+        let payload: PullRequestReviewCommentEvent = parse(serde_json::json!({
             "comment": {
                 "id": 999,
                 "line": 5,
@@ -1583,9 +2157,9 @@ num_max_findings = 3
                 "side": "RIGHT",
                 "path": "main.rs"
             }
-        });
+        }));
 
-        let result = handle_line_comments(&payload, "/ask why does /ask appear here?");
+        let result = handle_line_comments(&payload.comment, "/ask why does /ask appear here?");
         assert!(
             result.contains("why does /ask appear here?"),
             "inner /ask should be preserved, got: {result}"
@@ -1609,12 +2183,13 @@ num_max_findings = 3
         assert_eq!(compute_hours_between("", ""), 0.0);
     }
 
-    #[test]
-    fn test_handle_closed_pr_merged() {
-        // Should not panic, just logs
-        let payload = serde_json::json!({
+    #[tokio::test]
+    async fn test_handle_closed_pr_merged() {
+        let payload: PullRequestEvent = parse(serde_json::json!({
+            "repository": { "full_name": "o/r" },
             "pull_request": {
                 "html_url": "https://github.com/o/r/pull/1",
+                "number": 1,
                 "title": "Add feature",
                 "merged": true,
                 "commits": 3,
@@ -1628,26 +2203,44 @@ num_max_findings = 3
                 "created_at": "2025-01-01T00:00:00Z",
                 "merged_at": "2025-01-02T12:00:00Z"
             }
-        });
-        // Just verify it doesn't panic
-        handle_closed_pr(&payload);
+        }));
+        let record = closed_pr_record(&payload);
+        assert!(record.merged);
+        assert_eq!(record.merged_by, "reviewer");
+        assert_eq!(record.time_to_merge_hours, 36.0);
+        assert_eq!(record.closed_at, "2025-01-02T12:00:00Z");
+
+        let folder =
+            std::env::temp_dir().join(format!("pr-agent-closed-pr-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        handle_closed_pr(&payload, folder.to_str().unwrap()).await;
+        assert_eq!(
+            crate::analytics::load_closed_prs(&folder).unwrap(),
+            vec![record]
+        );
+        let _ = std::fs::remove_dir_all(&folder);
     }
 
-    #[test]
-    fn test_handle_closed_pr_not_merged() {
-        let payload = serde_json::json!({
+    #[tokio::test]
+    async fn test_handle_closed_pr_not_merged() {
+        let payload: PullRequestEvent = parse(serde_json::json!({
             "pull_request": {
-                "merged": false
+                "merged": false,
+                "closed_at": "2025-01-03T00:00:00Z"
             }
-        });
-        // Should return early without panic
-        handle_closed_pr(&payload);
+        }));
+        let record = closed_pr_record(&payload);
+        assert!(!record.merged);
+        assert_eq!(record.time_to_merge_hours, 0.0);
+        assert_eq!(record.closed_at, "2025-01-03T00:00:00Z");
+        // No analytics folder: only logs
+        handle_closed_pr(&payload, "").await;
     }
 
     // ── Unknown command early-rejection tests ────────────────────────
 
     /// dispatch_event should silently ignore unknown `/` commands in issue
-    /// comments — no provider creation, no eyes reaction, no error.
+    /// comments — no provider creation, no ack reaction, no error.
     #[tokio::test]
     async fn test_dispatch_event_ignores_unknown_slash_command() {
         let payload = serde_json::json!({
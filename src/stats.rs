@@ -0,0 +1,604 @@
+//! PR metrics store (`[metrics_store]`) and the `pr-agent stats` report.
+//!
+//! With `metrics_store.database_url` set, closed PRs from the GitHub
+//! `pull_request` webhook are recorded with their merge stats, and each tool
+//! run adds what it published: effort and findings of a `/review`, counts of
+//! `/improve` suggestions, and suggestions checked as applied. The store is a
+//! SQLite file or a Postgres database, reached through sqlx in builds with
+//! the `metrics-store` feature. Recording failures are logged and never fail
+//! the run.
+
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::analytics::ClosedPrRecord;
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::output::review_formatter::{extract_effort_score, is_value_no, yaml_value_to_string};
+
+/// Something to record in the metrics store.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricEvent {
+    /// A PR closed, with or without merge.
+    Closed(ClosedPrRecord),
+    /// A full `/review` was published.
+    Review {
+        repo: String,
+        pr_number: u64,
+        head_sha: String,
+        /// Estimated effort to review, 1-5.
+        effort: Option<u32>,
+        findings: usize,
+        security_concerns: bool,
+    },
+    /// `/improve` published suggestions.
+    Suggestions {
+        repo: String,
+        pr_number: u64,
+        head_sha: String,
+        count: usize,
+        /// Suggestions scored at or above `new_score_mechanism_th_high`.
+        critical: usize,
+    },
+    /// Suggestions checked as applied in the `/improve` comment.
+    Accepted {
+        repo: String,
+        pr_number: u64,
+        /// Login of the user who checked them.
+        user: String,
+        count: usize,
+    },
+}
+
+impl MetricEvent {
+    /// The review event for the parsed review YAML.
+    pub fn review(data: &serde_yaml_ng::Value, repo: &str, pr_number: u64, head_sha: &str) -> Self {
+        let review = data.get("review").unwrap_or(data);
+        let effort = review
+            .get("estimated_effort_to_review_[1-5]")
+            .or_else(|| review.get("estimated_effort_to_review"))
+            .map(extract_effort_score)
+            .filter(|&e| e > 0)
+            .map(u32::from);
+        let findings = review
+            .get("key_issues_to_review")
+            .and_then(|v| v.as_sequence())
+            .map_or(0, |s| s.len());
+        let security_concerns = review
+            .get("security_concerns")
+            .is_some_and(|v| !is_value_no(&yaml_value_to_string(v)));
+        Self::Review {
+            repo: repo.to_string(),
+            pr_number,
+            head_sha: head_sha.to_string(),
+            effort,
+            findings,
+            security_concerns,
+        }
+    }
+}
+
+/// Totals for one repository over the period queried.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RepoStats {
+    pub repo: String,
+    pub merged_prs: u64,
+    pub avg_time_to_merge_hours: Option<f64>,
+    pub reviews: u64,
+    pub avg_review_effort: Option<f64>,
+    pub review_findings: u64,
+    pub suggestion_runs: u64,
+    pub suggestions: u64,
+    pub critical_suggestions: u64,
+    pub accepted_suggestions: u64,
+}
+
+impl RepoStats {
+    /// Share of suggestions checked as applied, in percent.
+    pub fn acceptance_rate(&self) -> Option<f64> {
+        (self.suggestions > 0)
+            .then(|| self.accepted_suggestions as f64 * 100.0 / self.suggestions as f64)
+    }
+}
+
+/// Record `event` when `metrics_store.database_url` is set.
+pub async fn record(event: &MetricEvent) {
+    let settings = get_settings();
+    let url = settings.metrics_store.database_url.trim();
+    if url.is_empty() {
+        return;
+    }
+
+    #[cfg(feature = "metrics-store")]
+    {
+        let recorded = match sql::shared_pool(url) {
+            Ok(pool) => sql::insert(pool, event).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = recorded {
+            tracing::warn!(error = %e, "failed to record metrics");
+        }
+    }
+
+    #[cfg(not(feature = "metrics-store"))]
+    {
+        let _ = event;
+        static WARNED: std::sync::Once = std::sync::Once::new();
+        WARNED.call_once(|| {
+            tracing::warn!(
+                "metrics_store.database_url is set, but this build has no `metrics-store` feature; metrics are not recorded"
+            );
+        });
+    }
+}
+
+/// Per-repository totals since `since` (RFC 3339), for `repo` or every
+/// repository.
+pub async fn query(
+    database_url: &str,
+    since: &str,
+    repo: Option<&str>,
+) -> Result<Vec<RepoStats>, PrAgentError> {
+    if database_url.trim().is_empty() {
+        return Err(PrAgentError::Other(
+            "metrics_store.database_url is not set".into(),
+        ));
+    }
+
+    #[cfg(feature = "metrics-store")]
+    {
+        let pool = sql::connect(database_url.trim())?;
+        sql::query(&pool, since, repo.unwrap_or_default()).await
+    }
+
+    #[cfg(not(feature = "metrics-store"))]
+    {
+        let _ = (since, repo);
+        Err(PrAgentError::Unsupported(
+            "this build has no `metrics-store` feature; rebuild with `--features metrics-store`"
+                .into(),
+        ))
+    }
+}
+
+/// The report printed by `pr-agent stats`.
+pub fn render(stats: &[RepoStats], days: u32) -> String {
+    let mut out = format!("PR metrics for the last {days} days\n");
+    if stats.is_empty() {
+        out.push_str("\nNothing recorded.\n");
+    }
+    for s in stats {
+        let _ = write!(out, "\n{}\n  Merged PRs: {}", s.repo, s.merged_prs);
+        if let Some(hours) = s.avg_time_to_merge_hours {
+            let _ = write!(out, " (avg {hours:.1} h to merge)");
+        }
+        let _ = write!(out, "\n  Reviews: {}", s.reviews);
+        if let Some(effort) = s.avg_review_effort {
+            let _ = write!(out, " (avg effort {effort:.1}/5)");
+        }
+        let _ = write!(
+            out,
+            ", {} findings\n  Suggestions: {} in {} /improve runs ({} critical), {} accepted",
+            s.review_findings,
+            s.suggestions,
+            s.suggestion_runs,
+            s.critical_suggestions,
+            s.accepted_suggestions
+        );
+        if let Some(rate) = s.acceptance_rate() {
+            let _ = write!(out, " ({rate:.0}%)");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(feature = "metrics-store")]
+mod sql {
+    use std::collections::BTreeMap;
+    use std::sync::OnceLock;
+
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use sqlx::any::{AnyPoolOptions, install_default_drivers};
+    use sqlx::{AnyPool, Executor, Row};
+
+    use super::{MetricEvent, RepoStats};
+    use crate::error::PrAgentError;
+
+    /// Tables, created on every new connection. The SQL is shared by SQLite
+    /// and Postgres; times are RFC 3339 text.
+    const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS closed_prs (
+    repo TEXT NOT NULL,
+    pr_number BIGINT NOT NULL,
+    title TEXT NOT NULL,
+    merged BOOLEAN NOT NULL,
+    merged_by TEXT NOT NULL,
+    additions BIGINT NOT NULL,
+    deletions BIGINT NOT NULL,
+    time_to_merge_hours DOUBLE PRECISION NOT NULL,
+    closed_at TEXT NOT NULL,
+    PRIMARY KEY (repo, pr_number)
+);
+CREATE TABLE IF NOT EXISTS reviews (
+    repo TEXT NOT NULL,
+    pr_number BIGINT NOT NULL,
+    head_sha TEXT NOT NULL,
+    effort BIGINT,
+    findings BIGINT NOT NULL,
+    security_concerns BOOLEAN NOT NULL,
+    created_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS suggestion_runs (
+    repo TEXT NOT NULL,
+    pr_number BIGINT NOT NULL,
+    head_sha TEXT NOT NULL,
+    suggestions BIGINT NOT NULL,
+    critical BIGINT NOT NULL,
+    created_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS accepted_suggestions (
+    repo TEXT NOT NULL,
+    pr_number BIGINT NOT NULL,
+    accepted_by TEXT NOT NULL,
+    accepted BIGINT NOT NULL,
+    created_at TEXT NOT NULL
+);
+"#;
+
+    /// The pool for `url`, opened on first use and kept for the process.
+    static POOL: OnceLock<Result<AnyPool, String>> = OnceLock::new();
+
+    pub fn shared_pool(url: &str) -> Result<&'static AnyPool, PrAgentError> {
+        POOL.get_or_init(|| connect(url).map_err(|e| e.to_string()))
+            .as_ref()
+            .map_err(|e| PrAgentError::Other(e.clone()))
+    }
+
+    /// A pool for `url` (`sqlite://path?mode=rwc` or `postgres://...`).
+    /// Connections are opened when first used.
+    pub fn connect(url: &str) -> Result<AnyPool, PrAgentError> {
+        install_default_drivers();
+        AnyPoolOptions::new()
+            .max_connections(4)
+            .after_connect(|conn, _| {
+                Box::pin(async move { conn.execute(SCHEMA).await.map(|_| ()) })
+            })
+            .connect_lazy(url)
+            .map_err(PrAgentError::from)
+    }
+
+    /// `value` (RFC 3339) as UTC with a `Z` suffix, so stored times compare as
+    /// text; now when it doesn't parse.
+    fn normalize_time(value: &str) -> String {
+        let time = DateTime::parse_from_rfc3339(value)
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+        time.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+
+    /// Fold rows keyed by repository into one [`RepoStats`] per repository.
+    fn merge_rows(
+        stats: &mut BTreeMap<String, RepoStats>,
+        repo: String,
+        update: impl FnOnce(&mut RepoStats),
+    ) {
+        let entry = stats.entry(repo.clone()).or_insert_with(|| RepoStats {
+            repo,
+            ..Default::default()
+        });
+        update(entry);
+    }
+
+    pub async fn insert(pool: &AnyPool, event: &MetricEvent) -> Result<(), PrAgentError> {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let query = match event {
+            MetricEvent::Closed(pr) => sqlx::query(
+                "INSERT INTO closed_prs (repo, pr_number, title, merged, merged_by, additions, \
+                 deletions, time_to_merge_hours, closed_at) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+                 ON CONFLICT (repo, pr_number) DO UPDATE SET title = excluded.title, \
+                 merged = excluded.merged, merged_by = excluded.merged_by, \
+                 additions = excluded.additions, deletions = excluded.deletions, \
+                 time_to_merge_hours = excluded.time_to_merge_hours, \
+                 closed_at = excluded.closed_at",
+            )
+            .bind(pr.repo.clone())
+            .bind(pr.pr_number as i64)
+            .bind(pr.title.clone())
+            .bind(pr.merged)
+            .bind(pr.merged_by.clone())
+            .bind(pr.additions as i64)
+            .bind(pr.deletions as i64)
+            .bind(pr.time_to_merge_hours)
+            .bind(normalize_time(&pr.closed_at)),
+            MetricEvent::Review {
+                repo,
+                pr_number,
+                head_sha,
+                effort,
+                findings,
+                security_concerns,
+            } => sqlx::query(
+                "INSERT INTO reviews (repo, pr_number, head_sha, effort, findings, \
+                 security_concerns, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            )
+            .bind(repo.clone())
+            .bind(*pr_number as i64)
+            .bind(head_sha.clone())
+            .bind(effort.map(i64::from))
+            .bind(*findings as i64)
+            .bind(*security_concerns)
+            .bind(now),
+            MetricEvent::Suggestions {
+                repo,
+                pr_number,
+                head_sha,
+                count,
+                critical,
+            } => sqlx::query(
+                "INSERT INTO suggestion_runs (repo, pr_number, head_sha, suggestions, critical, \
+                 created_at) VALUES ($1, $2, $3, $4, $5, $6)",
+            )
+            .bind(repo.clone())
+            .bind(*pr_number as i64)
+            .bind(head_sha.clone())
+            .bind(*count as i64)
+            .bind(*critical as i64)
+            .bind(now),
+            MetricEvent::Accepted {
+                repo,
+                pr_number,
+                user,
+                count,
+            } => sqlx::query(
+                "INSERT INTO accepted_suggestions (repo, pr_number, accepted_by, accepted, \
+                 created_at) VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(repo.clone())
+            .bind(*pr_number as i64)
+            .bind(user.clone())
+            .bind(*count as i64)
+            .bind(now),
+        };
+        query.execute(pool).await.map_err(PrAgentError::from)?;
+        Ok(())
+    }
+
+    pub async fn query(
+        pool: &AnyPool,
+        since: &str,
+        repo: &str,
+    ) -> Result<Vec<RepoStats>, PrAgentError> {
+        let since = normalize_time(since);
+        let rows = |sql: &'static str| {
+            let since = since.clone();
+            async move {
+                sqlx::query(sql)
+                    .bind(since)
+                    .bind(repo.to_string())
+                    .fetch_all(pool)
+                    .await
+                    .map_err(PrAgentError::from)
+            }
+        };
+        let mut stats = BTreeMap::new();
+
+        for row in rows(
+            "SELECT repo, COUNT(*), AVG(time_to_merge_hours) FROM closed_prs \
+             WHERE merged AND closed_at >= $1 AND ($2 = '' OR repo = $2) GROUP BY repo",
+        )
+        .await?
+        {
+            let (merged, hours): (i64, Option<f64>) = (row.try_get(1)?, row.try_get(2)?);
+            merge_rows(&mut stats, row.try_get(0)?, |s| {
+                s.merged_prs = merged as u64;
+                s.avg_time_to_merge_hours = hours;
+            });
+        }
+        for row in rows(
+            "SELECT repo, COUNT(*), AVG(CAST(effort AS DOUBLE PRECISION)), \
+             CAST(COALESCE(SUM(findings), 0) AS BIGINT) FROM reviews \
+             WHERE created_at >= $1 AND ($2 = '' OR repo = $2) GROUP BY repo",
+        )
+        .await?
+        {
+            let (reviews, effort, findings): (i64, Option<f64>, i64) =
+                (row.try_get(1)?, row.try_get(2)?, row.try_get(3)?);
+            merge_rows(&mut stats, row.try_get(0)?, |s| {
+                s.reviews = reviews as u64;
+                s.avg_review_effort = effort;
+                s.review_findings = findings as u64;
+            });
+        }
+        for row in rows(
+            "SELECT repo, COUNT(*), CAST(COALESCE(SUM(suggestions), 0) AS BIGINT), \
+             CAST(COALESCE(SUM(critical), 0) AS BIGINT) FROM suggestion_runs \
+             WHERE created_at >= $1 AND ($2 = '' OR repo = $2) GROUP BY repo",
+        )
+        .await?
+        {
+            let (runs, suggestions, critical): (i64, i64, i64) =
+                (row.try_get(1)?, row.try_get(2)?, row.try_get(3)?);
+            merge_rows(&mut stats, row.try_get(0)?, |s| {
+                s.suggestion_runs = runs as u64;
+                s.suggestions = suggestions as u64;
+                s.critical_suggestions = critical as u64;
+            });
+        }
+        for row in rows(
+            "SELECT repo, CAST(COALESCE(SUM(accepted), 0) AS BIGINT) FROM accepted_suggestions \
+             WHERE created_at >= $1 AND ($2 = '' OR repo = $2) GROUP BY repo",
+        )
+        .await?
+        {
+            let accepted: i64 = row.try_get(1)?;
+            merge_rows(&mut stats, row.try_get(0)?, |s| {
+                s.accepted_suggestions = accepted as u64;
+            });
+        }
+        Ok(stats.into_values().collect())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::analytics::ClosedPrRecord;
+
+        #[test]
+        fn test_normalize_time() {
+            assert_eq!(
+                normalize_time("2026-01-06T10:00:00+02:00"),
+                "2026-01-06T08:00:00Z"
+            );
+            assert_eq!(
+                normalize_time("2026-01-06T08:00:00Z"),
+                "2026-01-06T08:00:00Z"
+            );
+        }
+
+        #[tokio::test]
+        async fn test_insert_and_query() {
+            let path =
+                std::env::temp_dir().join(format!("pr-agent-stats-{}.db", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            let pool = connect(&format!("sqlite://{}?mode=rwc", path.display())).unwrap();
+
+            let closed = |pr_number: u64, merged: bool, hours: f64| {
+                MetricEvent::Closed(ClosedPrRecord {
+                    repo: "o/r".into(),
+                    pr_number,
+                    title: "Add feature".into(),
+                    url: String::new(),
+                    merged,
+                    merged_by: "alice".into(),
+                    additions: 10,
+                    deletions: 2,
+                    time_to_merge_hours: hours,
+                    closed_at: "2026-01-06T10:00:00+02:00".into(),
+                })
+            };
+            for event in [
+                closed(1, true, 4.0),
+                closed(2, true, 8.0),
+                // A redelivered webhook replaces the PR's row
+                closed(2, true, 6.0),
+                closed(3, false, 0.0),
+                MetricEvent::Suggestions {
+                    repo: "o/r".into(),
+                    pr_number: 1,
+                    head_sha: "abc".into(),
+                    count: 4,
+                    critical: 1,
+                },
+                MetricEvent::Accepted {
+                    repo: "o/r".into(),
+                    pr_number: 1,
+                    user: "bob".into(),
+                    count: 2,
+                },
+                MetricEvent::Review {
+                    repo: "o/other".into(),
+                    pr_number: 9,
+                    head_sha: "def".into(),
+                    effort: Some(3),
+                    findings: 2,
+                    security_concerns: false,
+                },
+            ] {
+                insert(&pool, &event).await.unwrap();
+            }
+
+            let stats = query(&pool, "2026-01-01T00:00:00Z", "").await.unwrap();
+            assert_eq!(
+                stats,
+                vec![
+                    RepoStats {
+                        repo: "o/other".into(),
+                        reviews: 1,
+                        avg_review_effort: Some(3.0),
+                        review_findings: 2,
+                        ..Default::default()
+                    },
+                    RepoStats {
+                        repo: "o/r".into(),
+                        merged_prs: 2,
+                        avg_time_to_merge_hours: Some(5.0),
+                        suggestion_runs: 1,
+                        suggestions: 4,
+                        critical_suggestions: 1,
+                        accepted_suggestions: 2,
+                        ..Default::default()
+                    },
+                ]
+            );
+            let stats = query(&pool, "2026-01-01T00:00:00Z", "o/other")
+                .await
+                .unwrap();
+            assert_eq!(stats.len(), 1);
+            // Merges before the period are left out
+            let stats = query(&pool, "2026-01-07T00:00:00Z", "o/r").await.unwrap();
+            assert_eq!(stats[0].merged_prs, 0);
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review_event() {
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(
+            r#"
+review:
+  estimated_effort_to_review_[1-5]: |
+    4, because the parser changed
+  security_concerns: No
+  key_issues_to_review:
+    - issue_header: A
+    - issue_header: B
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            MetricEvent::review(&data, "o/r", 7, "abc"),
+            MetricEvent::Review {
+                repo: "o/r".into(),
+                pr_number: 7,
+                head_sha: "abc".into(),
+                effort: Some(4),
+                findings: 2,
+                security_concerns: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let stats = RepoStats {
+            repo: "o/r".into(),
+            merged_prs: 2,
+            avg_time_to_merge_hours: Some(5.0),
+            reviews: 3,
+            avg_review_effort: Some(2.5),
+            review_findings: 4,
+            suggestion_runs: 2,
+            suggestions: 8,
+            critical_suggestions: 1,
+            accepted_suggestions: 2,
+        };
+        assert_eq!(
+            render(&[stats], 30),
+            "PR metrics for the last 30 days\n\no/r\n  Merged PRs: 2 (avg 5.0 h to merge)\n  \
+             Reviews: 3 (avg effort 2.5/5), 4 findings\n  \
+             Suggestions: 8 in 2 /improve runs (1 critical), 2 accepted (25%)\n"
+        );
+        assert_eq!(
+            render(&[], 7),
+            "PR metrics for the last 7 days\n\nNothing recorded.\n"
+        );
+    }
+}
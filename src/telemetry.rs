@@ -0,0 +1,125 @@
+//! Log output and OpenTelemetry trace export (`[telemetry]`).
+//!
+//! Logs go to stderr, filtered by `RUST_LOG`. Built with the `otel` feature
+//! and with `telemetry.otlp_endpoint` set, the spans of every run are also
+//! exported over OTLP/HTTP: `request` (see [`crate::request_context`]) with
+//! `tool`, `ai_request`, `github_request` and `gitlab_request` beneath it.
+//! Export is sampled per request and doesn't depend on `RUST_LOG`.
+
+use std::collections::HashMap;
+
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::config::loader::load_settings;
+
+/// Keeps the trace exporter alive; [`Telemetry::shutdown`] flushes it.
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+/// Install the global subscriber. `[telemetry]` is read from the settings
+/// files and environment, as command-line overrides aren't parsed yet.
+pub fn init() -> Telemetry {
+    let config = load_settings(&HashMap::new(), None, None)
+        .map(|s| s.telemetry)
+        .unwrap_or_default();
+    // Logs go to stderr so `--output json` keeps stdout machine-readable.
+    let logs = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(EnvFilter::from_default_env());
+    let endpoint = config.otlp_endpoint.trim();
+
+    #[cfg(feature = "otel")]
+    {
+        let exporter = (!endpoint.is_empty()).then(|| otel::provider(endpoint, &config));
+        let provider = exporter.as_ref().and_then(|p| p.as_ref().ok()).cloned();
+        let traces = provider.as_ref().map(otel::layer);
+        tracing_subscriber::registry()
+            .with(traces)
+            .with(logs)
+            .init();
+        match exporter {
+            Some(Ok(_)) => tracing::info!(endpoint, "exporting traces over OTLP"),
+            Some(Err(e)) => {
+                tracing::warn!(endpoint, error = %e, "failed to create the OTLP exporter, traces are not exported");
+            }
+            None => {}
+        }
+        Telemetry { provider }
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        tracing_subscriber::registry().with(logs).init();
+        if !endpoint.is_empty() {
+            tracing::warn!(
+                endpoint,
+                "telemetry.otlp_endpoint is set, but this build has no `otel` feature; traces are not exported"
+            );
+        }
+        Telemetry {}
+    }
+}
+
+impl Telemetry {
+    /// Export the spans still buffered. Call before the process exits.
+    pub fn shutdown(self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider
+            && let Err(e) = provider.shutdown()
+        {
+            tracing::warn!(error = %e, "failed to flush traces");
+        }
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use tracing::Subscriber;
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::filter::LevelFilter;
+    use tracing_subscriber::registry::LookupSpan;
+
+    use crate::config::types::TelemetryConfig;
+
+    /// A batching OTLP/HTTP exporter. Requests are sampled as a whole: the
+    /// `request` span decides, the spans beneath it follow.
+    pub fn provider(
+        endpoint: &str,
+        config: &TelemetryConfig,
+    ) -> Result<SdkTracerProvider, opentelemetry_otlp::ExporterBuildError> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()?;
+        Ok(SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                config.sample_ratio,
+            ))))
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(config.service_name.clone())
+                    .build(),
+            )
+            .build())
+    }
+
+    /// Spans at info level and above, whatever `RUST_LOG` says.
+    pub fn layer<S>(provider: &SdkTracerProvider) -> impl Layer<S> + use<S>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer("pr-agent"))
+            .with_filter(LevelFilter::INFO)
+    }
+}
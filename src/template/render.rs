@@ -25,6 +25,9 @@ pub struct RenderedPrompt {
 /// Takes ownership of `vars` to avoid cloning large Values (e.g. the diff
 /// string, which can be 100 KB+). The context Value is built once from
 /// the owned map and shared across both template renders via cheap Arc clone.
+///
+/// A repo-overridden template that fails to render falls back to the
+/// built-in one it replaced.
 pub fn render_prompt(
     template: &PromptTemplate,
     vars: HashMap<String, Value>,
@@ -35,8 +38,26 @@ pub fn render_prompt(
     // Value::clone() is cheap (Arc-based internally).
     let ctx = Value::from_iter(vars);
 
-    let system = render_template(env, "system", &template.system, &ctx)?;
-    let user = render_template(env, "user", &template.user, &ctx)?;
+    match (
+        render_pair(env, template, &ctx),
+        template.builtin.as_deref(),
+    ) {
+        (Err(e), Some(builtin)) => {
+            tracing::warn!(error = %e, "custom prompt template failed to render, using the built-in one");
+            render_pair(env, builtin, &ctx)
+        }
+        (result, _) => result,
+    }
+}
+
+/// Render both halves of a template pair.
+fn render_pair(
+    env: &Environment,
+    template: &PromptTemplate,
+    ctx: &Value,
+) -> Result<RenderedPrompt, PrAgentError> {
+    let system = render_template(env, "system", &template.system, ctx)?;
+    let user = render_template(env, "user", &template.user, ctx)?;
 
     Ok(RenderedPrompt { system, user })
 }
@@ -66,6 +87,7 @@ pub fn render_prompt_strings(
     let template = PromptTemplate {
         system: system_template.to_string(),
         user: user_template.to_string(),
+        ..Default::default()
     };
     render_prompt(&template, vars)
 }
@@ -79,6 +101,7 @@ mod tests {
         let template = PromptTemplate {
             system: "Review PR titled '{{ title }}' on branch '{{ branch }}'.".into(),
             user: "Diff:\n{{ diff }}".into(),
+            ..Default::default()
         };
 
         let mut vars = HashMap::new();
@@ -97,6 +120,7 @@ mod tests {
         let template = PromptTemplate {
             system: "{%- if extra_instructions %}Extra: {{ extra_instructions }}{% endif %}".into(),
             user: "Hello".into(),
+            ..Default::default()
         };
 
         // With extra_instructions set
@@ -119,6 +143,7 @@ mod tests {
         let template = PromptTemplate {
             system: "{{ undefined_var }}".into(),
             user: "".into(),
+            ..Default::default()
         };
 
         let vars = HashMap::new();
@@ -126,11 +151,31 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_render_override_falls_back_to_builtin() {
+        let template = PromptTemplate {
+            system: "{{ undefined_var }}".into(),
+            user: "{{ diff }}".into(),
+            builtin: Some(Box::new(PromptTemplate {
+                system: "Built-in".into(),
+                user: "Diff: {{ diff }}".into(),
+                ..Default::default()
+            })),
+        };
+
+        let mut vars = HashMap::new();
+        vars.insert("diff".into(), Value::from("+x"));
+        let result = render_prompt(&template, vars).unwrap();
+        assert_eq!(result.system, "Built-in");
+        assert_eq!(result.user, "Diff: +x");
+    }
+
     #[test]
     fn test_render_list_iteration() {
         let template = PromptTemplate {
             system: "".into(),
             user: "{%- for item in items %}{{ item }}\n{% endfor %}".into(),
+            ..Default::default()
         };
 
         let mut vars = HashMap::new();
@@ -147,6 +192,7 @@ mod tests {
         let template = PromptTemplate {
             system: "".into(),
             user: "{{ diff|trim }}".into(),
+            ..Default::default()
         };
 
         let mut vars = HashMap::new();
@@ -162,6 +208,7 @@ mod tests {
         let template = PromptTemplate {
             system: "Title: {{ title }}".into(),
             user: "Branch: {{ branch }}".into(),
+            ..Default::default()
         };
 
         let mut vars = HashMap::new();
@@ -226,6 +273,11 @@ mod tests {
         vars.insert("date".into(), Value::from("2025-01-15"));
         vars.insert("best_practices_content".into(), Value::from(""));
         vars.insert("repo_metadata".into(), Value::from(""));
+        vars.insert("describe_context".into(), Value::from(""));
+        vars.insert("planning_context".into(), Value::from(""));
+        vars.insert("repo_context".into(), Value::from(""));
+        vars.insert("checklist".into(), Value::from(Vec::<String>::new()));
+        vars.insert("codeowners".into(), Value::from(""));
 
         let result = render_prompt(&settings.pr_review_prompt, vars).unwrap();
 
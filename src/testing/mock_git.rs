@@ -7,6 +7,9 @@ use crate::error::PrAgentError;
 use crate::git::GitProvider;
 use crate::git::types::*;
 
+/// Head SHA reported by `MockGitProvider::get_pr_head_sha`.
+pub const MOCK_HEAD_SHA: &str = "0123456789abcdef0123456789abcdef01234567";
+
 /// Captured calls made to the mock provider, for test assertions.
 #[derive(Debug, Default)]
 #[allow(dead_code)]
@@ -19,6 +22,22 @@ pub struct MockCalls {
     pub inline_comments: Vec<Vec<InlineComment>>,
     pub edited_comments: Vec<(String, String)>,
     pub auto_approvals: Vec<()>,
+    pub check_runs: Vec<CheckRunReport>,
+    /// `(base, head)` SHAs passed to `get_diff_files_between`.
+    pub diffs_between: Vec<(String, String)>,
+    /// `(comment_id, reaction)` pairs passed to `add_reaction`.
+    pub reactions: Vec<(u64, Reaction)>,
+    /// `(comment_id, reaction_id)` pairs passed to `remove_reaction`.
+    pub removed_reactions: Vec<(u64, u64)>,
+    /// `(file_path, branch, text)` passed to `append_to_repo_file`.
+    pub repo_file_appends: Vec<(String, String, String)>,
+    /// `(path, branch, contents, message)` of files added by `create_pr_file`.
+    pub pr_files: Vec<(String, String, String, String)>,
+    pub removed_labels: Vec<String>,
+    /// `(comment_id, body)` passed to `reply_to_comment`.
+    pub replies: Vec<(u64, String)>,
+    /// `(name, png)` passed to `upload_image`.
+    pub uploaded_images: Vec<(String, Vec<u8>)>,
 }
 
 /// Mock git provider for integration tests.
@@ -31,10 +50,28 @@ pub struct MockGitProvider {
     pub branch: String,
     pub commit_messages: String,
     pub diff_files: Vec<FilePatchInfo>,
+    pub commits: Vec<CommitInfo>,
     pub issue_comments: Vec<IssueComment>,
+    pub review_thread: Vec<IssueComment>,
+    /// Comments returned by `get_review_comments`.
+    pub review_comments: Vec<ReviewComment>,
+    pub labels: Vec<String>,
     pub issue_bodies: HashMap<u64, (String, String)>,
     pub repo_settings_toml: Option<String>,
     pub global_settings_toml: Option<String>,
+    pub repo_files: HashMap<String, String>,
+    /// Contents returned by `get_head_file_bytes`.
+    pub binary_files: HashMap<String, Vec<u8>>,
+    pub planning: PlanningContext,
+    /// `(team, login)` memberships reported by `is_team_member`.
+    pub team_members: Vec<(String, String)>,
+    /// `author_association` per login reported by `get_author_association`;
+    /// `NONE` for anyone else.
+    pub associations: HashMap<String, String>,
+    /// Capabilities `is_supported` denies; everything else is supported.
+    pub unsupported: Vec<Capability>,
+    /// Reported by `max_comment_chars`; no limit by default.
+    pub max_comment_chars: Option<usize>,
     pub calls: Mutex<MockCalls>,
 }
 
@@ -46,10 +83,21 @@ impl MockGitProvider {
             branch: "feature/test".into(),
             commit_messages: "feat: add test feature".into(),
             diff_files: Vec::new(),
+            commits: Vec::new(),
             issue_comments: Vec::new(),
+            review_thread: Vec::new(),
+            review_comments: Vec::new(),
+            labels: Vec::new(),
             issue_bodies: HashMap::new(),
             repo_settings_toml: None,
             global_settings_toml: None,
+            repo_files: HashMap::new(),
+            binary_files: HashMap::new(),
+            planning: PlanningContext::default(),
+            team_members: Vec::new(),
+            associations: HashMap::new(),
+            unsupported: Vec::new(),
+            max_comment_chars: None,
             calls: Mutex::new(MockCalls::default()),
         }
     }
@@ -59,6 +107,11 @@ impl MockGitProvider {
         self
     }
 
+    pub fn with_commits(mut self, commits: Vec<CommitInfo>) -> Self {
+        self.commits = commits;
+        self
+    }
+
     pub fn with_pr_description(mut self, title: &str, description: &str) -> Self {
         self.title = title.into();
         self.description = description.into();
@@ -75,12 +128,40 @@ impl MockGitProvider {
         self
     }
 
+    pub fn with_repo_file(mut self, path: &str, content: &str) -> Self {
+        self.repo_files.insert(path.into(), content.into());
+        self
+    }
+
+    pub fn with_binary_file(mut self, path: &str, content: &[u8]) -> Self {
+        self.binary_files.insert(path.into(), content.to_vec());
+        self
+    }
+
     pub fn with_issue_body(mut self, number: u64, title: &str, body: &str) -> Self {
         self.issue_bodies
             .insert(number, (title.into(), body.into()));
         self
     }
 
+    /// Labels the PR already has.
+    pub fn with_labels(mut self, labels: &[&str]) -> Self {
+        self.labels = labels.iter().map(|l| l.to_string()).collect();
+        self
+    }
+
+    /// A platform without `capabilities`.
+    pub fn without(mut self, capabilities: &[Capability]) -> Self {
+        self.unsupported.extend_from_slice(capabilities);
+        self
+    }
+
+    /// Comments returned by `get_review_thread_comments` for any comment.
+    pub fn with_review_thread(mut self, comments: Vec<IssueComment>) -> Self {
+        self.review_thread = comments;
+        self
+    }
+
     pub fn get_calls(&self) -> std::sync::MutexGuard<'_, MockCalls> {
         self.calls.lock().unwrap()
     }
@@ -176,13 +257,13 @@ impl GitProvider for MockGitProvider {
     async fn publish_code_suggestions(
         &self,
         suggestions: &[CodeSuggestion],
-    ) -> Result<bool, PrAgentError> {
-        self.calls
-            .lock()
-            .unwrap()
-            .code_suggestions
-            .push(suggestions.to_vec());
-        Ok(true)
+    ) -> Result<Option<String>, PrAgentError> {
+        let mut calls = self.calls.lock().unwrap();
+        calls.code_suggestions.push(suggestions.to_vec());
+        Ok(Some(format!(
+            "https://github.com/owner/repo/pull/1#pullrequestreview-{}",
+            calls.code_suggestions.len()
+        )))
     }
 
     async fn publish_labels(&self, labels: &[String]) -> Result<(), PrAgentError> {
@@ -191,22 +272,47 @@ impl GitProvider for MockGitProvider {
     }
 
     async fn get_pr_labels(&self) -> Result<Vec<String>, PrAgentError> {
-        Ok(vec![])
+        Ok(self.labels.clone())
+    }
+
+    async fn remove_label(&self, label: &str) -> Result<(), PrAgentError> {
+        self.calls.lock().unwrap().removed_labels.push(label.into());
+        Ok(())
+    }
+
+    fn reaction_name(&self, reaction: Reaction) -> Option<&'static str> {
+        Some(match reaction {
+            Reaction::Ack => "eyes",
+            Reaction::Success => "white_check_mark",
+            Reaction::Failure => "x",
+        })
     }
 
-    async fn add_eyes_reaction(
+    async fn add_reaction(
         &self,
-        _comment_id: u64,
-        _disable_eyes: bool,
+        comment_id: u64,
+        reaction: Reaction,
     ) -> Result<Option<u64>, PrAgentError> {
-        Ok(None)
+        let mut calls = self.calls.lock().unwrap();
+        calls.reactions.push((comment_id, reaction));
+        Ok(Some(calls.reactions.len() as u64))
     }
 
-    async fn remove_reaction(
-        &self,
-        _comment_id: u64,
-        _reaction_id: u64,
-    ) -> Result<(), PrAgentError> {
+    async fn upload_image(&self, name: &str, png: Vec<u8>) -> Result<String, PrAgentError> {
+        self.calls
+            .lock()
+            .unwrap()
+            .uploaded_images
+            .push((name.into(), png));
+        Ok(format!("https://example.com/uploads/{name}"))
+    }
+
+    async fn remove_reaction(&self, comment_id: u64, reaction_id: u64) -> Result<(), PrAgentError> {
+        self.calls
+            .lock()
+            .unwrap()
+            .removed_reactions
+            .push((comment_id, reaction_id));
         Ok(())
     }
 
@@ -222,12 +328,77 @@ impl GitProvider for MockGitProvider {
         Ok(self.global_settings_toml.clone())
     }
 
+    async fn get_repo_file(&self, path: &str) -> Result<Option<String>, PrAgentError> {
+        Ok(self.repo_files.get(path).cloned())
+    }
+
+    async fn get_repo_tree(&self) -> Result<RepoTree, PrAgentError> {
+        let mut files: Vec<(String, u64)> = self
+            .repo_files
+            .iter()
+            .map(|(path, content)| (path.clone(), content.len() as u64))
+            .collect();
+        files.sort();
+        Ok(RepoTree {
+            revision: "mock-tree".into(),
+            files,
+        })
+    }
+
+    async fn search_code(&self, query: &str) -> Result<Vec<String>, PrAgentError> {
+        let mut paths: Vec<String> = self
+            .repo_files
+            .iter()
+            .filter(|(_, content)| content.contains(query))
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    async fn create_pr_file(
+        &self,
+        file_path: &str,
+        contents: &[u8],
+        message: &str,
+    ) -> Result<bool, PrAgentError> {
+        if self.repo_files.contains_key(file_path) {
+            return Ok(false);
+        }
+        self.calls.lock().unwrap().pr_files.push((
+            file_path.into(),
+            self.branch.clone(),
+            String::from_utf8_lossy(contents).into_owned(),
+            message.into(),
+        ));
+        Ok(true)
+    }
+
+    async fn append_to_repo_file(
+        &self,
+        file_path: &str,
+        branch: &str,
+        text: &str,
+        _message: &str,
+    ) -> Result<(), PrAgentError> {
+        self.calls.lock().unwrap().repo_file_appends.push((
+            file_path.into(),
+            branch.into(),
+            text.into(),
+        ));
+        Ok(())
+    }
+
     async fn get_issue_comments(&self) -> Result<Vec<IssueComment>, PrAgentError> {
         Ok(self.issue_comments.clone())
     }
 
-    fn is_supported(&self, capability: &str) -> bool {
-        capability == "gfm_markdown"
+    fn is_supported(&self, capability: Capability) -> bool {
+        !self.unsupported.contains(&capability)
+    }
+
+    fn max_comment_chars(&self) -> Option<usize> {
+        self.max_comment_chars
     }
 
     async fn edit_comment(&self, comment_id: &CommentId, body: &str) -> Result<(), PrAgentError> {
@@ -239,19 +410,95 @@ impl GitProvider for MockGitProvider {
         Ok(())
     }
 
+    async fn reply_to_comment(&self, comment_id: u64, body: &str) -> Result<(), PrAgentError> {
+        self.calls
+            .lock()
+            .unwrap()
+            .replies
+            .push((comment_id, body.into()));
+        Ok(())
+    }
+
+    async fn get_review_thread_comments(
+        &self,
+        _comment_id: u64,
+    ) -> Result<Vec<IssueComment>, PrAgentError> {
+        Ok(self.review_thread.clone())
+    }
+
+    async fn get_review_comments(&self) -> Result<Vec<ReviewComment>, PrAgentError> {
+        Ok(self.review_comments.clone())
+    }
+
     async fn auto_approve(&self) -> Result<bool, PrAgentError> {
         self.calls.lock().unwrap().auto_approvals.push(());
         Ok(true)
     }
 
+    async fn publish_check_run(&self, check: &CheckRunReport) -> Result<(), PrAgentError> {
+        self.calls.lock().unwrap().check_runs.push(check.clone());
+        Ok(())
+    }
+
+    fn get_pr_id(&self) -> &str {
+        "42"
+    }
+
     fn repo_owner_and_name(&self) -> (String, String) {
         ("test-owner".into(), "test-repo".into())
     }
 
+    async fn get_pr_head_sha(&self) -> Result<String, PrAgentError> {
+        Ok(MOCK_HEAD_SHA.into())
+    }
+
+    async fn get_head_file_bytes(&self, path: &str) -> Result<Vec<u8>, PrAgentError> {
+        self.binary_files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| PrAgentError::GitProvider(format!("{path} not found")))
+    }
+
+    async fn get_pr_commits(&self) -> Result<Vec<CommitInfo>, PrAgentError> {
+        Ok(self.commits.clone())
+    }
+
+    async fn get_diff_files_between(
+        &self,
+        base_sha: &str,
+        head_sha: &str,
+    ) -> Result<Vec<FilePatchInfo>, PrAgentError> {
+        self.calls
+            .lock()
+            .unwrap()
+            .diffs_between
+            .push((base_sha.to_string(), head_sha.to_string()));
+        Ok(self.diff_files.clone())
+    }
+
     async fn get_issue_body(&self, issue_number: u64) -> Result<(String, String), PrAgentError> {
         self.issue_bodies
             .get(&issue_number)
             .cloned()
             .ok_or_else(|| PrAgentError::GitProvider(format!("issue #{issue_number} not found")))
     }
+
+    async fn is_team_member(&self, team: &str, login: &str) -> Result<bool, PrAgentError> {
+        Ok(self
+            .team_members
+            .iter()
+            .any(|(t, l)| t == team && l == login))
+    }
+
+    async fn get_author_association(&self, login: &str) -> Result<String, PrAgentError> {
+        Ok(self
+            .associations
+            .get(login)
+            .cloned()
+            .unwrap_or_else(|| "NONE".into()))
+    }
+
+    async fn get_planning_context(&self) -> Result<PlanningContext, PrAgentError> {
+        Ok(self.planning.clone())
+    }
 }
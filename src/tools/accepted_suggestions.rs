@@ -0,0 +1,218 @@
+//! Team knowledge base of accepted `/improve` suggestions
+//! (`pr_code_suggestions.wiki_page_accepted_suggestions`).
+//!
+//! The suggestions comment lists every suggestion as a checkbox. When someone
+//! checks one, the webhook appends it to `accepted_suggestions_file` on
+//! `accepted_suggestions_branch`, so the file grows into a record of the
+//! changes the team chose to make.
+
+use std::fmt::Write;
+
+use crate::config::types::Settings;
+use crate::git::GitProvider;
+use crate::output::improve_formatter::ParsedSuggestion;
+use crate::output::markdown::{sanitize_table_cell, user_code_span};
+
+/// Marks a checklist line as an accepted-suggestion checkbox.
+pub const SUGGESTION_MARKER: &str = "<!-- pr-agent:accepted-suggestion -->";
+
+/// Append the "Applied suggestions" checklist to the suggestions comment.
+pub fn append_checklist(body: &mut String, suggestions: &[ParsedSuggestion]) {
+    if suggestions.is_empty() {
+        return;
+    }
+    body.push_str(
+        "\n\n#### Applied suggestions\n\n\
+         Check the suggestions you applied to add them to the team's accepted suggestions.\n\n",
+    );
+    for s in suggestions {
+        let summary = if s.one_sentence_summary.is_empty() {
+            &s.suggestion_content
+        } else {
+            &s.one_sentence_summary
+        };
+        let lines = match (s.relevant_lines_start, s.relevant_lines_end) {
+            (start, end) if start <= 0 || end <= 0 => String::new(),
+            (start, end) if start == end => format!(" [{start}]"),
+            (start, end) => format!(" [{start}-{end}]"),
+        };
+        let _ = writeln!(
+            body,
+            "- [ ] **{}**: {} ({}{lines}) {SUGGESTION_MARKER}",
+            sanitize_table_cell(&s.label),
+            sanitize_table_cell(summary),
+            user_code_span(&s.relevant_file),
+        );
+    }
+}
+
+/// Suggestions checked in `body` that were not checked in `previous` (the
+/// comment before the edit), as their checklist text.
+pub fn newly_checked(body: &str, previous: Option<&str>) -> Vec<String> {
+    let before = previous.map(checked_entries).unwrap_or_default();
+    checked_entries(body)
+        .into_iter()
+        .filter(|entry| !before.contains(entry))
+        .collect()
+}
+
+fn checked_entries(body: &str) -> Vec<String> {
+    body.lines()
+        .filter(|line| line.contains(SUGGESTION_MARKER))
+        .filter_map(|line| {
+            let line = line.trim();
+            line.strip_prefix("- [x]")
+                .or_else(|| line.strip_prefix("- [X]"))
+        })
+        .map(|entry| entry.replace(SUGGESTION_MARKER, "").trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+/// Markdown section recording `entries` accepted on one PR.
+fn format_entries(
+    entries: &[String],
+    pr_url: &str,
+    pr_number: Option<u64>,
+    user: &str,
+    date: &str,
+) -> String {
+    let pr = match pr_number {
+        Some(number) => format!("[PR #{number}]({pr_url})"),
+        None => pr_url.to_string(),
+    };
+    let mut out = format!("### {pr}, {date}, accepted by @{user}\n\n");
+    for entry in entries {
+        let _ = writeln!(out, "- {entry}");
+    }
+    out.push('\n');
+    out
+}
+
+/// Append `entries`, checked by `user`, to the accepted suggestions file.
+/// Failures are logged, not returned.
+pub async fn record(
+    provider: &dyn GitProvider,
+    settings: &Settings,
+    entries: &[String],
+    user: &str,
+) {
+    let config = &settings.pr_code_suggestions;
+    let date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let text = format_entries(
+        entries,
+        provider.get_pr_url(),
+        provider.get_pr_number(),
+        user,
+        &date,
+    );
+    let message = format!(
+        "Record {} accepted code suggestion(s) from {}",
+        entries.len(),
+        provider.get_pr_url()
+    );
+    match provider
+        .append_to_repo_file(
+            &config.accepted_suggestions_file,
+            &config.accepted_suggestions_branch,
+            &text,
+            &message,
+        )
+        .await
+    {
+        Ok(()) => tracing::info!(
+            file = %config.accepted_suggestions_file,
+            count = entries.len(),
+            "recorded accepted suggestions"
+        ),
+        Err(e) => tracing::warn!(error = %e, "failed to record accepted suggestions"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::load_settings;
+    use crate::testing::mock_git::MockGitProvider;
+    use std::collections::HashMap;
+
+    fn suggestion(summary: &str, start: i32, end: i32) -> ParsedSuggestion {
+        ParsedSuggestion {
+            label: "possible issue".into(),
+            relevant_file: "src/lib.rs".into(),
+            relevant_lines_start: start,
+            relevant_lines_end: end,
+            existing_code: "a".into(),
+            improved_code: "b".into(),
+            one_sentence_summary: summary.into(),
+            suggestion_content: String::new(),
+            score: 8,
+            best_practice: None,
+        }
+    }
+
+    #[test]
+    fn test_append_checklist() {
+        let mut body = String::from("table");
+        append_checklist(
+            &mut body,
+            &[
+                suggestion("Handle empty input", 3, 5),
+                suggestion("Use a | pipe", 0, 0),
+            ],
+        );
+        assert!(body.contains(
+            "- [ ] **possible issue**: Handle empty input (`src/lib.rs` [3-5]) \
+             <!-- pr-agent:accepted-suggestion -->"
+        ));
+        // No line range for high-level suggestions.
+        assert!(body.contains("Use a \\| pipe (`src/lib.rs`) <!--"));
+
+        let mut empty = String::from("table");
+        append_checklist(&mut empty, &[]);
+        assert_eq!(empty, "table");
+    }
+
+    #[test]
+    fn test_newly_checked() {
+        let before = format!(
+            "- [x] **a**: First {SUGGESTION_MARKER}\n- [ ] **b**: Second {SUGGESTION_MARKER}\n"
+        );
+        let after = before.replace("- [ ] **b**", "- [X] **b**");
+        assert_eq!(newly_checked(&after, Some(&before)), vec!["**b**: Second"]);
+        assert_eq!(newly_checked(&after, None).len(), 2);
+        // Unchecking records nothing; unrelated checkboxes are ignored.
+        assert!(newly_checked("- [ ] **a**: First", Some(&before)).is_empty());
+        assert!(newly_checked("- [x] regular checkbox", None).is_empty());
+    }
+
+    #[test]
+    fn test_format_entries() {
+        let text = format_entries(
+            &["**a**: First".into()],
+            "https://github.com/o/r/pull/12",
+            Some(12),
+            "alice",
+            "2026-01-02",
+        );
+        assert_eq!(
+            text,
+            "### [PR #12](https://github.com/o/r/pull/12), 2026-01-02, accepted by @alice\n\n\
+             - **a**: First\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_appends_to_file() {
+        let overrides: HashMap<String, String> = HashMap::new();
+        let settings = load_settings(&overrides, None, None).unwrap();
+        let provider = MockGitProvider::new();
+        record(&provider, &settings, &["**a**: First".into()], "alice").await;
+        let calls = provider.get_calls();
+        let (path, branch, text) = &calls.repo_file_appends[0];
+        assert_eq!(path, ".pr_agent_accepted_suggestions.md");
+        assert_eq!(branch, "");
+        assert!(text.contains("accepted by @alice"));
+        assert!(text.contains("- **a**: First"));
+    }
+}
@@ -0,0 +1,301 @@
+//! AI metadata pre-pass (`config.enable_ai_metadata`).
+//!
+//! Before `/review` and `/improve` build their prompts, `model_weak` (or
+//! `model` when unset) writes a one-line summary of each changed file. The
+//! summaries are stored in [`FilePatchInfo::ai_file_summary`] and rendered
+//! under the file headers of the diff as "AI-generated changes summary".
+//!
+//! With `config.large_patch_policy = "summarize"`, the same model also
+//! condenses each patch too large for the main model's prompt into a few
+//! lines, stored in [`FilePatchInfo::patch_summary`] and shown in place of
+//! the patch.
+
+use std::fmt::Write;
+
+use minijinja::Value;
+
+use crate::ai::AiHandler;
+use crate::ai::router::{ModelRouter, ModelTier};
+use crate::ai::token::{
+    OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD, clip_tokens, get_max_tokens_with_fallback, tokenizer_for,
+};
+use crate::config::types::Settings;
+use crate::error::PrAgentError;
+use crate::git::types::FilePatchInfo;
+use crate::output::yaml_parser::load_yaml;
+use crate::processing::diff::patch_stats_note;
+use crate::processing::filter::is_patch_skipped;
+use crate::template::render::render_prompt;
+
+/// Tokens of each file's patch shown to the summarizer.
+const MAX_FILE_PATCH_TOKENS: u32 = 1000;
+
+/// Summarize each file in `files` and store the summaries on them. Returns
+/// whether any file got a summary; failures are logged and leave the files
+/// unchanged.
+pub async fn annotate_files(
+    ai: &dyn AiHandler,
+    files: &mut [FilePatchInfo],
+    title: &str,
+    settings: &Settings,
+) -> bool {
+    if !settings.config.enable_ai_metadata || files.is_empty() {
+        return false;
+    }
+    let summaries = match summarize(ai, files, title, settings).await {
+        Ok(summaries) => summaries,
+        Err(e) => {
+            tracing::warn!(error = %e, "AI metadata pass failed, continuing without it");
+            return false;
+        }
+    };
+    let mut annotated = 0;
+    for file in files.iter_mut() {
+        if let Some((_, summary)) = summaries
+            .iter()
+            .find(|(name, _)| name.trim_start_matches('/') == file.filename.trim_start_matches('/'))
+        {
+            file.ai_file_summary = Some(summary.clone());
+            annotated += 1;
+        }
+    }
+    tracing::info!(
+        annotated,
+        num_files = files.len(),
+        "added AI summaries to changed files"
+    );
+    annotated > 0
+}
+
+async fn summarize(
+    ai: &dyn AiHandler,
+    files: &[FilePatchInfo],
+    title: &str,
+    settings: &Settings,
+) -> Result<Vec<(String, String)>, PrAgentError> {
+    let model = ModelRouter::new(&settings.config).model(ModelTier::Weak);
+    let max_tokens = get_max_tokens_with_fallback(model, settings.config.max_model_tokens)
+        .saturating_sub(OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD);
+
+    let mut diff = String::new();
+    let skip_types = &settings.config.patch_extension_skip_types;
+    for file in files.iter().filter(|f| !f.patch.is_empty()) {
+        let patch = if is_patch_skipped(&file.filename, skip_types) {
+            patch_stats_note(&file.patch)
+        } else {
+            clip_tokens(file.patch.trim(), MAX_FILE_PATCH_TOKENS, true)
+        };
+        let _ = write!(diff, "## File: '{}'\n{}\n\n", file.filename, patch);
+    }
+    let vars = [
+        ("title".to_string(), Value::from(title)),
+        (
+            "diff".to_string(),
+            Value::from(clip_tokens(&diff, max_tokens, true)),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let rendered = render_prompt(&settings.pr_ai_metadata_prompt, vars)?;
+
+    tracing::info!(model, "calling AI model for file summaries");
+    let response = crate::ai::chat_completion_with_fallback(
+        ai,
+        model,
+        &[],
+        &rendered.system,
+        &rendered.user,
+        Some(settings.config.temperature),
+        None,
+    )
+    .await?;
+    let data = load_yaml(
+        &response.content,
+        &["filename:", "summary:"],
+        "files",
+        "summary",
+    )
+    .ok_or_else(|| PrAgentError::AiHandler("could not parse file summaries".into()))?;
+    Ok(parse_summaries(&data))
+}
+
+/// Replace patches too large for `model`'s prompt with short summaries from
+/// the weak model, when `large_patch_policy` is "summarize". Returns the
+/// summarized filenames; a failed summary leaves the patch to be clipped.
+pub async fn summarize_large_patches(
+    ai: &dyn AiHandler,
+    files: &mut [FilePatchInfo],
+    model: &str,
+    settings: &Settings,
+) -> Vec<String> {
+    if settings.config.large_patch_policy != "summarize" {
+        return Vec::new();
+    }
+    let budget = get_max_tokens_with_fallback(model, settings.config.max_model_tokens)
+        .saturating_sub(OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD);
+    let tokenizer = tokenizer_for(model);
+    let mut summarized = Vec::new();
+    for file in files.iter_mut() {
+        if file.patch.is_empty()
+            || is_patch_skipped(&file.filename, &settings.config.patch_extension_skip_types)
+            || tokenizer.count(&file.patch) <= budget
+        {
+            continue;
+        }
+        match summarize_patch(ai, file, settings).await {
+            Ok(summary) => {
+                file.patch_summary = Some(summary);
+                summarized.push(file.filename.clone());
+            }
+            Err(e) => {
+                tracing::warn!(file = %file.filename, error = %e, "large patch summary failed, clipping instead");
+            }
+        }
+    }
+    if !summarized.is_empty() {
+        tracing::info!(
+            num_files = summarized.len(),
+            "summarized patches too large for the prompt"
+        );
+    }
+    summarized
+}
+
+async fn summarize_patch(
+    ai: &dyn AiHandler,
+    file: &FilePatchInfo,
+    settings: &Settings,
+) -> Result<String, PrAgentError> {
+    let model = ModelRouter::new(&settings.config).model(ModelTier::Weak);
+    let max_tokens = get_max_tokens_with_fallback(model, settings.config.max_model_tokens)
+        .saturating_sub(OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD);
+    let num_lines = settings.config.large_patch_summary_lines.max(1);
+    let vars = [
+        ("filename".to_string(), Value::from(file.filename.as_str())),
+        (
+            "diff".to_string(),
+            Value::from(clip_tokens(file.patch.trim(), max_tokens, true)),
+        ),
+        ("num_lines".to_string(), Value::from(num_lines)),
+    ]
+    .into_iter()
+    .collect();
+    let rendered = render_prompt(&settings.pr_large_patch_prompt, vars)?;
+
+    tracing::info!(model, file = %file.filename, "calling AI model for large patch summary");
+    let response = crate::ai::chat_completion_with_fallback(
+        ai,
+        model,
+        &[],
+        &rendered.system,
+        &rendered.user,
+        Some(settings.config.temperature),
+        None,
+    )
+    .await?;
+    let summary: Vec<&str> = response
+        .content
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.trim().is_empty() && !l.trim_start().starts_with("```"))
+        .take(num_lines as usize)
+        .collect();
+    if summary.is_empty() {
+        return Err(PrAgentError::AiHandler("empty large patch summary".into()));
+    }
+    Ok(summary.join("\n"))
+}
+
+/// `(filename, summary)` pairs from the summarizer's YAML.
+fn parse_summaries(data: &serde_yaml_ng::Value) -> Vec<(String, String)> {
+    data.get("files")
+        .and_then(|files| files.as_sequence())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let filename = entry.get("filename")?.as_str()?.trim();
+            let summary = entry.get("summary")?.as_str()?.trim();
+            (!filename.is_empty() && !summary.is_empty())
+                .then(|| (filename.to_string(), summary.replace('\n', " ")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::load_settings;
+    use crate::testing::fixtures::{SAMPLE_PATCH, sample_diff_file};
+    use crate::testing::mock_ai::MockAiHandler;
+    use std::collections::HashMap;
+
+    const SUMMARIES_YAML: &str = "```yaml\nfiles:\n- filename: |\n    src/main.rs\n  \
+                                  summary: |\n    Adds debug output to `main`.\n```";
+
+    #[test]
+    fn test_parse_summaries() {
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(
+            "files:\n- filename: a.rs\n  summary: Fixes parsing\n- filename: b.rs\n  summary: ''\n",
+        )
+        .unwrap();
+        assert_eq!(
+            parse_summaries(&data),
+            vec![("a.rs".to_string(), "Fixes parsing".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_annotate_files() {
+        let mut overrides = HashMap::new();
+        overrides.insert("config.enable_ai_metadata".into(), "true".into());
+        overrides.insert("config.model_weak".into(), "gpt-4o-mini".into());
+        let settings = load_settings(&overrides, None, None).unwrap();
+        let ai = MockAiHandler::new(SUMMARIES_YAML);
+        let mut files = vec![
+            sample_diff_file("src/main.rs", SAMPLE_PATCH),
+            sample_diff_file("src/other.rs", SAMPLE_PATCH),
+        ];
+
+        assert!(annotate_files(&ai, &mut files, "Add debug output", &settings).await);
+        assert_eq!(
+            files[0].ai_file_summary.as_deref(),
+            Some("Adds debug output to `main`.")
+        );
+        assert_eq!(files[1].ai_file_summary, None);
+        assert_eq!(ai.get_recorded_calls()[0].model, "gpt-4o-mini");
+    }
+
+    #[tokio::test]
+    async fn test_summarize_large_patches() {
+        let mut overrides = HashMap::new();
+        overrides.insert("config.large_patch_policy".into(), "summarize".into());
+        overrides.insert("config.max_model_tokens".into(), "2000".into());
+        overrides.insert("config.large_patch_summary_lines".into(), "2".into());
+        let settings = load_settings(&overrides, None, None).unwrap();
+        let ai = MockAiHandler::new("- Rewrites `parse`\n- Drops `legacy`\n- Extra line");
+        let big = format!("@@ -1,3000 +1,3000 @@\n{}", "+let x = 1;\n".repeat(3000));
+        let mut files = vec![
+            sample_diff_file("src/big.rs", &big),
+            sample_diff_file("src/main.rs", SAMPLE_PATCH),
+        ];
+
+        let summarized =
+            summarize_large_patches(&ai, &mut files, "custom/local-model", &settings).await;
+        assert_eq!(summarized, vec!["src/big.rs"]);
+        assert_eq!(
+            files[0].patch_summary.as_deref(),
+            Some("- Rewrites `parse`\n- Drops `legacy`")
+        );
+        assert_eq!(files[1].patch_summary, None);
+        assert_eq!(ai.get_call_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_annotate_files_disabled() {
+        let settings = load_settings(&HashMap::new(), None, None).unwrap();
+        let ai = MockAiHandler::new(SUMMARIES_YAML);
+        let mut files = vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)];
+        assert!(!annotate_files(&ai, &mut files, "", &settings).await);
+        assert_eq!(ai.get_call_count(), 0);
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt::Write;
+use std::sync::Arc;
+
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::git::GitProvider;
+use crate::output::help_text::{ANALYZE_HELP_TEXT, add_guide_text};
+use crate::output::markdown::user_code_span;
+use crate::processing::components::{
+    ChangedComponent, ComponentChange, extract_changed_components,
+};
+
+/// Maximum number of component rows in the analysis table.
+const MAX_COMPONENTS: usize = 50;
+
+/// PR Analyze tool — list the components changed in the PR.
+///
+/// Parses the diff into changed functions/classes per file (no AI call) and
+/// posts a table with follow-up commands for each component, so reviewers can
+/// request tests or focused suggestions for one component at a time.
+pub struct PRAnalyze {
+    provider: Arc<dyn GitProvider>,
+}
+
+impl PRAnalyze {
+    pub fn new(provider: Arc<dyn GitProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// Run the analysis and publish (or print) the component table.
+    pub async fn run(&self) -> Result<(), PrAgentError> {
+        let settings = get_settings();
+
+        let files = super::fetch_diff_files(self.provider.as_ref(), "analyze").await?;
+        let components = extract_changed_components(&files);
+        tracing::info!(
+            num_files = files.len(),
+            num_components = components.len(),
+            "analyzed changed components"
+        );
+
+        let mut output = format_analyze_output(&components);
+        if settings.pr_analyze.enable_help_text {
+            add_guide_text(&mut output, "", ANALYZE_HELP_TEXT);
+        }
+
+        if settings.config.publish_output {
+            self.provider.publish_comment(&output, false).await?;
+        } else {
+            println!("{output}");
+        }
+        Ok(())
+    }
+}
+
+/// Render changed components as a markdown table with follow-up commands.
+fn format_analyze_output(components: &[ChangedComponent]) -> String {
+    let mut out = String::from("## PR Analysis 🔬\n\n");
+    if components.is_empty() {
+        out.push_str("No changed functions or classes were found in this PR.\n");
+        return out;
+    }
+
+    out.push_str("| File | Component | Change | Lines | Follow-up |\n");
+    out.push_str("|------|-----------|--------|-------|-----------|\n");
+    for c in components.iter().take(MAX_COMPONENTS) {
+        let follow_up = if c.change == ComponentChange::Removed {
+            "—".to_string()
+        } else {
+            format!("`/test {0}` · `/improve_component {0}`", c.name)
+        };
+        let _ = writeln!(
+            out,
+            "| {} | {} ({}) | {} | +{} -{} | {} |",
+            user_code_span(&c.file),
+            user_code_span(&c.name),
+            c.kind,
+            c.change.as_str(),
+            c.added_lines,
+            c.removed_lines,
+            follow_up
+        );
+    }
+    if components.len() > MAX_COMPONENTS {
+        let _ = writeln!(
+            out,
+            "\n_…and {} more components not shown._",
+            components.len() - MAX_COMPONENTS
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::{load_settings, with_settings};
+    use crate::testing::fixtures::sample_diff_file;
+    use crate::testing::mock_git::MockGitProvider;
+
+    fn component(name: &str, change: ComponentChange) -> ChangedComponent {
+        ChangedComponent {
+            file: "src/lib.rs".into(),
+            name: name.into(),
+            kind: "function",
+            change,
+            added_lines: 3,
+            removed_lines: 1,
+        }
+    }
+
+    #[test]
+    fn test_format_analyze_output() {
+        let out = format_analyze_output(&[
+            component("parse", ComponentChange::Modified),
+            component("legacy", ComponentChange::Removed),
+        ]);
+        assert!(out.starts_with("## PR Analysis 🔬\n\n| File |"));
+        assert!(out.contains(
+            "| `src/lib.rs` | `parse` (function) | modified | +3 -1 | `/test parse` · `/improve_component parse` |"
+        ));
+        assert!(out.contains("| `legacy` (function) | removed | +3 -1 | — |"));
+
+        let out = format_analyze_output(&[]);
+        assert!(out.contains("No changed functions or classes"));
+    }
+
+    #[test]
+    fn test_format_analyze_output_truncates() {
+        let components: Vec<ChangedComponent> = (0..MAX_COMPONENTS + 2)
+            .map(|i| component(&format!("f{i}"), ComponentChange::Added))
+            .collect();
+        let out = format_analyze_output(&components);
+        assert!(out.contains("`f49`"));
+        assert!(!out.contains("`f50`"));
+        assert!(out.contains("…and 2 more components not shown."));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_publishes_component_table() {
+        let patch =
+            "@@ -1,3 +1,7 @@ fn main() {\n     let a = 1;\n+    run();\n }\n+\n+fn run() {\n+}";
+        let provider = Arc::new(
+            MockGitProvider::new().with_diff_files(vec![sample_diff_file("src/main.rs", patch)]),
+        );
+        let tool = PRAnalyze::new(provider.clone());
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, tool.run()).await.unwrap();
+
+        let comment = &provider.get_calls().comments[0].0;
+        assert!(comment.contains("| `src/main.rs` | `main` (function) | modified | +2 -0 |"));
+        assert!(comment.contains("| `run` (function) | added | +2 -0 | `/test run`"));
+        assert!(comment.contains("Tool usage guide:"));
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt::Write;
 use std::sync::Arc;
 
 use minijinja::Value;
@@ -6,9 +7,17 @@ use crate::ai::AiHandler;
 use crate::config::loader::get_settings;
 use crate::error::PrAgentError;
 use crate::git::GitProvider;
+use crate::git::types::FilePatchInfo;
+use crate::output::markdown::sanitize_user_markdown;
 use crate::processing::compression::get_pr_diff;
-use crate::template::render::render_prompt;
-use crate::tools::{PrMetadata, build_common_vars, resolve_ai_handler, with_progress_comment};
+use crate::processing::context_retrieval;
+use crate::processing::language;
+use crate::processing::symbol_lookup;
+use crate::processing::token_budget::{PromptComponent, cap_component};
+use crate::tools::{
+    PrMetadata, build_common_vars, render_prompt_fitting_context, resolve_ai_handler,
+    with_progress_comment,
+};
 
 /// PR Ask tool — answer free-form questions about a PR's code changes.
 ///
@@ -17,11 +26,16 @@ use crate::tools::{PrMetadata, build_common_vars, resolve_ai_handler, with_progr
 pub struct PRAsk {
     provider: Arc<dyn GitProvider>,
     ai: Option<Arc<dyn AiHandler>>,
+    full_files: bool,
 }
 
 impl PRAsk {
     pub fn new(provider: Arc<dyn GitProvider>) -> Self {
-        Self { provider, ai: None }
+        Self {
+            provider,
+            ai: None,
+            full_files: false,
+        }
     }
 
     #[cfg(test)]
@@ -29,9 +43,17 @@ impl PRAsk {
         Self {
             provider,
             ai: Some(ai),
+            full_files: false,
         }
     }
 
+    /// Add full file contents for symbols the diff doesn't show, as with
+    /// `pr_questions.use_full_files`.
+    pub fn with_full_files(mut self, full_files: bool) -> Self {
+        self.full_files = full_files;
+        self
+    }
+
     /// Run the ask pipeline with the given question text.
     pub async fn run(&self, question: &str) -> Result<(), PrAgentError> {
         if question.trim().is_empty() {
@@ -52,23 +74,52 @@ impl PRAsk {
         let model = &settings.config.model;
 
         // 1. Fetch PR metadata
-        let meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
+        let mut meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
 
         // 2. Fetch and compress diff
-        let mut files = self.provider.get_diff_files().await?;
-        let diff_result = get_pr_diff(&mut files, model, true);
-        drop(files);
-        let diff = diff_result.diff;
+        let mut files = super::fetch_diff_files(self.provider.as_ref(), "ask").await?;
+        meta.language = language::annotate_files(self.provider.as_ref(), &mut files).await;
+        meta.repo_context =
+            context_retrieval::retrieve(self.provider.as_ref(), &files, &settings).await;
+        let mut diff_result = get_pr_diff(&mut files, model, true);
 
         // 3. Detect images in the question
         let image_url = extract_image_url(question);
 
         // 4. Build template variables
-        let mut vars = build_common_vars(&meta, &diff);
+        let mut vars = build_common_vars(&meta, &diff_result.diff);
         vars.insert("questions".to_string(), Value::from(question.trim()));
+        if (self.full_files || settings.pr_questions.use_full_files)
+            && let Some(full_files) = full_files_context(question, &diff_result.diff, &files)
+        {
+            vars.insert(
+                "full_files".to_string(),
+                Value::from(cap_component(
+                    PromptComponent::FullFiles,
+                    &full_files,
+                    &settings.config,
+                )),
+            );
+        }
+        let symbol_definitions =
+            symbol_lookup::lookup(self.provider.as_ref(), question, &settings).await;
+        if !symbol_definitions.is_empty() {
+            vars.insert(
+                "symbol_definitions".to_string(),
+                Value::from(symbol_definitions),
+            );
+        }
 
-        // 5. Render prompts
-        let rendered = render_prompt(&settings.pr_questions_prompt, vars)?;
+        // 5. Render prompts (re-compressing the diff if it overflows the context window)
+        let (rendered, overflow) = render_prompt_fitting_context(
+            &settings.pr_questions_prompt,
+            vars,
+            &mut files,
+            &mut diff_result,
+            model,
+            true,
+        )?;
+        drop(files);
 
         // 6. Call AI
         let ai = resolve_ai_handler(&self.ai)?;
@@ -88,13 +139,19 @@ impl PRAsk {
                 image_ref,
             )
             .await?;
+        crate::usage::record_usage(model, &response);
 
         // 7. Sanitize and format answer
         let answer = sanitize_answer(&response.content);
-        let output = format_ask_output(question, &answer);
+        let mut output = format_ask_output(question, &answer);
+        if let Some(overflow) = &overflow {
+            output.push_str("\n\n");
+            output.push_str(&overflow.notice());
+        }
 
         // 8. Publish
         if settings.config.publish_output {
+            let output = crate::usage::with_cost_footer(&output);
             self.provider.publish_comment(&output, false).await?;
         }
 
@@ -102,6 +159,77 @@ impl PRAsk {
     }
 }
 
+/// Split the `--full_files` flag off `/ask` text, returning whether it was
+/// present and the remaining question.
+pub fn strip_full_files_flag(text: &str) -> (bool, String) {
+    if !text.split_whitespace().any(|w| w == "--full_files") {
+        return (false, text.to_string());
+    }
+    let question: Vec<&str> = text
+        .split_whitespace()
+        .filter(|w| *w != "--full_files")
+        .collect();
+    (true, question.join(" "))
+}
+
+/// Names in the question that look like code: anything in backticks, and
+/// words with `_`, `::`, a call `(`, or inner capitals (`camelCase`).
+fn question_symbols(question: &str) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    for (i, span) in question.split('`').enumerate() {
+        let in_code = i % 2 == 1;
+        for word in span.split_whitespace() {
+            if !(in_code || word.contains("::") || word.contains(['_', '(']) || is_mixed_case(word))
+            {
+                continue;
+            }
+            for part in word.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+                if part.len() >= 3
+                    && !part.chars().all(|c| c.is_ascii_digit())
+                    && !symbols.iter().any(|s| s == part)
+                {
+                    symbols.push(part.to_string());
+                }
+            }
+        }
+    }
+    symbols
+}
+
+fn is_mixed_case(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+    word.chars().skip(1).any(char::is_uppercase) && word.chars().any(char::is_lowercase)
+}
+
+/// Head-revision content of the changed files that mention symbols from the
+/// question missing in `diff`; `None` when the diff already covers them.
+fn full_files_context(question: &str, diff: &str, files: &[FilePatchInfo]) -> Option<String> {
+    let missing: Vec<String> = question_symbols(question)
+        .into_iter()
+        .filter(|s| !diff.contains(s.as_str()))
+        .collect();
+    if missing.is_empty() {
+        return None;
+    }
+    let mut out = String::new();
+    let mut included = 0;
+    for file in files {
+        if file.head_file.is_empty() || !missing.iter().any(|s| file.head_file.contains(s.as_str()))
+        {
+            continue;
+        }
+        let _ = write!(
+            out,
+            "## File: '{}'\n```\n{}\n```\n\n",
+            file.filename.trim(),
+            file.head_file.trim_end()
+        );
+        included += 1;
+    }
+    tracing::info!(symbols = ?missing, files = included, "adding full files to /ask prompt");
+    (included > 0).then_some(out)
+}
+
 /// Extract image URL from question text.
 fn extract_image_url(question: &str) -> Option<String> {
     if let Some(marker_pos) = question.find("![image]") {
@@ -170,9 +298,8 @@ fn format_ask_output(question: &str, answer: &str) -> String {
         .lines()
         .filter(|line| !line.trim().starts_with("> ![image]"))
         .collect::<Vec<_>>()
-        .join("\n")
-        .trim()
-        .to_string();
+        .join("\n");
+    let display_question = sanitize_user_markdown(display_question.trim());
 
     format!("### **Ask**\n{display_question}\n\n### **Answer:**\n{answer}\n\n")
 }
@@ -276,4 +403,62 @@ mod tests {
         assert!(!output.contains("![image]"));
         assert!(output.contains("What is this?"));
     }
+
+    #[test]
+    fn test_format_ask_output_sanitizes_question() {
+        let question = "<!-- pr-agent:review -->Why <details> here?";
+        let output = format_ask_output(question, "Because.");
+        assert!(!output.contains("pr-agent:review"));
+        assert!(output.contains("Why &lt;details> here?"));
+    }
+
+    #[test]
+    fn test_strip_full_files_flag() {
+        assert_eq!(
+            strip_full_files_flag("--full_files Where is parse_config used?"),
+            (true, "Where is parse_config used?".to_string())
+        );
+        assert_eq!(
+            strip_full_files_flag("What does this do?"),
+            (false, "What does this do?".to_string())
+        );
+    }
+
+    #[test]
+    fn test_question_symbols() {
+        assert_eq!(
+            question_symbols(
+                "Why does `Config::load` call parse_toml() or retryCount here? Note: ok"
+            ),
+            vec!["Config", "load", "parse_toml", "retryCount"]
+        );
+    }
+
+    #[test]
+    fn test_full_files_context() {
+        let file = |name: &str, head: &str| {
+            FilePatchInfo::new(String::new(), head.into(), String::new(), name.into())
+        };
+        let files = vec![
+            file(
+                "src/config.rs",
+                "fn parse_toml() {}\nfn load() { parse_toml() }\n",
+            ),
+            file("src/main.rs", "fn main() {}\n"),
+        ];
+        let diff = "+fn load() { parse_toml() }";
+        // Everything the question names is in the diff.
+        assert!(full_files_context("Is `parse_toml` safe?", diff, &files).is_none());
+
+        let context = full_files_context("Who calls `validate_keys`?", diff, &files);
+        assert!(context.is_none(), "symbol in no changed file");
+
+        let files = vec![
+            file("src/config.rs", "fn validate_keys() {}\n"),
+            file("src/main.rs", "fn main() {}\n"),
+        ];
+        let context = full_files_context("Who calls `validate_keys`?", diff, &files).unwrap();
+        assert!(context.starts_with("## File: 'src/config.rs'\n```\nfn validate_keys() {}\n```"));
+        assert!(!context.contains("src/main.rs"));
+    }
 }
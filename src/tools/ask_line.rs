@@ -7,6 +7,8 @@ use crate::ai::AiHandler;
 use crate::config::loader::get_settings;
 use crate::error::PrAgentError;
 use crate::git::GitProvider;
+use crate::git::diff_cache;
+use crate::git::types::Capability;
 use crate::processing::diff::extract_hunk_lines_from_patch;
 use crate::template::render::render_prompt;
 use crate::tools::resolve_ai_handler;
@@ -68,7 +70,7 @@ impl PRAskLine {
             extract_hunk_lines_from_patch(diff_hunk, file_name, line_start, line_end, side)
         } else {
             // Fallback: fetch diff files and find the matching file
-            let files = self.provider.get_diff_files().await?;
+            let files = diff_cache::get_diff_files(self.provider.as_ref()).await?;
             let mut result = (String::new(), String::new());
             for file in &files {
                 if file.filename == file_name {
@@ -139,20 +141,36 @@ impl PRAskLine {
                 image_ref,
             )
             .await?;
+        crate::usage::record_usage(model, &response);
 
         // 6. Sanitize answer
         let answer = crate::tools::ask::sanitize_answer(&response.content);
 
-        // 7. Publish as reply to the code comment, or as a regular comment
-        if comment_id > 0 {
-            self.provider.reply_to_comment(comment_id, &answer).await?;
-        } else if settings.config.publish_output {
+        // 7. Publish as reply in the code comment's thread, or as a regular comment
+        let answer = crate::usage::with_cost_footer(&answer);
+        if !settings.config.publish_output {
+            tracing::info!(answer = %answer, "ask_line answer (publish_output=false)");
+        } else if comment_id > 0 && self.provider.is_supported(Capability::InlineComments) {
+            self.publish_reply(comment_id, &answer).await?;
+        } else {
             self.provider.publish_comment(&answer, false).await?;
         }
 
         Ok(())
     }
 
+    /// Reply in the review thread, or post a regular comment on providers
+    /// without threaded replies.
+    async fn publish_reply(&self, comment_id: u64, answer: &str) -> Result<(), PrAgentError> {
+        match self.provider.reply_to_comment(comment_id, answer).await {
+            Err(PrAgentError::Unsupported(_)) => {
+                self.provider.publish_comment(answer, false).await?;
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
     /// Load conversation history from the review thread.
     ///
     /// Fetches all comments in the same review thread and formats them as a
@@ -199,6 +217,53 @@ impl PRAskLine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::loader::with_settings;
+    use crate::git::types::IssueComment;
+    use crate::testing::fixtures::SAMPLE_PATCH;
+    use crate::testing::mock_ai::MockAiHandler;
+    use crate::testing::mock_git::MockGitProvider;
+
+    fn comment(id: u64, user: &str, body: &str) -> IssueComment {
+        IssueComment {
+            id,
+            body: body.into(),
+            user: user.into(),
+            created_at: String::new(),
+            url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_answer_replies_in_thread_with_history() {
+        let provider = Arc::new(MockGitProvider::new().with_review_thread(vec![
+            comment(1, "alice", "Why print here?"),
+            comment(2, "pr-agent", "It shows the greeting."),
+            comment(3, "alice", "/ask is `x` still needed?"),
+        ]));
+        let ai = Arc::new(MockAiHandler::new("Only for the `dbg!` call."));
+        let tool = PRAskLine::new_with_ai(provider.clone(), ai.clone());
+
+        let mut args = HashMap::new();
+        args.insert("line_start".to_string(), "3".to_string());
+        args.insert("line_end".to_string(), "5".to_string());
+        args.insert("file_name".to_string(), "src/main.rs".to_string());
+        args.insert("comment_id".to_string(), "3".to_string());
+        args.insert("_diff_hunk".to_string(), SAMPLE_PATCH.to_string());
+        args.insert("_text".to_string(), "is `x` still needed?".to_string());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&HashMap::new(), None, None).unwrap());
+        with_settings(settings, tool.run(&args)).await.unwrap();
+
+        let prompt = ai.get_recorded_calls()[0].user.clone();
+        assert!(prompt.contains("1. alice: Why print here?\n2. pr-agent: It shows the greeting."));
+        assert!(!prompt.contains("3. alice"));
+
+        let calls = provider.get_calls();
+        assert!(calls.comments.is_empty());
+        assert_eq!(calls.replies.len(), 1);
+        assert_eq!(calls.replies[0].0, 3);
+        assert!(calls.replies[0].1.contains("Only for the `dbg!` call."));
+    }
 
     #[test]
     fn test_parse_ask_line_args() {
@@ -0,0 +1,241 @@
+//! Auto-approval after `/review` and `/improve` (`config.enable_auto_approval`).
+//!
+//! A review approves the PR when its effort estimate is at or below
+//! `config.auto_approve_for_low_review_effort`; `/improve` approves it when
+//! `config.auto_approve_for_no_suggestions` is set and nothing was found.
+//! Reviews that raise security concerns and `/improve` runs with a failed
+//! batch never approve.
+//! Each approval is followed by a comment saying which rule applied.
+
+use crate::config::types::Settings;
+use crate::git::GitProvider;
+use crate::output::review_formatter::{extract_effort_score, is_value_no, yaml_value_to_string};
+
+/// Why a PR qualifies for auto-approval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalReason {
+    LowReviewEffort { effort: u8, threshold: i32 },
+    NoSuggestions,
+}
+
+impl ApprovalReason {
+    /// The comment posted after approving.
+    fn comment(&self) -> String {
+        let why = match self {
+            ApprovalReason::LowReviewEffort { effort, threshold } => format!(
+                "the estimated review effort ({effort}/5) is at or below the \
+                 `auto_approve_for_low_review_effort` threshold ({threshold})"
+            ),
+            ApprovalReason::NoSuggestions => "`/improve` found no code suggestions \
+                 and `auto_approve_for_no_suggestions` is enabled"
+                .to_string(),
+        };
+        format!("✅ **PR auto-approved**: {why}.")
+    }
+}
+
+/// Approval reason for a parsed review, if any.
+///
+/// A review that reports security concerns never approves, nor, with
+/// `config.ensure_ticket_compliance`, one that lists requirements the PR does
+/// not meet.
+pub fn review_approval_reason(
+    settings: &Settings,
+    data: &serde_yaml_ng::Value,
+) -> Option<ApprovalReason> {
+    let config = &settings.config;
+    let threshold = config.auto_approve_for_low_review_effort;
+    if !config.enable_auto_approval || threshold <= 0 {
+        return None;
+    }
+    let review = data.get("review").unwrap_or(data);
+    let effort = review
+        .get("estimated_effort_to_review_[1-5]")
+        .or_else(|| review.get("estimated_effort_to_review"))
+        .map(extract_effort_score)
+        .filter(|&e| e > 0)?;
+    if i32::from(effort) > threshold {
+        return None;
+    }
+    if let Some(concerns) = review.get("security_concerns").map(yaml_value_to_string)
+        && !is_value_no(&concerns)
+    {
+        tracing::info!("skipping auto-approval: the review reports security concerns");
+        return None;
+    }
+    if config.ensure_ticket_compliance && !is_ticket_compliant(review) {
+        tracing::info!("skipping auto-approval: ticket requirements not met");
+        return None;
+    }
+    Some(ApprovalReason::LowReviewEffort { effort, threshold })
+}
+
+/// Approval reason after `/improve` produced `num_suggestions`, if any.
+///
+/// An empty result only counts when no batch failed: a failed or unparseable
+/// AI call says nothing about the code.
+pub fn improve_approval_reason(
+    settings: &Settings,
+    num_suggestions: usize,
+    failed_batches: usize,
+) -> Option<ApprovalReason> {
+    let config = &settings.config;
+    if !config.enable_auto_approval || !config.auto_approve_for_no_suggestions {
+        return None;
+    }
+    if failed_batches > 0 {
+        tracing::info!(
+            failed_batches,
+            "skipping auto-approval: some batches failed"
+        );
+        return None;
+    }
+    (num_suggestions == 0).then_some(ApprovalReason::NoSuggestions)
+}
+
+/// Whether no ticket in the review's compliance check lists unmet
+/// requirements.
+fn is_ticket_compliant(review: &serde_yaml_ng::Value) -> bool {
+    let Some(tickets) = review
+        .get("ticket_compliance_check")
+        .and_then(|v| v.as_sequence())
+    else {
+        return true;
+    };
+    tickets.iter().all(|ticket| {
+        ticket
+            .get("not_compliant_requirements")
+            .map(yaml_value_to_string)
+            .is_none_or(|s| is_value_no(&s))
+    })
+}
+
+/// Approve the PR and explain why in a comment. Failures are logged, not
+/// returned, so they never fail the tool that triggered them.
+pub async fn approve(provider: &dyn GitProvider, reason: &ApprovalReason) {
+    match provider.auto_approve().await {
+        Ok(true) => {
+            tracing::info!(?reason, "PR auto-approved");
+            if let Err(e) = provider.publish_comment(&reason.comment(), false).await {
+                tracing::warn!(error = %e, "failed to publish auto-approval comment");
+            }
+        }
+        Ok(false) => tracing::warn!("auto-approve returned false (unsupported by provider)"),
+        Err(e) => tracing::error!(error = %e, "auto-approve failed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::load_settings;
+    use crate::testing::mock_git::MockGitProvider;
+    use std::collections::HashMap;
+
+    fn settings(overrides: &[(&str, &str)]) -> Settings {
+        let overrides: HashMap<String, String> = overrides
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        load_settings(&overrides, None, None).unwrap()
+    }
+
+    fn review(yaml: &str) -> serde_yaml_ng::Value {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_review_approval_reason() {
+        let low = review("review:\n  estimated_effort_to_review_[1-5]: 2\n");
+        let high = review("review:\n  estimated_effort_to_review_[1-5]: 4\n");
+        let enabled = settings(&[
+            ("config.enable_auto_approval", "true"),
+            ("config.auto_approve_for_low_review_effort", "2"),
+        ]);
+        assert_eq!(
+            review_approval_reason(&enabled, &low),
+            Some(ApprovalReason::LowReviewEffort {
+                effort: 2,
+                threshold: 2
+            })
+        );
+        assert_eq!(review_approval_reason(&enabled, &high), None);
+
+        // Threshold set but approval disabled, and the -1 default.
+        let disabled = settings(&[("config.auto_approve_for_low_review_effort", "2")]);
+        assert_eq!(review_approval_reason(&disabled, &low), None);
+        let no_threshold = settings(&[("config.enable_auto_approval", "true")]);
+        assert_eq!(review_approval_reason(&no_threshold, &low), None);
+    }
+
+    #[test]
+    fn test_ticket_compliance_blocks_approval() {
+        let data = review(
+            r#"
+review:
+  estimated_effort_to_review_[1-5]: 1
+  ticket_compliance_check:
+    - ticket_url: https://example.com/T-1
+      fully_compliant_requirements: "- Adds the endpoint"
+      not_compliant_requirements: "- Missing pagination"
+"#,
+        );
+        let base = [
+            ("config.enable_auto_approval", "true"),
+            ("config.auto_approve_for_low_review_effort", "2"),
+        ];
+        assert!(review_approval_reason(&settings(&base), &data).is_some());
+        let strict = settings(&[
+            base[0],
+            base[1],
+            ("config.ensure_ticket_compliance", "true"),
+        ]);
+        assert_eq!(review_approval_reason(&strict, &data), None);
+    }
+
+    #[test]
+    fn test_security_concerns_block_approval() {
+        let enabled = settings(&[
+            ("config.enable_auto_approval", "true"),
+            ("config.auto_approve_for_low_review_effort", "2"),
+        ]);
+        let clean =
+            review("review:\n  estimated_effort_to_review_[1-5]: 1\n  security_concerns: No\n");
+        assert!(review_approval_reason(&enabled, &clean).is_some());
+        let concerns = review(
+            "review:\n  estimated_effort_to_review_[1-5]: 1\n  \
+             security_concerns: \"SQL injection: the query interpolates user input\"\n",
+        );
+        assert_eq!(review_approval_reason(&enabled, &concerns), None);
+    }
+
+    #[test]
+    fn test_improve_approval_reason() {
+        let enabled = settings(&[
+            ("config.enable_auto_approval", "true"),
+            ("config.auto_approve_for_no_suggestions", "true"),
+        ]);
+        assert_eq!(
+            improve_approval_reason(&enabled, 0, 0),
+            Some(ApprovalReason::NoSuggestions)
+        );
+        assert_eq!(improve_approval_reason(&enabled, 3, 0), None);
+        assert_eq!(improve_approval_reason(&enabled, 0, 1), None);
+        let off = settings(&[("config.auto_approve_for_no_suggestions", "true")]);
+        assert_eq!(improve_approval_reason(&off, 0, 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_approve_posts_reason() {
+        let provider = MockGitProvider::new();
+        approve(&provider, &ApprovalReason::NoSuggestions).await;
+        let calls = provider.get_calls();
+        assert_eq!(calls.auto_approvals.len(), 1);
+        assert!(calls.comments[0].0.starts_with("✅ **PR auto-approved**"));
+        assert!(
+            calls.comments[0]
+                .0
+                .contains("auto_approve_for_no_suggestions")
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use std::sync::Arc;
+
+use minijinja::Value;
+
+use crate::ai::AiHandler;
+use crate::config::loader::get_settings;
+use crate::config::types::PrUpdateChangelogConfig;
+use crate::error::PrAgentError;
+use crate::git::GitProvider;
+use crate::output::markdown::user_code_span;
+use crate::output::yaml_parser::load_yaml;
+use crate::processing::compression::get_pr_diff;
+use crate::processing::language;
+use crate::tools::{
+    PrMetadata, build_common_vars, render_prompt_fitting_context, with_progress_comment,
+};
+
+/// Type used when `fragment_types` is empty.
+const DEFAULT_FRAGMENT_TYPE: &str = "misc";
+
+/// PR Changelog Fragment tool — write the PR's news fragment to its branch.
+///
+/// Instead of editing `CHANGELOG.md`, which conflicts between PRs, the entry
+/// goes to its own file at `pr_update_changelog.fragment_path` (towncrier
+/// style, e.g. `changes/123.feature.md`), committed to the PR branch with
+/// `create_pr_file`. An existing file is left alone. A release tool later
+/// collects the fragments.
+pub struct PRChangelogFragment {
+    provider: Arc<dyn GitProvider>,
+    ai: Option<Arc<dyn AiHandler>>,
+}
+
+/// A generated fragment and where it goes.
+#[derive(Debug, PartialEq, Eq)]
+struct Fragment {
+    path: String,
+    entry: String,
+}
+
+impl PRChangelogFragment {
+    pub fn new(provider: Arc<dyn GitProvider>) -> Self {
+        Self { provider, ai: None }
+    }
+
+    #[cfg(test)]
+    pub fn new_with_ai(provider: Arc<dyn GitProvider>, ai: Arc<dyn AiHandler>) -> Self {
+        Self {
+            provider,
+            ai: Some(ai),
+        }
+    }
+
+    /// Run the changelog fragment pipeline.
+    pub async fn run(&self) -> Result<(), PrAgentError> {
+        let provider = &self.provider;
+        with_progress_comment(provider.as_ref(), "Writing changelog fragment...", || {
+            self.run_inner()
+        })
+        .await
+    }
+
+    async fn run_inner(&self) -> Result<(), PrAgentError> {
+        let settings = get_settings();
+        let model = &settings.config.model;
+        let config = &settings.pr_update_changelog;
+        let pr_id = self.provider.get_pr_id().to_string();
+        if pr_id.is_empty() {
+            return Err(PrAgentError::Other(
+                "changelog fragments need a PR number for the file name".into(),
+            ));
+        }
+
+        // 1. Fetch PR metadata and diff
+        let mut meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
+        let mut files =
+            super::fetch_diff_files(self.provider.as_ref(), "changelog_fragment").await?;
+        meta.language = language::annotate_files(self.provider.as_ref(), &mut files).await;
+        let mut diff_result = get_pr_diff(&mut files, model, false);
+
+        // 2. Build template variables
+        let pr_link = if config.add_pr_link {
+            self.provider.get_pr_url()
+        } else {
+            ""
+        };
+        let mut vars = build_common_vars(&meta, &diff_result.diff);
+        vars.insert("fragment_types".into(), Value::from(fragment_types(config)));
+        vars.insert("pr_link".into(), Value::from(pr_link));
+        vars.insert("pr_number".into(), Value::from(pr_id.as_str()));
+        vars.insert(
+            "extra_instructions".into(),
+            Value::from(config.extra_instructions.as_str()),
+        );
+
+        // 3. Render prompt and call AI (with fallback models)
+        let (rendered, _overflow) = render_prompt_fitting_context(
+            &settings.pr_changelog_fragment_prompt,
+            vars,
+            &mut files,
+            &mut diff_result,
+            model,
+            false,
+        )?;
+        drop(files);
+        let ai = super::resolve_ai_handler(&self.ai)?;
+        let response = crate::ai::chat_completion_with_fallback(
+            ai.as_ref(),
+            model,
+            &settings.config.fallback_models,
+            &rendered.system,
+            &rendered.user,
+            Some(settings.config.temperature),
+            None,
+        )
+        .await?;
+
+        // 4. Write the fragment to the PR branch
+        let Some(data) = load_yaml(&response.content, &["entry:"], "type", "entry") else {
+            tracing::warn!("could not parse YAML from AI response, skipping the fragment");
+            return Ok(());
+        };
+        let Some(fragment) = build_fragment(&data, config, &pr_id) else {
+            tracing::warn!("AI response has no changelog entry, skipping the fragment");
+            return Ok(());
+        };
+        if !is_repo_relative(&fragment.path) {
+            return Err(PrAgentError::Other(format!(
+                "pr_update_changelog.fragment_path must be a relative path inside the repository, got {}",
+                fragment.path
+            )));
+        }
+        let output = format!(
+            "### Changelog fragment\n\nAdded {} to {}:\n\n```markdown\n{}\n```\n",
+            user_code_span(&fragment.path),
+            user_code_span(&meta.branch),
+            fragment.entry.trim_end()
+        );
+
+        if settings.config.publish_output {
+            let mut message = format!("Add changelog fragment for #{pr_id}");
+            if config.skip_ci_on_push {
+                message.push_str(" [skip ci]");
+            }
+            let created = self
+                .provider
+                .create_pr_file(&fragment.path, fragment.entry.as_bytes(), &message)
+                .await?;
+            if !created {
+                tracing::info!(path = %fragment.path, "changelog fragment already exists, leaving it");
+                let note = format!(
+                    "### Changelog fragment\n\n{} already exists on {}, so it was left \
+                     unchanged. Delete it and run the command again to write a new one.\n",
+                    user_code_span(&fragment.path),
+                    user_code_span(&meta.branch)
+                );
+                self.provider.publish_comment(&note, false).await?;
+                return Ok(());
+            }
+            tracing::info!(path = %fragment.path, "wrote changelog fragment");
+            self.provider.publish_comment(&output, false).await?;
+        } else {
+            println!("{output}");
+        }
+
+        Ok(())
+    }
+}
+
+/// The configured fragment types, or [`DEFAULT_FRAGMENT_TYPE`] alone.
+fn fragment_types(config: &PrUpdateChangelogConfig) -> Vec<String> {
+    let types: Vec<String> = config
+        .fragment_types
+        .iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if types.is_empty() {
+        vec![DEFAULT_FRAGMENT_TYPE.to_string()]
+    } else {
+        types
+    }
+}
+
+/// The fragment file for the parsed AI response. An unknown type falls back
+/// to the last configured one; `None` when the entry is empty.
+fn build_fragment(
+    data: &serde_yaml_ng::Value,
+    config: &PrUpdateChangelogConfig,
+    pr_id: &str,
+) -> Option<Fragment> {
+    let field = |key: &str| {
+        data.get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+    let entry = field("entry");
+    if entry.is_empty() {
+        return None;
+    }
+    let types = fragment_types(config);
+    let picked = field("type");
+    let kind = types
+        .iter()
+        .find(|t| t.eq_ignore_ascii_case(&picked))
+        .or(types.last())
+        .map_or(DEFAULT_FRAGMENT_TYPE, String::as_str);
+    let path = config
+        .fragment_path
+        .replace("{pr_number}", pr_id)
+        .replace("{type}", kind);
+    Some(Fragment {
+        path,
+        entry: format!("{entry}\n"),
+    })
+}
+
+/// Whether `path` is relative and stays inside the repository: no leading
+/// `/`, no `.` or `..` segments, no backslashes.
+fn is_repo_relative(path: &str) -> bool {
+    !path.is_empty()
+        && !path.contains('\\')
+        && path
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::with_settings;
+    use crate::testing::fixtures::{SAMPLE_PATCH, sample_diff_file};
+    use crate::testing::mock_ai::MockAiHandler;
+    use crate::testing::mock_git::MockGitProvider;
+
+    #[test]
+    fn test_build_fragment() {
+        let config = PrUpdateChangelogConfig::default();
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(
+            "type: |\n  Bugfix\nentry: |\n  Fix a crash on empty configs.\n",
+        )
+        .unwrap();
+        assert_eq!(
+            build_fragment(&data, &config, "7"),
+            Some(Fragment {
+                path: "changes/7.bugfix.md".into(),
+                entry: "Fix a crash on empty configs.\n".into(),
+            })
+        );
+
+        let unknown: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str("type: chore\nentry: Tidy up.").unwrap();
+        assert_eq!(
+            build_fragment(&unknown, &config, "7").unwrap().path,
+            "changes/7.misc.md"
+        );
+        let empty: serde_yaml_ng::Value = serde_yaml_ng::from_str("type: doc\nentry: ''").unwrap();
+        assert_eq!(build_fragment(&empty, &config, "7"), None);
+    }
+
+    #[test]
+    fn test_is_repo_relative() {
+        assert!(is_repo_relative("changes/7.bugfix.md"));
+        assert!(is_repo_relative("7.md"));
+        assert!(!is_repo_relative("/etc/passwd"));
+        assert!(!is_repo_relative("changes/../.github/workflows/ci.yml"));
+        assert!(!is_repo_relative("./changes/7.md"));
+        assert!(!is_repo_relative("changes\\..\\ci.yml"));
+        assert!(!is_repo_relative(""));
+    }
+
+    #[tokio::test]
+    async fn test_changelog_fragment_written_to_pr_branch() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new(
+            "```yaml\ntype: feature\nentry: |\n  Add a greeting.\n```",
+        ));
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert(
+            "pr_update_changelog.fragment_path".into(),
+            "newsfragments/{pr_number}.{type}".into(),
+        );
+        let settings = Arc::new(
+            crate::config::loader::load_settings(&overrides, None, None)
+                .expect("should load test settings"),
+        );
+        with_settings(
+            settings,
+            PRChangelogFragment::new_with_ai(provider.clone(), ai.clone()).run(),
+        )
+        .await
+        .unwrap();
+
+        let calls = provider.get_calls();
+        assert_eq!(
+            calls.pr_files,
+            vec![(
+                "newsfragments/42.feature".to_string(),
+                "feature/test".to_string(),
+                "Add a greeting.\n".to_string(),
+                "Add changelog fragment for #42 [skip ci]".to_string(),
+            )]
+        );
+        assert!(calls.comments[0].0.contains("`newsfragments/42.feature`"));
+        let prompt = &ai.get_recorded_calls()[0].system;
+        assert!(prompt.contains("feature, bugfix, doc, removal, misc"));
+    }
+
+    #[tokio::test]
+    async fn test_changelog_fragment_never_replaces_a_file() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+                .with_repo_file("changes/42.feature.md", "Someone else's entry.\n"),
+        );
+        let ai = Arc::new(MockAiHandler::new(
+            "```yaml\ntype: feature\nentry: |\n  Add a greeting.\n```",
+        ));
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        let settings = Arc::new(
+            crate::config::loader::load_settings(&overrides, None, None)
+                .expect("should load test settings"),
+        );
+        with_settings(
+            settings,
+            PRChangelogFragment::new_with_ai(provider.clone(), ai).run(),
+        )
+        .await
+        .unwrap();
+
+        let calls = provider.get_calls();
+        assert!(calls.pr_files.is_empty());
+        assert!(
+            calls.comments[0]
+                .0
+                .contains("`changes/42.feature.md` already exists")
+        );
+    }
+}
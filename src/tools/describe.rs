@@ -1,18 +1,36 @@
 use std::collections::HashMap;
+use std::fmt::Write;
 use std::sync::Arc;
 
+use futures_util::future::join_all;
 use minijinja::Value;
 
 use crate::ai::AiHandler;
+use crate::ai::schema;
 use crate::config::loader::get_settings;
 use crate::error::PrAgentError;
 use crate::git::GitProvider;
-use crate::output::describe_formatter::{FileStats, format_describe_output};
+use crate::git::types::{Capability, FilePatchInfo};
+use crate::integrations::diagram_renderer;
+use crate::integrations::jira::{self, Ticket};
+use crate::notify;
+use crate::output::describe_formatter::{
+    FileStats, describe_labels, fill_description_markers, format_describe_output, format_diagram,
+};
+use crate::output::description_document::DescriptionDocument;
+use crate::output::help_text::{DESCRIBE_HELP_TEXT, add_guide_text};
+use crate::output::json_parser::load_json;
+use crate::output::report::{self, Report};
+use crate::output::terminal;
 use crate::output::yaml_parser::load_yaml;
-use crate::processing::compression::get_pr_diff;
-use crate::template::render::render_prompt;
+use crate::processing::codeowners;
+use crate::processing::compression::{get_pr_diff, get_pr_diff_multiple_patches};
+use crate::processing::diff::main_hunk_range;
+use crate::processing::language;
+use crate::template::render::{RenderedPrompt, render_prompt};
 use crate::tools::{
-    PrMetadata, build_common_vars, insert_custom_labels_vars, with_progress_comment,
+    ContextOverflow, PrMetadata, ai_metadata, build_common_vars, insert_custom_labels_vars,
+    render_prompt_fitting_context, with_progress_comment,
 };
 
 /// PR Description tool.
@@ -48,17 +66,22 @@ impl PRDescription {
 
     async fn run_inner(&self) -> Result<(), PrAgentError> {
         let settings = get_settings();
-        let model = &settings.config.model;
+        let model = settings.config.weak_model();
 
         // 1. Fetch PR metadata
-        let meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
+        let mut meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
 
         // 2. Fetch and process diff
-        let mut files = self.provider.get_diff_files().await?;
+        let mut files = super::fetch_diff_files(self.provider.as_ref(), "describe").await?;
         let num_files = files.len();
         tracing::info!(num_files, "processing changed files for describe");
+        meta.language = language::annotate_files(self.provider.as_ref(), &mut files).await;
+        meta.codeowners =
+            codeowners::changed_file_owners(self.provider.as_ref(), &files, &settings).await;
 
-        let diff_result = get_pr_diff(&mut files, model, true);
+        let ai = super::resolve_ai_handler(&self.ai)?;
+        ai_metadata::summarize_large_patches(ai.as_ref(), &mut files, model, &settings).await;
+        let mut diff_result = get_pr_diff(&mut files, model, true);
 
         // Build per-file stats for the file walkthrough links (only uses metadata fields).
         // base_file/head_file already released by get_pr_diff internally.
@@ -73,35 +96,131 @@ impl PRDescription {
                         num_plus_lines: f.num_plus_lines,
                         num_minus_lines: f.num_minus_lines,
                         link,
+                        main_hunk: main_hunk_range(&f.patch),
+                        owners: meta
+                            .codeowners
+                            .get(&f.filename)
+                            .cloned()
+                            .unwrap_or_default(),
+                        language: f.language.clone(),
                     },
                 )
             })
             .collect();
 
-        // 3. Build template variables
-        let vars = self.build_vars(&meta, &diff_result.diff, num_files);
+        let config = &settings.pr_description;
+        let (yaml_data, raw_response, overflow) = if config.enable_large_pr_handling
+            && config.max_ai_calls > 1
+            && !diff_result.remaining_files.is_empty()
+        {
+            // 3-5. The diff doesn't fit one prompt: describe it in batches
+            let data = self
+                .describe_large_pr(ai.as_ref(), model, &meta, &mut files, num_files)
+                .await?;
+            let raw = serde_yaml_ng::to_string(&data).unwrap_or_default();
+            (Some(data), raw, None)
+        } else {
+            // 3. Build template variables
+            let vars = self.build_vars(&meta, &diff_result.diff, num_files);
+
+            // 4. Render prompt (re-compressing the diff if it overflows the context window)
+            let (rendered, overflow) = render_prompt_fitting_context(
+                &settings.pr_description_prompt,
+                vars,
+                &mut files,
+                &mut diff_result,
+                model,
+                true,
+            )?;
+
+            // 5. Call AI (with fallback models)
+            tracing::info!(model, "calling AI model for describe");
+            let image_urls = super::get_pr_images(
+                &meta.description,
+                self.provider.as_ref(),
+                self.provider.get_pr_number(),
+                true,
+            )
+            .await;
+            // 6. Parse JSON (structured output) or YAML from response
+            let (data, response) = self
+                .call_describe(ai.as_ref(), model, &rendered, image_urls.as_deref())
+                .await?;
+            (data, response, overflow)
+        };
 
-        // 4. Render prompt
-        let rendered = render_prompt(&settings.pr_description_prompt, vars)?;
+        // Persist the structured result so /review and /improve can reuse it
+        if let Some(data) = yaml_data.as_ref() {
+            super::persist_describe_record(self.provider.as_ref(), &settings, data).await;
+        }
 
-        // 5. Call AI (with fallback models)
-        tracing::info!(model, "calling AI model for describe");
-        let ai = super::resolve_ai_handler(&self.ai)?;
-        let image_urls = super::get_pr_images(
-            &meta.description,
-            self.provider.as_ref(),
-            self.provider.get_pr_number(),
-        )
-        .await;
-        let image_ref = image_urls.as_deref();
-        let response = crate::ai::chat_completion_with_fallback(
-            ai.as_ref(),
+        // 7. Format and publish
+        if report::is_enabled()
+            && let Some(data) = yaml_data.as_ref()
+        {
+            let output =
+                format_describe_output(data, &meta.title, &settings.pr_description, &file_stats);
+            report::write(&Report::describe(self.provider.get_pr_url(), data, &output))?;
+        }
+
+        if settings.config.publish_output
+            && settings.pr_description.use_description_markers
+            && !settings.pr_description.publish_description_as_comment
+        {
+            self.publish_with_markers(
+                yaml_data.as_ref(),
+                &meta.title,
+                &meta.description,
+                &file_stats,
+            )
+            .await?;
+        } else if settings.config.publish_output {
+            self.publish_description(
+                yaml_data.as_ref(),
+                &meta.title,
+                &meta.description,
+                &file_stats,
+                &meta.tickets,
+                overflow.as_ref(),
+            )
+            .await?;
+            if let Some(data) = yaml_data.as_ref() {
+                notify::set_summary(notify::summarize_description(data, &meta.title));
+            }
+        } else {
+            self.print_description(yaml_data.as_ref(), &raw_response);
+            if let Some(overflow) = &overflow {
+                eprintln!("{}", overflow.notice());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send a rendered describe prompt, with the JSON schema when
+    /// `structured_output` is on. Returns the parsed description (re-prompting
+    /// once per `reparse_attempts` if it isn't valid) and the response text.
+    async fn call_describe(
+        &self,
+        ai: &dyn AiHandler,
+        model: &str,
+        rendered: &RenderedPrompt,
+        image_urls: Option<&[String]>,
+    ) -> Result<(Option<serde_yaml_ng::Value>, String), PrAgentError> {
+        let settings = get_settings();
+        let schema = settings
+            .config
+            .structured_output
+            .then(schema::describe_schema);
+        let response = crate::ai::chat_completion_with_schema(
+            ai,
             model,
             &settings.config.fallback_models,
             &rendered.system,
             &rendered.user,
             Some(settings.config.temperature),
-            image_ref,
+            image_urls,
+            schema.as_ref(),
         )
         .await?;
 
@@ -109,28 +228,93 @@ impl PRDescription {
             tokens = response.usage.as_ref().map_or(0, |u| u.total_tokens),
             "AI response received"
         );
+        Ok(
+            super::parse_with_reprompt(ai, model, rendered, response.content, parse_description)
+                .await,
+        )
+    }
 
-        // 6. Parse YAML from response
-        let yaml_data = load_yaml(&response.content, &[], "type", "pr_files");
-
-        // 7. Format and publish
-        // Strip any previous pr-agent:describe content from original body
-        // (extract original user-written description)
-        let user_description = strip_pr_agent_content(&meta.description);
-
-        if settings.config.publish_output {
-            self.publish_description(
-                yaml_data.as_ref(),
-                &meta.title,
-                &user_description,
-                &file_stats,
+    /// Describe a PR whose diff doesn't fit one prompt
+    /// (`enable_large_pr_handling`).
+    ///
+    /// The diff is split into up to `max_ai_calls` batches, each described on
+    /// its own (in parallel with `async_ai_calls`). A merge prompt then turns
+    /// the batch summaries into one type, title and description, and the file
+    /// walkthrough is the batches' `pr_files` combined.
+    async fn describe_large_pr(
+        &self,
+        ai: &dyn AiHandler,
+        model: &str,
+        meta: &PrMetadata,
+        files: &mut Vec<FilePatchInfo>,
+        num_files: usize,
+    ) -> Result<serde_yaml_ng::Value, PrAgentError> {
+        let settings = get_settings();
+        let config = &settings.pr_description;
+        let batches =
+            get_pr_diff_multiple_patches(files, model, true, config.max_ai_calls as usize);
+        let num_batches = batches.len();
+        tracing::info!(num_batches, num_files, "describing large PR in batches");
+
+        let progress = terminal::bar(num_batches as u64, "Describing diff batches");
+        let prompt = &settings.pr_description_prompt;
+        let describe_batch = |i: usize, patches| {
+            let progress = &progress;
+            async move {
+                let vars = self.build_vars(meta, patches, num_files);
+                let result = match render_prompt(prompt, vars) {
+                    Ok(rendered) => self.call_describe(ai, model, &rendered, None).await,
+                    Err(e) => Err(e),
+                };
+                progress.inc(1);
+                match result {
+                    Ok((data, _)) => data,
+                    Err(e) => {
+                        tracing::error!(batch = i, error = %e, "describe batch failed");
+                        None
+                    }
+                }
+            }
+        };
+        let summaries: Vec<serde_yaml_ng::Value> = if config.async_ai_calls {
+            join_all(
+                batches
+                    .iter()
+                    .enumerate()
+                    .map(|(i, batch)| describe_batch(i, batch.patches.as_str())),
             )
-            .await?;
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
         } else {
-            self.print_description(yaml_data.as_ref(), &response.content);
+            let mut summaries = Vec::new();
+            for (i, batch) in batches.iter().enumerate() {
+                summaries.extend(describe_batch(i, batch.patches.as_str()).await);
+            }
+            summaries
+        };
+        progress.finish(&format!("Described {num_batches} diff batches"));
+        if summaries.is_empty() {
+            return Err(PrAgentError::AiHandler(
+                "no diff batch of the large PR could be described".into(),
+            ));
         }
 
-        Ok(())
+        let mut vars = self.build_vars(meta, "", num_files);
+        vars.insert(
+            "batch_summaries".into(),
+            Value::from(format_batch_summaries(&summaries)),
+        );
+        let rendered = render_prompt(&settings.pr_description_merge_prompt, vars)?;
+        let merged = match self.call_describe(ai, model, &rendered, None).await {
+            Ok((data, _)) => data,
+            Err(e) => {
+                tracing::error!(error = %e, "merging large PR description failed");
+                None
+            }
+        };
+        Ok(merge_batch_descriptions(merged, &summaries))
     }
 
     fn build_vars(
@@ -152,7 +336,10 @@ impl PRDescription {
             "enable_semantic_files_types".into(),
             Value::from(settings.pr_description.enable_semantic_files_types),
         );
-        vars.insert("related_tickets".into(), Value::from(Vec::<String>::new()));
+        vars.insert(
+            "related_tickets".into(),
+            jira::tickets_prompt_value(&meta.tickets),
+        );
         vars.insert(
             "include_file_summary_changes".into(),
             Value::from(num_files <= 20),
@@ -166,13 +353,17 @@ impl PRDescription {
         vars
     }
 
-    /// Publish the formatted description to the PR.
+    /// Publish the formatted description to the PR. In the PR body, the
+    /// describe section of `current_body` is replaced and the author's text
+    /// around it kept (`add_original_user_description`).
     async fn publish_description(
         &self,
         yaml_data: Option<&serde_yaml_ng::Value>,
         original_title: &str,
-        original_body: &str,
+        current_body: &str,
         file_stats: &HashMap<String, FileStats>,
+        tickets: &[Ticket],
+        overflow: Option<&ContextOverflow>,
     ) -> Result<(), PrAgentError> {
         let settings = get_settings();
 
@@ -181,16 +372,24 @@ impl PRDescription {
             return Ok(());
         };
 
-        let output = format_describe_output(
-            data,
-            original_title,
-            original_body,
-            &settings.pr_description,
-            file_stats,
-        );
+        let mut output =
+            format_describe_output(data, original_title, &settings.pr_description, file_stats);
+        self.render_diagram_image(data, &mut output.body).await;
+        if !tickets.is_empty() {
+            output.body.push_str("\n\n");
+            output.body.push_str(&jira::tickets_markdown(tickets));
+        }
+        if let Some(overflow) = overflow {
+            output.body.push_str("\n\n");
+            output.body.push_str(&overflow.notice());
+        }
+        if settings.pr_description.enable_help_text {
+            add_guide_text(&mut output.body, "", DESCRIBE_HELP_TEXT);
+        }
 
         if settings.pr_description.publish_description_as_comment {
             // Publish as comment instead of editing PR body
+            output.body = crate::usage::with_cost_footer(&output.body);
             if settings
                 .pr_description
                 .publish_description_as_comment_persistent
@@ -209,22 +408,86 @@ impl PRDescription {
             }
         } else {
             // Edit PR title and body directly
+            let mut doc = DescriptionDocument::parse(current_body);
+            if !settings.pr_description.add_original_user_description {
+                doc.clear_user_content();
+            }
+            doc.set_section("describe", &output.body);
             self.provider
-                .publish_description(&output.title, &output.body)
+                .publish_description(&output.title, &doc.render())
                 .await?;
         }
 
-        // Publish labels if enabled
-        if settings.pr_description.publish_labels && !output.labels.is_empty() {
-            self.provider.publish_labels(&output.labels).await?;
+        self.publish_labels(&output.labels).await
+    }
+
+    /// Replace the mermaid diagram in `body` with an image of it
+    /// (`pr_description.render_diagram_image`). Keeps the block when the
+    /// image can't be made.
+    async fn render_diagram_image(&self, data: &serde_yaml_ng::Value, body: &mut String) {
+        if !get_settings().pr_description.render_diagram_image {
+            return;
+        }
+        let diagram = format_diagram(data);
+        if diagram.is_empty() || !body.contains(&diagram) {
+            return;
         }
+        if let Some(image) =
+            diagram_renderer::image_markdown(self.provider.as_ref(), &diagram).await
+        {
+            *body = body.replacen(&diagram, &image, 1);
+        }
+    }
+
+    /// Publish the PR type labels when `pr_description.publish_labels` is
+    /// set and the platform has labels.
+    async fn publish_labels(&self, labels: &[String]) -> Result<(), PrAgentError> {
+        if !get_settings().pr_description.publish_labels || labels.is_empty() {
+            return Ok(());
+        }
+        if !self.provider.is_supported(Capability::Labels) {
+            tracing::info!("provider has no PR labels, skipping describe labels");
+            return Ok(());
+        }
+        self.provider.publish_labels(labels).await
+    }
+
+    /// Fill the `pr_agent:<key>` placeholders in the author's description
+    /// (`use_description_markers`), leaving the rest of the body and the
+    /// title as written.
+    async fn publish_with_markers(
+        &self,
+        yaml_data: Option<&serde_yaml_ng::Value>,
+        title: &str,
+        body: &str,
+        file_stats: &HashMap<String, FileStats>,
+    ) -> Result<(), PrAgentError> {
+        let settings = get_settings();
+        let Some(data) = yaml_data else {
+            tracing::warn!("could not parse YAML from AI response, skipping publish");
+            return Ok(());
+        };
+        let Some(mut filled) =
+            fill_description_markers(body, data, &settings.pr_description, file_stats)
+        else {
+            tracing::warn!(
+                "use_description_markers is on but the description has no pr_agent: markers, leaving it unchanged"
+            );
+            return Ok(());
+        };
+        self.render_diagram_image(data, &mut filled).await;
+        self.provider.publish_description(title, &filled).await?;
 
+        self.publish_labels(&describe_labels(data, &settings.pr_description))
+            .await?;
+        notify::set_summary(notify::summarize_description(data, title));
         Ok(())
     }
 
     /// Print description to stdout (CLI mode, uses raw body).
     fn print_description(&self, yaml_data: Option<&serde_yaml_ng::Value>, raw_response: &str) {
         match yaml_data {
+            Some(data) if terminal::is_json() => println!("{}", terminal::to_json(data)),
             Some(data) => {
                 println!(
                     "{}",
@@ -239,299 +502,51 @@ impl PRDescription {
     }
 }
 
-/// Headers that indicate the body was generated by pr-agent.
-///
-/// Known section headers emitted by pr-agent tools.
-const PR_AGENT_HEADERS: &[&str] = &[
-    "### **user description**",
-    "### **pr type**",
-    "### **pr description**",
-    "### **pr labels**",
-    "### **type**",
-    "### **description**",
-    "### **labels**",
-];
-
-/// Check if a body was generated by pr-agent.
-fn is_generated_by_pr_agent(body: &str) -> bool {
-    let lower = body.trim_start().to_lowercase();
-    // Check for HTML comment marker (Rust pr-agent style)
-    if lower.starts_with("<!-- pr-agent:") {
-        return true;
-    }
-    // Check for known section headers (legacy pr-agent style)
-    PR_AGENT_HEADERS
-        .iter()
-        .any(|header| lower.starts_with(header))
+/// Parse a describe response: JSON (structured output) or YAML.
+fn parse_description(content: &str) -> Option<serde_yaml_ng::Value> {
+    load_json(content).or_else(|| load_yaml(content, &[], "type", "pr_files"))
 }
 
-/// Strip any previous pr-agent generated content from the PR body,
-/// returning only the original user-written description.
-///
-/// Algorithm:
-/// 1. If body has `<!-- pr-agent:` marker, return content before it
-/// 2. If body starts with a pr-agent header → extract the "User description" section
-/// 3. Otherwise → return body as-is
-fn strip_pr_agent_content(body: &str) -> String {
-    // 1. HTML comment marker (Rust pr-agent style)
-    if let Some(pos) = body.find("<!-- pr-agent:") {
-        let before = body[..pos].trim();
-        // Strip the "---" separator that format_describe_output adds between
-        // the user description and the marker.
-        let before = before.strip_suffix("---").unwrap_or(before).trim();
-        return before.to_string();
-    }
-
-    // 2. Check for pr-agent generated headers
-    if !is_generated_by_pr_agent(body) {
-        return body.to_string();
+/// The batch summaries as the merge prompt shows them.
+fn format_batch_summaries(summaries: &[serde_yaml_ng::Value]) -> String {
+    let mut out = String::new();
+    for (i, summary) in summaries.iter().enumerate() {
+        let yaml = serde_yaml_ng::to_string(summary).unwrap_or_default();
+        let _ = write!(out, "Batch {}:\n```yaml\n{}```\n\n", i + 1, yaml);
     }
+    out
+}
 
-    let lower = body.to_lowercase();
-
-    // Find the "user description" section
-    let user_desc_header = "### **user description**";
-    if let Some(start) = lower.find(user_desc_header) {
-        let content_start = start + user_desc_header.len();
-
-        // Find where the user description ends (next pr-agent header)
-        let mut end_pos = body.len();
-        for header in PR_AGENT_HEADERS {
-            if *header == user_desc_header {
-                continue;
-            }
-            if let Some(pos) = lower[content_start..].find(header) {
-                end_pos = end_pos.min(content_start + pos);
-            }
-        }
-
-        let user_content = body[content_start..end_pos].trim();
-        // Strip trailing separator (___) that pr-agent adds
-        let user_content = user_content.strip_suffix("___").unwrap_or(user_content);
-        user_content.trim().to_string()
+/// Combine the merge response with the batches' file walkthroughs. Falls back
+/// to the first batch's type, title and description when the merge failed.
+fn merge_batch_descriptions(
+    merged: Option<serde_yaml_ng::Value>,
+    summaries: &[serde_yaml_ng::Value],
+) -> serde_yaml_ng::Value {
+    use serde_yaml_ng::{Mapping, Value as Yaml};
+
+    let mut data = match merged.or_else(|| summaries.first().cloned()) {
+        Some(Yaml::Mapping(map)) => map,
+        _ => Mapping::new(),
+    };
+    let pr_files: Vec<Yaml> = summaries
+        .iter()
+        .filter_map(|s| s.get("pr_files")?.as_sequence())
+        .flatten()
+        .cloned()
+        .collect();
+    if pr_files.is_empty() {
+        data.remove("pr_files");
     } else {
-        // Body is generated but has no user description section → return empty
-        String::new()
+        data.insert("pr_files".into(), Yaml::Sequence(pr_files));
     }
+    Yaml::Mapping(data)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_strip_pr_agent_content_with_marker() {
-        let body =
-            "User wrote this.\n\n---\n\n<!-- pr-agent:describe -->\n### PR Type\nGenerated stuff";
-        // The "---" separator is stripped — only the user's content is recovered
-        assert_eq!(strip_pr_agent_content(body), "User wrote this.");
-    }
-
-    #[test]
-    fn test_strip_pr_agent_content_without_marker() {
-        let body = "Just a normal body with no markers.";
-        assert_eq!(strip_pr_agent_content(body), body);
-    }
-
-    #[test]
-    fn test_strip_pr_agent_content_empty() {
-        assert_eq!(strip_pr_agent_content(""), "");
-    }
-
-    #[test]
-    fn test_strip_pr_agent_content_marker_at_start() {
-        let body = "<!-- pr-agent:describe -->\nAll generated";
-        assert_eq!(strip_pr_agent_content(body), "");
-    }
-
-    #[test]
-    fn test_strip_pr_agent_content_legacy_format() {
-        // Body generated by pr-agent with User description section
-        let body = "### **User description**\nUser wrote this.\n\n___\n\n### **PR Type**\nEnhancement\n\n___\n\n### **Description**\n- Generated bullet";
-        assert_eq!(strip_pr_agent_content(body), "User wrote this.");
-    }
-
-    #[test]
-    fn test_strip_pr_agent_content_legacy_format_no_user_desc() {
-        // Body generated by pr-agent but without User description section
-        let body = "### **PR Type**\nEnhancement\n\n### **Description**\n- Generated";
-        assert_eq!(strip_pr_agent_content(body), "");
-    }
-
-    #[test]
-    fn test_is_generated_by_pr_agent_html_marker() {
-        assert!(is_generated_by_pr_agent(
-            "<!-- pr-agent:describe -->\nContent"
-        ));
-    }
-
-    #[test]
-    fn test_is_generated_by_pr_agent_legacy_header() {
-        assert!(is_generated_by_pr_agent(
-            "### **User description**\nContent"
-        ));
-        assert!(is_generated_by_pr_agent("### **PR Type**\nContent"));
-    }
-
-    #[test]
-    fn test_is_generated_by_pr_agent_normal_body() {
-        assert!(!is_generated_by_pr_agent("Just a normal PR body."));
-    }
-
-    /// Integration test: simulates running describe twice on the same PR.
-    ///
-    /// First run: user has an original PR body, describe formats it with AI content.
-    /// Second run: the PR body is now the describe output, describe must recover
-    /// the original user description and preserve it in the new output.
-    #[test]
-    fn test_describe_round_trip_preserves_user_description() {
-        use crate::config::types::PrDescriptionConfig;
-        use crate::output::describe_formatter::format_describe_output;
-
-        let ai_yaml = r#"
-title: "AI generated title"
-type: "Enhancement"
-description: "AI generated description of changes"
-"#;
-        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(ai_yaml).unwrap();
-        let config = PrDescriptionConfig {
-            generate_ai_title: true,
-            add_original_user_description: true,
-            ..PrDescriptionConfig::default()
-        };
-        let empty_stats = HashMap::new();
-
-        let user_original_body = "This PR implements feature X.\n\nPlease review carefully.";
-
-        // --- First run: user has their original body ---
-        let first_output = format_describe_output(
-            &data,
-            "Original title",
-            user_original_body,
-            &config,
-            &empty_stats,
-        );
-
-        // User body must appear in the output
-        assert!(
-            first_output.body.contains(user_original_body),
-            "first run must include the user's original body"
-        );
-        assert!(
-            first_output.body.contains("<!-- pr-agent:describe -->"),
-            "first run must include the marker"
-        );
-
-        // User body must be BEFORE the marker so strip_pr_agent_content can find it
-        let marker_pos = first_output
-            .body
-            .find("<!-- pr-agent:describe -->")
-            .unwrap();
-        let user_pos = first_output.body.find(user_original_body).unwrap();
-        assert!(
-            user_pos < marker_pos,
-            "user description must appear before the pr-agent marker"
-        );
-
-        // --- Second run: PR body is now the first_output.body ---
-        // strip_pr_agent_content must recover the original user body
-        let recovered = strip_pr_agent_content(&first_output.body);
-        assert_eq!(
-            recovered.trim(),
-            user_original_body.trim(),
-            "strip_pr_agent_content must recover the original user description after first run"
-        );
-
-        // Format again with the recovered user description
-        let second_output =
-            format_describe_output(&data, "Original title", &recovered, &config, &empty_stats);
-
-        // The user body must still be present in the second output
-        assert!(
-            second_output.body.contains(user_original_body),
-            "second run must still include the user's original body"
-        );
-
-        // And recoverable again (third run would also work)
-        let recovered_again = strip_pr_agent_content(&second_output.body);
-        assert_eq!(
-            recovered_again.trim(),
-            user_original_body.trim(),
-            "strip_pr_agent_content must recover the user description after any number of runs"
-        );
-    }
-
-    /// Integration test: when add_original_user_description is false,
-    /// the marker should be at the start and no user body is embedded.
-    #[test]
-    fn test_describe_round_trip_no_original_description() {
-        use crate::config::types::PrDescriptionConfig;
-        use crate::output::describe_formatter::format_describe_output;
-
-        let ai_yaml = r#"
-title: "AI title"
-type: "Bug fix"
-description: "Fixed the bug"
-"#;
-        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(ai_yaml).unwrap();
-        let config = PrDescriptionConfig {
-            add_original_user_description: false,
-            ..PrDescriptionConfig::default()
-        };
-        let empty_stats = HashMap::new();
-
-        let output =
-            format_describe_output(&data, "Title", "User body here", &config, &empty_stats);
-
-        // User body must NOT be in the output when flag is false
-        assert!(
-            !output.body.contains("User body here"),
-            "user body must not appear when add_original_user_description is false"
-        );
-
-        // Marker should be at the start
-        assert!(
-            output
-                .body
-                .trim_start()
-                .starts_with("<!-- pr-agent:describe -->"),
-            "marker should be at the start when no user description"
-        );
-    }
-
-    /// Integration test: round-trip with the "---" separator.
-    /// Ensures the separator between user body and AI content doesn't leak
-    /// into the recovered user description.
-    #[test]
-    fn test_strip_pr_agent_content_does_not_include_separator() {
-        use crate::config::types::PrDescriptionConfig;
-        use crate::output::describe_formatter::format_describe_output;
-
-        let ai_yaml = r#"
-title: "Title"
-type: "Enhancement"
-description: "Changes"
-"#;
-        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(ai_yaml).unwrap();
-        let config = PrDescriptionConfig {
-            add_original_user_description: true,
-            ..PrDescriptionConfig::default()
-        };
-        let empty_stats = HashMap::new();
-
-        let user_body = "Simple description";
-        let output = format_describe_output(&data, "Title", user_body, &config, &empty_stats);
-        let recovered = strip_pr_agent_content(&output.body);
-
-        // Must not include the "---" separator
-        assert!(
-            !recovered.ends_with("---"),
-            "recovered description must not include the separator: got {:?}",
-            recovered
-        );
-        assert_eq!(recovered.trim(), user_body);
-    }
-
     // ── Integration tests ────────────────────────────────────────────
 
     use crate::config::loader::with_settings;
@@ -646,6 +661,152 @@ description: "Changes"
         );
     }
 
+    #[tokio::test]
+    async fn test_describe_updates_section_in_place() {
+        let prev_body = "Intro.\n\n---\n\n<!-- pr-agent:describe -->\nOld generated content\n\
+                         <!-- /pr-agent:describe -->\n\n---\n\nNotes added after the last run.";
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_pr_description("Title", prev_body)
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new(DESCRIBE_YAML));
+        let describer = PRDescription::new_with_ai(provider.clone(), ai);
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, describer.run()).await.unwrap();
+
+        let body = provider.get_calls().descriptions[0].1.clone();
+        assert!(body.starts_with("Intro.\n\n---\n\n<!-- pr-agent:describe -->\n"));
+        assert!(
+            body.ends_with("<!-- /pr-agent:describe -->\n\n---\n\nNotes added after the last run.")
+        );
+        assert!(!body.contains("Old generated content"));
+        assert_eq!(body.matches("<!-- pr-agent:describe -->").count(), 1);
+
+        // Without add_original_user_description only the section is kept
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_pr_description("Title", prev_body)
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let describer = PRDescription::new_with_ai(
+            provider.clone(),
+            Arc::new(MockAiHandler::new(DESCRIBE_YAML)),
+        );
+        overrides.insert(
+            "pr_description.add_original_user_description".into(),
+            "false".into(),
+        );
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, describer.run()).await.unwrap();
+        let (_, body) = &provider.get_calls().descriptions[0];
+        assert!(body.starts_with("<!-- pr-agent:describe -->"));
+        assert!(!body.contains("Intro.") && !body.contains("Notes added"));
+    }
+
+    #[tokio::test]
+    async fn test_describe_renders_diagram_image() {
+        let yaml = DESCRIBE_YAML.replace(
+            "pr_files:",
+            "changes_diagram: |\n  flowchart LR\n    A[\"main\"] --> B[\"debug output\"]\npr_files:",
+        );
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+                .without(&[Capability::ImageUploads]),
+        );
+        let describer =
+            PRDescription::new_with_ai(provider.clone(), Arc::new(MockAiHandler::new(yaml)));
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("pr_description.render_diagram_image".into(), "true".into());
+        overrides.insert(
+            "pr_description.diagram_renderer_url".into(),
+            "https://kroki.example.com".into(),
+        );
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, describer.run()).await.unwrap();
+
+        let calls = provider.get_calls();
+        let (_, body) = &calls.descriptions[0];
+        assert!(body.contains("### Diagram Walkthrough"));
+        assert!(body.contains("![Diagram](https://kroki.example.com/mermaid/png/"));
+        assert!(!body.contains("```mermaid"));
+    }
+
+    #[tokio::test]
+    async fn test_describe_help_text_after_marker() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_pr_description("Title", "User notes.")
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new(DESCRIBE_YAML));
+        let describer = PRDescription::new_with_ai(provider.clone(), ai);
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("pr_description.enable_help_text".into(), "true".into());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, describer.run()).await.unwrap();
+
+        let calls = provider.get_calls();
+        let (_, body) = &calls.descriptions[0];
+        let marker_pos = body.find("<!-- pr-agent:describe -->").unwrap();
+        let guide_pos = body.find("💡 Tool usage guide:").unwrap();
+        assert!(
+            guide_pos > marker_pos,
+            "guide belongs to the generated part"
+        );
+        assert!(body.contains("`/describe`"));
+        assert_eq!(
+            DescriptionDocument::parse(body).user_description(),
+            "User notes."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_describe_fills_description_markers() {
+        let template = "Closes #12\n\n### Summary\npr_agent:summary\n\n### Checklist\n- [x] tests";
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_pr_description("Author title", template)
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new(DESCRIBE_YAML));
+        let describer = PRDescription::new_with_ai(provider.clone(), ai);
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert(
+            "pr_description.use_description_markers".into(),
+            "true".into(),
+        );
+        overrides.insert("pr_description.generate_ai_title".into(), "true".into());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, describer.run()).await.unwrap();
+
+        let calls = provider.get_calls();
+        let (title, body) = &calls.descriptions[0];
+        assert_eq!(title, "Author title");
+        assert!(body.starts_with("Closes #12\n\n### Summary\n<!-- pr_agent:summary -->\n"));
+        assert!(body.ends_with("<!-- /pr_agent:summary -->\n\n### Checklist\n- [x] tests"));
+        assert!(!body.contains("<!-- pr-agent:describe -->"));
+    }
+
     #[tokio::test]
     async fn test_describe_as_comment_mode() {
         let provider = Arc::new(
@@ -678,6 +839,60 @@ description: "Changes"
         );
     }
 
+    #[tokio::test]
+    async fn test_describe_result_is_reused_by_review() {
+        use crate::analytics::load_describe_record;
+        use crate::testing::fixtures::REVIEW_YAML;
+        use crate::testing::mock_git::MOCK_HEAD_SHA;
+        use crate::tools::review::PRReviewer;
+
+        let folder =
+            std::env::temp_dir().join(format!("pr-agent-describe-reuse-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert(
+            "config.analytics_folder".into(),
+            folder.to_string_lossy().into_owned(),
+        );
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+
+        let describer = PRDescription::new_with_ai(
+            provider.clone(),
+            Arc::new(MockAiHandler::new(DESCRIBE_YAML)),
+        );
+        with_settings(settings.clone(), describer.run())
+            .await
+            .unwrap();
+
+        let record = load_describe_record(&folder, "test-owner/test-repo", 42, MOCK_HEAD_SHA)
+            .unwrap()
+            .expect("describe should persist its result");
+        assert_eq!(record.pr_type, vec!["Enhancement"]);
+        assert_eq!(record.files[0].filename, "src/main.rs");
+
+        let review_ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), review_ai.clone());
+        with_settings(settings, reviewer.run()).await.unwrap();
+
+        let recorded = review_ai.get_recorded_calls();
+        assert!(
+            recorded[0]
+                .user
+                .contains("categorized by a previous PR description run"),
+            "review prompt should include the stored describe result"
+        );
+        assert!(recorded[0].user.contains("- src/main.rs"));
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
     #[tokio::test]
     async fn test_describe_passes_images_to_ai() {
         let img_url = "https://github.com/user-attachments/assets/abc123-design";
@@ -709,4 +924,132 @@ description: "Changes"
         let urls = call.image_urls.as_ref().unwrap();
         assert_eq!(urls, &[img_url]);
     }
+
+    #[tokio::test]
+    async fn test_describe_passes_changed_images_to_ai() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![
+                    sample_diff_file("src/main.rs", SAMPLE_PATCH),
+                    sample_diff_file("ui/login.png", ""),
+                    sample_diff_file("ui/banner.jpg", ""),
+                ])
+                .with_binary_file("ui/login.png", b"PNG")
+                .with_binary_file("ui/banner.jpg", &[0; 2048]),
+        );
+        let ai = Arc::new(MockAiHandler::new(DESCRIBE_YAML));
+        let describer = PRDescription::new_with_ai(provider.clone(), ai.clone());
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "false".into());
+        overrides.insert("config.vision_changed_image_max_kb".into(), "1".into());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, describer.run()).await.unwrap();
+
+        // The JPEG is over the 1 KiB limit
+        let recorded = ai.get_recorded_calls();
+        assert_eq!(
+            recorded[0].image_urls.as_deref(),
+            Some(&["data:image/png;base64,UE5H".to_string()][..])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_describe_vision_file_globs() {
+        let img_url = "https://github.com/user-attachments/assets/abc123-design";
+        for (globs, expect_images) in [("[\"**/*.figma.md\"]", false), ("[\"src/**\"]", true)] {
+            let provider = Arc::new(
+                MockGitProvider::new()
+                    .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+                    .with_pr_description("Test PR", &format!("![design]({img_url})")),
+            );
+            let ai = Arc::new(MockAiHandler::new(DESCRIBE_YAML));
+            let describer = PRDescription::new_with_ai(provider.clone(), ai.clone());
+
+            let repo_toml =
+                format!("[config]\npublish_output = false\nvision_file_globs = {globs}\n");
+            let settings = Arc::new(
+                crate::config::loader::load_settings(
+                    &std::collections::HashMap::new(),
+                    None,
+                    Some(&repo_toml),
+                )
+                .unwrap(),
+            );
+            with_settings(settings, describer.run()).await.unwrap();
+
+            let recorded = ai.get_recorded_calls();
+            assert_eq!(
+                recorded[0].image_urls.is_some(),
+                expect_images,
+                "vision_file_globs = {globs}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_describe_large_pr_in_batches() {
+        let patch = |name: &str| {
+            let mut p = String::from("@@ -0,0 +1,30 @@\n");
+            for i in 0..30 {
+                let _ = writeln!(p, "+let {name}_value_{i} = compute_{name}({i});");
+            }
+            p
+        };
+        let provider = Arc::new(MockGitProvider::new().with_diff_files(vec![
+            sample_diff_file("src/a.rs", &patch("a")),
+            sample_diff_file("src/b.rs", &patch("b")),
+        ]));
+        let batch = |file: &str| {
+            format!(
+                "```yaml\ntype:\n- Enhancement\ndescription: |\n  batch\ntitle: |\n  Batch {file}\n\
+                 pr_files:\n- filename: |\n    {file}\n  changes_title: |\n    Update {file}\n  \
+                 label: |\n    enhancement\n```"
+            )
+        };
+        let merged = "```yaml\ntype:\n- Enhancement\ndescription: |\n  - Merged changes\n\
+                      title: |\n  Merged title\n```";
+        let ai = Arc::new(MockAiHandler::with_responses(vec![
+            batch("src/a.rs"),
+            batch("src/b.rs"),
+            merged.into(),
+        ]));
+        let describer = PRDescription::new_with_ai(provider.clone(), ai.clone());
+
+        // A small context window splits the two files into separate batches.
+        let repo_toml = "[config]\nmodel = \"custom/tiny\"\nmax_model_tokens = 2200\n\
+                         publish_output = true\npublish_output_progress = false\n\
+                         [pr_description]\nasync_ai_calls = false\ngenerate_ai_title = true\n";
+        let settings = Arc::new(
+            crate::config::loader::load_settings(
+                &std::collections::HashMap::new(),
+                None,
+                Some(repo_toml),
+            )
+            .unwrap(),
+        );
+        with_settings(settings, describer.run()).await.unwrap();
+
+        assert_eq!(ai.get_call_count(), 3, "two batches and the merge");
+        let recorded = ai.get_recorded_calls();
+        assert!(recorded[2].user.contains("Batch src/b.rs"));
+
+        let calls = provider.get_calls();
+        let (title, body) = &calls.descriptions[0];
+        assert!(title.contains("Merged title"), "got title: {title}");
+        assert!(body.contains("Update src/a.rs"), "got body: {body}");
+        assert!(body.contains("Update src/b.rs"), "got body: {body}");
+    }
+
+    #[test]
+    fn test_merge_batch_descriptions_falls_back_to_first_batch() {
+        let summaries: Vec<serde_yaml_ng::Value> = vec![
+            serde_yaml_ng::from_str("title: First\npr_files:\n- filename: a.rs\n").unwrap(),
+            serde_yaml_ng::from_str("title: Second\npr_files:\n- filename: b.rs\n").unwrap(),
+        ];
+        let data = merge_batch_descriptions(None, &summaries);
+        assert_eq!(data["title"].as_str(), Some("First"));
+        assert_eq!(data["pr_files"].as_sequence().unwrap().len(), 2);
+    }
 }
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use minijinja::Value;
+
+use crate::ai::AiHandler;
+use crate::ai::router::{ModelRouter, ModelTier};
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::git::GitProvider;
+use crate::git::types::Capability;
+use crate::output::yaml_parser::load_yaml;
+use crate::processing::compression::get_pr_diff;
+use crate::processing::language;
+use crate::tools::{
+    PrMetadata, build_common_vars, insert_custom_labels_vars, render_prompt_fitting_context,
+};
+
+/// PR label generation tool.
+///
+/// Runs only the label-classification part of describe (custom labels, or the
+/// built-in PR types when none are configured) and applies the result as PR
+/// labels. The PR title and description are never touched.
+pub struct PRGenerateLabels {
+    provider: Arc<dyn GitProvider>,
+    ai: Option<Arc<dyn AiHandler>>,
+}
+
+impl PRGenerateLabels {
+    pub fn new(provider: Arc<dyn GitProvider>) -> Self {
+        Self { provider, ai: None }
+    }
+
+    #[cfg(test)]
+    pub fn new_with_ai(provider: Arc<dyn GitProvider>, ai: Arc<dyn AiHandler>) -> Self {
+        Self {
+            provider,
+            ai: Some(ai),
+        }
+    }
+
+    /// Run the label generation pipeline.
+    pub async fn run(&self) -> Result<(), PrAgentError> {
+        let settings = get_settings();
+        if settings.config.publish_output && !self.provider.is_supported(Capability::Labels) {
+            tracing::warn!("provider has no PR labels, nothing to generate");
+            return Ok(());
+        }
+        // Classifying the PR into labels is a cheap subtask.
+        let model = ModelRouter::new(&settings.config).model(ModelTier::Weak);
+
+        // 1. Fetch PR metadata and diff
+        let mut meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
+        let mut files = super::fetch_diff_files(self.provider.as_ref(), "generate_labels").await?;
+        meta.language = language::annotate_files(self.provider.as_ref(), &mut files).await;
+        tracing::info!(
+            num_files = files.len(),
+            "processing changed files for labels"
+        );
+        let mut diff_result = get_pr_diff(&mut files, model, true);
+
+        // 2. Build template variables and render the labels prompt
+        let mut vars = build_common_vars(&meta, &diff_result.diff);
+        vars.insert(
+            "extra_instructions".into(),
+            Value::from(settings.pr_description.extra_instructions.as_str()),
+        );
+        insert_custom_labels_vars(&mut vars, &settings);
+        let (rendered, overflow) = render_prompt_fitting_context(
+            &settings.pr_custom_labels_prompt,
+            vars,
+            &mut files,
+            &mut diff_result,
+            model,
+            true,
+        )?;
+        if let Some(overflow) = &overflow {
+            tracing::warn!(
+                prompt_tokens = overflow.prompt_tokens,
+                max_tokens = overflow.max_tokens,
+                dropped_files = ?overflow.dropped_files,
+                "labels prompt exceeded the context window, diff re-compressed"
+            );
+        }
+
+        // 3. Call AI (with fallback models)
+        tracing::info!(model, "calling AI model for generate_labels");
+        let ai = super::resolve_ai_handler(&self.ai)?;
+        let response = crate::ai::chat_completion_with_fallback(
+            ai.as_ref(),
+            model,
+            &settings.config.fallback_models,
+            &rendered.system,
+            &rendered.user,
+            Some(settings.config.temperature),
+            None,
+        )
+        .await?;
+
+        // 4. Parse labels
+        let labels = load_yaml(&response.content, &[], "labels", "labels")
+            .map(|data| extract_labels(&data))
+            .unwrap_or_default();
+        if labels.is_empty() {
+            tracing::warn!("AI response contained no labels, nothing to apply");
+            return Ok(());
+        }
+
+        // 5. Apply only labels the PR doesn't already have
+        if !settings.config.publish_output {
+            println!("{}", labels.join(", "));
+            return Ok(());
+        }
+        let existing = self.provider.get_pr_labels().await.unwrap_or_default();
+        let new_labels: Vec<String> = labels
+            .into_iter()
+            .filter(|l| !existing.iter().any(|e| e.eq_ignore_ascii_case(l)))
+            .collect();
+        if new_labels.is_empty() {
+            tracing::info!("all generated labels already applied");
+            return Ok(());
+        }
+        tracing::info!(labels = ?new_labels, "applying generated labels");
+        self.provider.publish_labels(&new_labels).await
+    }
+}
+
+/// Extract the `labels` list, accepting a sequence or a comma-separated string.
+fn extract_labels(data: &serde_yaml_ng::Value) -> Vec<String> {
+    let mut labels: Vec<String> = match data.get("labels") {
+        Some(serde_yaml_ng::Value::Sequence(seq)) => seq
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+            .collect(),
+        Some(serde_yaml_ng::Value::String(s)) => {
+            s.split(',').map(|l| l.trim().to_string()).collect()
+        }
+        _ => Vec::new(),
+    };
+    labels.retain(|l| !l.is_empty());
+    labels.dedup();
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::{load_settings, with_settings};
+    use crate::testing::fixtures::{SAMPLE_PATCH, sample_diff_file};
+    use crate::testing::mock_ai::MockAiHandler;
+    use crate::testing::mock_git::MockGitProvider;
+
+    const LABELS_YAML: &str = "```yaml\nlabels:\n- Bug fix\n- Tests\n```";
+
+    #[test]
+    fn test_extract_labels() {
+        let data: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str("labels:\n- Bug fix\n- ''\n- Tests").unwrap();
+        assert_eq!(extract_labels(&data), vec!["Bug fix", "Tests"]);
+
+        let data: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str("labels: Enhancement, Documentation").unwrap();
+        assert_eq!(extract_labels(&data), vec!["Enhancement", "Documentation"]);
+    }
+
+    #[tokio::test]
+    async fn test_generate_labels_applies_labels_without_touching_description() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new(LABELS_YAML));
+        let tool = PRGenerateLabels::new_with_ai(provider.clone(), ai.clone());
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, tool.run()).await.unwrap();
+
+        let calls = provider.get_calls();
+        assert_eq!(calls.labels, vec![vec!["Bug fix", "Tests"]]);
+        assert!(calls.descriptions.is_empty(), "description must not change");
+        assert!(calls.comments.is_empty());
+        assert!(
+            ai.get_recorded_calls()[0]
+                .system
+                .contains("provide labels that describe the PR content")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_labels_uses_custom_labels() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new("labels:\n- database"));
+        let tool = PRGenerateLabels::new_with_ai(provider.clone(), ai.clone());
+
+        let repo_toml = "[config]\npublish_output = true\n\n[custom_labels.database]\ndescription = \"Changes to database schemas\"\n";
+        let settings = Arc::new(
+            load_settings(&std::collections::HashMap::new(), None, Some(repo_toml)).unwrap(),
+        );
+        with_settings(settings, tool.run()).await.unwrap();
+
+        let system = &ai.get_recorded_calls()[0].system;
+        assert!(system.contains("Label('database', description='Changes to database schemas')"));
+        assert!(!system.contains("bug_fix = \"Bug fix\""));
+        assert_eq!(provider.get_calls().labels, vec![vec!["database"]]);
+    }
+
+    #[tokio::test]
+    async fn test_generate_labels_skipped_without_labels() {
+        let provider = Arc::new(MockGitProvider::new().without(&[Capability::Labels]));
+        let ai = Arc::new(MockAiHandler::new(LABELS_YAML));
+        let tool = PRGenerateLabels::new_with_ai(provider.clone(), ai.clone());
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, tool.run()).await.unwrap();
+
+        assert!(ai.get_recorded_calls().is_empty());
+        assert!(provider.get_calls().labels.is_empty());
+    }
+}
@@ -2,8 +2,11 @@ use std::collections::HashSet;
 use std::sync::LazyLock;
 use std::time::Duration;
 
+use base64::Engine;
 use regex::Regex;
 
+use crate::git::types::{EditType, FilePatchInfo};
+
 /// Markdown image: `![alt](url)`
 static MD_IMAGE_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"!\[[^\]]*\]\(([^)\s]+)\)").unwrap());
@@ -151,6 +154,32 @@ pub async fn extract_and_validate_image_urls(text: &str) -> Vec<String> {
     validate_image_urls(urls).await
 }
 
+/// Maximum number of changed image files passed to the model.
+pub const MAX_CHANGED_IMAGES: usize = 4;
+
+/// MIME type of a changed file the model can look at: an added or modified
+/// PNG or JPEG.
+pub fn changed_image_type(file: &FilePatchInfo) -> Option<&'static str> {
+    if !matches!(file.edit_type, EditType::Added | EditType::Modified) {
+        return None;
+    }
+    let lower = file.filename.to_lowercase();
+    if lower.ends_with(".png") {
+        Some("image/png")
+    } else if lower.ends_with(".jpg") || lower.ends_with(".jpeg") {
+        Some("image/jpeg")
+    } else {
+        None
+    }
+}
+
+/// Image bytes as a `data:` URL. All AI handlers accept these, and unlike
+/// blob URLs they don't need access to a private repository.
+pub fn data_url(mime: &str, bytes: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    format!("data:{mime};base64,{encoded}")
+}
+
 /// Relative issue reference: `#123` (avoids matching inside URLs).
 static ISSUE_HASH_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?:^|[^/\w])#(\d+)").unwrap());
@@ -213,6 +242,25 @@ pub fn extract_linked_issue_numbers(text: &str, repo_owner: &str, repo_name: &st
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_changed_image_type() {
+        let file = |name: &str, edit_type| FilePatchInfo {
+            edit_type,
+            ..FilePatchInfo::new(String::new(), String::new(), String::new(), name.into())
+        };
+        assert_eq!(
+            changed_image_type(&file("ui/Login.PNG", EditType::Added)),
+            Some("image/png")
+        );
+        assert_eq!(
+            changed_image_type(&file("a.jpeg", EditType::Modified)),
+            Some("image/jpeg")
+        );
+        assert_eq!(changed_image_type(&file("a.png", EditType::Deleted)), None);
+        assert_eq!(changed_image_type(&file("a.svg", EditType::Added)), None);
+        assert_eq!(data_url("image/png", b"PNG"), "data:image/png;base64,UE5H");
+    }
+
     #[test]
     fn test_extract_markdown_image() {
         let text = "Here is a screenshot: ![alt text](https://example.com/photo.png)";
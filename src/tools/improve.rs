@@ -1,22 +1,45 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use minijinja::Value;
 
 use crate::ai::AiHandler;
+use crate::ai::router::{ModelRouter, ModelTier};
+use crate::ai::schema;
 use crate::config::loader::get_settings;
 use crate::error::PrAgentError;
 use crate::git::GitProvider;
+use crate::git::diff_cache;
+use crate::git::types::{Capability, CodeSuggestion, ReviewComment};
+use crate::notify;
+use crate::output::help_text::{
+    IMPROVE_CHAT_TEXT, IMPROVE_HELP_TEXT, add_chat_text, add_guide_text,
+};
 use crate::output::improve_formatter::{
-    ParsedSuggestion, append_self_review_checkbox, format_suggestions_table, parse_suggestions,
+    BEST_PRACTICE_LABEL, ParsedSuggestion, SuggestionBatch, align_to_diff,
+    append_self_review_checkbox, batch_code_suggestions, format_suggestion_batches,
+    format_suggestions_table, is_self_review_checked, parse_suggestions,
     suggestions_to_code_suggestions,
 };
+use crate::output::json_parser::load_json;
+use crate::output::markdown::persistent_comment_marker;
+use crate::output::report::{self, Report};
+use crate::output::terminal;
 use crate::output::yaml_parser::{load_yaml, yaml_value_as_i64, yaml_value_as_u64};
 use futures_util::future::join_all;
 
 use crate::processing::compression::get_pr_diff_multiple_patches;
+use crate::processing::context_retrieval;
+use crate::processing::language;
+use crate::stats::MetricEvent;
 use crate::template::render::render_prompt;
-use crate::tools::{PrMetadata, build_common_vars, publish_as_comment, with_progress_comment};
+use crate::tools::suggestion_history::{self, SuggestionHistory};
+use crate::tools::{
+    PrMetadata, build_common_vars, publish_as_comment, resolve_guide_text,
+    with_streaming_progress_comment,
+};
+use crate::tools::{accepted_suggestions, ai_metadata, auto_approve};
 
 /// PR Code Suggestions tool.
 ///
@@ -43,7 +66,7 @@ impl PRCodeSuggestions {
     /// Run the full improve pipeline.
     pub async fn run(&self) -> Result<(), PrAgentError> {
         let provider = &self.provider;
-        with_progress_comment(provider.as_ref(), "Preparing code suggestions...", || {
+        with_streaming_progress_comment(provider.as_ref(), "Preparing code suggestions...", || {
             self.run_inner()
         })
         .await
@@ -54,12 +77,19 @@ impl PRCodeSuggestions {
         let model = &settings.config.model;
 
         // 1. Fetch PR metadata
-        let meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
+        let mut meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
 
         // 2. Fetch and split diff into batches (extended mode).
-        let mut files = self.provider.get_diff_files().await?;
+        let mut files = super::fetch_diff_files(self.provider.as_ref(), "improve").await?;
         let num_files = files.len();
         tracing::info!(num_files, "processing changed files for improve");
+        meta.language = language::annotate_files(self.provider.as_ref(), &mut files).await;
+        meta.repo_context =
+            context_retrieval::retrieve(self.provider.as_ref(), &files, &settings).await;
+        let ai = super::resolve_ai_handler(&self.ai)?;
+        meta.ai_metadata =
+            ai_metadata::annotate_files(ai.as_ref(), &mut files, &meta.title, &settings).await;
+        ai_metadata::summarize_large_patches(ai.as_ref(), &mut files, model, &settings).await;
 
         let max_calls = settings.pr_code_suggestions.max_number_of_calls as usize;
 
@@ -82,7 +112,8 @@ impl PRCodeSuggestions {
             return Ok(());
         }
 
-        let ai = super::resolve_ai_handler(&self.ai)?;
+        let describe_context =
+            super::fetch_describe_context(self.provider.as_ref(), &settings).await;
         let num_batches = batches_no_lines.len();
         tracing::info!(num_batches, num_files, "processing PR in extended mode");
 
@@ -91,26 +122,41 @@ impl PRCodeSuggestions {
             &meta.description,
             self.provider.as_ref(),
             self.provider.get_pr_number(),
+            false,
         )
         .await;
         let image_ref = image_urls.as_deref();
 
         // 3. Process batches (parallel or sequential)
-        let all_suggestions = if settings.pr_code_suggestions.parallel_calls && num_batches > 1 {
+        let progress = terminal::bar(num_batches as u64, "Processing diff batches");
+        // Failed or unparseable AI calls; any of them rules out auto-approval.
+        let mut failed_batches = 0;
+        let mut all_suggestions = if settings.pr_code_suggestions.parallel_calls && num_batches > 1
+        {
             let futures: Vec<_> = batches_no_lines
                 .iter()
                 .zip(batches_with_lines.iter())
                 .enumerate()
                 .map(|(i, (batch, batch_lines))| {
-                    self.process_single_batch(
-                        ai.as_ref(),
-                        model,
-                        &meta,
-                        &batch.patches,
-                        &batch_lines.patches,
-                        i,
-                        image_ref,
-                    )
+                    let progress = &progress;
+                    let ai = ai.as_ref();
+                    let (meta, describe_context) = (&meta, &describe_context);
+                    async move {
+                        let result = self
+                            .process_single_batch(
+                                ai,
+                                model,
+                                meta,
+                                describe_context,
+                                &batch.patches,
+                                &batch_lines.patches,
+                                i,
+                                image_ref,
+                            )
+                            .await;
+                        progress.inc(1);
+                        result
+                    }
                 })
                 .collect();
             let results = join_all(futures).await;
@@ -121,6 +167,7 @@ impl PRCodeSuggestions {
                     Ok(s) => s,
                     Err(e) => {
                         tracing::error!(batch = i, error = %e, "batch failed");
+                        failed_batches += 1;
                         Vec::new()
                     }
                 })
@@ -137,6 +184,7 @@ impl PRCodeSuggestions {
                         ai.as_ref(),
                         model,
                         &meta,
+                        &describe_context,
                         &batch.patches,
                         &batch_lines.patches,
                         i,
@@ -145,11 +193,37 @@ impl PRCodeSuggestions {
                     .await
                 {
                     Ok(suggestions) => all.extend(suggestions),
-                    Err(e) => tracing::error!(batch = i, error = %e, "batch failed"),
+                    Err(e) => {
+                        tracing::error!(batch = i, error = %e, "batch failed");
+                        failed_batches += 1;
+                    }
                 }
+                progress.inc(1);
             }
             all
         };
+        progress.finish(&format!("Processed {num_batches} diff batches"));
+
+        if settings.pr_code_suggestions.num_best_practice_suggestions > 0
+            && !meta.best_practices.trim().is_empty()
+        {
+            match self
+                .best_practice_suggestions(
+                    ai.as_ref(),
+                    model,
+                    &meta,
+                    &batches_no_lines[0].patches,
+                    &batches_with_lines[0].patches,
+                )
+                .await
+            {
+                Ok(suggestions) => all_suggestions.extend(suggestions),
+                Err(e) => {
+                    tracing::warn!(error = %e, "best practices pass failed");
+                    failed_batches += 1;
+                }
+            }
+        }
 
         // 4. Filter by score threshold, sort, deduplicate
         let score_threshold = settings
@@ -162,9 +236,41 @@ impl PRCodeSuggestions {
             .collect();
         suggestions.sort_by(|a, b| b.score.cmp(&a.score));
 
+        if report::is_enabled() {
+            report::write(&Report::improve(
+                self.provider.get_pr_url(),
+                &suggestions,
+                settings.pr_code_suggestions.new_score_mechanism_th_high,
+                settings.pr_code_suggestions.new_score_mechanism_th_medium,
+            ))?;
+        }
+
         // 5. Format and publish
         if settings.config.publish_output {
-            self.publish_suggestions(&suggestions, false).await?;
+            let (fresh, history) = self.filter_repeated(&suggestions).await;
+            self.publish_suggestions(&fresh, false, &history).await?;
+            if let Some(reason) =
+                auto_approve::improve_approval_reason(&settings, suggestions.len(), failed_batches)
+            {
+                auto_approve::approve(self.provider.as_ref(), &reason).await;
+            }
+            notify::set_summary(notify::summarize_suggestions(
+                &suggestions,
+                settings.pr_code_suggestions.new_score_mechanism_th_high,
+            ));
+            let th_high = settings.pr_code_suggestions.new_score_mechanism_th_high;
+            super::record_metrics(
+                self.provider.as_ref(),
+                &settings,
+                |repo, pr_number, head_sha| MetricEvent::Suggestions {
+                    repo,
+                    pr_number,
+                    head_sha,
+                    count: suggestions.len(),
+                    critical: suggestions.iter().filter(|s| s.score >= th_high).count(),
+                },
+            )
+            .await;
         } else {
             self.print_suggestions(&suggestions);
         }
@@ -181,6 +287,7 @@ impl PRCodeSuggestions {
         ai: &dyn AiHandler,
         model: &str,
         meta: &PrMetadata,
+        describe_context: &str,
         diff: &str,
         diff_with_lines: &str,
         batch_index: usize,
@@ -189,14 +296,19 @@ impl PRCodeSuggestions {
         let settings = get_settings();
 
         // 1. Build template variables
-        let vars = self.build_vars(meta, diff);
+        let mut vars = self.build_vars(meta, diff);
+        vars.insert("describe_context".into(), Value::from(describe_context));
 
         // 2. Render prompt
         let rendered = render_prompt(&settings.pr_code_suggestions_prompt, vars)?;
 
         // 3. Call AI (generate suggestions, with fallback models)
         tracing::info!(model, batch = batch_index, "calling AI model for improve");
-        let response = crate::ai::chat_completion_with_fallback(
+        let schema = settings
+            .config
+            .structured_output
+            .then(schema::improve_schema);
+        let response = crate::ai::chat_completion_with_schema(
             ai,
             model,
             &settings.config.fallback_models,
@@ -204,6 +316,7 @@ impl PRCodeSuggestions {
             &rendered.user,
             Some(settings.config.temperature),
             image_urls,
+            schema.as_ref(),
         )
         .await?;
 
@@ -213,12 +326,20 @@ impl PRCodeSuggestions {
             "AI response received (improve pass 1)"
         );
 
-        // 4. Parse YAML
-        let yaml_data = load_yaml(&response.content, &[], "code_suggestions", "improved_code");
-        let mut suggestions = yaml_data
-            .as_ref()
-            .map(parse_suggestions)
-            .unwrap_or_default();
+        // 4. Parse JSON (structured output) or YAML
+        let (yaml_data, _) =
+            super::parse_with_reprompt(ai, model, &rendered, response.content, |content| {
+                load_json(content)
+                    .or_else(|| load_yaml(content, &[], "code_suggestions", "improved_code"))
+                    .filter(has_suggestion_list)
+            })
+            .await;
+        let Some(yaml_data) = yaml_data else {
+            return Err(PrAgentError::YamlParse(format!(
+                "could not parse the suggestions of batch {batch_index}"
+            )));
+        };
+        let mut suggestions = parse_suggestions(&yaml_data);
 
         if suggestions.is_empty() {
             return Ok(suggestions);
@@ -226,7 +347,14 @@ impl PRCodeSuggestions {
 
         // 5. Self-reflect pass (per-batch)
         match self
-            .self_reflect_on_suggestions(ai, model, &suggestions, diff_with_lines, &settings)
+            .self_reflect_on_suggestions(
+                ai,
+                ModelRouter::new(&settings.config).model(ModelTier::Weak),
+                &suggestions,
+                diff_with_lines,
+                meta.ai_metadata,
+                &settings,
+            )
             .await
         {
             Ok(feedback) => {
@@ -250,6 +378,88 @@ impl PRCodeSuggestions {
         Ok(suggestions)
     }
 
+    /// Suggestions applying the organization's best practices, from a
+    /// dedicated prompt over the first diff batch. The reflect pass scores and
+    /// locates them like the others; the `num_best_practice_suggestions`
+    /// highest-scoring are kept.
+    async fn best_practice_suggestions(
+        &self,
+        ai: &dyn AiHandler,
+        model: &str,
+        meta: &PrMetadata,
+        diff: &str,
+        diff_with_lines: &str,
+    ) -> Result<Vec<ParsedSuggestion>, PrAgentError> {
+        let settings = get_settings();
+        let limit = settings.pr_code_suggestions.num_best_practice_suggestions;
+
+        let mut vars = self.build_vars(meta, diff);
+        vars.insert("num_code_suggestions".into(), Value::from(limit));
+        let rendered = render_prompt(&settings.pr_best_practices_prompt, vars)?;
+
+        tracing::info!(model, "calling AI model for best practices");
+        let response = crate::ai::chat_completion_with_fallback(
+            ai,
+            model,
+            &settings.config.fallback_models,
+            &rendered.system,
+            &rendered.user,
+            Some(settings.config.temperature),
+            None,
+        )
+        .await?;
+        let mut suggestions: Vec<ParsedSuggestion> =
+            load_yaml(&response.content, &[], "code_suggestions", "improved_code")
+                .as_ref()
+                .map(parse_suggestions)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|s| s.best_practice.is_some())
+                .collect();
+        if suggestions.is_empty() {
+            return Ok(suggestions);
+        }
+
+        match self
+            .self_reflect_on_suggestions(
+                ai,
+                ModelRouter::new(&settings.config).model(ModelTier::Weak),
+                &suggestions,
+                diff_with_lines,
+                meta.ai_metadata,
+                &settings,
+            )
+            .await
+        {
+            Ok(feedback) => apply_reflect_feedback(&mut suggestions, &feedback),
+            Err(e) => {
+                tracing::warn!(error = %e, "reflect pass failed for best practices, using default scores");
+                for s in &mut suggestions {
+                    if s.score == 0 {
+                        s.score = 7;
+                    }
+                }
+            }
+        }
+        suggestions.sort_by_key(|s| std::cmp::Reverse(s.score));
+        suggestions.truncate(limit as usize);
+
+        // Rules from the repo file link to their line; configured ones can't.
+        let from_file = settings.best_practices.content.is_empty();
+        for s in &mut suggestions {
+            s.label = BEST_PRACTICE_LABEL.into();
+            if let Some(rule) = s.best_practice.as_mut()
+                && from_file
+                && let Some(line) = rule_line(&meta.best_practices, &rule.text)
+            {
+                let link = self.provider.get_best_practices_link(line);
+                rule.link = (!link.is_empty()).then_some(link);
+            }
+        }
+        tracing::info!(count = suggestions.len(), "best practice suggestions");
+        Ok(suggestions)
+    }
+
     /// Self-reflect on suggestions: second AI call to score and locate them.
     ///
     /// Second AI call to score and locate each suggestion in the diff.
@@ -259,6 +469,7 @@ impl PRCodeSuggestions {
         model: &str,
         suggestions: &[ParsedSuggestion],
         diff_with_lines: &str,
+        ai_metadata: bool,
         settings: &crate::config::types::Settings,
     ) -> Result<Vec<ReflectFeedback>, PrAgentError> {
         // Build suggestion string for the self-reflect prompt
@@ -289,7 +500,7 @@ impl PRCodeSuggestions {
             "num_code_suggestions".into(),
             Value::from(suggestions.len() as u32),
         );
-        vars.insert("is_ai_metadata".into(), Value::from(false));
+        vars.insert("is_ai_metadata".into(), Value::from(ai_metadata));
         vars.insert(
             "duplicate_prompt_examples".into(),
             Value::from(settings.config.duplicate_prompt_examples),
@@ -354,7 +565,7 @@ impl PRCodeSuggestions {
             "focus_only_on_problems".into(),
             Value::from(settings.pr_code_suggestions.focus_only_on_problems),
         );
-        vars.insert("is_ai_metadata".into(), Value::from(false));
+        vars.insert("is_ai_metadata".into(), Value::from(meta.ai_metadata));
         vars.insert(
             "duplicate_prompt_examples".into(),
             Value::from(settings.config.duplicate_prompt_examples),
@@ -376,10 +587,19 @@ impl PRCodeSuggestions {
     /// 2. **Inline-only** (`commitable_code_suggestions = true`): publish as
     ///    inline GitHub code suggestions; fall back to table on failure.
     /// 3. **Table-only** (default): publish as persistent comment table.
+    ///
+    /// Platforms without code suggestions always get the table.
+    ///
+    /// Inline suggestions go out in per-file review batches (see
+    /// [`Self::publish_inline_batches`]); when there is more than one batch the
+    /// summary comment links each of them.
+    ///
+    /// `history` is stored in the improve comment for the next run.
     async fn publish_suggestions(
         &self,
         suggestions: &[ParsedSuggestion],
         reflect_failed: bool,
+        history: &SuggestionHistory,
     ) -> Result<(), PrAgentError> {
         let settings = get_settings();
 
@@ -391,8 +611,13 @@ impl PRCodeSuggestions {
         tracing::info!(count = suggestions.len(), "publishing code suggestions");
 
         let threshold = settings.pr_code_suggestions.dual_publishing_score_threshold;
+        let commitable = settings.pr_code_suggestions.commitable_code_suggestions;
+        let inline_supported = self.provider.is_supported(Capability::CodeSuggestions);
+        if (threshold > -1 || commitable) && !inline_supported {
+            tracing::info!("provider has no code suggestions, publishing the table only");
+        }
 
-        if threshold > -1 {
+        if threshold > -1 && inline_supported {
             // Dual publishing mode: inline high-scoring + table for all
             let threshold_u32 = threshold.max(0) as u32;
             let high_scoring: Vec<ParsedSuggestion> = suggestions
@@ -400,21 +625,21 @@ impl PRCodeSuggestions {
                 .filter(|s| s.score >= threshold_u32)
                 .cloned()
                 .collect();
+            let high_scoring = self.skip_commented_lines(high_scoring).await;
 
+            let mut batches = Vec::new();
             if !high_scoring.is_empty() {
                 let code_suggestions = suggestions_to_code_suggestions(&high_scoring);
                 if !code_suggestions.is_empty() {
-                    match self
-                        .provider
-                        .publish_code_suggestions(&code_suggestions)
-                        .await
-                    {
-                        Ok(_) => {
+                    let count = code_suggestions.len();
+                    match self.publish_inline_batches(code_suggestions).await {
+                        Ok(published) => {
                             tracing::info!(
-                                count = code_suggestions.len(),
+                                count,
                                 threshold = threshold_u32,
                                 "published inline suggestions (dual mode)"
                             );
+                            batches = published;
                         }
                         Err(e) => {
                             tracing::warn!(error = %e, "failed to publish inline suggestions in dual mode");
@@ -424,42 +649,231 @@ impl PRCodeSuggestions {
             }
 
             // Always publish the full table as well
-            self.publish_table(suggestions, reflect_failed).await?;
-        } else if settings.pr_code_suggestions.commitable_code_suggestions {
+            self.publish_table(suggestions, reflect_failed, &batches, history)
+                .await?;
+        } else if commitable && inline_supported {
             // Inline-only mode
-            let code_suggestions = suggestions_to_code_suggestions(suggestions);
+            let inline = self.skip_commented_lines(suggestions.to_vec()).await;
+            if inline.is_empty() {
+                tracing::info!(
+                    "every suggestion is on an already discussed line, nothing to publish"
+                );
+                return Ok(());
+            }
+            let code_suggestions = suggestions_to_code_suggestions(&inline);
             if code_suggestions.is_empty() {
                 tracing::warn!(
                     total = suggestions.len(),
                     "all suggestions filtered out (missing line numbers), falling back to table mode"
                 );
-                self.publish_table(suggestions, reflect_failed).await?;
+                self.publish_table(suggestions, reflect_failed, &[], history)
+                    .await?;
             } else {
-                match self
-                    .provider
-                    .publish_code_suggestions(&code_suggestions)
-                    .await
-                {
+                match self.publish_inline_batches(code_suggestions).await {
+                    Ok(batches) if batches.len() > 1 => {
+                        self.publish_batch_summary(&batches, history).await?;
+                    }
                     Ok(_) => {}
                     Err(e) => {
                         tracing::warn!(error = %e, "failed to publish inline suggestions, falling back to table mode");
-                        self.publish_table(suggestions, reflect_failed).await?;
+                        self.publish_table(suggestions, reflect_failed, &[], history)
+                            .await?;
                     }
                 }
             }
         } else {
             // Table-only mode
-            self.publish_table(suggestions, reflect_failed).await?;
+            self.publish_table(suggestions, reflect_failed, &[], history)
+                .await?;
         }
 
         Ok(())
     }
 
+    /// Drop suggestions published in the last `max_history_len` runs or
+    /// rejected with a thumbs-down, and add the rest to the history as this
+    /// run. Keeps them all when `max_history_len` is 0.
+    async fn filter_repeated(
+        &self,
+        suggestions: &[ParsedSuggestion],
+    ) -> (Vec<ParsedSuggestion>, SuggestionHistory) {
+        let max_runs = get_settings().pr_code_suggestions.max_history_len as usize;
+        if max_runs == 0 {
+            return (suggestions.to_vec(), SuggestionHistory::default());
+        }
+        let mut history = suggestion_history::load(self.provider.as_ref()).await;
+        let fresh: Vec<ParsedSuggestion> = suggestions
+            .iter()
+            .filter(|s| !history.contains(s))
+            .cloned()
+            .collect();
+        if fresh.len() < suggestions.len() {
+            tracing::info!(
+                skipped = suggestions.len() - fresh.len(),
+                "skipping suggestions already published on this PR"
+            );
+        }
+        history.record(&fresh, max_runs);
+        (fresh, history)
+    }
+
+    /// Drop suggestions whose lines already have a substantive review comment,
+    /// when `pr_code_suggestions.skip_commented_lines` is set. Keeps them all
+    /// when the comments can't be fetched.
+    async fn skip_commented_lines(
+        &self,
+        suggestions: Vec<ParsedSuggestion>,
+    ) -> Vec<ParsedSuggestion> {
+        if !get_settings().pr_code_suggestions.skip_commented_lines || suggestions.is_empty() {
+            return suggestions;
+        }
+        let comments = match self.provider.get_review_comments().await {
+            Ok(comments) => comments,
+            Err(e) => {
+                tracing::debug!(error = %e, "review comments unavailable, not skipping commented lines");
+                return suggestions;
+            }
+        };
+        let discussed: Vec<&ReviewComment> = comments
+            .iter()
+            .filter(|c| is_substantive_comment(&c.body))
+            .collect();
+        let total = suggestions.len();
+        let kept: Vec<ParsedSuggestion> = suggestions
+            .into_iter()
+            .filter(|s| !discussed.iter().any(|c| overlaps_comment(s, c)))
+            .collect();
+        if kept.len() < total {
+            tracing::info!(
+                skipped = total - kept.len(),
+                "skipped suggestions on lines already under discussion"
+            );
+        }
+        kept
+    }
+
+    /// Publish inline suggestions as one review per file, each holding at most
+    /// `max_suggestions_per_review` suggestions, pausing between review calls.
+    ///
+    /// Large single reviews get rejected by the API or bury reviewers, so they
+    /// are split up. Suggestions are first fitted to the diff (see
+    /// [`align_to_diff`]). Returns the batches that were published; fails
+    /// when none of them could be.
+    async fn publish_inline_batches(
+        &self,
+        code_suggestions: Vec<CodeSuggestion>,
+    ) -> Result<Vec<SuggestionBatch>, PrAgentError> {
+        let code_suggestions = self.align_to_diff(code_suggestions).await?;
+        let config = &get_settings().pr_code_suggestions;
+        let batches =
+            batch_code_suggestions(code_suggestions, config.max_suggestions_per_review as usize);
+        let delay = Duration::from_millis(config.suggestions_batch_delay_ms);
+
+        let total = batches.len();
+        let mut published = Vec::with_capacity(total);
+        let mut last_error = None;
+        for (i, mut batch) in batches.into_iter().enumerate() {
+            if i > 0 && !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            // Lets a thumbs-down on the comment be matched to the suggestion.
+            for s in &mut batch.suggestions {
+                let fp = suggestion_history::fingerprint(&s.relevant_file, &s.existing_code);
+                s.body.push_str("\n\n");
+                s.body.push_str(&suggestion_history::inline_marker(&fp));
+            }
+            match self
+                .provider
+                .publish_code_suggestions(&batch.suggestions)
+                .await
+            {
+                Ok(url) => {
+                    batch.url = url;
+                    published.push(batch);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        file = batch.file,
+                        batch = i + 1,
+                        total,
+                        error = %e,
+                        "failed to publish suggestion batch"
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if published.is_empty() => Err(e),
+            _ => Ok(published),
+        }
+    }
+
+    /// Move inline suggestions onto the diff lines holding their code and drop
+    /// those outside the diff, which the provider would reject. Fails when
+    /// none are left; leaves them unchanged when the diff can't be fetched.
+    async fn align_to_diff(
+        &self,
+        code_suggestions: Vec<CodeSuggestion>,
+    ) -> Result<Vec<CodeSuggestion>, PrAgentError> {
+        let files = match diff_cache::get_diff_files(self.provider.as_ref()).await {
+            Ok(files) => files,
+            Err(e) => {
+                tracing::debug!(error = %e, "diff unavailable, publishing suggestions unchecked");
+                return Ok(code_suggestions);
+            }
+        };
+        let total = code_suggestions.len();
+        let aligned = align_to_diff(code_suggestions, &files);
+        if aligned.len() < total {
+            tracing::warn!(
+                dropped = total - aligned.len(),
+                total,
+                "dropped inline suggestions outside the diff"
+            );
+        }
+        if aligned.is_empty() {
+            return Err(PrAgentError::Other(
+                "no inline suggestion lies within the diff".into(),
+            ));
+        }
+        Ok(aligned)
+    }
+
+    /// Publish a summary comment linking the inline suggestion batches.
+    async fn publish_batch_summary(
+        &self,
+        batches: &[SuggestionBatch],
+        history: &SuggestionHistory,
+    ) -> Result<(), PrAgentError> {
+        let settings = get_settings();
+        let body = format!(
+            "{}\n## PR Code Suggestions ✨\n\n{}{}",
+            persistent_comment_marker("improve"),
+            format_suggestion_batches(batches),
+            history.render()
+        );
+        publish_as_comment(
+            self.provider.as_ref(),
+            &body,
+            "improve",
+            settings.pr_code_suggestions.persistent_comment,
+            false,
+        )
+        .await
+    }
+
     /// Publish suggestions as a formatted table (persistent or regular comment).
+    ///
+    /// When inline suggestions went out in several `batches`, the table links
+    /// each of them.
     async fn publish_table(
         &self,
         suggestions: &[ParsedSuggestion],
         reflect_failed: bool,
+        batches: &[SuggestionBatch],
+        history: &SuggestionHistory,
     ) -> Result<(), PrAgentError> {
         let settings = get_settings();
         let mut table = format_suggestions_table(
@@ -468,24 +882,44 @@ impl PRCodeSuggestions {
             settings.pr_code_suggestions.new_score_mechanism_th_medium,
         );
 
+        if batches.len() > 1 {
+            table.push('\n');
+            table.push_str(&format_suggestion_batches(batches));
+        }
+
         if reflect_failed {
             table.push_str("\n> **Note:** Suggestion scoring may be less accurate (self-review pass was unavailable).\n");
         }
 
-        if settings
-            .pr_code_suggestions
-            .demand_code_suggestions_self_review
-        {
+        let config = &settings.pr_code_suggestions;
+        if config.wiki_page_accepted_suggestions {
+            accepted_suggestions::append_checklist(&mut table, suggestions);
+        }
+        let intro = resolve_guide_text(self.provider.as_ref(), &config.intro_text, "").await;
+        let help = if config.enable_help_text {
+            resolve_guide_text(self.provider.as_ref(), &config.help_text, IMPROVE_HELP_TEXT).await
+        } else {
+            String::new()
+        };
+        if config.enable_chat_text {
+            add_chat_text(&mut table, IMPROVE_CHAT_TEXT);
+        }
+        add_guide_text(&mut table, &intro, &help);
+
+        if config.demand_code_suggestions_self_review {
+            // A regenerated persistent comment keeps the author's earlier answer.
+            let checked = config.persistent_comment && self.self_review_checked().await;
             append_self_review_checkbox(
                 &mut table,
-                &settings
-                    .pr_code_suggestions
-                    .code_suggestions_self_review_text,
-                settings.pr_code_suggestions.approve_pr_on_self_review,
-                settings.pr_code_suggestions.fold_suggestions_on_self_review,
+                &config.code_suggestions_self_review_text,
+                config.approve_pr_on_self_review,
+                config.fold_suggestions_on_self_review,
+                checked,
             );
         }
 
+        table.push_str(&history.render());
+
         publish_as_comment(
             self.provider.as_ref(),
             &table,
@@ -496,17 +930,37 @@ impl PRCodeSuggestions {
         .await
     }
 
+    /// Whether the self-review checkbox in the existing improve comment is checked.
+    async fn self_review_checked(&self) -> bool {
+        let marker = persistent_comment_marker("improve");
+        match self.provider.get_issue_comments().await {
+            Ok(comments) => comments
+                .iter()
+                .find(|c| c.body.trim_start().starts_with(&marker))
+                .is_some_and(|c| is_self_review_checked(&c.body)),
+            Err(e) => {
+                tracing::debug!(error = %e, "could not read the previous improve comment");
+                false
+            }
+        }
+    }
+
     /// Print suggestions to stdout (CLI mode).
     fn print_suggestions(&self, suggestions: &[ParsedSuggestion]) {
-        if suggestions.is_empty() {
+        let settings = get_settings();
+        let th_high = settings.pr_code_suggestions.new_score_mechanism_th_high;
+        let th_medium = settings.pr_code_suggestions.new_score_mechanism_th_medium;
+        if terminal::is_json() {
+            println!("{}", terminal::to_json(&suggestions));
+        } else if terminal::is_rich() {
+            print!(
+                "{}",
+                terminal::render_suggestions(suggestions, th_high, th_medium)
+            );
+        } else if suggestions.is_empty() {
             println!("No code suggestions found.");
         } else {
-            let settings = get_settings();
-            let table = format_suggestions_table(
-                suggestions,
-                settings.pr_code_suggestions.new_score_mechanism_th_high,
-                settings.pr_code_suggestions.new_score_mechanism_th_medium,
-            );
+            let table = format_suggestions_table(suggestions, th_high, th_medium);
             println!("{table}");
         }
     }
@@ -520,6 +974,74 @@ struct ReflectFeedback {
     suggestion_score: u32,
 }
 
+/// Whether a parsed response has a suggestion list, even an empty one, where
+/// [`parse_suggestions`] looks for it. Anything else is an unusable answer.
+fn has_suggestion_list(data: &serde_yaml_ng::Value) -> bool {
+    ["code_suggestions", "suggestions", "improve"]
+        .iter()
+        .any(|key| data.get(key).is_some())
+        || data.is_sequence()
+}
+
+/// Whether a review comment says something beyond an acknowledgement like
+/// "LGTM", "+1" or "done".
+fn is_substantive_comment(body: &str) -> bool {
+    const ACKNOWLEDGEMENTS: &[&str] = &[
+        "lgtm",
+        "+1",
+        "ok",
+        "okay",
+        "done",
+        "fixed",
+        "thanks",
+        "thank you",
+        "nice",
+        "agreed",
+        "ack",
+        "👍",
+        "✅",
+    ];
+    let text = body.trim().trim_end_matches(['.', '!']).to_lowercase();
+    !text.is_empty() && !ACKNOWLEDGEMENTS.contains(&text.as_str())
+}
+
+/// Whether `suggestion`'s line range on its file overlaps `comment`'s.
+fn overlaps_comment(suggestion: &ParsedSuggestion, comment: &ReviewComment) -> bool {
+    suggestion.relevant_file == comment.path
+        && suggestion.relevant_lines_start > 0
+        && suggestion.relevant_lines_start <= comment.line
+        && comment.start_line
+            <= suggestion
+                .relevant_lines_end
+                .max(suggestion.relevant_lines_start)
+}
+
+/// 1-based line of `content` holding `rule`, the heading or first line of a
+/// best practice as quoted by the model. Markdown list and heading markers
+/// are ignored.
+fn rule_line(content: &str, rule: &str) -> Option<usize> {
+    fn bare(line: &str) -> String {
+        line.trim()
+            .trim_start_matches(|c: char| {
+                matches!(c, '#' | '-' | '*' | '+' | '.' | ')') || c.is_ascii_digit()
+            })
+            .trim()
+            .trim_matches('*')
+            .trim()
+            .to_lowercase()
+    }
+    let wanted = bare(rule.lines().next()?);
+    if wanted.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = content.lines().map(bare).collect();
+    lines
+        .iter()
+        .position(|line| *line == wanted)
+        .or_else(|| lines.iter().position(|line| line.contains(&wanted)))
+        .map(|i| i + 1)
+}
+
 /// Parse the reflect response YAML into feedback items.
 fn parse_reflect_response(data: &serde_yaml_ng::Value) -> Vec<ReflectFeedback> {
     let suggestions_val = data.get("code_suggestions").unwrap_or(data);
@@ -628,6 +1150,7 @@ code_suggestions:
                 one_sentence_summary: "Fix bug".into(),
                 suggestion_content: "Fix the bug".into(),
                 score: 5,
+                best_practice: None,
             },
             ParsedSuggestion {
                 label: "enhancement".into(),
@@ -639,6 +1162,7 @@ code_suggestions:
                 one_sentence_summary: "Improve".into(),
                 suggestion_content: "Improve this".into(),
                 score: 5,
+                best_practice: None,
             },
         ];
 
@@ -676,6 +1200,7 @@ code_suggestions:
             one_sentence_summary: "Fix".into(),
             suggestion_content: "Fix".into(),
             score: 5,
+            best_practice: None,
         }];
 
         let feedback = vec![ReflectFeedback {
@@ -703,6 +1228,7 @@ code_suggestions:
                 one_sentence_summary: "Fix".into(),
                 suggestion_content: "Fix".into(),
                 score: 5,
+                best_practice: None,
             },
             ParsedSuggestion {
                 label: "enhancement".into(),
@@ -714,6 +1240,7 @@ code_suggestions:
                 one_sentence_summary: "Improve".into(),
                 suggestion_content: "Improve".into(),
                 score: 7,
+                best_practice: None,
             },
         ];
 
@@ -738,6 +1265,7 @@ code_suggestions:
 
     use crate::config::loader::with_settings;
     use crate::config::types::Settings;
+    use crate::git::types::IssueComment;
     use crate::testing::fixtures::{
         IMPROVE_YAML_PASS1, IMPROVE_YAML_PASS2_REFLECT, SAMPLE_PATCH, sample_diff_file,
     };
@@ -793,6 +1321,158 @@ code_suggestions:
         );
     }
 
+    #[tokio::test]
+    async fn test_improve_keeps_self_review_checkbox_state() {
+        let mut provider = MockGitProvider::new()
+            .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]);
+        provider.issue_comments = vec![IssueComment {
+            id: 7,
+            body: "<!-- pr-agent:improve -->\n## PR Code Suggestions ✨\n\n\
+                   - [x]  I reviewed <!-- approve and fold suggestions self-review -->\n"
+                .into(),
+            user: "bot".into(),
+            created_at: String::new(),
+            url: None,
+        }];
+        let provider = Arc::new(provider);
+        let ai = Arc::new(MockAiHandler::with_responses(vec![
+            IMPROVE_YAML_PASS1.into(),
+            IMPROVE_YAML_PASS2_REFLECT.into(),
+        ]));
+        let improver = PRCodeSuggestions::new_with_ai(provider.clone(), ai);
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert(
+            "pr_code_suggestions.demand_code_suggestions_self_review".into(),
+            "true".into(),
+        );
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, improver.run()).await.unwrap();
+
+        let calls = provider.get_calls();
+        let (id, body) = calls.edited_comments.last().expect("comment updated");
+        assert_eq!(id, "7");
+        assert!(is_self_review_checked(body), "got body: {body}");
+    }
+
+    #[tokio::test]
+    async fn test_improve_rerun_skips_published_suggestions() {
+        let run = |issue_comments: Vec<IssueComment>| async move {
+            let mut provider = MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]);
+            provider.issue_comments = issue_comments;
+            let provider = Arc::new(provider);
+            let ai = Arc::new(MockAiHandler::with_responses(vec![
+                IMPROVE_YAML_PASS1.into(),
+                IMPROVE_YAML_PASS2_REFLECT.into(),
+            ]));
+            let improver = PRCodeSuggestions::new_with_ai(provider.clone(), ai);
+            with_settings(test_settings(), improver.run())
+                .await
+                .unwrap();
+            provider.get_calls().comments.clone()
+        };
+
+        let first = run(Vec::new()).await;
+        let body = first[0].0.clone();
+        assert!(
+            body.contains("<!-- pr-agent:suggestion-history"),
+            "got body: {body}"
+        );
+
+        // Same suggestions on the next push: nothing new to publish
+        let second = run(vec![IssueComment {
+            id: 7,
+            body,
+            user: "bot".into(),
+            created_at: String::new(),
+            url: None,
+        }])
+        .await;
+        assert!(second.is_empty(), "got comments: {second:?}");
+    }
+
+    #[tokio::test]
+    async fn test_improve_best_practice_section() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let best_practice = "```yaml
+code_suggestions:
+- relevant_file: |
+    src/main.rs
+  relevant_best_practice: |
+    No debug macros in production code
+  existing_code: |
+    dbg!(x);
+  suggestion_content: |
+    Remove the `dbg!` call
+  improved_code: |
+    tracing::debug!(x);
+  one_sentence_summary: |
+    Log instead of dbg
+```";
+        let reflect = "```yaml
+code_suggestions:
+- suggestion_score: 8
+  relevant_lines_start: 4
+  relevant_lines_end: 4
+```";
+        let ai = Arc::new(MockAiHandler::with_responses(vec![
+            IMPROVE_YAML_PASS1.into(),
+            IMPROVE_YAML_PASS2_REFLECT.into(),
+            best_practice.into(),
+            reflect.into(),
+        ]));
+        let improver = PRCodeSuggestions::new_with_ai(provider.clone(), ai.clone());
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert(
+            "best_practices.content".into(),
+            "# Rules\n- No debug macros in production code\n".into(),
+        );
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, improver.run()).await.unwrap();
+
+        assert_eq!(ai.get_call_count(), 4);
+        let calls = ai.get_recorded_calls();
+        assert!(
+            calls[2]
+                .system
+                .contains("No debug macros in production code")
+        );
+        let comment = &provider.get_calls().comments[0].0;
+        let section = comment
+            .split("### Organization Best Practices")
+            .nth(1)
+            .expect("best practices section");
+        assert!(
+            section.contains("| No debug macros in production code | **Log instead of dbg**"),
+            "got comment: {comment}"
+        );
+    }
+
+    #[test]
+    fn test_rule_line() {
+        let content =
+            "# Team rules\n\n## Errors\n1. **Return typed errors**\n- No panics in library code\n";
+        assert_eq!(rule_line(content, "Return typed errors"), Some(4));
+        assert_eq!(
+            rule_line(content, "- no panics in library code\nmore"),
+            Some(5)
+        );
+        assert_eq!(rule_line(content, "panics in library"), Some(5));
+        assert_eq!(rule_line(content, "Unknown rule"), None);
+        assert_eq!(rule_line(content, ""), None);
+    }
+
     #[tokio::test]
     async fn test_improve_reflect_failure_uses_default_scores() {
         let provider = Arc::new(
@@ -834,6 +1514,44 @@ code_suggestions:
         assert!(calls.comments.is_empty(), "should not publish when no diff");
     }
 
+    #[tokio::test]
+    async fn test_improve_failed_batches_never_auto_approve() {
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("config.enable_auto_approval".into(), "true".into());
+        overrides.insert(
+            "config.auto_approve_for_no_suggestions".into(),
+            "true".into(),
+        );
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+
+        // No responses: every AI call errors. Then a response that never parses.
+        for ai in [
+            MockAiHandler::with_responses(Vec::new()),
+            MockAiHandler::new("not valid yaml at all"),
+        ] {
+            let provider = Arc::new(
+                MockGitProvider::new()
+                    .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+            );
+            let improver = PRCodeSuggestions::new_with_ai(provider.clone(), Arc::new(ai));
+            with_settings(settings.clone(), improver.run())
+                .await
+                .unwrap();
+
+            let calls = provider.get_calls();
+            assert!(calls.auto_approvals.is_empty(), "must not approve");
+            assert!(
+                !calls
+                    .comments
+                    .iter()
+                    .any(|(body, _)| body.contains("PR auto-approved"))
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_improve_high_level_suggestions() {
         // Suggestions with lines 0-0 should appear as "Architecture & Design" bullet list
@@ -938,4 +1656,283 @@ code_suggestions:
             "reflect pass should NOT include images"
         );
     }
+
+    fn parsed_suggestion(file: &str, line: i32) -> ParsedSuggestion {
+        ParsedSuggestion {
+            label: "bug fix".into(),
+            relevant_file: file.into(),
+            relevant_lines_start: line,
+            relevant_lines_end: line,
+            existing_code: "let x = 1;".into(),
+            improved_code: "let x = 2;".into(),
+            one_sentence_summary: "Fix value".into(),
+            suggestion_content: "The value should be 2".into(),
+            score: 8,
+            best_practice: None,
+        }
+    }
+
+    /// Diff in which every line of `src/a.rs` and `src/b.rs` holds the code of
+    /// [`parsed_suggestion`].
+    fn suggestion_diff() -> Vec<crate::git::types::FilePatchInfo> {
+        let patch = format!("@@ -0,0 +1,12 @@\n{}", "+let x = 1;\n".repeat(12));
+        vec![
+            sample_diff_file("src/a.rs", &patch),
+            sample_diff_file("src/b.rs", &patch),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_improve_commitable_suggestions_published_in_batches() {
+        let provider = Arc::new(MockGitProvider::new().with_diff_files(suggestion_diff()));
+        let improver = PRCodeSuggestions::new_with_ai(
+            provider.clone(),
+            Arc::new(MockAiHandler::new(IMPROVE_YAML_PASS1)),
+        );
+        let suggestions = vec![
+            parsed_suggestion("src/a.rs", 1),
+            parsed_suggestion("src/b.rs", 5),
+            parsed_suggestion("src/a.rs", 9),
+            parsed_suggestion("src/a.rs", 12),
+        ];
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert(
+            "pr_code_suggestions.commitable_code_suggestions".into(),
+            "true".into(),
+        );
+        overrides.insert(
+            "pr_code_suggestions.max_suggestions_per_review".into(),
+            "2".into(),
+        );
+        overrides.insert(
+            "pr_code_suggestions.suggestions_batch_delay_ms".into(),
+            "0".into(),
+        );
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(
+            settings,
+            improver.publish_suggestions(&suggestions, false, &SuggestionHistory::default()),
+        )
+        .await
+        .unwrap();
+
+        let calls = provider.get_calls();
+        let batches: Vec<Vec<(&str, i32)>> = calls
+            .code_suggestions
+            .iter()
+            .map(|batch| {
+                batch
+                    .iter()
+                    .map(|s| (s.relevant_file.as_str(), s.relevant_lines_start))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            batches,
+            vec![
+                vec![("src/a.rs", 1), ("src/a.rs", 9)],
+                vec![("src/a.rs", 12)],
+                vec![("src/b.rs", 5)],
+            ]
+        );
+
+        let summary = &calls.comments[0].0;
+        assert!(summary.starts_with("<!-- pr-agent:improve -->"));
+        assert!(summary.contains("published in 3 review batches"));
+        assert!(summary.contains(
+            "| [#3](https://github.com/owner/repo/pull/1#pullrequestreview-3) | `src/b.rs` | 1 |"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_improve_publishes_table_without_code_suggestions() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(suggestion_diff())
+                .without(&[Capability::CodeSuggestions]),
+        );
+        let improver = PRCodeSuggestions::new_with_ai(
+            provider.clone(),
+            Arc::new(MockAiHandler::new(IMPROVE_YAML_PASS1)),
+        );
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert(
+            "pr_code_suggestions.commitable_code_suggestions".into(),
+            "true".into(),
+        );
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(
+            settings,
+            improver.publish_suggestions(
+                &[parsed_suggestion("src/a.rs", 1)],
+                false,
+                &SuggestionHistory::default(),
+            ),
+        )
+        .await
+        .unwrap();
+
+        let calls = provider.get_calls();
+        assert!(calls.code_suggestions.is_empty());
+        assert!(calls.comments[0].0.contains("src/a.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_improve_inline_suggestions_fitted_to_diff() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let improver = PRCodeSuggestions::new_with_ai(
+            provider.clone(),
+            Arc::new(MockAiHandler::new(IMPROVE_YAML_PASS1)),
+        );
+        let mut off_by_one = parsed_suggestion("src/main.rs", 4);
+        off_by_one.existing_code = "let x = 42;".into();
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert(
+            "pr_code_suggestions.commitable_code_suggestions".into(),
+            "true".into(),
+        );
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(
+            settings.clone(),
+            improver.publish_suggestions(
+                &[off_by_one, parsed_suggestion("src/other.rs", 1)],
+                false,
+                &SuggestionHistory::default(),
+            ),
+        )
+        .await
+        .unwrap();
+        let published: Vec<(String, i32)> = provider.get_calls().code_suggestions[0]
+            .iter()
+            .map(|s| (s.relevant_file.clone(), s.relevant_lines_start))
+            .collect();
+        assert_eq!(published, vec![("src/main.rs".to_string(), 3)]);
+
+        // Nothing within the diff: falls back to the table
+        let provider = Arc::new(MockGitProvider::new());
+        let improver = PRCodeSuggestions::new_with_ai(
+            provider.clone(),
+            Arc::new(MockAiHandler::new(IMPROVE_YAML_PASS1)),
+        );
+        with_settings(
+            settings,
+            improver.publish_suggestions(
+                &[parsed_suggestion("src/other.rs", 1)],
+                false,
+                &SuggestionHistory::default(),
+            ),
+        )
+        .await
+        .unwrap();
+        let calls = provider.get_calls();
+        assert!(calls.code_suggestions.is_empty());
+        assert!(calls.comments[0].0.contains("src/other.rs"));
+    }
+
+    fn review_comment(path: &str, lines: (i32, i32), body: &str) -> ReviewComment {
+        ReviewComment {
+            id: 1,
+            path: path.into(),
+            start_line: lines.0,
+            line: lines.1,
+            body: body.into(),
+            user: "alice".into(),
+            thumbs_down: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_improve_skips_lines_under_discussion() {
+        let mut provider = MockGitProvider::new().with_diff_files(suggestion_diff());
+        provider.review_comments = vec![
+            review_comment("src/a.rs", (8, 10), "Should this handle the empty case?"),
+            review_comment("src/b.rs", (5, 5), "LGTM!"),
+        ];
+        let provider = Arc::new(provider);
+        let improver = PRCodeSuggestions::new_with_ai(
+            provider.clone(),
+            Arc::new(MockAiHandler::new(IMPROVE_YAML_PASS1)),
+        );
+        let suggestions = vec![
+            parsed_suggestion("src/a.rs", 1),
+            parsed_suggestion("src/a.rs", 9),
+            parsed_suggestion("src/b.rs", 5),
+        ];
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert(
+            "pr_code_suggestions.commitable_code_suggestions".into(),
+            "true".into(),
+        );
+        overrides.insert(
+            "pr_code_suggestions.suggestions_batch_delay_ms".into(),
+            "0".into(),
+        );
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(
+            settings.clone(),
+            improver.publish_suggestions(&suggestions, false, &SuggestionHistory::default()),
+        )
+        .await
+        .unwrap();
+
+        let published: Vec<(String, i32)> = provider
+            .get_calls()
+            .code_suggestions
+            .iter()
+            .flatten()
+            .map(|s| (s.relevant_file.clone(), s.relevant_lines_start))
+            .collect();
+        assert_eq!(
+            published,
+            vec![("src/a.rs".to_string(), 1), ("src/b.rs".to_string(), 5)]
+        );
+
+        // With the setting off, every suggestion goes out
+        let mut off = (*settings).clone();
+        off.pr_code_suggestions.skip_commented_lines = false;
+        let mut commented = MockGitProvider::new().with_diff_files(suggestion_diff());
+        commented.review_comments = vec![review_comment("src/a.rs", (9, 9), "Why?")];
+        let commented = Arc::new(commented);
+        let improver = PRCodeSuggestions::new_with_ai(
+            commented.clone(),
+            Arc::new(MockAiHandler::new(IMPROVE_YAML_PASS1)),
+        );
+        with_settings(
+            Arc::new(off),
+            improver.publish_suggestions(&suggestions, false, &SuggestionHistory::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            commented
+                .get_calls()
+                .code_suggestions
+                .iter()
+                .flatten()
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_is_substantive_comment() {
+        assert!(is_substantive_comment("Could this overflow?"));
+        assert!(!is_substantive_comment("  LGTM.  "));
+        assert!(!is_substantive_comment("👍"));
+        assert!(!is_substantive_comment(""));
+    }
 }
@@ -1,29 +1,85 @@
+pub mod accepted_suggestions;
+pub mod ai_metadata;
+pub mod analyze;
 pub mod ask;
 pub mod ask_line;
+pub mod auto_approve;
+pub mod changelog_fragment;
+pub mod config;
 pub mod describe;
+pub mod generate_labels;
 pub mod image;
 pub mod improve;
+pub mod quick_take;
+pub mod resolve;
 pub mod review;
+pub mod suggestion_history;
+pub mod test_gen;
 
 use std::collections::HashMap;
 use std::fmt::Write;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use minijinja::Value;
 
 use crate::ai::AiHandler;
+use crate::ai::anthropic::{AnthropicHandler, is_anthropic_model};
+use crate::ai::bedrock::{BedrockHandler, is_bedrock_model};
+use crate::ai::cache;
+use crate::ai::fixed::{FixedResponseHandler, is_mock_model};
+use crate::ai::ollama::{OllamaHandler, is_ollama_model};
 use crate::ai::openai::OpenAiCompatibleHandler;
-use crate::config::loader::{get_settings, load_settings, with_settings};
-use crate::config::types::{CustomLabelEntry, Settings};
+use crate::ai::stream::{StreamProgress, with_stream_progress};
+use crate::ai::token::{
+    OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD, get_max_tokens_with_fallback, tokenizer_for,
+};
+use crate::analytics::{
+    DescribeRecord, FindingStatus, ReviewRecord, load_describe_record, save_describe_record,
+    save_review_record,
+};
+use crate::audit;
+use crate::config::loader::{get_settings, load_command_settings, with_settings};
+use crate::config::types::{CustomLabelEntry, PromptTemplate, Settings};
 use crate::error::PrAgentError;
 use crate::git::GitProvider;
+use crate::git::diff_cache;
+use crate::git::dry_run::DryRunProvider;
+use crate::git::types::{CheckRunReport, FilePatchInfo, PlanningContext};
+use crate::integrations::jira::{self, Ticket};
+use crate::metrics;
+use crate::notify;
+use crate::output::markdown::{sanitize_user_markdown, user_code_span};
+use crate::output::terminal;
+use crate::output::yaml_parser::yaml_parse_error;
+use crate::processing::codeowners::{self, FileOwners};
+use crate::processing::compression::{PrDiffResult, get_pr_diff_with_reserve};
+use crate::processing::filter::{filter_files, glob_matches};
+use crate::processing::token_budget::{self, PromptComponent};
+use crate::stats::{self, MetricEvent};
+use crate::template::render::{RenderedPrompt, render_prompt};
+use crate::usage;
 
 /// Resolve the AI handler: use the injected one or create from settings.
+///
+/// A `mock/` model selects the canned-response handler instead of a real provider.
 pub fn resolve_ai_handler(
     injected: &Option<Arc<dyn AiHandler>>,
 ) -> Result<Arc<dyn AiHandler>, PrAgentError> {
     match injected {
         Some(ai) => Ok(ai.clone()),
+        None if is_mock_model(&get_settings().config.model) => {
+            Ok(Arc::new(FixedResponseHandler::from_settings()?))
+        }
+        None if is_anthropic_model(&get_settings().config.model) => {
+            Ok(Arc::new(AnthropicHandler::from_settings()?))
+        }
+        None if is_bedrock_model(&get_settings().config.model) => {
+            Ok(Arc::new(BedrockHandler::from_settings()?))
+        }
+        None if is_ollama_model(&get_settings().config.model) => {
+            Ok(Arc::new(OllamaHandler::from_settings()?))
+        }
         None => Ok(Arc::new(OpenAiCompatibleHandler::from_settings()?)),
     }
 }
@@ -39,13 +95,31 @@ pub struct PrMetadata {
     pub commit_messages: String,
     pub best_practices: String,
     pub repo_metadata: String,
+    /// Main language of the PR; set by tools that run
+    /// [`crate::processing::language::annotate_files`] once the diff is known.
+    pub language: String,
+    /// Related repository snippets; filled in by tools that use
+    /// [`crate::processing::context_retrieval::retrieve`] once the diff is known.
+    pub repo_context: String,
+    /// Whether the changed files carry AI summaries (`config.enable_ai_metadata`);
+    /// set by tools that run [`ai_metadata::annotate_files`].
+    pub ai_metadata: bool,
+    /// CODEOWNERS owners of the changed files; filled in by tools that run
+    /// [`codeowners::changed_file_owners`] once the diff is known.
+    pub codeowners: FileOwners,
+    /// Milestone/project fields; empty unless `add_planning_context` is on.
+    pub planning: PlanningContext,
+    /// Jira tickets referenced by the PR; empty unless `[jira]` is set up.
+    pub tickets: Vec<Ticket>,
 }
 
 impl PrMetadata {
     /// Fetch all common PR metadata from the provider and settings.
     ///
     /// This consolidates the identical metadata-fetching code that was
-    /// duplicated across review, describe, and improve tools.
+    /// duplicated across review, describe, and improve tools. Commit
+    /// messages, best practices and repo metadata are clipped to their
+    /// `max_*_tokens` caps.
     pub async fn fetch(
         provider: &dyn GitProvider,
         settings: &Settings,
@@ -63,18 +137,166 @@ impl PrMetadata {
             }
         };
         let repo_metadata = provider.get_repo_metadata().await.unwrap_or_default();
+        let planning = if settings.config.add_planning_context {
+            provider.get_planning_context().await.unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "failed to fetch planning context");
+                PlanningContext::default()
+            })
+        } else {
+            PlanningContext::default()
+        };
+        let tickets = if settings.jira.is_configured() {
+            jira::fetch_related_tickets(settings, &title, &branch, &description).await
+        } else {
+            Vec::new()
+        };
 
+        let cap =
+            |component, text: &str| token_budget::cap_component(component, text, &settings.config);
         Ok(Self {
             title,
             description,
             branch,
-            commit_messages,
-            best_practices,
-            repo_metadata,
+            commit_messages: cap(PromptComponent::CommitMessages, &commit_messages),
+            best_practices: cap(PromptComponent::BestPractices, &best_practices),
+            repo_metadata: cap(PromptComponent::RepoMetadata, &repo_metadata),
+            language: String::new(),
+            repo_context: String::new(),
+            ai_metadata: false,
+            codeowners: FileOwners::new(),
+            planning,
+            tickets,
         })
     }
 }
 
+/// Fetch the PR's changed files behind a terminal progress spinner, without
+/// binary files and those matched by `[ignore]` or
+/// `config.ignore_language_framework`.
+pub async fn fetch_diff_files(
+    provider: &dyn GitProvider,
+    tool: &str,
+) -> Result<Vec<FilePatchInfo>, PrAgentError> {
+    let progress = terminal::spinner("Fetching PR diff");
+    let mut files = diff_cache::get_diff_files(provider).await?;
+    let excluded = filter_files(&mut files);
+    if excluded > 0 {
+        tracing::info!(
+            tool,
+            excluded,
+            "excluded binary and ignored files from the diff"
+        );
+    }
+    progress.finish(&format!("Fetched {} changed files", files.len()));
+    Ok(files)
+}
+
+/// Identify the PR for the analytics store: `("owner/repo", pr_number, head_sha)`.
+///
+/// Returns `None` when the provider can't report all three.
+async fn analytics_key(provider: &dyn GitProvider) -> Option<(String, u64, String)> {
+    let (owner, repo) = provider.repo_owner_and_name();
+    let pr_number = provider.get_pr_number()?;
+    if owner.is_empty() || repo.is_empty() {
+        return None;
+    }
+    match provider.get_pr_head_sha().await {
+        Ok(sha) if !sha.is_empty() => Some((format!("{owner}/{repo}"), pr_number, sha)),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::debug!(error = %e, "head SHA unavailable, skipping analytics store");
+            None
+        }
+    }
+}
+
+/// Persist a parsed `/describe` result to the analytics store, if enabled.
+///
+/// Failures are logged and never abort the describe run.
+pub async fn persist_describe_record(
+    provider: &dyn GitProvider,
+    settings: &Settings,
+    data: &serde_yaml_ng::Value,
+) {
+    let folder = &settings.config.analytics_folder;
+    if folder.is_empty() {
+        return;
+    }
+    let Some((repo, pr_number, sha)) = analytics_key(provider).await else {
+        return;
+    };
+    let record = DescribeRecord::from_describe_yaml(data, &repo, pr_number, &sha);
+    if let Err(e) = save_describe_record(std::path::Path::new(folder), &record) {
+        tracing::warn!(error = %e, "failed to persist describe record");
+    }
+}
+
+/// Persist the findings of a parsed `/review` result to the analytics store,
+/// if enabled, for the weekly digest's open findings.
+///
+/// Failures are logged and never abort the review run.
+pub async fn persist_review_record(
+    provider: &dyn GitProvider,
+    settings: &Settings,
+    data: &serde_yaml_ng::Value,
+) {
+    let folder = &settings.config.analytics_folder;
+    if folder.is_empty() {
+        return;
+    }
+    let Some((repo, pr_number, sha)) = analytics_key(provider).await else {
+        return;
+    };
+    let record = ReviewRecord::from_review_yaml(data, &repo, pr_number, &sha);
+    if let Err(e) = save_review_record(std::path::Path::new(folder), &record) {
+        tracing::warn!(error = %e, "failed to persist review record");
+    }
+}
+
+/// Record a published tool run in the metrics store, if enabled. `event`
+/// builds the event from the PR's repo, number and head SHA.
+///
+/// Dry runs are not recorded.
+pub async fn record_metrics(
+    provider: &dyn GitProvider,
+    settings: &Settings,
+    event: impl FnOnce(String, u64, String) -> MetricEvent,
+) {
+    if settings.metrics_store.database_url.trim().is_empty() || settings.config.dry_run {
+        return;
+    }
+    let Some((repo, pr_number, sha)) = analytics_key(provider).await else {
+        return;
+    };
+    stats::record(&event(repo, pr_number, sha)).await;
+}
+
+/// Load the `/describe` result stored for the PR's current head commit and
+/// render it for the `describe_context` prompt variable.
+///
+/// Returns an empty string when the store is disabled or describe hasn't
+/// run on this commit yet.
+pub async fn fetch_describe_context(provider: &dyn GitProvider, settings: &Settings) -> String {
+    let folder = &settings.config.analytics_folder;
+    if folder.is_empty() {
+        return String::new();
+    }
+    let Some((repo, pr_number, sha)) = analytics_key(provider).await else {
+        return String::new();
+    };
+    match load_describe_record(std::path::Path::new(folder), &repo, pr_number, &sha) {
+        Ok(Some(record)) => {
+            tracing::info!(files = record.files.len(), "reusing stored describe result");
+            record.to_prompt_context()
+        }
+        Ok(None) => String::new(),
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to load describe record");
+            String::new()
+        }
+    }
+}
+
 /// Run a tool's inner logic wrapped with progress comment lifecycle.
 ///
 /// If `publish_output_progress` is enabled, creates a progress comment before
@@ -105,6 +327,71 @@ where
     result
 }
 
+/// Like [`with_progress_comment`], but streams the AI answer while `inner` runs.
+///
+/// With `config.stream_progress_interval` set, completions are streamed and
+/// the progress comment is edited to "Generating… (N tokens)" on every tick.
+/// Once `config.generation_deadline` has passed (checked on each tick), the
+/// streams are cancelled and `inner` continues with the partial answer.
+pub async fn with_streaming_progress_comment<F, Fut>(
+    provider: &dyn GitProvider,
+    message: &str,
+    inner: F,
+) -> Result<(), PrAgentError>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<(), PrAgentError>>,
+{
+    let settings = get_settings();
+    let interval = settings.config.stream_progress_interval;
+    if interval == 0 {
+        return with_progress_comment(provider, message, inner).await;
+    }
+
+    let progress_comment_id = if settings.config.publish_output_progress {
+        provider.publish_comment(message, true).await.ok().flatten()
+    } else {
+        None
+    };
+    let deadline = (settings.config.generation_deadline > 0)
+        .then(|| Instant::now() + Duration::from_secs(settings.config.generation_deadline));
+
+    let progress = Arc::new(StreamProgress::default());
+    let run = with_stream_progress(progress.clone(), inner());
+    tokio::pin!(run);
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut reported = 0;
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            _ = ticker.tick() => {
+                if deadline.is_some_and(|d| Instant::now() >= d) && !progress.is_cancelled() {
+                    tracing::warn!(
+                        deadline_secs = settings.config.generation_deadline,
+                        "generation deadline reached, using partial output"
+                    );
+                    progress.cancel();
+                }
+                let tokens = progress.tokens();
+                if tokens != reported
+                    && let Some(ref id) = progress_comment_id
+                {
+                    reported = tokens;
+                    let body = format!("{message}\n\nGenerating… ({tokens} tokens)");
+                    let _ = provider.edit_comment(id, &body).await;
+                }
+            }
+        }
+    };
+
+    if let Some(ref id) = progress_comment_id {
+        let _ = provider.remove_comment(id).await;
+    }
+
+    result
+}
+
 /// Build the custom labels class string for prompt templates.
 ///
 /// Produces the prompt-friendly label class format:
@@ -129,27 +416,67 @@ pub fn build_custom_labels_class(labels: &HashMap<String, CustomLabelEntry>) ->
 /// Returns a `HashMap` pre-populated with the 8 variables that every tool needs.
 /// Each tool then extends this map with its own tool-specific variables.
 pub fn build_common_vars(meta: &PrMetadata, diff: &str) -> HashMap<String, Value> {
+    let planning_context = meta.planning.to_prompt_context();
+    let owners = codeowners::format_file_owners(&meta.codeowners);
     [
         ("title", meta.title.as_str()),
         ("branch", meta.branch.as_str()),
         ("description", meta.description.as_str()),
-        ("language", ""),
+        ("language", meta.language.as_str()),
         ("diff", diff),
         ("commit_messages_str", meta.commit_messages.as_str()),
         ("best_practices_content", meta.best_practices.as_str()),
         ("repo_metadata", meta.repo_metadata.as_str()),
+        ("repo_context", meta.repo_context.as_str()),
+        ("planning_context", planning_context.as_str()),
+        ("codeowners", owners.as_str()),
     ]
     .into_iter()
     .map(|(k, v)| (k.to_string(), Value::from(v)))
     .collect()
 }
 
+/// Whether the PR qualifies for vision under `vision_labels` / `vision_file_globs`.
+///
+/// With neither set every PR qualifies; otherwise a matching label or a
+/// changed file matching a glob is enough.
+async fn vision_allowed(provider: &dyn GitProvider, settings: &Settings) -> bool {
+    let labels = &settings.config.vision_labels;
+    let globs = &settings.config.vision_file_globs;
+    if labels.is_empty() && globs.is_empty() {
+        return true;
+    }
+
+    if !labels.is_empty() {
+        let pr_labels = provider.get_pr_labels().await.unwrap_or_default();
+        if pr_labels
+            .iter()
+            .any(|l| labels.iter().any(|want| want.eq_ignore_ascii_case(l)))
+        {
+            return true;
+        }
+    }
+    if !globs.is_empty() {
+        let files = provider.get_files().await.unwrap_or_default();
+        if files
+            .iter()
+            .any(|f| globs.iter().any(|g| glob_matches(g, f)))
+        {
+            return true;
+        }
+    }
+    tracing::info!("no vision label or file glob matched, skipping PR images");
+    false
+}
+
 /// Extract validated image URLs from the PR description and linked issues,
-/// respecting `enable_vision` config.
+/// plus changed image files, respecting `enable_vision` config.
 ///
 /// Collects images from:
 /// 1. The PR description itself (markdown images, HTML `<img>` tags, bare URLs)
 /// 2. Bodies of issues referenced in the PR description (`#N`, full GitHub URLs)
+/// 3. With `changed_images`, added or modified PNG/JPEG files of the PR as
+///    `data:` URLs (`config.vision_changed_image_max_kb`)
 ///
 /// **Edge cases handled:**
 /// - Skips fetching the PR's own number (GitHub issues API returns PRs too)
@@ -165,9 +492,10 @@ pub async fn get_pr_images(
     description: &str,
     provider: &dyn GitProvider,
     pr_number: Option<u64>,
+    changed_images: bool,
 ) -> Option<Vec<String>> {
     let settings = get_settings();
-    if !settings.config.enable_vision {
+    if !settings.config.enable_vision || !vision_allowed(provider, &settings).await {
         return None;
     }
 
@@ -217,12 +545,24 @@ pub async fn get_pr_images(
         }
     }
 
-    if all_urls.is_empty() {
-        return None;
-    }
-
     // 3. Validate all URLs (HEAD requests, GitHub URLs trusted)
-    let validated = image::validate_image_urls(all_urls).await;
+    let mut validated = image::validate_image_urls(all_urls).await;
+
+    // 4. Changed image files, from the unfiltered diff (binary files are
+    // excluded from the one the tools prompt with)
+    if changed_images {
+        match diff_cache::get_diff_files(provider).await {
+            Ok(files) => validated.extend(
+                changed_image_urls(
+                    provider,
+                    &files,
+                    settings.config.vision_changed_image_max_kb,
+                )
+                .await,
+            ),
+            Err(e) => tracing::warn!(error = %e, "failed to list changed images, skipping"),
+        }
+    }
     if validated.is_empty() {
         None
     } else {
@@ -230,6 +570,50 @@ pub async fn get_pr_images(
     }
 }
 
+/// Up to [`image::MAX_CHANGED_IMAGES`] added or modified PNG/JPEG files of
+/// the PR as `data:` URLs, leaving out files over `max_kb` KiB.
+async fn changed_image_urls(
+    provider: &dyn GitProvider,
+    files: &[FilePatchInfo],
+    max_kb: u64,
+) -> Vec<String> {
+    if max_kb == 0 {
+        return Vec::new();
+    }
+    let images: Vec<(&str, &str)> = files
+        .iter()
+        .filter_map(|f| image::changed_image_type(f).map(|mime| (f.filename.as_str(), mime)))
+        .take(image::MAX_CHANGED_IMAGES)
+        .collect();
+    let fetched = futures_util::future::join_all(
+        images
+            .iter()
+            .map(|(path, _)| provider.get_head_file_bytes(path)),
+    )
+    .await;
+    images
+        .into_iter()
+        .zip(fetched)
+        .filter_map(|((path, mime), result)| match result {
+            Ok(bytes) if !bytes.is_empty() && bytes.len() as u64 <= max_kb * 1024 => {
+                Some(image::data_url(mime, &bytes))
+            }
+            Ok(bytes) => {
+                tracing::info!(
+                    path,
+                    size = bytes.len(),
+                    "changed image is empty or over vision_changed_image_max_kb, skipping"
+                );
+                None
+            }
+            Err(e) => {
+                tracing::warn!(path, error = %e, "failed to fetch changed image, skipping");
+                None
+            }
+        })
+        .collect()
+}
+
 /// Insert custom-labels template variables into the vars map.
 ///
 /// Shared by review and describe, which both need `enable_custom_labels`,
@@ -251,6 +635,237 @@ pub fn insert_custom_labels_vars(vars: &mut HashMap<String, Value>, settings: &S
     vars.insert("custom_labels".into(), Value::from(""));
 }
 
+/// What the context-window pre-check had to drop to make a prompt fit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextOverflow {
+    /// Token count of the prompt as first rendered.
+    pub prompt_tokens: u32,
+    /// The model's context window.
+    pub max_tokens: u32,
+    /// Non-diff components shortened per `config.prompt_trim_order`.
+    pub trimmed: Vec<PromptComponent>,
+    /// Whether the diff had to be re-compressed as well.
+    pub diff_recompressed: bool,
+    /// Files that were in the diff before re-compression but not after.
+    pub dropped_files: Vec<String>,
+    /// Files left out of the diff by `large_patch_policy = "skip"`.
+    pub skipped_files: Vec<String>,
+}
+
+impl ContextOverflow {
+    /// Markdown warning appended to tool output.
+    pub fn notice(&self) -> String {
+        let mut out = String::new();
+        if self.diff_recompressed || !self.trimmed.is_empty() {
+            let action = if self.diff_recompressed {
+                "the diff was re-compressed"
+            } else {
+                "some PR context was shortened"
+            };
+            let _ = write!(
+                out,
+                "> ⚠️ **Context limit:** the prompt ({} tokens) did not fit the model's context window ({} tokens), so {action}.",
+                self.prompt_tokens, self.max_tokens
+            );
+            if !self.trimmed.is_empty() {
+                let labels: Vec<&str> = self.trimmed.iter().map(|c| c.label()).collect();
+                let _ = write!(out, " Shortened: {}.", labels.join(", "));
+            }
+            if !self.dropped_files.is_empty() {
+                let _ = write!(out, " Files left out: {}.", code_list(&self.dropped_files));
+            }
+            out.push('\n');
+        }
+        if !self.skipped_files.is_empty() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            let _ = writeln!(
+                out,
+                "> ⚠️ **Large files:** {} had patches too large for the prompt and were left out (`large_patch_policy = \"skip\"`).",
+                code_list(&self.skipped_files)
+            );
+        }
+        out
+    }
+}
+
+/// `a`, `b`, `c` — filenames as a comma-separated list of code spans.
+fn code_list(files: &[String]) -> String {
+    files
+        .iter()
+        .map(|f| format!("`{f}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Render a prompt that embeds `diff`, checking it against the model's context window.
+///
+/// The diff budget only covers the patches themselves; instructions and PR
+/// metadata can still push the full prompt past the window. When that happens
+/// the PR context is shortened in `config.prompt_trim_order` (see
+/// [`token_budget`]); if that is not enough, the diff is re-compressed with
+/// the rest of the prompt reserved and `diff` is replaced. The overflow is
+/// reported so tools can tell the user what was dropped instead of failing on
+/// a provider-side context error.
+pub fn render_prompt_fitting_context(
+    template: &PromptTemplate,
+    vars: HashMap<String, Value>,
+    files: &mut Vec<FilePatchInfo>,
+    diff: &mut PrDiffResult,
+    model: &str,
+    add_line_numbers: bool,
+) -> Result<(RenderedPrompt, Option<ContextOverflow>), PrAgentError> {
+    let settings = get_settings();
+    let max_tokens = get_max_tokens_with_fallback(model, settings.config.max_model_tokens);
+    let tokenizer = tokenizer_for(model);
+
+    let rendered = render_prompt(template, vars.clone())?;
+    let prompt_tokens = tokenizer.count(&rendered.system) + tokenizer.count(&rendered.user);
+    if prompt_tokens + OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD <= max_tokens {
+        // Files skipped by `large_patch_policy` are still worth a notice.
+        let overflow = (!diff.skipped_files.is_empty()).then(|| ContextOverflow {
+            prompt_tokens,
+            max_tokens,
+            trimmed: Vec::new(),
+            diff_recompressed: false,
+            dropped_files: Vec::new(),
+            skipped_files: diff.skipped_files.clone(),
+        });
+        return Ok((rendered, overflow));
+    }
+
+    let budget = max_tokens.saturating_sub(OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD);
+    let mut vars = vars;
+    let order = token_budget::trim_order(&settings.config);
+    let outcome = token_budget::trim_to_fit(&mut vars, prompt_tokens - budget, &order, tokenizer);
+    let mut overflow = ContextOverflow {
+        prompt_tokens,
+        max_tokens,
+        trimmed: outcome.trimmed,
+        diff_recompressed: false,
+        dropped_files: Vec::new(),
+        skipped_files: diff.skipped_files.clone(),
+    };
+    let mut current_tokens = prompt_tokens;
+    if !overflow.trimmed.is_empty() {
+        let rendered = render_prompt(template, vars.clone())?;
+        current_tokens = tokenizer.count(&rendered.system) + tokenizer.count(&rendered.user);
+        if current_tokens <= budget {
+            return Ok((rendered, Some(overflow)));
+        }
+    }
+
+    let overhead = current_tokens.saturating_sub(diff.token_count);
+    tracing::warn!(
+        prompt_tokens = current_tokens,
+        max_tokens,
+        overhead,
+        "prompt exceeds model context window, re-compressing diff"
+    );
+    let refit = get_pr_diff_with_reserve(files, model, add_line_numbers, overhead);
+    overflow.diff_recompressed = true;
+    overflow.dropped_files = diff
+        .files_in_diff
+        .iter()
+        .filter(|f| !refit.files_in_diff.contains(f))
+        .cloned()
+        .collect();
+    *diff = refit;
+    overflow.skipped_files.clone_from(&diff.skipped_files);
+
+    vars.insert("diff".into(), Value::from(diff.diff.as_str()));
+    let rendered = render_prompt(template, vars)?;
+    Ok((rendered, Some(overflow)))
+}
+
+/// Parse a tool's AI response with `parse`, re-prompting on failure.
+///
+/// When every YAML fallback fails, the model gets the original prompt back
+/// with its invalid answer and the parse error appended, up to
+/// `config.reparse_attempts` times. Returns the parsed data (if any) and the
+/// response it came from, so raw-output fallbacks show the latest answer.
+pub async fn parse_with_reprompt<F>(
+    ai: &dyn AiHandler,
+    model: &str,
+    rendered: &RenderedPrompt,
+    content: String,
+    parse: F,
+) -> (Option<serde_yaml_ng::Value>, String)
+where
+    F: Fn(&str) -> Option<serde_yaml_ng::Value>,
+{
+    let settings = get_settings();
+    let mut content = content;
+    if let Some(data) = parse(&content) {
+        return (Some(data), content);
+    }
+    for attempt in 1..=settings.config.reparse_attempts {
+        let error = yaml_parse_error(&content);
+        tracing::warn!(attempt, error = %error, "AI response is not valid YAML, re-prompting");
+        let user = format!(
+            "{}\n\nYour previous response:\n======\n{}\n======\n\n\
+             Your previous response was not valid YAML ({}), fix it. \
+             Answer with the corrected YAML only, keeping its content.",
+            rendered.user,
+            content.trim(),
+            error
+        );
+        match crate::ai::chat_completion_with_fallback(
+            ai,
+            model,
+            &settings.config.fallback_models,
+            &rendered.system,
+            &user,
+            Some(settings.config.temperature),
+            None,
+        )
+        .await
+        {
+            Ok(response) => content = response.content,
+            Err(e) => {
+                tracing::error!(error = %e, "re-prompt for valid YAML failed");
+                break;
+            }
+        }
+        if let Some(data) = parse(&content) {
+            tracing::info!(attempt, "AI response parsed after re-prompt");
+            return (Some(data), content);
+        }
+    }
+    (None, content)
+}
+
+/// Resolve configured intro/help text, falling back to `default` when unset.
+///
+/// A value of the form `file:<path>` is read from the repository, so orgs can
+/// keep their review guidelines next to the code.
+pub async fn resolve_guide_text(
+    provider: &dyn GitProvider,
+    configured: &str,
+    default: &str,
+) -> String {
+    let configured = configured.trim();
+    let Some(path) = configured.strip_prefix("file:") else {
+        return if configured.is_empty() {
+            default.to_string()
+        } else {
+            configured.to_string()
+        };
+    };
+    match provider.get_repo_file(path.trim()).await {
+        Ok(Some(content)) => content,
+        Ok(None) => {
+            tracing::warn!(path, "guide text file not found in repo, using default");
+            default.to_string()
+        }
+        Err(e) => {
+            tracing::warn!(path, error = %e, "failed to read guide text file, using default");
+            default.to_string()
+        }
+    }
+}
+
 /// Publish tool output as either a persistent comment or a regular comment.
 ///
 /// Shared by review and improve, which both follow the same pattern:
@@ -263,13 +878,14 @@ pub async fn publish_as_comment(
     persistent: bool,
     final_update_message: bool,
 ) -> Result<(), PrAgentError> {
+    let content = usage::with_cost_footer(content);
     if persistent {
         let marker = format!("<!-- pr-agent:{tool_name} -->");
         provider
-            .publish_persistent_comment(content, &marker, "", tool_name, final_update_message)
+            .publish_persistent_comment(&content, &marker, "", tool_name, final_update_message)
             .await?;
     } else {
-        provider.publish_comment(content, false).await?;
+        provider.publish_comment(&content, false).await?;
     }
     Ok(())
 }
@@ -319,6 +935,23 @@ pub fn parse_command(input: &str) -> (String, HashMap<String, String>) {
     (command, overrides)
 }
 
+/// Canonical names of the tool commands, as reported by `GET /api/v1/info`.
+pub const TOOL_COMMANDS: &[&str] = &[
+    "review",
+    "review_range",
+    "describe",
+    "improve",
+    "ask",
+    "ask_line",
+    "generate_labels",
+    "changelog_fragment",
+    "test",
+    "analyze",
+    "config",
+    "resolve",
+    "wontfix",
+];
+
 /// Recognized tool commands.
 ///
 /// The single source of truth for command-name → tool mapping.
@@ -326,66 +959,278 @@ pub fn parse_command(input: &str) -> (String, HashMap<String, String>) {
 /// Adding a new tool here automatically makes it recognized by `is_known_command`.
 enum Command {
     Review,
+    ReviewRange,
     Describe,
     Improve,
     Ask,
     AskLine,
+    GenerateLabels,
+    ChangelogFragment,
+    Test,
+    Analyze,
+    Config,
+    Resolve,
+    Wontfix,
+}
+
+impl Command {
+    /// Canonical name, shared by all aliases of the command.
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Review => "review",
+            Command::ReviewRange => "review_range",
+            Command::Describe => "describe",
+            Command::Improve => "improve",
+            Command::Ask => "ask",
+            Command::AskLine => "ask_line",
+            Command::GenerateLabels => "generate_labels",
+            Command::ChangelogFragment => "changelog_fragment",
+            Command::Test => "test",
+            Command::Analyze => "analyze",
+            Command::Config => "config",
+            Command::Resolve => "resolve",
+            Command::Wontfix => "wontfix",
+        }
+    }
 }
 
 /// Map a command name string to its `Command` variant, if recognized.
 fn resolve_command(name: &str) -> Option<Command> {
     match name {
         "review" | "auto_review" | "review_pr" => Some(Command::Review),
+        "review_range" | "review_diff_between" => Some(Command::ReviewRange),
         "describe" | "describe_pr" => Some(Command::Describe),
         "improve" | "improve_code" => Some(Command::Improve),
         "ask" => Some(Command::Ask),
         "ask_line" => Some(Command::AskLine),
+        "generate_labels" => Some(Command::GenerateLabels),
+        "changelog_fragment" => Some(Command::ChangelogFragment),
+        "test" => Some(Command::Test),
+        "analyze" => Some(Command::Analyze),
+        "config" | "settings" => Some(Command::Config),
+        "resolve" => Some(Command::Resolve),
+        "wontfix" | "wont_fix" => Some(Command::Wontfix),
         _ => None,
     }
 }
 
+/// Canonical name of a command or alias (`improve_code` → `improve`).
+pub fn canonical_command_name(name: &str) -> Option<&'static str> {
+    resolve_command(name).map(|c| c.name())
+}
+
 /// Check whether a command name is one that pr-agent-rs can handle.
 ///
 /// Used by the webhook handler to reject unknown commands early — before
-/// creating a provider, adding eyes reactions, or fetching scoped settings.
+/// creating a provider, adding ack reactions, or fetching scoped settings.
 pub fn is_known_command(name: &str) -> bool {
     resolve_command(name).is_some()
 }
 
+/// Whether `name` is a reply command that resolves a review finding
+/// (`/resolve`, `/wontfix`). Webhooks attach the commenter as `_author`.
+pub fn is_finding_reply_command(name: &str) -> bool {
+    matches!(
+        resolve_command(name),
+        Some(Command::Resolve | Command::Wontfix)
+    )
+}
+
 /// Dispatch a command to the appropriate tool.
 ///
 /// If `args` contains per-command overrides (from `/command --key=value` parsing),
 /// creates a scoped settings override for this command execution.
+#[tracing::instrument(name = "tool", skip_all, fields(command = %command))]
 pub async fn handle_command(
     command: &str,
     provider: Arc<dyn GitProvider>,
     args: &HashMap<String, String>,
 ) -> Result<(), PrAgentError> {
-    // Separate config overrides (key=value flags) from tool data (_text, _diff_hunk, etc.)
+    // Separate config overrides (key=value flags) from tool data (_text, _diff_hunk, etc.).
+    // Settings keys are always `section.key`; undotted flags such as
+    // `--base=<sha>` are tool arguments.
     let config_overrides: HashMap<String, String> = args
         .iter()
-        .filter(|(k, _)| !k.starts_with('_'))
+        .filter(|(k, _)| !k.starts_with('_') && k.contains('.'))
         .map(|(k, v)| (k.clone(), v.clone()))
         .collect();
 
+    let started = Instant::now();
     // If there are per-command config overrides, scope them as settings overrides
-    if !config_overrides.is_empty() {
+    // (keys locked by the org-level settings keep their values)
+    let mut scoped = (!config_overrides.is_empty()).then(|| {
+        load_command_settings(&get_settings(), &config_overrides).unwrap_or_else(|e| {
+            tracing::warn!(
+                error = %e,
+                ?config_overrides,
+                "failed to apply command config overrides, using current settings"
+            );
+            (*get_settings()).clone()
+        })
+    });
+    // `config.large_patch_policy_by_tool`, unless the command set the policy itself
+    if !config_overrides.contains_key("config.large_patch_policy") {
         let current = get_settings();
-        let scoped = Arc::new(match load_settings(&config_overrides, None, None) {
-            Ok(s) => s,
-            Err(e) => {
-                tracing::warn!(
-                    error = %e,
-                    ?config_overrides,
-                    "failed to apply command config overrides, using current settings"
-                );
-                (*current).clone()
-            }
-        });
-        return with_settings(scoped, dispatch(command, provider, args)).await;
+        let base = scoped.as_ref().unwrap_or(&*current);
+        let tool_policy = resolve_command(command)
+            .and_then(|c| base.config.large_patch_policy_by_tool.get(c.name()))
+            .filter(|policy| **policy != base.config.large_patch_policy)
+            .cloned();
+        if let Some(policy) = tool_policy {
+            let mut settings = scoped.unwrap_or_else(|| (*current).clone());
+            settings.config.large_patch_policy = policy;
+            scoped = Some(settings);
+        }
     }
+    let result = match scoped {
+        Some(scoped) => {
+            with_settings(
+                Arc::new(scoped),
+                dispatch_and_record(command, provider, args),
+            )
+            .await
+        }
+        None => dispatch_and_record(command, provider, args).await,
+    };
+    metrics::record_tool_run(command, started.elapsed().as_secs_f64(), &result);
+    result
+}
 
-    dispatch(command, provider, args).await
+/// Run `dispatch`, log its AI usage and cost, then record the run on the PR:
+/// an audit comment entry when `config.enable_audit_trail` is set, and a
+/// re-runnable check run when `github_app.publish_check_runs` is set. Chat
+/// notifications go out per `[notifications]`, except in dry-run mode.
+/// Recording failures are logged, never returned.
+async fn dispatch_and_record(
+    command: &str,
+    provider: Arc<dyn GitProvider>,
+    args: &HashMap<String, String>,
+) -> Result<(), PrAgentError> {
+    let settings = get_settings();
+    let provider: Arc<dyn GitProvider> = if settings.config.dry_run {
+        Arc::new(DryRunProvider::new(
+            provider,
+            &settings.config.dry_run_output,
+        ))
+    } else {
+        provider
+    };
+    // Boxed so each wrapper below doesn't copy the whole tool future into
+    // its own state, which overflows the stack in debug builds.
+    let run = Box::pin(dispatch(command, provider.clone(), args));
+    let run = async {
+        match response_cache_scope(provider.as_ref()).await {
+            Some(scope) => cache::with_cache_scope(scope, run).await,
+            None => run.await,
+        }
+    };
+    let ((result, summary), usage) = usage::track_usage(notify::track_summary(run)).await;
+    usage::log_usage(command, &usage);
+    if !settings.config.publish_output {
+        return result;
+    }
+
+    if settings.config.enable_audit_trail {
+        let entry = audit::AuditEntry::new(command, usage, &result);
+        if let Err(e) = audit::append_audit_entry(provider.as_ref(), &entry).await {
+            tracing::warn!(error = %e, command, "failed to update audit comment");
+        }
+    }
+    if settings.github_app.publish_check_runs
+        && let Some(check) = check_run_report(command, args, &result)
+        && let Err(e) = provider.publish_check_run(&check).await
+    {
+        tracing::warn!(error = %e, command, "failed to publish check run");
+    }
+    if !settings.config.dry_run
+        && let Some(tool) = resolve_command(command).map(|c| c.name())
+        && notify::should_notify(&settings.notifications, tool, result.is_ok())
+    {
+        let notification = notify::Notification {
+            tool: tool.to_string(),
+            pr_url: provider.get_pr_url().to_string(),
+            pr_number: provider.get_pr_number(),
+            success: result.is_ok(),
+            summary: match &result {
+                Ok(()) => summary.unwrap_or_else(|| "completed".to_string()),
+                Err(e) => e.to_string(),
+            },
+        };
+        notify::send(&settings, &notification).await;
+    }
+    result
+}
+
+/// Cache scope for the PR's current head commit, when `response_cache.enable`
+/// is set and the provider reports a head SHA.
+async fn response_cache_scope(provider: &dyn GitProvider) -> Option<cache::CacheScope> {
+    if !get_settings().response_cache.enable {
+        return None;
+    }
+    match provider.get_pr_head_sha().await {
+        Ok(head_sha) if !head_sha.is_empty() => Some(cache::CacheScope {
+            pr_url: provider.get_pr_url().to_string(),
+            head_sha,
+        }),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::debug!(error = %e, "no head SHA, not caching AI responses");
+            None
+        }
+    }
+}
+
+/// Build the check run recording a tool run, or `None` for commands that
+/// can't be replayed from their arguments alone (line-level `/ask_line`).
+fn check_run_report(
+    command: &str,
+    args: &HashMap<String, String>,
+    result: &Result<(), PrAgentError>,
+) -> Option<CheckRunReport> {
+    if command == "ask_line" {
+        return None;
+    }
+    let mut flags: Vec<String> = args
+        .iter()
+        .filter(|(k, _)| !k.starts_with('_'))
+        .map(|(k, v)| format!("--{k}={v}"))
+        .collect();
+    flags.sort();
+    let mut command_line = format!("/{command}");
+    for part in flags
+        .iter()
+        .map(String::as_str)
+        .chain(args.get("_text").map(|t| t.trim()))
+    {
+        if !part.is_empty() {
+            command_line.push(' ');
+            command_line.push_str(part);
+        }
+    }
+
+    let (success, summary) = match result {
+        Ok(()) => (
+            true,
+            format!(
+                "{} completed. Re-run this check to run it again.",
+                user_code_span(&command_line)
+            ),
+        ),
+        Err(e) => (
+            false,
+            format!(
+                "{} failed: {}",
+                user_code_span(&command_line),
+                sanitize_user_markdown(&e.to_string())
+            ),
+        ),
+    };
+    Some(CheckRunReport {
+        name: format!("pr-agent: {command}"),
+        command: command_line,
+        success,
+        summary,
+    })
 }
 
 async fn dispatch(
@@ -397,20 +1242,157 @@ async fn dispatch(
         return Err(PrAgentError::Other(format!("unknown command: '{command}'")));
     };
     match cmd {
-        Command::Review => review::PRReviewer::new(provider).run().await,
+        Command::Review => {
+            // `/review -i` / `/review --incremental`
+            let incremental = args.get("_text").is_some_and(|text| {
+                text.split_whitespace()
+                    .any(|w| w == "-i" || w == "--incremental")
+            });
+            review::PRReviewer::new(provider)
+                .with_incremental(incremental)
+                .with_push_trigger(args.contains_key("_push_trigger"))
+                .run()
+                .await
+        }
+        Command::ReviewRange => {
+            // `/review_range --base=<sha> --head=<sha>`
+            let range = review::CommitRange::from_args(args)?;
+            review::PRReviewer::new(provider)
+                .with_range(range)
+                .run()
+                .await
+        }
         Command::Describe => describe::PRDescription::new(provider).run().await,
         Command::Improve => improve::PRCodeSuggestions::new(provider).run().await,
         Command::Ask => {
-            let question = args.get("_text").map(|s| s.as_str()).unwrap_or("");
-            ask::PRAsk::new(provider).run(question).await
+            // `/ask --full_files <question>`
+            let text = args.get("_text").map(|s| s.as_str()).unwrap_or("");
+            let (full_files, question) = ask::strip_full_files_flag(text);
+            ask::PRAsk::new(provider)
+                .with_full_files(full_files)
+                .run(&question)
+                .await
         }
         Command::AskLine => ask_line::PRAskLine::new(provider).run(args).await,
+        Command::GenerateLabels => generate_labels::PRGenerateLabels::new(provider).run().await,
+        Command::ChangelogFragment => {
+            changelog_fragment::PRChangelogFragment::new(provider)
+                .run()
+                .await
+        }
+        Command::Test => {
+            let component = args.get("_text").map(|s| s.as_str()).unwrap_or("");
+            test_gen::PRTestGen::new(provider).run(component).await
+        }
+        Command::Analyze => analyze::PRAnalyze::new(provider).run().await,
+        Command::Config => config::PRConfig::new(provider).run().await,
+        Command::Resolve => {
+            resolve::PRResolveFinding::new(provider)
+                .run(FindingStatus::Resolved, args)
+                .await
+        }
+        Command::Wontfix => {
+            resolve::PRResolveFinding::new(provider)
+                .run(FindingStatus::Wontfix, args)
+                .await
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::loader::load_settings;
+    use crate::git::types::Milestone;
+
+    #[test]
+    fn test_context_overflow_notice() {
+        let overflow = ContextOverflow {
+            prompt_tokens: 9_000,
+            max_tokens: 8_000,
+            trimmed: Vec::new(),
+            diff_recompressed: true,
+            dropped_files: vec!["src/a.rs".into(), "src/b.rs".into()],
+            skipped_files: Vec::new(),
+        };
+        assert_eq!(
+            overflow.notice(),
+            "> ⚠️ **Context limit:** the prompt (9000 tokens) did not fit the model's context window (8000 tokens), so the diff was re-compressed. Files left out: `src/a.rs`, `src/b.rs`.\n"
+        );
+
+        let overflow = ContextOverflow {
+            dropped_files: Vec::new(),
+            ..overflow
+        };
+        assert!(overflow.notice().ends_with("re-compressed.\n"));
+
+        let overflow = ContextOverflow {
+            trimmed: vec![
+                PromptComponent::RepoMetadata,
+                PromptComponent::CommitMessages,
+            ],
+            diff_recompressed: false,
+            ..overflow
+        };
+        assert!(overflow.notice().ends_with(
+            "so some PR context was shortened. Shortened: repo metadata, commit messages.\n"
+        ));
+
+        let overflow = ContextOverflow {
+            trimmed: Vec::new(),
+            skipped_files: vec!["data/huge.json".into()],
+            ..overflow
+        };
+        assert_eq!(
+            overflow.notice(),
+            "> ⚠️ **Large files:** `data/huge.json` had patches too large for the prompt and were left out (`large_patch_policy = \"skip\"`).\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_fitting_context_trims_before_diff() {
+        let template = PromptTemplate {
+            system: "{{ best_practices_content }}".into(),
+            user: "{{ diff }}".into(),
+            ..Default::default()
+        };
+        let mut vars = HashMap::new();
+        vars.insert(
+            "best_practices_content".into(),
+            Value::from("- Prefer small functions.\n".repeat(500)),
+        );
+        vars.insert("diff".into(), Value::from("+fn main() {}"));
+        let mut diff = PrDiffResult {
+            diff: "+fn main() {}".into(),
+            token_count: 5,
+            files_in_diff: vec!["src/main.rs".into()],
+            remaining_files: Vec::new(),
+            skipped_files: Vec::new(),
+        };
+
+        let mut overrides = HashMap::new();
+        overrides.insert("config.max_model_tokens".into(), "3000".into());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+        let (rendered, overflow) = with_settings(settings, async {
+            render_prompt_fitting_context(
+                &template,
+                vars,
+                &mut Vec::new(),
+                &mut diff,
+                "custom-model",
+                false,
+            )
+        })
+        .await
+        .unwrap();
+
+        let overflow = overflow.expect("prompt should overflow");
+        assert_eq!(overflow.trimmed, vec![PromptComponent::BestPractices]);
+        assert!(!overflow.diff_recompressed);
+        let prompt_tokens = tokenizer_for("custom-model").count(&rendered.system);
+        assert!(prompt_tokens + OUTPUT_BUFFER_TOKENS_SOFT_THRESHOLD <= 3000);
+        assert_eq!(rendered.user, "+fn main() {}");
+    }
 
     #[test]
     fn test_parse_command_simple() {
@@ -464,6 +1446,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_command_drops_fragment_path() {
+        let (_, args) = parse_command(
+            "/changelog_fragment --pr_update_changelog.fragment_path=.github/workflows/ci.yml",
+        );
+        assert!(!args.contains_key("pr_update_changelog.fragment_path"));
+    }
+
+    #[test]
+    fn test_parse_command_drops_response_cache_dir() {
+        let (_, args) = parse_command("/review --response_cache.dir=/etc/cron.d");
+        assert!(!args.contains_key("response_cache.dir"));
+    }
+
+    #[test]
+    fn test_parse_command_drops_aws_keys() {
+        let (_, args) = parse_command(
+            "/review --aws.bedrock_endpoint=https://evil.example --aws.session_token=x \
+             --aws.access_key_id=AKIA --aws.secret_access_key=y --aws.region_name=evil.com/x?",
+        );
+        assert!(args.is_empty(), "AWS keys should be dropped: {args:?}");
+    }
+
+    #[test]
+    fn test_parse_command_drops_diagram_renderer_keys() {
+        let (_, args) = parse_command(
+            "/describe --pr_description.render_diagram_image=true \
+             --pr_description.diagram_renderer_url=http://169.254.169.254/latest",
+        );
+        assert!(args.is_empty(), "renderer keys should be dropped: {args:?}");
+    }
+
+    #[test]
+    fn test_parse_command_drops_auto_approval_keys() {
+        let (_, args) = parse_command(
+            "/review --config.auto_approve_for_low_review_effort=5 \
+             --config.auto_approve_for_no_suggestions=true --config.enable_auto_approval=true",
+        );
+        assert!(
+            args.is_empty(),
+            "auto-approval keys should be dropped: {args:?}"
+        );
+    }
+
     #[test]
     fn test_build_common_vars_populates_all_keys() {
         let meta = PrMetadata {
@@ -473,6 +1499,18 @@ mod tests {
             commit_messages: "commit 1\ncommit 2".into(),
             best_practices: "Use Rust idioms".into(),
             repo_metadata: "CLAUDE.md content".into(),
+            language: "Rust".into(),
+            repo_context: String::new(),
+            ai_metadata: false,
+            codeowners: FileOwners::new(),
+            planning: PlanningContext {
+                milestone: Some(Milestone {
+                    title: "v1.2".into(),
+                    due_on: None,
+                }),
+                projects: vec![],
+            },
+            tickets: vec![],
         };
 
         let vars = build_common_vars(&meta, "the-diff-content");
@@ -490,7 +1528,8 @@ mod tests {
             "Use Rust idioms"
         );
         assert_eq!(vars["repo_metadata"].to_string(), "CLAUDE.md content");
-        assert_eq!(vars["language"].to_string(), "");
+        assert_eq!(vars["planning_context"].to_string(), "Milestone: v1.2\n");
+        assert_eq!(vars["language"].to_string(), "Rust");
     }
 
     #[test]
@@ -618,17 +1657,142 @@ mod tests {
             "review",
             "auto_review",
             "review_pr",
+            "review_range",
+            "review_diff_between",
             "describe",
             "describe_pr",
             "improve",
             "improve_code",
             "ask",
             "ask_line",
+            "generate_labels",
+            "changelog_fragment",
+            "test",
+            "analyze",
+            "config",
+            "settings",
+            "resolve",
+            "wontfix",
+            "wont_fix",
         ] {
             assert!(is_known_command(cmd), "'{cmd}' should be a known command");
         }
     }
 
+    #[tokio::test]
+    async fn test_handle_command_appends_audit_entry() {
+        use crate::testing::fixtures::{SAMPLE_PATCH, sample_diff_file};
+        use crate::testing::mock_git::MockGitProvider;
+
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let mut overrides = HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.enable_audit_trail".into(), "true".into());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+        with_settings(
+            settings,
+            handle_command("analyze", provider.clone(), &HashMap::new()),
+        )
+        .await
+        .unwrap();
+
+        let calls = provider.get_calls();
+        assert_eq!(calls.comments.len(), 2);
+        assert!(calls.comments[0].0.starts_with("## PR Analysis"));
+        let audit = &calls.comments[1].0;
+        assert!(audit.starts_with("<!-- pr-agent:audit -->"));
+        assert!(audit.contains("| `analyze` | — | 0/0 | ok |"));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_progress_comment_updates_and_cancels() {
+        use crate::ai::stream;
+        use crate::testing::mock_git::MockGitProvider;
+
+        let provider = MockGitProvider::new();
+        let mut overrides = HashMap::new();
+        overrides.insert("config.stream_progress_interval".into(), "1".into());
+        overrides.insert("config.generation_deadline".into(), "1".into());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+
+        with_settings(
+            settings,
+            with_streaming_progress_comment(&provider, "Preparing review...", || async {
+                let progress = stream::current().expect("streaming scope");
+                progress.add_tokens(42);
+                // Generate until the deadline cancels the stream.
+                while !progress.is_cancelled() {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                Ok(())
+            }),
+        )
+        .await
+        .unwrap();
+
+        let calls = provider.get_calls();
+        assert!(
+            calls
+                .edited_comments
+                .iter()
+                .any(|(_, body)| body == "Preparing review...\n\nGenerating… (42 tokens)")
+        );
+        assert_eq!(calls.removed_comments.len(), 1);
+    }
+
+    #[test]
+    fn test_check_run_report_replays_command() {
+        let (command, args) = parse_command("/ask --config.model=gpt-4 what does this change do?");
+        let check = check_run_report(&command, &args, &Ok(())).unwrap();
+        assert_eq!(check.name, "pr-agent: ask");
+        assert_eq!(
+            check.command,
+            "/ask --config.model=gpt-4 what does this change do?"
+        );
+        assert!(check.success);
+        assert_eq!(parse_command(&check.command), (command, args));
+
+        let err = PrAgentError::Other("boom".into());
+        let check = check_run_report("review", &HashMap::new(), &Err(err)).unwrap();
+        assert_eq!(check.command, "/review");
+        assert!(!check.success);
+        assert!(check.summary.contains("boom"));
+
+        assert!(check_run_report("ask_line", &HashMap::new(), &Ok(())).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_command_publishes_check_run() {
+        use crate::testing::mock_git::MockGitProvider;
+
+        let provider = Arc::new(MockGitProvider::new());
+        let mut overrides = HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("github_app.publish_check_runs".into(), "true".into());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+        with_settings(
+            settings,
+            handle_command("analyze", provider.clone(), &HashMap::new()),
+        )
+        .await
+        .unwrap();
+
+        let calls = provider.get_calls();
+        assert_eq!(calls.check_runs.len(), 1);
+        assert_eq!(calls.check_runs[0].command, "/analyze");
+        assert!(calls.check_runs[0].success);
+    }
+
+    #[test]
+    fn test_tool_commands_are_known() {
+        for cmd in TOOL_COMMANDS {
+            assert!(is_known_command(cmd), "'{cmd}' should be a known command");
+        }
+    }
+
     #[test]
     fn test_is_known_command_rejects_unknown() {
         for cmd in ["qa-verify", "qa-review", "help", "deploy", "", "REVIEW"] {
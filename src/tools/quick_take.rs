@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use minijinja::Value;
+
+use crate::ai::AiHandler;
+use crate::ai::router::{ModelRouter, ModelTier};
+use crate::ai::token::clip_tokens;
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::git::GitProvider;
+use crate::git::types::{CommentId, FilePatchInfo, IssueComment};
+use crate::output::markdown::{persistent_comment_marker, sanitize_user_markdown};
+use crate::processing::diff::patch_stats_note;
+use crate::processing::filter::is_patch_skipped;
+use crate::template::render::render_prompt;
+
+/// Footer shown until the full pipeline has finished.
+const PENDING_FOOTER: &str = "_Full review in progress…_";
+
+/// A posted quick take, kept so it can be linked to the finished outputs.
+pub struct QuickTake {
+    comment_id: CommentId,
+    body: String,
+}
+
+/// Quick take — a cheap first impression posted as soon as a PR opens.
+///
+/// One weak-model paragraph plus the PR size, time-boxed by
+/// `pr_quick_take.timeout`. Once `pr_commands` have run, [`Self::finalize`]
+/// swaps the pending footer for links to the review, suggestions and
+/// description.
+pub struct PRQuickTake {
+    provider: Arc<dyn GitProvider>,
+    ai: Option<Arc<dyn AiHandler>>,
+}
+
+impl PRQuickTake {
+    pub fn new(provider: Arc<dyn GitProvider>) -> Self {
+        Self { provider, ai: None }
+    }
+
+    #[cfg(test)]
+    pub fn new_with_ai(provider: Arc<dyn GitProvider>, ai: Arc<dyn AiHandler>) -> Self {
+        Self {
+            provider,
+            ai: Some(ai),
+        }
+    }
+
+    /// Post the quick take. Failures and timeouts are logged, never returned,
+    /// so they can't hold up the full pipeline.
+    pub async fn publish(&self) -> Option<QuickTake> {
+        let timeout = Duration::from_secs(get_settings().pr_quick_take.timeout);
+        match tokio::time::timeout(timeout, self.publish_inner()).await {
+            Ok(Ok(quick_take)) => quick_take,
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "quick take failed");
+                None
+            }
+            Err(_) => {
+                tracing::info!(
+                    timeout_secs = timeout.as_secs(),
+                    "quick take timed out, skipping"
+                );
+                None
+            }
+        }
+    }
+
+    async fn publish_inner(&self) -> Result<Option<QuickTake>, PrAgentError> {
+        let settings = get_settings();
+        let config = &settings.pr_quick_take;
+        let model = ModelRouter::new(&settings.config).model(ModelTier::Weak);
+
+        let (title, description) = self.provider.get_pr_description_full().await?;
+        let branch = self.provider.get_pr_branch().await.unwrap_or_default();
+        let files = super::fetch_diff_files(self.provider.as_ref(), "quick_take").await?;
+
+        let mut vars = HashMap::new();
+        vars.insert("title".into(), Value::from(title));
+        vars.insert("branch".into(), Value::from(branch));
+        vars.insert(
+            "description".into(),
+            Value::from(clip_tokens(&description, 500, true)),
+        );
+        vars.insert(
+            "diff".into(),
+            Value::from(clipped_diff(
+                &files,
+                config.max_diff_tokens,
+                &settings.config.patch_extension_skip_types,
+            )),
+        );
+        let rendered = render_prompt(&settings.pr_quick_take_prompt, vars)?;
+
+        tracing::info!(model, "calling AI model for quick take");
+        let ai = super::resolve_ai_handler(&self.ai)?;
+        let response = crate::ai::chat_completion_with_fallback(
+            ai.as_ref(),
+            model,
+            &[],
+            &rendered.system,
+            &rendered.user,
+            Some(settings.config.temperature),
+            None,
+        )
+        .await?;
+
+        let body = format_quick_take(response.content.trim(), &files);
+        let Some(comment_id) = self.provider.publish_comment(&body, false).await? else {
+            return Ok(None);
+        };
+        Ok(Some(QuickTake { comment_id, body }))
+    }
+
+    /// Replace the pending footer with links to what `commands` published.
+    pub async fn finalize(&self, quick_take: &QuickTake, commands: &[String]) {
+        let comments = match self.provider.get_issue_comments().await {
+            Ok(comments) => comments,
+            Err(e) => {
+                tracing::warn!(error = %e, "could not list comments to finalize quick take");
+                Vec::new()
+            }
+        };
+        let links = output_links(&comments, commands, self.provider.get_pr_url());
+        let footer = if links.is_empty() {
+            "_Full review finished._".to_string()
+        } else {
+            format!("**Full results:** {}", links.join(" · "))
+        };
+        let body = quick_take.body.replace(PENDING_FOOTER, &footer);
+        if let Err(e) = self
+            .provider
+            .edit_comment(&quick_take.comment_id, &body)
+            .await
+        {
+            tracing::warn!(error = %e, "failed to finalize quick take");
+        }
+    }
+}
+
+/// Concatenate file patches and clip them to `max_tokens`. Files matching
+/// `skip_types` show their line counts instead of the patch.
+fn clipped_diff(files: &[FilePatchInfo], max_tokens: u32, skip_types: &[String]) -> String {
+    let mut diff = String::new();
+    for file in files.iter().filter(|f| !f.patch.is_empty()) {
+        let patch = if is_patch_skipped(&file.filename, skip_types) {
+            patch_stats_note(&file.patch)
+        } else {
+            file.patch.trim().to_string()
+        };
+        let _ = write!(diff, "## File: '{}'\n{}\n\n", file.filename, patch);
+    }
+    clip_tokens(&diff, max_tokens, true)
+}
+
+/// T-shirt size for a PR with `changed_lines` added plus removed lines.
+fn size_label(changed_lines: i32) -> &'static str {
+    match changed_lines {
+        ..=10 => "XS",
+        11..=50 => "S",
+        51..=250 => "M",
+        251..=1000 => "L",
+        _ => "XL",
+    }
+}
+
+fn format_quick_take(take: &str, files: &[FilePatchInfo]) -> String {
+    let added: i32 = files.iter().map(|f| f.num_plus_lines).sum();
+    let removed: i32 = files.iter().map(|f| f.num_minus_lines).sum();
+    let num_files = files.len();
+    let plural = if num_files == 1 { "" } else { "s" };
+    format!(
+        "{marker}\n### ⚡ Quick take\n\n{take}\n\n**Estimated size:** {size} · {num_files} file{plural} · +{added} −{removed}\n\n{PENDING_FOOTER}\n",
+        marker = persistent_comment_marker("quick_take"),
+        take = sanitize_user_markdown(take),
+        size = size_label(added + removed),
+    )
+}
+
+/// Markdown links to the outputs of the commands that ran.
+fn output_links(comments: &[IssueComment], commands: &[String], pr_url: &str) -> Vec<String> {
+    let ran = |tool: &str| {
+        commands
+            .iter()
+            .any(|c| c.trim_start_matches('/').split_whitespace().next() == Some(tool))
+    };
+    let comment_url = |tool: &str| {
+        let marker = persistent_comment_marker(tool);
+        comments
+            .iter()
+            .rev()
+            .find(|c| c.body.contains(&marker))
+            .and_then(|c| c.url.clone())
+    };
+
+    let mut links = Vec::new();
+    for (tool, label) in [("review", "review"), ("improve", "code suggestions")] {
+        if ran(tool)
+            && let Some(url) = comment_url(tool)
+        {
+            links.push(format!("[{label}]({url})"));
+        }
+    }
+    if ran("describe") && !pr_url.is_empty() {
+        links.push(format!("[description]({pr_url})"));
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::with_settings;
+    use crate::testing::fixtures::{SAMPLE_PATCH, sample_diff_file};
+    use crate::testing::mock_ai::MockAiHandler;
+    use crate::testing::mock_git::MockGitProvider;
+
+    #[test]
+    fn test_size_label() {
+        assert_eq!(size_label(0), "XS");
+        assert_eq!(size_label(40), "S");
+        assert_eq!(size_label(250), "M");
+        assert_eq!(size_label(5000), "XL");
+    }
+
+    #[test]
+    fn test_output_links() {
+        let comment = |id, body: &str, url: &str| IssueComment {
+            id,
+            body: body.into(),
+            user: "bot".into(),
+            created_at: String::new(),
+            url: Some(url.into()),
+        };
+        let comments = vec![
+            comment(
+                1,
+                "<!-- pr-agent:review -->\n## PR Reviewer Guide",
+                "https://x/c1",
+            ),
+            comment(
+                2,
+                "<!-- pr-agent:improve -->\n## Suggestions",
+                "https://x/c2",
+            ),
+        ];
+        let commands = vec!["/describe".to_string(), "/review --x=1".to_string()];
+
+        assert_eq!(
+            output_links(&comments, &commands, "https://x/pull/1"),
+            vec![
+                "[review](https://x/c1)".to_string(),
+                "[description](https://x/pull/1)".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quick_take_publish_and_finalize() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new(
+            "Adds a greeting to main. Check the new print call first.",
+        ));
+        let quick_take = PRQuickTake::new_with_ai(provider.clone(), ai.clone());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&HashMap::new(), None, None).unwrap());
+
+        let posted = with_settings(settings, quick_take.publish())
+            .await
+            .expect("quick take should be posted");
+        assert!(ai.get_recorded_calls()[0].system.contains("PR-Quick-Take"));
+        let comment = provider.get_calls().comments[0].0.clone();
+        assert!(comment.contains("### ⚡ Quick take\n\nAdds a greeting to main."));
+        assert!(comment.contains("**Estimated size:** XS · 1 file"));
+        assert!(comment.contains(PENDING_FOOTER));
+
+        quick_take.finalize(&posted, &["/review".to_string()]).await;
+        let edited = &provider.get_calls().edited_comments[0];
+        assert_eq!(edited.0, "mock-comment-1");
+        assert!(edited.1.contains("_Full review finished._"));
+        assert!(!edited.1.contains(PENDING_FOOTER));
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::analytics::{FindingResolutionRecord, FindingStatus, save_finding_resolution};
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::git::GitProvider;
+use crate::git::types::CommentId;
+use crate::output::markdown::{persistent_comment_marker, sanitize_user_markdown};
+use crate::output::review_formatter::finding_marker;
+
+/// Start of every finding marker written by the review formatter.
+const FINDING_MARKER_PREFIX: &str = "<!-- pr-agent:finding ";
+
+/// Prefix of the status line added under a resolved finding's header.
+const STATUS_PREFIX: &str = "<br><!-- pr-agent:finding-status -->";
+
+/// End of the review table cell holding the findings.
+const FINDINGS_END: &str = "</td></tr>";
+
+/// PR Resolve tool — mark a review finding as resolved or won't-fix.
+///
+/// Handles `/resolve 2` and `/wontfix 3 reason` replies: edits the latest
+/// review comment to strike through or annotate the numbered finding, and
+/// records the verdict in the analytics store (when enabled) for
+/// acceptance-rate metrics.
+pub struct PRResolveFinding {
+    provider: Arc<dyn GitProvider>,
+}
+
+impl PRResolveFinding {
+    pub fn new(provider: Arc<dyn GitProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// Apply `status` to the finding named in `args["_text"]`.
+    ///
+    /// `args["_author"]`, when present, is credited in the annotation.
+    pub async fn run(
+        &self,
+        status: FindingStatus,
+        args: &HashMap<String, String>,
+    ) -> Result<(), PrAgentError> {
+        let settings = get_settings();
+        let text = args.get("_text").map(|s| s.as_str()).unwrap_or("");
+        let author = args.get("_author").map(|s| s.as_str()).unwrap_or("");
+        let Some((finding, reason)) = parse_finding_reply(text) else {
+            let command = match status {
+                FindingStatus::Resolved => "resolve",
+                FindingStatus::Wontfix => "wontfix",
+            };
+            return Err(PrAgentError::Other(format!(
+                "usage: /{command} <finding number> [reason]"
+            )));
+        };
+
+        let marker = persistent_comment_marker("review");
+        let comments = self.provider.get_issue_comments().await?;
+        let review = comments
+            .iter()
+            .rev()
+            .find(|c| c.body.starts_with(&marker))
+            .ok_or_else(|| PrAgentError::Other("no review comment found on this PR".into()))?;
+
+        let annotation = FindingAnnotation {
+            status,
+            author,
+            reason,
+        };
+        let (body, header) =
+            annotate_finding(&review.body, finding, &annotation).ok_or_else(|| {
+                PrAgentError::Other(format!("finding {finding} not found in the latest review"))
+            })?;
+
+        if !settings.config.publish_output {
+            println!("{body}");
+            return Ok(());
+        }
+        self.provider
+            .edit_comment(&CommentId(review.id.to_string()), &body)
+            .await?;
+        tracing::info!(finding, ?status, "updated review finding");
+
+        if !settings.config.analytics_folder.is_empty() {
+            self.record(
+                &settings.config.analytics_folder,
+                finding,
+                header,
+                &annotation,
+            );
+        }
+        Ok(())
+    }
+
+    /// Persist the verdict to the analytics store. Failures are logged only.
+    fn record(&self, folder: &str, finding: usize, header: String, annotation: &FindingAnnotation) {
+        let (owner, repo) = self.provider.repo_owner_and_name();
+        let Some(pr_number) = self.provider.get_pr_number() else {
+            return;
+        };
+        let record = FindingResolutionRecord {
+            repo: format!("{owner}/{repo}"),
+            pr_number,
+            finding,
+            header,
+            status: annotation.status,
+            reason: annotation.reason.to_string(),
+            user: annotation.author.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        if let Err(e) = save_finding_resolution(std::path::Path::new(folder), &record) {
+            tracing::warn!(error = %e, "failed to persist finding resolution");
+        }
+    }
+}
+
+/// What to write under a finding.
+struct FindingAnnotation<'a> {
+    status: FindingStatus,
+    author: &'a str,
+    reason: &'a str,
+}
+
+impl FindingAnnotation<'_> {
+    /// Status line shown under the finding header.
+    fn status_line(&self) -> String {
+        let mut line = match self.status {
+            FindingStatus::Resolved => "✅ <em>Resolved</em>".to_string(),
+            FindingStatus::Wontfix => "🚫 <em>Won't fix</em>".to_string(),
+        };
+        if !self.author.is_empty() {
+            line.push_str(" by @");
+            line.push_str(&sanitize_user_markdown(self.author));
+        }
+        let reason = self.reason.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !reason.is_empty() {
+            line.push_str(": ");
+            line.push_str(&sanitize_user_markdown(&reason));
+        }
+        format!("{STATUS_PREFIX}{line}")
+    }
+}
+
+/// Parse `"<n> [reason]"` from a reply. Finding numbers start at 1.
+fn parse_finding_reply(text: &str) -> Option<(usize, &str)> {
+    let text = text.trim();
+    let (number, reason) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let finding = number.trim_start_matches('#').parse().ok()?;
+    (finding > 0).then_some((finding, reason.trim()))
+}
+
+/// Rewrite finding `n` in a review body, returning the new body and the
+/// finding's header as plain text.
+///
+/// Resolved findings get a struck-through header; both statuses add a status
+/// line, replacing any earlier one. When an incremental review repeated the
+/// numbering, the most recent finding `n` is used.
+fn annotate_finding(
+    body: &str,
+    n: usize,
+    annotation: &FindingAnnotation,
+) -> Option<(String, String)> {
+    let marker = finding_marker(n);
+    let start = body.rfind(&marker)? + marker.len();
+    let end = [FINDING_MARKER_PREFIX, FINDINGS_END]
+        .iter()
+        .filter_map(|delim| body[start..].find(delim))
+        .min()
+        .map_or(body.len(), |i| start + i);
+    let entry = &body[start..end];
+
+    // Entry layout: `N.&nbsp;<header>[<br>status][<br>file][<br>body]\n\n`
+    let header_end = line_break(entry);
+    let (number, header_html) = entry[..header_end].split_once("&nbsp;")?;
+    let header_html = header_html
+        .strip_prefix("<del>")
+        .and_then(|h| h.strip_suffix("</del>"))
+        .unwrap_or(header_html);
+    let mut rest = &entry[header_end..];
+    if let Some(after) = rest.strip_prefix(STATUS_PREFIX) {
+        rest = &after[line_break(after)..];
+    }
+
+    let header = match annotation.status {
+        FindingStatus::Resolved => format!("<del>{header_html}</del>"),
+        FindingStatus::Wontfix => header_html.to_string(),
+    };
+    let new_entry = format!("{number}&nbsp;{header}{}{rest}", annotation.status_line());
+    let body = format!("{}{new_entry}{}", &body[..start], &body[end..]);
+    Some((body, strip_tags(header_html)))
+}
+
+/// Offset of the first `<br>` or newline in `s`, or its length.
+fn line_break(s: &str) -> usize {
+    [s.find("<br>"), s.find('\n')]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(s.len())
+}
+
+/// Drop HTML tags, keeping the text between them.
+fn strip_tags(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::{load_settings, with_settings};
+    use crate::git::types::IssueComment;
+    use crate::output::review_formatter::format_review_markdown;
+    use crate::testing::mock_git::MockGitProvider;
+
+    const REVIEW_YAML: &str = r#"
+review:
+  key_issues_to_review:
+    - issue_header: "Possible Bug"
+      issue_content: "Null dereference on empty input"
+      relevant_file: "src/parser.rs"
+      start_line: 15
+      end_line: 20
+    - issue_header: "Performance"
+      issue_content: "Query runs in a loop"
+      relevant_file: "src/db.rs"
+      start_line: 7
+      end_line: 7
+"#;
+
+    fn review_body() -> String {
+        let data: serde_yaml_ng::Value = serde_yaml_ng::from_str(REVIEW_YAML).unwrap();
+        format_review_markdown(&data, true, None)
+    }
+
+    fn annotation<'a>(status: FindingStatus, reason: &'a str) -> FindingAnnotation<'a> {
+        FindingAnnotation {
+            status,
+            author: "alice",
+            reason,
+        }
+    }
+
+    #[test]
+    fn test_parse_finding_reply() {
+        assert_eq!(parse_finding_reply("2"), Some((2, "")));
+        assert_eq!(
+            parse_finding_reply(" #3  covered by the caller "),
+            Some((3, "covered by the caller"))
+        );
+        assert_eq!(parse_finding_reply("0"), None);
+        assert_eq!(parse_finding_reply("two"), None);
+        assert_eq!(parse_finding_reply(""), None);
+    }
+
+    #[test]
+    fn test_annotate_finding_resolved_strikes_header() {
+        let body = review_body();
+        let (out, header) =
+            annotate_finding(&body, 1, &annotation(FindingStatus::Resolved, "")).unwrap();
+
+        assert_eq!(header, "Possible Issue");
+        assert!(out.contains(
+            "<!-- pr-agent:finding 1 -->1.&nbsp;<del><strong>Possible Issue</strong></del>\
+             <br><!-- pr-agent:finding-status -->✅ <em>Resolved</em> by @alice\
+             <br><code>src/parser.rs</code> (line 15-20)<br>Null dereference on empty input"
+        ));
+        // Other findings are untouched
+        assert!(out.contains("2.&nbsp;<strong>Performance</strong><br><code>src/db.rs</code>"));
+        assert!(annotate_finding(&body, 3, &annotation(FindingStatus::Resolved, "")).is_none());
+    }
+
+    #[test]
+    fn test_annotate_finding_replaces_previous_status() {
+        let body = review_body();
+        let (body, _) =
+            annotate_finding(&body, 2, &annotation(FindingStatus::Resolved, "")).unwrap();
+        let (out, header) = annotate_finding(
+            &body,
+            2,
+            &annotation(FindingStatus::Wontfix, "needs a <b>schema</b>\nchange"),
+        )
+        .unwrap();
+
+        assert_eq!(header, "Performance");
+        assert!(out.contains(
+            "2.&nbsp;<strong>Performance</strong>\
+             <br><!-- pr-agent:finding-status -->🚫 <em>Won't fix</em> by @alice: \
+             needs a &lt;b>schema&lt;/b> change<br><code>src/db.rs</code>"
+        ));
+        assert!(!out.contains("<del>"));
+        assert_eq!(out.matches("finding-status").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_edits_review_and_records_resolution() {
+        let folder = std::env::temp_dir().join(format!("pr-agent-resolve-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+
+        let mut provider = MockGitProvider::new();
+        provider.issue_comments = vec![IssueComment {
+            id: 42,
+            body: review_body(),
+            user: "bot".into(),
+            created_at: String::new(),
+            url: None,
+        }];
+        let provider = Arc::new(provider);
+
+        let mut overrides = HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert(
+            "config.analytics_folder".into(),
+            folder.to_string_lossy().into_owned(),
+        );
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+
+        let mut args = HashMap::new();
+        args.insert("_text".to_string(), "2 tracked in a follow-up".to_string());
+        args.insert("_author".to_string(), "alice".to_string());
+        with_settings(
+            settings,
+            PRResolveFinding::new(provider.clone()).run(FindingStatus::Wontfix, &args),
+        )
+        .await
+        .unwrap();
+
+        let calls = provider.get_calls();
+        let (id, body) = &calls.edited_comments[0];
+        assert_eq!(id, "42");
+        assert!(body.contains("Won't fix</em> by @alice: tracked in a follow-up"));
+
+        let (owner, repo) = provider.repo_owner_and_name();
+        let records = crate::analytics::load_finding_resolutions(
+            &folder,
+            &format!("{owner}/{repo}"),
+            provider.get_pr_number().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].finding, 2);
+        assert_eq!(records[0].header, "Performance");
+        assert_eq!(records[0].status, FindingStatus::Wontfix);
+        assert_eq!(records[0].user, "alice");
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_without_number_fails() {
+        let provider = Arc::new(MockGitProvider::new());
+        let err = PRResolveFinding::new(provider)
+            .run(FindingStatus::Resolved, &HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("usage: /resolve"));
+    }
+}
@@ -4,20 +4,47 @@ use std::sync::Arc;
 use minijinja::Value;
 
 use crate::ai::AiHandler;
+use crate::ai::router::{ModelRouter, ModelTier};
+use crate::ai::schema;
 use crate::config::loader::get_settings;
+use crate::config::types::PrReviewerConfig;
 use crate::config::types::Settings;
 use crate::error::PrAgentError;
 use crate::git::GitProvider;
+use crate::git::types::{Capability, CheckRunReport, CommentId, IssueComment, PlanningContext};
+use crate::integrations::jira::{self, Ticket};
+use crate::notify;
+use crate::output::help_text::{REVIEW_HELP_TEXT, REVIEW_INTRO_TEXT, add_guide_text};
+use crate::output::json_parser::load_json;
+use crate::output::markdown::persistent_comment_marker;
+use crate::output::report::{self, Report};
 use crate::output::review_formatter::{
-    LinkGenerator, extract_effort_score, format_review_markdown, is_value_no, yaml_value_to_string,
+    FindingsLayout, LinkGenerator, extract_effort_score, format_review_markdown,
+    format_review_markdown_with_layout, is_value_no, retain_review_sections, security_severity,
+    yaml_value_to_string,
 };
+use crate::output::terminal;
 use crate::output::yaml_parser::load_yaml;
+use crate::processing::codeowners::{self, FileOwners};
 use crate::processing::compression::get_pr_diff;
-use crate::template::render::render_prompt;
+use crate::processing::context_retrieval;
+use crate::processing::language;
+use crate::processing::test_impact::{TestImpact, analyze_test_impact};
+use crate::stats::MetricEvent;
 use crate::tools::{
-    PrMetadata, build_common_vars, insert_custom_labels_vars, publish_as_comment,
-    with_progress_comment,
+    ContextOverflow, PrMetadata, build_common_vars, insert_custom_labels_vars, publish_as_comment,
+    render_prompt_fitting_context, resolve_guide_text, with_streaming_progress_comment,
 };
+use crate::tools::{ai_metadata, auto_approve};
+
+/// Hidden marker recording the head commit a published review covers.
+const REVIEWED_COMMIT_PREFIX: &str = "<!-- pr-agent:review-commit ";
+
+/// Review labels: `Review effort [1-5]: 3`, `Score: 85`, and the security
+/// labels from `security_label`.
+const EFFORT_LABEL_PREFIX: &str = "Review effort [1-5]: ";
+const SCORE_LABEL_PREFIX: &str = "Score: ";
+const SECURITY_LABEL_PREFIXES: &[&str] = &["security: ", "Security concern"];
 
 /// PR Reviewer tool.
 ///
@@ -26,11 +53,67 @@ use crate::tools::{
 pub struct PRReviewer {
     provider: Arc<dyn GitProvider>,
     ai: Option<Arc<dyn AiHandler>>,
+    incremental: bool,
+    push_trigger: bool,
+    range: Option<CommitRange>,
+}
+
+/// Commits picked with `/review_range --base=<sha> --head=<sha>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitRange {
+    pub base: String,
+    pub head: String,
+}
+
+impl CommitRange {
+    /// Read `--base` and `--head` from the command args. Both must be
+    /// commit SHAs, full or abbreviated to at least 7 characters.
+    pub fn from_args(args: &HashMap<String, String>) -> Result<Self, PrAgentError> {
+        let sha = |key: &str| {
+            let value = args.get(key).map(|v| v.trim()).unwrap_or_default();
+            if (7..=40).contains(&value.len()) && value.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(value.to_lowercase())
+            } else {
+                Err(PrAgentError::Other(format!(
+                    "/review_range needs --base=<sha> and --head=<sha>, got --{key}={value:?}"
+                )))
+            }
+        };
+        Ok(Self {
+            base: sha("base")?,
+            head: sha("head")?,
+        })
+    }
+}
+
+/// The previous review an incremental run builds on.
+struct IncrementalBase {
+    comment_id: u64,
+    body: String,
+    /// Head commit the previous review covered.
+    reviewed_sha: String,
+    head_sha: String,
+    num_new_commits: usize,
+}
+
+/// What an incremental `/review -i` run should do.
+enum IncrementalPlan {
+    /// No usable previous review: review the whole PR.
+    Full,
+    /// Nothing to do yet; the reason is logged.
+    Skip(&'static str),
+    Since(IncrementalBase),
 }
 
 impl PRReviewer {
     pub fn new(provider: Arc<dyn GitProvider>) -> Self {
-        Self { provider, ai: None }
+        Self {
+            provider,
+            ai: None,
+            incremental: false,
+            push_trigger: false,
+            range: None,
+        }
     }
 
     #[cfg(test)]
@@ -38,13 +121,35 @@ impl PRReviewer {
         Self {
             provider,
             ai: Some(ai),
+            incremental: false,
+            push_trigger: false,
+            range: None,
         }
     }
 
+    /// Only review the commits pushed since the last published review.
+    pub fn with_incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// Only review the changes between two commits of the PR.
+    pub fn with_range(mut self, range: CommitRange) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    /// Started by a push: skip unless the incremental review commit/minute
+    /// thresholds are met since the last published review.
+    pub fn with_push_trigger(mut self, push_trigger: bool) -> Self {
+        self.push_trigger = push_trigger;
+        self
+    }
+
     /// Run the full review pipeline.
     pub async fn run(&self) -> Result<(), PrAgentError> {
         let provider = &self.provider;
-        with_progress_comment(provider.as_ref(), "Preparing review...", || {
+        with_streaming_progress_comment(provider.as_ref(), "Preparing review...", || {
             self.run_inner()
         })
         .await
@@ -52,20 +157,102 @@ impl PRReviewer {
 
     async fn run_inner(&self) -> Result<(), PrAgentError> {
         let settings = get_settings();
-        let model = &settings.config.model;
+
+        let range = match &self.range {
+            Some(range) => Some(self.resolve_range(range).await?),
+            None => None,
+        };
+
+        if self.push_trigger
+            && !self.incremental
+            && range.is_none()
+            && let Some(reason) = self.push_review_skip_reason(&settings.pr_reviewer).await
+        {
+            tracing::info!(reason, "skipping push-triggered review");
+            return Ok(());
+        }
+
+        let incremental = if self.incremental && range.is_none() {
+            match self.plan_incremental(&settings.pr_reviewer).await? {
+                IncrementalPlan::Full => None,
+                IncrementalPlan::Skip(reason) => {
+                    tracing::info!(reason, "skipping incremental review");
+                    return Ok(());
+                }
+                IncrementalPlan::Since(base) => Some(base),
+            }
+        } else {
+            None
+        };
 
         // 1. Fetch PR metadata
-        let meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
+        let mut meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
 
         // 2. Fetch and process diff
-        let mut files = self.provider.get_diff_files().await?;
+        let (mut files, head_sha) = match (&range, &incremental) {
+            (Some(range), _) => {
+                let files = self
+                    .provider
+                    .get_diff_files_between(&range.base, &range.head)
+                    .await?;
+                (files, None)
+            }
+            (None, Some(base)) => {
+                let files = self
+                    .provider
+                    .get_diff_files_between(&base.reviewed_sha, &base.head_sha)
+                    .await?;
+                (files, Some(base.head_sha.clone()))
+            }
+            (None, None) => {
+                // Recorded in the comment so a later `/review -i` knows where to start.
+                let head_sha = self.provider.get_pr_head_sha().await.ok();
+                (
+                    super::fetch_diff_files(self.provider.as_ref(), "review").await?,
+                    head_sha,
+                )
+            }
+        };
+        if let Some(range) = &range
+            && files.is_empty()
+        {
+            return Err(PrAgentError::Other(format!(
+                "no changes between `{}` and `{}`",
+                short_sha(&range.base),
+                short_sha(&range.head)
+            )));
+        }
+        if incremental.is_some() && files.is_empty() {
+            tracing::info!("no files changed since the last review, skipping incremental review");
+            return Ok(());
+        }
         let num_files = files.len();
         tracing::info!(num_files, "processing changed files for review");
+        meta.language = language::annotate_files(self.provider.as_ref(), &mut files).await;
+        meta.repo_context =
+            context_retrieval::retrieve(self.provider.as_ref(), &files, &settings).await;
+        meta.codeowners =
+            codeowners::changed_file_owners(self.provider.as_ref(), &files, &settings).await;
+        let ai = super::resolve_ai_handler(&self.ai)?;
+        meta.ai_metadata =
+            ai_metadata::annotate_files(ai.as_ref(), &mut files, &meta.title, &settings).await;
+
+        let reviewer = &settings.pr_reviewer;
+        // A security review benefits from the reasoning model, when one is set.
+        let tier = if reviewer.require_security_review && reviewer.section_enabled("security") {
+            ModelTier::Reasoning
+        } else {
+            ModelTier::Default
+        };
+        let routed = ModelRouter::new(&settings.config).resolve(tier, ai.as_ref());
+        let model = routed.model.as_str();
+        let test_impact = (reviewer.require_tests_review && reviewer.section_enabled("tests"))
+            .then(|| analyze_test_impact(&files));
 
-        let diff_result = get_pr_diff(
+        ai_metadata::summarize_large_patches(ai.as_ref(), &mut files, model, &settings).await;
+        let mut diff_result = get_pr_diff(
             &mut files, model, true, /* add_line_numbers for review */
         );
-        drop(files); // release file contents now that diff is built
         tracing::info!(
             tokens = diff_result.token_count,
             files_included = diff_result.files_in_diff.len(),
@@ -74,22 +261,37 @@ impl PRReviewer {
         );
 
         // 3. Build template variables
-        let vars = self.build_vars(&meta, &diff_result.diff, num_files);
-
-        // 4. Render prompt
-        let rendered = render_prompt(&settings.pr_review_prompt, vars)?;
-
-        // 5. Call AI (with fallback models)
-        tracing::info!(model, "calling AI model for review");
-        let ai = super::resolve_ai_handler(&self.ai)?;
+        let mut vars = self.build_vars(&meta, &diff_result.diff, num_files);
+        let describe_context =
+            super::fetch_describe_context(self.provider.as_ref(), &settings).await;
+        vars.insert("describe_context".into(), Value::from(describe_context));
+
+        // 4. Render prompt (re-compressing the diff if it overflows the context window)
+        let (rendered, overflow) = render_prompt_fitting_context(
+            &settings.pr_review_prompt,
+            vars,
+            &mut files,
+            &mut diff_result,
+            model,
+            true,
+        )?;
         let image_urls = super::get_pr_images(
             &meta.description,
             self.provider.as_ref(),
             self.provider.get_pr_number(),
+            true,
         )
         .await;
+        drop(files); // release patches now that the prompt is built
+
+        // 5. Call AI (with fallback models)
+        tracing::info!(model, "calling AI model for review");
         let image_ref = image_urls.as_deref();
-        let response = crate::ai::chat_completion_with_fallback(
+        let schema = settings
+            .config
+            .structured_output
+            .then(schema::review_schema);
+        let response = crate::ai::chat_completion_with_schema(
             ai.as_ref(),
             model,
             &settings.config.fallback_models,
@@ -97,6 +299,7 @@ impl PRReviewer {
             &rendered.user,
             Some(settings.config.temperature),
             image_ref,
+            schema.as_ref(),
         )
         .await?;
 
@@ -106,28 +309,88 @@ impl PRReviewer {
             "AI response received"
         );
 
-        // 6. Parse YAML from response
-        let yaml_data = load_yaml(
-            &response.content,
-            &[
-                "estimated_effort_to_review_[1-5]:",
-                "security_concerns:",
-                "key_issues_to_review:",
-                "relevant_file:",
-                "issue_header:",
-                "issue_content:",
-                "ticket_compliance_check:",
-            ],
-            "review",
-            "security_concerns",
-        );
+        // 6. Parse JSON (structured output) or YAML from response
+        let (mut yaml_data, content) = super::parse_with_reprompt(
+            ai.as_ref(),
+            model,
+            &rendered,
+            response.content,
+            parse_review,
+        )
+        .await;
+
+        if let (Some(data), Some(impact)) = (yaml_data.as_mut(), &test_impact) {
+            attach_test_impact(data, impact);
+        }
+        if let Some(data) = yaml_data.as_mut() {
+            attach_checklist(data, &settings.pr_reviewer.checklist.questions);
+            retain_review_sections(data, &settings.pr_reviewer.sections);
+            attach_planning_context(data, &meta.planning);
+            attach_related_tickets(data, &meta.tickets, &settings.pr_reviewer);
+        }
+
+        if report::is_enabled() {
+            report::write(&Report::review(
+                self.provider.get_pr_url(),
+                yaml_data.as_ref(),
+                &content,
+            ))?;
+        }
 
         // 7. Format and publish
         if settings.config.publish_output {
-            self.publish_review(yaml_data.as_ref(), &response.content)
+            if let Some(range) = &range {
+                self.publish_range_review(range, yaml_data.as_ref(), &content, overflow.as_ref())
+                    .await?;
+            } else if let Some(base) = &incremental {
+                self.publish_incremental_review(
+                    base,
+                    yaml_data.as_ref(),
+                    &content,
+                    overflow.as_ref(),
+                )
                 .await?;
+            } else {
+                self.publish_review(
+                    yaml_data.as_ref(),
+                    &content,
+                    overflow.as_ref(),
+                    head_sha.as_deref(),
+                    &meta.codeowners,
+                )
+                .await?;
+                if let Some(data) = yaml_data.as_ref() {
+                    super::persist_review_record(self.provider.as_ref(), &settings, data).await;
+                    super::record_metrics(self.provider.as_ref(), &settings, |repo, pr, sha| {
+                        MetricEvent::review(data, &repo, pr, &sha)
+                    })
+                    .await;
+                }
+            }
+            // Publish review labels (effort / score / security) if enabled.
+            // A range review covers only part of the PR, so it leaves them alone.
+            if let Some(data) = yaml_data.as_ref().filter(|_| range.is_none()) {
+                self.publish_review_labels(data, &settings).await?;
+                if settings.pr_reviewer.block_on_high_severity {
+                    self.publish_security_check(data.get("review").unwrap_or(data))
+                        .await;
+                }
+            }
+            if let Some(reason) = yaml_data
+                .as_ref()
+                .filter(|_| range.is_none())
+                .and_then(|data| auto_approve::review_approval_reason(&settings, data))
+            {
+                auto_approve::approve(self.provider.as_ref(), &reason).await;
+            }
+            if let Some(data) = yaml_data.as_ref() {
+                notify::set_summary(notify::summarize_review(data));
+            }
         } else {
-            self.print_review(yaml_data.as_ref(), &response.content);
+            self.print_review(yaml_data.as_ref(), &content);
+            if let Some(overflow) = &overflow {
+                eprintln!("{}", overflow.notice());
+            }
         }
 
         Ok(())
@@ -142,7 +405,8 @@ impl PRReviewer {
         let settings = get_settings();
         let mut vars = build_common_vars(meta, diff);
 
-        // Review-specific variables
+        // Review-specific variables (sections outside the whitelist aren't requested)
+        let reviewer = &settings.pr_reviewer;
         vars.insert("num_pr_files".into(), Value::from(num_files));
         vars.insert(
             "num_max_findings".into(),
@@ -150,36 +414,49 @@ impl PRReviewer {
         );
         vars.insert(
             "require_score".into(),
-            Value::from(settings.pr_reviewer.require_score_review),
+            Value::from(reviewer.require_score_review && reviewer.section_enabled("score")),
         );
         vars.insert(
             "require_tests".into(),
-            Value::from(settings.pr_reviewer.require_tests_review),
+            Value::from(reviewer.require_tests_review && reviewer.section_enabled("tests")),
         );
         vars.insert(
             "require_estimate_effort_to_review".into(),
-            Value::from(settings.pr_reviewer.require_estimate_effort_to_review),
+            Value::from(
+                reviewer.require_estimate_effort_to_review && reviewer.section_enabled("effort"),
+            ),
         );
         vars.insert(
             "require_estimate_contribution_time_cost".into(),
-            Value::from(settings.pr_reviewer.require_estimate_contribution_time_cost),
+            Value::from(
+                reviewer.require_estimate_contribution_time_cost
+                    && reviewer.section_enabled("contribution_time"),
+            ),
         );
         vars.insert(
             "require_can_be_split_review".into(),
-            Value::from(settings.pr_reviewer.require_can_be_split_review),
+            Value::from(reviewer.require_can_be_split_review && reviewer.section_enabled("split")),
         );
         vars.insert(
             "require_security_review".into(),
-            Value::from(settings.pr_reviewer.require_security_review),
+            Value::from(reviewer.require_security_review && reviewer.section_enabled("security")),
         );
         vars.insert(
             "require_todo_scan".into(),
-            Value::from(settings.pr_reviewer.require_todo_scan),
+            Value::from(reviewer.require_todo_scan && reviewer.section_enabled("todo")),
         );
         vars.insert(
             "require_ticket_analysis_review".into(),
-            Value::from(settings.pr_reviewer.require_ticket_analysis_review),
+            Value::from(
+                reviewer.require_ticket_analysis_review && reviewer.section_enabled("ticket"),
+            ),
         );
+        let checklist: &[String] = if reviewer.section_enabled("checklist") {
+            &reviewer.checklist.questions
+        } else {
+            &[]
+        };
+        vars.insert("checklist".into(), Value::from(checklist.to_vec()));
         vars.insert("question_str".into(), Value::from(""));
         vars.insert("answer_str".into(), Value::from(""));
         vars.insert(
@@ -187,8 +464,14 @@ impl PRReviewer {
             Value::from(settings.pr_reviewer.extra_instructions.as_str()),
         );
         insert_custom_labels_vars(&mut vars, &settings);
-        vars.insert("is_ai_metadata".into(), Value::from(false));
-        vars.insert("related_tickets".into(), Value::from(Vec::<String>::new()));
+        vars.insert("is_ai_metadata".into(), Value::from(meta.ai_metadata));
+        let related_tickets =
+            if reviewer.require_ticket_analysis_review && reviewer.section_enabled("ticket") {
+                jira::tickets_prompt_value(&meta.tickets)
+            } else {
+                Value::from(Vec::<String>::new())
+            };
+        vars.insert("related_tickets".into(), related_tickets);
         vars.insert("duplicate_prompt_examples".into(), Value::from(false));
         vars.insert(
             "date".into(),
@@ -199,13 +482,74 @@ impl PRReviewer {
     }
 
     /// Publish the formatted review to the PR.
+    ///
+    /// `head_sha` is recorded in a hidden marker for later incremental reviews.
     async fn publish_review(
         &self,
         yaml_data: Option<&serde_yaml_ng::Value>,
         raw_response: &str,
+        overflow: Option<&ContextOverflow>,
+        head_sha: Option<&str>,
+        owners: &FileOwners,
     ) -> Result<(), PrAgentError> {
         let settings = get_settings();
-        let gfm_supported = self.provider.is_supported("gfm_markdown");
+        let mut markdown = self.format_review(yaml_data, raw_response);
+
+        let reviewer = &settings.pr_reviewer;
+        let intro = if reviewer.enable_intro_text {
+            resolve_guide_text(
+                self.provider.as_ref(),
+                &reviewer.intro_text,
+                REVIEW_INTRO_TEXT,
+            )
+            .await
+        } else {
+            String::new()
+        };
+        let help = if reviewer.enable_help_text {
+            resolve_guide_text(
+                self.provider.as_ref(),
+                &reviewer.help_text,
+                REVIEW_HELP_TEXT,
+            )
+            .await
+        } else {
+            String::new()
+        };
+        add_guide_text(&mut markdown, &intro, &help);
+        if let Some(overflow) = overflow {
+            markdown.push('\n');
+            markdown.push_str(&overflow.notice());
+        }
+        if reviewer.mention_codeowners && !owners.is_empty() {
+            markdown.push_str(&format!(
+                "\n\n**Code owners:** {}\n",
+                codeowners::all_owners(owners).join(" ")
+            ));
+        }
+        if let Some(sha) = head_sha {
+            markdown.push_str(&reviewed_commit_marker(sha));
+        }
+
+        publish_as_comment(
+            self.provider.as_ref(),
+            &markdown,
+            "review",
+            settings.pr_reviewer.persistent_comment,
+            settings.pr_reviewer.final_update_message,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Render the review YAML (or the raw response if it didn't parse) as markdown.
+    fn format_review(
+        &self,
+        yaml_data: Option<&serde_yaml_ng::Value>,
+        raw_response: &str,
+    ) -> String {
+        let gfm_supported = self.provider.is_supported(Capability::GfmMarkdown);
 
         // Build link generator from provider
         let provider = self.provider.clone();
@@ -213,32 +557,159 @@ impl PRReviewer {
             provider.get_line_link(file, start, end)
         });
 
-        let markdown = match yaml_data {
-            Some(data) => format_review_markdown(data, gfm_supported, Some(&link_gen)),
+        match yaml_data {
+            Some(data) => format_review_markdown_with_layout(
+                data,
+                gfm_supported,
+                Some(&link_gen),
+                &FindingsLayout::from_config(&get_settings().pr_reviewer),
+            ),
             None => {
                 tracing::warn!("could not parse YAML from AI response, publishing raw");
                 format!("## PR Reviewer Guide 🔍\n\n{}\n", raw_response)
             }
+        }
+    }
+
+    /// Decide whether `/review -i` can build on a previous review.
+    ///
+    /// Finds the latest review comment carrying a reviewed-commit marker and
+    /// counts the commits pushed since. Falls back to a full review when there
+    /// is no such comment or the commit is gone from the branch (force-push).
+    async fn plan_incremental(
+        &self,
+        reviewer: &PrReviewerConfig,
+    ) -> Result<IncrementalPlan, PrAgentError> {
+        let comments = self.provider.get_issue_comments().await?;
+        let Some((comment, reviewed_sha)) = last_review(&comments) else {
+            tracing::info!("no previous review with a recorded commit, running a full review");
+            return Ok(IncrementalPlan::Full);
         };
 
-        publish_as_comment(
-            self.provider.as_ref(),
-            &markdown,
-            "review",
-            settings.pr_reviewer.persistent_comment,
-            settings.pr_reviewer.final_update_message,
-        )
-        .await?;
+        let commits = match self.provider.get_pr_commits().await {
+            Ok(commits) => commits,
+            Err(e) => {
+                tracing::warn!(error = %e, "could not list PR commits, running a full review");
+                return Ok(IncrementalPlan::Full);
+            }
+        };
+        let Some(pos) = commits.iter().position(|c| c.sha == reviewed_sha) else {
+            tracing::info!(
+                reviewed_sha,
+                "reviewed commit is no longer on the branch, running a full review"
+            );
+            return Ok(IncrementalPlan::Full);
+        };
 
-        // Publish review labels (effort / security) if enabled
-        if let Some(data) = yaml_data {
-            self.publish_review_labels(data, &settings).await?;
+        let num_new_commits = commits.len() - pos - 1;
+        if num_new_commits == 0 {
+            return Ok(IncrementalPlan::Skip(
+                "no new commits since the last review",
+            ));
+        }
+        let minutes = minutes_since(reviewed_at(&comment.body).unwrap_or(&commits[pos].date));
+        if !incremental_thresholds_met(num_new_commits, minutes, reviewer) {
+            return Ok(IncrementalPlan::Skip(
+                "incremental review commit/minute thresholds not met",
+            ));
         }
 
-        Ok(())
+        Ok(IncrementalPlan::Since(IncrementalBase {
+            comment_id: comment.id,
+            body: comment.body.clone(),
+            reviewed_sha,
+            head_sha: commits[commits.len() - 1].sha.clone(),
+            num_new_commits,
+        }))
     }
 
-    /// Extract and publish review labels (effort score, security concern) from AI response.
+    /// Why a push-triggered review should be skipped, if it should.
+    ///
+    /// Reviews run when there is no previous review with a recorded commit,
+    /// or when its commit is gone from the branch (force-push).
+    async fn push_review_skip_reason(&self, reviewer: &PrReviewerConfig) -> Option<&'static str> {
+        let comments = self.provider.get_issue_comments().await.ok()?;
+        let (comment, reviewed_sha) = last_review(&comments)?;
+        let commits = self.provider.get_pr_commits().await.ok()?;
+        let pos = commits.iter().position(|c| c.sha == reviewed_sha)?;
+
+        let num_new_commits = commits.len() - pos - 1;
+        let minutes = minutes_since(reviewed_at(&comment.body).unwrap_or(&commits[pos].date));
+        (!incremental_thresholds_met(num_new_commits, minutes, reviewer))
+            .then_some("incremental review commit/minute thresholds not met")
+    }
+
+    /// Append the review of the new commits to the previous review comment.
+    async fn publish_incremental_review(
+        &self,
+        base: &IncrementalBase,
+        yaml_data: Option<&serde_yaml_ng::Value>,
+        raw_response: &str,
+        overflow: Option<&ContextOverflow>,
+    ) -> Result<(), PrAgentError> {
+        let mut markdown = self.format_review(yaml_data, raw_response);
+        if let Some(overflow) = overflow {
+            markdown.push('\n');
+            markdown.push_str(&overflow.notice());
+        }
+        let body = append_incremental_section(base, &markdown);
+        self.provider
+            .edit_comment(&CommentId(base.comment_id.to_string()), &body)
+            .await
+    }
+
+    /// Expand abbreviated SHAs in `range` to the PR's commits. The head must
+    /// be a commit of the PR; the base may also be where the branch started.
+    /// Without a commit list the range is used as given.
+    async fn resolve_range(&self, range: &CommitRange) -> Result<CommitRange, PrAgentError> {
+        let commits = match self.provider.get_pr_commits().await {
+            Ok(commits) => commits,
+            Err(e) => {
+                tracing::warn!(error = %e, "could not list PR commits, using the range as given");
+                return Ok(range.clone());
+            }
+        };
+        let find = |sha: &str| {
+            commits
+                .iter()
+                .find(|c| c.sha.starts_with(sha))
+                .map(|c| c.sha.clone())
+        };
+        let head = find(&range.head).ok_or_else(|| {
+            PrAgentError::Other(format!(
+                "commit `{}` is not part of this PR",
+                short_sha(&range.head)
+            ))
+        })?;
+        let base = find(&range.base).unwrap_or_else(|| range.base.clone());
+        Ok(CommitRange { base, head })
+    }
+
+    /// Publish the review of a commit range as a new comment, leaving the
+    /// PR's review comment as it is.
+    async fn publish_range_review(
+        &self,
+        range: &CommitRange,
+        yaml_data: Option<&serde_yaml_ng::Value>,
+        raw_response: &str,
+        overflow: Option<&ContextOverflow>,
+    ) -> Result<(), PrAgentError> {
+        let mut markdown = self.format_review(yaml_data, raw_response);
+        if let Some(overflow) = overflow {
+            markdown.push('\n');
+            markdown.push_str(&overflow.notice());
+        }
+        let body = format!(
+            "## Review of `{}..{}` 🔍\n\n{}\n",
+            short_sha(&range.base),
+            short_sha(&range.head),
+            review_section(&markdown)
+        );
+        publish_as_comment(self.provider.as_ref(), &body, "review_range", false, false).await
+    }
+
+    /// Extract and publish review labels (effort, score, security concern)
+    /// from the AI response, replacing the ones an earlier review left.
     async fn publish_review_labels(
         &self,
         data: &serde_yaml_ng::Value,
@@ -246,6 +717,8 @@ impl PRReviewer {
     ) -> Result<(), PrAgentError> {
         let review = data.get("review").unwrap_or(data);
         let mut labels = Vec::new();
+        // Prefixes of the label kinds this review decides on.
+        let mut replaced: Vec<&str> = Vec::new();
 
         if settings.pr_reviewer.enable_review_labels_effort
             && let Some(effort_val) = review
@@ -253,7 +726,15 @@ impl PRReviewer {
                 .or_else(|| review.get("estimated_effort_to_review"))
         {
             let effort = extract_effort_score(effort_val);
-            labels.push(format!("Review effort [1-5]: {effort}"));
+            labels.push(format!("{EFFORT_LABEL_PREFIX}{effort}"));
+            replaced.push(EFFORT_LABEL_PREFIX);
+        }
+
+        if settings.pr_reviewer.require_score_review
+            && let Some(score) = review.get("score").and_then(review_score)
+        {
+            labels.push(format!("{SCORE_LABEL_PREFIX}{score}"));
+            replaced.push(SCORE_LABEL_PREFIX);
         }
 
         if settings.pr_reviewer.enable_review_labels_security
@@ -261,10 +742,29 @@ impl PRReviewer {
         {
             let text = yaml_value_to_string(sec_val);
             if !is_value_no(&text) {
-                labels.push("Security concern".to_string());
+                labels.push(security_label(security_severity(review)));
             }
+            replaced.extend(SECURITY_LABEL_PREFIXES);
         }
 
+        if replaced.is_empty() {
+            return Ok(());
+        }
+        if !self.provider.is_supported(Capability::Labels) {
+            tracing::info!("provider has no PR labels, skipping review labels");
+            return Ok(());
+        }
+        let existing = self.provider.get_pr_labels().await.unwrap_or_default();
+        for stale in existing
+            .iter()
+            .filter(|l| replaced.iter().any(|p| l.starts_with(p)) && !labels.contains(l))
+        {
+            tracing::info!(label = %stale, "removing stale review label");
+            if let Err(e) = self.provider.remove_label(stale).await {
+                tracing::warn!(label = %stale, error = %e, "failed to remove stale review label");
+            }
+        }
+        labels.retain(|l| !existing.contains(l));
         if !labels.is_empty() {
             tracing::info!(?labels, "publishing review labels");
             self.provider.publish_labels(&labels).await?;
@@ -273,9 +773,38 @@ impl PRReviewer {
         Ok(())
     }
 
+    /// Publish a `pr-agent: security` check that fails on high-severity
+    /// security concerns, so branch protection can block the merge. Its
+    /// "Re-run" replays `/review`.
+    async fn publish_security_check(&self, review: &serde_yaml_ng::Value) {
+        let severity = security_severity(review);
+        let blocked = severity == Some("high");
+        let check = CheckRunReport {
+            name: "pr-agent: security".to_string(),
+            command: "/review".to_string(),
+            success: !blocked,
+            summary: match severity {
+                Some(severity) if blocked => format!(
+                    "The review found {severity}-severity security concerns. See the review comment for details."
+                ),
+                Some(severity) => {
+                    format!("No high-severity security concerns ({severity} at most).")
+                }
+                None => "No security concerns found.".to_string(),
+            },
+        };
+        if let Err(e) = self.provider.publish_check_run(&check).await {
+            tracing::warn!(error = %e, "failed to publish security check");
+        }
+    }
+
     /// Print review to stdout (CLI mode).
     fn print_review(&self, yaml_data: Option<&serde_yaml_ng::Value>, raw_response: &str) {
         match yaml_data {
+            Some(data) if terminal::is_json() => println!("{}", terminal::to_json(data)),
+            Some(data) if terminal::is_rich() => {
+                print!("{}", terminal::render_review_summary(data))
+            }
             Some(data) => {
                 let formatted = format_review_markdown(data, true, None);
                 println!("{formatted}");
@@ -288,6 +817,274 @@ impl PRReviewer {
     }
 }
 
+/// Hidden marker recording that a review covers the PR up to `sha`.
+/// Parse a review response: JSON (structured output) or YAML.
+fn parse_review(content: &str) -> Option<serde_yaml_ng::Value> {
+    load_json(content).or_else(|| {
+        load_yaml(
+            content,
+            &[
+                "estimated_effort_to_review_[1-5]:",
+                "security_concerns:",
+                "security_severity:",
+                "key_issues_to_review:",
+                "relevant_file:",
+                "issue_header:",
+                "issue_content:",
+                "ticket_compliance_check:",
+            ],
+            "review",
+            "security_concerns",
+        )
+    })
+}
+
+/// The 0-100 review score, from `89` or `"89, because ..."`.
+fn review_score(value: &serde_yaml_ng::Value) -> Option<u32> {
+    let text = yaml_value_to_string(value);
+    let digits: String = text
+        .trim()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok().filter(|score| *score <= 100)
+}
+
+/// Label for security concerns: `security: high` / `security: medium`, or
+/// `Security concern` when the severity is low or unknown.
+fn security_label(severity: Option<&str>) -> String {
+    match severity {
+        Some(severity @ ("high" | "medium")) => format!("security: {severity}"),
+        _ => "Security concern".to_string(),
+    }
+}
+
+/// The first 7 characters of a commit SHA, as shown in comments.
+fn short_sha(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
+
+fn reviewed_commit_marker(sha: &str) -> String {
+    let reviewed_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    format!("\n{REVIEWED_COMMIT_PREFIX}{sha} {reviewed_at} -->\n")
+}
+
+/// Fields of the last reviewed-commit marker: the commit, then when the
+/// review was published (absent in markers written before it was recorded).
+fn reviewed_commit_fields(body: &str) -> impl Iterator<Item = &str> {
+    body.rfind(REVIEWED_COMMIT_PREFIX)
+        .and_then(|start| body[start + REVIEWED_COMMIT_PREFIX.len()..].split_once(" -->"))
+        .map(|(fields, _)| fields.split_whitespace())
+        .into_iter()
+        .flatten()
+}
+
+/// The last reviewed commit recorded in a review comment body.
+fn reviewed_commit(body: &str) -> Option<&str> {
+    reviewed_commit_fields(body).next()
+}
+
+/// When the last review recorded in a review comment body was published.
+fn reviewed_at(body: &str) -> Option<&str> {
+    reviewed_commit_fields(body).nth(1)
+}
+
+/// The latest review comment with a reviewed-commit marker, and its commit.
+fn last_review(comments: &[IssueComment]) -> Option<(&IssueComment, String)> {
+    let marker = persistent_comment_marker("review");
+    comments
+        .iter()
+        .rev()
+        .filter(|c| c.body.starts_with(&marker))
+        .find_map(|c| reviewed_commit(&c.body).map(|sha| (c, sha.to_string())))
+}
+
+/// Minutes elapsed since an RFC 3339 timestamp (`i64::MAX` if unparseable).
+fn minutes_since(date: &str) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(date)
+        .map(|d| (chrono::Utc::now() - d.with_timezone(&chrono::Utc)).num_minutes())
+        .unwrap_or(i64::MAX)
+}
+
+/// Whether enough commits / time have passed since the last review.
+///
+/// With `require_all_thresholds_for_incremental_review` both the commit and
+/// the minute threshold must be met; otherwise either one is enough.
+fn incremental_thresholds_met(
+    num_new_commits: usize,
+    minutes_since_last_review: i64,
+    reviewer: &PrReviewerConfig,
+) -> bool {
+    let enough_commits =
+        num_new_commits >= reviewer.minimal_commits_for_incremental_review as usize;
+    let enough_time =
+        minutes_since_last_review >= i64::from(reviewer.minimal_minutes_for_incremental_review);
+    if reviewer.require_all_thresholds_for_incremental_review {
+        enough_commits && enough_time
+    } else {
+        enough_commits || enough_time
+    }
+}
+
+/// A formatted review without its comment marker and top-level header, for
+/// embedding under another header.
+fn review_section(review: &str) -> &str {
+    let marker = persistent_comment_marker("review");
+    review
+        .trim_start()
+        .trim_start_matches(marker.as_str())
+        .trim_start()
+        .trim_start_matches("## PR Reviewer Guide 🔍")
+        .trim()
+}
+
+/// Previous review body with an "Incremental review" section for `review`
+/// appended and the reviewed-commit marker moved to the new head.
+fn append_incremental_section(base: &IncrementalBase, review: &str) -> String {
+    let previous: String = base
+        .body
+        .lines()
+        .filter(|line| !line.starts_with(REVIEWED_COMMIT_PREFIX))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let section = review_section(review);
+    let short_sha = short_sha(&base.reviewed_sha);
+    let commits = if base.num_new_commits == 1 {
+        "1 new commit".to_string()
+    } else {
+        format!("{} new commits", base.num_new_commits)
+    };
+    format!(
+        "{}\n\n---\n\n## Incremental review 🔁\n\n_{commits} since `{short_sha}`_\n\n{section}\n{}",
+        previous.trim_end(),
+        reviewed_commit_marker(&base.head_sha)
+    )
+}
+
+/// Attach the deterministic test-impact result next to the AI's
+/// `relevant_tests` answer, so the formatter can list untested changes.
+fn attach_test_impact(data: &mut serde_yaml_ng::Value, impact: &TestImpact) {
+    if impact.untested.is_empty() {
+        return;
+    }
+    let review = if data.get("review").is_some() {
+        &mut data["review"]
+    } else {
+        data
+    };
+    if let Some(map) = review.as_mapping_mut() {
+        let files = impact
+            .untested
+            .iter()
+            .map(|f| serde_yaml_ng::Value::from(f.as_str()))
+            .collect();
+        map.insert(
+            "untested_changes".into(),
+            serde_yaml_ng::Value::Sequence(files),
+        );
+    }
+}
+
+/// Line the model's checklist answers up with the configured `questions`:
+/// one entry per question, in config order, matched by question text and
+/// otherwise by position (when the answer at that position isn't for another
+/// question). Questions the model skipped are answered `?`.
+fn attach_checklist(data: &mut serde_yaml_ng::Value, questions: &[String]) {
+    if questions.is_empty() {
+        return;
+    }
+    let review = if data.get("review").is_some() {
+        &mut data["review"]
+    } else {
+        data
+    };
+    let Some(map) = review.as_mapping_mut() else {
+        return;
+    };
+    let answers = map
+        .get("checklist")
+        .and_then(|v| v.as_sequence())
+        .cloned()
+        .unwrap_or_default();
+    let field = |entry: &serde_yaml_ng::Value, key: &str| {
+        entry
+            .get(key)
+            .map(yaml_value_to_string)
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+    let entries = questions
+        .iter()
+        .enumerate()
+        .map(|(i, question)| {
+            let asks = |a: &serde_yaml_ng::Value, q: &str| {
+                field(a, "question").eq_ignore_ascii_case(q.trim())
+            };
+            let answer = answers.iter().find(|a| asks(a, question)).or_else(|| {
+                answers
+                    .get(i)
+                    .filter(|a| !questions.iter().any(|q| asks(a, q)))
+            });
+            let mut entry = serde_yaml_ng::Mapping::new();
+            entry.insert("question".into(), question.trim().into());
+            entry.insert(
+                "answer".into(),
+                answer
+                    .map_or_else(|| "?".to_string(), |a| field(a, "answer"))
+                    .into(),
+            );
+            entry.insert(
+                "explanation".into(),
+                answer
+                    .map(|a| field(a, "explanation"))
+                    .unwrap_or_default()
+                    .into(),
+            );
+            serde_yaml_ng::Value::Mapping(entry)
+        })
+        .collect();
+    map.insert("checklist".into(), serde_yaml_ng::Value::Sequence(entries));
+}
+
+/// Attach the PR's milestone/project context so the formatter can show it
+/// in the review header.
+fn attach_planning_context(data: &mut serde_yaml_ng::Value, planning: &PlanningContext) {
+    if planning.is_empty() {
+        return;
+    }
+    let review = if data.get("review").is_some() {
+        &mut data["review"]
+    } else {
+        data
+    };
+    if let Some(map) = review.as_mapping_mut() {
+        map.insert(
+            "planning_context".into(),
+            planning.to_prompt_context().trim_end().into(),
+        );
+    }
+}
+
+/// Add links to the PR's Jira tickets as the `related_tickets` section.
+fn attach_related_tickets(
+    data: &mut serde_yaml_ng::Value,
+    tickets: &[Ticket],
+    reviewer: &PrReviewerConfig,
+) {
+    if tickets.is_empty() || !reviewer.section_enabled("ticket") {
+        return;
+    }
+    let review = if data.get("review").is_some() {
+        &mut data["review"]
+    } else {
+        data
+    };
+    if let Some(map) = review.as_mapping_mut() {
+        map.insert("related_tickets".into(), jira::tickets_html(tickets).into());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -360,6 +1157,52 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_review_reprompts_on_invalid_yaml() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::with_responses(vec![
+            "{{{{not yaml at all!!!!".into(),
+            REVIEW_YAML.into(),
+        ]));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai.clone());
+
+        with_settings(test_settings(), reviewer.run())
+            .await
+            .unwrap();
+
+        assert_eq!(ai.get_call_count(), 2, "should re-prompt once");
+        let retry = &ai.get_recorded_calls()[1].user;
+        assert!(retry.contains("{{{{not yaml at all!!!!"));
+        assert!(retry.contains("was not valid YAML"));
+        let comment = &provider.get_calls().comments[0].0;
+        assert!(comment.contains("Potential null pointer"));
+    }
+
+    #[tokio::test]
+    async fn test_review_reparse_disabled() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new("{{{{not yaml at all!!!!"));
+        let reviewer = PRReviewer::new_with_ai(provider, ai.clone());
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("config.reparse_attempts".into(), "0".into());
+        let settings = Arc::new(
+            crate::config::loader::load_settings(&overrides, None, None)
+                .expect("should load test settings"),
+        );
+        with_settings(settings, reviewer.run()).await.unwrap();
+
+        assert_eq!(ai.get_call_count(), 1);
+    }
+
     #[tokio::test]
     async fn test_review_publishes_labels_when_enabled() {
         let provider = Arc::new(
@@ -390,6 +1233,304 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_high_severity_security_label_and_check() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let response = "```yaml\nreview:\n  key_issues_to_review: []\n  security_concerns: |\n    SQL injection: the query is built from user input\n  security_severity: high\n```";
+        let reviewer =
+            PRReviewer::new_with_ai(provider.clone(), Arc::new(MockAiHandler::new(response)));
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("pr_reviewer.block_on_high_severity".into(), "true".into());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, reviewer.run()).await.unwrap();
+
+        let calls = provider.get_calls();
+        assert_eq!(calls.labels, vec![vec!["security: high".to_string()]]);
+        assert_eq!(calls.check_runs.len(), 1);
+        assert_eq!(calls.check_runs[0].name, "pr-agent: security");
+        assert!(!calls.check_runs[0].success);
+        assert!(
+            calls.comments[0]
+                .0
+                .contains("Security concerns (high severity)")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_review_replaces_stale_effort_and_score_labels() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+                .with_labels(&[
+                    "Review effort [1-5]: 5",
+                    "Score: 40",
+                    "Security concern",
+                    "bug",
+                ]),
+        );
+        let response = "```yaml\nreview:\n  estimated_effort_to_review_[1-5]: |\n    2\n  score: 85\n  key_issues_to_review: []\n  security_concerns: No\n```";
+        let reviewer =
+            PRReviewer::new_with_ai(provider.clone(), Arc::new(MockAiHandler::new(response)));
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("pr_reviewer.require_score_review".into(), "true".into());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, reviewer.run()).await.unwrap();
+
+        let calls = provider.get_calls();
+        assert_eq!(
+            calls.removed_labels,
+            vec!["Review effort [1-5]: 5", "Score: 40", "Security concern"]
+        );
+        assert_eq!(
+            calls.labels,
+            vec![vec![
+                "Review effort [1-5]: 2".to_string(),
+                "Score: 85".to_string()
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_review_score() {
+        let score = |yaml: &str| review_score(&serde_yaml_ng::from_str(yaml).unwrap());
+        assert_eq!(score("85"), Some(85));
+        assert_eq!(score("'70, because the tests are thin'"), Some(70));
+        assert_eq!(score("high"), None);
+        assert_eq!(score("250"), None);
+    }
+
+    #[test]
+    fn test_security_label() {
+        assert_eq!(security_label(Some("high")), "security: high");
+        assert_eq!(security_label(Some("medium")), "security: medium");
+        assert_eq!(security_label(Some("low")), "Security concern");
+        assert_eq!(security_label(None), "Security concern");
+    }
+
+    #[tokio::test]
+    async fn test_review_lists_source_changes_without_tests() {
+        let provider = Arc::new(MockGitProvider::new().with_diff_files(vec![
+            sample_diff_file("src/main.rs", SAMPLE_PATCH),
+            sample_diff_file("src/parser.rs", SAMPLE_PATCH),
+            sample_diff_file("tests/parser.rs", SAMPLE_PATCH),
+        ]));
+        let ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai);
+
+        with_settings(test_settings(), reviewer.run())
+            .await
+            .unwrap();
+
+        let comment = &provider.get_calls().comments[0].0;
+        assert!(comment.contains("Changed files without matching test updates"));
+        assert!(comment.contains("<code>src/main.rs</code>"));
+        assert!(!comment.contains("<code>src/parser.rs</code>"));
+    }
+
+    #[tokio::test]
+    async fn test_review_sections_whitelist() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai.clone());
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        let repo_toml = "[pr_reviewer]\nsections = [\"issues\"]\n";
+        let settings = Arc::new(
+            crate::config::loader::load_settings(&overrides, None, Some(repo_toml)).unwrap(),
+        );
+
+        with_settings(settings, reviewer.run()).await.unwrap();
+
+        // Sections outside the whitelist are neither requested nor published
+        let calls = ai.get_recorded_calls();
+        assert!(!calls[0].system.contains("introduce vulnerabilities"));
+        let comment = &provider.get_calls().comments[0].0;
+        assert!(comment.contains("Potential null pointer"));
+        assert!(!comment.contains("Estimated effort to review"));
+        assert!(!comment.contains("relevant tests"));
+    }
+
+    #[tokio::test]
+    async fn test_review_parses_json_response() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let json = r#"{"review": {
+            "estimated_effort_to_review_[1-5]": 2,
+            "relevant_tests": "No",
+            "key_issues_to_review": [{
+                "relevant_file": "src/main.rs",
+                "issue_header": "Unchecked unwrap",
+                "issue_content": "Panics on empty input: see `parse`",
+                "start_line": 1,
+                "end_line": 2
+            }],
+            "security_concerns": "No"
+        }}"#;
+        let ai = Arc::new(MockAiHandler::new(json));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai);
+
+        with_settings(test_settings(), reviewer.run())
+            .await
+            .unwrap();
+
+        let comment = &provider.get_calls().comments[0].0;
+        assert!(comment.contains("Unchecked unwrap"));
+        assert!(comment.contains("Panics on empty input: see `parse`"));
+    }
+
+    #[tokio::test]
+    async fn test_review_includes_planning_context() {
+        let provider_with_milestone = || {
+            let mut provider = MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]);
+            provider.planning = PlanningContext {
+                milestone: Some(crate::git::types::Milestone {
+                    title: "v2.0".into(),
+                    due_on: Some("2026-11-01".into()),
+                }),
+                projects: vec![],
+            };
+            Arc::new(provider)
+        };
+
+        let provider = provider_with_milestone();
+        let ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai.clone());
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("config.add_planning_context".into(), "true".into());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+
+        with_settings(settings, reviewer.run()).await.unwrap();
+
+        let calls = ai.get_recorded_calls();
+        assert!(calls[0].user.contains("Milestone: v2.0 (due 2026-11-01)"));
+        let comment = provider.get_calls().comments[0].0.clone();
+        assert!(comment.contains("<strong>Planning context</strong><br>Milestone: v2.0"));
+
+        // Disabled by default: nothing fetched, nothing shown
+        let provider = provider_with_milestone();
+        let ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai.clone());
+        with_settings(test_settings(), reviewer.run())
+            .await
+            .unwrap();
+        assert!(!ai.get_recorded_calls()[0].user.contains("Milestone"));
+        assert!(
+            !provider.get_calls().comments[0]
+                .0
+                .contains("Planning context")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_review_includes_codeowners() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+                .with_repo_file(".github/CODEOWNERS", "*.rs @org/rust\n/docs/ @org/docs\n"),
+        );
+        let ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai.clone());
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("config.include_codeowners".into(), "true".into());
+        overrides.insert("pr_reviewer.mention_codeowners".into(), "true".into());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+
+        with_settings(settings, reviewer.run()).await.unwrap();
+
+        let calls = ai.get_recorded_calls();
+        assert!(calls[0].user.contains("- src/main.rs: @org/rust"));
+        let comment = &provider.get_calls().comments[0].0;
+        assert!(comment.contains("**Code owners:** @org/rust"));
+    }
+
+    #[tokio::test]
+    async fn test_review_custom_intro_and_help_text() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+                .with_repo_file(
+                    "docs/review.md",
+                    "Follow the [review guide](https://wiki/review).",
+                ),
+        );
+        let ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai);
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("pr_reviewer.enable_help_text".into(), "true".into());
+        overrides.insert(
+            "pr_reviewer.intro_text".into(),
+            "file:docs/review.md".into(),
+        );
+        overrides.insert("pr_reviewer.help_text".into(), "Ask in #code-review".into());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+
+        with_settings(settings, reviewer.run()).await.unwrap();
+
+        let comment = &provider.get_calls().comments[0].0;
+        assert!(comment.contains(
+            "## PR Reviewer Guide 🔍\n\nFollow the [review guide](https://wiki/review).\n\n"
+        ));
+        assert!(!comment.contains(REVIEW_INTRO_TEXT));
+        assert!(comment.contains("Tool usage guide:</summary>\n\nAsk in #code-review"));
+    }
+
+    #[tokio::test]
+    async fn test_review_reports_context_overflow() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai.clone());
+
+        // Unknown model: the context window comes from config.max_model_tokens.
+        // Minus the output buffer that leaves 500 tokens, well under the
+        // review instructions alone with any tokenizer.
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("config.model".into(), "custom-model".into());
+        overrides.insert("config.max_model_tokens".into(), "2000".into());
+        let settings =
+            Arc::new(crate::config::loader::load_settings(&overrides, None, None).unwrap());
+
+        with_settings(settings, reviewer.run()).await.unwrap();
+
+        assert_eq!(ai.get_call_count(), 1);
+        let comment = &provider.get_calls().comments[0].0;
+        assert!(comment.contains("**Context limit:**"));
+        assert!(comment.contains("(2000 tokens)"));
+    }
+
     #[tokio::test]
     async fn test_review_empty_diff() {
         let provider = Arc::new(MockGitProvider::new()); // no diff files
@@ -600,4 +1741,316 @@ mod tests {
         );
         assert_eq!(urls[0], pr_img);
     }
+
+    // ── Incremental review ───────────────────────────────────────────
+
+    use crate::git::types::{CommitInfo, IssueComment};
+    use crate::testing::mock_git::MOCK_HEAD_SHA;
+
+    fn commits(shas: &[&str]) -> Vec<CommitInfo> {
+        shas.iter()
+            .map(|sha| CommitInfo {
+                sha: (*sha).into(),
+                date: "2024-01-01T00:00:00Z".into(),
+            })
+            .collect()
+    }
+
+    fn previous_review(sha: &str) -> IssueComment {
+        IssueComment {
+            id: 42,
+            body: format!(
+                "<!-- pr-agent:review -->\n## PR Reviewer Guide 🔍\n\nOld findings\n{}",
+                reviewed_commit_marker(sha)
+            ),
+            user: "bot".into(),
+            created_at: String::new(),
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_reviewed_commit_marker_roundtrip() {
+        let body = format!(
+            "review{}more{}",
+            reviewed_commit_marker("abc"),
+            reviewed_commit_marker("def")
+        );
+        assert_eq!(reviewed_commit(&body), Some("def"));
+        assert_eq!(reviewed_commit("no marker"), None);
+        assert!(minutes_since(reviewed_at(&body).unwrap()) < 1);
+
+        // Markers written before the review time was recorded.
+        let legacy = format!("review\n{REVIEWED_COMMIT_PREFIX}abc -->\n");
+        assert_eq!(reviewed_commit(&legacy), Some("abc"));
+        assert_eq!(reviewed_at(&legacy), None);
+    }
+
+    #[test]
+    fn test_related_tickets_row() {
+        let ticket = Ticket {
+            key: "PROJ-12".into(),
+            url: "https://acme.atlassian.net/browse/PROJ-12".into(),
+            title: "Paginate lists".into(),
+            description: String::new(),
+            acceptance_criteria: String::new(),
+            labels: vec![],
+        };
+        let mut data: serde_yaml_ng::Value =
+            serde_yaml_ng::from_str("review:\n  relevant_tests: \"No\"\n").unwrap();
+        let reviewer = PrReviewerConfig::default();
+        attach_related_tickets(&mut data, std::slice::from_ref(&ticket), &reviewer);
+        let markdown = format_review_markdown(&data, true, None);
+        assert!(markdown.contains("<strong>🎫 Related tickets</strong>: <a href="));
+        assert!(markdown.contains("/browse/PROJ-12'>PROJ-12</a>: Paginate lists"));
+
+        // Hidden together with the other ticket sections.
+        let mut data: serde_yaml_ng::Value = serde_yaml_ng::from_str("review: {}").unwrap();
+        let reviewer = PrReviewerConfig {
+            sections: vec!["issues".into()],
+            ..Default::default()
+        };
+        attach_related_tickets(&mut data, &[ticket], &reviewer);
+        assert!(data["review"].get("related_tickets").is_none());
+    }
+
+    #[test]
+    fn test_incremental_thresholds() {
+        let mut reviewer = PrReviewerConfig {
+            minimal_commits_for_incremental_review: 3,
+            minimal_minutes_for_incremental_review: 60,
+            ..Default::default()
+        };
+        assert!(incremental_thresholds_met(1, 90, &reviewer));
+        assert!(incremental_thresholds_met(3, 5, &reviewer));
+        assert!(!incremental_thresholds_met(2, 30, &reviewer));
+
+        reviewer.require_all_thresholds_for_incremental_review = true;
+        assert!(!incremental_thresholds_met(1, 90, &reviewer));
+        assert!(incremental_thresholds_met(3, 60, &reviewer));
+    }
+
+    #[test]
+    fn test_attach_checklist_aligns_answers_with_questions() {
+        let mut data: serde_yaml_ng::Value = serde_yaml_ng::from_str(
+            "review:\n  checklist:\n  - question: is the changelog updated?\n    answer: 'no'\n    \
+             explanation: Missing entry\n  - question: Something else\n    answer: 'yes'\n  \
+             - question: Were the docs updated?\n    answer: n/a\n",
+        )
+        .unwrap();
+        let questions = vec![
+            "Are migrations reversible?".to_string(),
+            "Is the changelog updated?".to_string(),
+            "Are docs updated?".to_string(),
+        ];
+        attach_checklist(&mut data, &questions);
+
+        let entries = data["review"]["checklist"].as_sequence().unwrap();
+        assert_eq!(entries.len(), 3);
+        // The answer at position 0 belongs to another question
+        assert_eq!(
+            entries[0]["question"].as_str(),
+            Some("Are migrations reversible?")
+        );
+        assert_eq!(entries[0]["answer"].as_str(), Some("?"));
+        assert_eq!(entries[1]["answer"].as_str(), Some("no"));
+        assert_eq!(entries[1]["explanation"].as_str(), Some("Missing entry"));
+        // Rephrased question: matched by position
+        assert_eq!(entries[2]["answer"].as_str(), Some("n/a"));
+    }
+
+    #[tokio::test]
+    async fn test_full_review_records_head_commit() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let reviewer =
+            PRReviewer::new_with_ai(provider.clone(), Arc::new(MockAiHandler::new(REVIEW_YAML)));
+        with_settings(test_settings(), reviewer.run())
+            .await
+            .unwrap();
+
+        let comment = &provider.get_calls().comments[0].0;
+        assert_eq!(reviewed_commit(comment), Some(MOCK_HEAD_SHA));
+    }
+
+    #[tokio::test]
+    async fn test_incremental_review_appends_section() {
+        let mut provider = MockGitProvider::new()
+            .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+            .with_commits(commits(&["aaa1111", "bbb2222", "ccc3333"]));
+        provider.issue_comments = vec![previous_review("aaa1111")];
+        let provider = Arc::new(provider);
+        let reviewer =
+            PRReviewer::new_with_ai(provider.clone(), Arc::new(MockAiHandler::new(REVIEW_YAML)))
+                .with_incremental(true);
+        with_settings(test_settings(), reviewer.run())
+            .await
+            .unwrap();
+
+        let calls = provider.get_calls();
+        assert_eq!(
+            calls.diffs_between,
+            vec![("aaa1111".to_string(), "ccc3333".to_string())]
+        );
+        assert!(calls.comments.is_empty(), "should edit, not post");
+        let (id, body) = &calls.edited_comments[0];
+        assert_eq!(id, "42");
+        assert!(
+            body.starts_with("<!-- pr-agent:review -->\n## PR Reviewer Guide 🔍\n\nOld findings")
+        );
+        assert!(body.contains("## Incremental review 🔁\n\n_2 new commits since `aaa1111`_"));
+        assert!(body.contains("Potential null pointer"));
+        assert_eq!(body.matches(REVIEWED_COMMIT_PREFIX).count(), 1);
+        assert_eq!(body.matches("<!-- pr-agent:review -->").count(), 1);
+        assert_eq!(reviewed_commit(body), Some("ccc3333"));
+    }
+
+    #[tokio::test]
+    async fn test_incremental_review_skips_without_new_commits() {
+        let mut provider = MockGitProvider::new()
+            .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+            .with_commits(commits(&["aaa1111"]));
+        provider.issue_comments = vec![previous_review("aaa1111")];
+        let provider = Arc::new(provider);
+        let ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai.clone()).with_incremental(true);
+        with_settings(test_settings(), reviewer.run())
+            .await
+            .unwrap();
+
+        assert_eq!(ai.get_call_count(), 0);
+        let calls = provider.get_calls();
+        assert!(calls.comments.is_empty() && calls.edited_comments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_incremental_review_without_previous_review_runs_full() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+                .with_commits(commits(&["aaa1111"])),
+        );
+        let reviewer =
+            PRReviewer::new_with_ai(provider.clone(), Arc::new(MockAiHandler::new(REVIEW_YAML)))
+                .with_incremental(true);
+        with_settings(test_settings(), reviewer.run())
+            .await
+            .unwrap();
+
+        let calls = provider.get_calls();
+        assert!(calls.diffs_between.is_empty());
+        assert!(calls.comments[0].0.contains("PR Reviewer Guide"));
+    }
+
+    #[test]
+    fn test_commit_range_from_args() {
+        let args = HashMap::from([
+            ("base".to_string(), "AAA1111".to_string()),
+            ("head".to_string(), "ccc3333ddd".to_string()),
+        ]);
+        assert_eq!(
+            CommitRange::from_args(&args).unwrap(),
+            CommitRange {
+                base: "aaa1111".into(),
+                head: "ccc3333ddd".into(),
+            }
+        );
+        for (key, value) in [("head", "abc"), ("head", "not-a-sha"), ("base", "")] {
+            let mut bad = args.clone();
+            bad.insert(key.to_string(), value.to_string());
+            assert!(CommitRange::from_args(&bad).is_err(), "{key}={value:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_range_review_posts_separate_comment() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+                .with_commits(commits(&["aaa1111ffff", "bbb2222ffff", "ccc3333ffff"])),
+        );
+        let reviewer =
+            PRReviewer::new_with_ai(provider.clone(), Arc::new(MockAiHandler::new(REVIEW_YAML)))
+                .with_range(CommitRange {
+                    base: "bbb2222".into(),
+                    head: "ccc3333".into(),
+                });
+        with_settings(test_settings(), reviewer.run())
+            .await
+            .unwrap();
+
+        let calls = provider.get_calls();
+        assert_eq!(
+            calls.diffs_between,
+            vec![("bbb2222ffff".to_string(), "ccc3333ffff".to_string())]
+        );
+        let comment = &calls.comments[0].0;
+        assert!(comment.starts_with("## Review of `bbb2222..ccc3333` 🔍"));
+        assert!(comment.contains("Potential null pointer"));
+        assert!(!comment.contains("<!-- pr-agent:review -->"));
+        assert_eq!(reviewed_commit(comment), None);
+        assert!(calls.labels.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_range_review_rejects_foreign_head() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+                .with_commits(commits(&["aaa1111"])),
+        );
+        let ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let reviewer =
+            PRReviewer::new_with_ai(provider.clone(), ai.clone()).with_range(CommitRange {
+                base: "aaa1111".into(),
+                head: "fff9999".into(),
+            });
+        let err = with_settings(test_settings(), reviewer.run())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("`fff9999` is not part of this PR"));
+        assert_eq!(ai.get_call_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_push_review_waits_for_thresholds() {
+        let mut provider = MockGitProvider::new()
+            .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)])
+            .with_commits(commits(&["aaa1111", "bbb2222"]));
+        provider.issue_comments = vec![previous_review("aaa1111")];
+        let provider = Arc::new(provider);
+        let ai = Arc::new(MockAiHandler::new(REVIEW_YAML));
+        let settings = crate::config::loader::load_settings(
+            &HashMap::from([
+                (
+                    "pr_reviewer.minimal_commits_for_incremental_review".to_string(),
+                    "3".to_string(),
+                ),
+                (
+                    "pr_reviewer.minimal_minutes_for_incremental_review".to_string(),
+                    "60".to_string(),
+                ),
+            ]),
+            None,
+            None,
+        )
+        .unwrap();
+
+        // One new commit, and the last review was just published.
+        let reviewer =
+            PRReviewer::new_with_ai(provider.clone(), ai.clone()).with_push_trigger(true);
+        with_settings(Arc::new(settings.clone()), reviewer.run())
+            .await
+            .unwrap();
+        assert_eq!(ai.get_call_count(), 0);
+
+        // A comment-triggered review is not gated.
+        let reviewer = PRReviewer::new_with_ai(provider.clone(), ai.clone());
+        with_settings(Arc::new(settings), reviewer.run())
+            .await
+            .unwrap();
+        assert!(ai.get_call_count() > 0);
+    }
 }
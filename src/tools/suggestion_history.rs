@@ -0,0 +1,224 @@
+//! Suggestions already shown on a PR, so `/improve` re-runs don't repeat them
+//! (`pr_code_suggestions.max_history_len`).
+//!
+//! Each suggestion is identified by a fingerprint of its file and its
+//! whitespace-normalized `existing_code`, so it survives rewording and line
+//! shifts. The improve comment keeps the fingerprints of its last
+//! `max_history_len` runs in a hidden block. Inline suggestions carry their
+//! fingerprint too, so a thumbs-down on one rejects it for later runs.
+
+use std::collections::HashSet;
+
+use sha2::{Digest, Sha256};
+
+use crate::git::GitProvider;
+use crate::output::improve_formatter::ParsedSuggestion;
+use crate::output::markdown::persistent_comment_marker;
+
+const HISTORY_START: &str = "<!-- pr-agent:suggestion-history";
+const INLINE_PREFIX: &str = "<!-- pr-agent:suggestion ";
+
+/// Fingerprint of a suggestion on `file`: the file plus the
+/// whitespace-normalized code it replaces.
+pub fn fingerprint(file: &str, existing_code: &str) -> String {
+    let code = existing_code
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let digest = Sha256::digest(format!("{file}\n{code}"));
+    hex::encode(&digest[..6])
+}
+
+fn suggestion_fingerprint(s: &ParsedSuggestion) -> String {
+    fingerprint(&s.relevant_file, &s.existing_code)
+}
+
+/// Hidden marker carrying a fingerprint in an inline suggestion comment.
+pub fn inline_marker(fingerprint: &str) -> String {
+    format!("{INLINE_PREFIX}{fingerprint} -->")
+}
+
+/// Fingerprints of earlier runs, newest first.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SuggestionHistory {
+    runs: Vec<Vec<String>>,
+    /// Suggestions someone gave a thumbs-down.
+    rejected: HashSet<String>,
+}
+
+impl SuggestionHistory {
+    /// Read the history block of an improve comment.
+    pub fn parse(body: &str) -> Self {
+        let runs = body
+            .split_once(HISTORY_START)
+            .and_then(|(_, rest)| rest.split_once("-->"))
+            .map(|(block, _)| {
+                block
+                    .lines()
+                    .map(|line| line.split_whitespace().map(String::from).collect())
+                    .filter(|run: &Vec<String>| !run.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            runs,
+            rejected: HashSet::new(),
+        }
+    }
+
+    /// Whether `suggestion` was shown in a remembered run or rejected.
+    pub fn contains(&self, suggestion: &ParsedSuggestion) -> bool {
+        let fp = suggestion_fingerprint(suggestion);
+        self.rejected.contains(&fp) || self.runs.iter().flatten().any(|seen| *seen == fp)
+    }
+
+    /// Add a run's suggestions as the newest, keeping at most `max_runs` runs.
+    pub fn record(&mut self, suggestions: &[ParsedSuggestion], max_runs: usize) {
+        if !suggestions.is_empty() {
+            self.runs
+                .insert(0, suggestions.iter().map(suggestion_fingerprint).collect());
+        }
+        self.runs.truncate(max_runs);
+    }
+
+    /// The hidden block to append to the improve comment; empty without runs.
+    pub fn render(&self) -> String {
+        if self.runs.is_empty() {
+            return String::new();
+        }
+        let mut out = format!("\n{HISTORY_START}\n");
+        for run in &self.runs {
+            out.push_str(&run.join(" "));
+            out.push('\n');
+        }
+        out.push_str("-->\n");
+        out
+    }
+}
+
+/// Load the history from the PR's improve comment, plus the inline
+/// suggestions that got a thumbs-down. Empty when nothing can be read.
+pub async fn load(provider: &dyn GitProvider) -> SuggestionHistory {
+    let marker = persistent_comment_marker("improve");
+    let mut history = match provider.get_issue_comments().await {
+        Ok(comments) => comments
+            .iter()
+            .rev()
+            .find(|c| c.body.trim_start().starts_with(&marker))
+            .map(|c| SuggestionHistory::parse(&c.body))
+            .unwrap_or_default(),
+        Err(e) => {
+            tracing::debug!(error = %e, "could not read the previous improve comment");
+            SuggestionHistory::default()
+        }
+    };
+    if let Ok(comments) = provider.get_review_comments().await {
+        history.rejected = comments
+            .iter()
+            .filter(|c| c.thumbs_down)
+            .filter_map(|c| inline_fingerprint(&c.body))
+            .collect();
+    }
+    history
+}
+
+fn inline_fingerprint(body: &str) -> Option<String> {
+    let (_, rest) = body.split_once(INLINE_PREFIX)?;
+    let (fp, _) = rest.split_once(" -->")?;
+    Some(fp.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::types::{IssueComment, ReviewComment};
+    use crate::testing::mock_git::MockGitProvider;
+
+    fn suggestion(file: &str, code: &str) -> ParsedSuggestion {
+        ParsedSuggestion {
+            label: "bug fix".into(),
+            relevant_file: file.into(),
+            relevant_lines_start: 3,
+            relevant_lines_end: 3,
+            existing_code: code.into(),
+            improved_code: "let x = 2;".into(),
+            one_sentence_summary: "Fix value".into(),
+            suggestion_content: "The value should be 2".into(),
+            score: 8,
+            best_practice: None,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_whitespace_and_wording() {
+        let mut history = SuggestionHistory::default();
+        history.record(&[suggestion("src/a.rs", "let x =  1;\n")], 4);
+
+        let mut moved = suggestion("src/a.rs", "  let x = 1;");
+        moved.suggestion_content = "Reworded".into();
+        moved.relevant_lines_start = 10;
+        assert!(history.contains(&moved));
+        assert!(!history.contains(&suggestion("src/b.rs", "let x = 1;")));
+    }
+
+    #[test]
+    fn test_history_round_trip_keeps_latest_runs() {
+        let mut history = SuggestionHistory::default();
+        let first = suggestion("a.rs", "one");
+        let second = suggestion("a.rs", "two");
+        let third = suggestion("a.rs", "three");
+        history.record(std::slice::from_ref(&first), 2);
+        history.record(&[second, third.clone()], 2);
+        let body = format!("<!-- pr-agent:improve -->\n## Table\n{}", history.render());
+
+        let mut parsed = SuggestionHistory::parse(&body);
+        assert_eq!(parsed, history);
+        assert!(parsed.contains(&first));
+
+        // A third run pushes the oldest one out
+        parsed.record(&[suggestion("a.rs", "four")], 2);
+        assert!(!parsed.contains(&first));
+        assert!(parsed.contains(&third));
+        assert!(!parsed.contains(&suggestion("a.rs", "five")));
+    }
+
+    #[tokio::test]
+    async fn test_load_reads_comment_and_thumbs_down() {
+        let mut history = SuggestionHistory::default();
+        history.record(&[suggestion("a.rs", "shown")], 4);
+        let rejected = suggestion("b.rs", "rejected");
+
+        let mut provider = MockGitProvider::new();
+        provider.issue_comments = vec![IssueComment {
+            id: 1,
+            body: format!("<!-- pr-agent:improve -->\n## Table\n{}", history.render()),
+            user: "bot".into(),
+            created_at: String::new(),
+            url: None,
+        }];
+        provider.review_comments = vec![ReviewComment {
+            id: 2,
+            path: "b.rs".into(),
+            start_line: 3,
+            line: 3,
+            body: format!(
+                "**Suggestion:** x\n\n{}",
+                inline_marker(&fingerprint("b.rs", "rejected"))
+            ),
+            user: "bot".into(),
+            thumbs_down: true,
+        }];
+
+        let loaded = load(&provider).await;
+        assert!(loaded.contains(&suggestion("a.rs", "shown")));
+        assert!(loaded.contains(&rejected));
+        assert!(!loaded.contains(&suggestion("a.rs", "new")));
+    }
+
+    #[test]
+    fn test_inline_fingerprint() {
+        let body = format!("**Suggestion:** x\n{}", inline_marker("abc123"));
+        assert_eq!(inline_fingerprint(&body).as_deref(), Some("abc123"));
+        assert_eq!(inline_fingerprint("plain comment"), None);
+    }
+}
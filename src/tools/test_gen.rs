@@ -0,0 +1,357 @@
+use std::fmt::Write;
+use std::sync::Arc;
+
+use minijinja::Value;
+
+use crate::ai::AiHandler;
+use crate::config::loader::get_settings;
+use crate::error::PrAgentError;
+use crate::git::GitProvider;
+use crate::git::types::FilePatchInfo;
+use crate::output::help_text::{TEST_HELP_TEXT, add_guide_text};
+use crate::output::markdown::user_code_span;
+use crate::output::yaml_parser::load_yaml;
+use crate::processing::compression::get_pr_diff;
+use crate::processing::language;
+use crate::tools::{
+    PrMetadata, build_common_vars, render_prompt_fitting_context, with_progress_comment,
+};
+
+/// Upper bound for `pr_test.num_tests`.
+const MAX_TESTS: u32 = 5;
+
+/// PR Test tool — generate unit tests for a component or changed file.
+///
+/// The target is the component named after `/test` (e.g. `/test parse_config`),
+/// narrowed by `pr_test.file` / `pr_test.class_name`; with neither, tests cover
+/// the whole PR diff. Tests are published as a comment with copyable code blocks.
+pub struct PRTestGen {
+    provider: Arc<dyn GitProvider>,
+    ai: Option<Arc<dyn AiHandler>>,
+}
+
+impl PRTestGen {
+    pub fn new(provider: Arc<dyn GitProvider>) -> Self {
+        Self { provider, ai: None }
+    }
+
+    #[cfg(test)]
+    pub fn new_with_ai(provider: Arc<dyn GitProvider>, ai: Arc<dyn AiHandler>) -> Self {
+        Self {
+            provider,
+            ai: Some(ai),
+        }
+    }
+
+    /// Run the test generation pipeline for `component` (may be empty).
+    pub async fn run(&self, component: &str) -> Result<(), PrAgentError> {
+        let provider = &self.provider;
+        let component = component.trim().to_string();
+        with_progress_comment(provider.as_ref(), "Generating tests...", || {
+            self.run_inner(&component)
+        })
+        .await
+    }
+
+    async fn run_inner(&self, component: &str) -> Result<(), PrAgentError> {
+        let settings = get_settings();
+        let model = &settings.config.model;
+        let config = &settings.pr_test;
+
+        // 1. Fetch PR metadata and pick the files to test
+        let mut meta = PrMetadata::fetch(self.provider.as_ref(), &settings).await?;
+        let files = super::fetch_diff_files(self.provider.as_ref(), "test").await?;
+        let mut files = select_target_files(files, component, &config.file);
+        if files.is_empty() {
+            let target = if component.is_empty() {
+                config.file.as_str()
+            } else {
+                component
+            };
+            tracing::info!(target, "no changed file matches the /test target");
+            if settings.config.publish_output {
+                self.provider
+                    .publish_comment(
+                        &format!(
+                            "No changed file in this PR matches {}.",
+                            user_code_span(target)
+                        ),
+                        false,
+                    )
+                    .await?;
+            }
+            return Ok(());
+        }
+        tracing::info!(num_files = files.len(), component, "generating tests");
+        meta.language = language::annotate_files(self.provider.as_ref(), &mut files).await;
+        let mut diff_result = get_pr_diff(&mut files, model, true);
+        let fallback_language = files
+            .first()
+            .and_then(|f| f.filename.rsplit_once('.'))
+            .map(|(_, ext)| ext.to_lowercase())
+            .unwrap_or_default();
+
+        // 2. Build template variables
+        let num_tests = config.num_tests.clamp(1, MAX_TESTS);
+        let mut vars = build_common_vars(&meta, &diff_result.diff);
+        vars.insert("component".into(), Value::from(component));
+        vars.insert("class_name".into(), Value::from(config.class_name.as_str()));
+        vars.insert("num_tests".into(), Value::from(num_tests));
+        vars.insert(
+            "testing_framework".into(),
+            Value::from(config.testing_framework.as_str()),
+        );
+        vars.insert("avoid_mocks".into(), Value::from(config.avoid_mocks));
+        vars.insert(
+            "extra_instructions".into(),
+            Value::from(config.extra_instructions.as_str()),
+        );
+
+        // 3. Render prompt and call AI (with fallback models)
+        let (rendered, overflow) = render_prompt_fitting_context(
+            &settings.pr_test_prompt,
+            vars,
+            &mut files,
+            &mut diff_result,
+            model,
+            true,
+        )?;
+        drop(files);
+        let ai = super::resolve_ai_handler(&self.ai)?;
+        let response = crate::ai::chat_completion_with_fallback(
+            ai.as_ref(),
+            model,
+            &settings.config.fallback_models,
+            &rendered.system,
+            &rendered.user,
+            Some(settings.config.temperature),
+            None,
+        )
+        .await?;
+
+        // 4. Format and publish
+        let Some(data) = load_yaml(&response.content, &["test_code:"], "language", "tests") else {
+            tracing::warn!("could not parse YAML from AI response, skipping publish");
+            return Ok(());
+        };
+        let mut output =
+            format_tests_output(&data, component, num_tests as usize, &fallback_language);
+        if config.enable_help_text {
+            add_guide_text(&mut output, "", TEST_HELP_TEXT);
+        }
+        if let Some(overflow) = &overflow {
+            output.push('\n');
+            output.push_str(&overflow.notice());
+        }
+
+        if settings.config.publish_output {
+            self.provider.publish_comment(&output, false).await?;
+        } else {
+            println!("{output}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Keep the changed files that the `/test` target refers to.
+///
+/// `file` matches a path suffix; `component` matches files whose new content
+/// (or patch) mentions it. Deleted files never match a component.
+fn select_target_files(
+    files: Vec<FilePatchInfo>,
+    component: &str,
+    file: &str,
+) -> Vec<FilePatchInfo> {
+    let file = file.trim().trim_start_matches('/');
+    files
+        .into_iter()
+        .filter(|f| file.is_empty() || f.filename.ends_with(file))
+        .filter(|f| {
+            component.is_empty()
+                || f.head_file.contains(component)
+                || f.patch
+                    .lines()
+                    .any(|l| l.starts_with('+') && l.contains(component))
+        })
+        .collect()
+}
+
+/// Render the generated tests as markdown with one code block per test.
+fn format_tests_output(
+    data: &serde_yaml_ng::Value,
+    component: &str,
+    max_tests: usize,
+    fallback_language: &str,
+) -> String {
+    let field = |v: &serde_yaml_ng::Value, key: &str| {
+        v.get(key)
+            .and_then(|s| s.as_str())
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+
+    let language = match field(data, "language") {
+        l if l.is_empty() => fallback_language.to_string(),
+        l => l.to_lowercase(),
+    };
+    let framework = field(data, "testing_framework");
+
+    let mut out = String::with_capacity(4_000);
+    if component.is_empty() {
+        out.push_str("## Generated tests 🧪\n\n");
+    } else {
+        let _ = writeln!(
+            out,
+            "## Generated tests for {} 🧪\n",
+            user_code_span(component)
+        );
+    }
+    if !framework.is_empty() {
+        let _ = writeln!(out, "**Testing framework:** {framework}\n");
+    }
+
+    let tests = data
+        .get("tests")
+        .and_then(|t| t.as_sequence())
+        .map(|seq| seq.as_slice())
+        .unwrap_or_default();
+    let mut written = 0;
+    for test in tests.iter().take(max_tests) {
+        let code = test
+            .get("test_code")
+            .and_then(|s| s.as_str())
+            .unwrap_or_default()
+            .trim_end();
+        if code.trim().is_empty() {
+            continue;
+        }
+        let name = field(test, "test_name");
+        let behavior = field(test, "test_behavior");
+        let _ = writeln!(out, "### `{name}`\n");
+        if !behavior.is_empty() {
+            let _ = writeln!(out, "{behavior}\n");
+        }
+        let _ = writeln!(out, "```{language}\n{code}\n```\n");
+        written += 1;
+    }
+
+    if written == 0 {
+        out.push_str("No tests were generated.\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::{load_settings, with_settings};
+    use crate::testing::fixtures::{SAMPLE_PATCH, sample_diff_file};
+    use crate::testing::mock_ai::MockAiHandler;
+    use crate::testing::mock_git::MockGitProvider;
+
+    const TESTS_YAML: &str = r#"```yaml
+language: |
+  rust
+testing_framework: |
+  cargo test
+tests:
+- test_name: |
+    test_main_prints
+  test_behavior: |
+    main runs without panicking
+  test_code: |
+    #[test]
+    fn test_main_prints() {
+        main();
+    }
+```"#;
+
+    #[test]
+    fn test_select_target_files() {
+        let files = || {
+            vec![
+                sample_diff_file("src/main.rs", SAMPLE_PATCH),
+                sample_diff_file(
+                    "src/util.rs",
+                    "@@ -1 +1 @@\n-fn a() {}\n+fn parse_config() {}",
+                ),
+            ]
+        };
+        let names = |files: Vec<FilePatchInfo>| -> Vec<String> {
+            files.into_iter().map(|f| f.filename).collect()
+        };
+        assert_eq!(names(select_target_files(files(), "", "")).len(), 2);
+        assert_eq!(
+            names(select_target_files(files(), "parse_config", "")),
+            vec!["src/util.rs"]
+        );
+        assert_eq!(
+            names(select_target_files(files(), "", "main.rs")),
+            vec!["src/main.rs"]
+        );
+        assert!(select_target_files(files(), "parse_config", "main.rs").is_empty());
+    }
+
+    #[test]
+    fn test_format_tests_output() {
+        let data = load_yaml(TESTS_YAML, &[], "language", "tests").unwrap();
+        let out = format_tests_output(&data, "main", 3, "rs");
+        assert!(out.starts_with("## Generated tests for `main` 🧪\n\n"));
+        assert!(out.contains("**Testing framework:** cargo test"));
+        assert!(out.contains("### `test_main_prints`\n\nmain runs without panicking\n"));
+        assert!(out.contains("```rust\n#[test]\nfn test_main_prints() {\n    main();\n}\n```"));
+
+        let empty: serde_yaml_ng::Value = serde_yaml_ng::from_str("tests: []").unwrap();
+        let out = format_tests_output(&empty, "", 3, "py");
+        assert!(out.contains("No tests were generated."));
+    }
+
+    #[tokio::test]
+    async fn test_generate_tests_for_component() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new(TESTS_YAML));
+        let tool = PRTestGen::new_with_ai(provider.clone(), ai.clone());
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        overrides.insert("pr_test.num_tests".into(), "9".into());
+        overrides.insert("pr_test.testing_framework".into(), "rstest".into());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, tool.run("dbg")).await.unwrap();
+
+        let system = &ai.get_recorded_calls()[0].system;
+        assert!(system.contains("write 5 unit tests for the component 'dbg'"));
+        assert!(system.contains("Use the 'rstest' testing framework."));
+        assert!(system.contains("Prefer real objects over mocks."));
+
+        let comment = &provider.get_calls().comments[0].0;
+        assert!(comment.contains("```rust\n#[test]"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_tests_unknown_component() {
+        let provider = Arc::new(
+            MockGitProvider::new()
+                .with_diff_files(vec![sample_diff_file("src/main.rs", SAMPLE_PATCH)]),
+        );
+        let ai = Arc::new(MockAiHandler::new(TESTS_YAML));
+        let tool = PRTestGen::new_with_ai(provider.clone(), ai.clone());
+
+        let mut overrides = std::collections::HashMap::new();
+        overrides.insert("config.publish_output".into(), "true".into());
+        overrides.insert("config.publish_output_progress".into(), "false".into());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+        with_settings(settings, tool.run("no_such_fn"))
+            .await
+            .unwrap();
+
+        assert_eq!(ai.get_call_count(), 0);
+        assert!(provider.get_calls().comments[0].0.contains("`no_such_fn`"));
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use crate::ai::types::ChatResponse;
+use crate::config::loader::get_settings;
+use crate::config::types::ModelPrice;
+
+tokio::task_local! {
+    /// AI usage accumulated by the tool currently running in this task.
+    static USAGE: Mutex<AiUsage>;
+}
+
+/// Calls and token counts for one model within a tool run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelUsage {
+    pub model: String,
+    pub calls: u32,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+/// Models and token counts used by one tool run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AiUsage {
+    /// Per-model usage, in order of first call.
+    pub models: Vec<ModelUsage>,
+}
+
+impl AiUsage {
+    /// Models that answered, in call order.
+    pub fn model_names(&self) -> Vec<&str> {
+        self.models.iter().map(|m| m.model.as_str()).collect()
+    }
+
+    pub fn prompt_tokens(&self) -> u32 {
+        self.models.iter().map(|m| m.prompt_tokens).sum()
+    }
+
+    pub fn completion_tokens(&self) -> u32 {
+        self.models.iter().map(|m| m.completion_tokens).sum()
+    }
+
+    /// Cost in USD from `prices`, or `None` if a model that used tokens has
+    /// no price (a partial sum would understate the run).
+    pub fn cost(&self, prices: &HashMap<String, ModelPrice>) -> Option<f64> {
+        self.models
+            .iter()
+            .filter(|m| m.prompt_tokens + m.completion_tokens > 0)
+            .map(|m| {
+                let price = find_price(prices, &m.model)?;
+                Some(
+                    (m.prompt_tokens as f64 * price.input
+                        + m.completion_tokens as f64 * price.output)
+                        / 1_000_000.0,
+                )
+            })
+            .sum()
+    }
+
+    fn record(&mut self, model: &str, response: &ChatResponse) {
+        let idx = match self.models.iter().position(|m| m.model == model) {
+            Some(idx) => idx,
+            None => {
+                self.models.push(ModelUsage {
+                    model: model.to_string(),
+                    ..ModelUsage::default()
+                });
+                self.models.len() - 1
+            }
+        };
+        let entry = &mut self.models[idx];
+        entry.calls += 1;
+        if let Some(u) = response.usage {
+            entry.prompt_tokens += u.prompt_tokens;
+            entry.completion_tokens += u.completion_tokens;
+        }
+    }
+}
+
+/// Price for `model` in `prices`.
+///
+/// Keys match the model name or a dash/colon-delimited prefix of it, also
+/// after a provider prefix (`anthropic/`, `bedrock/us.anthropic.`). The
+/// longest matching key wins, so `gpt-4o-mini` beats `gpt-4o`.
+pub fn find_price<'a>(
+    prices: &'a HashMap<String, ModelPrice>,
+    model: &str,
+) -> Option<&'a ModelPrice> {
+    let starts = std::iter::once(0).chain(
+        model
+            .char_indices()
+            .filter(|(_, c)| matches!(c, '/' | '.'))
+            .map(|(i, _)| i + 1),
+    );
+    let mut best: Option<(&str, &ModelPrice)> = None;
+    for start in starts {
+        let name = &model[start..];
+        for (key, price) in prices {
+            let matches = name
+                .strip_prefix(key.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', ':']));
+            if matches && best.is_none_or(|(k, _)| key.len() > k.len()) {
+                best = Some((key, price));
+            }
+        }
+    }
+    best.map(|(_, price)| price)
+}
+
+/// Run `fut` while collecting the AI usage it records.
+pub async fn track_usage<F, T>(fut: F) -> (T, AiUsage)
+where
+    F: std::future::Future<Output = T>,
+{
+    USAGE
+        .scope(Mutex::new(AiUsage::default()), async {
+            let out = fut.await;
+            (out, current().unwrap_or_default())
+        })
+        .await
+}
+
+/// Usage recorded so far by the enclosing [`track_usage`], if any.
+pub fn current() -> Option<AiUsage> {
+    USAGE
+        .try_with(|u| u.lock().map(|u| u.clone()).ok())
+        .ok()
+        .flatten()
+}
+
+/// Record a successful AI response for the current tool run.
+///
+/// A no-op outside [`track_usage`].
+pub fn record_usage(model: &str, response: &ChatResponse) {
+    let _ = USAGE.try_with(|usage| {
+        if let Ok(mut usage) = usage.lock() {
+            usage.record(model, response);
+        }
+    });
+}
+
+/// Log the token counts and cost of a finished tool run.
+pub fn log_usage(tool: &str, usage: &AiUsage) {
+    if usage.models.is_empty() {
+        return;
+    }
+    let settings = get_settings();
+    let cost = usage.cost(&settings.model_prices);
+    tracing::info!(
+        tool,
+        models = ?usage.model_names(),
+        calls = usage.models.iter().map(|m| m.calls).sum::<u32>(),
+        prompt_tokens = usage.prompt_tokens(),
+        completion_tokens = usage.completion_tokens(),
+        cost_usd = cost.map(|c| format!("{c:.4}")).unwrap_or_else(|| "unknown".into()),
+        "AI usage"
+    );
+}
+
+/// `body` plus a cost footer when `config.output_cost` is set and every model
+/// used so far has a price.
+pub fn with_cost_footer(body: &str) -> String {
+    let settings = get_settings();
+    let mut out = body.to_string();
+    if !settings.config.output_cost {
+        return out;
+    }
+    let Some(usage) = current().filter(|u| !u.models.is_empty()) else {
+        return out;
+    };
+    match usage.cost(&settings.model_prices) {
+        Some(cost) => {
+            let _ = write!(
+                out,
+                "\n\n<sub>cost: ${cost:.4} · {} prompt / {} completion tokens</sub>\n",
+                usage.prompt_tokens(),
+                usage.completion_tokens()
+            );
+        }
+        None => tracing::debug!(
+            models = ?usage.model_names(),
+            "no price for model, skipping cost footer"
+        ),
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::types::{FinishReason, Usage};
+    use crate::config::loader::{load_settings, with_settings};
+    use std::sync::Arc;
+
+    fn response(prompt_tokens: u32, completion_tokens: u32) -> ChatResponse {
+        ChatResponse {
+            content: String::new(),
+            finish_reason: FinishReason::Stop,
+            usage: Some(Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            }),
+        }
+    }
+
+    fn price(input: f64, output: f64) -> ModelPrice {
+        ModelPrice { input, output }
+    }
+
+    #[tokio::test]
+    async fn test_track_usage_accumulates() {
+        let ((), usage) = track_usage(async {
+            record_usage("gpt-4o", &response(100, 20));
+            record_usage("gpt-4o", &response(50, 5));
+            record_usage("o4-mini", &response(10, 1));
+        })
+        .await;
+        assert_eq!(usage.model_names(), vec!["gpt-4o", "o4-mini"]);
+        assert_eq!(usage.models[0].calls, 2);
+        assert_eq!(usage.prompt_tokens(), 160);
+        assert_eq!(usage.completion_tokens(), 26);
+
+        // Outside a tracked scope recording is a no-op.
+        record_usage("gpt-4o", &response(1, 1));
+    }
+
+    #[test]
+    fn test_find_price() {
+        let prices = HashMap::from([
+            ("gpt-4o".to_string(), price(2.5, 10.0)),
+            ("gpt-4o-mini".to_string(), price(0.15, 0.6)),
+            ("claude-sonnet-4-5".to_string(), price(3.0, 15.0)),
+        ]);
+        assert_eq!(find_price(&prices, "gpt-4o-2024-11-20").unwrap().input, 2.5);
+        assert_eq!(find_price(&prices, "gpt-4o-mini").unwrap().input, 0.15);
+        assert_eq!(
+            find_price(&prices, "anthropic/claude-sonnet-4-5-20250929")
+                .unwrap()
+                .output,
+            15.0
+        );
+        assert_eq!(
+            find_price(
+                &prices,
+                "bedrock/us.anthropic.claude-sonnet-4-5-20250929-v1:0"
+            )
+            .unwrap()
+            .output,
+            15.0
+        );
+        assert!(find_price(&prices, "gpt-4o1").is_none());
+        assert!(find_price(&prices, "ollama/llama3.1").is_none());
+    }
+
+    #[test]
+    fn test_cost() {
+        let prices = HashMap::from([
+            ("gpt-4o".to_string(), price(2.5, 10.0)),
+            ("o4-mini".to_string(), price(1.1, 4.4)),
+        ]);
+        let usage = AiUsage {
+            models: vec![
+                ModelUsage {
+                    model: "gpt-4o".into(),
+                    calls: 2,
+                    prompt_tokens: 100_000,
+                    completion_tokens: 10_000,
+                },
+                ModelUsage {
+                    model: "o4-mini".into(),
+                    calls: 1,
+                    prompt_tokens: 1_000_000,
+                    completion_tokens: 0,
+                },
+            ],
+        };
+        let cost = usage.cost(&prices).unwrap();
+        assert!((cost - (0.25 + 0.1 + 1.1)).abs() < 1e-9);
+
+        let mut unpriced = usage.clone();
+        unpriced.models[1].model = "custom-model".into();
+        assert!(unpriced.cost(&prices).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cost_footer() {
+        let mut overrides = HashMap::new();
+        overrides.insert("config.output_cost".into(), "true".into());
+        let settings = Arc::new(load_settings(&overrides, None, None).unwrap());
+
+        let (body, _) = with_settings(
+            settings,
+            track_usage(async {
+                record_usage("gpt-4o", &response(1_000, 100));
+                with_cost_footer("## Review")
+            }),
+        )
+        .await;
+        assert_eq!(
+            body,
+            "## Review\n\n<sub>cost: $0.0035 · 1000 prompt / 100 completion tokens</sub>\n"
+        );
+
+        // Off by default.
+        let settings = Arc::new(load_settings(&HashMap::new(), None, None).unwrap());
+        let (body, _) = with_settings(
+            settings,
+            track_usage(async {
+                record_usage("gpt-4o", &response(1_000, 100));
+                with_cost_footer("## Review")
+            }),
+        )
+        .await;
+        assert_eq!(body, "## Review");
+    }
+}
@@ -2,6 +2,10 @@ use std::collections::HashMap;
 use std::sync::{Arc, LazyLock, Mutex};
 
 use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::config::types::HttpConfig;
+use crate::error::PrAgentError;
 
 /// Thread-safe cache for compiled regexes.
 ///
@@ -55,10 +59,83 @@ pub fn truncate_on_line_boundary(text: &str, max_bytes: usize) -> &str {
     }
 }
 
+/// Start a `reqwest` client builder with the `[http]` proxy settings and the
+/// given extra default headers applied.
+///
+/// Header values are marked sensitive so gateway credentials never show up
+/// in debug output.
+pub fn http_client_builder(
+    http: &HttpConfig,
+    extra_headers: &HashMap<String, String>,
+) -> Result<reqwest::ClientBuilder, PrAgentError> {
+    let mut builder = reqwest::Client::builder();
+
+    if !http.proxy.is_empty() {
+        let proxy = reqwest::Proxy::all(&http.proxy)
+            .map_err(|e| PrAgentError::Other(format!("invalid http.proxy URL: {e}")))?
+            .no_proxy(reqwest::NoProxy::from_string(&http.no_proxy.join(",")));
+        builder = builder.proxy(proxy);
+    }
+
+    if !extra_headers.is_empty() {
+        let mut headers = HeaderMap::new();
+        for (name, value) in extra_headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                PrAgentError::Other(format!("invalid HTTP header name '{name}': {e}"))
+            })?;
+            let mut value = HeaderValue::from_str(value).map_err(|e| {
+                PrAgentError::Other(format!("invalid value for HTTP header '{name}': {e}"))
+            })?;
+            value.set_sensitive(true);
+            headers.insert(name, value);
+        }
+        builder = builder.default_headers(headers);
+    }
+
+    Ok(builder)
+}
+
+/// Async locks keyed by name, e.g. one per PR or repository, so work on one
+/// key is serialized without holding up the others. Only tasks within this
+/// process are serialized.
+#[derive(Default)]
+pub struct KeyedLocks {
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl KeyedLocks {
+    /// Wait for and take the lock for `key`; it is released when the guard
+    /// is dropped.
+    pub async fn lock(&self, key: &str) -> tokio::sync::OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap_or_else(|p| p.into_inner());
+            // Drop the locks nobody holds or waits for, so keys don't pile up.
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(key.to_string()).or_default().clone()
+        };
+        lock.lock_owned().await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_keyed_locks_serialize_per_key() {
+        let locks = KeyedLocks::default();
+        let held = locks.lock("a").await;
+        // Another key is free while "a" is held.
+        drop(locks.lock("b").await);
+        let waiting = tokio::time::timeout(std::time::Duration::from_millis(20), locks.lock("a"));
+        assert!(
+            waiting.await.is_err(),
+            "second lock on a held key should wait"
+        );
+        drop(held);
+        drop(locks.lock("a").await);
+    }
+
     #[test]
     fn test_truncate_ascii() {
         assert_eq!(truncate_on_line_boundary("hello\nworld", 7), "hello");
@@ -119,4 +196,35 @@ mod tests {
         // max_bytes exceeds length
         assert_eq!(floor_char_boundary(text, 100), text.len());
     }
+
+    #[test]
+    fn test_http_client_builder_accepts_proxy_and_headers() {
+        let http = HttpConfig {
+            proxy: "http://proxy.internal:3128".into(),
+            no_proxy: vec!["localhost".into(), ".corp".into()],
+            ..HttpConfig::default()
+        };
+        let headers = HashMap::from([("X-Gateway-Auth".to_string(), "token".to_string())]);
+        let client = http_client_builder(&http, &headers).unwrap().build();
+        assert!(client.is_ok());
+    }
+
+    #[test]
+    fn test_http_client_builder_rejects_invalid_header_name() {
+        let headers = HashMap::from([("bad header".to_string(), "v".to_string())]);
+        let err = http_client_builder(&HttpConfig::default(), &headers)
+            .expect_err("header names with spaces must be rejected");
+        assert!(err.to_string().contains("invalid HTTP header name"));
+    }
+
+    #[test]
+    fn test_http_client_builder_rejects_invalid_proxy() {
+        let http = HttpConfig {
+            proxy: "not a url".into(),
+            ..HttpConfig::default()
+        };
+        let err = http_client_builder(&http, &HashMap::new())
+            .expect_err("malformed proxy URL must be rejected");
+        assert!(err.to_string().contains("http.proxy"));
+    }
 }